@@ -1,13 +1,34 @@
 use std::env;
+use std::path::Path;
 
 fn main() {
+    // 打包一个中文字体，在找不到系统字体时使用，例如 SERVER_CHECK_EMBED_FONT=NotoSansSC-Subset.otf
+    println!("cargo::rustc-check-cfg=cfg(embedded_font)");
+    println!("cargo:rerun-if-env-changed=SERVER_CHECK_EMBED_FONT");
+    if let Some(font) = env::var_os("SERVER_CHECK_EMBED_FONT").filter(|f| !f.is_empty()) {
+        println!("cargo:rerun-if-changed={}", Path::new(&font).display());
+        let out = Path::new(&env::var("OUT_DIR").unwrap()).join("embedded_font");
+        if let Err(e) = std::fs::copy(&font, out) {
+            panic!(
+                "无法读取 SERVER_CHECK_EMBED_FONT 指定的字体 {:?}: {}",
+                font, e
+            );
+        }
+        println!("cargo:rustc-cfg=embedded_font");
+    }
+
     // 只在Windows平台编译时处理资源文件
     if env::var("CARGO_CFG_TARGET_OS").unwrap_or_default() == "windows" {
         // 如果图标文件发生变化，重新运行构建脚本
-        println!("cargo:rerun-if-changed=Icon.png");
+        println!("cargo:rerun-if-changed=Icon.ico");
         println!("cargo:rerun-if-changed=resources.rc");
-        
-        // 编译并链接资源文件
-        embed_resource::compile("resources.rc", embed_resource::NONE);
+
+        // 没有图标文件时跳过，程序仍可编译，只是exe没有图标
+        if Path::new("resources.rc").exists() && Path::new("Icon.ico").exists() {
+            // 编译并链接资源文件
+            embed_resource::compile("resources.rc", embed_resource::NONE);
+        } else {
+            println!("cargo:warning=缺少 resources.rc 或 Icon.ico，exe将不带图标");
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,493 @@
+use eframe::egui;
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+
+use crate::address::{self, ParsedAddress};
+use crate::checks::{self, CheckOutcome};
+use crate::i18n::tr;
+use crate::{Server, ServerStatus};
+
+// 地址输入方式
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+enum InputMode {
+    // 粘贴完整URL，可以包含路径和查询参数
+    #[default]
+    Url,
+    // 分别填写IP和端口
+    HostPort,
+    // 粘贴多行，一次添加多台
+    Bulk,
+}
+
+// 主机名解析结果，失败时为错误信息
+type Resolved = Result<Vec<IpAddr>, String>;
+
+// 添加服务器对话框
+#[derive(Default)]
+pub struct AddDialog {
+    pub open: bool,
+    mode: InputMode,
+    name: String,
+    url: String,
+    ip: String,
+    port: String,
+    scheme: String,
+    bulk_text: String,
+    // 最近一次自动填入的名称；名称仍等于它时说明用户没有手动修改过
+    auto_name: Option<String>,
+    // 正在为哪个地址生成名称，以及后台任务的结果 (地址, 名称)
+    suggest_for: Option<ParsedAddress>,
+    suggestion: Arc<Mutex<Option<(ParsedAddress, String)>>>,
+    // 预检：正在测试的URL，以及后台返回的 (URL, 结果)
+    probe_for: Option<String>,
+    probe_result: Arc<Mutex<Option<(String, CheckOutcome)>>>,
+    probe_shown: Option<CheckOutcome>,
+    // 主机名解析：正在解析的主机，以及后台返回的 (主机, 结果)
+    resolve_for: Option<String>,
+    resolve_result: Arc<Mutex<Option<(String, Resolved)>>>,
+    resolve_shown: Option<Resolved>,
+}
+
+impl AddDialog {
+    pub fn open(&mut self) {
+        self.open = true;
+    }
+
+    // 关闭并清空输入，保留上次选择的输入方式
+    fn reset(&mut self) {
+        let mode = self.mode;
+        *self = Self::default();
+        self.mode = mode;
+    }
+
+    fn scheme(&self) -> &str {
+        if self.scheme.is_empty() {
+            "http"
+        } else {
+            &self.scheme
+        }
+    }
+
+    fn current_address(&self) -> Option<ParsedAddress> {
+        if self.mode == InputMode::Bulk {
+            return None;
+        }
+        if self.mode == InputMode::Url {
+            let url = self.url.trim();
+            // 没写协议时按http处理
+            return if url.contains("://") {
+                address::parse_address(url)
+            } else {
+                address::parse_address(&format!("http://{}", url))
+            };
+        }
+
+        let port = self.port.trim().parse().ok()?;
+        let host = self.ip.trim().trim_matches(['[', ']']);
+        address::is_valid_host(host).then(|| ParsedAddress {
+            scheme: self.scheme().to_string(),
+            host: host.to_string(),
+            port,
+            path: String::new(),
+        })
+    }
+
+    // 名称为空或仍是自动填入的值时，才允许覆盖
+    fn set_auto_name(&mut self, name: String) {
+        if self.name.is_empty() || self.auto_name.as_ref() == Some(&self.name) {
+            self.name = name.clone();
+            self.auto_name = Some(name);
+        }
+    }
+
+    // 地址确定后先填入 主机-端口，再在后台尝试获取网页标题或反向DNS名称
+    fn request_name(&mut self, client: &reqwest::Client) {
+        let Some(address) = self.current_address() else {
+            return;
+        };
+        if self.suggest_for.as_ref() == Some(&address) {
+            return;
+        }
+        self.set_auto_name(address::fallback_name(&address.host, address.port));
+        self.suggest_for = Some(address.clone());
+
+        let client = client.clone();
+        let slot = Arc::clone(&self.suggestion);
+        tokio::spawn(async move {
+            if let Some(name) = address::suggest_name(&client, &address).await {
+                *slot.lock().unwrap() = Some((address, name));
+            }
+        });
+    }
+
+    fn apply_suggestion(&mut self) {
+        let Some((address, name)) = self.suggestion.lock().unwrap().take() else {
+            return;
+        };
+        if self.suggest_for.as_ref() == Some(&address) {
+            self.set_auto_name(name);
+        }
+    }
+
+    // 对当前地址做一次检查，在添加前提示是否可达
+    fn start_probe(&mut self, client: &reqwest::Client) {
+        let Some(address) = self.current_address() else {
+            return;
+        };
+        let url = address.full_url();
+        self.probe_for = Some(url.clone());
+        self.probe_shown = None;
+
+        let mut server = Server::new(String::new(), address.host.clone(), address.port);
+        server.url = url.clone();
+        let client = client.clone();
+        let slot = Arc::clone(&self.probe_result);
+        tokio::spawn(async move {
+            let outcome = checks::run_check(&client, &server, &[], false).await;
+            *slot.lock().unwrap() = Some((url, outcome));
+        });
+    }
+
+    fn apply_probe_result(&mut self) {
+        let Some((url, outcome)) = self.probe_result.lock().unwrap().take() else {
+            return;
+        };
+        // 地址已经改了，旧结果不再显示
+        if self.probe_for.as_ref() == Some(&url) {
+            self.probe_shown = Some(outcome);
+        }
+    }
+
+    // 主机名在后台解析一次，显示解析到的IP，IP地址不需要解析
+    fn start_resolve(&mut self) {
+        let Some(address) = self.current_address() else {
+            return;
+        };
+        if address.host.parse::<IpAddr>().is_ok()
+            || self.resolve_for.as_ref() == Some(&address.host)
+        {
+            return;
+        }
+        self.resolve_for = Some(address.host.clone());
+        self.resolve_shown = None;
+
+        let slot = Arc::clone(&self.resolve_result);
+        tokio::spawn(async move {
+            let result = address::resolve_host(&address.host, address.port)
+                .await
+                .map_err(|e| e.to_string());
+            *slot.lock().unwrap() = Some((address.host, result));
+        });
+    }
+
+    fn apply_resolve_result(&mut self) {
+        let Some((host, result)) = self.resolve_result.lock().unwrap().take() else {
+            return;
+        };
+        if self.resolve_for.as_ref() == Some(&host) {
+            self.resolve_shown = Some(result);
+        }
+    }
+
+    fn show_resolved(&self, ui: &mut egui::Ui) {
+        let current = self.current_address().map(|a| a.host);
+        if current.is_none() || current != self.resolve_for {
+            return;
+        }
+        match &self.resolve_shown {
+            Some(Ok(ips)) if ips.is_empty() => {
+                ui.small(tr!("⚠ 主机名没有解析到地址", "⚠ Hostname has no addresses"));
+            }
+            Some(Ok(ips)) => {
+                let ips: Vec<String> = ips.iter().map(IpAddr::to_string).collect();
+                ui.small(tr!("🌐 解析为 {}", "🌐 Resolves to {}", ips.join(", ")));
+            }
+            Some(Err(e)) => {
+                ui.colored_label(
+                    ServerStatus::Offline.color(),
+                    tr!("❌ 无法解析主机名: {}", "❌ Cannot resolve hostname: {}", e),
+                );
+            }
+            None => {
+                ui.small(tr!("正在解析主机名...", "Resolving hostname..."));
+            }
+        }
+    }
+
+    fn show_probe(&mut self, ui: &mut egui::Ui, client: &reqwest::Client) {
+        let current = self.current_address().map(|a| a.full_url());
+        ui.horizontal(|ui| {
+            match (&self.probe_shown, &self.probe_for) {
+                (Some(outcome), Some(url)) if current.as_ref() == Some(url) => {
+                    let latency = outcome
+                        .latency
+                        .map(|d| format!(", {}ms", d.as_millis()))
+                        .unwrap_or_default();
+                    let text = match outcome.status {
+                        ServerStatus::Online => tr!("✅ 可达{}", "✅ Reachable{}", latency),
+                        ServerStatus::Error(code) => {
+                            tr!("⚠ HTTP {}{}", "⚠ HTTP {}{}", code, latency)
+                        }
+                        _ => format!("❌ {}", outcome.detail),
+                    };
+                    ui.colored_label(outcome.status.color(), text);
+                }
+                (None, Some(url)) if current.as_ref() == Some(url) => {
+                    ui.spinner();
+                    ui.small(tr!("正在测试连接...", "Testing connection..."));
+                }
+                _ => {}
+            }
+            if current.is_some() && ui.small_button(tr!("🧪 测试", "🧪 Test")).clicked() {
+                self.start_probe(client);
+            }
+        });
+    }
+
+    fn build_server(&self) -> Option<Server> {
+        let address = self.current_address()?;
+        let name = self.name.trim();
+        if name.is_empty() {
+            return None;
+        }
+        let mut server = Server::new(name.to_string(), address.host.clone(), address.port);
+        server.url = address.full_url();
+        Some(server)
+    }
+
+    // 完整URL输入，返回地址是否已确定
+    fn show_url_input(&mut self, ui: &mut egui::Ui) -> bool {
+        ui.label("URL:");
+        let response = ui.add(
+            egui::TextEdit::singleline(&mut self.url)
+                .hint_text("https://api.example.com:8443/healthz?probe=1")
+                .desired_width(300.0),
+        );
+
+        match self.current_address() {
+            Some(address) => {
+                egui::Grid::new("parsed_url").num_columns(2).show(ui, |ui| {
+                    ui.small(tr!("协议:", "Scheme:"));
+                    ui.small(&address.scheme);
+                    ui.end_row();
+                    ui.small(tr!("主机:", "Host:"));
+                    ui.small(&address.host);
+                    ui.end_row();
+                    ui.small(tr!("端口:", "Port:"));
+                    ui.small(address.port.to_string());
+                    ui.end_row();
+                    ui.small(tr!("路径:", "Path:"));
+                    ui.small(if address.path.is_empty() {
+                        "/"
+                    } else {
+                        &address.path
+                    });
+                    ui.end_row();
+                });
+            }
+            None if !self.url.trim().is_empty() => {
+                ui.small(tr!("无法解析URL", "Cannot parse URL"));
+            }
+            None => {}
+        }
+
+        response.lost_focus() || (response.changed() && self.url.contains("://"))
+    }
+
+    // IP和端口分开输入，返回地址是否已确定
+    fn show_host_port_input(&mut self, ui: &mut egui::Ui) -> bool {
+        ui.label(tr!("主机:", "Host:"));
+        let ip_response = ui.add(egui::TextEdit::singleline(&mut self.ip).hint_text(tr!(
+            "IP、主机名或粘贴完整URL",
+            "IP, hostname or paste a full URL"
+        )));
+        let host = self.ip.trim();
+        if !host.is_empty() && !host.contains("://") && !address::is_valid_host(host) {
+            ui.small(tr!("无效的主机名", "Invalid hostname"));
+        }
+        // 粘贴完整URL时立即拆出协议、主机和端口；host:port 在离开输入框时再拆，避免输入过程中被打断
+        let mut parsed = false;
+        let pasted_url = ip_response.changed() && self.ip.contains("://");
+        if pasted_url || ip_response.lost_focus() {
+            if let Some(address) = address::parse_address(&self.ip) {
+                // 带路径的URL切换到完整URL模式，避免丢失路径
+                if !address.path.is_empty() && address.path != "/" {
+                    self.url = address.full_url();
+                    self.mode = InputMode::Url;
+                    return true;
+                }
+                self.scheme = address.scheme;
+                self.ip = address.host;
+                self.port = address.port.to_string();
+                parsed = true;
+            }
+        }
+
+        ui.label(tr!("端口号:", "Port:"));
+        let port_response = ui.text_edit_singleline(&mut self.port);
+
+        if self.scheme() == "https" {
+            ui.small(tr!("协议: HTTPS", "Scheme: HTTPS"));
+        }
+
+        parsed || ip_response.lost_focus() || port_response.lost_focus()
+    }
+
+    // 批量添加：每行一台，预览解析结果，返回 (点击添加时的有效条目, 是否取消)
+    fn show_bulk_input(&mut self, ui: &mut egui::Ui, existing: &[String]) -> (Vec<Server>, bool) {
+        ui.add(
+            egui::TextEdit::multiline(&mut self.bulk_text)
+                .hint_text(tr!(
+                    "每行一台，例如:\nweb-1,10.0.0.5,8080\n10.0.0.6:8080\napi,https://api.example.com/health",
+                    "One per line, e.g.:\nweb-1,10.0.0.5,8080\n10.0.0.6:8080\napi,https://api.example.com/health"
+                ))
+                .desired_rows(6)
+                .desired_width(360.0),
+        );
+
+        let results = address::parse_bulk(&self.bulk_text, existing);
+        let valid: Vec<Server> = results
+            .iter()
+            .filter_map(|(_, r)| r.as_ref().ok())
+            .map(|entry| {
+                let address = &entry.address;
+                let name = entry
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| address::fallback_name(&address.host, address.port));
+                let mut server = Server::new(name, address.host.clone(), address.port);
+                server.url = address.full_url();
+                server
+            })
+            .collect();
+        let errors = results.len() - valid.len();
+
+        if !results.is_empty() {
+            egui::ScrollArea::vertical()
+                .max_height(200.0)
+                .show(ui, |ui| {
+                    egui::Grid::new("bulk_preview")
+                        .num_columns(3)
+                        .striped(true)
+                        .show(ui, |ui| {
+                            let mut servers = valid.iter();
+                            for (line, result) in &results {
+                                ui.small(tr!("第{}行", "Line {}", line));
+                                match result {
+                                    Ok(_) => {
+                                        let server = servers.next().expect("有效条目数量一致");
+                                        ui.small(&server.name);
+                                        ui.small(&server.url);
+                                    }
+                                    Err(e) => {
+                                        ui.colored_label(ServerStatus::Offline.color(), "❌");
+                                        ui.colored_label(ServerStatus::Offline.color(), e);
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+            if errors > 0 {
+                ui.small(tr!(
+                    "{} 行有错误，添加时跳过",
+                    "{} line(s) have errors and will be skipped",
+                    errors
+                ));
+            }
+        }
+
+        let mut added = Vec::new();
+        let mut cancel = false;
+        ui.horizontal(|ui| {
+            let add = egui::Button::new(tr!("添加 {} 台", "Add {}", valid.len()));
+            if ui.add_enabled(!valid.is_empty(), add).clicked() {
+                added = valid;
+            }
+            cancel = ui.button(tr!("取消", "Cancel")).clicked();
+        });
+        (added, cancel)
+    }
+
+    // 显示对话框，点击添加且输入有效时返回新服务器；existing 为已有服务器的URL，用于批量添加时去重
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        client: &reqwest::Client,
+        existing: &[String],
+    ) -> Vec<Server> {
+        if !self.open {
+            return Vec::new();
+        }
+        self.apply_suggestion();
+        self.apply_probe_result();
+        self.apply_resolve_result();
+
+        let mut added = Vec::new();
+        let mut cancel = false;
+        egui::Window::new(tr!("添加服务器", "Add server"))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr!("地址:", "Address:"));
+                    ui.radio_value(&mut self.mode, InputMode::Url, tr!("完整URL", "Full URL"));
+                    ui.radio_value(
+                        &mut self.mode,
+                        InputMode::HostPort,
+                        tr!("主机+端口", "Host + port"),
+                    );
+                    ui.radio_value(&mut self.mode, InputMode::Bulk, tr!("批量添加", "Bulk add"));
+                });
+
+                // 批量添加时名称写在每一行中
+                if self.mode != InputMode::Bulk {
+                    ui.label(tr!("服务器名称:", "Server name:"));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.name)
+                            .hint_text(tr!("留空自动生成", "Leave empty to auto-generate")),
+                    );
+                }
+
+                let address_changed = match self.mode {
+                    InputMode::Url => self.show_url_input(ui),
+                    InputMode::HostPort => self.show_host_port_input(ui),
+                    InputMode::Bulk => {
+                        (added, cancel) = self.show_bulk_input(ui, existing);
+                        return;
+                    }
+                };
+                if address_changed {
+                    self.request_name(client);
+                    self.start_resolve();
+                    let url = self.current_address().map(|a| a.full_url());
+                    if url.is_some() && url != self.probe_for {
+                        self.start_probe(client);
+                    }
+                }
+                self.show_resolved(ui);
+                self.show_probe(ui, client);
+
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("添加", "Add")).clicked() {
+                        // 没有离开输入框直接点添加时，先补上默认名称
+                        if self.name.trim().is_empty() {
+                            if let Some(address) = self.current_address() {
+                                self.name = address::fallback_name(&address.host, address.port);
+                            }
+                        }
+                        added.extend(self.build_server());
+                    }
+
+                    if ui.button(tr!("取消", "Cancel")).clicked() {
+                        cancel = true;
+                    }
+                });
+            });
+
+        if !added.is_empty() || cancel {
+            self.reset();
+        }
+        added
+    }
+}
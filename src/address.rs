@@ -0,0 +1,431 @@
+use std::net::IpAddr;
+use std::time::Duration;
+
+use crate::checks::http::{HttpMethod, HttpOptions, HttpVersion};
+use crate::i18n::tr;
+
+// 从输入中识别出的地址
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParsedAddress {
+    pub scheme: String,
+    pub host: String,
+    pub port: u16,
+    // 路径和查询参数，例如 /healthz?probe=1
+    pub path: String,
+}
+
+// 识别粘贴的完整URL（https://example.com:8443/health）或 host:port 形式的输入
+pub fn parse_address(input: &str) -> Option<ParsedAddress> {
+    let input = input.trim();
+    if input.is_empty() {
+        return None;
+    }
+
+    let with_scheme = if input.contains("://") {
+        input.to_string()
+    } else if (input.contains(':') && !input.contains("::"))
+        || input.contains('/')
+        || (input.starts_with('[') && input.contains("]:"))
+    {
+        // host:port、[IPv6]:port 或 host/path，按http处理
+        format!("http://{}", input)
+    } else {
+        return None;
+    };
+
+    let url = url::Url::parse(&with_scheme).ok()?;
+    let scheme = url.scheme().to_string();
+    if scheme != "http" && scheme != "https" {
+        return None;
+    }
+    let host = match url.host()? {
+        url::Host::Ipv6(addr) => addr.to_string(),
+        host => host.to_string(),
+    };
+    let path = match url.query() {
+        Some(query) => format!("{}?{}", url.path(), query),
+        None => url.path().to_string(),
+    }
+    // 路径中的 {timestamp} 等占位符会被编码，这里还原
+    .replace("%7B", "{")
+    .replace("%7D", "}");
+    Some(ParsedAddress {
+        port: url.port_or_known_default()?,
+        scheme,
+        host,
+        path,
+    })
+}
+
+impl ParsedAddress {
+    // 完整URL，包括路径和查询参数；协议的默认端口不写出来
+    pub fn full_url(&self) -> String {
+        let default_port = matches!(
+            (self.scheme.as_str(), self.port),
+            ("http", 80) | ("https", 443)
+        );
+        let base = if default_port {
+            build_url_without_port(&self.scheme, &self.host)
+        } else {
+            build_url(&self.scheme, &self.host, self.port)
+        };
+        if self.path.is_empty() || self.path == "/" {
+            base
+        } else {
+            format!("{}{}", base, self.path)
+        }
+    }
+}
+
+// 修改URL中的主机和端口，保留协议、路径和查询参数
+pub fn replace_host_port(url: &str, host: &str, port: u16) -> String {
+    match parse_address(url) {
+        Some(parsed) => ParsedAddress {
+            host: host.to_string(),
+            port,
+            ..parsed
+        }
+        .full_url(),
+        None => build_url("http", host, port),
+    }
+}
+
+// 拼接服务器URL，IPv6地址需要加方括号
+pub fn build_url(scheme: &str, host: &str, port: u16) -> String {
+    if host.parse::<std::net::Ipv6Addr>().is_ok() {
+        format!("{}://[{}]:{}", scheme, host, port)
+    } else {
+        format!("{}://{}:{}", scheme, host, port)
+    }
+}
+
+// 主机:端口，IPv6地址加方括号
+pub fn host_port(host: &str, port: u16) -> String {
+    if host.parse::<std::net::Ipv6Addr>().is_ok() {
+        format!("[{}]:{}", host, port)
+    } else {
+        format!("{}:{}", host, port)
+    }
+}
+
+// 用单引号括起来作为一个 sh 参数
+pub fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+// 等价的curl命令，参数用单引号括起来，避免 & ? 等被shell解释
+pub fn curl_command(url: &str, options: &HttpOptions) -> String {
+    let mut command = String::from("curl -i");
+    match options.method {
+        HttpMethod::Get => {}
+        HttpMethod::Head => command.push_str(" -I"),
+        method => command.push_str(&format!(" -X {}", method.as_str())),
+    }
+    if options.method.has_body() && !options.content_type.trim().is_empty() {
+        let header = format!("Content-Type: {}", options.content_type.trim());
+        command.push_str(&format!(" -H {}", shell_quote(&header)));
+    }
+    if options.method.has_body() && !options.body.is_empty() {
+        command.push_str(&format!(" --data-raw {}", shell_quote(&options.body)));
+    }
+    match options.protocol {
+        HttpVersion::Http1 => command.push_str(" --http1.1"),
+        HttpVersion::Http2 => command.push_str(" --http2-prior-knowledge"),
+        // 只检查响应头，curl 照常协商
+        HttpVersion::Auto => {}
+    }
+    if !options.dns_server.trim().is_empty() {
+        command.push_str(&format!(" --dns-servers {}", options.dns_server.trim()));
+    }
+    // 请求改为发给 Host 中的主机名，再让curl连接原来的地址
+    let name = options.host_header.trim();
+    let mut url = url.to_string();
+    if let Ok(mut parsed) = url::Url::parse(&url) {
+        if let Some(host) = parsed.host_str().map(str::to_string) {
+            if !name.is_empty() && parsed.set_host(Some(name)).is_ok() {
+                let connect_to = format!("{}::{}:", name, host);
+                command.push_str(&format!(" --connect-to {}", shell_quote(&connect_to)));
+                url = parsed.to_string();
+            }
+        }
+    }
+    format!("{} {}", command, shell_quote(&url))
+}
+
+fn build_url_without_port(scheme: &str, host: &str) -> String {
+    if host.parse::<std::net::Ipv6Addr>().is_ok() {
+        format!("{}://[{}]", scheme, host)
+    } else {
+        format!("{}://{}", scheme, host)
+    }
+}
+
+// IP地址（IPv6不带方括号）或合法的DNS主机名
+pub fn is_valid_host(host: &str) -> bool {
+    !host.is_empty() && (host.parse::<IpAddr>().is_ok() || url::Host::parse(host).is_ok())
+}
+
+// 解析主机名得到的地址，IP地址原样返回
+pub async fn resolve_host(host: &str, port: u16) -> anyhow::Result<Vec<IpAddr>> {
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Ok(vec![ip]);
+    }
+    let mut ips: Vec<IpAddr> = Vec::new();
+    for addr in tokio::net::lookup_host((host, port)).await? {
+        if !ips.contains(&addr.ip()) {
+            ips.push(addr.ip());
+        }
+    }
+    Ok(ips)
+}
+
+// 批量添加中解析出的一台服务器，没有写名称时为None
+#[derive(Debug, Clone, PartialEq)]
+pub struct BulkEntry {
+    pub name: Option<String>,
+    pub address: ParsedAddress,
+}
+
+// 单独的主机名或IP按 http:80 处理
+fn parse_host_or_url(input: &str) -> Option<ParsedAddress> {
+    if let Some(address) = parse_address(input) {
+        return Some(address);
+    }
+    is_valid_host(input).then(|| ParsedAddress {
+        scheme: "http".to_string(),
+        host: input.to_string(),
+        port: 80,
+        path: String::new(),
+    })
+}
+
+fn parse_port(input: &str) -> Result<u16, String> {
+    input
+        .parse()
+        .map_err(|_| tr!("端口无效: {}", "Invalid port: {}", input))
+}
+
+// 一行可以是 名称,主机,端口、名称,URL、主机,端口、主机:端口 或完整URL，字段也可以用Tab分隔
+fn parse_bulk_line(line: &str) -> Result<BulkEntry, String> {
+    let fields: Vec<&str> = line.split([',', '\t']).map(str::trim).collect();
+    let unknown = |input: &str| tr!("无法识别的地址: {}", "Unrecognized address: {}", input);
+    let (name, address) = match fields.as_slice() {
+        [address] => (
+            None,
+            parse_host_or_url(address).ok_or_else(|| unknown(address))?,
+        ),
+        [host, port] if port.chars().all(|c| c.is_ascii_digit()) => {
+            let port = parse_port(port)?;
+            if !is_valid_host(host) {
+                return Err(unknown(host));
+            }
+            (
+                None,
+                parse_host_or_url(&build_url("http", host, port)).ok_or_else(|| unknown(host))?,
+            )
+        }
+        [name, address] => (
+            Some(name.to_string()),
+            parse_host_or_url(address).ok_or_else(|| unknown(address))?,
+        ),
+        [name, host, port] => {
+            let port = parse_port(port)?;
+            if !is_valid_host(host) {
+                return Err(unknown(host));
+            }
+            (
+                Some(name.to_string()),
+                parse_host_or_url(&build_url("http", host, port)).ok_or_else(|| unknown(host))?,
+            )
+        }
+        _ => {
+            return Err(tr!(
+                "字段过多，应为 名称,主机,端口",
+                "Too many fields, expected name,host,port"
+            )
+            .to_string())
+        }
+    };
+    let name = name.filter(|n| !n.is_empty());
+    Ok(BulkEntry { name, address })
+}
+
+// 解析批量粘贴的文本，返回 (行号, 结果)；空行和 # 开头的注释行忽略，
+// 与 existing 中或前面的行重复的地址视为错误
+pub fn parse_bulk(text: &str, existing: &[String]) -> Vec<(usize, Result<BulkEntry, String>)> {
+    // 已有服务器的URL可能写了默认端口，统一格式后再比较
+    let mut seen: Vec<String> = existing
+        .iter()
+        .map(|url| parse_address(url).map_or_else(|| url.clone(), |a| a.full_url()))
+        .collect();
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| {
+            let line = line.trim();
+            !line.is_empty() && !line.starts_with('#')
+        })
+        .map(|(i, line)| {
+            let result = parse_bulk_line(line.trim()).and_then(|entry| {
+                let url = entry.address.full_url();
+                if seen.contains(&url) {
+                    return Err(tr!("重复的地址: {}", "Duplicate address: {}", url));
+                }
+                seen.push(url);
+                Ok(entry)
+            });
+            (i + 1, result)
+        })
+        .collect()
+}
+
+// 无法获取更好的名称时使用 主机-端口
+pub fn fallback_name(host: &str, port: u16) -> String {
+    format!("{}-{}", host, port)
+}
+
+// 从HTML中提取<title>
+fn extract_title(html: &str) -> Option<String> {
+    let lower = html.to_lowercase();
+    let start = lower.find("<title")?;
+    let start = start + lower[start..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title = html
+        .get(start..end)?
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ");
+    (!title.is_empty()).then(|| title.chars().take(40).collect())
+}
+
+async fn page_title(client: &reqwest::Client, url: &str) -> Option<String> {
+    let resp = client
+        .get(url)
+        .timeout(Duration::from_secs(3))
+        .send()
+        .await
+        .ok()?;
+    let is_html = resp
+        .headers()
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.contains("html"));
+    if !is_html {
+        return None;
+    }
+    let body = resp.text().await.ok()?;
+    extract_title(&body)
+}
+
+async fn reverse_dns(host: &str) -> Option<String> {
+    let ip: IpAddr = host.parse().ok()?;
+    let name = tokio::task::spawn_blocking(move || dns_lookup::lookup_addr(&ip).ok())
+        .await
+        .ok()??;
+    // 没有PTR记录时系统会原样返回IP
+    (name != host).then_some(name)
+}
+
+// 依次尝试网页标题、反向DNS，都失败时返回None
+pub async fn suggest_name(client: &reqwest::Client, address: &ParsedAddress) -> Option<String> {
+    let url = build_url(&address.scheme, &address.host, address.port);
+    if let Some(title) = page_title(client, &url).await {
+        return Some(title);
+    }
+    reverse_dns(&address.host).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(text: &str) -> Vec<Result<(Option<String>, String), String>> {
+        parse_bulk(text, &[])
+            .into_iter()
+            .map(|(_, r)| r.map(|e| (e.name, e.address.full_url())))
+            .collect()
+    }
+
+    #[test]
+    fn bulk_accepts_all_line_formats() {
+        let text = "web-1,10.0.0.5,8080\n\
+                    10.0.0.6:8081\n\
+                    # 注释\n\
+                    \n\
+                    api,https://api.example.com/health\n\
+                    db.internal\t5432\n\
+                    example.org";
+        assert_eq!(
+            urls(text),
+            vec![
+                Ok((Some("web-1".into()), "http://10.0.0.5:8080".into())),
+                Ok((None, "http://10.0.0.6:8081".into())),
+                Ok((Some("api".into()), "https://api.example.com/health".into())),
+                Ok((None, "http://db.internal:5432".into())),
+                Ok((None, "http://example.org".into())),
+            ]
+        );
+    }
+
+    #[test]
+    fn bulk_reports_errors_with_line_numbers() {
+        let existing = vec!["http://10.0.0.1:80".to_string()];
+        let text =
+            "a,10.0.0.2,99999\nb,not a host,80\n10.0.0.1,80\nc,1,2,3\n10.0.0.3:22\n10.0.0.3:22";
+        let results = parse_bulk(text, &existing);
+        let lines: Vec<(usize, bool)> = results.iter().map(|(n, r)| (*n, r.is_ok())).collect();
+        assert_eq!(
+            lines,
+            vec![
+                (1, false),
+                (2, false),
+                (3, false),
+                (4, false),
+                (5, true),
+                (6, false)
+            ]
+        );
+    }
+
+    #[test]
+    fn copy_formats() {
+        assert_eq!(host_port("::1", 22), "[::1]:22");
+        assert_eq!(host_port("10.0.0.1", 22), "10.0.0.1:22");
+        assert_eq!(
+            curl_command("http://a/?x=1&y='2'", &HttpOptions::default()),
+            "curl -i 'http://a/?x=1&y='\\''2'\\'''"
+        );
+        let options = HttpOptions {
+            method: HttpMethod::Post,
+            content_type: "application/json".to_string(),
+            body: "{\"probe\":true}".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            curl_command("http://a/health", &options),
+            "curl -i -X POST -H 'Content-Type: application/json' --data-raw '{\"probe\":true}' 'http://a/health'"
+        );
+        let options = HttpOptions {
+            host_header: "app.example.com".into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            curl_command("https://10.0.0.5:8443/health", &options),
+            "curl -i --connect-to 'app.example.com::10.0.0.5:' 'https://app.example.com:8443/health'"
+        );
+    }
+
+    #[test]
+    fn full_url_omits_default_port() {
+        let parsed = parse_address("https://example.com:443/a?b=1").unwrap();
+        assert_eq!(parsed.full_url(), "https://example.com/a?b=1");
+        let parsed = parse_address("http://[::1]:8080").unwrap();
+        assert_eq!(parsed.full_url(), "http://[::1]:8080");
+        let parsed = parse_address("[2001:db8::1]:8443").unwrap();
+        assert_eq!((parsed.host.as_str(), parsed.port), ("2001:db8::1", 8443));
+        assert_eq!(parse_address("2001:db8::1"), None);
+        assert_eq!(
+            replace_host_port("http://a:80/health", "2001:db8::1", 8080),
+            "http://[2001:db8::1]:8080/health"
+        );
+    }
+}
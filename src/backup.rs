@@ -0,0 +1,757 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Local, Utc};
+use russh::Disconnect;
+use serde::{Deserialize, Serialize};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+use crate::checks::ssh;
+use crate::checks::target::Target;
+use crate::discovery::aws::{self, SigningRequest};
+use crate::discovery::{sha256_hex, uri_encode, CloudAccount};
+use crate::i18n::tr;
+use crate::schedule::ActionLog;
+use crate::storage::{self, Storage, BASELINE_DOC, HISTORY_DOC};
+
+// 定时把设置和所有配置方案的服务器列表（可选包括状态历史）上传到远程，
+// 运行本程序的机器坏掉后可以从那里恢复。每次覆盖上一次的文件
+
+pub const MIN_BACKUP_HOURS: u64 = 1;
+pub const MAX_BACKUP_HOURS: u64 = 24 * 7;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum BackupTarget {
+    // S3 兼容的对象存储，例如 AWS S3、MinIO、Cloudflare R2
+    #[default]
+    S3,
+    WebDav,
+    Sftp,
+}
+
+impl BackupTarget {
+    pub const ALL: [BackupTarget; 3] = [BackupTarget::S3, BackupTarget::WebDav, BackupTarget::Sftp];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            BackupTarget::S3 => "S3",
+            BackupTarget::WebDav => "WebDAV",
+            BackupTarget::Sftp => "SFTP",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BackupSettings {
+    pub enabled: bool,
+    pub target: BackupTarget,
+    pub interval_hours: u64,
+    pub include_history: bool,
+    // 远程的目录（S3 中为对象名前缀），留空时放在根目录
+    pub path: String,
+    // 例如 https://s3.us-east-1.amazonaws.com 或 http://minio:9000，按路径方式访问存储桶
+    pub s3_endpoint: String,
+    pub s3_region: String,
+    pub s3_bucket: String,
+    pub s3_access_key: String,
+    pub s3_secret_key: String,
+    // 例如 https://dav.example.com/remote.php/dav/files/me
+    pub webdav_url: String,
+    pub webdav_user: String,
+    pub webdav_password: String,
+    // SFTP 与SSH检查一样使用密钥认证
+    pub sftp_host: String,
+    pub sftp_port: u16,
+    pub sftp_user: String,
+    pub sftp_key_path: String,
+    pub sftp_key_passphrase: String,
+    // 主机密钥不在 known_hosts 中时信任并记录下来
+    pub sftp_trust_unknown_host: bool,
+}
+
+impl Default for BackupSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            target: BackupTarget::S3,
+            interval_hours: 24,
+            include_history: false,
+            path: "servercheck".to_string(),
+            s3_endpoint: String::new(),
+            s3_region: "us-east-1".to_string(),
+            s3_bucket: String::new(),
+            s3_access_key: String::new(),
+            s3_secret_key: String::new(),
+            webdav_url: String::new(),
+            webdav_user: String::new(),
+            webdav_password: String::new(),
+            sftp_host: String::new(),
+            sftp_port: 22,
+            sftp_user: "root".to_string(),
+            sftp_key_path: String::new(),
+            sftp_key_passphrase: String::new(),
+            sftp_trust_unknown_host: false,
+        }
+    }
+}
+
+impl BackupSettings {
+    // 所选目标的必填项都已填写
+    pub fn ready(&self) -> bool {
+        match self.target {
+            BackupTarget::S3 => [
+                &self.s3_endpoint,
+                &self.s3_bucket,
+                &self.s3_access_key,
+                &self.s3_secret_key,
+            ]
+            .iter()
+            .all(|s| !s.trim().is_empty()),
+            BackupTarget::WebDav => !self.webdav_url.trim().is_empty(),
+            BackupTarget::Sftp => {
+                !self.sftp_host.trim().is_empty() && !self.sftp_key_path.trim().is_empty()
+            }
+        }
+    }
+
+    pub fn active(&self) -> bool {
+        self.enabled && self.ready()
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(
+            self.interval_hours
+                .clamp(MIN_BACKUP_HOURS, MAX_BACKUP_HOURS)
+                * 3600,
+        )
+    }
+
+    // 远程目录的各级名称
+    fn dirs(&self) -> Vec<&str> {
+        self.path
+            .split('/')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .collect()
+    }
+}
+
+// 最近一次备份的时间，失败时带上原因；保存在存储中，重启后据此判断备份是否已经过期
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupResult {
+    pub at: DateTime<Local>,
+    pub files: usize,
+    pub error: Option<String>,
+}
+
+pub type LastBackup = Arc<Mutex<Option<BackupResult>>>;
+
+const LAST_BACKUP_DOC: &str = "last_backup";
+
+pub fn load_last(storage: &dyn Storage) -> LastBackup {
+    let last = match storage.load(LAST_BACKUP_DOC) {
+        Ok(content) => content.and_then(|c| serde_json::from_str(&c).ok()),
+        Err(e) => {
+            eprintln!("读取上次备份的结果失败: {}", e);
+            None
+        }
+    };
+    Arc::new(Mutex::new(last))
+}
+
+fn save_last(storage: &dyn Storage, result: &BackupResult) {
+    let saved = serde_json::to_string(result)
+        .map_err(anyhow::Error::from)
+        .and_then(|json| storage.save(LAST_BACKUP_DOC, &json));
+    if let Err(e) = saved {
+        eprintln!("保存备份结果失败: {}", e);
+    }
+}
+
+// 从没备份过、上次失败或距离上次备份已经超过间隔，启动时立即备份一次
+pub fn overdue(last: &LastBackup, settings: &BackupSettings, now: DateTime<Local>) -> bool {
+    let interval = chrono::Duration::from_std(settings.interval()).unwrap_or_default();
+    last.lock()
+        .unwrap()
+        .as_ref()
+        .is_none_or(|r| r.error.is_some() || now - r.at >= interval)
+}
+
+// 要上传的文件名和内容；文档名中的 / 换成 -，例如 servers/默认 为 servers-默认.json
+pub fn collect(
+    storage: &dyn Storage,
+    settings_json: &str,
+    include_history: bool,
+) -> Result<Vec<(String, String)>> {
+    let mut docs: Vec<String> = storage::list_profiles(storage)
+        .iter()
+        .map(|profile| storage::servers_doc(profile))
+        .collect();
+    docs.push(BASELINE_DOC.to_string());
+    if include_history {
+        docs.push(HISTORY_DOC.to_string());
+    }
+    let mut files = vec![("settings.json".to_string(), settings_json.to_string())];
+    for doc in docs {
+        if let Some(content) = storage.load(&doc)? {
+            files.push((format!("{}.json", doc.replace('/', "-")), content));
+        }
+    }
+    Ok(files)
+}
+
+fn error_status(resp: &reqwest::Response) -> anyhow::Error {
+    anyhow!(tr!(
+        "{} 返回错误状态 {}",
+        "{} returned error status {}",
+        resp.url().path(),
+        resp.status().as_u16()
+    ))
+}
+
+// 按路径方式 PUT {地址}/{存储桶}/{目录}/{文件名}，使用 Signature Version 4 签名
+async fn put_s3(
+    client: &reqwest::Client,
+    settings: &BackupSettings,
+    name: &str,
+    content: &str,
+) -> Result<()> {
+    let mut url = url::Url::parse(settings.s3_endpoint.trim())?;
+    let host = match (url.host_str(), url.port()) {
+        (Some(host), Some(port)) => format!("{}:{}", host, port),
+        (Some(host), None) => host.to_string(),
+        _ => bail!(tr!("S3 地址无效", "Invalid S3 endpoint")),
+    };
+    let mut segments: Vec<&str> = url
+        .path_segments()
+        .map(|s| s.filter(|s| !s.is_empty()).collect())
+        .unwrap_or_default();
+    segments.push(settings.s3_bucket.trim());
+    segments.extend(settings.dirs());
+    segments.push(name);
+    let path: String = segments
+        .iter()
+        .map(|s| format!("/{}", uri_encode(s)))
+        .collect();
+    url.set_path(&path);
+
+    let account = CloudAccount {
+        region: settings.s3_region.clone(),
+        access_key_id: settings.s3_access_key.clone(),
+        secret_key: settings.s3_secret_key.clone(),
+        ..Default::default()
+    };
+    let payload_hash = sha256_hex(content.as_bytes());
+    let now = Utc::now();
+    let request = SigningRequest {
+        method: "PUT",
+        host: &host,
+        path: &path,
+        query: "",
+        headers: &[("x-amz-content-sha256", &payload_hash)],
+        payload: content.as_bytes(),
+    };
+    let auth = aws::authorization(&account, "s3", now, &request);
+    let resp = client
+        .put(url)
+        .header("x-amz-date", now.format("%Y%m%dT%H%M%SZ").to_string())
+        .header("x-amz-content-sha256", payload_hash)
+        .header("authorization", auth)
+        .header("content-type", "application/json")
+        .body(content.to_string())
+        .send()
+        .await?;
+    if !resp.status().is_success() {
+        return Err(error_status(&resp));
+    }
+    Ok(())
+}
+
+fn webdav_url(settings: &BackupSettings, dirs: &[&str]) -> Result<url::Url> {
+    let mut url = url::Url::parse(settings.webdav_url.trim())?;
+    url.path_segments_mut()
+        .map_err(|_| anyhow!(tr!("WebDAV 地址无效", "Invalid WebDAV URL")))?
+        .pop_if_empty()
+        .extend(dirs);
+    Ok(url)
+}
+
+fn webdav_request(
+    client: &reqwest::Client,
+    settings: &BackupSettings,
+    method: reqwest::Method,
+    url: url::Url,
+) -> reqwest::RequestBuilder {
+    let request = client.request(method, url);
+    if settings.webdav_user.is_empty() {
+        request
+    } else {
+        request.basic_auth(&settings.webdav_user, Some(&settings.webdav_password))
+    }
+}
+
+// 目录不存在时服务器返回409，逐级 MKCOL 后重试
+async fn put_webdav(
+    client: &reqwest::Client,
+    settings: &BackupSettings,
+    name: &str,
+    content: &str,
+) -> Result<()> {
+    let dirs = settings.dirs();
+    let mut path = dirs.clone();
+    path.push(name);
+    let url = webdav_url(settings, &path)?;
+    for attempt in 0..2 {
+        let resp = webdav_request(client, settings, reqwest::Method::PUT, url.clone())
+            .body(content.to_string())
+            .send()
+            .await?;
+        if resp.status().is_success() {
+            return Ok(());
+        }
+        if resp.status() != reqwest::StatusCode::CONFLICT || attempt > 0 {
+            return Err(error_status(&resp));
+        }
+        let mkcol = reqwest::Method::from_bytes(b"MKCOL")?;
+        for depth in 1..=dirs.len() {
+            // 已存在的目录返回405，忽略
+            webdav_request(
+                client,
+                settings,
+                mkcol.clone(),
+                webdav_url(settings, &dirs[..depth])?,
+            )
+            .send()
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+// SFTP 版本3中用到的报文类型
+const SSH_FXP_INIT: u8 = 1;
+const SSH_FXP_OPEN: u8 = 3;
+const SSH_FXP_CLOSE: u8 = 4;
+const SSH_FXP_WRITE: u8 = 6;
+const SSH_FXP_MKDIR: u8 = 14;
+const SSH_FXP_STATUS: u8 = 101;
+const SSH_FXP_HANDLE: u8 = 102;
+const SSH_FXF_WRITE: u32 = 0x02;
+const SSH_FXF_CREAT: u32 = 0x08;
+const SSH_FXF_TRUNC: u32 = 0x10;
+const SFTP_CHUNK: usize = 32 * 1024;
+
+struct Sftp<S> {
+    stream: S,
+    next_id: u32,
+}
+
+fn sftp_string(out: &mut Vec<u8>, data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(data);
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Sftp<S> {
+    async fn send(&mut self, kind: u8, body: &[u8]) -> Result<()> {
+        let mut out = (body.len() as u32 + 1).to_be_bytes().to_vec();
+        out.push(kind);
+        out.extend_from_slice(body);
+        self.stream.write_all(&out).await?;
+        Ok(())
+    }
+
+    async fn receive(&mut self) -> Result<(u8, Vec<u8>)> {
+        let len = self.stream.read_u32().await? as usize;
+        if !(1..=256 * 1024).contains(&len) {
+            bail!(tr!("无效的SFTP报文长度", "Invalid SFTP packet length"));
+        }
+        let kind = self.stream.read_u8().await?;
+        let mut body = vec![0; len - 1];
+        self.stream.read_exact(&mut body).await?;
+        Ok((kind, body))
+    }
+
+    async fn init(stream: S) -> Result<Self> {
+        let mut sftp = Self { stream, next_id: 0 };
+        sftp.send(SSH_FXP_INIT, &3u32.to_be_bytes()).await?;
+        sftp.receive().await?;
+        Ok(sftp)
+    }
+
+    // 发送一个带请求ID的请求，返回回复的类型和去掉ID后的内容
+    async fn request(&mut self, kind: u8, body: &[u8]) -> Result<(u8, Vec<u8>)> {
+        self.next_id += 1;
+        let mut out = self.next_id.to_be_bytes().to_vec();
+        out.extend_from_slice(body);
+        self.send(kind, &out).await?;
+        let (kind, body) = self.receive().await?;
+        Ok((kind, body.get(4..).unwrap_or_default().to_vec()))
+    }
+
+    // 状态码为0（成功）之外的回复转为错误
+    async fn expect_ok(&mut self, kind: u8, body: &[u8]) -> Result<()> {
+        let (reply, body) = self.request(kind, body).await?;
+        let code = body
+            .get(..4)
+            .map(|c| u32::from_be_bytes([c[0], c[1], c[2], c[3]]));
+        if reply != SSH_FXP_STATUS || code != Some(0) {
+            let message = body
+                .get(8..)
+                .map(|m| String::from_utf8_lossy(m).trim_matches('\0').to_string())
+                .unwrap_or_default();
+            bail!(tr!(
+                "SFTP 请求失败 ({}): {}",
+                "SFTP request failed ({}): {}",
+                code.unwrap_or_default(),
+                message
+            ));
+        }
+        Ok(())
+    }
+
+    async fn mkdir(&mut self, path: &str) -> Result<()> {
+        let mut body = Vec::new();
+        sftp_string(&mut body, path.as_bytes());
+        body.extend_from_slice(&0u32.to_be_bytes());
+        // 目录已存在时也会失败，不影响之后写入文件
+        let _ = self.request(SSH_FXP_MKDIR, &body).await?;
+        Ok(())
+    }
+
+    async fn write_file(&mut self, path: &str, content: &[u8]) -> Result<()> {
+        let mut body = Vec::new();
+        sftp_string(&mut body, path.as_bytes());
+        body.extend_from_slice(&(SSH_FXF_WRITE | SSH_FXF_CREAT | SSH_FXF_TRUNC).to_be_bytes());
+        body.extend_from_slice(&0u32.to_be_bytes());
+        let (reply, body) = self.request(SSH_FXP_OPEN, &body).await?;
+        if reply != SSH_FXP_HANDLE || body.len() < 4 {
+            bail!(tr!(
+                "无法在SFTP服务器上创建 {}",
+                "Cannot create {} on the SFTP server",
+                path
+            ));
+        }
+        let handle = body[4..].to_vec();
+        for (i, chunk) in content.chunks(SFTP_CHUNK).enumerate() {
+            let mut body = Vec::new();
+            sftp_string(&mut body, &handle);
+            body.extend_from_slice(&((i * SFTP_CHUNK) as u64).to_be_bytes());
+            sftp_string(&mut body, chunk);
+            self.expect_ok(SSH_FXP_WRITE, &body).await?;
+        }
+        let mut body = Vec::new();
+        sftp_string(&mut body, &handle);
+        self.expect_ok(SSH_FXP_CLOSE, &body).await
+    }
+}
+
+async fn upload_sftp(settings: &BackupSettings, files: &[(String, String)]) -> Result<()> {
+    let session = ssh::connect(
+        Target::new(settings.sftp_host.trim()),
+        settings.sftp_port,
+        &settings.sftp_user,
+        &settings.sftp_key_path,
+        &settings.sftp_key_passphrase,
+        settings.sftp_trust_unknown_host,
+        None,
+    )
+    .await?;
+    let channel = session.channel_open_session().await?;
+    channel.request_subsystem(true, "sftp").await?;
+    let mut sftp = Sftp::init(channel.into_stream()).await?;
+
+    let mut dir = String::new();
+    for part in settings.dirs() {
+        if !dir.is_empty() {
+            dir.push('/');
+        }
+        dir.push_str(part);
+        sftp.mkdir(&dir).await?;
+    }
+    for (name, content) in files {
+        let path = if dir.is_empty() {
+            name.clone()
+        } else {
+            format!("{}/{}", dir, name)
+        };
+        sftp.write_file(&path, content.as_bytes()).await?;
+    }
+    let _ = session
+        .disconnect(Disconnect::ByApplication, "", "English")
+        .await;
+    Ok(())
+}
+
+pub async fn upload(
+    client: &reqwest::Client,
+    settings: &BackupSettings,
+    files: &[(String, String)],
+) -> Result<()> {
+    match settings.target {
+        BackupTarget::S3 => {
+            for (name, content) in files {
+                put_s3(client, settings, name, content).await?;
+            }
+        }
+        BackupTarget::WebDav => {
+            for (name, content) in files {
+                put_webdav(client, settings, name, content).await?;
+            }
+        }
+        BackupTarget::Sftp => upload_sftp(settings, files).await?,
+    }
+    Ok(())
+}
+
+// 在后台备份一次，结果写入 last 并记入定时任务日志
+pub fn spawn_backup(
+    client: &reqwest::Client,
+    settings: &BackupSettings,
+    storage: &Arc<dyn Storage>,
+    settings_json: String,
+    last: &LastBackup,
+    log: &ActionLog,
+) {
+    let client = client.clone();
+    let settings = settings.clone();
+    let storage = Arc::clone(storage);
+    let last = Arc::clone(last);
+    let log = log.clone();
+    tokio::spawn(async move {
+        let result = async {
+            let files = collect(&*storage, &settings_json, settings.include_history)?;
+            upload(&client, &settings, &files).await?;
+            Ok::<_, anyhow::Error>(files.len())
+        }
+        .await;
+        let (files, error) = match result {
+            Ok(files) => {
+                log.push(tr!(
+                    "已备份 {} 个文件到 {}",
+                    "Backed up {} files to {}",
+                    files,
+                    settings.target.label()
+                ));
+                (files, None)
+            }
+            Err(e) => {
+                log.push(tr!("备份失败: {}", "Backup failed: {}", e));
+                (0, Some(e.to_string()))
+            }
+        };
+        let result = BackupResult {
+            at: Local::now(),
+            files,
+            error,
+        };
+        save_last(&*storage, &result);
+        *last.lock().unwrap() = Some(result);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use std::collections::BTreeMap;
+    use wiremock::matchers::{header, header_exists, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[derive(Default)]
+    struct MemoryStorage(Mutex<BTreeMap<String, String>>);
+
+    impl Storage for MemoryStorage {
+        fn load(&self, doc: &str) -> Result<Option<String>> {
+            Ok(self.0.lock().unwrap().get(doc).cloned())
+        }
+        fn save(&self, doc: &str, content: &str) -> Result<()> {
+            self.0
+                .lock()
+                .unwrap()
+                .insert(doc.to_string(), content.to_string());
+            Ok(())
+        }
+        fn delete(&self, doc: &str) -> Result<()> {
+            self.0.lock().unwrap().remove(doc);
+            Ok(())
+        }
+        fn list(&self, prefix: &str) -> Result<Vec<String>> {
+            Ok(self
+                .0
+                .lock()
+                .unwrap()
+                .keys()
+                .filter(|k| k.starts_with(prefix))
+                .cloned()
+                .collect())
+        }
+    }
+
+    #[test]
+    fn collects_profiles_and_optional_history() {
+        let storage = MemoryStorage::default();
+        storage.save(&storage::servers_doc("默认"), "[]").unwrap();
+        storage.save(&storage::servers_doc("prod"), "[1]").unwrap();
+        storage.save(HISTORY_DOC, "{}").unwrap();
+
+        let names = |files: Vec<(String, String)>| -> Vec<String> {
+            files.into_iter().map(|(name, _)| name).collect()
+        };
+        let files = collect(&storage, "{}", false).unwrap();
+        assert_eq!(
+            names(files),
+            ["settings.json", "servers-默认.json", "servers-prod.json"]
+        );
+        let files = collect(&storage, "{}", true).unwrap();
+        assert_eq!(names(files).last().unwrap(), "history.json");
+    }
+
+    #[tokio::test]
+    async fn uploads_to_s3_and_webdav() {
+        let mock = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/backups/servercheck/settings.json"))
+            .and(header_exists("authorization"))
+            .and(header_exists("x-amz-content-sha256"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        let client = testing::client(Duration::from_secs(5));
+        let files = vec![("settings.json".to_string(), "{}".to_string())];
+        let mut settings = BackupSettings {
+            s3_endpoint: mock.uri(),
+            s3_bucket: "backups".to_string(),
+            s3_access_key: "AKID".to_string(),
+            s3_secret_key: "secret".to_string(),
+            ..Default::default()
+        };
+        assert!(settings.ready());
+        upload(&client, &settings, &files).await.unwrap();
+
+        // 第一次 PUT 时目录还不存在
+        let dav = MockServer::start().await;
+        Mock::given(method("PUT"))
+            .and(path("/dav/servercheck/settings.json"))
+            .respond_with(ResponseTemplate::new(409))
+            .up_to_n_times(1)
+            .mount(&dav)
+            .await;
+        Mock::given(method("MKCOL"))
+            .and(path("/dav/servercheck"))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&dav)
+            .await;
+        Mock::given(method("PUT"))
+            .and(path("/dav/servercheck/settings.json"))
+            .and(header("authorization", "Basic bWU6cHc="))
+            .respond_with(ResponseTemplate::new(201))
+            .expect(1)
+            .mount(&dav)
+            .await;
+        settings.target = BackupTarget::WebDav;
+        settings.webdav_url = format!("{}/dav/", dav.uri());
+        settings.webdav_user = "me".to_string();
+        settings.webdav_password = "pw".to_string();
+        upload(&client, &settings, &files).await.unwrap();
+
+        settings.webdav_url = format!("{}/missing", mock.uri());
+        assert!(upload(&client, &settings, &files).await.is_err());
+    }
+
+    // 模拟SFTP服务器：创建目录成功，打开文件返回句柄，记录写入的内容
+    async fn fake_sftp_server(mut stream: tokio::io::DuplexStream) -> Vec<(u8, Vec<u8>)> {
+        let mut requests = Vec::new();
+        let mut written = Vec::new();
+        while let Ok(len) = stream.read_u32().await {
+            let kind = stream.read_u8().await.unwrap();
+            let mut body = vec![0; len as usize - 1];
+            stream.read_exact(&mut body).await.unwrap();
+            let reply = match kind {
+                SSH_FXP_INIT => {
+                    let mut reply = vec![2];
+                    reply.extend_from_slice(&3u32.to_be_bytes());
+                    reply
+                }
+                SSH_FXP_OPEN => {
+                    let mut reply = vec![SSH_FXP_HANDLE];
+                    reply.extend_from_slice(&body[..4]);
+                    sftp_string(&mut reply, b"h1");
+                    reply
+                }
+                _ => {
+                    if kind == SSH_FXP_WRITE {
+                        // ID、句柄、偏移，之后是数据
+                        written.extend_from_slice(&body[4 + 4 + 2 + 8 + 4..]);
+                    }
+                    let mut reply = vec![SSH_FXP_STATUS];
+                    reply.extend_from_slice(&body[..4]);
+                    reply.extend_from_slice(&0u32.to_be_bytes());
+                    reply
+                }
+            };
+            requests.push((kind, body));
+            let mut out = (reply.len() as u32).to_be_bytes().to_vec();
+            out.extend_from_slice(&reply);
+            stream.write_all(&out).await.unwrap();
+        }
+        requests.push((0, written));
+        requests
+    }
+
+    #[tokio::test]
+    async fn writes_files_over_sftp() {
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let server = tokio::spawn(fake_sftp_server(server));
+        let mut sftp = Sftp::init(client).await.unwrap();
+        sftp.mkdir("servercheck").await.unwrap();
+        let content = "x".repeat(SFTP_CHUNK + 10);
+        sftp.write_file("servercheck/settings.json", content.as_bytes())
+            .await
+            .unwrap();
+        drop(sftp);
+
+        let requests = server.await.unwrap();
+        let kinds: Vec<u8> = requests.iter().map(|(kind, _)| *kind).collect();
+        // 超过一块的内容分两次写入
+        assert_eq!(
+            kinds,
+            [
+                SSH_FXP_INIT,
+                SSH_FXP_MKDIR,
+                SSH_FXP_OPEN,
+                SSH_FXP_WRITE,
+                SSH_FXP_WRITE,
+                SSH_FXP_CLOSE,
+                0
+            ]
+        );
+        let open = &requests[2].1;
+        assert_eq!(&open[8..8 + 25], b"servercheck/settings.json");
+        assert_eq!(requests.last().unwrap().1, content.as_bytes());
+    }
+
+    #[test]
+    fn backs_up_at_startup_when_overdue() {
+        let storage = MemoryStorage::default();
+        let settings = BackupSettings::default();
+        let now = Local::now();
+        let last = load_last(&storage);
+        assert!(overdue(&last, &settings, now));
+
+        save_last(
+            &storage,
+            &BackupResult {
+                at: now - chrono::Duration::hours(1),
+                files: 2,
+                error: None,
+            },
+        );
+        let last = load_last(&storage);
+        assert!(!overdue(&last, &settings, now));
+        assert!(overdue(&last, &settings, now + chrono::Duration::hours(23)));
+    }
+}
@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::i18n::tr;
+use crate::storage::{Storage, BASELINE_DOC};
+
+// 与安全相关的响应属性
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct SecurityProfile {
+    pub tls_version: Option<String>,
+    pub cipher: Option<String>,
+    // Strict-Transport-Security 响应头
+    pub hsts: Option<String>,
+    // Server 响应头
+    pub server_header: Option<String>,
+}
+
+impl SecurityProfile {
+    // 与基线相比发生变化的属性，例如 "TLS版本: TLS 1.3 → TLS 1.2"
+    pub fn changes_from(&self, baseline: &SecurityProfile) -> Vec<String> {
+        let fields = [
+            (
+                tr!("TLS版本", "TLS version"),
+                &baseline.tls_version,
+                &self.tls_version,
+            ),
+            (tr!("加密套件", "Cipher"), &baseline.cipher, &self.cipher),
+            ("HSTS", &baseline.hsts, &self.hsts),
+            ("Server", &baseline.server_header, &self.server_header),
+        ];
+        let show =
+            |value: &Option<String>| value.clone().unwrap_or_else(|| tr!("无", "none").into());
+        fields
+            .into_iter()
+            .filter(|(_, old, new)| old != new)
+            .map(|(label, old, new)| format!("{}: {} → {}", label, show(old), show(new)))
+            .collect()
+    }
+}
+
+// 每台服务器的安全配置基线，默认保存在配置目录的 baseline.json
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Baselines {
+    profiles: HashMap<String, SecurityProfile>,
+}
+
+impl Baselines {
+    pub fn load(storage: &dyn Storage) -> Self {
+        match storage.load(BASELINE_DOC) {
+            Ok(content) => content
+                .and_then(|content| serde_json::from_str(&content).ok())
+                .unwrap_or_default(),
+            Err(e) => {
+                eprintln!("读取安全基线失败: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, storage: &dyn Storage) -> Result<(), Box<dyn std::error::Error>> {
+        storage.save(BASELINE_DOC, &serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    pub fn get(&self, key: &str) -> Option<&SecurityProfile> {
+        self.profiles.get(key)
+    }
+
+    // 设置或接受新的基线
+    pub fn set(&mut self, key: &str, profile: SecurityProfile) {
+        self.profiles.insert(key.to_string(), profile);
+    }
+
+    pub fn remove(&mut self, key: &str) {
+        self.profiles.remove(key);
+    }
+
+    // 把 from 的基线移到 to 下，to 已有基线时不动
+    pub fn rename(&mut self, from: &str, to: &str) -> bool {
+        if self.profiles.contains_key(to) {
+            return false;
+        }
+        let Some(profile) = self.profiles.remove(from) else {
+            return false;
+        };
+        self.profiles.insert(to.to_string(), profile);
+        true
+    }
+}
@@ -0,0 +1,280 @@
+use eframe::egui;
+
+use crate::check_editor;
+use crate::i18n::tr;
+use crate::notify::{self, Channel, ChannelKind, StatusEvent};
+use crate::schedule::ActionLog;
+use crate::ServerStatus;
+
+// 通知渠道窗口：编辑渠道，导出/导入不含密钥的模板
+#[derive(Default)]
+pub struct ChannelsWindow {
+    pub open: bool,
+    // 粘贴的模板内容
+    import_text: String,
+    // 已解析、等待填写密钥的渠道
+    importing: Option<Vec<Channel>>,
+    message: Option<String>,
+    error: Option<String>,
+}
+
+// 渠道的地址或会话ID一栏，返回是否有修改
+fn show_target_field(ui: &mut egui::Ui, channel: &mut Channel) -> bool {
+    match channel.kind {
+        ChannelKind::Webhook => {
+            ui.label(tr!("地址:", "URL:"));
+            ui.text_edit_singleline(&mut channel.url).changed()
+        }
+        ChannelKind::Telegram => {
+            ui.label("Chat ID:");
+            ui.text_edit_singleline(&mut channel.chat_id).changed()
+        }
+        ChannelKind::Command => {
+            ui.label(tr!("命令:", "Command:"));
+            ui.add(
+                egui::TextEdit::singleline(&mut channel.command)
+                    .hint_text("/opt/hooks/on_change.sh")
+                    .desired_width(220.0),
+            )
+            .changed()
+        }
+        _ => false,
+    }
+}
+
+// 运行命令渠道不使用密钥一栏，需要的密钥从钥匙串传入
+fn show_token_field(ui: &mut egui::Ui, channel: &mut Channel) -> bool {
+    if channel.kind == ChannelKind::Command {
+        return false;
+    }
+    ui.label(format!("{}:", channel.kind.token_label()));
+    let key = format!("channel-{}", channel.name.trim());
+    check_editor::secret_input(ui, &mut channel.token, &key)
+}
+
+impl ChannelsWindow {
+    // 返回渠道设置是否被修改
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        channels: &mut Vec<Channel>,
+        client: &reqwest::Client,
+        log: &ActionLog,
+    ) -> bool {
+        let mut open = self.open;
+        let mut changed = false;
+
+        egui::Window::new(tr!("🔔 通知渠道", "🔔 Notification channels"))
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.small(tr!(
+                    "服务器状态变化时向启用的渠道发送消息",
+                    "Enabled channels receive a message whenever a server's status changes"
+                ));
+                let mut remove = None;
+                for (i, channel) in channels.iter_mut().enumerate() {
+                    ui.push_id(i, |ui| {
+                        ui.horizontal(|ui| {
+                            changed |= ui.checkbox(&mut channel.enabled, "").changed();
+                            changed |= ui
+                                .add(
+                                    egui::TextEdit::singleline(&mut channel.name)
+                                        .hint_text(tr!("名称", "Name"))
+                                        .desired_width(100.0),
+                                )
+                                .changed();
+                            egui::ComboBox::from_id_source("channel_kind")
+                                .selected_text(channel.kind.label())
+                                .show_ui(ui, |ui| {
+                                    for kind in ChannelKind::ALL {
+                                        changed |= ui
+                                            .selectable_value(&mut channel.kind, kind, kind.label())
+                                            .changed();
+                                    }
+                                });
+                            if ui
+                                .small_button(tr!("测试", "Test"))
+                                .on_hover_text(tr!("发送一条测试消息", "Send a test message"))
+                                .clicked()
+                            {
+                                send_test(client, channel, log);
+                            }
+                            if ui.small_button("🗑").clicked() {
+                                remove = Some(i);
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            ui.add_space(24.0);
+                            changed |= show_target_field(ui, channel);
+                            changed |= show_token_field(ui, channel);
+                            if channel.missing_token() {
+                                ui.colored_label(
+                                    ServerStatus::Degraded.color(),
+                                    tr!("⚠ 未填写密钥", "⚠ Token not set"),
+                                );
+                            }
+                        });
+                        if channel.kind == ChannelKind::Command {
+                            ui.indent("command_channel", |ui| {
+                                changed |= check_editor::show_hook_secrets(
+                                    ui,
+                                    "command_channel",
+                                    &mut channel.secrets,
+                                );
+                                check_editor::show_env_docs(
+                                    ui,
+                                    "command_env",
+                                    notify::command_env_docs(),
+                                );
+                            });
+                        }
+                    });
+                }
+                if let Some(i) = remove {
+                    channels.remove(i);
+                    changed = true;
+                }
+                if ui.button(tr!("➕ 添加渠道", "➕ Add channel")).clicked() {
+                    channels.push(Channel {
+                        name: tr!("渠道{}", "Channel {}", channels.len() + 1),
+                        ..Default::default()
+                    });
+                    changed = true;
+                }
+
+                ui.separator();
+                ui.strong(tr!("分享模板", "Share as template"));
+                ui.horizontal(|ui| {
+                    if ui
+                        .add_enabled(
+                            !channels.is_empty(),
+                            egui::Button::new(tr!("📤 复制模板", "📤 Copy template")),
+                        )
+                        .clicked()
+                    {
+                        ui.ctx().copy_text(notify::export_template(channels));
+                        self.message = Some(
+                            tr!(
+                                "已复制到剪贴板，模板中不包含密钥",
+                                "Copied to clipboard; tokens are not included"
+                            )
+                            .to_string(),
+                        );
+                    }
+                    if let Some(message) = &self.message {
+                        ui.small(message);
+                    }
+                });
+                changed |= self.show_import(ui, channels);
+            });
+
+        self.open = open;
+        changed
+    }
+
+    // 粘贴模板、填写密钥后导入
+    fn show_import(&mut self, ui: &mut egui::Ui, channels: &mut Vec<Channel>) -> bool {
+        let mut changed = false;
+        if let Some(importing) = &mut self.importing {
+            ui.label(tr!(
+                "请填写导入渠道的密钥，留空的可以之后再填：",
+                "Fill in tokens for the imported channels, or leave them for later:"
+            ));
+            if importing.iter().any(|c| c.kind == ChannelKind::Command) {
+                ui.colored_label(
+                    ServerStatus::Degraded.color(),
+                    tr!(
+                        "⚠ 导入的运行命令渠道默认不启用，请确认命令后再启用",
+                        "⚠ Imported command channels start disabled; review the command before enabling"
+                    ),
+                );
+            }
+            egui::Grid::new("import_channels")
+                .num_columns(3)
+                .show(ui, |ui| {
+                    for channel in importing.iter_mut() {
+                        ui.label(format!("{} ({})", channel.name, channel.kind.label()));
+                        // 模板中没有地址时一并填写
+                        ui.horizontal(|ui| {
+                            let empty = match channel.kind {
+                                ChannelKind::Webhook => channel.url.is_empty(),
+                                ChannelKind::Command => channel.command.is_empty(),
+                                _ => false,
+                            };
+                            if empty {
+                                show_target_field(ui, channel);
+                            }
+                        });
+                        ui.horizontal(|ui| {
+                            show_token_field(ui, channel);
+                        });
+                        ui.end_row();
+                    }
+                });
+            let mut done = false;
+            ui.horizontal(|ui| {
+                if ui.button(tr!("导入", "Import")).clicked() {
+                    notify::merge_imported(channels, importing.clone());
+                    changed = true;
+                    done = true;
+                }
+                if ui.button(tr!("取消", "Cancel")).clicked() {
+                    done = true;
+                }
+            });
+            if done {
+                self.importing = None;
+                self.import_text.clear();
+            }
+            return changed;
+        }
+
+        ui.add(
+            egui::TextEdit::multiline(&mut self.import_text)
+                .hint_text(tr!("在此粘贴模板", "Paste a template here"))
+                .desired_rows(3)
+                .desired_width(f32::INFINITY),
+        );
+        if ui
+            .add_enabled(
+                !self.import_text.trim().is_empty(),
+                egui::Button::new(tr!("📥 导入模板", "📥 Import template")),
+            )
+            .clicked()
+        {
+            match notify::import_template(&self.import_text) {
+                Ok(imported) => {
+                    self.importing = Some(imported);
+                    self.error = None;
+                }
+                Err(e) => self.error = Some(e.to_string()),
+            }
+        }
+        if let Some(error) = &self.error {
+            ui.colored_label(ServerStatus::Offline.color(), error);
+        }
+        changed
+    }
+}
+
+fn send_test(client: &reqwest::Client, channel: &Channel, log: &ActionLog) {
+    let client = client.clone();
+    let channel = channel.clone();
+    let log = log.clone();
+    tokio::spawn(async move {
+        match notify::send(&client, &channel, &StatusEvent::test()).await {
+            Ok(()) => log.push(tr!(
+                "通知渠道 {} 测试消息已发送",
+                "Test message sent to notification channel {}",
+                channel.name
+            )),
+            Err(e) => log.push(tr!(
+                "通知渠道 {} 发送失败: {}",
+                "Notification channel {} failed: {}",
+                channel.name,
+                e
+            )),
+        }
+    });
+}
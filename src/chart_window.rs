@@ -0,0 +1,250 @@
+use chrono::{DateTime, Duration, Local, TimeZone};
+use eframe::egui;
+use egui_plot::{GridInput, GridMark, Line, Plot, PlotPoint, Points};
+
+use crate::history::{History, LATENCY_BUCKET_MINUTES};
+use crate::i18n::tr;
+use crate::{theme, Server, ServerStatus};
+
+// 相邻两个点相隔超过几个时间段（例如程序没有运行）时折线断开
+const GAP_BUCKETS: i64 = 3;
+// 横轴刻度可取的间隔（秒）
+const TIME_STEPS: [f64; 9] = [
+    60.0, 300.0, 900.0, 1800.0, 3600.0, 10800.0, 21600.0, 43200.0, 86400.0,
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+enum ChartRange {
+    #[default]
+    Day,
+    Week,
+}
+
+impl ChartRange {
+    const ALL: [ChartRange; 2] = [ChartRange::Day, ChartRange::Week];
+
+    fn label(&self) -> &'static str {
+        match self {
+            ChartRange::Day => tr!("24小时", "24h"),
+            ChartRange::Week => tr!("7天", "7d"),
+        }
+    }
+
+    fn duration(&self) -> Duration {
+        match self {
+            ChartRange::Day => Duration::hours(24),
+            ChartRange::Week => Duration::days(7),
+        }
+    }
+}
+
+// 单台服务器的延迟和状态图表：滚轮缩放、拖动平移、双击还原，悬停查看具体数值
+#[derive(Default)]
+pub struct ChartWindow {
+    // 以历史记录的键确定服务器
+    target: Option<String>,
+    range: ChartRange,
+}
+
+fn local_time(secs: f64) -> Option<DateTime<Local>> {
+    Local.timestamp_opt(secs as i64, 0).single()
+}
+
+// 横轴最多约6个主刻度，间隔取整分钟、整小时或整天
+fn time_grid(input: GridInput) -> [f64; 3] {
+    let span = input.bounds.1 - input.bounds.0;
+    let large = TIME_STEPS
+        .iter()
+        .position(|step| span / step <= 6.0)
+        .unwrap_or(TIME_STEPS.len() - 1);
+    [
+        TIME_STEPS[large.saturating_sub(2)],
+        TIME_STEPS[large.saturating_sub(1)],
+        TIME_STEPS[large],
+    ]
+}
+
+impl ChartWindow {
+    pub fn is_open(&self) -> bool {
+        self.target.is_some()
+    }
+
+    pub fn open(&mut self, server: &Server) {
+        self.target = Some(server.history_key());
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        servers: &[Server],
+        history: &History,
+        now: DateTime<Local>,
+    ) {
+        let Some(key) = self.target.clone() else {
+            return;
+        };
+        // 服务器被删除后关闭窗口
+        let Some(server) = servers.iter().find(|s| s.history_key() == key) else {
+            self.target = None;
+            return;
+        };
+
+        let mut open = true;
+        egui::Window::new(tr!("📈 {} 图表", "📈 {} chart", server.name))
+            .id(egui::Id::new("latency_chart"))
+            .open(&mut open)
+            .resizable(true)
+            .default_size([640.0, 320.0])
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    for range in ChartRange::ALL {
+                        ui.selectable_value(&mut self.range, range, range.label());
+                    }
+                    ui.small(tr!(
+                        "滚轮缩放，拖动平移，双击还原",
+                        "Scroll to zoom, drag to pan, double-click to reset"
+                    ));
+                });
+                let full = (
+                    (now - self.range.duration()).timestamp() as f64,
+                    now.timestamp() as f64,
+                );
+                self.plot(ui, history, &key, full);
+            });
+        if !open {
+            self.target = None;
+        }
+    }
+
+    fn plot(&self, ui: &mut egui::Ui, history: &History, key: &str, full: (f64, f64)) {
+        let bucket = (LATENCY_BUCKET_MINUTES * 60) as f64;
+        let samples = local_time(full.0 - bucket)
+            .map(|since| history.latency(key, since))
+            .unwrap_or_default();
+        let spans = history.spans(key);
+
+        // 平均延迟折线，相隔太久的点之间断开
+        let mut segments: Vec<Vec<[f64; 2]>> = Vec::new();
+        let mut previous: Option<i64> = None;
+        for sample in samples {
+            let at = sample.at.timestamp();
+            if previous.is_none_or(|p| at - p > GAP_BUCKETS * bucket as i64) {
+                segments.push(Vec::new());
+            }
+            previous = Some(at);
+            if let Some(segment) = segments.last_mut() {
+                segment.push([at as f64, sample.avg_ms]);
+            }
+        }
+
+        // 悬停时显示该时间的状态和离鼠标最近的点
+        let label = |_: &str, value: &PlotPoint| {
+            let secs = value.x;
+            let mut lines = Vec::new();
+            if let Some(time) = local_time(secs) {
+                lines.push(time.format("%Y-%m-%d %H:%M").to_string());
+            }
+            let up = spans
+                .iter()
+                .find(|s| (s.start.timestamp() as f64..=s.end.timestamp() as f64).contains(&secs))
+                .map(|s| s.up);
+            let status = match up {
+                Some(true) => tr!("可用", "Up"),
+                Some(false) => tr!("故障", "Down"),
+                None => tr!("无数据", "No data"),
+            };
+            lines.push(tr!("状态: {}", "Status: {}", status));
+            let nearest = samples
+                .iter()
+                .min_by_key(|s| (s.at.timestamp() as f64 - secs).abs() as i64);
+            if let Some(sample) = nearest {
+                lines.push(tr!(
+                    "{} 平均 {:.0}ms，最高 {}ms（{} 次检查）",
+                    "{} avg {:.0}ms, max {}ms ({} checks)",
+                    sample.at.format("%H:%M"),
+                    sample.avg_ms,
+                    sample.max_ms,
+                    sample.count
+                ));
+            }
+            lines.join("\n")
+        };
+
+        let color = theme::status_color(&ServerStatus::Online);
+        let height = (ui.available_height() - 14.0).max(160.0);
+        // 切换范围时换一个id，缩放状态随之还原
+        let response = Plot::new(("latency_chart_plot", key, self.range))
+            .height(height)
+            .include_x(full.0)
+            .include_x(full.1)
+            .include_y(0.0)
+            .allow_zoom([true, false])
+            .allow_drag([true, false])
+            .allow_scroll([true, false])
+            .allow_boxed_zoom(false)
+            .x_grid_spacer(egui_plot::uniform_grid_spacer(time_grid))
+            .x_axis_formatter(|mark: GridMark, range| {
+                // 跨度超过两天时带上日期
+                let format = if range.end() - range.start() > 2.0 * 86400.0 {
+                    "%m-%d %H:%M"
+                } else {
+                    "%H:%M"
+                };
+                local_time(mark.value)
+                    .map(|t| t.format(format).to_string())
+                    .unwrap_or_default()
+            })
+            .y_axis_formatter(|mark: GridMark, _| format!("{}ms", mark.value))
+            .label_formatter(label)
+            .show(ui, |plot_ui| {
+                for segment in segments {
+                    // 只有一个点的段画成圆点
+                    if segment.len() == 1 {
+                        plot_ui.points(Points::new(segment).radius(2.0).color(color));
+                    } else {
+                        plot_ui.line(Line::new(segment).width(1.5).color(color));
+                    }
+                }
+            });
+
+        let plot = *response.transform.frame();
+        if samples.is_empty() {
+            ui.painter().text(
+                plot.center(),
+                egui::Align2::CENTER_CENTER,
+                tr!("这段时间没有延迟数据", "No latency data in this period"),
+                egui::FontId::proportional(14.0),
+                ui.visuals().weak_text_color(),
+            );
+        }
+
+        // 状态条与折线的横轴对齐：可用为在线的颜色，故障为离线的颜色，没有检查记录的时间留空
+        let (band, _) =
+            ui.allocate_exact_size(egui::vec2(ui.available_width(), 8.0), egui::Sense::hover());
+        let band = egui::Rect::from_x_y_ranges(plot.x_range(), band.y_range());
+        let painter = ui.painter_at(band);
+        painter.rect_filled(band, 0.0, ui.visuals().faint_bg_color);
+        let bounds = response.transform.bounds();
+        let (from, to) = (bounds.min()[0], bounds.max()[0]);
+        let x_of = |secs: f64| response.transform.position_from_point_x(secs);
+        for span in spans {
+            let (start, end) = (span.start.timestamp() as f64, span.end.timestamp() as f64);
+            if end < from || start > to {
+                continue;
+            }
+            let status = if span.up {
+                ServerStatus::Online
+            } else {
+                ServerStatus::Offline
+            };
+            let left = x_of(start.max(from));
+            // 很短的故障也至少画出一个像素
+            let right = x_of(end.min(to)).max(left + 1.0);
+            painter.rect_filled(
+                egui::Rect::from_x_y_ranges(left..=right, band.y_range()),
+                0.0,
+                theme::status_color(&status),
+            );
+        }
+    }
+}
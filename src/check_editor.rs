@@ -0,0 +1,1345 @@
+use chrono::{NaiveTime, Timelike};
+use eframe::egui;
+
+use crate::checks::assertion;
+use crate::checks::command::{self, CommandCheck};
+use crate::checks::database::{DatabaseCheck, DbEngine};
+use crate::checks::docker::{self, DockerCheck};
+use crate::checks::dual_stack::IpFamily;
+use crate::checks::elasticsearch::ElasticsearchCheck;
+use crate::checks::game::{GameCheck, GameProtocol};
+use crate::checks::http::{HttpMethod, HttpOptions, HttpVersion, RedirectPolicy};
+use crate::checks::http_steps::{ExtractSource, Extraction, HttpStep, HttpSteps};
+use crate::checks::kubernetes::{self, KubernetesCheck};
+use crate::checks::mail::{MailCheck, MailProtocol};
+use crate::checks::mapping::{RuleCondition, RuleResult, StatusRule};
+use crate::checks::metrics::{MetricsCheck, MetricsSource};
+use crate::checks::mqtt::MqttCheck;
+use crate::checks::ntp::NtpCheck;
+use crate::checks::resolver;
+use crate::checks::snmp::{self, SnmpAuth, SnmpCheck, SnmpVersion};
+use crate::checks::ssh::SshCheck;
+use crate::checks::ssh_banner::SshBannerCheck;
+use crate::checks::systemd::SystemdCheck;
+use crate::checks::tunnel::SshTunnel;
+use crate::checks::udp::{PayloadFormat, UdpCheck};
+use crate::checks::winrm::WinRmCheck;
+use crate::checks::CheckKind;
+use crate::history::Exclusion;
+use crate::i18n::tr;
+use crate::proxy::{ProxyMode, ProxySettings};
+use crate::{secret, ServerStatus, MAX_TIMEOUT_SECS};
+
+// 编辑对话框中的检查方式设置
+pub fn show(ui: &mut egui::Ui, check: &mut CheckKind, ip: &str) {
+    ui.horizontal(|ui| {
+        ui.label(tr!("检查方式:", "Check type:"));
+        egui::ComboBox::from_id_source("check_kind")
+            .selected_text(check.label())
+            .show_ui(ui, |ui| {
+                for kind in [
+                    CheckKind::Http,
+                    CheckKind::Ssh(SshCheck::default()),
+                    CheckKind::Metrics(MetricsCheck::default()),
+                    CheckKind::WinRm(WinRmCheck::default()),
+                    CheckKind::Systemd(SystemdCheck::default()),
+                    CheckKind::SshBanner(SshBannerCheck::default()),
+                    CheckKind::Database(DatabaseCheck::default()),
+                    CheckKind::Mqtt(MqttCheck::default()),
+                    CheckKind::Mail(MailCheck::default()),
+                    CheckKind::Elasticsearch(ElasticsearchCheck::default()),
+                    CheckKind::Docker(DockerCheck::default()),
+                    CheckKind::Kubernetes(KubernetesCheck::default()),
+                    CheckKind::Snmp(SnmpCheck::default()),
+                    CheckKind::Ntp(NtpCheck::default()),
+                    CheckKind::Game(GameCheck::default()),
+                    CheckKind::Command(CommandCheck::default()),
+                    CheckKind::Udp(UdpCheck::default()),
+                    CheckKind::HttpSteps(HttpSteps::default()),
+                ] {
+                    let selected = std::mem::discriminant(check) == std::mem::discriminant(&kind);
+                    if ui.selectable_label(selected, kind.label()).clicked() && !selected {
+                        *check = kind;
+                    }
+                }
+            });
+    });
+
+    match check {
+        CheckKind::Http => {}
+        CheckKind::Ssh(ssh) => show_ssh(ui, ssh),
+        CheckKind::Metrics(metrics) => show_metrics(ui, metrics, ip),
+        CheckKind::WinRm(winrm) => show_winrm(ui, winrm),
+        CheckKind::Systemd(systemd) => show_systemd(ui, systemd),
+        CheckKind::Database(database) => show_database(ui, database),
+        CheckKind::Mqtt(mqtt) => show_mqtt(ui, mqtt),
+        CheckKind::Mail(mail) => show_mail(ui, mail),
+        CheckKind::Elasticsearch(es) => show_elasticsearch(ui, es, ip),
+        CheckKind::Docker(docker) => show_docker(ui, docker),
+        CheckKind::Kubernetes(k8s) => show_kubernetes(ui, k8s),
+        CheckKind::Snmp(snmp) => show_snmp(ui, snmp),
+        CheckKind::Game(game) => show_game(ui, game),
+        CheckKind::Udp(udp) => show_udp(ui, udp),
+        CheckKind::HttpSteps(steps) => show_http_steps(ui, steps),
+        CheckKind::Command(command) => {
+            ui.horizontal(|ui| {
+                ui.label(tr!("命令:", "Command:"));
+                ui.add(
+                    egui::TextEdit::singleline(&mut command.command)
+                        .hint_text(
+                            "/usr/lib/nagios/plugins/check_ping -H {host} -w 100,20% -c 500,60%",
+                        )
+                        .desired_width(320.0),
+                );
+            });
+            ui.small(tr!(
+                "在本机运行，{host} 替换为加了引号的服务器地址；按 Nagios 插件约定，退出码0为在线，1为降级，2为离线，其他为错误；输出第一行显示在说明中，| 之后的性能数据可以作为自定义列",
+                "Runs on this machine with {host} replaced by the quoted server address. Following the Nagios plugin convention, exit code 0 is online, 1 degraded, 2 offline and anything else an error. The first output line is shown as the detail; perfdata after | can be used as custom columns"
+            ));
+            show_hook_secrets(ui, "command_check", &mut command.secrets);
+            show_env_docs(ui, "command_check_env", command::env_docs());
+        }
+        CheckKind::Ntp(ntp) => {
+            ui.horizontal(|ui| {
+                ui.label(tr!("端口:", "Port:"));
+                ui.add(egui::DragValue::new(&mut ntp.port));
+                ui.label(tr!("允许偏差:", "Max offset:"));
+                ui.add(
+                    egui::DragValue::new(&mut ntp.max_offset_ms)
+                        .range(0..=60_000)
+                        .suffix(" ms"),
+                );
+            });
+            ui.small(tr!(
+                "发送一次SNTP查询，说明中显示本机与服务器的时钟偏差、层级和参考源；偏差超过设置值或服务器未同步时为错误，0表示不检查偏差",
+                "Sends one SNTP query and shows the clock offset, stratum and reference. An offset above the limit or an unsynchronized server is an error; 0 skips the offset check"
+            ));
+        }
+        CheckKind::SshBanner(banner) => {
+            ui.horizontal(|ui| {
+                ui.label(tr!("SSH端口:", "SSH port:"));
+                ui.add(egui::DragValue::new(&mut banner.port));
+            });
+            ui.small(tr!(
+                "只读取服务器的SSH版本标识，不登录；收到标识为在线，说明中显示版本",
+                "Only reads the server's SSH version banner without logging in; online when a banner arrives, shown in the detail"
+            ));
+        }
+    }
+}
+
+fn show_ssh(ui: &mut egui::Ui, ssh: &mut SshCheck) {
+    egui::Grid::new("ssh_check").num_columns(2).show(ui, |ui| {
+        ui.label(tr!("用户名:", "User:"));
+        ui.text_edit_singleline(&mut ssh.user);
+        ui.end_row();
+        ui.label(tr!("SSH端口:", "SSH port:"));
+        ui.add(egui::DragValue::new(&mut ssh.port));
+        ui.end_row();
+        ui.label(tr!("私钥路径:", "Private key:"));
+        ui.text_edit_singleline(&mut ssh.key_path);
+        ui.end_row();
+        ui.label(tr!("私钥密码:", "Key passphrase:"));
+        ui.horizontal(|ui| {
+            let key = format!("ssh-key-{}", ssh.key_path.trim());
+            secret_input(ui, &mut ssh.key_passphrase, &key);
+        });
+        ui.end_row();
+        ui.label("");
+        trust_unknown_host_checkbox(ui, &mut ssh.trust_unknown_host);
+        ui.end_row();
+        ui.label(tr!("命令:", "Command:"));
+        ui.text_edit_singleline(&mut ssh.command);
+        ui.end_row();
+        ui.label(tr!("输出需包含:", "Output must contain:"));
+        ui.text_edit_singleline(&mut ssh.expect);
+        ui.end_row();
+    });
+    ui.small(tr!(
+        "退出码为0视为在线，非0显示为错误(退出码)",
+        "Exit code 0 means online; non-zero is shown as an error (exit code)"
+    ));
+}
+
+// 默认只连接 known_hosts 中记录过的主机，勾选后第一次连接时记录主机密钥
+pub fn trust_unknown_host_checkbox(ui: &mut egui::Ui, trust: &mut bool) -> bool {
+    ui.checkbox(trust, tr!("信任新主机", "Trust new hosts"))
+        .on_hover_text(tr!(
+            "主机密钥不在 known_hosts 中时接受并记录下来；之后密钥变化仍会拒绝连接",
+            "Accept and record host keys missing from known_hosts; a changed key is still refused"
+        ))
+        .changed()
+}
+
+fn show_metrics(ui: &mut egui::Ui, metrics: &mut MetricsCheck, ip: &str) {
+    egui::Grid::new("metrics_check")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label(tr!("数据来源:", "Source:"));
+            egui::ComboBox::from_id_source("metrics_source")
+                .selected_text(metrics.source.label())
+                .show_ui(ui, |ui| {
+                    for source in [MetricsSource::NodeExporter, MetricsSource::Glances] {
+                        ui.selectable_value(&mut metrics.source, source, source.label());
+                    }
+                });
+            ui.end_row();
+            ui.label(tr!("指标地址:", "Metrics URL:"));
+            let default_endpoint = MetricsCheck {
+                url: String::new(),
+                ..metrics.clone()
+            }
+            .endpoint(ip);
+            ui.add(egui::TextEdit::singleline(&mut metrics.url).hint_text(default_endpoint));
+            ui.end_row();
+            ui.label(tr!("CPU阈值:", "CPU threshold:"));
+            ui.add(egui::Slider::new(&mut metrics.cpu_threshold, 10.0..=100.0).suffix("%"));
+            ui.end_row();
+            ui.label(tr!("内存阈值:", "Memory threshold:"));
+            ui.add(egui::Slider::new(&mut metrics.mem_threshold, 10.0..=100.0).suffix("%"));
+            ui.end_row();
+            ui.label(tr!("磁盘阈值:", "Disk threshold:"));
+            ui.add(egui::Slider::new(&mut metrics.disk_threshold, 10.0..=100.0).suffix("%"));
+            ui.end_row();
+        });
+    ui.small(tr!(
+        "任一使用率达到阈值时显示为降级",
+        "Shown as degraded when any usage reaches its threshold"
+    ));
+}
+
+fn show_winrm(ui: &mut egui::Ui, winrm: &mut WinRmCheck) {
+    egui::Grid::new("winrm_check")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label(tr!("协议:", "Scheme:"));
+            ui.horizontal(|ui| {
+                if ui.radio_value(&mut winrm.https, false, "HTTP").clicked() {
+                    winrm.port = 5985;
+                }
+                if ui.radio_value(&mut winrm.https, true, "HTTPS").clicked() {
+                    winrm.port = 5986;
+                }
+            });
+            ui.end_row();
+            ui.label(tr!("端口:", "Port:"));
+            ui.add(egui::DragValue::new(&mut winrm.port));
+            ui.end_row();
+            ui.label(tr!("用户名:", "User:"));
+            ui.text_edit_singleline(&mut winrm.user);
+            ui.end_row();
+            ui.label(tr!("密码:", "Password:"));
+            ui.add(egui::TextEdit::singleline(&mut winrm.password).password(true));
+            ui.end_row();
+            ui.label(tr!("服务名:", "Service:"));
+            ui.add(
+                egui::TextEdit::singleline(&mut winrm.service).hint_text(tr!(
+                    "例如 W3SVC，留空不检查",
+                    "e.g. W3SVC, leave empty to skip"
+                )),
+            );
+            ui.end_row();
+            ui.label(tr!("磁盘:", "Disk:"));
+            ui.add(
+                egui::TextEdit::singleline(&mut winrm.disk)
+                    .hint_text(tr!("例如 C:，留空不检查", "e.g. C:, leave empty to skip")),
+            );
+            ui.end_row();
+            ui.label(tr!("最低剩余空间:", "Minimum free space:"));
+            ui.add(egui::Slider::new(&mut winrm.min_free_percent, 1.0..=50.0).suffix("%"));
+            ui.end_row();
+        });
+    if winrm.https {
+        ui.checkbox(
+            &mut winrm.accept_invalid_certs,
+            tr!("允许自签名证书", "Accept self-signed certificates"),
+        );
+    }
+    ui.small(tr!(
+        "使用Basic认证，需在目标机器启用 winrm/config/service/auth Basic",
+        "Uses Basic auth; enable winrm/config/service/auth Basic on the target"
+    ));
+    ui.small(tr!(
+        "密码以明文保存在配置文件中",
+        "The password is stored in plain text in the config file"
+    ));
+}
+
+fn show_systemd(ui: &mut egui::Ui, systemd: &mut SystemdCheck) {
+    egui::Grid::new("systemd_check")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label(tr!("单元:", "Unit:"));
+            ui.add(egui::TextEdit::singleline(&mut systemd.unit).hint_text(tr!(
+                "例如 nginx 或 docker.socket",
+                "e.g. nginx or docker.socket"
+            )));
+            ui.end_row();
+            ui.label(tr!("查询位置:", "Query on:"));
+            ui.horizontal(|ui| {
+                ui.radio_value(
+                    &mut systemd.remote,
+                    false,
+                    tr!("本机 (D-Bus)", "This machine (D-Bus)"),
+                );
+                ui.radio_value(
+                    &mut systemd.remote,
+                    true,
+                    tr!("服务器 (SSH)", "Server (SSH)"),
+                );
+            });
+            ui.end_row();
+            if systemd.remote {
+                ui.label(tr!("用户名:", "User:"));
+                ui.text_edit_singleline(&mut systemd.user);
+                ui.end_row();
+                ui.label(tr!("SSH端口:", "SSH port:"));
+                ui.add(egui::DragValue::new(&mut systemd.port));
+                ui.end_row();
+                ui.label(tr!("私钥路径:", "Private key:"));
+                ui.text_edit_singleline(&mut systemd.key_path);
+                ui.end_row();
+                ui.label(tr!("私钥密码:", "Key passphrase:"));
+                ui.horizontal(|ui| {
+                    let key = format!("ssh-key-{}", systemd.key_path.trim());
+                    secret_input(ui, &mut systemd.key_passphrase, &key);
+                });
+                ui.end_row();
+                ui.label("");
+                trust_unknown_host_checkbox(ui, &mut systemd.trust_unknown_host);
+                ui.end_row();
+            }
+        });
+    ui.small(tr!(
+        "active 为在线，正在启动或重新加载为降级，其他状态为离线；本机查询只支持Linux",
+        "active is online, activating or reloading is degraded, anything else is offline; local queries need Linux"
+    ));
+}
+
+fn show_database(ui: &mut egui::Ui, database: &mut DatabaseCheck) {
+    egui::Grid::new("database_check")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label(tr!("类型:", "Engine:"));
+            ui.horizontal(|ui| {
+                for engine in DbEngine::ALL {
+                    let selected = database.engine == engine;
+                    if ui.radio(selected, engine.label()).clicked() && !selected {
+                        // 端口还是上一种的默认值时跟着切换
+                        if database.port == database.engine.default_port() {
+                            database.port = engine.default_port();
+                        }
+                        database.engine = engine;
+                    }
+                }
+            });
+            ui.end_row();
+            ui.label(tr!("端口:", "Port:"));
+            ui.add(egui::DragValue::new(&mut database.port));
+            ui.end_row();
+            ui.label(tr!("用户名:", "User:"));
+            let hint = match database.engine {
+                DbEngine::Redis => tr!("Redis 6 的ACL用户，可留空", "Redis 6 ACL user, optional"),
+                DbEngine::MySql => "",
+                DbEngine::Postgres => tr!("留空为 postgres", "Defaults to postgres"),
+            };
+            ui.add(egui::TextEdit::singleline(&mut database.user).hint_text(hint));
+            ui.end_row();
+            ui.label(tr!("密码:", "Password:"));
+            ui.add(egui::TextEdit::singleline(&mut database.password).password(true));
+            ui.end_row();
+            if database.engine != DbEngine::Redis {
+                ui.label(tr!("数据库:", "Database:"));
+                ui.add(
+                    egui::TextEdit::singleline(&mut database.database)
+                        .hint_text(tr!("可留空", "Optional")),
+                );
+                ui.end_row();
+            }
+        });
+    if !database.engine.available() {
+        ui.colored_label(
+            ServerStatus::Offline.color(),
+            tr!(
+                "此版本编译时未包含 {} 检查",
+                "This build does not include {} checks",
+                database.engine.label()
+            ),
+        );
+    }
+    ui.small(tr!(
+        "Redis 发送 PING，MySQL/PostgreSQL 登录后执行 SELECT 1；不使用TLS，密码以明文保存在配置文件中",
+        "Redis is sent PING; MySQL/PostgreSQL log in and run SELECT 1. TLS is not used and the password is stored in plain text"
+    ));
+}
+
+// 证书上的主机名，服务器地址是IP时证书按它校验
+fn show_tls_name(ui: &mut egui::Ui, name: &mut String) {
+    ui.horizontal(|ui| {
+        ui.label(tr!("证书主机名:", "Certificate name:"));
+        ui.add(egui::TextEdit::singleline(name).hint_text(tr!(
+            "留空使用服务器地址",
+            "Leave empty to use the server address"
+        )));
+    });
+}
+
+fn show_mqtt(ui: &mut egui::Ui, mqtt: &mut MqttCheck) {
+    egui::Grid::new("mqtt_check").num_columns(2).show(ui, |ui| {
+        ui.label(tr!("协议:", "Scheme:"));
+        ui.horizontal(|ui| {
+            if ui.radio_value(&mut mqtt.tls, false, "MQTT").clicked() {
+                mqtt.port = 1883;
+            }
+            if ui
+                .radio_value(&mut mqtt.tls, true, "MQTT over TLS")
+                .clicked()
+            {
+                mqtt.port = 8883;
+            }
+        });
+        ui.end_row();
+        ui.label(tr!("端口:", "Port:"));
+        ui.add(egui::DragValue::new(&mut mqtt.port));
+        ui.end_row();
+        ui.label(tr!("用户名:", "User:"));
+        ui.add(
+            egui::TextEdit::singleline(&mut mqtt.user)
+                .hint_text(tr!("留空为匿名连接", "Leave empty to connect anonymously")),
+        );
+        ui.end_row();
+        ui.label(tr!("密码:", "Password:"));
+        ui.add(egui::TextEdit::singleline(&mut mqtt.password).password(true));
+        ui.end_row();
+        ui.label(tr!("测试主题:", "Test topic:"));
+        ui.add(egui::TextEdit::singleline(&mut mqtt.topic).hint_text(tr!(
+            "例如 server_check/probe，留空只检查连接",
+            "e.g. server_check/probe, leave empty to only connect"
+        )));
+        ui.end_row();
+    });
+    if mqtt.tls {
+        ui.checkbox(
+            &mut mqtt.accept_invalid_certs,
+            tr!("允许自签名证书", "Accept self-signed certificates"),
+        );
+        show_tls_name(ui, &mut mqtt.tls_name);
+    }
+    ui.small(tr!(
+        "填写测试主题时订阅该主题并发布一条消息，收到后以往返时间作为延迟；需要账号有该主题的发布和订阅权限。密码以明文保存在配置文件中",
+        "With a test topic, subscribes to it and publishes a message; the round trip is used as the latency. The account needs publish and subscribe rights on the topic. The password is stored in plain text"
+    ));
+}
+
+fn show_mail(ui: &mut egui::Ui, mail: &mut MailCheck) {
+    egui::Grid::new("mail_check").num_columns(2).show(ui, |ui| {
+        ui.label(tr!("协议:", "Protocol:"));
+        ui.horizontal(|ui| {
+            for protocol in MailProtocol::ALL {
+                let selected = mail.protocol == protocol;
+                if ui.radio(selected, protocol.label()).clicked() && !selected {
+                    // 端口还是上一种的默认值时跟着切换
+                    if mail.port == mail.protocol.default_port(mail.implicit_tls) {
+                        mail.port = protocol.default_port(mail.implicit_tls);
+                    }
+                    mail.protocol = protocol;
+                }
+            }
+        });
+        ui.end_row();
+        ui.label(tr!("端口:", "Port:"));
+        ui.add(egui::DragValue::new(&mut mail.port));
+        ui.end_row();
+    });
+    let implicit = ui.checkbox(
+        &mut mail.implicit_tls,
+        tr!(
+            "直接TLS连接（如 465/993/995 端口）",
+            "Connect with TLS directly (e.g. ports 465/993/995)"
+        ),
+    );
+    if implicit.changed() && mail.port == mail.protocol.default_port(!mail.implicit_tls) {
+        mail.port = mail.protocol.default_port(mail.implicit_tls);
+    }
+    ui.checkbox(
+        &mut mail.commands,
+        match mail.protocol {
+            MailProtocol::Smtp => tr!("发送 EHLO 和 NOOP", "Send EHLO and NOOP"),
+            MailProtocol::Imap => tr!("发送 CAPABILITY 和 NOOP", "Send CAPABILITY and NOOP"),
+            MailProtocol::Pop3 => tr!("发送 CAPA", "Send CAPA"),
+        },
+    );
+    if !mail.implicit_tls {
+        ui.checkbox(
+            &mut mail.require_starttls,
+            tr!(
+                "要求支持 STARTTLS，并完成一次TLS握手",
+                "Require STARTTLS and complete a TLS handshake"
+            ),
+        );
+    }
+    if mail.implicit_tls || mail.require_starttls {
+        ui.checkbox(
+            &mut mail.accept_invalid_certs,
+            tr!("允许自签名证书", "Accept self-signed certificates"),
+        );
+        show_tls_name(ui, &mut mail.tls_name);
+    }
+    ui.small(tr!(
+        "不登录；欢迎语或命令得到否定回复为错误，要求 STARTTLS 但服务器没有提供时为降级",
+        "Does not log in. A negative greeting or reply is an error; missing STARTTLS when required is degraded"
+    ));
+}
+
+fn show_elasticsearch(ui: &mut egui::Ui, es: &mut ElasticsearchCheck, ip: &str) {
+    egui::Grid::new("elasticsearch_check")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label(tr!("地址:", "URL:"));
+            ui.add(
+                egui::TextEdit::singleline(&mut es.url)
+                    .hint_text(ElasticsearchCheck::default().endpoint(ip)),
+            );
+            ui.end_row();
+            ui.label(tr!("用户名:", "User:"));
+            ui.add(egui::TextEdit::singleline(&mut es.user).hint_text(tr!("可留空", "Optional")));
+            ui.end_row();
+            ui.label(tr!("密码:", "Password:"));
+            ui.add(egui::TextEdit::singleline(&mut es.password).password(true));
+            ui.end_row();
+        });
+    ui.checkbox(
+        &mut es.accept_invalid_certs,
+        tr!("允许自签名证书", "Accept self-signed certificates"),
+    );
+    ui.small(tr!(
+        "请求 /_cluster/health，green 为在线，yellow 为降级，red 为错误；也适用于 OpenSearch",
+        "Requests /_cluster/health: green is online, yellow degraded, red an error. Works with OpenSearch too"
+    ));
+}
+
+fn show_docker(ui: &mut egui::Ui, docker: &mut DockerCheck) {
+    egui::Grid::new("docker_check")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label(tr!("守护进程:", "Daemon:"));
+            ui.add(
+                egui::TextEdit::singleline(&mut docker.endpoint).hint_text(docker::DEFAULT_SOCKET),
+            );
+            ui.end_row();
+            ui.label(tr!("容器:", "Container:"));
+            ui.add(
+                egui::TextEdit::singleline(&mut docker.container)
+                    .hint_text(tr!("容器名或ID", "Name or ID")),
+            );
+            ui.end_row();
+        });
+    ui.small(tr!(
+        "远程守护进程填写 tcp://主机:2375；运行中且健康检查通过为在线，启动中、重启中或暂停为降级，不健康为错误，已停止为离线",
+        "Use tcp://host:2375 for a remote daemon. Running and healthy is online; starting, restarting or paused is degraded; unhealthy is an error; stopped is offline"
+    ));
+}
+
+fn show_kubernetes(ui: &mut egui::Ui, k8s: &mut KubernetesCheck) {
+    egui::Grid::new("kubernetes_check")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label(tr!("API地址:", "API server:"));
+            ui.add(
+                egui::TextEdit::singleline(&mut k8s.server)
+                    .hint_text(tr!("留空使用 kubeconfig", "Empty uses kubeconfig")),
+            );
+            ui.end_row();
+            ui.label("kubeconfig:");
+            ui.add(
+                egui::TextEdit::singleline(&mut k8s.kubeconfig)
+                    .hint_text(kubernetes::DEFAULT_KUBECONFIG),
+            );
+            ui.end_row();
+            ui.label(tr!("令牌:", "Token:"));
+            ui.add(egui::TextEdit::singleline(&mut k8s.token).password(true));
+            ui.end_row();
+            ui.label(tr!("命名空间:", "Namespace:"));
+            ui.add(
+                egui::TextEdit::singleline(&mut k8s.namespace)
+                    .hint_text(tr!("留空使用上下文的命名空间", "Empty uses the context's")),
+            );
+            ui.end_row();
+            ui.label("Deployment:");
+            ui.add(
+                egui::TextEdit::singleline(&mut k8s.deployment)
+                    .hint_text(tr!("留空只检查API", "Empty checks the API only")),
+            );
+            ui.end_row();
+        });
+    ui.checkbox(
+        &mut k8s.accept_invalid_certs,
+        tr!("允许自签名证书", "Accept self-signed certificates"),
+    );
+    ui.small(tr!(
+        "请求 /readyz（旧版本为 /healthz）；填写 Deployment 后全部副本就绪为在线，部分就绪为降级，没有就绪的为离线，副本数显示在详情中",
+        "Requests /readyz (/healthz on older versions). With a Deployment: all replicas ready is online, some ready degraded, none ready offline; replica counts are shown in the details"
+    ));
+}
+
+fn show_snmp(ui: &mut egui::Ui, snmp: &mut SnmpCheck) {
+    ui.horizontal(|ui| {
+        ui.label(tr!("版本:", "Version:"));
+        for version in SnmpVersion::ALL {
+            ui.radio_value(&mut snmp.version, version, version.label());
+        }
+        ui.label(tr!("端口:", "Port:"));
+        ui.add(egui::DragValue::new(&mut snmp.port));
+    });
+    egui::Grid::new("snmp_check").num_columns(2).show(ui, |ui| {
+        ui.label("OID:");
+        ui.add(egui::TextEdit::singleline(&mut snmp.oid).hint_text(snmp::SYS_UPTIME));
+        ui.end_row();
+        match snmp.version {
+            SnmpVersion::V2c => {
+                ui.label(tr!("团体名:", "Community:"));
+                ui.add(egui::TextEdit::singleline(&mut snmp.community).password(true));
+                ui.end_row();
+            }
+            SnmpVersion::V3 => {
+                ui.label(tr!("用户名:", "User:"));
+                ui.text_edit_singleline(&mut snmp.user);
+                ui.end_row();
+                ui.label(tr!("认证:", "Authentication:"));
+                ui.horizontal(|ui| {
+                    for auth in SnmpAuth::ALL {
+                        ui.radio_value(&mut snmp.auth, auth, auth.label());
+                    }
+                });
+                ui.end_row();
+                ui.label(tr!("认证密码:", "Auth password:"));
+                ui.add(
+                    egui::TextEdit::singleline(&mut snmp.auth_password)
+                        .password(true)
+                        .hint_text(tr!("留空不认证", "Empty for noAuth")),
+                );
+                ui.end_row();
+                ui.label(tr!("加密密码 (AES):", "Privacy password (AES):"));
+                ui.add(
+                    egui::TextEdit::singleline(&mut snmp.privacy_password)
+                        .password(true)
+                        .hint_text(tr!("留空不加密", "Empty for noPriv")),
+                );
+                ui.end_row();
+            }
+        }
+    });
+    ui.small(tr!(
+        "读取到值为在线，值显示在说明中，也可以添加以OID为标题的自定义列；没有回复为离线，OID不存在或认证失败为错误",
+        "Online when the value is read; it is shown in the detail and in a custom column titled with the OID. No reply is offline; a missing OID or failed authentication is an error"
+    ));
+}
+
+fn show_game(ui: &mut egui::Ui, game: &mut GameCheck) {
+    ui.horizontal(|ui| {
+        ui.label(tr!("协议:", "Protocol:"));
+        for protocol in GameProtocol::ALL {
+            let selected = game.protocol == protocol;
+            if ui.radio(selected, protocol.label()).clicked() && !selected {
+                // 端口还是上一种的默认值时跟着切换
+                if game.port == game.protocol.default_port() {
+                    game.port = protocol.default_port();
+                }
+                game.protocol = protocol;
+            }
+        }
+    });
+    ui.horizontal(|ui| {
+        ui.label(tr!("查询端口:", "Query port:"));
+        ui.add(egui::DragValue::new(&mut game.port));
+    });
+    ui.small(tr!(
+        "查询服务器状态，卡片中显示在线玩家数和 MOTD/服务器名；Source 协议适用于 CS2、TF2、Garry's Mod、ARK 等，查询端口可能与游戏端口不同",
+        "Queries the server status and shows the player count and MOTD/server name on the card. Source works for CS2, TF2, Garry's Mod, ARK and others; the query port may differ from the game port"
+    ));
+}
+
+fn show_udp(ui: &mut egui::Ui, udp: &mut UdpCheck) {
+    egui::Grid::new("udp_check").num_columns(2).show(ui, |ui| {
+        ui.label(tr!("端口:", "Port:"));
+        ui.add(egui::DragValue::new(&mut udp.port));
+        ui.end_row();
+        ui.label(tr!("格式:", "Format:"));
+        ui.horizontal(|ui| {
+            for format in PayloadFormat::ALL {
+                ui.radio_value(&mut udp.format, format, format.label());
+            }
+        });
+        ui.end_row();
+        ui.label(tr!("发送内容:", "Payload:"));
+        ui.add(
+            egui::TextEdit::singleline(&mut udp.payload).hint_text(match udp.format {
+                PayloadFormat::Text => "status\\n",
+                PayloadFormat::Hex => "ff ff ff ff 54",
+            }),
+        );
+        ui.end_row();
+        ui.label(tr!("期望回复开头:", "Expected prefix:"));
+        ui.add(
+            egui::TextEdit::singleline(&mut udp.expect)
+                .hint_text(tr!("留空接受任何回复", "Empty accepts any reply")),
+        );
+        ui.end_row();
+    });
+    let invalid = [&udp.payload, &udp.expect]
+        .into_iter()
+        .find_map(|text| udp.format.decode(text).err());
+    if let Some(e) = invalid {
+        ui.colored_label(ServerStatus::Offline.color(), e.to_string());
+    }
+    ui.small(tr!(
+        "发送一个数据包，在超时前收到任何回复即为在线，没有回复为离线；文本格式支持 \\r \\n \\t \\0 \\xNN 转义，期望回复使用相同格式",
+        "Sends one datagram; any reply before the timeout is online and no reply is offline. Text supports \\r \\n \\t \\0 \\xNN escapes; the expected prefix uses the same format"
+    ));
+}
+
+fn show_http_steps(ui: &mut egui::Ui, steps: &mut HttpSteps) {
+    let count = steps.steps.len();
+    let mut remove = None;
+    let mut move_up = None;
+    for (i, step) in steps.steps.iter_mut().enumerate() {
+        ui.group(|ui| {
+            ui.horizontal(|ui| {
+                ui.strong(tr!("步骤 {}", "Step {}", i + 1));
+                ui.add(
+                    egui::TextEdit::singleline(&mut step.name)
+                        .hint_text(tr!("名称", "Name"))
+                        .desired_width(120.0),
+                );
+                if ui
+                    .add_enabled(i > 0, egui::Button::new("⬆").small())
+                    .clicked()
+                {
+                    move_up = Some(i);
+                }
+                if ui
+                    .add_enabled(i + 1 < count, egui::Button::new("⬇").small())
+                    .clicked()
+                {
+                    move_up = Some(i + 1);
+                }
+                if ui.small_button("🗑").clicked() {
+                    remove = Some(i);
+                }
+            });
+            show_http_step(ui, i, step);
+        });
+    }
+    if let Some(i) = remove {
+        steps.steps.remove(i);
+    }
+    if let Some(i) = move_up {
+        steps.steps.swap(i - 1, i);
+    }
+    if ui.button(tr!("➕ 添加步骤", "➕ Add step")).clicked() {
+        steps.steps.push(HttpStep::default());
+    }
+    ui.small(tr!(
+        "按顺序请求，全部通过才为在线；地址相对于上面的URL，提取的变量在后面的步骤中用 {名称} 引用，收到的Cookie会自动带上",
+        "Requests run in order and all must pass for online. Paths are relative to the URL above; extracted variables are used as {name} in later steps, and received cookies are sent automatically"
+    ));
+}
+
+fn show_http_step(ui: &mut egui::Ui, i: usize, step: &mut HttpStep) {
+    egui::Grid::new(("http_step", i))
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label(tr!("请求:", "Request:"));
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_source(("step_method", i))
+                    .selected_text(step.method.as_str())
+                    .width(80.0)
+                    .show_ui(ui, |ui| {
+                        for method in HttpMethod::ALL {
+                            ui.selectable_value(&mut step.method, method, method.as_str());
+                        }
+                    });
+                ui.add(
+                    egui::TextEdit::singleline(&mut step.url)
+                        .hint_text("/api/login")
+                        .desired_width(220.0),
+                );
+            });
+            ui.end_row();
+            ui.label(tr!("请求头:", "Headers:"));
+            ui.add(
+                egui::TextEdit::multiline(&mut step.headers)
+                    .hint_text("Authorization: Bearer {token}")
+                    .desired_rows(1)
+                    .desired_width(300.0),
+            );
+            ui.end_row();
+            ui.label(tr!("请求体:", "Body:"));
+            ui.add(
+                egui::TextEdit::multiline(&mut step.body)
+                    .desired_rows(1)
+                    .desired_width(300.0),
+            );
+            ui.end_row();
+            ui.label(tr!("期望状态码:", "Expected status:"));
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut step.expect_status).range(0..=999));
+                if step.expect_status == 0 {
+                    ui.weak(tr!("任意2xx", "Any 2xx"));
+                }
+            });
+            ui.end_row();
+            ui.label(tr!("响应包含:", "Body contains:"));
+            ui.add(
+                egui::TextEdit::singleline(&mut step.expect_body)
+                    .hint_text(tr!("留空不检查", "Leave empty to skip")),
+            );
+            ui.end_row();
+        });
+    let mut remove = None;
+    for (j, extraction) in step.extract.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            ui.label(tr!("提取", "Extract"));
+            ui.add(
+                egui::TextEdit::singleline(&mut extraction.name)
+                    .hint_text(tr!("变量名", "Variable"))
+                    .desired_width(80.0),
+            );
+            ui.label("←");
+            egui::ComboBox::from_id_source(("step_extract", i, j))
+                .selected_text(extraction.source.label())
+                .show_ui(ui, |ui| {
+                    for source in ExtractSource::ALL {
+                        ui.selectable_value(&mut extraction.source, source, source.label());
+                    }
+                });
+            let hint = match extraction.source {
+                ExtractSource::JsonField => "data.token",
+                ExtractSource::Regex => "csrf=(\\w+)",
+                ExtractSource::Header => "X-Request-Id",
+            };
+            ui.add(
+                egui::TextEdit::singleline(&mut extraction.expr)
+                    .hint_text(hint)
+                    .desired_width(140.0),
+            );
+            if ui.small_button("🗑").clicked() {
+                remove = Some(j);
+            }
+        });
+    }
+    if let Some(j) = remove {
+        step.extract.remove(j);
+    }
+    if ui
+        .small_button(tr!("➕ 提取变量", "➕ Extract variable"))
+        .clicked()
+    {
+        step.extract.push(Extraction::default());
+    }
+}
+
+// HTTP检查的请求设置，用于需要登录或被WAF拦截的地址
+pub fn show_http(ui: &mut egui::Ui, http: &mut HttpOptions) {
+    egui::CollapsingHeader::new(tr!("请求设置", "Request options"))
+        .id_source("http_options")
+        .default_open(
+            !http.user_agent.is_empty()
+                || !http.username.is_empty()
+                || !http.assertions.is_empty()
+                || http.method != HttpMethod::Get
+                || http.redirect != RedirectPolicy::Follow
+                || http.proxy_mode != ProxyMode::Global,
+        )
+        .show(ui, |ui| {
+            egui::Grid::new("http_options_grid")
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label(tr!("请求方法:", "Method:"));
+                    egui::ComboBox::from_id_source("http_method")
+                        .selected_text(http.method.as_str())
+                        .show_ui(ui, |ui| {
+                            for method in [
+                                HttpMethod::Get,
+                                HttpMethod::Head,
+                                HttpMethod::Post,
+                                HttpMethod::Put,
+                            ] {
+                                ui.selectable_value(&mut http.method, method, method.as_str());
+                            }
+                        });
+                    ui.end_row();
+                    if http.method.has_body() {
+                        ui.label("Content-Type:");
+                        ui.add(
+                            egui::TextEdit::singleline(&mut http.content_type)
+                                .hint_text("application/json"),
+                        );
+                        ui.end_row();
+                        ui.label(tr!("请求体:", "Body:"));
+                        ui.add(
+                            egui::TextEdit::multiline(&mut http.body)
+                                .hint_text("{\"probe\": true}")
+                                .desired_rows(2),
+                        );
+                        ui.end_row();
+                    }
+                    ui.label(tr!("跳转:", "Redirects:"));
+                    ui.horizontal(|ui| {
+                        egui::ComboBox::from_id_source("http_redirect")
+                            .selected_text(http.redirect.label())
+                            .show_ui(ui, |ui| {
+                                for policy in RedirectPolicy::ALL {
+                                    ui.selectable_value(&mut http.redirect, policy, policy.label());
+                                }
+                            });
+                        if http.redirect == RedirectPolicy::Follow {
+                            ui.label(tr!("最多", "Max"));
+                            ui.add(
+                                egui::DragValue::new(&mut http.max_redirects)
+                                    .range(0..=30)
+                                    .custom_formatter(|n, _| match n as u8 {
+                                        0 => tr!("默认(10)", "default (10)").to_string(),
+                                        n => n.to_string(),
+                                    }),
+                            );
+                        }
+                    });
+                    ui.end_row();
+                    ui.label(tr!("HTTP版本:", "HTTP version:"));
+                    egui::ComboBox::from_id_source("http_protocol")
+                        .selected_text(http.protocol.label())
+                        .show_ui(ui, |ui| {
+                            for version in HttpVersion::ALL {
+                                ui.selectable_value(&mut http.protocol, version, version.label());
+                            }
+                        });
+                    ui.end_row();
+                    ui.label(tr!("代理:", "Proxy:"));
+                    egui::ComboBox::from_id_source("http_proxy_mode")
+                        .selected_text(http.proxy_mode.label())
+                        .show_ui(ui, |ui| {
+                            for mode in ProxyMode::ALL {
+                                ui.selectable_value(&mut http.proxy_mode, mode, mode.label());
+                            }
+                        });
+                    ui.end_row();
+                    ui.label("Host:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut http.host_header).hint_text(tr!(
+                            "留空使用URL中的主机名",
+                            "Leave empty to use the URL host"
+                        )),
+                    );
+                    ui.end_row();
+                    ui.label(tr!("DNS服务器:", "DNS server:"));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut http.dns_server)
+                            .hint_text(tr!("留空使用系统设置", "Leave empty for the system resolver")),
+                    );
+                    ui.end_row();
+                    ui.label("User-Agent:");
+                    ui.add(
+                        egui::TextEdit::singleline(&mut http.user_agent)
+                            .hint_text(tr!("留空使用默认值", "Leave empty for the default")),
+                    );
+                    ui.end_row();
+                    ui.label(tr!("用户名:", "User:"));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut http.username).hint_text(tr!(
+                            "Basic认证，留空不发送",
+                            "Basic auth, leave empty to skip"
+                        )),
+                    );
+                    ui.end_row();
+                    ui.label(tr!("密码:", "Password:"));
+                    ui.horizontal(|ui| {
+                        let key = format!("http-{}", http.username.trim());
+                        secret_input(ui, &mut http.password, &key);
+                    });
+                    ui.end_row();
+                    ui.label(tr!("JSON断言:", "JSON assertions:"));
+                    ui.add(
+                        egui::TextEdit::multiline(&mut http.assertions)
+                            .hint_text("$.status == \"ok\"\n$.db == \"up\"")
+                            .desired_rows(2),
+                    );
+                    ui.end_row();
+                });
+            if http.proxy_mode == ProxyMode::Custom {
+                show_proxy(ui, "http_proxy", &mut http.proxy);
+            }
+            if !http.host_header.trim().is_empty() {
+                ui.small(tr!(
+                    "连接URL中的地址，Host 和 TLS SNI 使用上面的主机名，证书也按它校验",
+                    "Connects to the URL address but sends this name as Host and TLS SNI, and verifies the certificate against it"
+                ));
+            }
+            if !http.dns_server.trim().is_empty() {
+                if let Err(e) = resolver::parse_server(&http.dns_server) {
+                    ui.colored_label(ServerStatus::Offline.color(), e.to_string());
+                }
+            }
+            if let Err(e) = assertion::parse_lines(&http.assertions) {
+                ui.colored_label(ServerStatus::Offline.color(), e);
+            }
+            ui.small(tr!(
+                "每行一条，支持 == != > >= < <= contains matches，只写路径表示字段存在且不为 false；状态码正常但断言不成立时显示为内容异常",
+                "One per line, supporting == != > >= < <= contains matches; a bare path requires the field to exist and not be false. A failed assertion with a normal status shows as unexpected content"
+            ));
+            if !http.username.is_empty()
+                && !http.password.is_empty()
+                && !secret::is_reference(&http.password)
+            {
+                ui.small(tr!(
+                    "密码以明文保存在配置文件中，可以点 🔐 存入系统钥匙串",
+                    "The password is stored in plain text in the config file; click 🔐 to move it into the system keychain"
+                ));
+            }
+        });
+}
+
+// 代理地址、认证和不经过代理的地址，返回是否有输入框失去焦点（修改完成）
+pub fn show_proxy(ui: &mut egui::Ui, id: &str, proxy: &mut ProxySettings) -> bool {
+    let mut changed = false;
+    egui::Grid::new(id).num_columns(2).show(ui, |ui| {
+        ui.label(tr!("代理地址:", "Proxy URL:"));
+        changed |= ui
+            .add(
+                egui::TextEdit::singleline(&mut proxy.url)
+                    .hint_text("http://proxy.corp:8080 / socks5h://127.0.0.1:1080")
+                    .desired_width(260.0),
+            )
+            .lost_focus();
+        ui.end_row();
+        ui.label(tr!("用户名:", "User:"));
+        changed |= ui.text_edit_singleline(&mut proxy.username).lost_focus();
+        ui.end_row();
+        ui.label(tr!("密码:", "Password:"));
+        changed |= ui
+            .add(egui::TextEdit::singleline(&mut proxy.password).password(true))
+            .lost_focus();
+        ui.end_row();
+        ui.label(tr!("不使用代理:", "Bypass:"));
+        changed |= ui
+            .add(
+                egui::TextEdit::singleline(&mut proxy.bypass)
+                    .hint_text("localhost, .corp.example, 10.0.0.0/8"),
+            )
+            .lost_focus();
+        ui.end_row();
+    });
+    if proxy.is_set() {
+        if let Err(e) = proxy.proxy_url() {
+            ui.colored_label(ServerStatus::Offline.color(), e);
+        }
+    }
+    changed
+}
+
+// 主机名同时有IPv4和IPv6地址时检查哪一个
+pub fn show_ip_family(ui: &mut egui::Ui, family: &mut IpFamily) {
+    ui.horizontal(|ui| {
+        ui.label(tr!("IP协议:", "IP version:"));
+        egui::ComboBox::from_id_source("ip_family")
+            .selected_text(family.label())
+            .show_ui(ui, |ui| {
+                for option in IpFamily::ALL {
+                    ui.selectable_value(family, option, option.label());
+                }
+            });
+    });
+    if *family == IpFamily::Both {
+        ui.small(tr!(
+            "两个地址都在线才算在线，详情中分别显示 IPv4 和 IPv6 的结果",
+            "Online only when both addresses are; the details show IPv4 and IPv6 results separately"
+        ));
+    }
+}
+
+// 经过SSH跳板机检查内网中的服务器
+pub fn show_tunnel(ui: &mut egui::Ui, tunnel: &mut SshTunnel) {
+    ui.checkbox(
+        &mut tunnel.enabled,
+        tr!("通过SSH跳板机检查", "Check through an SSH jump host"),
+    );
+    if !tunnel.enabled {
+        return;
+    }
+    egui::Grid::new("ssh_tunnel").num_columns(2).show(ui, |ui| {
+        ui.label(tr!("跳板机:", "Jump host:"));
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut tunnel.host)
+                    .hint_text("bastion.example.com")
+                    .desired_width(180.0),
+            );
+            ui.label(tr!("端口:", "Port:"));
+            ui.add(egui::DragValue::new(&mut tunnel.port));
+        });
+        ui.end_row();
+        ui.label(tr!("用户名:", "User:"));
+        ui.text_edit_singleline(&mut tunnel.user);
+        ui.end_row();
+        ui.label(tr!("私钥路径:", "Private key:"));
+        ui.text_edit_singleline(&mut tunnel.key_path);
+        ui.end_row();
+        ui.label(tr!("私钥密码:", "Key passphrase:"));
+        ui.horizontal(|ui| {
+            let key = format!("ssh-key-{}", tunnel.key_path.trim());
+            secret_input(ui, &mut tunnel.key_passphrase, &key);
+        });
+        ui.end_row();
+        ui.label("");
+        trust_unknown_host_checkbox(ui, &mut tunnel.trust_unknown_host);
+        ui.end_row();
+    });
+    ui.small(tr!(
+        "每次检查时连接跳板机，由跳板机连接目标地址，检查结束后断开；适用于HTTP和使用TCP端口的检查，不支持UDP和本机执行的检查",
+        "Each check connects to the jump host, which connects to the target, and disconnects afterwards. Works for HTTP and TCP port checks, not UDP or local checks"
+    ));
+}
+
+// 单独的请求超时，适合响应较慢的健康检查接口
+pub fn show_timeout(ui: &mut egui::Ui, timeout_secs: &mut Option<u64>) {
+    ui.horizontal(|ui| {
+        let mut custom = timeout_secs.is_some();
+        if ui
+            .checkbox(&mut custom, tr!("单独设置超时:", "Custom timeout:"))
+            .changed()
+        {
+            *timeout_secs = custom.then_some(15);
+        }
+        match timeout_secs {
+            Some(secs) => {
+                ui.add(
+                    egui::DragValue::new(secs)
+                        .range(1..=MAX_TIMEOUT_SECS)
+                        .suffix(tr!(" 秒", " s")),
+                );
+            }
+            None => {
+                ui.weak(tr!("默认5秒", "Default 5 s"));
+            }
+        }
+    });
+}
+
+// 状态映射规则：按顺序匹配，第一条满足条件的把检查结果改写为指定状态
+pub fn show_status_rules(ui: &mut egui::Ui, rules: &mut Vec<StatusRule>) {
+    ui.strong(tr!("状态映射", "Status mapping"));
+    let mut remove = None;
+    for (i, rule) in rules.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_id_source(("rule_condition", i))
+                .selected_text(rule.condition.label())
+                .show_ui(ui, |ui| {
+                    for condition in RuleCondition::ALL {
+                        let selected = std::mem::discriminant(&rule.condition)
+                            == std::mem::discriminant(&condition);
+                        if ui.selectable_label(selected, condition.label()).clicked() && !selected {
+                            rule.condition = condition;
+                        }
+                    }
+                });
+            match &mut rule.condition {
+                RuleCondition::StatusCode(code) => {
+                    ui.add(egui::DragValue::new(code).range(100..=999));
+                }
+                RuleCondition::DetailContains(text) => {
+                    ui.add(egui::TextEdit::singleline(text).desired_width(120.0));
+                }
+                RuleCondition::Offline | RuleCondition::Timeout => {}
+            }
+            ui.label("→");
+            egui::ComboBox::from_id_source(("rule_result", i))
+                .selected_text(rule.result.label())
+                .show_ui(ui, |ui| {
+                    for result in RuleResult::ALL {
+                        ui.selectable_value(&mut rule.result, result, result.label());
+                    }
+                });
+            if ui.small_button("🗑").clicked() {
+                remove = Some(i);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr!("生效时段:", "Active:"));
+            show_rule_window(ui, i, &mut rule.during);
+        });
+    }
+    if let Some(i) = remove {
+        rules.remove(i);
+    }
+    if ui.button(tr!("➕ 添加规则", "➕ Add rule")).clicked() {
+        rules.push(StatusRule::default());
+    }
+    ui.small(tr!(
+        "例如排空期间把503视为在线，或把特定错误视为维护中；第一条匹配的规则生效",
+        "e.g. treat 503 as online while draining, or a specific error as maintenance; the first matching rule wins"
+    ));
+}
+
+fn show_rule_window(ui: &mut egui::Ui, i: usize, during: &mut Option<Exclusion>) {
+    let text = during
+        .as_ref()
+        .map(Exclusion::label)
+        .unwrap_or_else(|| tr!("始终", "Always").to_string());
+    let daily = Exclusion::Daily {
+        start: NaiveTime::from_hms_opt(0, 0, 0).unwrap_or_default(),
+        end: NaiveTime::from_hms_opt(6, 0, 0).unwrap_or_default(),
+    };
+    egui::ComboBox::from_id_source(("rule_window", i))
+        .selected_text(text)
+        .show_ui(ui, |ui| {
+            ui.selectable_value(during, None, tr!("始终", "Always"));
+            ui.selectable_value(during, Some(Exclusion::Weekends), tr!("周末", "Weekends"));
+            let is_daily = matches!(during, Some(Exclusion::Daily { .. }));
+            if ui
+                .selectable_label(is_daily, tr!("每天", "Daily"))
+                .clicked()
+                && !is_daily
+            {
+                *during = Some(daily);
+            }
+        });
+    if let Some(Exclusion::Daily { start, end }) = during {
+        time_edit(ui, start);
+        ui.label("-");
+        time_edit(ui, end);
+    }
+}
+
+fn time_edit(ui: &mut egui::Ui, time: &mut NaiveTime) {
+    let mut hour = time.hour();
+    let mut minute = time.minute();
+    let hour_changed = ui
+        .add(egui::DragValue::new(&mut hour).range(0..=23))
+        .changed();
+    ui.label(":");
+    let minute_changed = ui
+        .add(egui::DragValue::new(&mut minute).range(0..=59))
+        .changed();
+    if hour_changed || minute_changed {
+        if let Some(t) = NaiveTime::from_hms_opt(hour, minute, 0) {
+            *time = t;
+        }
+    }
+}
+
+// 命令可用的环境变量
+pub fn show_env_docs(ui: &mut egui::Ui, id: &str, docs: Vec<(&'static str, &'static str)>) {
+    egui::CollapsingHeader::new(tr!("可用的环境变量", "Environment variables"))
+        .id_source(id)
+        .show(ui, |ui| {
+            egui::Grid::new((id, "grid"))
+                .num_columns(2)
+                .striped(true)
+                .show(ui, |ui| {
+                    for (name, description) in docs {
+                        ui.monospace(name);
+                        ui.small(description);
+                        ui.end_row();
+                    }
+                });
+        });
+}
+
+// 命令运行时传入的钥匙串密钥，以及把新的密钥存入钥匙串的输入框
+pub fn show_hook_secrets(ui: &mut egui::Ui, id: &str, secrets: &mut String) -> bool {
+    let changed = ui
+        .horizontal(|ui| {
+            ui.label(tr!("钥匙串密钥:", "Keychain secrets:"));
+            ui.add(
+                egui::TextEdit::singleline(secrets)
+                    .hint_text("db-password, api-token")
+                    .desired_width(200.0),
+            )
+            .on_hover_text(tr!(
+                "逗号分隔的钥匙串条目名称，运行时以 SERVERCHECK_SECRET_<NAME> 传入，配置中不保存密钥",
+                "Comma-separated keychain entry names, passed as SERVERCHECK_SECRET_<NAME>; the secrets are not saved in the config"
+            ))
+            .changed()
+        })
+        .inner;
+    egui::CollapsingHeader::new(tr!("🔐 存入钥匙串", "🔐 Store in keychain"))
+        .id_source((id, "keyring"))
+        .show(ui, |ui| {
+            let state = ui.id().with("keyring_input");
+            let (mut name, mut value, mut message) = ui.data_mut(|d| {
+                d.get_temp::<(String, String, String)>(state)
+                    .unwrap_or_default()
+            });
+            ui.horizontal(|ui| {
+                ui.add(
+                    egui::TextEdit::singleline(&mut name)
+                        .hint_text(tr!("名称", "Name"))
+                        .desired_width(100.0),
+                );
+                ui.add(
+                    egui::TextEdit::singleline(&mut value)
+                        .hint_text(tr!("密钥", "Secret"))
+                        .password(true)
+                        .desired_width(140.0),
+                );
+                let ready = !name.trim().is_empty() && !value.is_empty();
+                if ui
+                    .add_enabled(ready, egui::Button::new(tr!("保存", "Save")))
+                    .clicked()
+                {
+                    message = match secret::store(&name, &value) {
+                        Ok(_) => {
+                            value.clear();
+                            tr!("已保存 {}", "Saved {}", name.trim())
+                        }
+                        Err(e) => e.to_string(),
+                    };
+                }
+            });
+            if !message.is_empty() {
+                ui.small(&message);
+            }
+            ui.data_mut(|d| d.insert_temp(state, (name, value, message)));
+        });
+    changed
+}
+
+// 密码输入框；直接填写的密码可以一键存入钥匙串，配置中只保留 keyring:名称
+pub fn secret_input(ui: &mut egui::Ui, value: &mut String, key: &str) -> bool {
+    let masked = !secret::is_reference(value);
+    let mut changed = ui
+        .add(
+            egui::TextEdit::singleline(value)
+                .password(masked)
+                .desired_width(160.0),
+        )
+        .on_hover_text(tr!(
+            "也可以填 keyring:名称 从系统钥匙串读取，或 env:变量名 从环境变量读取",
+            "You can also enter keyring:NAME to read from the system keychain, or env:VAR to read an environment variable"
+        ))
+        .changed();
+    if !value.is_empty() && !secret::is_reference(value) {
+        let error = ui.id().with(("keyring_error", key));
+        if ui
+            .small_button("🔐")
+            .on_hover_text(tr!(
+                "存入系统钥匙串，配置中只保存引用",
+                "Move into the system keychain and keep only a reference in the config"
+            ))
+            .clicked()
+        {
+            match secret::store(key, value) {
+                Ok(reference) => {
+                    *value = reference;
+                    changed = true;
+                    ui.data_mut(|d| d.remove::<String>(error));
+                }
+                Err(e) => ui.data_mut(|d| d.insert_temp(error, e.to_string())),
+            }
+        }
+        if let Some(message) = ui.data(|d| d.get_temp::<String>(error)) {
+            ui.colored_label(ServerStatus::Offline.color(), message);
+        }
+    }
+    changed
+}
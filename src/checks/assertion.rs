@@ -0,0 +1,193 @@
+use serde_json::Value;
+
+use crate::columns::json_value;
+use crate::i18n::tr;
+
+const OPERATORS: [&str; 8] = ["==", "!=", ">=", "<=", ">", "<", " contains ", " matches "];
+
+// 对JSON响应的一条断言，例如 $.status == "ok"、$.queue.size < 100、$.ready
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assertion {
+    text: String,
+    path: String,
+    // 只写路径时为None，要求字段存在且不是 false/null
+    op: Option<(&'static str, Value)>,
+}
+
+// $.a.b[0].c 转为 a.b.0.c
+fn normalize_path(path: &str) -> String {
+    let path = path.trim();
+    let path = path.strip_prefix('$').unwrap_or(path);
+    path.replace('[', ".")
+        .replace(']', "")
+        .trim_start_matches('.')
+        .to_string()
+}
+
+// 期望值按JSON解析，不是合法JSON时当作字符串，例如 == ok 与 == "ok" 相同
+fn literal(text: &str) -> Value {
+    let text = text.trim();
+    serde_json::from_str(text).unwrap_or_else(|_| Value::String(text.to_string()))
+}
+
+fn number(value: &Value) -> Option<f64> {
+    match value {
+        Value::Number(n) => n.as_f64(),
+        Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn equals(actual: &Value, expected: &Value) -> bool {
+    match (actual, expected) {
+        (Value::Number(_), Value::Number(_)) => number(actual) == number(expected),
+        _ => actual == expected,
+    }
+}
+
+impl Assertion {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let text = text.trim();
+        let found = OPERATORS
+            .iter()
+            .filter_map(|op| text.find(op).map(|pos| (pos, *op)))
+            .min_by_key(|(pos, op)| (*pos, std::cmp::Reverse(op.len())));
+        let (path, op) = match found {
+            Some((pos, op)) => {
+                let expected = literal(&text[pos + op.len()..]);
+                if op == " matches " {
+                    let pattern = expected.as_str().unwrap_or_default();
+                    regex::Regex::new(pattern)
+                        .map_err(|e| tr!("无效的正则表达式: {}", "Invalid regex: {}", e))?;
+                }
+                (&text[..pos], Some((op.trim(), expected)))
+            }
+            None => (text, None),
+        };
+        let path = normalize_path(path);
+        if path.is_empty() && op.is_none() {
+            return Err(tr!("缺少JSON路径", "Missing JSON path").to_string());
+        }
+        Ok(Self {
+            text: text.to_string(),
+            path,
+            op,
+        })
+    }
+
+    // 不满足时返回实际的值，用于显示在说明中
+    pub fn evaluate(&self, json: &Value) -> Result<(), String> {
+        let actual = json_value(json, &self.path);
+        let passed = match (&self.op, actual) {
+            (None, Some(value)) => !matches!(value, Value::Null | Value::Bool(false)),
+            (Some(("!=", expected)), actual) => !actual.is_some_and(|a| equals(a, expected)),
+            (Some(_), None) | (None, None) => false,
+            (Some((op, expected)), Some(actual)) => match *op {
+                "==" => equals(actual, expected),
+                "contains" => match (actual, expected) {
+                    (Value::String(s), Value::String(e)) => s.contains(e.as_str()),
+                    (Value::Array(items), e) => items.iter().any(|item| equals(item, e)),
+                    _ => false,
+                },
+                "matches" => {
+                    let text = match actual {
+                        Value::String(s) => s.clone(),
+                        other => other.to_string(),
+                    };
+                    regex::Regex::new(expected.as_str().unwrap_or_default())
+                        .is_ok_and(|re| re.is_match(&text))
+                }
+                op => match (number(actual), number(expected)) {
+                    (Some(a), Some(e)) => match op {
+                        ">" => a > e,
+                        ">=" => a >= e,
+                        "<" => a < e,
+                        _ => a <= e,
+                    },
+                    _ => false,
+                },
+            },
+        };
+        if passed {
+            return Ok(());
+        }
+        let actual = actual
+            .map(Value::to_string)
+            .unwrap_or_else(|| tr!("不存在", "missing").to_string());
+        Err(tr!(
+            "{} 不成立，实际为 {}",
+            "{} failed, actual {}",
+            self.text,
+            actual
+        ))
+    }
+}
+
+// 每行一条，空行和 # 开头的行忽略
+pub fn parse_lines(text: &str) -> Result<Vec<Assertion>, String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| Assertion::parse(line).map_err(|e| format!("{}: {}", line, e)))
+        .collect()
+}
+
+// 检查所有断言，返回不成立的说明；响应不是JSON时也算不成立
+pub fn failures(text: &str, body: Option<&str>) -> Vec<String> {
+    let assertions = match parse_lines(text) {
+        Ok(assertions) if assertions.is_empty() => return Vec::new(),
+        Ok(assertions) => assertions,
+        Err(e) => return vec![e],
+    };
+    let Some(json) = body.and_then(|b| serde_json::from_str::<Value>(b).ok()) else {
+        return vec![tr!("响应不是JSON", "Response is not JSON").to_string()];
+    };
+    assertions
+        .iter()
+        .filter_map(|a| a.evaluate(&json).err())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_assertions() {
+        let json = serde_json::json!({
+            "status": "ok",
+            "db": "down",
+            "queue": {"size": 120},
+            "ready": true,
+            "nodes": [{"name": "a"}],
+            "version": "2.4.1",
+        });
+        let check = |text: &str| Assertion::parse(text).unwrap().evaluate(&json);
+        assert!(check("$.status == \"ok\"").is_ok());
+        assert!(check("status == ok").is_ok());
+        assert!(check("$.ready").is_ok());
+        assert!(check("$.nodes[0].name == \"a\"").is_ok());
+        assert!(check("$.queue.size >= 100").is_ok());
+        assert!(check("$.version matches ^2\\.").is_ok());
+        assert!(check("$.missing != 1").is_ok());
+        assert_eq!(
+            check("$.db == \"up\"").unwrap_err(),
+            tr!(
+                "$.db == \"up\" 不成立，实际为 \"down\"",
+                "$.db == \"up\" failed, actual \"down\""
+            )
+        );
+        assert!(check("$.queue.size < 100").unwrap_err().contains("120"));
+        assert!(check("$.missing").is_err());
+        assert!(Assertion::parse("$.a matches (").is_err());
+    }
+
+    #[test]
+    fn reports_all_failures() {
+        let rules = "# 健康检查\n$.status == ok\n\n$.db == up\n";
+        let body = r#"{"status":"ok","db":"down"}"#;
+        assert_eq!(failures(rules, Some(body)).len(), 1);
+        assert!(failures("", Some("not json")).is_empty());
+        assert_eq!(failures(rules, Some("<html>")).len(), 1);
+    }
+}
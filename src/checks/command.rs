@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::Duration;
+
+use super::CheckOutcome;
+use crate::i18n::tr;
+use crate::notify::{kill_tree, shell};
+use crate::{address, secret, template, ServerStatus};
+
+// 在本机运行命令或脚本，按 Nagios 插件的约定判断退出码：
+// 0 为正常，1 为警告（降级），2 为严重（离线），其他（3 为未知）显示为错误(退出码)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CommandCheck {
+    // {host} 替换为加了引号的服务器地址，同时以环境变量 SERVERCHECK_HOST 传入
+    pub command: String,
+    // 运行时传入的钥匙串条目名称，逗号分隔
+    pub secrets: String,
+}
+
+impl CommandCheck {
+    pub fn command_line(&self, host: &str) -> String {
+        let command = template::expand(self.command.trim());
+        command.replace("{host}", &quote_host(host))
+    }
+}
+
+// 地址中的 ; & 等字符不会被shell解释；cmd 只认双引号，去掉地址中的双引号
+#[cfg(windows)]
+fn quote_host(host: &str) -> String {
+    format!("\"{}\"", host.replace('"', ""))
+}
+
+#[cfg(not(windows))]
+fn quote_host(host: &str) -> String {
+    address::shell_quote(host)
+}
+
+// 运行命令时传入的环境变量及说明，编辑对话框中显示这份列表
+pub fn env_docs() -> Vec<(&'static str, &'static str)> {
+    vec![
+        ("SERVERCHECK_HOST", tr!("服务器地址", "Server address")),
+        ("SERVERCHECK_SERVER", tr!("服务器名称", "Server name")),
+        (
+            "SERVERCHECK_SECRET_<NAME>",
+            tr!(
+                "标记的钥匙串密钥，名称转为大写，字母和数字以外的字符换成 _",
+                "Marked keychain secrets; the name is upper-cased with other characters replaced by _"
+            ),
+        ),
+    ]
+}
+
+// 输出第一行中 | 之后的性能数据，例如 time=0.12s;1;2;0 load1=0.5
+fn perfdata(text: &str) -> HashMap<String, String> {
+    let mut fields = HashMap::new();
+    let mut rest = text.trim();
+    while let Some((label, tail)) = rest.split_once('=') {
+        let label = label.trim().trim_matches('\'');
+        let (value, tail) = tail.split_once(' ').unwrap_or((tail, ""));
+        let value = value.split(';').next().unwrap_or_default();
+        if !label.is_empty() && !value.is_empty() {
+            fields.insert(label.to_string(), value.to_string());
+        }
+        rest = tail.trim_start();
+    }
+    fields
+}
+
+fn status(code: Option<i32>) -> ServerStatus {
+    match code {
+        Some(0) => ServerStatus::Online,
+        Some(1) => ServerStatus::Degraded,
+        Some(2) => ServerStatus::Offline,
+        Some(code) => ServerStatus::Error(code.clamp(1, u16::MAX as i32) as u16),
+        // 被信号终止
+        None => ServerStatus::Error(1),
+    }
+}
+
+// 超时后结束命令及其启动的子进程，显示为离线（超时）
+pub async fn check(
+    name: &str,
+    host: &str,
+    check: &CommandCheck,
+    timeout: Duration,
+) -> CheckOutcome {
+    let command = check.command_line(host);
+    if command.is_empty() {
+        return CheckOutcome::new(
+            ServerStatus::Error(1),
+            tr!("未填写命令", "Command is not set"),
+        );
+    }
+    let secrets = match secret::hook_env(&check.secrets).await {
+        Ok(secrets) => secrets,
+        Err(e) => return CheckOutcome::new(ServerStatus::Error(1), e.to_string()),
+    };
+    let mut cmd = shell(&command);
+    cmd.env("SERVERCHECK_HOST", host)
+        .env("SERVERCHECK_SERVER", name)
+        .envs(secrets)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let run_error = |e: std::io::Error| {
+        CheckOutcome::new(
+            ServerStatus::Error(1),
+            tr!("无法运行命令: {}", "Cannot run command: {}", e),
+        )
+    };
+    let child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => return run_error(e),
+    };
+    let pid = child.id();
+    let output = match tokio::time::timeout(timeout, child.wait_with_output()).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => return run_error(e),
+        Err(_) => {
+            if let Some(pid) = pid {
+                kill_tree(pid);
+            }
+            return CheckOutcome::timeout(tr!("命令执行超时", "Command timed out"));
+        }
+    };
+    // 没有标准输出时用标准错误
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let first = stdout
+        .lines()
+        .chain(stderr.lines())
+        .find(|l| !l.trim().is_empty())
+        .unwrap_or_default();
+    let (text, perf) = first.split_once('|').unwrap_or((first, ""));
+    let mut outcome = CheckOutcome::new(status(output.status.code()), text.trim());
+    outcome.fields = perfdata(perf);
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn parses_nagios_perfdata() {
+        let fields = perfdata(" time=0.120s;1.0;2.0;0 'free space'=42%;20;10 size=10GB");
+        assert_eq!(fields["time"], "0.120s");
+        assert_eq!(fields["free space"], "42%");
+        assert_eq!(fields["size"], "10GB");
+        assert_eq!(status(Some(1)), ServerStatus::Degraded);
+        assert_eq!(status(Some(3)), ServerStatus::Error(3));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn maps_plugin_exit_codes() {
+        let timeout = Duration::from_secs(5);
+        let run = |command: &str| CommandCheck {
+            command: command.to_string(),
+            ..Default::default()
+        };
+
+        let outcome = check(
+            "nas",
+            "10.0.0.5",
+            &run("echo \"PING OK - $SERVERCHECK_HOST\" {host} \"| rta=0.5ms;100;500\"; echo more"),
+            timeout,
+        )
+        .await;
+        assert_eq!(outcome.status, ServerStatus::Online);
+        assert_eq!(outcome.detail, "PING OK - 10.0.0.5 10.0.0.5");
+        assert_eq!(outcome.fields["rta"], "0.5ms");
+
+        let outcome = check(
+            "nas",
+            "10.0.0.5",
+            &run("echo DISK CRITICAL >&2; exit 2"),
+            timeout,
+        )
+        .await;
+        assert_eq!(outcome.status, ServerStatus::Offline);
+        assert_eq!(outcome.detail, "DISK CRITICAL");
+
+        // 地址作为一个参数传入，不会被当作命令执行
+        let outcome = check("nas", "x; echo pwned", &run("echo {host}"), timeout).await;
+        assert_eq!(outcome.detail, "x; echo pwned");
+
+        // 超时后后台的子进程也被结束
+        let dir = TempDir::new("command");
+        let marker = dir.join("marker");
+        let command = format!("(sleep 1; touch '{}') & sleep 5", marker.display());
+        let outcome = check(
+            "nas",
+            "10.0.0.5",
+            &run(&command),
+            Duration::from_millis(200),
+        )
+        .await;
+        assert!(outcome.timed_out);
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        assert!(!marker.exists());
+    }
+}
@@ -0,0 +1,122 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::net::TcpStream;
+
+use super::target::Target;
+use super::CheckOutcome;
+use crate::address;
+use crate::i18n::tr;
+use crate::ServerStatus;
+
+// 数据库协议层面的检查：Redis 发送 PING，MySQL/PostgreSQL 登录后执行 SELECT 1。
+// 各协议分别由 redis、mysql、postgres 特性编译，未启用的在检查时报错，配置仍可正常读取
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum DbEngine {
+    #[default]
+    Redis,
+    MySql,
+    Postgres,
+}
+
+impl DbEngine {
+    pub const ALL: [DbEngine; 3] = [DbEngine::Redis, DbEngine::MySql, DbEngine::Postgres];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            DbEngine::Redis => "Redis",
+            DbEngine::MySql => "MySQL",
+            DbEngine::Postgres => "PostgreSQL",
+        }
+    }
+
+    pub fn default_port(&self) -> u16 {
+        match self {
+            DbEngine::Redis => 6379,
+            DbEngine::MySql => 3306,
+            DbEngine::Postgres => 5432,
+        }
+    }
+
+    // 编译时是否包含这种协议
+    pub fn available(&self) -> bool {
+        match self {
+            DbEngine::Redis => cfg!(feature = "redis"),
+            DbEngine::MySql => cfg!(feature = "mysql"),
+            DbEngine::Postgres => cfg!(feature = "postgres"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DatabaseCheck {
+    pub engine: DbEngine,
+    pub port: u16,
+    // 留空时 Redis 不认证；PostgreSQL 默认用户为 postgres
+    pub user: String,
+    pub password: String,
+    // MySQL/PostgreSQL 连接的数据库，留空时使用默认值
+    pub database: String,
+}
+
+impl Default for DatabaseCheck {
+    fn default() -> Self {
+        Self {
+            engine: DbEngine::Redis,
+            port: DbEngine::Redis.default_port(),
+            user: String::new(),
+            password: String::new(),
+            database: String::new(),
+        }
+    }
+}
+
+impl DatabaseCheck {
+    pub fn endpoint(&self, host: &str) -> String {
+        address::build_url(&self.engine.label().to_lowercase(), host, self.port)
+    }
+}
+
+// 成功时返回显示在说明中的文字，例如服务器版本
+async fn handshake(stream: TcpStream, check: &DatabaseCheck) -> Result<String> {
+    match check.engine {
+        #[cfg(feature = "redis")]
+        DbEngine::Redis => super::redis::ping(stream, check).await,
+        #[cfg(feature = "mysql")]
+        DbEngine::MySql => super::mysql::select_one(stream, check).await,
+        #[cfg(feature = "postgres")]
+        DbEngine::Postgres => super::postgres::select_one(stream, check).await,
+        #[allow(unreachable_patterns)]
+        engine => {
+            drop(stream);
+            anyhow::bail!(tr!(
+                "此版本编译时未包含 {} 检查",
+                "This build does not include {} checks",
+                engine.label()
+            ))
+        }
+    }
+}
+
+// 连不上端口为离线，连上之后握手、认证或查询失败为错误
+pub async fn check(target: Target<'_>, check: &DatabaseCheck, timeout: Duration) -> CheckOutcome {
+    let probe = async {
+        let stream = target
+            .connect(check.port)
+            .await
+            .map_err(|e| CheckOutcome::new(ServerStatus::Offline, e.to_string()))?;
+        handshake(stream, check)
+            .await
+            .map_err(|e| CheckOutcome::new(ServerStatus::Error(1), e.to_string()))
+    };
+    match tokio::time::timeout(timeout, probe).await {
+        Ok(Ok(detail)) => CheckOutcome::new(ServerStatus::Online, detail),
+        Ok(Err(outcome)) => outcome,
+        Err(_) => CheckOutcome::timeout(tr!(
+            "{} 检查超时",
+            "{} check timed out",
+            check.engine.label()
+        )),
+    }
+}
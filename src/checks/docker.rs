@@ -0,0 +1,483 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use super::CheckOutcome;
+use crate::discovery::uri_encode;
+use crate::i18n::tr;
+use crate::{Server, ServerStatus};
+
+// 通过 Docker Engine API 查询容器状态：本机走 unix socket，远程走 tcp://host:2375。
+// 不支持需要客户端证书的 2376 端口，远程可以用 SSH 隧道或 socket 代理
+
+pub const DEFAULT_SOCKET: &str = "unix:///var/run/docker.sock";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DockerCheck {
+    // 留空时使用本机的 /var/run/docker.sock
+    pub endpoint: String,
+    // 容器名或ID
+    pub container: String,
+}
+
+impl DockerCheck {
+    pub fn endpoint(&self) -> &str {
+        match self.endpoint.trim() {
+            "" => DEFAULT_SOCKET,
+            endpoint => endpoint,
+        }
+    }
+}
+
+// 从Docker导入服务器的设置
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DockerImportSettings {
+    pub endpoint: String,
+    // 导入的服务器的分组
+    pub group: String,
+}
+
+impl Default for DockerImportSettings {
+    fn default() -> Self {
+        Self {
+            endpoint: String::new(),
+            group: "Docker".to_string(),
+        }
+    }
+}
+
+enum Endpoint<'a> {
+    Unix(&'a str),
+    Tcp(&'a str),
+}
+
+fn parse_endpoint(endpoint: &str) -> Endpoint<'_> {
+    if let Some(path) = endpoint.strip_prefix("unix://") {
+        return Endpoint::Unix(path);
+    }
+    let host = ["tcp://", "http://"]
+        .iter()
+        .find_map(|scheme| endpoint.strip_prefix(scheme))
+        .unwrap_or(endpoint);
+    Endpoint::Tcp(host.trim_end_matches('/'))
+}
+
+// 发布端口绑定在所有地址上时，导入的服务器使用守护进程所在的主机
+fn daemon_host(endpoint: &str) -> String {
+    match parse_endpoint(endpoint) {
+        Endpoint::Unix(_) => "127.0.0.1".to_string(),
+        Endpoint::Tcp(addr) => match addr.rsplit_once(':') {
+            Some((host, port)) if port.parse::<u16>().is_ok() => host.to_string(),
+            _ => addr.to_string(),
+        },
+    }
+}
+
+// 读取 Connection: close 的HTTP响应，返回状态码和正文
+async fn exchange<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    path: &str,
+) -> Result<(u16, Vec<u8>)> {
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: docker\r\nUser-Agent: server_check\r\nConnection: close\r\n\r\n",
+        path
+    );
+    stream.write_all(request.as_bytes()).await?;
+    let mut raw = Vec::new();
+    stream.read_to_end(&mut raw).await?;
+
+    let split = raw
+        .windows(4)
+        .position(|w| w == b"\r\n\r\n")
+        .context(tr!("Docker 的响应不完整", "Incomplete Docker response"))?;
+    let head = String::from_utf8_lossy(&raw[..split]);
+    let mut lines = head.lines();
+    let code = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|code| code.parse().ok())
+        .context(tr!(
+            "Docker 的响应不是HTTP",
+            "Docker did not reply with HTTP"
+        ))?;
+    let chunked = lines.any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.eq_ignore_ascii_case("transfer-encoding") && value.trim() == "chunked"
+        })
+    });
+    let body = &raw[split + 4..];
+    let body = if chunked {
+        dechunk(body)?
+    } else {
+        body.to_vec()
+    };
+    Ok((code, body))
+}
+
+fn dechunk(mut data: &[u8]) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    loop {
+        let end = data
+            .windows(2)
+            .position(|w| w == b"\r\n")
+            .context(tr!("分块编码不完整", "Incomplete chunked encoding"))?;
+        let size = std::str::from_utf8(&data[..end])?;
+        let size = usize::from_str_radix(size.split(';').next().unwrap_or("").trim(), 16)?;
+        if size == 0 {
+            return Ok(out);
+        }
+        let chunk = data
+            .get(end + 2..end + 2 + size)
+            .context(tr!("分块编码不完整", "Incomplete chunked encoding"))?;
+        out.extend_from_slice(chunk);
+        data = data.get(end + 4 + size..).unwrap_or_default();
+    }
+}
+
+async fn get(endpoint: &str, path: &str) -> Result<(u16, Vec<u8>)> {
+    match parse_endpoint(endpoint) {
+        #[cfg(unix)]
+        Endpoint::Unix(socket) => {
+            exchange(tokio::net::UnixStream::connect(socket).await?, path).await
+        }
+        #[cfg(not(unix))]
+        Endpoint::Unix(_) => bail!(tr!(
+            "此系统不支持 unix socket，请使用 tcp://主机:2375",
+            "Unix sockets are not supported here, use tcp://host:2375"
+        )),
+        Endpoint::Tcp(addr) => exchange(TcpStream::connect(addr).await?, path).await,
+    }
+}
+
+// 连接守护进程失败，用于区分离线和接口错误
+fn unreachable(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<std::io::Error>().is_some()
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Health {
+    #[serde(rename = "Status")]
+    status: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct State {
+    #[serde(rename = "Status")]
+    status: String,
+    #[serde(rename = "Health")]
+    health: Option<Health>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Inspect {
+    #[serde(rename = "State")]
+    state: State,
+}
+
+impl State {
+    // running 且健康检查通过（或没有健康检查）为在线，启动中、重启中和暂停为降级
+    fn outcome(&self) -> CheckOutcome {
+        let health = self.health.as_ref().map(|h| h.status.as_str());
+        let status = match (self.status.as_str(), health) {
+            ("running", None | Some("healthy" | "none" | "")) => ServerStatus::Online,
+            ("running", Some("unhealthy")) => ServerStatus::Error(1),
+            ("running" | "restarting" | "paused", _) => ServerStatus::Degraded,
+            _ => ServerStatus::Offline,
+        };
+        let detail = match health {
+            Some(health) if !health.is_empty() && health != "none" => {
+                format!("{} ({})", self.status, health)
+            }
+            _ => self.status.clone(),
+        };
+        CheckOutcome::new(status, detail)
+    }
+}
+
+async fn inspect(check: &DockerCheck) -> Result<CheckOutcome> {
+    let container = check.container.trim();
+    if container.is_empty() {
+        bail!(tr!("未填写容器名", "No container name"));
+    }
+    let path = format!("/containers/{}/json", uri_encode(container));
+    let (code, body) = get(check.endpoint(), &path).await?;
+    if code == 404 {
+        return Ok(CheckOutcome::new(
+            ServerStatus::Error(404),
+            tr!("容器 {} 不存在", "No such container: {}", container),
+        ));
+    }
+    if !(200..300).contains(&code) {
+        bail!(tr!(
+            "Docker 返回 {}: {}",
+            "Docker returned {}: {}",
+            code,
+            String::from_utf8_lossy(&body).trim()
+        ));
+    }
+    let inspect: Inspect = serde_json::from_slice(&body)?;
+    Ok(inspect.state.outcome())
+}
+
+// 守护进程连不上为离线，容器已停止为离线，不健康或接口出错为错误
+pub async fn check(check: &DockerCheck, timeout: Duration) -> CheckOutcome {
+    match tokio::time::timeout(timeout, inspect(check)).await {
+        Ok(Ok(outcome)) => outcome,
+        Ok(Err(e)) if unreachable(&e) => CheckOutcome::new(ServerStatus::Offline, e.to_string()),
+        Ok(Err(e)) => CheckOutcome::new(ServerStatus::Error(1), e.to_string()),
+        Err(_) => CheckOutcome::timeout(tr!("Docker 检查超时", "Docker check timed out")),
+    }
+}
+
+// 运行中且有发布端口的容器
+#[derive(Debug, Clone, PartialEq)]
+pub struct Container {
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ListedPort {
+    #[serde(rename = "IP")]
+    ip: String,
+    #[serde(rename = "PublicPort")]
+    public_port: Option<u16>,
+    #[serde(rename = "Type")]
+    kind: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Listed {
+    #[serde(rename = "Names")]
+    names: Vec<String>,
+    #[serde(rename = "Ports")]
+    ports: Vec<ListedPort>,
+}
+
+// 每个容器取最小的TCP发布端口，IPv4 和 IPv6 各出现一次的只算一个
+pub async fn list_containers(endpoint: &str, timeout: Duration) -> Result<Vec<Container>> {
+    let endpoint = match endpoint.trim() {
+        "" => DEFAULT_SOCKET,
+        endpoint => endpoint,
+    };
+    let (code, body) = tokio::time::timeout(timeout, get(endpoint, "/containers/json"))
+        .await
+        .map_err(|_| anyhow::anyhow!(tr!("连接 Docker 超时", "Docker timed out")))??;
+    if !(200..300).contains(&code) {
+        bail!(tr!("Docker 返回 {}", "Docker returned {}", code));
+    }
+    let listed: Vec<Listed> = serde_json::from_slice(&body)?;
+    let daemon = daemon_host(endpoint);
+    Ok(listed
+        .into_iter()
+        .filter_map(|container| {
+            let port = container
+                .ports
+                .iter()
+                .filter(|p| p.kind == "tcp" && p.public_port.is_some())
+                .min_by_key(|p| (p.public_port, p.ip.contains(':')))?;
+            let host = match port.ip.as_str() {
+                "" | "0.0.0.0" | "::" => daemon.clone(),
+                ip => ip.to_string(),
+            };
+            let name = container.names.first()?.trim_start_matches('/').to_string();
+            Some(Container {
+                name,
+                host,
+                port: port.public_port?,
+            })
+        })
+        .collect())
+}
+
+pub type ContainerListing = Arc<Mutex<Option<Result<Vec<Container>, String>>>>;
+
+// 在后台列出容器，完成后写入 listing
+pub fn spawn_list(endpoint: &str, listing: &ContainerListing) {
+    let endpoint = endpoint.to_string();
+    let listing = Arc::clone(listing);
+    tokio::spawn(async move {
+        let result = list_containers(&endpoint, Duration::from_secs(10)).await;
+        *listing.lock().unwrap() = Some(result.map_err(|e| e.to_string()));
+    });
+}
+
+// 把容器加到服务器列表，检查方式为Docker；已有同名服务器的跳过，返回新增数量
+pub fn import(
+    servers: &mut Vec<Server>,
+    containers: &[Container],
+    settings: &DockerImportSettings,
+) -> usize {
+    let mut added = 0;
+    for container in containers {
+        if servers.iter().any(|s| s.name == container.name) {
+            continue;
+        }
+        let mut server = Server::new(
+            container.name.clone(),
+            container.host.clone(),
+            container.port,
+        );
+        server.group = settings.group.trim().to_string();
+        server.check = super::CheckKind::Docker(DockerCheck {
+            endpoint: settings.endpoint.trim().to_string(),
+            container: container.name.clone(),
+        });
+        servers.push(server);
+        added += 1;
+    }
+    added
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    // 按请求路径返回固定内容的假 Docker 守护进程，列表接口用分块编码
+    async fn fake_daemon() -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut buf = vec![0; 4096];
+                let n = socket.read(&mut buf).await.unwrap();
+                let request = String::from_utf8_lossy(&buf[..n]).into_owned();
+                let path = request.split_whitespace().nth(1).unwrap_or("").to_string();
+                let (code, body) = match path.as_str() {
+                    "/containers/web/json" => (
+                        200,
+                        r#"{"State":{"Status":"running","Health":{"Status":"healthy"}}}"#,
+                    ),
+                    "/containers/api/json" => (
+                        200,
+                        r#"{"State":{"Status":"running","Health":{"Status":"unhealthy"}}}"#,
+                    ),
+                    "/containers/job/json" => (200, r#"{"State":{"Status":"exited"}}"#),
+                    "/containers/json" => (
+                        200,
+                        r#"[{"Names":["/web"],"Ports":[{"IP":"::","PrivatePort":80,"PublicPort":8080,"Type":"tcp"},{"IP":"0.0.0.0","PrivatePort":80,"PublicPort":8080,"Type":"tcp"}]},
+                            {"Names":["/db"],"Ports":[{"IP":"127.0.0.1","PrivatePort":5432,"PublicPort":5432,"Type":"tcp"}]},
+                            {"Names":["/worker"],"Ports":[{"PrivatePort":9000,"Type":"tcp"}]}]"#,
+                    ),
+                    _ => (404, r#"{"message":"No such container"}"#),
+                };
+                let response = if path == "/containers/json" {
+                    let (a, b) = body.split_at(body.len() / 2);
+                    format!(
+                        "HTTP/1.1 {} OK\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n{}\r\n{:x}\r\n{}\r\n0\r\n\r\n",
+                        code,
+                        a.len(),
+                        a,
+                        b.len(),
+                        b
+                    )
+                } else {
+                    format!(
+                        "HTTP/1.1 {} OK\r\nContent-Length: {}\r\n\r\n{}",
+                        code,
+                        body.len(),
+                        body
+                    )
+                };
+                let _ = socket.write_all(response.as_bytes()).await;
+            }
+        });
+        format!("tcp://{}", addr)
+    }
+
+    #[tokio::test]
+    async fn maps_container_state() {
+        let endpoint = fake_daemon().await;
+        let timeout = Duration::from_secs(5);
+        let docker = |container: &str| DockerCheck {
+            endpoint: endpoint.clone(),
+            container: container.to_string(),
+        };
+
+        let outcome = check(&docker("web"), timeout).await;
+        assert_eq!(outcome.status, ServerStatus::Online);
+        assert_eq!(outcome.detail, "running (healthy)");
+        assert_eq!(
+            check(&docker("api"), timeout).await.status,
+            ServerStatus::Error(1)
+        );
+        assert_eq!(
+            check(&docker("job"), timeout).await.status,
+            ServerStatus::Offline
+        );
+        assert_eq!(
+            check(&docker("gone"), timeout).await.status,
+            ServerStatus::Error(404)
+        );
+
+        // 守护进程没有运行
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let closed = format!("tcp://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let outcome = check(
+            &DockerCheck {
+                endpoint: closed,
+                container: "web".to_string(),
+            },
+            timeout,
+        )
+        .await;
+        assert_eq!(outcome.status, ServerStatus::Offline);
+    }
+
+    #[tokio::test]
+    async fn imports_containers_with_published_ports() {
+        let endpoint = fake_daemon().await;
+        let containers = list_containers(&endpoint, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(
+            containers,
+            vec![
+                Container {
+                    name: "web".to_string(),
+                    host: "127.0.0.1".to_string(),
+                    port: 8080,
+                },
+                Container {
+                    name: "db".to_string(),
+                    host: "127.0.0.1".to_string(),
+                    port: 5432,
+                },
+            ]
+        );
+
+        let settings = DockerImportSettings {
+            endpoint: endpoint.clone(),
+            ..Default::default()
+        };
+        let mut servers = vec![Server::new("db".to_string(), "10.0.0.5".to_string(), 5432)];
+        assert_eq!(import(&mut servers, &containers, &settings), 1);
+        assert_eq!(servers[1].group, "Docker");
+        assert_eq!(servers[1].url, "http://127.0.0.1:8080");
+        assert!(
+            matches!(&servers[1].check, super::super::CheckKind::Docker(d) if d.container == "web")
+        );
+        assert_eq!(import(&mut servers, &containers, &settings), 0);
+    }
+
+    #[test]
+    fn finds_daemon_host() {
+        assert_eq!(daemon_host(DEFAULT_SOCKET), "127.0.0.1");
+        assert_eq!(daemon_host("tcp://10.0.0.2:2375"), "10.0.0.2");
+        assert_eq!(daemon_host("http://docker.lan:2375/"), "docker.lan");
+    }
+}
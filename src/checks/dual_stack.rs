@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+use super::CheckOutcome;
+use crate::i18n::tr;
+use crate::ServerStatus;
+
+// 主机名同时有IPv4和IPv6地址时检查哪一个
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IpFamily {
+    // 由系统决定，与没有这个设置之前相同
+    #[default]
+    Auto,
+    PreferIpv4,
+    PreferIpv6,
+    // 两个都检查，分别显示结果
+    Both,
+}
+
+impl IpFamily {
+    pub const ALL: [IpFamily; 4] = [
+        IpFamily::Auto,
+        IpFamily::PreferIpv4,
+        IpFamily::PreferIpv6,
+        IpFamily::Both,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            IpFamily::Auto => tr!("自动", "Automatic"),
+            IpFamily::PreferIpv4 => tr!("优先IPv4", "Prefer IPv4"),
+            IpFamily::PreferIpv6 => tr!("优先IPv6", "Prefer IPv6"),
+            IpFamily::Both => tr!("IPv4和IPv6都检查", "Check both IPv4 and IPv6"),
+        }
+    }
+
+    // 从解析结果中选出要检查的地址，优先的协议族没有地址时使用另一个
+    pub fn pick(&self, ips: &[IpAddr]) -> Vec<IpAddr> {
+        let v4 = ips.iter().copied().find(IpAddr::is_ipv4);
+        let v6 = ips.iter().copied().find(IpAddr::is_ipv6);
+        match self {
+            IpFamily::Auto => ips.iter().take(1).copied().collect(),
+            IpFamily::PreferIpv4 => v4.or(v6).into_iter().collect(),
+            IpFamily::PreferIpv6 => v6.or(v4).into_iter().collect(),
+            IpFamily::Both => v4.into_iter().chain(v6).collect(),
+        }
+    }
+}
+
+fn family_name(ip: &IpAddr) -> &'static str {
+    if ip.is_ipv4() {
+        "IPv4"
+    } else {
+        "IPv6"
+    }
+}
+
+// 合并各地址的结果：都在线才在线，否则使用第一个异常的结果；
+// 每个地址的状态另外记在 IPv4 / IPv6 字段中
+pub fn combine(results: Vec<(IpAddr, CheckOutcome)>, both: bool) -> CheckOutcome {
+    let details: Vec<String> = results
+        .iter()
+        .map(|(ip, outcome)| format!("{} {}: {}", family_name(ip), ip, outcome.detail))
+        .collect();
+    let mut fields: Vec<(String, String)> = results
+        .iter()
+        .map(|(ip, outcome)| {
+            let value = match outcome.latency {
+                Some(latency) if outcome.status == ServerStatus::Online => {
+                    format!("{} {} {}ms", outcome.status, ip, latency.as_millis())
+                }
+                _ => format!("{} {} {}", outcome.status, ip, outcome.detail),
+            };
+            (family_name(ip).to_string(), value)
+        })
+        .collect();
+    if both {
+        for family in ["IPv4", "IPv6"] {
+            if !fields.iter().any(|(name, _)| name == family) {
+                let missing = tr!("没有{}地址", "No {} address", family);
+                fields.push((family.to_string(), missing));
+            }
+        }
+    }
+    let index = results
+        .iter()
+        .position(|(_, outcome)| outcome.status != ServerStatus::Online)
+        .unwrap_or(0);
+    let Some((_, mut outcome)) = results.into_iter().nth(index) else {
+        return CheckOutcome::new(ServerStatus::Offline, tr!("没有可用的地址", "No address"));
+    };
+    outcome.detail = details.join(" · ");
+    outcome.fields.extend(fields);
+    outcome
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn picks_addresses_by_family() {
+        let v4: IpAddr = "192.0.2.1".parse().unwrap();
+        let v6: IpAddr = "2001:db8::1".parse().unwrap();
+        let ips = [v6, v4, "192.0.2.2".parse().unwrap()];
+        assert_eq!(IpFamily::Auto.pick(&ips), [v6]);
+        assert_eq!(IpFamily::PreferIpv4.pick(&ips), [v4]);
+        assert_eq!(IpFamily::PreferIpv6.pick(&ips), [v6]);
+        assert_eq!(IpFamily::Both.pick(&ips), [v4, v6]);
+        assert_eq!(IpFamily::PreferIpv6.pick(&[v4]), [v4]);
+    }
+
+    #[test]
+    fn combines_results_per_family() {
+        let v4: IpAddr = "192.0.2.1".parse().unwrap();
+        let v6: IpAddr = "2001:db8::1".parse().unwrap();
+        let mut online = CheckOutcome::new(ServerStatus::Online, "200 OK");
+        online.latency = Some(Duration::from_millis(12));
+        let offline = CheckOutcome::new(ServerStatus::Offline, "refused");
+        let outcome = combine(vec![(v4, online.clone()), (v6, offline)], true);
+        assert_eq!(outcome.status, ServerStatus::Offline);
+        assert!(outcome.detail.contains("IPv6 2001:db8::1: refused"));
+        assert!(outcome.fields["IPv4"].ends_with("192.0.2.1 12ms"));
+
+        let outcome = combine(vec![(v4, online)], true);
+        assert_eq!(outcome.status, ServerStatus::Online);
+        assert_eq!(
+            outcome.fields["IPv6"],
+            tr!("没有IPv6地址", "No IPv6 address")
+        );
+    }
+}
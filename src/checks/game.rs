@@ -0,0 +1,396 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWriteExt};
+
+use super::target::{udp_connect, Target};
+use super::CheckOutcome;
+use crate::address;
+use crate::i18n::tr;
+use crate::ServerStatus;
+
+// 游戏服务器查询：Minecraft 的服务器列表 Ping（1.7 以后的协议）和 Source 引擎的 A2S_INFO，
+// 说明中显示玩家数和 MOTD/服务器名，不需要登录游戏
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum GameProtocol {
+    #[default]
+    Minecraft,
+    // CS2、TF2、Garry's Mod、Rust、ARK 等使用 Source 查询协议的游戏
+    Source,
+}
+
+impl GameProtocol {
+    pub const ALL: [GameProtocol; 2] = [GameProtocol::Minecraft, GameProtocol::Source];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            GameProtocol::Minecraft => "Minecraft",
+            GameProtocol::Source => "Source (A2S)",
+        }
+    }
+
+    pub fn default_port(&self) -> u16 {
+        match self {
+            GameProtocol::Minecraft => 25565,
+            GameProtocol::Source => 27015,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GameCheck {
+    pub protocol: GameProtocol,
+    pub port: u16,
+}
+
+impl Default for GameCheck {
+    fn default() -> Self {
+        Self {
+            protocol: GameProtocol::Minecraft,
+            port: GameProtocol::Minecraft.default_port(),
+        }
+    }
+}
+
+impl GameCheck {
+    pub fn endpoint(&self, host: &str) -> String {
+        let scheme = match self.protocol {
+            GameProtocol::Minecraft => "minecraft",
+            GameProtocol::Source => "a2s",
+        };
+        address::build_url(scheme, host, self.port)
+    }
+}
+
+// 查询得到的服务器信息
+#[derive(Debug, Default, PartialEq)]
+struct ServerInfo {
+    name: String,
+    players: u64,
+    max_players: u64,
+    version: String,
+    map: String,
+    latency: Option<Duration>,
+}
+
+impl ServerInfo {
+    fn outcome(self) -> CheckOutcome {
+        let mut detail = format!("👥 {}/{} · {}", self.players, self.max_players, self.name);
+        if !self.map.is_empty() {
+            detail.push_str(&format!(" · {}", self.map));
+        }
+        let mut outcome = CheckOutcome::new(ServerStatus::Online, detail);
+        outcome.latency = self.latency;
+        for (key, value) in [
+            ("players", self.players.to_string()),
+            ("max_players", self.max_players.to_string()),
+            ("version", self.version),
+            ("map", self.map),
+        ] {
+            if !value.is_empty() {
+                outcome.fields.insert(key.to_string(), value);
+            }
+        }
+        outcome
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, value: i32) {
+    let mut value = value as u32;
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            out.push(byte);
+            return;
+        }
+        out.push(byte | 0x80);
+    }
+}
+
+async fn read_varint<S: AsyncRead + Unpin>(stream: &mut S) -> Result<i32> {
+    let mut value = 0u32;
+    for shift in 0..5 {
+        let byte = stream.read_u8().await?;
+        value |= ((byte & 0x7f) as u32) << (7 * shift);
+        if byte & 0x80 == 0 {
+            return Ok(value as i32);
+        }
+    }
+    bail!(tr!("无效的数据长度", "Invalid length"))
+}
+
+// 带长度前缀的数据包
+fn mc_packet(id: u8, body: &[u8]) -> Vec<u8> {
+    let mut packet = Vec::new();
+    write_varint(&mut packet, body.len() as i32 + 1);
+    packet.push(id);
+    packet.extend_from_slice(body);
+    packet
+}
+
+async fn read_mc_packet<S: AsyncRead + Unpin>(stream: &mut S) -> Result<Vec<u8>> {
+    let len = read_varint(stream).await?;
+    if !(1..=1 << 21).contains(&len) {
+        bail!(tr!("无效的数据长度", "Invalid length"));
+    }
+    let mut packet = vec![0; len as usize];
+    stream.read_exact(&mut packet).await?;
+    Ok(packet)
+}
+
+// 聊天组件中的文字：字符串，或带 text 和 extra 的对象
+fn chat_text(value: &serde_json::Value) -> String {
+    match value {
+        serde_json::Value::String(text) => text.clone(),
+        serde_json::Value::Array(parts) => parts.iter().map(chat_text).collect(),
+        serde_json::Value::Object(map) => {
+            let mut text = map
+                .get("text")
+                .and_then(|t| t.as_str())
+                .unwrap_or_default()
+                .to_string();
+            if let Some(extra) = map.get("extra") {
+                text.push_str(&chat_text(extra));
+            }
+            text
+        }
+        _ => String::new(),
+    }
+}
+
+// 去掉 § 开头的颜色和格式代码，多行 MOTD 合并为一行
+fn strip_formatting(text: &str) -> String {
+    let mut out = String::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c == '§' {
+            chars.next();
+        } else {
+            out.push(c);
+        }
+    }
+    out.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn parse_status(json: &str) -> Result<ServerInfo> {
+    let status: serde_json::Value = serde_json::from_str(json)?;
+    let players = &status["players"];
+    Ok(ServerInfo {
+        name: strip_formatting(&chat_text(&status["description"])),
+        players: players["online"].as_u64().unwrap_or(0),
+        max_players: players["max"].as_u64().unwrap_or(0),
+        version: strip_formatting(status["version"]["name"].as_str().unwrap_or_default()),
+        ..Default::default()
+    })
+}
+
+// deadline 是整个检查的截止时间，等待 Pong 不会超过它
+async fn minecraft(
+    target: Target<'_>,
+    port: u16,
+    deadline: tokio::time::Instant,
+) -> Result<ServerInfo> {
+    let mut stream = target.connect(port).await?;
+    let host = target.name();
+    let mut handshake = Vec::new();
+    // 协议版本填 -1 表示只查询状态
+    write_varint(&mut handshake, -1);
+    write_varint(&mut handshake, host.len() as i32);
+    handshake.extend_from_slice(host.as_bytes());
+    handshake.extend_from_slice(&port.to_be_bytes());
+    write_varint(&mut handshake, 1);
+    stream.write_all(&mc_packet(0x00, &handshake)).await?;
+    stream.write_all(&mc_packet(0x00, &[])).await?;
+
+    let packet = read_mc_packet(&mut stream).await?;
+    let mut body = &packet[1..];
+    let len = read_varint(&mut body).await? as usize;
+    let json = body
+        .get(..len)
+        .context(tr!("状态数据不完整", "Incomplete status response"))?;
+    let mut info = parse_status(&String::from_utf8_lossy(json))?;
+
+    // Ping/Pong 得到的往返时间；有的服务器不回复 Pong，不影响结果
+    let payload = chrono::Local::now().timestamp_millis();
+    let started = Instant::now();
+    stream
+        .write_all(&mc_packet(0x01, &payload.to_be_bytes()))
+        .await?;
+    let wait = deadline.min(tokio::time::Instant::now() + Duration::from_secs(2));
+    if let Ok(Ok(pong)) = tokio::time::timeout_at(wait, read_mc_packet(&mut stream)).await {
+        if pong.first() == Some(&0x01) {
+            info.latency = Some(started.elapsed());
+        }
+    }
+    Ok(info)
+}
+
+const A2S_INFO: &[u8] = b"\xff\xff\xff\xffTSource Engine Query\0";
+
+// 读取以0结尾的字符串
+fn cstring(data: &mut &[u8]) -> Result<String> {
+    let end = data
+        .iter()
+        .position(|b| *b == 0)
+        .context(tr!("A2S 回复不完整", "Incomplete A2S reply"))?;
+    let text = String::from_utf8_lossy(&data[..end]).into_owned();
+    *data = &data[end + 1..];
+    Ok(text)
+}
+
+fn parse_a2s_info(reply: &[u8]) -> Result<ServerInfo> {
+    let Some(mut data) = reply.strip_prefix(b"\xff\xff\xff\xff\x49") else {
+        bail!(tr!("不是 A2S_INFO 回复", "Not an A2S_INFO reply"));
+    };
+    // 协议版本
+    data = data.get(1..).unwrap_or_default();
+    let name = cstring(&mut data)?;
+    let map = cstring(&mut data)?;
+    let _folder = cstring(&mut data)?;
+    let game = cstring(&mut data)?;
+    // 2字节的 Steam 应用ID，之后是玩家数、最大玩家数
+    let counts = data
+        .get(2..4)
+        .context(tr!("A2S 回复不完整", "Incomplete A2S reply"))?;
+    Ok(ServerInfo {
+        name,
+        players: counts[0] as u64,
+        max_players: counts[1] as u64,
+        version: game,
+        map,
+        latency: None,
+    })
+}
+
+async fn source(host: &str, port: u16) -> Result<ServerInfo> {
+    let socket = udp_connect((host, port)).await?;
+    let mut request = A2S_INFO.to_vec();
+    let mut buf = vec![0; 1400];
+    // 新版本的服务器先返回挑战码，带上挑战码重发一次
+    for _ in 0..3 {
+        let started = Instant::now();
+        socket.send(&request).await?;
+        let n = socket.recv(&mut buf).await?;
+        let reply = &buf[..n];
+        if let Some(challenge) = reply.strip_prefix(b"\xff\xff\xff\xff\x41") {
+            request = [A2S_INFO, challenge].concat();
+            continue;
+        }
+        let mut info = parse_a2s_info(reply)?;
+        info.latency = Some(started.elapsed());
+        return Ok(info);
+    }
+    bail!(tr!(
+        "服务器一直返回挑战码",
+        "Server kept sending challenges"
+    ))
+}
+
+// 连不上端口为离线，回复无法解析为错误
+pub async fn check(target: Target<'_>, check: &GameCheck, timeout: Duration) -> CheckOutcome {
+    let deadline = tokio::time::Instant::now() + timeout;
+    let query = async {
+        match check.protocol {
+            GameProtocol::Minecraft => minecraft(target, check.port, deadline).await,
+            GameProtocol::Source => source(&target.address(), check.port).await,
+        }
+    };
+    // 两个截止时间相同时先轮询查询本身，等 Pong 超时仍会返回已读到的状态
+    match tokio::time::timeout_at(deadline, query).await {
+        Ok(Ok(info)) => info.outcome(),
+        Ok(Err(e)) => match e.downcast::<std::io::Error>() {
+            Ok(e) => CheckOutcome::new(ServerStatus::Offline, e.to_string()),
+            Err(e) => CheckOutcome::new(ServerStatus::Error(1), e.to_string()),
+        },
+        Err(_) => CheckOutcome::timeout(tr!("游戏服务器无响应", "Game server did not respond")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::{TcpListener, UdpSocket};
+
+    // 回复状态的假服务器，pong 为 false 时不回复 Ping
+    async fn fake_minecraft(pong: bool) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let handshake = read_mc_packet(&mut socket).await.unwrap();
+            assert_eq!(handshake[0], 0x00);
+            assert_eq!(read_mc_packet(&mut socket).await.unwrap(), vec![0x00]);
+            let json = r#"{"version":{"name":"Paper 1.21"},"players":{"max":20,"online":3},
+                "description":{"text":"§aHome ","extra":[{"text":"Survival\n§7welcome"}]}}"#;
+            let mut body = Vec::new();
+            write_varint(&mut body, json.len() as i32);
+            body.extend_from_slice(json.as_bytes());
+            socket.write_all(&mc_packet(0x00, &body)).await.unwrap();
+            let ping = read_mc_packet(&mut socket).await.unwrap();
+            if pong {
+                socket
+                    .write_all(&mc_packet(0x01, &ping[1..]))
+                    .await
+                    .unwrap();
+            } else {
+                tokio::time::sleep(Duration::from_secs(10)).await;
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn queries_minecraft_status() {
+        let game = GameCheck {
+            port: fake_minecraft(true).await,
+            ..Default::default()
+        };
+        let outcome = check(Target::new("127.0.0.1"), &game, Duration::from_secs(5)).await;
+        assert_eq!(outcome.status, ServerStatus::Online);
+        assert_eq!(outcome.detail, "👥 3/20 · Home Survival welcome");
+        assert_eq!(outcome.fields["version"], "Paper 1.21");
+        assert!(outcome.latency.is_some());
+
+        // 不回复 Pong 时只等到检查的超时，仍然返回状态
+        let game = GameCheck {
+            port: fake_minecraft(false).await,
+            ..Default::default()
+        };
+        let outcome = check(Target::new("127.0.0.1"), &game, Duration::from_millis(500)).await;
+        assert_eq!(outcome.status, ServerStatus::Online);
+        assert!(outcome.latency.is_none());
+    }
+
+    #[tokio::test]
+    async fn queries_source_with_challenge() {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1400];
+            loop {
+                let (n, peer) = socket.recv_from(&mut buf).await.unwrap();
+                let reply = if n == A2S_INFO.len() {
+                    b"\xff\xff\xff\xff\x41\x01\x02\x03\x04".to_vec()
+                } else {
+                    assert_eq!(&buf[A2S_INFO.len()..n], &[1, 2, 3, 4]);
+                    let mut reply = b"\xff\xff\xff\xff\x49\x11".to_vec();
+                    reply.extend_from_slice(b"Friday Night\0de_dust2\0csgo\0Counter-Strike 2\0");
+                    reply.extend_from_slice(&[0xda, 0x02, 7, 16, 0]);
+                    reply
+                };
+                socket.send_to(&reply, peer).await.unwrap();
+            }
+        });
+        let game = GameCheck {
+            protocol: GameProtocol::Source,
+            port,
+        };
+        let outcome = check(Target::new("127.0.0.1"), &game, Duration::from_secs(5)).await;
+        assert_eq!(outcome.status, ServerStatus::Online);
+        assert_eq!(outcome.detail, "👥 7/16 · Friday Night · de_dust2");
+        assert_eq!(outcome.fields["version"], "Counter-Strike 2");
+    }
+}
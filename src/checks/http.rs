@@ -0,0 +1,510 @@
+use reqwest::header::HeaderMap;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::time::Duration;
+
+use super::{assertion, tls, CheckOutcome};
+use crate::baseline::SecurityProfile;
+use crate::columns::{self, ColumnDef};
+use crate::i18n::tr;
+use crate::proxy::{self, ProxyMode, ProxySettings};
+use crate::secret;
+use crate::{template, ServerStatus};
+
+// 每台服务器单独的HTTP请求设置，留空时使用默认值
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct HttpOptions {
+    // 覆盖默认的 User-Agent，部分WAF会拦截非浏览器的请求
+    pub user_agent: String,
+    // Basic认证，用户名为空时不发送
+    pub username: String,
+    pub password: String,
+    // 对JSON响应的断言，每行一条，例如 $.status == "ok"
+    pub assertions: String,
+    // 有的健康检查接口只接受POST或需要JSON请求体
+    pub method: HttpMethod,
+    pub body: String,
+    // 请求体的 Content-Type，为空时不发送
+    pub content_type: String,
+    pub redirect: RedirectPolicy,
+    // 最多跟随的跳转次数，0表示默认的10次
+    pub max_redirects: u8,
+    pub proxy_mode: ProxyMode,
+    // 单独设置代理时使用
+    pub proxy: ProxySettings,
+    // 连接URL中的地址，但 Host 和 SNI 使用这个主机名，用于检查负载均衡后面的虚拟主机
+    pub host_header: String,
+    // 用这个DNS服务器解析URL中的主机名，为空时使用系统的解析
+    pub dns_server: String,
+    pub protocol: HttpVersion,
+}
+
+// 要求使用的HTTP版本
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HttpVersion {
+    // 由服务器协商，与没有这个设置之前相同；不支持HTTP/3，旧配置中的 Http3 和 AltSvcH3 也读成这一项
+    #[default]
+    #[serde(alias = "Http3", alias = "AltSvcH3")]
+    Auto,
+    Http1,
+    // 不经协商直接使用HTTP/2，服务器不支持时检查失败
+    Http2,
+}
+
+impl HttpVersion {
+    pub const ALL: [HttpVersion; 3] = [HttpVersion::Auto, HttpVersion::Http1, HttpVersion::Http2];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            HttpVersion::Auto => tr!("自动", "Automatic"),
+            HttpVersion::Http1 => "HTTP/1.1",
+            HttpVersion::Http2 => "HTTP/2",
+        }
+    }
+}
+
+fn version_name(version: reqwest::Version) -> &'static str {
+    match version {
+        reqwest::Version::HTTP_09 => "HTTP/0.9",
+        reqwest::Version::HTTP_10 => "HTTP/1.0",
+        reqwest::Version::HTTP_2 => "HTTP/2",
+        reqwest::Version::HTTP_3 => "HTTP/3",
+        _ => "HTTP/1.1",
+    }
+}
+
+// Alt-Svc 中声明的 h3 端点，例如 h3=":443"; ma=86400
+fn advertised_h3(headers: &HeaderMap) -> Option<String> {
+    headers
+        .get_all(reqwest::header::ALT_SVC)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .find(|entry| entry.starts_with("h3=") || entry.starts_with("h3-"))
+        .map(|entry| entry.split(';').next().unwrap_or(entry).to_string())
+}
+
+// 遇到 3xx 跳转时的处理方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RedirectPolicy {
+    // 跟随跳转，以最终的状态码为准
+    #[default]
+    Follow,
+    // 不跟随，3xx 视为在线
+    StopOnline,
+    // 不跟随，3xx 视为错误，用于发现服务开始跳转到登录页或SSO错误页
+    StopError,
+}
+
+impl RedirectPolicy {
+    pub const ALL: [RedirectPolicy; 3] = [
+        RedirectPolicy::Follow,
+        RedirectPolicy::StopOnline,
+        RedirectPolicy::StopError,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RedirectPolicy::Follow => tr!("跟随跳转", "Follow"),
+            RedirectPolicy::StopOnline => tr!("不跟随，3xx视为在线", "Don't follow, 3xx is online"),
+            RedirectPolicy::StopError => {
+                tr!("不跟随，3xx视为错误", "Don't follow, 3xx is an error")
+            }
+        }
+    }
+}
+
+// 与 reqwest 的默认值相同
+const DEFAULT_MAX_REDIRECTS: u8 = 10;
+// 单独创建客户端时使用，与共享客户端的超时相同
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+impl HttpOptions {
+    pub fn max_redirects(&self) -> u8 {
+        match self.max_redirects {
+            0 => DEFAULT_MAX_REDIRECTS,
+            n => n,
+        }
+    }
+
+    // 跳转、代理或HTTP版本与共享客户端不同时需要单独创建客户端
+    pub fn needs_own_client(&self) -> bool {
+        self.redirect != RedirectPolicy::Follow
+            || self.max_redirects() != DEFAULT_MAX_REDIRECTS
+            || self.proxy_mode != ProxyMode::Global
+            || matches!(self.protocol, HttpVersion::Http1 | HttpVersion::Http2)
+    }
+
+    pub fn client_builder(&self) -> reqwest::ClientBuilder {
+        let policy = match self.redirect {
+            RedirectPolicy::Follow => {
+                reqwest::redirect::Policy::limited(self.max_redirects() as usize)
+            }
+            RedirectPolicy::StopOnline | RedirectPolicy::StopError => {
+                reqwest::redirect::Policy::none()
+            }
+        };
+        let mut builder = reqwest::Client::builder()
+            .timeout(DEFAULT_TIMEOUT)
+            .tls_info(true)
+            .redirect(policy);
+        builder = match self.protocol {
+            HttpVersion::Http1 => builder.http1_only(),
+            HttpVersion::Http2 => builder.http2_prior_knowledge(),
+            HttpVersion::Auto => builder,
+        };
+        match self.proxy_mode {
+            ProxyMode::Global => builder.proxy(proxy::global()),
+            ProxyMode::Direct => builder.no_proxy(),
+            ProxyMode::Custom => builder.proxy(proxy::custom(&self.proxy)),
+        }
+    }
+
+    // 密码可以是钥匙串或环境变量的引用，发送请求前取出实际的值
+    pub async fn with_secrets(&self) -> anyhow::Result<Cow<'_, HttpOptions>> {
+        if !secret::is_reference(&self.password) {
+            return Ok(Cow::Borrowed(self));
+        }
+        let mut options = self.clone();
+        options.password = secret::resolve_async(&self.password).await?;
+        Ok(Cow::Owned(options))
+    }
+
+    pub fn apply(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        self.apply_scoped(request, true)
+    }
+
+    // 发往其他站点的请求只带 User-Agent，不带基本认证
+    pub fn apply_scoped(
+        &self,
+        mut request: reqwest::RequestBuilder,
+        same_origin: bool,
+    ) -> reqwest::RequestBuilder {
+        if !self.user_agent.trim().is_empty() {
+            request = request.header(reqwest::header::USER_AGENT, self.user_agent.trim());
+        }
+        if same_origin && !self.username.is_empty() {
+            request = request.basic_auth(&self.username, Some(&self.password));
+        }
+        request
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HttpMethod {
+    #[default]
+    Get,
+    Post,
+    Put,
+    Patch,
+    Delete,
+    Head,
+}
+
+impl HttpMethod {
+    pub const ALL: [HttpMethod; 6] = [
+        HttpMethod::Get,
+        HttpMethod::Post,
+        HttpMethod::Put,
+        HttpMethod::Patch,
+        HttpMethod::Delete,
+        HttpMethod::Head,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HttpMethod::Get => "GET",
+            HttpMethod::Post => "POST",
+            HttpMethod::Put => "PUT",
+            HttpMethod::Patch => "PATCH",
+            HttpMethod::Delete => "DELETE",
+            HttpMethod::Head => "HEAD",
+        }
+    }
+
+    // 切换为 GET/HEAD 后保留填写的请求体，但不发送
+    pub fn has_body(self) -> bool {
+        !matches!(self, HttpMethod::Get | HttpMethod::Head)
+    }
+
+    pub fn to_reqwest(self) -> reqwest::Method {
+        match self {
+            HttpMethod::Get => reqwest::Method::GET,
+            HttpMethod::Post => reqwest::Method::POST,
+            HttpMethod::Put => reqwest::Method::PUT,
+            HttpMethod::Patch => reqwest::Method::PATCH,
+            HttpMethod::Delete => reqwest::Method::DELETE,
+            HttpMethod::Head => reqwest::Method::HEAD,
+        }
+    }
+}
+
+// 401/403 等响应的具体原因：需要登录，或被WAF的验证页拦截
+#[derive(Debug, Clone, PartialEq)]
+pub enum Challenge {
+    // WWW-Authenticate 中的认证方式和 realm
+    Auth {
+        scheme: String,
+        realm: Option<String>,
+    },
+    // 识别出的WAF厂商
+    Waf(&'static str),
+}
+
+impl Challenge {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Challenge::Auth { .. } => tr!("🔒 需要认证", "🔒 Authentication required"),
+            Challenge::Waf(_) => tr!("🛡 被WAF拦截", "🛡 Blocked by WAF"),
+        }
+    }
+
+    // 显示在详情中的说明和处理建议
+    pub fn hint(&self) -> String {
+        match self {
+            Challenge::Auth { scheme, realm } => {
+                let realm = realm
+                    .as_deref()
+                    .map(|r| format!(" realm=\"{}\"", r))
+                    .unwrap_or_default();
+                tr!(
+                    "服务器要求 {}{} 认证，请在编辑对话框中填写用户名和密码，或改为检查无需登录的健康检查地址",
+                    "The server requires {}{} authentication; set a user and password in the edit dialog, or check an endpoint that needs no login",
+                    scheme,
+                    realm
+                )
+            }
+            Challenge::Waf(vendor) => tr!(
+                "请求被 {} 拦截，请在编辑对话框中设置 User-Agent，或把本机地址加入WAF白名单",
+                "The request was blocked by {}; set a User-Agent in the edit dialog, or allow this machine in the WAF",
+                vendor
+            ),
+        }
+    }
+}
+
+// 读取响应体，最多 limit 字节，超出部分丢弃
+pub async fn read_text(mut resp: reqwest::Response, limit: usize) -> reqwest::Result<String> {
+    let mut body = Vec::new();
+    while let Some(chunk) = resp.chunk().await? {
+        body.extend_from_slice(&chunk);
+        if body.len() >= limit {
+            body.truncate(limit);
+            break;
+        }
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+// 只在这些状态码下识别，WAF的验证页通常是 403/429/503
+const CHALLENGE_CODES: [u16; 4] = [401, 403, 429, 503];
+// 只在响应体开头查找WAF的特征
+const BODY_SCAN_LIMIT: usize = 64 * 1024;
+// 断言和自定义列最多读取的响应体，超出部分截断，JSON不完整时断言不成立
+const BODY_LIMIT: usize = 1024 * 1024;
+
+// 响应头中的WAF标识：头名称、需要包含的值（空为任意）、厂商
+const WAF_HEADERS: [(&str, &str, &str); 7] = [
+    ("cf-mitigated", "", "Cloudflare"),
+    ("server", "cloudflare", "Cloudflare"),
+    ("x-sucuri-id", "", "Sucuri"),
+    ("x-iinfo", "", "Imperva"),
+    ("x-amzn-waf-action", "", "AWS WAF"),
+    ("server", "akamaighost", "Akamai"),
+    ("server", "tengine-aserver", "Aliyun WAF"),
+];
+
+// 验证页或拦截页中的文字（小写）
+const WAF_BODY_MARKERS: [(&str, &str); 8] = [
+    ("cf-chl", "Cloudflare"),
+    ("attention required! | cloudflare", "Cloudflare"),
+    ("just a moment...", "Cloudflare"),
+    ("request unsuccessful. incapsula", "Imperva"),
+    ("sucuri website firewall", "Sucuri"),
+    ("aliyun_waf", "Aliyun WAF"),
+    ("腾讯云web应用防火墙", "Tencent Cloud WAF"),
+    ("安全狗", "SafeDog"),
+];
+
+// 识别认证要求和WAF拦截，其他错误返回None
+pub fn detect(status: u16, headers: &HeaderMap, body: Option<&str>) -> Option<Challenge> {
+    if !CHALLENGE_CODES.contains(&status) {
+        return None;
+    }
+    let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+    if let Some(value) = header("www-authenticate").filter(|_| status == 401 || status == 403) {
+        let value = value.trim();
+        let scheme = value.split_whitespace().next().unwrap_or("Basic");
+        let realm = value
+            .split(',')
+            .map(str::trim)
+            .map(|part| part.strip_prefix(scheme).map_or(part, str::trim_start))
+            .find_map(|part| part.strip_prefix("realm="))
+            .map(|realm| realm.trim_matches('"').to_string());
+        return Some(Challenge::Auth {
+            scheme: scheme.to_string(),
+            realm,
+        });
+    }
+    if status == 401 {
+        return None;
+    }
+    let from_body = body.and_then(body_vendor);
+    for (name, needle, vendor) in WAF_HEADERS {
+        if header(name).is_some_and(|v| v.to_ascii_lowercase().contains(needle)) {
+            // server: cloudflare 也出现在源站本身返回的错误上，还要求响应体是验证页
+            if name != "server" || vendor != "Cloudflare" || from_body.is_some() {
+                return Some(Challenge::Waf(vendor));
+            }
+        }
+    }
+    from_body.map(Challenge::Waf)
+}
+
+fn body_vendor(body: &str) -> Option<&'static str> {
+    let mut end = BODY_SCAN_LIMIT.min(body.len());
+    while !body.is_char_boundary(end) {
+        end -= 1;
+    }
+    let lower = body[..end].to_lowercase();
+    WAF_BODY_MARKERS
+        .iter()
+        .find(|(marker, _)| lower.contains(marker))
+        .map(|(_, vendor)| *vendor)
+}
+
+// 请求URL，2xx为在线，其他状态码为错误，连接失败为离线；同时提取自定义列的值和安全相关的响应头。
+// timeout 为这台服务器单独设置的超时，覆盖客户端的全局超时
+pub async fn check(
+    client: &reqwest::Client,
+    url: &str,
+    options: &HttpOptions,
+    columns: &[ColumnDef],
+    timeout: Option<Duration>,
+) -> CheckOutcome {
+    let options = match options.with_secrets().await {
+        Ok(options) => options,
+        Err(e) => return CheckOutcome::new(ServerStatus::Error(1), e.to_string()),
+    };
+    let mut request = client.request(options.method.to_reqwest(), url);
+    if options.method.has_body() {
+        if !options.content_type.trim().is_empty() {
+            request = request.header(reqwest::header::CONTENT_TYPE, options.content_type.trim());
+        }
+        if !options.body.is_empty() {
+            request = request.body(template::expand(&options.body).into_owned());
+        }
+    }
+    if let Some(timeout) = timeout {
+        request = request.timeout(timeout);
+    }
+    match options.apply(request).send().await {
+        Ok(resp) => {
+            let code = resp.status().as_u16();
+            let status = match options.redirect {
+                _ if resp.status().is_success() => ServerStatus::Online,
+                RedirectPolicy::StopOnline if resp.status().is_redirection() => {
+                    ServerStatus::Online
+                }
+                _ => ServerStatus::Error(code),
+            };
+            let version = version_name(resp.version());
+            let headers = resp.headers().clone();
+            let h3 = advertised_h3(&headers);
+            let redirected = redirect_target(url, resp.url(), &headers);
+            // 客户端开启了 tls_info 时可以直接取到本次连接的证书
+            let cert_expires = resp
+                .extensions()
+                .get::<reqwest::tls::TlsInfo>()
+                .and_then(|info| info.peer_certificate())
+                .and_then(tls::cert_not_after);
+            // 只有用到JSON字段或需要识别WAF验证页时才读取响应体
+            let assert = status == ServerStatus::Online && !options.assertions.trim().is_empty();
+            let body = if assert || columns::needs_body(columns) {
+                read_text(resp, BODY_LIMIT).await.ok()
+            } else if code != 401 && CHALLENGE_CODES.contains(&code) {
+                // 只为识别验证页读取时不需要完整的响应体
+                read_text(resp, BODY_SCAN_LIMIT).await.ok()
+            } else {
+                None
+            };
+            let challenge = detect(code, &headers, body.as_deref());
+            let failures = if assert {
+                assertion::failures(&options.assertions, body.as_deref())
+            } else {
+                Vec::new()
+            };
+            let mut outcome = if failures.is_empty() {
+                let mut detail = challenge.as_ref().map(Challenge::hint).unwrap_or_default();
+                if let Some(target) = redirected {
+                    if !detail.is_empty() {
+                        detail.push_str(" · ");
+                    }
+                    detail.push_str(&tr!("跳转到 {}", "Redirected to {}", target));
+                }
+                CheckOutcome::new(status, detail)
+            } else {
+                // 状态码正常但内容不符合断言
+                CheckOutcome {
+                    content_error: true,
+                    ..CheckOutcome::new(ServerStatus::Error(code), failures.join("; "))
+                }
+            };
+            outcome.challenge = challenge;
+            outcome.cert_expires = cert_expires;
+            outcome.fields = columns::extract(columns, &headers, body.as_deref());
+            let protocol = match h3 {
+                Some(h3) => format!("{} · Alt-Svc {}", version, h3),
+                None => version.to_string(),
+            };
+            outcome
+                .fields
+                .insert(tr!("协议", "Protocol").to_string(), protocol);
+            let header = |name: &str| {
+                headers
+                    .get(name)
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string)
+            };
+            outcome.security = Some(SecurityProfile {
+                hsts: header("strict-transport-security"),
+                server_header: header("server"),
+                ..Default::default()
+            });
+            outcome
+        }
+        Err(e) if e.is_timeout() => CheckOutcome::timeout(e.to_string()),
+        Err(e) if e.is_redirect() => CheckOutcome::new(
+            ServerStatus::Error(1),
+            tr!(
+                "跳转超过 {} 次: {}",
+                "More than {} redirects: {}",
+                options.max_redirects(),
+                e
+            ),
+        ),
+        // 端口可以连上但不支持HTTP/2
+        Err(e) if options.protocol == HttpVersion::Http2 && !e.is_connect() => CheckOutcome::new(
+            ServerStatus::Error(1),
+            tr!("HTTP/2 请求失败: {}", "HTTP/2 request failed: {}", e),
+        ),
+        Err(e) => CheckOutcome::new(ServerStatus::Offline, e.to_string()),
+    }
+}
+
+// 跟随跳转后的最终地址，或者没有跟随时 Location 指向的地址；没有跳转时为None
+fn redirect_target(requested: &str, last: &url::Url, headers: &HeaderMap) -> Option<String> {
+    let location = headers
+        .get(reqwest::header::LOCATION)
+        .and_then(|v| v.to_str().ok());
+    if let Some(location) = location {
+        return Some(
+            last.join(location)
+                .map_or(location.to_string(), String::from),
+        );
+    }
+    let requested = url::Url::parse(requested).ok();
+    (requested.as_ref() != Some(last)).then(|| last.to_string())
+}
@@ -0,0 +1,662 @@
+use reqwest::header::{COOKIE, LOCATION, SET_COOKIE};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use super::http::{HttpMethod, HttpOptions, RedirectPolicy};
+use super::{CheckOutcome, CHECK_TIMEOUT};
+use crate::columns::json_field;
+use crate::i18n::tr;
+use crate::{template, ServerStatus};
+
+// 从响应中提取变量的位置
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExtractSource {
+    // 点分隔的JSON路径，与自定义列相同，例如 data.token
+    #[default]
+    JsonField,
+    // 正则表达式，有分组时取第一个分组
+    Regex,
+    Header,
+}
+
+impl ExtractSource {
+    pub const ALL: [ExtractSource; 3] = [
+        ExtractSource::JsonField,
+        ExtractSource::Regex,
+        ExtractSource::Header,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExtractSource::JsonField => tr!("JSON字段", "JSON field"),
+            ExtractSource::Regex => tr!("正则", "Regex"),
+            ExtractSource::Header => tr!("响应头", "Header"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Extraction {
+    // 后续步骤中用 {name} 引用
+    pub name: String,
+    pub source: ExtractSource,
+    pub expr: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpStep {
+    pub name: String,
+    pub method: HttpMethod,
+    // 相对于服务器URL，也可以是完整地址
+    pub url: String,
+    // 每行一个 名称: 值
+    pub headers: String,
+    pub body: String,
+    // 0表示任意2xx
+    pub expect_status: u16,
+    // 响应中需要包含的文字，为空时不检查
+    pub expect_body: String,
+    pub extract: Vec<Extraction>,
+}
+
+impl HttpStep {
+    pub fn title(&self) -> String {
+        match self.name.trim() {
+            "" => format!("{} {}", self.method.as_str(), self.url.trim()),
+            name => name.to_string(),
+        }
+    }
+}
+
+// 按顺序执行的一组请求，全部通过才为在线；前面步骤提取的变量和收到的Cookie带到后面的步骤，
+// 超时是所有步骤合计的时长
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HttpSteps {
+    pub steps: Vec<HttpStep>,
+}
+
+// 先替换提取的变量，再展开 {timestamp} 等占位符
+fn substitute(text: &str, vars: &HashMap<String, String>) -> String {
+    let mut text = text.to_string();
+    for (name, value) in vars {
+        text = text.replace(&format!("{{{}}}", name), value);
+    }
+    template::expand(&text).into_owned()
+}
+
+fn resolve(base: &str, url: &str) -> Result<String, String> {
+    if url.is_empty() {
+        return Ok(base.to_string());
+    }
+    url::Url::parse(base)
+        .and_then(|base| base.join(url))
+        .map(String::from)
+        .map_err(|e| tr!("无效的URL {}: {}", "Invalid URL {}: {}", url, e))
+}
+
+fn extract(
+    extraction: &Extraction,
+    headers: &reqwest::header::HeaderMap,
+    body: &str,
+) -> Result<Option<String>, String> {
+    let expr = extraction.expr.trim();
+    Ok(match extraction.source {
+        ExtractSource::JsonField => serde_json::from_str::<serde_json::Value>(body)
+            .ok()
+            .and_then(|json| json_field(&json, expr)),
+        ExtractSource::Regex => {
+            let re = regex::Regex::new(expr)
+                .map_err(|e| tr!("无效的正则表达式: {}", "Invalid regex: {}", e))?;
+            re.captures(body)
+                .and_then(|c| c.get(1).or_else(|| c.get(0)))
+                .map(|m| m.as_str().to_string())
+        }
+        ExtractSource::Header => headers
+            .get(expr)
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string),
+    })
+}
+
+// 一条 Cookie 及其作用范围；没有 Domain 属性时只发回设置它的主机
+#[derive(Debug, Clone, PartialEq)]
+struct Cookie {
+    name: String,
+    value: String,
+    domain: String,
+    host_only: bool,
+    path: String,
+    secure: bool,
+}
+
+impl Cookie {
+    fn matches(&self, url: &url::Url) -> bool {
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        let domain_ok = if self.host_only {
+            host == self.domain
+        } else {
+            host == self.domain || host.ends_with(&format!(".{}", self.domain))
+        };
+        let path = url.path();
+        let path_ok = path == self.path
+            || path.starts_with(&self.path)
+                && (self.path.ends_with('/') || path[self.path.len()..].starts_with('/'));
+        domain_ok && path_ok && (!self.secure || url.scheme() == "https")
+    }
+}
+
+// 步骤之间共享的 Cookie，按 Domain 和 Path 决定发给哪些请求
+#[derive(Debug, Default)]
+struct CookieJar {
+    cookies: Vec<Cookie>,
+}
+
+impl CookieJar {
+    // 保存一次响应设置的 Cookie；Max-Age 为0或 Expires 已过时删除
+    fn store(&mut self, url: &url::Url, headers: &reqwest::header::HeaderMap) {
+        let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+        for value in headers.get_all(SET_COOKIE) {
+            let mut parts = value.to_str().unwrap_or_default().split(';');
+            let Some((name, value)) = parts.next().and_then(|p| p.split_once('=')) else {
+                continue;
+            };
+            let mut cookie = Cookie {
+                name: name.trim().to_string(),
+                value: value.trim().to_string(),
+                domain: host.clone(),
+                host_only: true,
+                path: default_path(url),
+                secure: false,
+            };
+            let mut expired = false;
+            for attr in parts {
+                let (key, val) = attr.split_once('=').unwrap_or((attr, ""));
+                let val = val.trim();
+                match key.trim().to_ascii_lowercase().as_str() {
+                    "domain" if !val.is_empty() => {
+                        cookie.domain = val.trim_start_matches('.').to_ascii_lowercase();
+                        cookie.host_only = false;
+                    }
+                    "path" if val.starts_with('/') => cookie.path = val.to_string(),
+                    "secure" => cookie.secure = true,
+                    "max-age" => expired |= val.parse::<i64>().is_ok_and(|age| age <= 0),
+                    "expires" => {
+                        expired |= chrono::DateTime::parse_from_rfc2822(val)
+                            .is_ok_and(|t| t < chrono::Utc::now())
+                    }
+                    _ => {}
+                }
+            }
+            // 不接受为其他站点设置的 Cookie
+            if cookie.name.is_empty()
+                || !(host == cookie.domain || host.ends_with(&format!(".{}", cookie.domain)))
+            {
+                continue;
+            }
+            self.cookies.retain(|c| {
+                (&c.name, &c.domain, &c.path) != (&cookie.name, &cookie.domain, &cookie.path)
+            });
+            if !expired {
+                self.cookies.push(cookie);
+            }
+        }
+    }
+
+    fn header(&self, url: &url::Url) -> Option<String> {
+        let pairs: Vec<String> = self
+            .cookies
+            .iter()
+            .filter(|c| c.matches(url))
+            .map(|c| format!("{}={}", c.name, c.value))
+            .collect();
+        (!pairs.is_empty()).then(|| pairs.join("; "))
+    }
+}
+
+// 没有 Path 属性时为请求路径去掉最后一段
+fn default_path(url: &url::Url) -> String {
+    match url.path().rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(i) => url.path()[..i].to_string(),
+    }
+}
+
+// 一次检查中所有步骤共享的状态
+struct Session {
+    vars: HashMap<String, String>,
+    cookies: CookieJar,
+    // 服务器URL的来源，基本认证只发给它
+    origin: url::Origin,
+    // 所有步骤共用的超时
+    deadline: Instant,
+}
+
+impl Session {
+    // 剩余的时间，用完时为 None
+    fn remaining(&self) -> Option<Duration> {
+        Some(self.deadline.saturating_duration_since(Instant::now())).filter(|d| !d.is_zero())
+    }
+}
+
+fn timed_out() -> CheckOutcome {
+    CheckOutcome::timeout(tr!("检查超时", "Check timed out"))
+}
+
+// 期望的状态码是跳转时不跟随，检查跳转本身
+fn follows(options: &HttpOptions, step: &HttpStep, status: reqwest::StatusCode) -> bool {
+    status.is_redirection()
+        && options.redirect == RedirectPolicy::Follow
+        && !(300..400).contains(&step.expect_status)
+}
+
+// 客户端不自动跳转，每一步在这里跟随跳转：每次响应的 Cookie 都会保存，
+// 跳转到其他站点后不再带上基本认证和步骤中的请求头
+async fn send_step(
+    client: &reqwest::Client,
+    base: &str,
+    step: &HttpStep,
+    options: &HttpOptions,
+    session: &mut Session,
+) -> Result<(url::Url, reqwest::Response), CheckOutcome> {
+    let error = |detail: String| CheckOutcome::new(ServerStatus::Error(1), detail);
+    let url = resolve(base, &substitute(step.url.trim(), &session.vars)).map_err(error)?;
+    let mut url = url::Url::parse(&url).map_err(|e| error(e.to_string()))?;
+    let step_origin = url.origin();
+    let mut method = step.method.to_reqwest();
+    let mut body = (!step.body.is_empty()).then(|| substitute(&step.body, &session.vars));
+    let mut redirects = 0;
+    loop {
+        let same_origin = url.origin() == session.origin;
+        let mut request = options
+            .apply_scoped(client.request(method.clone(), url.clone()), same_origin)
+            .timeout(session.remaining().ok_or_else(timed_out)?);
+        if url.origin() == step_origin {
+            for line in step.headers.lines() {
+                if let Some((name, value)) = line.split_once(':') {
+                    if !name.trim().is_empty() {
+                        let value = substitute(value.trim(), &session.vars);
+                        request = request.header(name.trim(), value);
+                    }
+                }
+            }
+        }
+        if let Some(cookie) = session.cookies.header(&url) {
+            request = request.header(COOKIE, cookie);
+        }
+        if let Some(body) = &body {
+            request = request.body(body.clone());
+        }
+        let resp = match request.send().await {
+            Ok(resp) => resp,
+            Err(e) if e.is_timeout() => return Err(CheckOutcome::timeout(e.to_string())),
+            Err(e) if e.is_builder() => return Err(error(e.to_string())),
+            Err(e) => return Err(CheckOutcome::new(ServerStatus::Offline, e.to_string())),
+        };
+        session.cookies.store(&url, resp.headers());
+        let status = resp.status();
+        let location = resp
+            .headers()
+            .get(LOCATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|l| url.join(l).ok());
+        let Some(next) = location.filter(|_| follows(options, step, status)) else {
+            return Ok((url, resp));
+        };
+        redirects += 1;
+        if redirects > options.max_redirects() {
+            return Err(error(tr!(
+                "跳转超过 {} 次",
+                "More than {} redirects",
+                options.max_redirects()
+            )));
+        }
+        // 303，以及 301/302 之后的 POST 改为不带请求体的 GET
+        if status == reqwest::StatusCode::SEE_OTHER
+            || (matches!(status.as_u16(), 301 | 302) && method == reqwest::Method::POST)
+        {
+            method = reqwest::Method::GET;
+            body = None;
+        }
+        url = next;
+    }
+}
+
+async fn run_step(
+    client: &reqwest::Client,
+    base: &str,
+    step: &HttpStep,
+    options: &HttpOptions,
+    session: &mut Session,
+) -> Result<(), CheckOutcome> {
+    let error = |detail: String| CheckOutcome::new(ServerStatus::Error(1), detail);
+    let (_, resp) = send_step(client, base, step, options, session).await?;
+    let code = resp.status().as_u16();
+    let headers = resp.headers().clone();
+    let passed = match step.expect_status {
+        0 => resp.status().is_success(),
+        expected => code == expected,
+    };
+    if !passed {
+        let detail = match step.expect_status {
+            0 => tr!("状态码 {}", "Status {}", code),
+            expected => tr!(
+                "状态码 {}，期望 {}",
+                "Status {}, expected {}",
+                code,
+                expected
+            ),
+        };
+        return Err(CheckOutcome::new(ServerStatus::Error(code), detail));
+    }
+    let remaining = session.remaining().ok_or_else(timed_out)?;
+    let body = match tokio::time::timeout(remaining, resp.text()).await {
+        Ok(body) => body.unwrap_or_default(),
+        Err(_) => return Err(timed_out()),
+    };
+    let vars = &mut session.vars;
+    let expect_body = substitute(&step.expect_body, vars);
+    if !expect_body.is_empty() && !body.contains(&expect_body) {
+        return Err(error(tr!(
+            "响应中没有 {}",
+            "Response does not contain {}",
+            expect_body
+        )));
+    }
+    for extraction in step.extract.iter().filter(|e| !e.name.trim().is_empty()) {
+        match extract(extraction, &headers, &body).map_err(error)? {
+            Some(value) => {
+                vars.insert(extraction.name.trim().to_string(), value);
+            }
+            None => {
+                return Err(error(tr!(
+                    "无法提取变量 {}",
+                    "Could not extract {}",
+                    extraction.name.trim()
+                )))
+            }
+        }
+    }
+    Ok(())
+}
+
+// 任何一步失败时使用该步的结果，说明中加上是第几步；超时是所有步骤合计的时长
+pub async fn check(
+    client: &reqwest::Client,
+    base: &str,
+    steps: &HttpSteps,
+    options: &HttpOptions,
+    timeout: Option<Duration>,
+) -> CheckOutcome {
+    if steps.steps.is_empty() {
+        return CheckOutcome::new(
+            ServerStatus::Error(1),
+            tr!("未添加步骤", "No steps configured"),
+        );
+    }
+    let options = match options.with_secrets().await {
+        Ok(options) => options,
+        Err(e) => return CheckOutcome::new(ServerStatus::Error(1), e.to_string()),
+    };
+    let base = template::expand(base);
+    let Ok(origin) = url::Url::parse(&base).map(|url| url.origin()) else {
+        return CheckOutcome::new(
+            ServerStatus::Error(1),
+            tr!("无效的URL {}", "Invalid URL {}", base),
+        );
+    };
+    let mut session = Session {
+        vars: HashMap::new(),
+        cookies: CookieJar::default(),
+        origin,
+        deadline: Instant::now() + timeout.unwrap_or(CHECK_TIMEOUT),
+    };
+    let mut timings = Vec::new();
+    for (i, step) in steps.steps.iter().enumerate() {
+        let started = Instant::now();
+        let result = run_step(client, &base, step, &options, &mut session).await;
+        if let Err(mut outcome) = result {
+            outcome.detail = tr!(
+                "步骤 {} {}: {}",
+                "Step {} {}: {}",
+                i + 1,
+                step.title(),
+                outcome.detail
+            );
+            return outcome;
+        }
+        timings.push(format!(
+            "{} {}ms",
+            step.title(),
+            started.elapsed().as_millis()
+        ));
+    }
+    CheckOutcome::new(
+        ServerStatus::Online,
+        tr!(
+            "{} 个步骤全部通过 · {}",
+            "All {} steps passed · {}",
+            steps.steps.len(),
+            timings.join(" · ")
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{body_string, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn substitutes_variables() {
+        let vars = HashMap::from([("token".to_string(), "abc".to_string())]);
+        assert_eq!(
+            substitute("{\"t\":\"{token}\",\"x\":{other}}", &vars),
+            "{\"t\":\"abc\",\"x\":{other}}"
+        );
+        assert_eq!(
+            resolve("https://example.com/app/", "api/me").unwrap(),
+            "https://example.com/app/api/me"
+        );
+        assert_eq!(
+            resolve("https://example.com/app/", "https://auth.example.com/login").unwrap(),
+            "https://auth.example.com/login"
+        );
+    }
+
+    #[tokio::test]
+    async fn carries_token_and_cookies() {
+        let mock = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/login"))
+            .and(body_string("{\"user\":\"admin\"}"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("set-cookie", "session=s1; Path=/; HttpOnly")
+                    .set_body_json(serde_json::json!({"data": {"token": "abc"}})),
+            )
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/me"))
+            .and(header("authorization", "Bearer abc"))
+            .and(header("cookie", "session=s1"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("<id>42</id> alice"))
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/api/items/42"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&mock)
+            .await;
+
+        let mut steps = HttpSteps {
+            steps: vec![
+                HttpStep {
+                    name: "login".into(),
+                    method: HttpMethod::Post,
+                    url: "/login".into(),
+                    headers: "Content-Type: application/json".into(),
+                    body: "{\"user\":\"admin\"}".into(),
+                    extract: vec![Extraction {
+                        name: "token".into(),
+                        source: ExtractSource::JsonField,
+                        expr: "data.token".into(),
+                    }],
+                    ..Default::default()
+                },
+                HttpStep {
+                    url: "/api/me".into(),
+                    headers: "Authorization: Bearer {token}".into(),
+                    expect_status: 200,
+                    expect_body: "alice".into(),
+                    extract: vec![Extraction {
+                        name: "id".into(),
+                        source: ExtractSource::Regex,
+                        expr: "<id>(\\d+)</id>".into(),
+                    }],
+                    ..Default::default()
+                },
+            ],
+        };
+        let client = reqwest::Client::new();
+        let options = HttpOptions::default();
+        let outcome = check(&client, &mock.uri(), &steps, &options, None).await;
+        assert_eq!(outcome.status, ServerStatus::Online, "{}", outcome.detail);
+        assert!(outcome.detail.contains("login"));
+
+        steps.steps.push(HttpStep {
+            url: "/api/items/{id}".into(),
+            ..Default::default()
+        });
+        let outcome = check(&client, &mock.uri(), &steps, &options, None).await;
+        assert_eq!(outcome.status, ServerStatus::Error(404));
+        assert!(outcome.detail.contains("GET /api/items/{id}"));
+        assert!(outcome.detail.contains('3'));
+    }
+
+    #[test]
+    fn scopes_cookies() {
+        let url = url::Url::parse("https://app.example.com/account/login").unwrap();
+        let mut headers = reqwest::header::HeaderMap::new();
+        for cookie in [
+            "session=s1; Secure",
+            "site=1; Domain=.example.com; Path=/",
+            "tracker=x; Domain=other.example",
+            "old=1; Max-Age=0",
+        ] {
+            headers.append(SET_COOKIE, cookie.parse().unwrap());
+        }
+        let mut jar = CookieJar::default();
+        jar.store(&url, &headers);
+        let header = |url: &str| jar.header(&url::Url::parse(url).unwrap());
+        assert_eq!(
+            header("https://app.example.com/account/me").as_deref(),
+            Some("session=s1; site=1")
+        );
+        // 没有 Domain 时只发回同一主机和路径，Secure 的只通过 https 发送
+        assert_eq!(
+            header("https://api.example.com/").as_deref(),
+            Some("site=1")
+        );
+        assert_eq!(
+            header("http://app.example.com/account/me").as_deref(),
+            Some("site=1")
+        );
+        assert_eq!(
+            header("https://app.example.com/accounts").as_deref(),
+            Some("site=1")
+        );
+        assert_eq!(header("https://other.example/"), None);
+    }
+
+    #[tokio::test]
+    async fn follows_redirects_without_leaking_credentials() {
+        let mock = MockServer::start().await;
+        let other = MockServer::start().await;
+        // 同一端口用 localhost 访问是另一个站点
+        let other_uri = other.uri().replace("127.0.0.1", "localhost");
+        Mock::given(method("POST"))
+            .and(path("/login"))
+            .respond_with(
+                ResponseTemplate::new(302)
+                    .insert_header("set-cookie", "session=s1; Path=/")
+                    .insert_header("location", "/home"),
+            )
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/home"))
+            .and(header("cookie", "session=s1"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("welcome"))
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/sso"))
+            .respond_with(
+                ResponseTemplate::new(302)
+                    .insert_header("location", format!("{}/landing", other_uri)),
+            )
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/landing"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&other)
+            .await;
+
+        let steps = HttpSteps {
+            steps: vec![
+                HttpStep {
+                    method: HttpMethod::Post,
+                    url: "/login".into(),
+                    body: "user=admin".into(),
+                    expect_body: "welcome".into(),
+                    ..Default::default()
+                },
+                HttpStep {
+                    url: "/sso".into(),
+                    headers: "X-Api-Key: k1".into(),
+                    ..Default::default()
+                },
+            ],
+        };
+        let client = reqwest::Client::builder()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()
+            .unwrap();
+        let options = HttpOptions {
+            username: "admin".into(),
+            password: "pw".into(),
+            ..Default::default()
+        };
+        let outcome = check(&client, &mock.uri(), &steps, &options, None).await;
+        assert_eq!(outcome.status, ServerStatus::Online, "{}", outcome.detail);
+
+        let landing = &other.received_requests().await.unwrap()[0];
+        for name in ["cookie", "authorization", "x-api-key"] {
+            assert!(!landing.headers.contains_key(name), "{}", name);
+        }
+        // 302 之后的 POST 改为 GET
+        let home = mock.received_requests().await.unwrap();
+        assert!(home
+            .iter()
+            .any(|r| r.url.path() == "/home" && r.method.as_str() == "GET"));
+
+        // 期望状态码是跳转时检查跳转本身
+        let steps = HttpSteps {
+            steps: vec![HttpStep {
+                url: "/sso".into(),
+                expect_status: 302,
+                ..Default::default()
+            }],
+        };
+        let outcome = check(&client, &mock.uri(), &steps, &options, None).await;
+        assert_eq!(outcome.status, ServerStatus::Online, "{}", outcome.detail);
+        assert_eq!(other.received_requests().await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,158 @@
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+
+use super::tls::{self, TlsInfo};
+use crate::i18n::tr;
+
+// 响应体最多保留的字节数
+const BODY_PREVIEW_BYTES: usize = 4096;
+const STEP_TIMEOUT: Duration = Duration::from_secs(5);
+
+// 详情面板中显示的一次HTTP请求的完整信息
+#[derive(Debug, Clone, Default)]
+pub struct HttpDetails {
+    pub url: String,
+    // 主机名解析到的全部地址，以及请求实际连接的地址
+    pub resolved: Vec<IpAddr>,
+    pub remote_addr: Option<SocketAddr>,
+    pub status: Option<u16>,
+    // 例如 HTTP/1.1
+    pub version: String,
+    pub headers: Vec<(String, String)>,
+    pub body_preview: String,
+    pub body_truncated: bool,
+    pub tls: Option<TlsInfo>,
+    // 分段耗时：DNS解析、TCP连接（单独连接一次测得）、从发出请求到收到响应头
+    pub dns: Option<Duration>,
+    pub connect: Option<Duration>,
+    pub ttfb: Option<Duration>,
+    pub total: Option<Duration>,
+    // 某一步失败时的原因，之前各步的结果仍然保留
+    pub error: Option<String>,
+}
+
+// 逐步执行DNS解析、TCP连接、TLS握手和HTTP请求，记录每一步的结果
+pub async fn inspect(client: &reqwest::Client, url: &str) -> HttpDetails {
+    let mut details = HttpDetails {
+        url: url.to_string(),
+        ..Default::default()
+    };
+    if let Err(e) = run(client, url, &mut details).await {
+        details.error = Some(e.to_string());
+    }
+    details
+}
+
+async fn run(client: &reqwest::Client, url: &str, details: &mut HttpDetails) -> anyhow::Result<()> {
+    let parsed = url::Url::parse(url)?;
+    let host = parsed
+        .host_str()
+        .ok_or_else(|| anyhow::anyhow!(tr!("URL中没有主机", "URL has no host")))?
+        .trim_matches(['[', ']'])
+        .to_string();
+    let port = parsed.port_or_known_default().unwrap_or(80);
+
+    let started = Instant::now();
+    let addrs: Vec<SocketAddr> =
+        tokio::time::timeout(STEP_TIMEOUT, tokio::net::lookup_host((host.as_str(), port)))
+            .await
+            .map_err(|_| anyhow::anyhow!(tr!("DNS解析超时", "DNS lookup timed out")))??
+            .collect();
+    details.dns = Some(started.elapsed());
+    for addr in &addrs {
+        if !details.resolved.contains(&addr.ip()) {
+            details.resolved.push(addr.ip());
+        }
+    }
+
+    if let Some(addr) = addrs.first() {
+        let started = Instant::now();
+        tokio::time::timeout(STEP_TIMEOUT, TcpStream::connect(addr))
+            .await
+            .map_err(|_| anyhow::anyhow!(tr!("TCP连接超时", "TCP connect timed out")))??;
+        details.connect = Some(started.elapsed());
+    }
+
+    if parsed.scheme() == "https" {
+        match tls::probe(&host, port, STEP_TIMEOUT).await {
+            Ok(info) => details.tls = Some(info),
+            // 握手失败时HTTP请求也会失败，错误原因以请求的为准
+            Err(e) => eprintln!("TLS握手失败 {}: {}", url, e),
+        }
+    }
+
+    let started = Instant::now();
+    let mut resp = client.get(url).send().await?;
+    details.ttfb = Some(started.elapsed());
+    details.status = Some(resp.status().as_u16());
+    details.version = format!("{:?}", resp.version());
+    details.remote_addr = resp.remote_addr();
+    details.headers = resp
+        .headers()
+        .iter()
+        .map(|(name, value)| {
+            (
+                name.to_string(),
+                String::from_utf8_lossy(value.as_bytes()).into_owned(),
+            )
+        })
+        .collect();
+
+    let mut body = Vec::new();
+    while let Some(chunk) = resp.chunk().await? {
+        body.extend_from_slice(&chunk);
+        if body.len() > BODY_PREVIEW_BYTES {
+            body.truncate(BODY_PREVIEW_BYTES);
+            details.body_truncated = true;
+            break;
+        }
+    }
+    details.body_preview = String::from_utf8_lossy(&body).into_owned();
+    details.total = Some(started.elapsed());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use wiremock::matchers::method;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn captures_headers_body_and_timings() {
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .respond_with(
+                ResponseTemplate::new(201)
+                    .insert_header("X-App-Version", "1.2.3")
+                    .set_body_string("x".repeat(BODY_PREVIEW_BYTES + 100)),
+            )
+            .mount(&mock)
+            .await;
+
+        let details = inspect(&testing::client(Duration::from_secs(5)), &mock.uri()).await;
+        assert_eq!(details.error, None);
+        assert_eq!(details.status, Some(201));
+        assert!(details
+            .headers
+            .iter()
+            .any(|(k, v)| k == "x-app-version" && v == "1.2.3"));
+        assert_eq!(details.body_preview.len(), BODY_PREVIEW_BYTES);
+        assert!(details.body_truncated);
+        assert!(details.dns.is_some() && details.connect.is_some() && details.ttfb.is_some());
+        assert_eq!(details.remote_addr, Some(*mock.address()));
+        assert!(details.tls.is_none());
+    }
+
+    #[tokio::test]
+    async fn keeps_earlier_steps_when_request_fails() {
+        let mock = MockServer::start().await;
+        let url = mock.uri().replace("http://", "https://");
+        let details = inspect(&testing::client(Duration::from_secs(5)), &url).await;
+        assert!(details.error.is_some());
+        assert!(details.dns.is_some() && details.connect.is_some());
+        assert_eq!(details.status, None);
+    }
+}
@@ -0,0 +1,667 @@
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Stdio;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use super::ssh::expand_home;
+use super::CheckOutcome;
+use crate::discovery::uri_encode;
+use crate::i18n::tr;
+use crate::{yaml, ServerStatus};
+
+// Kubernetes API 的健康检查：请求 /readyz（旧版本没有时用 /healthz），
+// 填写了 Deployment 时再查询它的副本数，全部就绪为在线，部分就绪或正在滚动更新为降级
+
+pub const DEFAULT_KUBECONFIG: &str = "~/.kube/config";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KubernetesCheck {
+    // API 地址，例如 https://10.0.0.1:6443，留空时使用 kubeconfig 当前上下文的集群
+    pub server: String,
+    // 留空且没有填写 API 地址时使用 ~/.kube/config
+    pub kubeconfig: String,
+    // ServiceAccount 令牌，优先于 kubeconfig 中的认证信息
+    pub token: String,
+    pub accept_invalid_certs: bool,
+    // 留空时使用 kubeconfig 上下文的命名空间，都没有时为 default
+    pub namespace: String,
+    // 留空时只检查 API
+    pub deployment: String,
+}
+
+impl KubernetesCheck {
+    fn kubeconfig_path(&self) -> Option<String> {
+        match self.kubeconfig.trim() {
+            "" if self.server.trim().is_empty() => Some(expand_home(DEFAULT_KUBECONFIG)),
+            "" => None,
+            path => Some(expand_home(path)),
+        }
+    }
+
+    pub fn target(&self) -> String {
+        let server = match self.server.trim() {
+            "" => self
+                .kubeconfig_path()
+                .unwrap_or_default()
+                .rsplit(['/', '\\'])
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+            server => server.to_string(),
+        };
+        match (self.namespace.trim(), self.deployment.trim()) {
+            (_, "") => server,
+            ("", deployment) => format!("{} {}", server, deployment),
+            (namespace, deployment) => format!("{} {}/{}", server, namespace, deployment),
+        }
+    }
+}
+
+// kubeconfig 中当前上下文的集群和用户，证书文件的路径已按 kubeconfig 所在目录展开
+#[derive(Debug, Default, PartialEq)]
+struct Kubeconfig {
+    server: String,
+    // 上下文的命名空间，检查中没有填写时使用
+    namespace: String,
+    ca_data: String,
+    ca_file: String,
+    insecure: bool,
+    token: String,
+    token_file: String,
+    client_cert_data: String,
+    client_cert_file: String,
+    client_key_data: String,
+    client_key_file: String,
+    exec: Option<ExecPlugin>,
+}
+
+// 获取凭据的 exec 插件，例如 aws eks get-token、gke-gcloud-auth-plugin
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+struct ExecPlugin {
+    api_version: String,
+    command: String,
+    args: Vec<String>,
+    env: Vec<(String, String)>,
+}
+
+// exec 插件输出的 ExecCredential
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct ExecStatus {
+    token: String,
+    client_certificate_data: String,
+    client_key_data: String,
+    expiration_timestamp: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ExecCredential {
+    status: ExecStatus,
+}
+
+// 插件给出的凭据在过期前重复使用，不必每次检查都运行一次
+static EXEC_CREDENTIALS: LazyLock<Mutex<HashMap<ExecPlugin, ExecStatus>>> =
+    LazyLock::new(Default::default);
+
+fn parse_kubeconfig(text: &str, dir: &Path) -> Result<Kubeconfig> {
+    let root = yaml::parse(text)?;
+    let list = |key: &str| root.get(key).map_or(&[][..], |n| n.items.as_slice());
+    let find = |key: &str, name: &str| {
+        list(key)
+            .iter()
+            .find(|item| item.str(&["name"]) == Some(name))
+    };
+    let current = root.str(&["current-context"]).unwrap_or_default();
+    let context = find("contexts", current)
+        .or_else(|| list("contexts").first())
+        .context(tr!("kubeconfig 中没有上下文", "No context in kubeconfig"))?;
+    let field = |node: Option<&yaml::Node>, path: &[&str]| {
+        node.and_then(|n| n.str(path))
+            .unwrap_or_default()
+            .to_string()
+    };
+    // 相对路径相对于 kubeconfig 所在的目录
+    let file = |node: Option<&yaml::Node>, key: &str| match field(node, &[key]) {
+        path if path.is_empty() => path,
+        path => dir.join(expand_home(&path)).to_string_lossy().into_owned(),
+    };
+    let cluster_name = field(Some(context), &["context", "cluster"]);
+    let cluster = find("clusters", &cluster_name).and_then(|c| c.get("cluster"));
+    let user_name = field(Some(context), &["context", "user"]);
+    let user = find("users", &user_name).and_then(|u| u.get("user"));
+
+    // 旧的 auth-provider（gcp、oidc）只使用已经写在配置中的令牌，不负责刷新
+    let provider = user.and_then(|u| u.get("auth-provider"));
+    let token = [
+        field(user, &["token"]),
+        field(provider, &["config", "access-token"]),
+        field(provider, &["config", "id-token"]),
+    ]
+    .into_iter()
+    .find(|t| !t.is_empty())
+    .unwrap_or_default();
+    let exec = user.and_then(|u| u.get("exec")).map(|exec| {
+        let command = field(Some(exec), &["command"]);
+        ExecPlugin {
+            api_version: field(Some(exec), &["apiVersion"]),
+            // 带路径的相对命令相对于 kubeconfig 所在的目录，只有名字时在 PATH 中查找
+            command: if command.contains(['/', '\\']) {
+                dir.join(&command).to_string_lossy().into_owned()
+            } else {
+                command
+            },
+            args: exec.get("args").map_or(Vec::new(), |args| {
+                args.items.iter().filter_map(|a| a.value.clone()).collect()
+            }),
+            env: exec.get("env").map_or(Vec::new(), |env| {
+                env.items
+                    .iter()
+                    .map(|e| (field(Some(e), &["name"]), field(Some(e), &["value"])))
+                    .collect()
+            }),
+        }
+    });
+    let config = Kubeconfig {
+        server: field(cluster, &["server"]),
+        namespace: field(Some(context), &["context", "namespace"]),
+        ca_data: field(cluster, &["certificate-authority-data"]),
+        ca_file: file(cluster, "certificate-authority"),
+        insecure: field(cluster, &["insecure-skip-tls-verify"]) == "true",
+        token,
+        token_file: file(user, "tokenFile"),
+        client_cert_data: field(user, &["client-certificate-data"]),
+        client_cert_file: file(user, "client-certificate"),
+        client_key_data: field(user, &["client-key-data"]),
+        client_key_file: file(user, "client-key"),
+        exec,
+    };
+    if config.server.is_empty() {
+        bail!(tr!(
+            "kubeconfig 中找不到集群 {} 的地址",
+            "No server for cluster {} in kubeconfig",
+            cluster_name
+        ));
+    }
+    Ok(config)
+}
+
+fn decode(data: &str) -> Result<Vec<u8>> {
+    Ok(base64::engine::general_purpose::STANDARD.decode(data.trim())?)
+}
+
+// 证书优先使用内嵌的 base64 数据，其次读取文件
+async fn pem(data: &str, file: &str) -> Result<Option<Vec<u8>>> {
+    if !data.is_empty() {
+        return decode(data).map(Some);
+    }
+    if file.is_empty() {
+        return Ok(None);
+    }
+    let bytes = tokio::fs::read(file)
+        .await
+        .with_context(|| tr!("无法读取 {}", "Cannot read {}", file))?;
+    Ok(Some(bytes))
+}
+
+// 运行 exec 插件取得凭据
+async fn exec_credential(plugin: &ExecPlugin, timeout: Duration) -> Result<ExecStatus> {
+    let cached = EXEC_CREDENTIALS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(plugin)
+        .cloned();
+    // 离过期不到一分钟时重新获取
+    if let Some(status) = cached.filter(|s| {
+        s.expiration_timestamp
+            .is_some_and(|t| t - chrono::Duration::minutes(1) > Utc::now())
+    }) {
+        return Ok(status);
+    }
+    let info = serde_json::json!({
+        "apiVersion": plugin.api_version,
+        "kind": "ExecCredential",
+        "spec": { "interactive": false },
+    });
+    let mut cmd = tokio::process::Command::new(&plugin.command);
+    cmd.args(&plugin.args)
+        .envs(plugin.env.iter().map(|(k, v)| (k, v)))
+        .env("KUBERNETES_EXEC_INFO", info.to_string())
+        .stdin(Stdio::null())
+        .kill_on_drop(true);
+    let output = tokio::time::timeout(timeout, cmd.output())
+        .await
+        .map_err(|_| {
+            anyhow!(tr!(
+                "凭据插件 {} 超时",
+                "Credential plugin {} timed out",
+                plugin.command
+            ))
+        })?
+        .with_context(|| tr!("无法运行 {}", "Cannot run {}", plugin.command))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        bail!(tr!(
+            "凭据插件 {} 失败: {}",
+            "Credential plugin {} failed: {}",
+            plugin.command,
+            stderr.trim().lines().last().unwrap_or_default()
+        ));
+    }
+    let credential: ExecCredential = serde_json::from_slice(&output.stdout).context(tr!(
+        "凭据插件的输出不是 ExecCredential",
+        "The credential plugin did not print an ExecCredential"
+    ))?;
+    if credential.status.expiration_timestamp.is_some() {
+        EXEC_CREDENTIALS
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(plugin.clone(), credential.status.clone());
+    }
+    Ok(credential.status)
+}
+
+// 按检查设置和 kubeconfig 建立的客户端
+struct Api {
+    client: reqwest::Client,
+    server: String,
+    token: String,
+    namespace: String,
+}
+
+async fn client(check: &KubernetesCheck, timeout: Duration) -> Result<Api> {
+    let config = match check.kubeconfig_path() {
+        Some(path) => {
+            let text = tokio::fs::read_to_string(&path)
+                .await
+                .with_context(|| tr!("无法读取 {}", "Cannot read {}", path))?;
+            let dir = Path::new(&path).parent().unwrap_or(Path::new(""));
+            parse_kubeconfig(&text, dir)?
+        }
+        None => Kubeconfig::default(),
+    };
+    let mut builder = reqwest::Client::builder()
+        .timeout(timeout)
+        .proxy(crate::proxy::global())
+        .danger_accept_invalid_certs(check.accept_invalid_certs || config.insecure);
+    if let Some(ca) = pem(&config.ca_data, &config.ca_file).await? {
+        builder = builder.add_root_certificate(reqwest::Certificate::from_pem(&ca)?);
+    }
+    let mut token = match check.token.trim() {
+        "" => config.token.clone(),
+        token => token.to_string(),
+    };
+    if token.is_empty() && !config.token_file.is_empty() {
+        token = tokio::fs::read_to_string(&config.token_file)
+            .await
+            .with_context(|| tr!("无法读取 {}", "Cannot read {}", config.token_file))?
+            .trim()
+            .to_string();
+    }
+    let mut identity = match (
+        pem(&config.client_cert_data, &config.client_cert_file).await?,
+        pem(&config.client_key_data, &config.client_key_file).await?,
+    ) {
+        (Some(cert), Some(key)) => Some((cert, key)),
+        _ => None,
+    };
+    if token.is_empty() && identity.is_none() {
+        if let Some(plugin) = &config.exec {
+            let status = exec_credential(plugin, timeout).await?;
+            token = status.token;
+            // 插件给出的证书直接是PEM
+            if !status.client_certificate_data.is_empty() {
+                identity = Some((
+                    status.client_certificate_data.into_bytes(),
+                    status.client_key_data.into_bytes(),
+                ));
+            }
+        }
+    }
+    if let (true, Some((cert, key))) = (token.is_empty(), identity) {
+        let identity = reqwest::Identity::from_pkcs8_pem(&cert, &key).context(tr!(
+            "客户端证书的私钥需要是 PKCS#8 格式，也可以改用令牌",
+            "The client key must be PKCS#8; a token can be used instead"
+        ))?;
+        builder = builder.identity(identity);
+    }
+    let server = match check.server.trim() {
+        "" => config.server,
+        server => server.to_string(),
+    };
+    let namespace = match (check.namespace.trim(), config.namespace.as_str()) {
+        ("", "") => "default".to_string(),
+        ("", namespace) => namespace.to_string(),
+        (namespace, _) => namespace.to_string(),
+    };
+    Ok(Api {
+        client: builder.build()?,
+        server: server.trim_end_matches('/').to_string(),
+        token,
+        namespace,
+    })
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct DeploymentSpec {
+    replicas: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct DeploymentStatus {
+    replicas: u64,
+    ready_replicas: u64,
+    updated_replicas: u64,
+    available_replicas: u64,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Deployment {
+    spec: DeploymentSpec,
+    status: DeploymentStatus,
+}
+
+impl Deployment {
+    fn outcome(&self, name: &str) -> CheckOutcome {
+        // 没有写 replicas 时默认为1
+        let desired = self.spec.replicas.unwrap_or(1);
+        let status = &self.status;
+        let state = if status.ready_replicas >= desired && status.updated_replicas >= desired {
+            ServerStatus::Online
+        } else if status.ready_replicas == 0 {
+            ServerStatus::Offline
+        } else {
+            ServerStatus::Degraded
+        };
+        let mut outcome = CheckOutcome::new(
+            state,
+            tr!(
+                "{}: {}/{} 个副本就绪",
+                "{}: {}/{} replicas ready",
+                name,
+                status.ready_replicas,
+                desired
+            ),
+        );
+        for (key, value) in [
+            ("desired", desired),
+            ("current", status.replicas),
+            ("ready", status.ready_replicas),
+            ("updated", status.updated_replicas),
+            ("available", status.available_replicas),
+        ] {
+            outcome.fields.insert(key.to_string(), value.to_string());
+        }
+        outcome
+    }
+}
+
+impl Api {
+    async fn get(&self, path: &str) -> Result<reqwest::Response> {
+        let mut request = self.client.get(format!("{}/{}", self.server, path));
+        if !self.token.is_empty() {
+            request = request.bearer_auth(&self.token);
+        }
+        Ok(request.send().await?)
+    }
+}
+
+async fn run(check: &KubernetesCheck, timeout: Duration) -> Result<CheckOutcome> {
+    let api = client(check, timeout).await?;
+    let mut resp = api.get("readyz").await?;
+    if resp.status() == reqwest::StatusCode::NOT_FOUND {
+        resp = api.get("healthz").await?;
+    }
+    let code = resp.status();
+    let body = resp.text().await.unwrap_or_default();
+    if !code.is_success() {
+        return Ok(CheckOutcome::new(
+            ServerStatus::Error(code.as_u16()),
+            tr!(
+                "API 未就绪 ({}): {}",
+                "API not ready ({}): {}",
+                code.as_u16(),
+                body.lines().next().unwrap_or_default()
+            ),
+        ));
+    }
+
+    let deployment = check.deployment.trim();
+    if deployment.is_empty() {
+        return Ok(CheckOutcome::new(
+            ServerStatus::Online,
+            tr!("API 就绪: {}", "API ready: {}", body.trim()),
+        ));
+    }
+    let path = format!(
+        "apis/apps/v1/namespaces/{}/deployments/{}",
+        uri_encode(&api.namespace),
+        uri_encode(deployment)
+    );
+    let resp = api.get(&path).await?;
+    let code = resp.status();
+    if !code.is_success() {
+        return Ok(CheckOutcome::new(
+            ServerStatus::Error(code.as_u16()),
+            tr!(
+                "查询 Deployment {} 返回 {}",
+                "Deployment {} returned {}",
+                deployment,
+                code.as_u16()
+            ),
+        ));
+    }
+    let deployment_info: Deployment = resp.json().await?;
+    Ok(deployment_info.outcome(deployment))
+}
+
+// API 连不上为离线，未就绪、认证失败或 Deployment 不存在为错误
+pub async fn check(check: &KubernetesCheck, timeout: Duration) -> CheckOutcome {
+    match run(check, timeout).await {
+        Ok(outcome) => outcome,
+        Err(e) => match e.downcast_ref::<reqwest::Error>() {
+            Some(err) if err.is_timeout() => CheckOutcome::timeout(e.to_string()),
+            Some(err) if err.is_connect() => {
+                CheckOutcome::new(ServerStatus::Offline, e.to_string())
+            }
+            _ => CheckOutcome::new(ServerStatus::Error(1), format!("{:#}", e)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn reads_current_context_from_kubeconfig() {
+        let text = "\
+apiVersion: v1
+clusters:
+- cluster:
+    certificate-authority-data: Q0E=
+    server: https://10.0.0.1:6443
+  name: prod
+- cluster:
+    insecure-skip-tls-verify: true
+    server: \"https://127.0.0.1:41234\"
+  name: kind-dev
+contexts:
+- context:
+    cluster: prod
+    user: admin
+  name: prod
+- context:
+    cluster: kind-dev
+    namespace: web
+    user: kind-dev
+  name: kind-dev
+- context:
+    cluster: prod
+    user: eks
+  name: eks
+current-context: kind-dev
+kind: Config
+users:
+- name: admin
+  user:
+    token: prod-token
+- name: kind-dev
+  user:
+    client-certificate: certs/dev.crt
+    client-key: /etc/dev.key
+    token: \"dev #token\" # 注释
+- name: eks
+  user:
+    exec:
+      apiVersion: client.authentication.k8s.io/v1beta1
+      args:
+      - eks
+      - get-token
+      command: aws
+      env:
+      - name: AWS_PROFILE
+        value: prod
+";
+        let dir = Path::new("/home/me/.kube");
+        assert_eq!(
+            parse_kubeconfig(text, dir).unwrap(),
+            Kubeconfig {
+                server: "https://127.0.0.1:41234".to_string(),
+                namespace: "web".to_string(),
+                insecure: true,
+                token: "dev #token".to_string(),
+                client_cert_file: dir.join("certs/dev.crt").to_string_lossy().into_owned(),
+                client_key_file: "/etc/dev.key".to_string(),
+                ..Default::default()
+            }
+        );
+
+        let eks = parse_kubeconfig(
+            &text.replace("current-context: kind-dev", "current-context: eks"),
+            dir,
+        )
+        .unwrap();
+        assert_eq!(eks.server, "https://10.0.0.1:6443");
+        assert_eq!(eks.ca_data, "Q0E=");
+        assert_eq!(
+            eks.exec,
+            Some(ExecPlugin {
+                api_version: "client.authentication.k8s.io/v1beta1".to_string(),
+                command: "aws".to_string(),
+                args: vec!["eks".to_string(), "get-token".to_string()],
+                env: vec![("AWS_PROFILE".to_string(), "prod".to_string())],
+            })
+        );
+        assert!(parse_kubeconfig("apiVersion: v1\n", dir).is_err());
+    }
+
+    #[tokio::test]
+    async fn checks_readyz_and_deployment_replicas() {
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/readyz"))
+            .and(header("authorization", "Bearer secret"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/apis/apps/v1/namespaces/shop/deployments/web"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "spec": { "replicas": 3 },
+                "status": { "replicas": 3, "readyReplicas": 2, "updatedReplicas": 3, "availableReplicas": 2 },
+            })))
+            .mount(&mock)
+            .await;
+        let mut k8s = KubernetesCheck {
+            server: mock.uri(),
+            token: "secret".to_string(),
+            ..Default::default()
+        };
+        let timeout = Duration::from_secs(5);
+
+        let outcome = check(&k8s, timeout).await;
+        assert_eq!(outcome.status, ServerStatus::Online);
+
+        k8s.namespace = "shop".to_string();
+        k8s.deployment = "web".to_string();
+        let outcome = check(&k8s, timeout).await;
+        assert_eq!(outcome.status, ServerStatus::Degraded);
+        assert_eq!(outcome.fields["ready"], "2");
+        assert_eq!(outcome.fields["desired"], "3");
+
+        k8s.deployment = "missing".to_string();
+        assert_eq!(check(&k8s, timeout).await.status, ServerStatus::Error(404));
+    }
+
+    // 令牌由 exec 插件给出，没有填写命名空间时使用上下文的命名空间
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn uses_exec_plugin_and_context_namespace() {
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/readyz"))
+            .and(header("authorization", "Bearer from-plugin"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("ok"))
+            .mount(&mock)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/apis/apps/v1/namespaces/shop/deployments/web"))
+            .and(header("authorization", "Bearer from-plugin"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "spec": { "replicas": 1 },
+                "status": { "replicas": 1, "readyReplicas": 1, "updatedReplicas": 1 },
+            })))
+            .mount(&mock)
+            .await;
+        let dir = TempDir::new("kube");
+        let kubeconfig = dir.join("config");
+        std::fs::write(
+            &kubeconfig,
+            format!(
+                "\
+clusters:
+- cluster:
+    server: {}
+  name: test
+contexts:
+- context:
+    cluster: test
+    namespace: shop
+    user: test
+  name: test
+current-context: test
+users:
+- name: test
+  user:
+    exec:
+      command: sh
+      args:
+      - -c
+      - echo '{{\"status\":{{\"token\":\"from-plugin\"}}}}'
+",
+                mock.uri()
+            ),
+        )
+        .unwrap();
+        let k8s = KubernetesCheck {
+            kubeconfig: kubeconfig.to_string_lossy().into_owned(),
+            deployment: "web".to_string(),
+            ..Default::default()
+        };
+        let outcome = check(&k8s, Duration::from_secs(5)).await;
+        assert_eq!(outcome.status, ServerStatus::Online, "{}", outcome.detail);
+    }
+}
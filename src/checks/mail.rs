@@ -0,0 +1,467 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_native_tls::native_tls;
+
+use super::target::Target;
+use super::CheckOutcome;
+use crate::address;
+use crate::i18n::tr;
+use crate::ServerStatus;
+
+// 邮件服务检查：读取欢迎语，可选发送 EHLO/NOOP（IMAP 为 CAPABILITY/NOOP，POP3 为 CAPA），
+// 可选要求服务器提供 STARTTLS 并真正完成一次TLS握手。不登录
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum MailProtocol {
+    #[default]
+    Smtp,
+    Imap,
+    Pop3,
+}
+
+impl MailProtocol {
+    pub const ALL: [MailProtocol; 3] = [MailProtocol::Smtp, MailProtocol::Imap, MailProtocol::Pop3];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MailProtocol::Smtp => "SMTP",
+            MailProtocol::Imap => "IMAP",
+            MailProtocol::Pop3 => "POP3",
+        }
+    }
+
+    // 明文（STARTTLS）端口和直接TLS的端口
+    pub fn default_port(&self, implicit_tls: bool) -> u16 {
+        match (self, implicit_tls) {
+            (MailProtocol::Smtp, false) => 25,
+            (MailProtocol::Smtp, true) => 465,
+            (MailProtocol::Imap, false) => 143,
+            (MailProtocol::Imap, true) => 993,
+            (MailProtocol::Pop3, false) => 110,
+            (MailProtocol::Pop3, true) => 995,
+        }
+    }
+
+    fn starttls_name(&self) -> &'static str {
+        match self {
+            MailProtocol::Pop3 => "STLS",
+            _ => "STARTTLS",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MailCheck {
+    pub protocol: MailProtocol,
+    pub port: u16,
+    // 连接后直接TLS握手（465/993/995端口）
+    pub implicit_tls: bool,
+    // 欢迎语之后再发送 EHLO/NOOP 等命令
+    pub commands: bool,
+    // 服务器没有提供 STARTTLS 时显示为降级
+    pub require_starttls: bool,
+    pub accept_invalid_certs: bool,
+    // 校验证书用的主机名，留空时用服务器地址；按IP地址连接时填写证书上的名称
+    pub tls_name: String,
+}
+
+impl Default for MailCheck {
+    fn default() -> Self {
+        Self {
+            protocol: MailProtocol::Smtp,
+            port: 25,
+            implicit_tls: false,
+            commands: true,
+            require_starttls: false,
+            accept_invalid_certs: false,
+            tls_name: String::new(),
+        }
+    }
+}
+
+impl MailCheck {
+    pub fn endpoint(&self, host: &str) -> String {
+        let scheme = self.protocol.label().to_lowercase();
+        let suffix = if self.implicit_tls { "s" } else { "" };
+        address::build_url(&format!("{}{}", scheme, suffix), host, self.port)
+    }
+
+    fn tls_name<'a>(&'a self, host: &'a str) -> &'a str {
+        match self.tls_name.trim() {
+            "" => host,
+            name => name,
+        }
+    }
+}
+
+const CLIENT_NAME: &str = "server_check";
+// 单行回复的最大长度，防止对方一直不发换行
+const MAX_LINE_BYTES: usize = 8192;
+
+async fn read_line<S: AsyncRead + Unpin>(reader: &mut BufReader<S>) -> Result<String> {
+    let mut line = String::new();
+    let read = (&mut *reader)
+        .take(MAX_LINE_BYTES as u64)
+        .read_line(&mut line)
+        .await?;
+    if read == 0 {
+        bail!(tr!("连接被关闭", "Connection closed"));
+    }
+    Ok(line.trim_end().to_string())
+}
+
+// 一条完整的回复，ok 为肯定回复
+struct Reply {
+    ok: bool,
+    lines: Vec<String>,
+}
+
+impl Reply {
+    fn text(&self) -> String {
+        self.lines.last().cloned().unwrap_or_default()
+    }
+
+    fn into_result(self) -> Result<Reply> {
+        if !self.ok {
+            bail!(self.text());
+        }
+        Ok(self)
+    }
+}
+
+struct Session<S> {
+    reader: BufReader<S>,
+    protocol: MailProtocol,
+    next_tag: u32,
+}
+
+impl<S: AsyncRead + AsyncWrite + Unpin> Session<S> {
+    fn new(stream: S, protocol: MailProtocol) -> Self {
+        Self {
+            reader: BufReader::new(stream),
+            protocol,
+            next_tag: 0,
+        }
+    }
+
+    // SMTP 的多行回复以 "250-" 续行；POP3 的多行回复 multiline 为true时读到 "." 为止
+    async fn reply(&mut self, tag: Option<&str>, multiline: bool) -> Result<Reply> {
+        let mut lines = Vec::new();
+        loop {
+            let line = read_line(&mut self.reader).await?;
+            match self.protocol {
+                MailProtocol::Smtp => {
+                    let done = line.as_bytes().get(3) != Some(&b'-');
+                    let ok = line.starts_with('2') || line.starts_with('3');
+                    lines.push(line);
+                    if done {
+                        return Ok(Reply { ok, lines });
+                    }
+                }
+                MailProtocol::Imap => {
+                    let prefix = tag.map(|t| format!("{} ", t));
+                    match prefix.as_deref().and_then(|p| line.strip_prefix(p)) {
+                        Some(status) => {
+                            let ok = status.starts_with("OK");
+                            lines.push(line);
+                            return Ok(Reply { ok, lines });
+                        }
+                        None if tag.is_none() => {
+                            let ok = line.starts_with("* OK") || line.starts_with("* PREAUTH");
+                            return Ok(Reply {
+                                ok,
+                                lines: vec![line],
+                            });
+                        }
+                        None => lines.push(line),
+                    }
+                }
+                MailProtocol::Pop3 => {
+                    if lines.is_empty() {
+                        let ok = line.starts_with("+OK");
+                        lines.push(line);
+                        if !ok || !multiline {
+                            return Ok(Reply { ok, lines });
+                        }
+                    } else if line == "." {
+                        return Ok(Reply { ok: true, lines });
+                    } else {
+                        lines.push(line);
+                    }
+                }
+            }
+        }
+    }
+
+    // 发送一条命令并读取回复，IMAP 命令自动加上标签
+    async fn command(&mut self, command: &str, multiline: bool) -> Result<Reply> {
+        let tag = (self.protocol == MailProtocol::Imap).then(|| {
+            self.next_tag += 1;
+            format!("a{}", self.next_tag)
+        });
+        let line = match &tag {
+            Some(tag) => format!("{} {}\r\n", tag, command),
+            None => format!("{}\r\n", command),
+        };
+        self.reader.get_mut().write_all(line.as_bytes()).await?;
+        self.reply(tag.as_deref(), multiline).await?.into_result()
+    }
+
+    async fn greeting(&mut self) -> Result<String> {
+        let reply = self.reply(None, false).await?.into_result()?;
+        let text = reply.lines.first().cloned().unwrap_or_default();
+        // 去掉前面的状态码，例如 "220 "、"* OK "、"+OK "
+        let text = text.strip_prefix("* ").unwrap_or(&text);
+        Ok(text
+            .split_once(' ')
+            .map(|(_, rest)| rest)
+            .unwrap_or(text)
+            .to_string())
+    }
+
+    // 服务器声明的扩展，大写
+    async fn capabilities(&mut self) -> Result<Vec<String>> {
+        let words = |lines: &[String], skip: usize| -> Vec<String> {
+            lines
+                .iter()
+                .skip(skip)
+                .flat_map(|line| line.split_whitespace().map(str::to_uppercase))
+                .collect()
+        };
+        Ok(match self.protocol {
+            MailProtocol::Smtp => {
+                let reply = self
+                    .command(&format!("EHLO {}", CLIENT_NAME), false)
+                    .await?;
+                // 去掉每行的 "250-"，第一行是服务器名
+                let lines: Vec<String> = reply
+                    .lines
+                    .iter()
+                    .map(|l| l.get(4..).unwrap_or_default().to_string())
+                    .collect();
+                words(&lines, 1)
+            }
+            MailProtocol::Imap => {
+                let reply = self.command("CAPABILITY", false).await?;
+                let lines: Vec<String> = reply
+                    .lines
+                    .iter()
+                    .filter_map(|l| l.strip_prefix("* CAPABILITY").map(str::to_string))
+                    .collect();
+                words(&lines, 0)
+            }
+            MailProtocol::Pop3 => words(&self.command("CAPA", true).await?.lines, 1),
+        })
+    }
+
+    // POP3 在登录前不接受 NOOP
+    async fn noop(&mut self) -> Result<()> {
+        if self.protocol != MailProtocol::Pop3 {
+            self.command("NOOP", false).await?;
+        }
+        Ok(())
+    }
+
+    async fn quit(&mut self) {
+        let command = match self.protocol {
+            MailProtocol::Imap => "a0 LOGOUT\r\n",
+            _ => "QUIT\r\n",
+        };
+        let _ = self.reader.get_mut().write_all(command.as_bytes()).await;
+    }
+}
+
+// 欢迎语和服务器是否提供 STARTTLS（没有查询扩展时为空）
+async fn converse<S: AsyncRead + AsyncWrite + Unpin>(
+    session: &mut Session<S>,
+    check: &MailCheck,
+) -> Result<(String, Option<bool>)> {
+    let greeting = session.greeting().await?;
+    let mut starttls = None;
+    if check.commands || check.require_starttls {
+        let capabilities = session.capabilities().await?;
+        starttls = Some(
+            capabilities
+                .iter()
+                .any(|c| c == check.protocol.starttls_name()),
+        );
+    }
+    if check.commands {
+        session.noop().await?;
+    }
+    Ok((greeting, starttls))
+}
+
+fn connector(check: &MailCheck) -> Result<tokio_native_tls::TlsConnector> {
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(check.accept_invalid_certs)
+        .build()?;
+    Ok(tokio_native_tls::TlsConnector::from(connector))
+}
+
+async fn probe(stream: TcpStream, host: &str, check: &MailCheck) -> Result<(ServerStatus, String)> {
+    if check.implicit_tls {
+        let stream = connector(check)?
+            .connect(check.tls_name(host), stream)
+            .await
+            .context(tr!("TLS握手失败", "TLS handshake failed"))?;
+        let mut session = Session::new(stream, check.protocol);
+        let (greeting, _) = converse(&mut session, check).await?;
+        session.quit().await;
+        return Ok((ServerStatus::Online, greeting));
+    }
+
+    let mut session = Session::new(stream, check.protocol);
+    let (greeting, starttls) = converse(&mut session, check).await?;
+    if !check.require_starttls {
+        session.quit().await;
+        return Ok((ServerStatus::Online, greeting));
+    }
+    if starttls != Some(true) {
+        session.quit().await;
+        return Ok((
+            ServerStatus::Degraded,
+            tr!(
+                "服务器没有提供 {}: {}",
+                "Server does not offer {}: {}",
+                check.protocol.starttls_name(),
+                greeting
+            ),
+        ));
+    }
+    session
+        .command(check.protocol.starttls_name(), false)
+        .await?;
+    let stream = session.reader.into_inner();
+    let stream = connector(check)?
+        .connect(check.tls_name(host), stream)
+        .await
+        .context(tr!("STARTTLS 握手失败", "STARTTLS handshake failed"))?;
+    Session::new(stream, check.protocol).quit().await;
+    Ok((ServerStatus::Online, format!("{} · STARTTLS", greeting)))
+}
+
+// 连不上端口为离线，欢迎语或命令得到否定回复、TLS握手失败为错误
+pub async fn check(target: Target<'_>, check: &MailCheck, timeout: Duration) -> CheckOutcome {
+    let run = async {
+        let stream = target
+            .connect(check.port)
+            .await
+            .map_err(|e| CheckOutcome::new(ServerStatus::Offline, e.to_string()))?;
+        probe(stream, target.name(), check)
+            .await
+            .map_err(|e| CheckOutcome::new(ServerStatus::Error(1), format!("{:#}", e)))
+    };
+    match tokio::time::timeout(timeout, run).await {
+        Ok(Ok((status, detail))) => CheckOutcome::new(status, detail),
+        Ok(Err(outcome)) => outcome,
+        Err(_) => CheckOutcome::timeout(tr!(
+            "{} 检查超时",
+            "{} check timed out",
+            check.protocol.label()
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    // 按命令开头回复固定内容的假服务器，没有匹配的命令时关闭连接
+    async fn fake_server(greeting: &'static str, replies: &'static [(&str, &str)]) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let (socket, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut reader = BufReader::new(socket);
+                    reader
+                        .get_mut()
+                        .write_all(greeting.as_bytes())
+                        .await
+                        .unwrap();
+                    while let Ok(line) = read_line(&mut reader).await {
+                        let Some((_, reply)) = replies.iter().find(|(c, _)| line.contains(c))
+                        else {
+                            break;
+                        };
+                        let _ = reader.get_mut().write_all(reply.as_bytes()).await;
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn smtp_without_starttls_is_degraded_when_required() {
+        let port = fake_server(
+            "220 mail.example.com ESMTP Postfix\r\n",
+            &[
+                (
+                    "EHLO",
+                    "250-mail.example.com\r\n250-PIPELINING\r\n250 SIZE 10240000\r\n",
+                ),
+                ("NOOP", "250 2.0.0 Ok\r\n"),
+            ],
+        )
+        .await;
+        let timeout = Duration::from_secs(5);
+        let mut mail = MailCheck {
+            port,
+            ..Default::default()
+        };
+        let outcome = check(Target::new("127.0.0.1"), &mail, timeout).await;
+        assert_eq!(outcome.status, ServerStatus::Online);
+        assert_eq!(outcome.detail, "mail.example.com ESMTP Postfix");
+
+        mail.require_starttls = true;
+        let outcome = check(Target::new("127.0.0.1"), &mail, timeout).await;
+        assert_eq!(outcome.status, ServerStatus::Degraded);
+    }
+
+    #[tokio::test]
+    async fn imap_capabilities_and_rejections() {
+        let port = fake_server(
+            "* OK [CAPABILITY IMAP4rev1] Dovecot ready.\r\n",
+            &[
+                (
+                    "CAPABILITY",
+                    "* CAPABILITY IMAP4rev1 STARTTLS LOGINDISABLED\r\na1 OK done\r\n",
+                ),
+                ("NOOP", "a2 OK NOOP completed\r\n"),
+                ("STARTTLS", "a3 NO not now\r\n"),
+            ],
+        )
+        .await;
+        let timeout = Duration::from_secs(5);
+        let mut mail = MailCheck {
+            protocol: MailProtocol::Imap,
+            port,
+            ..Default::default()
+        };
+        let outcome = check(Target::new("127.0.0.1"), &mail, timeout).await;
+        assert_eq!(outcome.status, ServerStatus::Online);
+
+        // 声明了 STARTTLS，但命令被拒绝
+        mail.require_starttls = true;
+        let outcome = check(Target::new("127.0.0.1"), &mail, timeout).await;
+        assert_eq!(outcome.status, ServerStatus::Error(1));
+        assert!(outcome.detail.contains("not now"));
+
+        let port = fake_server("-ERR too many connections\r\n", &[]).await;
+        let pop3 = MailCheck {
+            protocol: MailProtocol::Pop3,
+            port,
+            ..Default::default()
+        };
+        let outcome = check(Target::new("127.0.0.1"), &pop3, timeout).await;
+        assert_eq!(outcome.status, ServerStatus::Error(1));
+        assert_eq!(outcome.detail, "-ERR too many connections");
+    }
+}
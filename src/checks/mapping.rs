@@ -0,0 +1,209 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use super::CheckOutcome;
+use crate::history::Exclusion;
+use crate::i18n::tr;
+use crate::ServerStatus;
+
+// 检查结果满足的条件
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum RuleCondition {
+    // HTTP状态码或命令退出码
+    StatusCode(u16),
+    // 说明（错误原因、命令输出等）包含指定文字，不区分大小写
+    DetailContains(String),
+    // 离线，包括超时
+    Offline,
+    Timeout,
+}
+
+impl Default for RuleCondition {
+    fn default() -> Self {
+        RuleCondition::StatusCode(503)
+    }
+}
+
+impl RuleCondition {
+    pub const ALL: [RuleCondition; 4] = [
+        RuleCondition::StatusCode(503),
+        RuleCondition::DetailContains(String::new()),
+        RuleCondition::Offline,
+        RuleCondition::Timeout,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RuleCondition::StatusCode(_) => tr!("状态码为", "Status code is"),
+            RuleCondition::DetailContains(_) => tr!("说明包含", "Detail contains"),
+            RuleCondition::Offline => tr!("离线", "Offline"),
+            RuleCondition::Timeout => tr!("超时", "Timed out"),
+        }
+    }
+
+    fn matches(&self, outcome: &CheckOutcome) -> bool {
+        match self {
+            RuleCondition::StatusCode(code) => outcome.status == ServerStatus::Error(*code),
+            RuleCondition::DetailContains(text) => {
+                !text.trim().is_empty()
+                    && outcome
+                        .detail
+                        .to_lowercase()
+                        .contains(&text.trim().to_lowercase())
+            }
+            RuleCondition::Offline => outcome.status == ServerStatus::Offline,
+            RuleCondition::Timeout => outcome.timed_out,
+        }
+    }
+}
+
+// 改写后的结果
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleResult {
+    #[default]
+    Online,
+    Degraded,
+    Offline,
+    // 保留原状态，但显示为维护中
+    Maintenance,
+}
+
+impl RuleResult {
+    pub const ALL: [RuleResult; 4] = [
+        RuleResult::Online,
+        RuleResult::Degraded,
+        RuleResult::Offline,
+        RuleResult::Maintenance,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RuleResult::Online => tr!("在线", "Online"),
+            RuleResult::Degraded => tr!("降级", "Degraded"),
+            RuleResult::Offline => tr!("离线", "Offline"),
+            RuleResult::Maintenance => tr!("维护中", "Maintenance"),
+        }
+    }
+}
+
+// 服务器的状态映射规则，按顺序匹配，第一条满足条件的生效
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatusRule {
+    pub condition: RuleCondition,
+    pub result: RuleResult,
+    // 只在该时段内生效，None 表示始终生效
+    pub during: Option<Exclusion>,
+}
+
+// 按规则改写检查结果，返回生效的规则
+pub fn apply<'a>(
+    rules: &'a [StatusRule],
+    outcome: &mut CheckOutcome,
+    now: DateTime<Local>,
+) -> Option<&'a StatusRule> {
+    let rule = rules.iter().find(|rule| {
+        rule.during.as_ref().is_none_or(|w| w.contains(now)) && rule.condition.matches(outcome)
+    })?;
+    let original = outcome.status.to_string();
+    match rule.result {
+        RuleResult::Online => outcome.status = ServerStatus::Online,
+        RuleResult::Degraded => outcome.status = ServerStatus::Degraded,
+        RuleResult::Offline => outcome.status = ServerStatus::Offline,
+        RuleResult::Maintenance => outcome.maintenance = true,
+    }
+    if rule.result != RuleResult::Offline {
+        outcome.timed_out = false;
+    }
+    let note = tr!(
+        "按规则由 {} 视为{}",
+        "mapped from {} to {} by rule",
+        original,
+        rule.result.label()
+    );
+    outcome.detail = if outcome.detail.is_empty() {
+        note
+    } else {
+        format!("{} ({})", outcome.detail, note)
+    };
+    Some(rule)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{NaiveTime, TimeZone};
+
+    fn rule(condition: RuleCondition, result: RuleResult) -> StatusRule {
+        StatusRule {
+            condition,
+            result,
+            during: None,
+        }
+    }
+
+    fn at(hour: u32) -> DateTime<Local> {
+        Local.with_ymd_and_hms(2024, 5, 1, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn maps_status_code_only_inside_window() {
+        let draining = StatusRule {
+            during: Some(Exclusion::Daily {
+                start: NaiveTime::from_hms_opt(2, 0, 0).unwrap(),
+                end: NaiveTime::from_hms_opt(4, 0, 0).unwrap(),
+            }),
+            ..rule(RuleCondition::StatusCode(503), RuleResult::Online)
+        };
+        let rules = [draining];
+
+        let mut outcome = CheckOutcome::new(ServerStatus::Error(503), "");
+        assert!(apply(&rules, &mut outcome, at(3)).is_some());
+        assert_eq!(outcome.status, ServerStatus::Online);
+        assert!(!outcome.detail.is_empty());
+
+        let mut outcome = CheckOutcome::new(ServerStatus::Error(503), "");
+        assert!(apply(&rules, &mut outcome, at(12)).is_none());
+        assert_eq!(outcome.status, ServerStatus::Error(503));
+    }
+
+    #[test]
+    fn first_matching_rule_wins() {
+        let rules = [
+            rule(RuleCondition::StatusCode(500), RuleResult::Degraded),
+            rule(
+                RuleCondition::DetailContains("Upgrade In Progress".into()),
+                RuleResult::Maintenance,
+            ),
+            rule(RuleCondition::Offline, RuleResult::Degraded),
+        ];
+        let mut outcome = CheckOutcome::new(ServerStatus::Offline, "upgrade in progress, retry");
+        let applied = apply(&rules, &mut outcome, at(12)).unwrap();
+        assert_eq!(applied.result, RuleResult::Maintenance);
+        assert!(outcome.maintenance);
+        assert_eq!(outcome.status, ServerStatus::Offline);
+    }
+
+    #[test]
+    fn timeout_condition_clears_timeout_flag() {
+        let rules = [rule(RuleCondition::Timeout, RuleResult::Degraded)];
+        let mut outcome = CheckOutcome::timeout("timed out");
+        apply(&rules, &mut outcome, at(12));
+        assert_eq!(outcome.status, ServerStatus::Degraded);
+        assert!(!outcome.timed_out);
+
+        // 没有超时的离线不匹配
+        let mut outcome = CheckOutcome::new(ServerStatus::Offline, "refused");
+        assert!(apply(&rules, &mut outcome, at(12)).is_none());
+    }
+
+    #[test]
+    fn empty_detail_text_never_matches() {
+        let rules = [rule(
+            RuleCondition::DetailContains("  ".into()),
+            RuleResult::Online,
+        )];
+        let mut outcome = CheckOutcome::new(ServerStatus::Offline, "anything");
+        assert!(apply(&rules, &mut outcome, at(12)).is_none());
+    }
+}
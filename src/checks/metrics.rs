@@ -0,0 +1,299 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use super::CheckOutcome;
+use crate::address;
+use crate::i18n::tr;
+use crate::ServerStatus;
+
+// 主机指标数据来源
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum MetricsSource {
+    NodeExporter,
+    Glances,
+}
+
+impl MetricsSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            MetricsSource::NodeExporter => "node_exporter",
+            MetricsSource::Glances => "Glances",
+        }
+    }
+
+    pub fn default_port(&self) -> u16 {
+        match self {
+            MetricsSource::NodeExporter => 9100,
+            MetricsSource::Glances => 61208,
+        }
+    }
+}
+
+// 抓取 node_exporter / Glances 的 CPU、内存、磁盘使用率，超过阈值显示为降级
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct MetricsCheck {
+    pub source: MetricsSource,
+    // 指标端点，留空时使用 http://ip:默认端口
+    #[serde(default)]
+    pub url: String,
+    pub cpu_threshold: f32,
+    pub mem_threshold: f32,
+    pub disk_threshold: f32,
+}
+
+impl Default for MetricsCheck {
+    fn default() -> Self {
+        Self {
+            source: MetricsSource::NodeExporter,
+            url: String::new(),
+            cpu_threshold: 90.0,
+            mem_threshold: 90.0,
+            disk_threshold: 90.0,
+        }
+    }
+}
+
+impl MetricsCheck {
+    pub fn endpoint(&self, ip: &str) -> String {
+        if !self.url.is_empty() {
+            return self.url.trim_end_matches('/').to_string();
+        }
+        match self.source {
+            MetricsSource::NodeExporter => {
+                format!(
+                    "{}/metrics",
+                    address::build_url("http", ip, self.source.default_port())
+                )
+            }
+            MetricsSource::Glances => address::build_url("http", ip, self.source.default_port()),
+        }
+    }
+}
+
+// 使用率百分比，无法获取的项为None
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HostMetrics {
+    pub cpu: Option<f32>,
+    pub mem: Option<f32>,
+    pub disk: Option<f32>,
+}
+
+// CPU使用率需要两次采样的差值，按端点缓存上一次的 (空闲, 总计) 秒数
+static CPU_SAMPLES: LazyLock<Mutex<HashMap<String, (f64, f64)>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// 解析一行Prometheus文本格式，返回 (指标名, 标签, 值)
+fn parse_line(line: &str) -> Option<(&str, HashMap<&str, &str>, f64)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let (name_and_labels, value) = match line.find('}') {
+        Some(end) => (&line[..=end], line[end + 1..].trim()),
+        None => line.split_once(' ')?,
+    };
+    let value: f64 = value.split_whitespace().next()?.parse().ok()?;
+
+    let mut labels = HashMap::new();
+    let name = match name_and_labels.find('{') {
+        Some(start) => {
+            let body = name_and_labels[start + 1..].trim_end_matches('}');
+            for pair in body.split(',') {
+                if let Some((k, v)) = pair.split_once('=') {
+                    labels.insert(k.trim(), v.trim().trim_matches('"'));
+                }
+            }
+            &name_and_labels[..start]
+        }
+        None => name_and_labels,
+    };
+    Some((name, labels, value))
+}
+
+// 这些文件系统类型不代表真实磁盘
+const IGNORED_FS: &[&str] = &[
+    "tmpfs", "devtmpfs", "overlay", "squashfs", "ramfs", "autofs",
+];
+
+fn parse_node_exporter(text: &str, endpoint: &str) -> HostMetrics {
+    let (mut idle, mut total) = (0.0, 0.0);
+    let (mut mem_total, mut mem_avail) = (None, None);
+    // 挂载点 -> (总大小, 可用)
+    let mut fs: HashMap<String, (f64, f64)> = HashMap::new();
+
+    for (name, labels, value) in text.lines().filter_map(parse_line) {
+        match name {
+            "node_cpu_seconds_total" => {
+                total += value;
+                if matches!(labels.get("mode"), Some(&"idle") | Some(&"iowait")) {
+                    idle += value;
+                }
+            }
+            "node_memory_MemTotal_bytes" => mem_total = Some(value),
+            "node_memory_MemAvailable_bytes" => mem_avail = Some(value),
+            "node_filesystem_size_bytes" | "node_filesystem_avail_bytes" => {
+                if labels.get("fstype").is_some_and(|t| IGNORED_FS.contains(t)) {
+                    continue;
+                }
+                let Some(mount) = labels.get("mountpoint") else {
+                    continue;
+                };
+                let entry = fs.entry(mount.to_string()).or_default();
+                if name == "node_filesystem_size_bytes" {
+                    entry.0 = value;
+                } else {
+                    entry.1 = value;
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let cpu = (total > 0.0).then(|| {
+        let mut samples = CPU_SAMPLES.lock().unwrap();
+        // 第一次采样使用开机以来的平均值
+        let (d_idle, d_total) = match samples.get(endpoint) {
+            Some(&(prev_idle, prev_total)) if total > prev_total => {
+                (idle - prev_idle, total - prev_total)
+            }
+            _ => (idle, total),
+        };
+        samples.insert(endpoint.to_string(), (idle, total));
+        ((1.0 - d_idle / d_total) * 100.0) as f32
+    });
+
+    let mem = match (mem_total, mem_avail) {
+        (Some(t), Some(a)) if t > 0.0 => Some(((1.0 - a / t) * 100.0) as f32),
+        _ => None,
+    };
+
+    let usage = |(size, avail): (f64, f64)| ((1.0 - avail / size) * 100.0) as f32;
+    let disk = match fs.get("/") {
+        Some(&root) if root.0 > 0.0 => Some(usage(root)),
+        _ => fs
+            .values()
+            .filter(|(size, _)| *size > 0.0)
+            .map(|&v| usage(v))
+            .reduce(f32::max),
+    };
+
+    HostMetrics { cpu, mem, disk }
+}
+
+// 每个请求都带上检查的超时，替代共享客户端的默认值
+async fn fetch_node_exporter(
+    client: &reqwest::Client,
+    endpoint: &str,
+    timeout: Duration,
+) -> Result<HostMetrics> {
+    let resp = client
+        .get(endpoint)
+        .timeout(timeout)
+        .send()
+        .await?
+        .error_for_status()?;
+    let text = resp.text().await?;
+    Ok(parse_node_exporter(&text, endpoint))
+}
+
+#[derive(Deserialize)]
+struct GlancesQuicklook {
+    cpu: Option<f32>,
+    mem: Option<f32>,
+}
+
+#[derive(Deserialize)]
+struct GlancesFs {
+    percent: Option<f32>,
+}
+
+async fn fetch_glances(
+    client: &reqwest::Client,
+    base: &str,
+    timeout: Duration,
+) -> Result<HostMetrics> {
+    // 新版Glances使用 /api/4，旧版为 /api/3
+    let mut last_error = anyhow!(tr!("无法访问Glances接口", "Cannot reach the Glances API"));
+    for version in [4, 3] {
+        let quicklook = match client
+            .get(format!("{}/api/{}/quicklook", base, version))
+            .timeout(timeout)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+        {
+            Ok(resp) => resp.json::<GlancesQuicklook>().await?,
+            Err(e) => {
+                last_error = e.into();
+                continue;
+            }
+        };
+        let fs: Vec<GlancesFs> = client
+            .get(format!("{}/api/{}/fs", base, version))
+            .timeout(timeout)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        return Ok(HostMetrics {
+            cpu: quicklook.cpu,
+            mem: quicklook.mem,
+            disk: fs.iter().filter_map(|f| f.percent).reduce(f32::max),
+        });
+    }
+    Err(last_error)
+}
+
+fn format_metrics(metrics: &HostMetrics) -> String {
+    let fmt = |v: Option<f32>| v.map(|v| format!("{:.0}%", v)).unwrap_or("-".to_string());
+    tr!(
+        "CPU {} · 内存 {} · 磁盘 {}",
+        "CPU {} · Mem {} · Disk {}",
+        fmt(metrics.cpu),
+        fmt(metrics.mem),
+        fmt(metrics.disk)
+    )
+}
+
+// timeout 是整个检查的时间，Glances 要请求多个接口
+pub async fn check(
+    client: &reqwest::Client,
+    ip: &str,
+    check: &MetricsCheck,
+    timeout: Duration,
+) -> CheckOutcome {
+    let endpoint = check.endpoint(ip);
+    let fetch = async {
+        match check.source {
+            MetricsSource::NodeExporter => fetch_node_exporter(client, &endpoint, timeout).await,
+            MetricsSource::Glances => fetch_glances(client, &endpoint, timeout).await,
+        }
+    };
+    let Ok(result) = tokio::time::timeout(timeout, fetch).await else {
+        return CheckOutcome::timeout(tr!("读取主机指标超时", "Timed out reading host metrics"));
+    };
+
+    match result {
+        Ok(metrics) => {
+            let over = |v: Option<f32>, limit: f32| v.is_some_and(|v| v >= limit);
+            let degraded = over(metrics.cpu, check.cpu_threshold)
+                || over(metrics.mem, check.mem_threshold)
+                || over(metrics.disk, check.disk_threshold);
+            let status = if degraded {
+                ServerStatus::Degraded
+            } else {
+                ServerStatus::Online
+            };
+            let mut outcome = CheckOutcome::new(status, format_metrics(&metrics));
+            outcome.metrics = Some(metrics);
+            outcome
+        }
+        Err(e) => CheckOutcome::new(ServerStatus::Offline, e.to_string()),
+    }
+}
@@ -0,0 +1,505 @@
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio_native_tls::native_tls;
+
+use super::target::Target;
+use super::CheckOutcome;
+use crate::address;
+use crate::i18n::tr;
+use crate::ServerStatus;
+
+// MQTT 3.1.1 的连接检查：CONNECT 后等待 CONNACK；填写了测试主题时再订阅该主题、
+// 发布一条消息并等它送回来，以此得到经过代理的往返时间
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttCheck {
+    pub tls: bool,
+    pub port: u16,
+    // 留空时匿名连接
+    pub user: String,
+    pub password: String,
+    // 留空时只检查能否连接
+    pub topic: String,
+    // 自签名证书
+    pub accept_invalid_certs: bool,
+    // 校验证书用的主机名，留空时用服务器地址；按IP地址连接代理时填写证书上的名称
+    pub tls_name: String,
+}
+
+impl Default for MqttCheck {
+    fn default() -> Self {
+        Self {
+            tls: false,
+            port: 1883,
+            user: String::new(),
+            password: String::new(),
+            topic: String::new(),
+            accept_invalid_certs: false,
+            tls_name: String::new(),
+        }
+    }
+}
+
+impl MqttCheck {
+    pub fn endpoint(&self, host: &str) -> String {
+        let scheme = if self.tls { "mqtts" } else { "mqtt" };
+        address::build_url(scheme, host, self.port)
+    }
+
+    fn tls_name<'a>(&'a self, host: &'a str) -> &'a str {
+        match self.tls_name.trim() {
+            "" => host,
+            name => name,
+        }
+    }
+}
+
+const KEEP_ALIVE_SECS: u16 = 30;
+// 只等自己发布的测试消息，其他报文过大时视为异常
+const MAX_PACKET_BYTES: usize = 1 << 20;
+
+const CONNECT: u8 = 0x10;
+const CONNACK: u8 = 0x20;
+const PUBLISH: u8 = 0x30;
+const SUBSCRIBE: u8 = 0x82;
+const SUBACK: u8 = 0x90;
+const PINGREQ: u8 = 0xc0;
+const DISCONNECT: u8 = 0xe0;
+
+struct Packet {
+    kind: u8,
+    body: Vec<u8>,
+}
+
+// 明文或TLS连接
+trait Stream: AsyncRead + AsyncWrite + Unpin + Send {}
+impl<S: AsyncRead + AsyncWrite + Unpin + Send> Stream for S {}
+
+fn string(out: &mut Vec<u8>, text: &str) {
+    out.extend_from_slice(&(text.len() as u16).to_be_bytes());
+    out.extend_from_slice(text.as_bytes());
+}
+
+// 固定报头：类型和剩余长度（每字节7位）
+fn packet(kind: u8, body: &[u8]) -> Vec<u8> {
+    let mut out = vec![kind];
+    let mut len = body.len();
+    loop {
+        let mut byte = (len % 128) as u8;
+        len /= 128;
+        if len > 0 {
+            byte |= 0x80;
+        }
+        out.push(byte);
+        if len == 0 {
+            break;
+        }
+    }
+    out.extend_from_slice(body);
+    out
+}
+
+async fn receive<S: AsyncRead + Unpin>(stream: &mut S) -> Result<Packet> {
+    let kind = stream.read_u8().await?;
+    let mut len = 0usize;
+    for shift in 0..4 {
+        let byte = stream.read_u8().await?;
+        len |= ((byte & 0x7f) as usize) << (7 * shift);
+        if byte & 0x80 == 0 {
+            break;
+        }
+    }
+    if len > MAX_PACKET_BYTES {
+        bail!(tr!("无效的报文长度", "Invalid packet length"));
+    }
+    let mut body = vec![0; len];
+    stream.read_exact(&mut body).await?;
+    Ok(Packet { kind, body })
+}
+
+// 遗嘱：连接异常断开时代理以保留消息发布到这个主题
+pub struct Will<'a> {
+    pub topic: &'a str,
+    pub payload: &'a str,
+}
+
+fn connect_packet(check: &MqttCheck, client_id: &str, will: Option<&Will>) -> Vec<u8> {
+    let mut flags = 0x02;
+    if will.is_some() {
+        // QoS 0，保留
+        flags |= 0x04 | 0x20;
+    }
+    if !check.user.is_empty() {
+        flags |= 0x80;
+        if !check.password.is_empty() {
+            flags |= 0x40;
+        }
+    }
+    let mut body = Vec::new();
+    string(&mut body, "MQTT");
+    body.push(4);
+    body.push(flags);
+    body.extend_from_slice(&KEEP_ALIVE_SECS.to_be_bytes());
+    string(&mut body, client_id);
+    if let Some(will) = will {
+        string(&mut body, will.topic);
+        string(&mut body, will.payload);
+    }
+    if flags & 0x80 != 0 {
+        string(&mut body, &check.user);
+    }
+    if flags & 0x40 != 0 {
+        string(&mut body, &check.password);
+    }
+    packet(CONNECT, &body)
+}
+
+// CONNACK 的返回码
+fn refused_reason(code: u8) -> String {
+    match code {
+        1 => tr!(
+            "代理不支持 MQTT 3.1.1",
+            "Broker does not support MQTT 3.1.1"
+        )
+        .to_string(),
+        2 => tr!("客户端ID被拒绝", "Client identifier rejected").to_string(),
+        3 => tr!("MQTT服务不可用", "MQTT service unavailable").to_string(),
+        4 => tr!("用户名或密码错误", "Bad user name or password").to_string(),
+        5 => tr!("未授权", "Not authorized").to_string(),
+        code => tr!("连接被拒绝 ({})", "Connection refused ({})", code),
+    }
+}
+
+// 从收到的 PUBLISH 报文中取出主题和内容
+fn parse_publish(kind: u8, body: &[u8]) -> Option<(&[u8], &[u8])> {
+    let len = u16::from_be_bytes([*body.first()?, *body.get(1)?]) as usize;
+    let topic = body.get(2..2 + len)?;
+    // QoS 大于0时主题后面有报文ID
+    let skip = if (kind >> 1) & 0x03 > 0 { 2 } else { 0 };
+    Some((topic, body.get(2 + len + skip..)?))
+}
+
+// 发送 CONNECT 并等待代理接受
+async fn login<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    check: &MqttCheck,
+    client_id: &str,
+    will: Option<&Will<'_>>,
+) -> Result<()> {
+    stream
+        .write_all(&connect_packet(check, client_id, will))
+        .await?;
+    let ack = receive(stream).await?;
+    if ack.kind != CONNACK || ack.body.len() < 2 {
+        bail!(tr!(
+            "代理的回复不是 CONNACK",
+            "Broker did not reply with CONNACK"
+        ));
+    }
+    if ack.body[1] != 0 {
+        bail!(refused_reason(ack.body[1]));
+    }
+    Ok(())
+}
+
+// 返回发布/订阅的往返时间，没有测试主题时为空
+async fn session<S: AsyncRead + AsyncWrite + Unpin>(
+    stream: &mut S,
+    check: &MqttCheck,
+) -> Result<Option<Duration>> {
+    let nonce = uuid::Uuid::new_v4().simple().to_string();
+    let client_id = format!("server_check-{}", &nonce[..12]);
+    login(stream, check, &client_id, None).await?;
+
+    let topic = check.topic.trim();
+    if topic.is_empty() {
+        let _ = stream.write_all(&packet(DISCONNECT, &[])).await;
+        return Ok(None);
+    }
+    let mut body = 1u16.to_be_bytes().to_vec();
+    string(&mut body, topic);
+    body.push(0);
+    stream.write_all(&packet(SUBSCRIBE, &body)).await?;
+    loop {
+        let reply = receive(stream).await?;
+        if reply.kind != SUBACK {
+            continue;
+        }
+        if reply.body.get(2) == Some(&0x80) {
+            bail!(tr!(
+                "订阅主题 {} 被拒绝",
+                "Subscription to {} was refused",
+                topic
+            ));
+        }
+        break;
+    }
+
+    let mut body = Vec::new();
+    string(&mut body, topic);
+    body.extend_from_slice(nonce.as_bytes());
+    let started = Instant::now();
+    stream.write_all(&packet(PUBLISH, &body)).await?;
+    // 主题上可能还有保留消息或其他客户端的消息，等到自己发的那条为止
+    loop {
+        let message = receive(stream).await?;
+        if message.kind & 0xf0 != PUBLISH {
+            continue;
+        }
+        if parse_publish(message.kind, &message.body)
+            .is_some_and(|(t, payload)| t == topic.as_bytes() && payload == nonce.as_bytes())
+        {
+            break;
+        }
+    }
+    let round_trip = started.elapsed();
+    let _ = stream.write_all(&packet(DISCONNECT, &[])).await;
+    Ok(Some(round_trip))
+}
+
+async fn secure(stream: TcpStream, host: &str, check: &MqttCheck) -> Result<Box<dyn Stream>> {
+    if !check.tls {
+        return Ok(Box::new(stream));
+    }
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(check.accept_invalid_certs)
+        .build()?;
+    let stream = tokio_native_tls::TlsConnector::from(connector)
+        .connect(check.tls_name(host), stream)
+        .await
+        .context(tr!("TLS握手失败", "TLS handshake failed"))?;
+    Ok(Box::new(stream))
+}
+
+async fn handshake(stream: TcpStream, host: &str, check: &MqttCheck) -> Result<Option<Duration>> {
+    let mut stream = secure(stream, host, check).await?;
+    session(&mut stream, check).await
+}
+
+// 保持连接的发布客户端。代理的回复（PINGRESP）由后台任务读取并丢弃，读到连接关闭时 closed 为 true
+pub struct Connection {
+    writer: tokio::io::WriteHalf<Box<dyn Stream>>,
+    reader: tokio::task::JoinHandle<()>,
+}
+
+impl Connection {
+    pub async fn open(host: &str, check: &MqttCheck, will: Option<&Will<'_>>) -> Result<Self> {
+        let host = host.trim_matches(['[', ']']);
+        let stream = TcpStream::connect((host, check.port)).await?;
+        let mut stream = secure(stream, host, check).await?;
+        let nonce = uuid::Uuid::new_v4().simple().to_string();
+        login(
+            &mut stream,
+            check,
+            &format!("server_check-{}", &nonce[..12]),
+            will,
+        )
+        .await?;
+        let (mut reader, writer) = tokio::io::split(stream);
+        let reader = tokio::spawn(async move { while receive(&mut reader).await.is_ok() {} });
+        Ok(Self { writer, reader })
+    }
+
+    pub fn closed(&self) -> bool {
+        self.reader.is_finished()
+    }
+
+    // 以 QoS 0 发布一组（主题, 内容）；retain 时代理为每个主题保留最后一条，之后订阅的客户端立即收到
+    pub async fn publish(&mut self, messages: &[(String, String)], retain: bool) -> Result<()> {
+        let kind = if retain { PUBLISH | 0x01 } else { PUBLISH };
+        for (topic, payload) in messages {
+            let mut body = Vec::new();
+            string(&mut body, topic);
+            body.extend_from_slice(payload.as_bytes());
+            self.writer.write_all(&packet(kind, &body)).await?;
+        }
+        self.writer.flush().await?;
+        Ok(())
+    }
+
+    // 在 KEEP_ALIVE_SECS 内至少发送一次，否则代理断开连接并发布遗嘱
+    pub async fn ping(&mut self) -> Result<()> {
+        self.writer.write_all(&packet(PINGREQ, &[])).await?;
+        self.writer.flush().await?;
+        Ok(())
+    }
+
+    // 正常断开，代理不发布遗嘱
+    pub async fn disconnect(mut self) -> Result<()> {
+        self.writer.write_all(&packet(DISCONNECT, &[])).await?;
+        self.writer.flush().await?;
+        Ok(())
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.reader.abort();
+    }
+}
+
+pub const PING_INTERVAL: Duration = Duration::from_secs(KEEP_ALIVE_SECS as u64 / 2);
+
+// 连不上端口为离线，TLS握手、CONNACK 拒绝或收不到测试消息为错误
+pub async fn check(target: Target<'_>, check: &MqttCheck, timeout: Duration) -> CheckOutcome {
+    let probe = async {
+        let stream = target
+            .connect(check.port)
+            .await
+            .map_err(|e| CheckOutcome::new(ServerStatus::Offline, e.to_string()))?;
+        handshake(stream, target.name(), check)
+            .await
+            .map_err(|e| CheckOutcome::new(ServerStatus::Error(1), format!("{:#}", e)))
+    };
+    match tokio::time::timeout(timeout, probe).await {
+        Ok(Ok(None)) => CheckOutcome::new(ServerStatus::Online, tr!("已连接", "Connected")),
+        Ok(Ok(Some(round_trip))) => CheckOutcome {
+            latency: Some(round_trip),
+            ..CheckOutcome::new(
+                ServerStatus::Online,
+                tr!(
+                    "发布/订阅往返 {}ms",
+                    "Publish/subscribe round trip {}ms",
+                    round_trip.as_millis()
+                ),
+            )
+        },
+        Ok(Err(outcome)) => outcome,
+        Err(_) => CheckOutcome::timeout(tr!("MQTT 检查超时", "MQTT check timed out")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::TcpListener;
+
+    // 只接受用户 probe 的假代理，把发布的消息原样送回给订阅者
+    async fn fake_broker() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    while let Ok(request) = receive(&mut socket).await {
+                        let reply = match request.kind {
+                            CONNECT => {
+                                let accepted = request.body.windows(5).any(|w| w == b"probe");
+                                packet(CONNACK, &[0, if accepted { 0 } else { 4 }])
+                            }
+                            SUBSCRIBE => packet(SUBACK, &[request.body[0], request.body[1], 0]),
+                            PUBLISH => {
+                                // 先送一条其他内容的保留消息
+                                let mut other =
+                                    request.body[..2 + request.body[1] as usize].to_vec();
+                                other.extend_from_slice(b"retained");
+                                let mut out = packet(PUBLISH | 0x01, &other);
+                                out.extend(packet(PUBLISH, &request.body));
+                                out
+                            }
+                            _ => break,
+                        };
+                        let _ = socket.write_all(&reply).await;
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn connects_and_round_trips_a_message() {
+        let port = fake_broker().await;
+        let timeout = Duration::from_secs(5);
+        let mut mqtt = MqttCheck {
+            port,
+            ..Default::default()
+        };
+
+        let outcome = check(Target::new("127.0.0.1"), &mqtt, timeout).await;
+        assert_eq!(outcome.status, ServerStatus::Error(1));
+        assert_eq!(outcome.detail, refused_reason(4));
+
+        mqtt.user = "probe".to_string();
+        let outcome = check(Target::new("127.0.0.1"), &mqtt, timeout).await;
+        assert_eq!(outcome.status, ServerStatus::Online);
+        assert!(outcome.latency.is_none());
+
+        mqtt.topic = "server_check/probe".to_string();
+        let outcome = check(Target::new("127.0.0.1"), &mqtt, timeout).await;
+        assert_eq!(outcome.status, ServerStatus::Online);
+        assert!(outcome.latency.is_some());
+    }
+
+    #[tokio::test]
+    async fn publishes_retained_messages() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let broker = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut received = Vec::new();
+            while let Ok(request) = receive(&mut socket).await {
+                match request.kind & 0xf0 {
+                    CONNECT => {
+                        // 带着保留的遗嘱
+                        assert_eq!(request.body[7] & 0x24, 0x24);
+                        assert!(request.body.windows(7).any(|w| w == b"offline"));
+                        socket.write_all(&packet(CONNACK, &[0, 0])).await.unwrap()
+                    }
+                    PINGREQ => socket.write_all(&[0xd0, 0]).await.unwrap(),
+                    PUBLISH => {
+                        let (topic, payload) = parse_publish(request.kind, &request.body).unwrap();
+                        received.push((
+                            request.kind & 0x01 == 1,
+                            String::from_utf8_lossy(topic).to_string(),
+                            String::from_utf8_lossy(payload).to_string(),
+                        ));
+                    }
+                    _ => break,
+                }
+            }
+            received
+        });
+        let mqtt = MqttCheck {
+            port,
+            ..Default::default()
+        };
+        let messages = vec![
+            ("servercheck/web/status".to_string(), "offline".to_string()),
+            ("servercheck/db/status".to_string(), "online".to_string()),
+        ];
+        let will = Will {
+            topic: "servercheck/availability",
+            payload: "offline",
+        };
+        let mut connection = Connection::open("127.0.0.1", &mqtt, Some(&will))
+            .await
+            .unwrap();
+        connection.publish(&messages, true).await.unwrap();
+        connection.ping().await.unwrap();
+        assert!(!connection.closed());
+        connection.disconnect().await.unwrap();
+        let received = broker.await.unwrap();
+        assert_eq!(received.len(), 2);
+        assert_eq!(
+            received[0],
+            (
+                true,
+                "servercheck/web/status".to_string(),
+                "offline".to_string()
+            )
+        );
+    }
+
+    #[test]
+    fn encodes_remaining_length() {
+        assert_eq!(packet(DISCONNECT, &[]), vec![0xe0, 0x00]);
+        let long = packet(PUBLISH, &[0; 200]);
+        assert_eq!(&long[..3], &[0x30, 0xc8, 0x01]);
+        assert_eq!(long.len(), 203);
+    }
+}
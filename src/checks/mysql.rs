@@ -0,0 +1,294 @@
+use anyhow::{bail, Context, Result};
+use ring::digest;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use super::database::DatabaseCheck;
+use crate::i18n::tr;
+
+// MySQL/MariaDB 客户端协议：读取握手包，用 mysql_native_password 或 caching_sha2_password 登录后执行 SELECT 1。
+// 不使用TLS，caching_sha2_password 只能走快速认证（服务器缓存中已有这个账号）
+
+const CLIENT_LONG_PASSWORD: u32 = 0x1;
+const CLIENT_CONNECT_WITH_DB: u32 = 0x8;
+const CLIENT_PROTOCOL_41: u32 = 0x200;
+const CLIENT_SECURE_CONNECTION: u32 = 0x8000;
+const CLIENT_PLUGIN_AUTH: u32 = 0x80000;
+// utf8mb4_general_ci
+const CHARSET: u8 = 45;
+const MAX_PACKET: usize = 1 << 20;
+
+const NATIVE_PASSWORD: &str = "mysql_native_password";
+const CACHING_SHA2: &str = "caching_sha2_password";
+
+async fn read_packet(stream: &mut TcpStream) -> Result<(u8, Vec<u8>)> {
+    let mut header = [0u8; 4];
+    stream.read_exact(&mut header).await?;
+    let len = u32::from_le_bytes([header[0], header[1], header[2], 0]) as usize;
+    if len > MAX_PACKET {
+        bail!(tr!("数据包过大", "Packet too large"));
+    }
+    let mut payload = vec![0; len];
+    stream.read_exact(&mut payload).await?;
+    if payload.first() == Some(&0xff) {
+        bail!(error_message(&payload));
+    }
+    Ok((header[3], payload))
+}
+
+async fn write_packet(stream: &mut TcpStream, seq: u8, payload: &[u8]) -> Result<()> {
+    let mut out = (payload.len() as u32).to_le_bytes();
+    out[3] = seq;
+    stream.write_all(&out).await?;
+    stream.write_all(payload).await?;
+    Ok(())
+}
+
+// ERR 包：0xff、错误码、可选的 #SQLSTATE、说明
+fn error_message(payload: &[u8]) -> String {
+    let code = payload
+        .get(1..3)
+        .map_or(0, |b| u16::from_le_bytes([b[0], b[1]]));
+    let message = match payload.get(3) {
+        Some(b'#') => payload.get(9..).unwrap_or_default(),
+        _ => payload.get(3..).unwrap_or_default(),
+    };
+    format!("{} ({})", String::from_utf8_lossy(message), code)
+}
+
+fn cstring(data: &[u8]) -> (&[u8], &[u8]) {
+    match data.iter().position(|b| *b == 0) {
+        Some(end) => (&data[..end], &data[end + 1..]),
+        None => (data, &[]),
+    }
+}
+
+struct Handshake {
+    version: String,
+    scramble: Vec<u8>,
+    plugin: String,
+}
+
+fn parse_handshake(payload: &[u8]) -> Result<Handshake> {
+    let incomplete = || tr!("握手包不完整", "Incomplete handshake packet");
+    if payload.first() != Some(&10) {
+        bail!(tr!(
+            "不支持的协议版本，可能不是MySQL服务",
+            "Unsupported protocol version; this may not be a MySQL server"
+        ));
+    }
+    let (version, rest) = cstring(&payload[1..]);
+    // 连接ID(4) 随机数第一段(8) 填充(1) 能力标志低位(2)
+    let part1 = rest.get(4..12).with_context(incomplete)?;
+    let mut scramble = part1.to_vec();
+    let mut plugin = NATIVE_PASSWORD.to_string();
+    // 字符集(1) 状态(2) 能力标志高位(2) 随机数长度(1) 保留(10)
+    if let Some(extra) = rest.get(31..) {
+        let auth_len = rest[20] as usize;
+        let part2_len = auth_len.saturating_sub(8).max(13);
+        let part2 = extra.get(..part2_len).with_context(incomplete)?;
+        scramble.extend_from_slice(cstring(part2).0);
+        let (name, _) = cstring(&extra[part2_len..]);
+        if !name.is_empty() {
+            plugin = String::from_utf8_lossy(name).into_owned();
+        }
+    }
+    Ok(Handshake {
+        version: String::from_utf8_lossy(version).into_owned(),
+        scramble,
+        plugin,
+    })
+}
+
+fn xor(a: &[u8], b: &[u8]) -> Vec<u8> {
+    a.iter().zip(b).map(|(x, y)| x ^ y).collect()
+}
+
+fn sha(algorithm: &'static digest::Algorithm, parts: &[&[u8]]) -> Vec<u8> {
+    let mut context = digest::Context::new(algorithm);
+    for part in parts {
+        context.update(part);
+    }
+    context.finish().as_ref().to_vec()
+}
+
+// 按认证插件计算密码的回应，空密码发送空串
+fn auth_response(plugin: &str, password: &str, scramble: &[u8]) -> Result<Vec<u8>> {
+    if password.is_empty() {
+        return Ok(Vec::new());
+    }
+    let password = password.as_bytes();
+    Ok(match plugin {
+        NATIVE_PASSWORD => {
+            let algorithm = &digest::SHA1_FOR_LEGACY_USE_ONLY;
+            let stage1 = sha(algorithm, &[password]);
+            let stage2 = sha(algorithm, &[&stage1]);
+            xor(&stage1, &sha(algorithm, &[scramble, &stage2]))
+        }
+        CACHING_SHA2 => {
+            let algorithm = &digest::SHA256;
+            let stage1 = sha(algorithm, &[password]);
+            let stage2 = sha(algorithm, &[&stage1]);
+            xor(&stage1, &sha(algorithm, &[&stage2, scramble]))
+        }
+        other => bail!(tr!(
+            "不支持的认证插件: {}",
+            "Unsupported authentication plugin: {}",
+            other
+        )),
+    })
+}
+
+// 登录并执行 SELECT 1，返回服务器版本
+pub async fn select_one(mut stream: TcpStream, check: &DatabaseCheck) -> Result<String> {
+    let (seq, payload) = read_packet(&mut stream).await?;
+    let handshake = parse_handshake(&payload)?;
+    let database = check.database.trim();
+
+    let mut flags =
+        CLIENT_LONG_PASSWORD | CLIENT_PROTOCOL_41 | CLIENT_SECURE_CONNECTION | CLIENT_PLUGIN_AUTH;
+    if !database.is_empty() {
+        flags |= CLIENT_CONNECT_WITH_DB;
+    }
+    let auth = auth_response(&handshake.plugin, &check.password, &handshake.scramble)?;
+    let mut response = flags.to_le_bytes().to_vec();
+    response.extend_from_slice(&(MAX_PACKET as u32).to_le_bytes());
+    response.push(CHARSET);
+    response.extend_from_slice(&[0; 23]);
+    response.extend_from_slice(check.user.trim().as_bytes());
+    response.push(0);
+    response.push(auth.len() as u8);
+    response.extend_from_slice(&auth);
+    if !database.is_empty() {
+        response.extend_from_slice(database.as_bytes());
+        response.push(0);
+    }
+    response.extend_from_slice(handshake.plugin.as_bytes());
+    response.push(0);
+    write_packet(&mut stream, seq.wrapping_add(1), &response).await?;
+
+    let mut plugin = handshake.plugin;
+    loop {
+        let (seq, payload) = read_packet(&mut stream).await?;
+        match payload.first() {
+            Some(0x00) => break,
+            // 服务器要求换一种认证插件
+            Some(0xfe) => {
+                let (name, data) = cstring(&payload[1..]);
+                plugin = String::from_utf8_lossy(name).into_owned();
+                let scramble = cstring(data).0;
+                let auth = auth_response(&plugin, &check.password, scramble)?;
+                write_packet(&mut stream, seq.wrapping_add(1), &auth).await?;
+            }
+            // caching_sha2_password：3 为快速认证成功，接着会收到OK包；4 需要完整认证
+            Some(0x01) if plugin == CACHING_SHA2 => match payload.get(1) {
+                Some(3) => {}
+                _ => bail!(tr!(
+                    "服务器要求 caching_sha2_password 完整认证，需要TLS连接；可用客户端登录一次让服务器缓存账号，或改用 mysql_native_password",
+                    "The server requires full caching_sha2_password authentication, which needs TLS; log in once with a client so the account is cached, or use mysql_native_password"
+                )),
+            },
+            _ => bail!(tr!("服务器的认证回复异常", "Unexpected authentication reply")),
+        }
+    }
+
+    let mut query = vec![0x03];
+    query.extend_from_slice(b"SELECT 1");
+    write_packet(&mut stream, 0, &query).await?;
+    // 出错时 read_packet 返回ERR包中的说明，否则为结果集的列数
+    read_packet(&mut stream).await?;
+    let _ = write_packet(&mut stream, 0, &[0x01]).await;
+    Ok(format!("MySQL {}", handshake.version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checks::database::{self, DbEngine};
+    use crate::checks::target::Target;
+    use crate::ServerStatus;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+
+    const SCRAMBLE: &[u8; 20] = b"abcdefghijklmnopqrst";
+
+    fn handshake() -> Vec<u8> {
+        let mut p = vec![10];
+        p.extend_from_slice(b"8.0.36\0");
+        p.extend_from_slice(&7u32.to_le_bytes());
+        p.extend_from_slice(&SCRAMBLE[..8]);
+        p.push(0);
+        p.extend_from_slice(&0xffffu16.to_le_bytes());
+        p.push(CHARSET);
+        p.extend_from_slice(&2u16.to_le_bytes());
+        p.extend_from_slice(&0x000fu16.to_le_bytes());
+        p.push(21);
+        p.extend_from_slice(&[0; 10]);
+        p.extend_from_slice(&SCRAMBLE[8..]);
+        p.push(0);
+        p.extend_from_slice(b"mysql_native_password\0");
+        p
+    }
+
+    // 服务器端的校验方式：用保存的 SHA1(SHA1(密码)) 还原出 SHA1(密码) 再比较
+    fn verify(reply: &[u8], stored: &[u8]) -> bool {
+        let algorithm = &digest::SHA1_FOR_LEGACY_USE_ONLY;
+        let stage1 = xor(reply, &sha(algorithm, &[SCRAMBLE, stored]));
+        sha(algorithm, &[&stage1]) == stored
+    }
+
+    #[test]
+    fn native_password_matches_stored_hash() {
+        // MySQL 中 PASSWORD('password') 的结果
+        let stored = hex::decode("2470C0C06DEE42FD1618BB99005ADCA2EC9D1E19").unwrap();
+        let reply = auth_response(NATIVE_PASSWORD, "password", SCRAMBLE).unwrap();
+        assert!(verify(&reply, &stored));
+        let wrong = auth_response(NATIVE_PASSWORD, "passw0rd", SCRAMBLE).unwrap();
+        assert!(!verify(&wrong, &stored));
+        assert!(auth_response(NATIVE_PASSWORD, "", SCRAMBLE)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn logs_in_and_runs_select() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            write_packet(&mut socket, 0, &handshake()).await.unwrap();
+            let (seq, response) = read_packet(&mut socket).await.unwrap();
+            assert_eq!(seq, 1);
+            // 用户名在固定的32字节之后
+            let (user, rest) = cstring(&response[32..]);
+            assert_eq!(user, b"monitor");
+            let auth = &rest[1..1 + rest[0] as usize];
+            let expected = auth_response(NATIVE_PASSWORD, "secret", SCRAMBLE).unwrap();
+            if auth == expected.as_slice() {
+                write_packet(&mut socket, 2, &[0, 0, 0, 2, 0, 0, 0])
+                    .await
+                    .unwrap();
+            } else {
+                let mut err = vec![0xff, 0x15, 0x04, b'#'];
+                err.extend_from_slice(b"28000Access denied");
+                write_packet(&mut socket, 2, &err).await.unwrap();
+                return;
+            }
+            let (_, query) = read_packet(&mut socket).await.unwrap();
+            assert_eq!(query, b"\x03SELECT 1");
+            write_packet(&mut socket, 1, &[1]).await.unwrap();
+        });
+
+        let check = DatabaseCheck {
+            engine: DbEngine::MySql,
+            port,
+            user: "monitor".to_string(),
+            password: "secret".to_string(),
+            database: String::new(),
+        };
+        let outcome =
+            database::check(Target::new("127.0.0.1"), &check, Duration::from_secs(5)).await;
+        assert_eq!(outcome.status, ServerStatus::Online, "{}", outcome.detail);
+        assert_eq!(outcome.detail, "MySQL 8.0.36");
+    }
+}
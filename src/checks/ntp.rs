@@ -0,0 +1,240 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::target::udp_connect;
+use super::CheckOutcome;
+use crate::address;
+use crate::i18n::tr;
+use crate::ServerStatus;
+
+// SNTP (RFC 4330) 查询：校验回复的模式、层级和时间戳，计算本机与服务器的时钟偏差，
+// 偏差超过阈值时为错误
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NtpCheck {
+    pub port: u16,
+    // 允许的时钟偏差，0表示不检查
+    pub max_offset_ms: u64,
+}
+
+impl Default for NtpCheck {
+    fn default() -> Self {
+        Self {
+            port: 123,
+            max_offset_ms: 1000,
+        }
+    }
+}
+
+impl NtpCheck {
+    pub fn endpoint(&self, host: &str) -> String {
+        address::build_url("ntp", host, self.port)
+    }
+}
+
+// 1900-01-01 到 1970-01-01 的秒数
+const NTP_EPOCH_OFFSET: u64 = 2_208_988_800;
+const PACKET_LEN: usize = 48;
+
+// 以秒为单位的NTP时间戳，32位整数部分和32位小数部分
+fn to_ntp(time: SystemTime) -> u64 {
+    let since = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since.as_secs() + NTP_EPOCH_OFFSET;
+    let frac = ((since.subsec_nanos() as u64) << 32) / 1_000_000_000;
+    (secs << 32) | frac
+}
+
+fn from_ntp(stamp: u64) -> f64 {
+    (stamp >> 32) as f64 + (stamp & 0xffff_ffff) as f64 / 4_294_967_296.0
+}
+
+fn stamp(packet: &[u8], offset: usize) -> u64 {
+    u64::from_be_bytes(packet[offset..offset + 8].try_into().unwrap_or_default())
+}
+
+#[derive(Debug, PartialEq)]
+struct Reply {
+    stratum: u8,
+    reference: String,
+    // 秒，正数表示本机时钟落后于服务器
+    offset: f64,
+    delay: f64,
+}
+
+// 层级为1时参考标识是4个字符的时钟源，否则是上级服务器的IPv4地址
+fn reference_id(stratum: u8, id: &[u8]) -> String {
+    if stratum <= 1 {
+        String::from_utf8_lossy(id)
+            .trim_end_matches('\0')
+            .to_string()
+    } else {
+        id.iter()
+            .map(|b| b.to_string())
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+}
+
+fn parse_reply(packet: &[u8], sent: u64, received: u64) -> Result<Reply> {
+    if packet.len() < PACKET_LEN {
+        bail!(tr!("NTP 回复不完整", "Incomplete NTP reply"));
+    }
+    let leap = packet[0] >> 6;
+    let mode = packet[0] & 0x07;
+    let stratum = packet[1];
+    if mode != 4 {
+        bail!(tr!(
+            "回复的模式不是服务器 ({})",
+            "Reply mode is not server ({})",
+            mode
+        ));
+    }
+    // 层级0为 Kiss-o'-Death，参考标识中是原因，例如 RATE 表示请求过于频繁
+    if stratum == 0 {
+        bail!(tr!(
+            "服务器拒绝请求: {}",
+            "Server refused: {}",
+            reference_id(1, &packet[12..16])
+        ));
+    }
+    if leap == 3 || stratum > 15 {
+        bail!(tr!("服务器时钟未同步", "Server clock is not synchronized"));
+    }
+    if stamp(packet, 24) != sent {
+        bail!(tr!("回复与请求不对应", "Reply does not match the request"));
+    }
+    let (t1, t4) = (from_ntp(sent), from_ntp(received));
+    let (t2, t3) = (from_ntp(stamp(packet, 32)), from_ntp(stamp(packet, 40)));
+    Ok(Reply {
+        stratum,
+        reference: reference_id(stratum, &packet[12..16]),
+        offset: ((t2 - t1) + (t3 - t4)) / 2.0,
+        delay: ((t4 - t1) - (t3 - t2)).max(0.0),
+    })
+}
+
+async fn query(host: &str, port: u16) -> Result<Reply> {
+    let socket = udp_connect((host, port)).await?;
+    let mut request = [0u8; PACKET_LEN];
+    // LI=0, VN=4, Mode=3（客户端）
+    request[0] = 0x23;
+    let sent = to_ntp(SystemTime::now());
+    request[40..48].copy_from_slice(&sent.to_be_bytes());
+    socket.send(&request).await?;
+    let mut reply = [0u8; 256];
+    let n = socket.recv(&mut reply).await?;
+    let received = to_ntp(SystemTime::now());
+    parse_reply(&reply[..n], sent, received)
+}
+
+// 没有回复为离线，回复无效或偏差超过阈值为错误
+pub async fn check(host: &str, check: &NtpCheck, timeout: Duration) -> CheckOutcome {
+    let host = host.trim_matches(['[', ']']);
+    let reply = match tokio::time::timeout(timeout, query(host, check.port)).await {
+        Ok(Ok(reply)) => reply,
+        Ok(Err(e)) => {
+            let status = if e.downcast_ref::<std::io::Error>().is_some() {
+                ServerStatus::Offline
+            } else {
+                ServerStatus::Error(1)
+            };
+            return CheckOutcome::new(status, e.to_string());
+        }
+        Err(_) => return CheckOutcome::timeout(tr!("NTP 无响应", "No NTP response")),
+    };
+    let offset_ms = reply.offset * 1000.0;
+    let too_far = check.max_offset_ms > 0 && offset_ms.abs() > check.max_offset_ms as f64;
+    let status = if too_far {
+        ServerStatus::Error(1)
+    } else {
+        ServerStatus::Online
+    };
+    let mut detail = tr!(
+        "偏差 {:+.1}ms · 层级 {}",
+        "Offset {:+.1}ms · stratum {}",
+        offset_ms,
+        reply.stratum
+    );
+    if !reply.reference.is_empty() {
+        detail.push_str(&tr!(" · 参考 {}", " · ref {}", reply.reference));
+    }
+    if too_far {
+        detail.push_str(&tr!(" · 超过 {}ms", " · exceeds {}ms", check.max_offset_ms));
+    }
+    CheckOutcome {
+        latency: Some(Duration::from_secs_f64(reply.delay)),
+        ..CheckOutcome::new(status, detail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::UdpSocket;
+
+    // 时钟比真实时间快 skew 秒的假服务器
+    async fn fake_server(skew: f64) -> u16 {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut buf = [0u8; PACKET_LEN];
+            loop {
+                let (_, peer) = socket.recv_from(&mut buf).await.unwrap();
+                let now = to_ntp(SystemTime::now() + Duration::from_secs_f64(skew));
+                let mut reply = [0u8; PACKET_LEN];
+                reply[0] = 0x24;
+                reply[1] = 1;
+                reply[12..16].copy_from_slice(b"GPS\0");
+                reply[24..32].copy_from_slice(&buf[40..48]);
+                reply[32..40].copy_from_slice(&now.to_be_bytes());
+                reply[40..48].copy_from_slice(&now.to_be_bytes());
+                socket.send_to(&reply, peer).await.unwrap();
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn measures_clock_offset() {
+        let timeout = Duration::from_secs(5);
+        let mut ntp = NtpCheck {
+            port: fake_server(0.0).await,
+            ..Default::default()
+        };
+        let outcome = check("127.0.0.1", &ntp, timeout).await;
+        assert_eq!(outcome.status, ServerStatus::Online);
+        assert!(outcome.detail.contains("GPS"));
+
+        ntp.port = fake_server(5.0).await;
+        let outcome = check("127.0.0.1", &ntp, timeout).await;
+        assert_eq!(outcome.status, ServerStatus::Error(1));
+        assert!(outcome.detail.contains("1000ms"));
+
+        ntp.max_offset_ms = 0;
+        let outcome = check("127.0.0.1", &ntp, timeout).await;
+        assert_eq!(outcome.status, ServerStatus::Online);
+    }
+
+    #[test]
+    fn rejects_invalid_replies() {
+        let sent = to_ntp(SystemTime::now());
+        let mut packet = [0u8; PACKET_LEN];
+        packet[0] = 0x24;
+        packet[1] = 0;
+        packet[12..16].copy_from_slice(b"RATE");
+        packet[24..32].copy_from_slice(&sent.to_be_bytes());
+        let err = parse_reply(&packet, sent, sent).unwrap_err();
+        assert!(err.to_string().contains("RATE"));
+
+        packet[1] = 2;
+        packet[0] = 0xe4;
+        assert!(parse_reply(&packet, sent, sent).is_err());
+        packet[0] = 0x24;
+        assert!(parse_reply(&packet, sent + 1, sent).is_err());
+        assert_eq!(
+            parse_reply(&packet, sent, sent).unwrap().reference,
+            "82.65.84.69"
+        );
+    }
+}
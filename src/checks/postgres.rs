@@ -0,0 +1,297 @@
+use anyhow::{bail, Context, Result};
+use base64::Engine;
+use ring::{digest, hmac, pbkdf2};
+use std::num::NonZeroU32;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use super::database::DatabaseCheck;
+use crate::i18n::tr;
+
+// PostgreSQL 前后端协议 v3：启动、认证（明文/MD5/SCRAM-SHA-256）后执行 SELECT 1。
+// 不发起SSL协商，只允许SSL连接的服务器会返回错误说明
+
+const PROTOCOL_VERSION: i32 = 196608;
+const SCRAM_MECHANISM: &str = "SCRAM-SHA-256";
+
+struct Message {
+    tag: u8,
+    body: Vec<u8>,
+}
+
+async fn send(stream: &mut TcpStream, tag: u8, body: &[u8]) -> Result<()> {
+    let mut out = vec![tag];
+    out.extend_from_slice(&(body.len() as i32 + 4).to_be_bytes());
+    out.extend_from_slice(body);
+    stream.write_all(&out).await?;
+    Ok(())
+}
+
+async fn receive(stream: &mut TcpStream) -> Result<Message> {
+    let tag = stream.read_u8().await?;
+    let len = stream.read_i32().await?;
+    if !(4..=1 << 20).contains(&len) {
+        bail!(tr!("无效的消息长度", "Invalid message length"));
+    }
+    let mut body = vec![0; len as usize - 4];
+    stream.read_exact(&mut body).await?;
+    if tag == b'E' {
+        bail!(error_message(&body));
+    }
+    Ok(Message { tag, body })
+}
+
+// ErrorResponse 中的 SQLSTATE 和说明
+fn error_message(body: &[u8]) -> String {
+    let (mut code, mut message) = ("", String::new());
+    for field in body.split(|b| *b == 0) {
+        match field.split_first() {
+            Some((b'C', value)) => code = std::str::from_utf8(value).unwrap_or(""),
+            Some((b'M', value)) => message = String::from_utf8_lossy(value).into_owned(),
+            _ => {}
+        }
+    }
+    format!("{} ({})", message, code)
+}
+
+fn cstring(out: &mut Vec<u8>, text: &str) {
+    out.extend_from_slice(text.as_bytes());
+    out.push(0);
+}
+
+fn md5_password(user: &str, password: &str, salt: &[u8]) -> String {
+    let inner = format!("{:x}", md5::compute(format!("{}{}", password, user)));
+    let mut outer = inner.into_bytes();
+    outer.extend_from_slice(salt);
+    format!("md5{:x}", md5::compute(outer))
+}
+
+// SCRAM-SHA-256 (RFC 5802/7677) 的客户端状态
+struct Scram {
+    // PostgreSQL 以启动消息中的用户名为准，这里留空
+    user: String,
+    nonce: String,
+    password: String,
+}
+
+impl Scram {
+    fn client_first_bare(&self) -> String {
+        format!("n={},r={}", self.user, self.nonce)
+    }
+
+    // 根据服务器的 server-first 消息算出 client-final 消息，同时返回服务器签名用于校验
+    fn client_final(&self, server_first: &str) -> Result<(String, Vec<u8>)> {
+        let attr = |name: &str| {
+            server_first
+                .split(',')
+                .find_map(|part| part.strip_prefix(name))
+                .with_context(|| tr!("SCRAM 消息不完整", "Incomplete SCRAM message"))
+        };
+        let nonce = attr("r=")?;
+        if !nonce.starts_with(&self.nonce) {
+            bail!(tr!("SCRAM 随机数不匹配", "SCRAM nonce mismatch"));
+        }
+        let salt = base64::engine::general_purpose::STANDARD.decode(attr("s=")?)?;
+        let iterations: NonZeroU32 = attr("i=")?.parse()?;
+
+        let mut salted = [0u8; 32];
+        pbkdf2::derive(
+            pbkdf2::PBKDF2_HMAC_SHA256,
+            iterations,
+            &salt,
+            self.password.as_bytes(),
+            &mut salted,
+        );
+        let salted = hmac::Key::new(hmac::HMAC_SHA256, &salted);
+        let client_key = hmac::sign(&salted, b"Client Key");
+        let stored_key = digest::digest(&digest::SHA256, client_key.as_ref());
+        let without_proof = format!("c=biws,r={}", nonce);
+        let auth_message = format!(
+            "{},{},{}",
+            self.client_first_bare(),
+            server_first,
+            without_proof
+        );
+        let signature = hmac::sign(
+            &hmac::Key::new(hmac::HMAC_SHA256, stored_key.as_ref()),
+            auth_message.as_bytes(),
+        );
+        let proof: Vec<u8> = client_key
+            .as_ref()
+            .iter()
+            .zip(signature.as_ref())
+            .map(|(a, b)| a ^ b)
+            .collect();
+        let server_key = hmac::sign(&salted, b"Server Key");
+        let server_signature = hmac::sign(
+            &hmac::Key::new(hmac::HMAC_SHA256, server_key.as_ref()),
+            auth_message.as_bytes(),
+        );
+        Ok((
+            format!(
+                "{},p={}",
+                without_proof,
+                base64::engine::general_purpose::STANDARD.encode(proof)
+            ),
+            server_signature.as_ref().to_vec(),
+        ))
+    }
+}
+
+fn auth_code(body: &[u8]) -> Result<i32> {
+    let code = body
+        .get(..4)
+        .with_context(|| tr!("认证消息不完整", "Incomplete authentication message"))?;
+    Ok(i32::from_be_bytes(code.try_into()?))
+}
+
+async fn authenticate(stream: &mut TcpStream, user: &str, password: &str) -> Result<()> {
+    let mut scram: Option<(Scram, Vec<u8>)> = None;
+    loop {
+        let message = receive(stream).await?;
+        if message.tag != b'R' {
+            bail!(tr!(
+                "服务器的认证回复异常",
+                "Unexpected authentication reply"
+            ));
+        }
+        let code = auth_code(&message.body)?;
+        let data = &message.body[4..];
+        match code {
+            0 => return Ok(()),
+            // 明文密码
+            3 => {
+                let mut body = Vec::new();
+                cstring(&mut body, password);
+                send(stream, b'p', &body).await?;
+            }
+            5 => {
+                let mut body = Vec::new();
+                cstring(&mut body, &md5_password(user, password, data));
+                send(stream, b'p', &body).await?;
+            }
+            // SASL：服务器列出支持的机制
+            10 => {
+                let mechanisms = String::from_utf8_lossy(data);
+                if !mechanisms.split('\0').any(|m| m == SCRAM_MECHANISM) {
+                    bail!(tr!(
+                        "不支持的认证方式: {}",
+                        "Unsupported authentication: {}",
+                        mechanisms.trim_matches('\0')
+                    ));
+                }
+                let state = Scram {
+                    user: String::new(),
+                    nonce: uuid::Uuid::new_v4().simple().to_string(),
+                    password: password.to_string(),
+                };
+                let first = format!("n,,{}", state.client_first_bare());
+                let mut body = Vec::new();
+                cstring(&mut body, SCRAM_MECHANISM);
+                body.extend_from_slice(&(first.len() as i32).to_be_bytes());
+                body.extend_from_slice(first.as_bytes());
+                send(stream, b'p', &body).await?;
+                scram = Some((state, Vec::new()));
+            }
+            11 => {
+                let Some((state, expected)) = scram.as_mut() else {
+                    bail!(tr!("SCRAM 消息顺序异常", "Unexpected SCRAM message"));
+                };
+                let (last, signature) = state.client_final(&String::from_utf8_lossy(data))?;
+                *expected = signature;
+                send(stream, b'p', last.as_bytes()).await?;
+            }
+            // 服务器签名，确认对方也知道密码
+            12 => {
+                let verifier = String::from_utf8_lossy(data);
+                let signature = verifier
+                    .strip_prefix("v=")
+                    .and_then(|v| base64::engine::general_purpose::STANDARD.decode(v).ok());
+                if scram.as_ref().map(|(_, expected)| expected) != signature.as_ref() {
+                    bail!(tr!("服务器签名校验失败", "Server signature mismatch"));
+                }
+            }
+            code => bail!(tr!(
+                "不支持的认证方式 ({})",
+                "Unsupported authentication ({})",
+                code
+            )),
+        }
+    }
+}
+
+// 登录并执行 SELECT 1，返回服务器版本
+pub async fn select_one(mut stream: TcpStream, check: &DatabaseCheck) -> Result<String> {
+    let user = match check.user.trim() {
+        "" => "postgres",
+        user => user,
+    };
+    let mut body = PROTOCOL_VERSION.to_be_bytes().to_vec();
+    cstring(&mut body, "user");
+    cstring(&mut body, user);
+    if !check.database.trim().is_empty() {
+        cstring(&mut body, "database");
+        cstring(&mut body, check.database.trim());
+    }
+    body.push(0);
+    let mut startup = (body.len() as i32 + 4).to_be_bytes().to_vec();
+    startup.extend_from_slice(&body);
+    stream.write_all(&startup).await?;
+
+    authenticate(&mut stream, user, &check.password).await?;
+
+    // 等到 ReadyForQuery，期间的参数里有服务器版本
+    let mut version = String::new();
+    loop {
+        let message = receive(&mut stream).await?;
+        match message.tag {
+            b'S' => {
+                let mut parts = message.body.split(|b| *b == 0);
+                if parts.next() == Some(b"server_version") {
+                    version =
+                        String::from_utf8_lossy(parts.next().unwrap_or_default()).into_owned();
+                }
+            }
+            b'Z' => break,
+            _ => {}
+        }
+    }
+
+    let mut query = Vec::new();
+    cstring(&mut query, "SELECT 1");
+    send(&mut stream, b'Q', &query).await?;
+    while receive(&mut stream).await?.tag != b'Z' {}
+    let _ = send(&mut stream, b'X', &[]).await;
+    Ok(format!("PostgreSQL {}", version).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scram_matches_rfc_example() {
+        // RFC 7677 中的示例
+        let scram = Scram {
+            user: "user".to_string(),
+            nonce: "rOprNGfwEbeRWgbNEkqO".to_string(),
+            password: "pencil".to_string(),
+        };
+        let server_first =
+            "r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,s=W22ZaJ0SNY7soEsUEjb6gQ==,i=4096";
+        let (last, signature) = scram.client_final(server_first).unwrap();
+        assert_eq!(
+            last,
+            "c=biws,r=rOprNGfwEbeRWgbNEkqO%hvYDpWUa2RaTCAfuxFIlj)hNlF$k0,p=dHzbZapWIk4jUhN+Ute9ytag9zjfMHgsqmmiz7AndVQ="
+        );
+        assert_eq!(
+            base64::engine::general_purpose::STANDARD.encode(signature),
+            "6rriTRBi23WpRR/wtup+mMhUZUn/dB5nLTJRsjl95G4="
+        );
+        // 服务器的随机数必须以客户端的开头
+        assert!(scram.client_final("r=other,s=AAAA,i=1").is_err());
+
+        let md5 = md5_password("postgres", "secret", &[1, 2, 3, 4]);
+        assert!(md5.starts_with("md5") && md5.len() == 35);
+    }
+}
@@ -0,0 +1,127 @@
+use anyhow::{bail, Result};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+
+use super::database::DatabaseCheck;
+use crate::i18n::tr;
+
+// RESP 格式的命令
+fn command(args: &[&str]) -> Vec<u8> {
+    let mut out = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        out.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        out.extend_from_slice(arg.as_bytes());
+        out.extend_from_slice(b"\r\n");
+    }
+    out
+}
+
+// 读取一行简单回复，-ERR 之类的错误回复转为错误
+async fn reply(reader: &mut BufReader<TcpStream>) -> Result<String> {
+    let mut line = String::new();
+    if reader.read_line(&mut line).await? == 0 {
+        bail!(tr!("连接被关闭", "Connection closed"));
+    }
+    let line = line.trim_end();
+    match line.strip_prefix('-') {
+        Some(error) => bail!("{}", error),
+        None => Ok(line.trim_start_matches('+').to_string()),
+    }
+}
+
+// 设置了密码时先 AUTH（有用户名时按 Redis 6 的 ACL 方式），再 PING
+pub async fn ping(stream: TcpStream, check: &DatabaseCheck) -> Result<String> {
+    let mut reader = BufReader::new(stream);
+    if !check.password.is_empty() {
+        let auth = if check.user.is_empty() {
+            command(&["AUTH", &check.password])
+        } else {
+            command(&["AUTH", &check.user, &check.password])
+        };
+        reader.get_mut().write_all(&auth).await?;
+        reply(&mut reader).await?;
+    }
+    reader.get_mut().write_all(&command(&["PING"])).await?;
+    let pong = reply(&mut reader).await?;
+    if pong != "PONG" {
+        bail!(tr!(
+            "PING 的回复异常: {}",
+            "Unexpected PING reply: {}",
+            pong
+        ));
+    }
+    let _ = reader.get_mut().write_all(&command(&["QUIT"])).await;
+    Ok(pong)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::checks::database::{self, DbEngine};
+    use crate::checks::target::Target;
+    use crate::ServerStatus;
+    use std::time::Duration;
+    use tokio::io::AsyncReadExt;
+    use tokio::net::TcpListener;
+
+    // 只接受密码 secret 的假 Redis
+    async fn fake_redis() -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    let mut authed = false;
+                    let mut buffer = [0u8; 256];
+                    while let Ok(n) = socket.read(&mut buffer).await {
+                        if n == 0 {
+                            break;
+                        }
+                        let request = String::from_utf8_lossy(&buffer[..n]).to_string();
+                        let response: &[u8] = if request.contains("AUTH") {
+                            authed = request.contains("secret");
+                            if authed {
+                                b"+OK\r\n"
+                            } else {
+                                b"-WRONGPASS invalid password\r\n"
+                            }
+                        } else if request.contains("PING") && authed {
+                            b"+PONG\r\n"
+                        } else if request.contains("PING") {
+                            b"-NOAUTH Authentication required.\r\n"
+                        } else {
+                            b"+OK\r\n"
+                        };
+                        let _ = socket.write_all(response).await;
+                    }
+                });
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn pings_with_and_without_auth() {
+        let port = fake_redis().await;
+        let mut check = DatabaseCheck {
+            engine: DbEngine::Redis,
+            port,
+            ..Default::default()
+        };
+        let timeout = Duration::from_secs(5);
+
+        let outcome = database::check(Target::new("127.0.0.1"), &check, timeout).await;
+        assert_eq!(outcome.status, ServerStatus::Error(1));
+        assert!(outcome.detail.contains("NOAUTH"));
+
+        check.password = "secret".to_string();
+        let outcome = database::check(Target::new("127.0.0.1"), &check, timeout).await;
+        assert_eq!(outcome.status, ServerStatus::Online);
+        assert_eq!(outcome.detail, "PONG");
+
+        check.password = "wrong".to_string();
+        let outcome = database::check(Target::new("127.0.0.1"), &check, timeout).await;
+        assert!(outcome.detail.contains("WRONGPASS"));
+    }
+}
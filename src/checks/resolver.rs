@@ -0,0 +1,284 @@
+use anyhow::{anyhow, bail, Result};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use super::dual_stack::IpFamily;
+use super::target::udp_connect;
+use crate::i18n::tr;
+
+const TYPE_A: u16 = 1;
+const TYPE_AAAA: u16 = 28;
+// 回复被截断（TC）的标志位
+const FLAG_TC: u16 = 0x0200;
+const RETRANSMIT: Duration = Duration::from_secs(1);
+
+// 指定的DNS服务器，例如 10.0.0.53、10.0.0.53:5353、[2001:4860:4860::8888]:53
+pub fn parse_server(text: &str) -> Result<SocketAddr> {
+    let text = text.trim();
+    if let Ok(addr) = text.parse::<SocketAddr>() {
+        return Ok(addr);
+    }
+    let ip = text
+        .trim_matches(['[', ']'])
+        .parse::<IpAddr>()
+        .map_err(|_| {
+            anyhow!(tr!(
+                "DNS服务器需要填写IP地址: {}",
+                "DNS server must be an IP address: {}",
+                text
+            ))
+        })?;
+    Ok(SocketAddr::new(ip, 53))
+}
+
+fn query(id: u16, host: &str, qtype: u16) -> Result<Vec<u8>> {
+    // 标志只设置 RD（期望递归）
+    let mut packet = vec![];
+    packet.extend_from_slice(&id.to_be_bytes());
+    packet.extend_from_slice(&[0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0]);
+    for label in host.trim_end_matches('.').split('.') {
+        if label.is_empty() || label.len() > 63 {
+            bail!(tr!("无效的主机名 {}", "Invalid hostname {}", host));
+        }
+        packet.push(label.len() as u8);
+        packet.extend_from_slice(label.as_bytes());
+    }
+    packet.push(0);
+    packet.extend_from_slice(&qtype.to_be_bytes());
+    packet.extend_from_slice(&1u16.to_be_bytes());
+    Ok(packet)
+}
+
+// 跳过一个可能带压缩指针的名称，返回之后的位置
+fn skip_name(packet: &[u8], mut pos: usize) -> Option<usize> {
+    loop {
+        let len = *packet.get(pos)?;
+        match len {
+            0 => return Some(pos + 1),
+            len if len & 0xc0 == 0xc0 => return Some(pos + 2),
+            len => pos += 1 + len as usize,
+        }
+    }
+}
+
+fn read_u16(packet: &[u8], pos: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([
+        *packet.get(pos)?,
+        *packet.get(pos + 1)?,
+    ]))
+}
+
+// 回答部分中的 A/AAAA 记录；CNAME 由递归服务器一起返回，这里直接忽略
+fn parse_answers(packet: &[u8]) -> Result<Vec<IpAddr>> {
+    let invalid = || anyhow!(tr!("DNS回复格式无效", "Malformed DNS reply"));
+    let flags = read_u16(packet, 2).ok_or_else(invalid)?;
+    match flags & 0x0f {
+        0 => {}
+        3 => bail!(tr!("域名不存在", "Name does not exist")),
+        rcode => bail!(tr!("DNS服务器返回错误 {}", "DNS server error {}", rcode)),
+    }
+    let questions = read_u16(packet, 4).ok_or_else(invalid)?;
+    let answers = read_u16(packet, 6).ok_or_else(invalid)?;
+    let mut pos = 12;
+    for _ in 0..questions {
+        pos = skip_name(packet, pos).ok_or_else(invalid)? + 4;
+    }
+    let mut ips = Vec::new();
+    for _ in 0..answers {
+        pos = skip_name(packet, pos).ok_or_else(invalid)?;
+        let rtype = read_u16(packet, pos).ok_or_else(invalid)?;
+        let len = read_u16(packet, pos + 8).ok_or_else(invalid)? as usize;
+        let data = packet.get(pos + 10..pos + 10 + len).ok_or_else(invalid)?;
+        match (rtype, data.len()) {
+            (TYPE_A, 4) => ips.push(IpAddr::V4(Ipv4Addr::new(
+                data[0], data[1], data[2], data[3],
+            ))),
+            (TYPE_AAAA, 16) => {
+                let octets: [u8; 16] = data.try_into().map_err(|_| invalid())?;
+                ips.push(IpAddr::V6(Ipv6Addr::from(octets)));
+            }
+            _ => {}
+        }
+        pos += 10 + len;
+    }
+    Ok(ips)
+}
+
+// 回复被截断时改用TCP重新查询，TCP的报文前有两字节长度
+async fn query_tcp(server: SocketAddr, request: &[u8]) -> Result<Vec<u8>> {
+    let mut stream = TcpStream::connect(server).await?;
+    let mut framed = (request.len() as u16).to_be_bytes().to_vec();
+    framed.extend_from_slice(request);
+    stream.write_all(&framed).await?;
+    let len = stream.read_u16().await? as usize;
+    let mut reply = vec![0; len];
+    stream.read_exact(&mut reply).await?;
+    Ok(reply)
+}
+
+// 向指定的DNS服务器同时查询 A 和 AAAA 记录，IPv4地址在前；没有回复的查询每秒重发一次，
+// 由调用方限制总时间。只需要一个地址时，首选的协议族有地址就返回，不等另一个
+pub async fn lookup(server: &str, host: &str, family: IpFamily) -> Result<Vec<IpAddr>> {
+    if let Ok(ip) = host.parse::<IpAddr>() {
+        return Ok(vec![ip]);
+    }
+    let server = parse_server(server)?;
+    let socket = udp_connect(server).await?;
+    let id = (uuid::Uuid::new_v4().as_u128() as u16) & 0xfffe;
+    let queries = [query(id, host, TYPE_A)?, query(id + 1, host, TYPE_AAAA)?];
+    let primary = match family {
+        IpFamily::Both => None,
+        IpFamily::PreferIpv6 => Some(1),
+        IpFamily::Auto | IpFamily::PreferIpv4 => Some(0),
+    };
+
+    let mut replies: [Option<Result<Vec<IpAddr>>>; 2] = [None, None];
+    let finished = |replies: &[Option<Result<Vec<IpAddr>>>; 2]| {
+        replies.iter().all(Option::is_some)
+            || primary.is_some_and(|i| matches!(&replies[i], Some(Ok(ips)) if !ips.is_empty()))
+    };
+    let mut buf = vec![0u8; 4096];
+    while !finished(&replies) {
+        for (request, reply) in queries.iter().zip(&replies) {
+            if reply.is_none() {
+                socket.send(request).await?;
+            }
+        }
+        let deadline = tokio::time::Instant::now() + RETRANSMIT;
+        while let Ok(received) = tokio::time::timeout_at(deadline, socket.recv(&mut buf)).await {
+            let reply = &buf[..received?];
+            // 不是这次查询的回复或重复的回复时忽略
+            let Some(index) = read_u16(reply, 0)
+                .and_then(|reply_id| reply_id.checked_sub(id))
+                .filter(|i| *i < 2)
+                .map(usize::from)
+                .filter(|i| replies[*i].is_none())
+            else {
+                continue;
+            };
+            let truncated = read_u16(reply, 2).is_some_and(|flags| flags & FLAG_TC != 0);
+            replies[index] = Some(if truncated {
+                query_tcp(server, &queries[index])
+                    .await
+                    .and_then(|reply| parse_answers(&reply))
+            } else {
+                parse_answers(reply)
+            });
+            if finished(&replies) {
+                break;
+            }
+        }
+    }
+    let mut ips = Vec::new();
+    let mut error = None;
+    for result in replies.into_iter().flatten() {
+        match result {
+            Ok(found) => ips.extend(found),
+            Err(e) => error = Some(e),
+        }
+    }
+    match error {
+        Some(e) if ips.is_empty() => Err(e),
+        _ if ips.is_empty() => bail!(tr!("没有解析到地址", "No addresses found")),
+        _ => Ok(ips),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::UdpSocket;
+
+    // 把查询原样返回并加上一条回答，名称使用指向问题的压缩指针
+    fn answer(request: &[u8], data: &[u8]) -> Vec<u8> {
+        let qtype = read_u16(request, request.len() - 4).unwrap();
+        let mut reply = request.to_vec();
+        reply[2] = 0x81;
+        reply[3] = 0x80;
+        reply[7] = 1;
+        reply.extend_from_slice(&[0xc0, 12]);
+        reply.extend_from_slice(&qtype.to_be_bytes());
+        reply.extend_from_slice(&[0, 1, 0, 0, 0, 60]);
+        reply.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        reply.extend_from_slice(data);
+        reply
+    }
+
+    #[test]
+    fn parses_server_and_errors() {
+        assert_eq!(
+            parse_server("10.0.0.53").unwrap(),
+            "10.0.0.53:53".parse().unwrap()
+        );
+        assert_eq!(parse_server("[::1]:5353").unwrap().port(), 5353);
+        assert!(parse_server("dns.example.com").is_err());
+
+        let mut reply = query(7, "missing.example", TYPE_A).unwrap();
+        reply[3] = 0x83;
+        assert!(parse_answers(&reply).is_err());
+        assert!(parse_answers(&reply[..5]).is_err());
+    }
+
+    // 假的DNS服务器：丢掉第一个 A 查询，AAAA 的UDP回复设置TC标志，完整的回答只通过TCP给出；
+    // v4only 开头的名称不回复 AAAA
+    #[tokio::test]
+    async fn resolves_through_custom_server() {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            let mut dropped = false;
+            loop {
+                let (n, peer) = socket.recv_from(&mut buf).await.unwrap();
+                let request = &buf[..n];
+                let reply = match read_u16(request, n - 4).unwrap() {
+                    TYPE_A if !std::mem::replace(&mut dropped, true) => continue,
+                    TYPE_A => answer(request, &[10, 0, 0, 5]),
+                    _ if request[13..].starts_with(b"v4only") => continue,
+                    _ => {
+                        let mut reply = request.to_vec();
+                        reply[2] = 0x83;
+                        reply
+                    }
+                };
+                socket.send_to(&reply, peer).await.unwrap();
+            }
+        });
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let len = stream.read_u16().await.unwrap() as usize;
+                let mut request = vec![0; len];
+                stream.read_exact(&mut request).await.unwrap();
+                let reply = answer(&request, &Ipv6Addr::LOCALHOST.octets());
+                stream
+                    .write_all(&(reply.len() as u16).to_be_bytes())
+                    .await
+                    .unwrap();
+                stream.write_all(&reply).await.unwrap();
+            }
+        });
+        let server = addr.to_string();
+        let ips = lookup(&server, "app.internal", IpFamily::Both)
+            .await
+            .unwrap();
+        assert_eq!(
+            ips,
+            [
+                "10.0.0.5".parse::<IpAddr>().unwrap(),
+                IpAddr::V6(Ipv6Addr::LOCALHOST)
+            ]
+        );
+
+        // 只需要IPv4时不等 AAAA 的回复
+        let lookup = lookup(&server, "v4only.internal", IpFamily::PreferIpv4);
+        let ips = tokio::time::timeout(Duration::from_secs(3), lookup)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(ips, ["10.0.0.5".parse::<IpAddr>().unwrap()]);
+    }
+}
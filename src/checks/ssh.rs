@@ -0,0 +1,272 @@
+use anyhow::{anyhow, bail, Result};
+use russh::client::{self, Handle};
+use russh::keys::{self, PrivateKeyWithHashAlg, PublicKeyOrCertificate};
+use russh::{ChannelMsg, Disconnect};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use super::target::Target;
+use super::CheckOutcome;
+use crate::i18n::tr;
+use crate::{secret, ServerStatus};
+
+// 通过SSH执行命令，根据退出码判断状态（基于密钥认证）
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SshCheck {
+    pub user: String,
+    pub port: u16,
+    // 私钥文件路径，例如 ~/.ssh/id_ed25519
+    pub key_path: String,
+    // 私钥密码，可以填 keyring: 或 env: 引用
+    #[serde(default)]
+    pub key_passphrase: String,
+    // 主机密钥不在 known_hosts 中时信任并记录下来，否则拒绝连接
+    #[serde(default)]
+    pub trust_unknown_host: bool,
+    pub command: String,
+    // 输出中必须包含的文本，留空表示只看退出码
+    #[serde(default)]
+    pub expect: String,
+}
+
+impl Default for SshCheck {
+    fn default() -> Self {
+        Self {
+            user: "root".to_string(),
+            port: 22,
+            key_path: default_key_path(),
+            key_passphrase: String::new(),
+            trust_unknown_host: false,
+            command: "systemctl is-active nginx".to_string(),
+            expect: String::new(),
+        }
+    }
+}
+
+fn default_key_path() -> String {
+    std::env::var("HOME")
+        .or_else(|_| std::env::var("USERPROFILE"))
+        .map(|home| format!("{}/.ssh/id_ed25519", home))
+        .unwrap_or_default()
+}
+
+// 展开路径开头的 ~
+pub fn expand_home(path: &str) -> String {
+    match path.strip_prefix("~/") {
+        Some(rest) => std::env::var("HOME")
+            .or_else(|_| std::env::var("USERPROFILE"))
+            .map(|home| format!("{}/{}", home, rest))
+            .unwrap_or_else(|_| path.to_string()),
+        None => path.to_string(),
+    }
+}
+
+// 没有指定 known_hosts 文件时，设置这个环境变量则使用它，而不是 ~/.ssh/known_hosts，
+// 适合在主目录不保留的容器中运行
+const KNOWN_HOSTS_ENV: &str = "SERVERCHECK_KNOWN_HOSTS";
+
+fn default_known_hosts() -> Option<PathBuf> {
+    std::env::var_os(KNOWN_HOSTS_ENV)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+pub struct SshClient {
+    host: String,
+    port: u16,
+    trust_unknown: bool,
+    // 为空时使用 ~/.ssh/known_hosts
+    known_hosts: Option<PathBuf>,
+    // 拒绝主机密钥的原因，连接失败时代替 russh 的错误显示
+    rejected: Arc<Mutex<Option<String>>>,
+}
+
+impl SshClient {
+    fn reject(&self, reason: String) -> Result<bool, russh::Error> {
+        *self.rejected.lock().unwrap() = Some(reason);
+        Ok(false)
+    }
+}
+
+impl client::Handler for SshClient {
+    type Error = russh::Error;
+
+    // 主机密钥与 known_hosts 中的记录不一致时拒绝连接；没有记录的主机只在设置了信任时接受，并记录到 known_hosts
+    async fn check_server_key(
+        &mut self,
+        server_key: &PublicKeyOrCertificate,
+    ) -> Result<bool, Self::Error> {
+        // 证书形式的主机密钥无法与 known_hosts 比对，同样只在设置了信任时接受
+        let PublicKeyOrCertificate::PublicKey { key, .. } = server_key else {
+            if self.trust_unknown {
+                return Ok(true);
+            }
+            return self.reject(
+                tr!(
+                    "无法验证证书形式的主机密钥",
+                    "Cannot verify a host certificate"
+                )
+                .to_string(),
+            );
+        };
+        let known = match &self.known_hosts {
+            Some(path) => keys::check_known_hosts_path(&self.host, self.port, key, path),
+            None => keys::check_known_hosts(&self.host, self.port, key),
+        };
+        match known {
+            Ok(true) => Ok(true),
+            Err(keys::Error::KeyChanged { line }) => self.reject(tr!(
+                "{}:{} 的主机密钥与 known_hosts 第{}行不一致，拒绝连接",
+                "The host key of {}:{} does not match line {} of known_hosts, refusing to connect",
+                self.host,
+                self.port,
+                line
+            )),
+            Ok(false) if self.trust_unknown => {
+                let learned = match &self.known_hosts {
+                    Some(path) => {
+                        keys::known_hosts::learn_known_hosts_path(&self.host, self.port, key, path)
+                    }
+                    None => keys::known_hosts::learn_known_hosts(&self.host, self.port, key),
+                };
+                if let Err(e) = learned {
+                    eprintln!("无法把 {} 的主机密钥写入 known_hosts: {}", self.host, e);
+                }
+                Ok(true)
+            }
+            Ok(false) => self.reject(tr!(
+                "{}:{} 的主机密钥不在 known_hosts 中，请先用 ssh 连接一次，或在设置中选择信任新主机",
+                "The host key of {}:{} is not in known_hosts; connect once with ssh first, or choose to trust new hosts",
+                self.host,
+                self.port
+            )),
+            Err(e) => self.reject(tr!(
+                "无法读取 known_hosts: {}",
+                "Cannot read known_hosts: {}",
+                e
+            )),
+        }
+    }
+}
+
+// 建立SSH连接并完成密钥认证；trust_unknown 为 true 时接受 known_hosts 中没有记录的主机。
+// 主机密钥按 target 的主机名和 port 核对，即使实际连接的是隧道的转发端口
+pub async fn connect(
+    target: Target<'_>,
+    port: u16,
+    user: &str,
+    key_path: &str,
+    passphrase: &str,
+    trust_unknown: bool,
+    known_hosts: Option<&Path>,
+) -> Result<Handle<SshClient>> {
+    let passphrase = secret::resolve_async(passphrase).await?;
+    let passphrase = (!passphrase.is_empty()).then_some(passphrase.as_str());
+    let key = keys::load_secret_key(expand_home(key_path), passphrase)
+        .map_err(|e| anyhow!(tr!("无法读取私钥: {}", "Cannot read private key: {}", e)))?;
+
+    let config = Arc::new(client::Config {
+        inactivity_timeout: Some(Duration::from_secs(10)),
+        ..Default::default()
+    });
+    let rejected = Arc::new(Mutex::new(None));
+    let handler = SshClient {
+        host: target.name().to_string(),
+        port,
+        trust_unknown,
+        known_hosts: known_hosts
+            .map(Path::to_path_buf)
+            .or_else(default_known_hosts),
+        rejected: Arc::clone(&rejected),
+    };
+
+    let stream = target.connect(port).await?;
+    let mut session = match client::connect_stream(config, stream, handler).await {
+        Ok(session) => session,
+        Err(e) => match rejected.lock().unwrap().take() {
+            Some(reason) => bail!(reason),
+            None => return Err(e.into()),
+        },
+    };
+    let hash = session.best_supported_rsa_hash().await?.flatten();
+    let auth = session
+        .authenticate_publickey(user, PrivateKeyWithHashAlg::new(Arc::new(key), hash))
+        .await?;
+    if !auth.success() {
+        bail!(tr!("SSH认证失败", "SSH authentication failed"));
+    }
+    Ok(session)
+}
+
+// 在已建立的会话上执行命令，返回退出码和输出
+pub async fn exec(session: &Handle<SshClient>, command: &str) -> Result<(u32, String)> {
+    let mut channel = session.channel_open_session().await?;
+    channel.exec(true, command).await?;
+
+    let mut output = Vec::new();
+    let mut code = None;
+    while let Some(msg) = channel.wait().await {
+        match msg {
+            ChannelMsg::Data { ref data } | ChannelMsg::ExtendedData { ref data, .. } => {
+                output.extend_from_slice(data)
+            }
+            ChannelMsg::ExitStatus { exit_status } => code = Some(exit_status),
+            _ => {}
+        }
+    }
+
+    let code =
+        code.ok_or_else(|| anyhow!(tr!("命令未正常退出", "Command did not exit normally")))?;
+    Ok((code, String::from_utf8_lossy(&output).into_owned()))
+}
+
+async fn run(target: Target<'_>, check: &SshCheck) -> Result<(u32, String)> {
+    let session = connect(
+        target,
+        check.port,
+        &check.user,
+        &check.key_path,
+        &check.key_passphrase,
+        check.trust_unknown_host,
+        None,
+    )
+    .await?;
+    let result = exec(&session, &check.command).await;
+    let _ = session
+        .disconnect(Disconnect::ByApplication, "", "English")
+        .await;
+    result
+}
+
+pub async fn check(target: Target<'_>, check: &SshCheck, timeout: Duration) -> CheckOutcome {
+    let result = match tokio::time::timeout(timeout, run(target, check)).await {
+        Ok(result) => result,
+        Err(_) => return CheckOutcome::timeout(tr!("SSH检查超时", "SSH check timed out")),
+    };
+
+    match result {
+        Ok((code, output)) => {
+            let first_line = output.lines().next().unwrap_or("").trim().to_string();
+            if code != 0 {
+                // 被信号终止等情况下退出码可能超出范围，显示为最大值
+                let status = ServerStatus::Error(u16::try_from(code).unwrap_or(u16::MAX));
+                CheckOutcome::new(status, first_line)
+            } else if !check.expect.is_empty() && !output.contains(&check.expect) {
+                CheckOutcome::new(
+                    ServerStatus::Error(1),
+                    tr!(
+                        "输出不包含 \"{}\": {}",
+                        "Output does not contain \"{}\": {}",
+                        check.expect,
+                        first_line
+                    ),
+                )
+            } else {
+                CheckOutcome::new(ServerStatus::Online, first_line)
+            }
+        }
+        Err(e) => CheckOutcome::new(ServerStatus::Offline, e.to_string()),
+    }
+}
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use tokio::io::AsyncReadExt;
+use tokio::net::TcpStream;
+
+use super::target::Target;
+use super::CheckOutcome;
+use crate::i18n::tr;
+use crate::ServerStatus;
+
+// 只连接SSH端口读取服务器发来的版本标识（如 SSH-2.0-OpenSSH_9.6），不需要账号和密钥
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SshBannerCheck {
+    pub port: u16,
+}
+
+impl Default for SshBannerCheck {
+    fn default() -> Self {
+        Self { port: 22 }
+    }
+}
+
+// RFC 4253 规定版本行最长255个字符，之前允许有其他文字行
+const MAX_BANNER_BYTES: usize = 4096;
+
+// 读到以 SSH- 开头的一行为止，返回去掉换行的版本标识
+async fn read_banner(stream: &mut TcpStream) -> std::io::Result<Option<String>> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 512];
+    loop {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            return Ok(None);
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        let text = String::from_utf8_lossy(&buffer);
+        let mut lines: Vec<&str> = text.split('\n').collect();
+        // 最后一段还没有读完整
+        let partial = lines.pop().unwrap_or("");
+        if let Some(line) = lines.iter().find(|l| l.starts_with("SSH-")) {
+            return Ok(Some(line.trim_end_matches('\r').to_string()));
+        }
+        if buffer.len() >= MAX_BANNER_BYTES {
+            return Ok(partial
+                .starts_with("SSH-")
+                .then(|| partial.trim_end().to_string()));
+        }
+    }
+}
+
+pub async fn check(target: Target<'_>, check: &SshBannerCheck, timeout: Duration) -> CheckOutcome {
+    let probe = async {
+        let mut stream = target.connect(check.port).await?;
+        read_banner(&mut stream).await
+    };
+    match tokio::time::timeout(timeout, probe).await {
+        Ok(Ok(Some(banner))) => CheckOutcome::new(ServerStatus::Online, banner),
+        Ok(Ok(None)) => CheckOutcome::new(
+            ServerStatus::Error(1),
+            tr!(
+                "端口已连接，但没有收到SSH版本标识",
+                "Connected, but no SSH version banner was received"
+            ),
+        ),
+        Ok(Err(e)) => CheckOutcome::new(ServerStatus::Offline, e.to_string()),
+        Err(_) => CheckOutcome::timeout(tr!(
+            "等待SSH版本标识超时",
+            "Timed out waiting for the SSH banner"
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+    use tokio::net::TcpListener;
+
+    // 在本机端口上发送 greeting 后关闭连接
+    async fn serve(greeting: &'static [u8]) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            socket.write_all(greeting).await.unwrap();
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn reads_the_version_banner() {
+        let port = serve(b"welcome\r\nSSH-2.0-OpenSSH_9.6p1 Ubuntu-3\r\n").await;
+        let outcome = check(
+            Target::new("127.0.0.1"),
+            &SshBannerCheck { port },
+            Duration::from_secs(5),
+        )
+        .await;
+        assert_eq!(outcome.status, ServerStatus::Online);
+        assert_eq!(outcome.detail, "SSH-2.0-OpenSSH_9.6p1 Ubuntu-3");
+
+        let port = serve(b"HTTP/1.1 400 Bad Request\r\n\r\n").await;
+        let outcome = check(
+            Target::new("127.0.0.1"),
+            &SshBannerCheck { port },
+            Duration::from_secs(5),
+        )
+        .await;
+        assert_eq!(outcome.status, ServerStatus::Error(1));
+    }
+}
@@ -0,0 +1,277 @@
+use anyhow::Result;
+use russh::Disconnect;
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::target::Target;
+use super::{ssh, CheckOutcome};
+use crate::i18n::tr;
+use crate::ServerStatus;
+
+// 查询systemd单元（服务、socket、定时器等）的运行状态：本机通过D-Bus，远程主机通过SSH执行 systemctl。
+// 适合只监听 localhost、从外部无法访问的服务
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct SystemdCheck {
+    // 单元名，例如 nginx、docker.socket；没有后缀时按 .service 查询
+    pub unit: String,
+    // 通过SSH查询服务器的IP，否则查询运行本程序的机器
+    pub remote: bool,
+    pub user: String,
+    pub port: u16,
+    pub key_path: String,
+    pub key_passphrase: String,
+    pub trust_unknown_host: bool,
+}
+
+impl Default for SystemdCheck {
+    fn default() -> Self {
+        let ssh = ssh::SshCheck::default();
+        Self {
+            unit: "nginx.service".to_string(),
+            remote: false,
+            user: ssh.user,
+            port: ssh.port,
+            key_path: ssh.key_path,
+            key_passphrase: ssh.key_passphrase,
+            trust_unknown_host: ssh.trust_unknown_host,
+        }
+    }
+}
+
+impl SystemdCheck {
+    pub fn unit_name(&self) -> String {
+        let unit = self.unit.trim();
+        if unit.contains('.') {
+            unit.to_string()
+        } else {
+            format!("{}.service", unit)
+        }
+    }
+}
+
+// 单元的加载状态和运行状态，对应 LoadState、ActiveState、SubState 属性
+#[derive(Debug, Clone, Default, PartialEq)]
+struct UnitState {
+    load: String,
+    active: String,
+    sub: String,
+}
+
+impl UnitState {
+    // active 为在线，正在启动或重新加载为降级，找不到单元时显示为错误，其他为离线
+    fn outcome(&self) -> CheckOutcome {
+        if self.load == "not-found" {
+            return CheckOutcome::new(
+                ServerStatus::Error(404),
+                tr!("单元不存在", "Unit not found"),
+            );
+        }
+        let detail = format!("{} ({})", self.active, self.sub);
+        let status = match self.active.as_str() {
+            "active" => ServerStatus::Online,
+            "activating" | "reloading" => ServerStatus::Degraded,
+            _ => ServerStatus::Offline,
+        };
+        CheckOutcome::new(status, detail)
+    }
+
+    // systemctl show 输出的 键=值 行
+    fn parse(output: &str) -> Self {
+        let mut state = Self::default();
+        for line in output.lines() {
+            let Some((key, value)) = line.trim().split_once('=') else {
+                continue;
+            };
+            match key {
+                "LoadState" => state.load = value.to_string(),
+                "ActiveState" => state.active = value.to_string(),
+                "SubState" => state.sub = value.to_string(),
+                _ => {}
+            }
+        }
+        state
+    }
+}
+
+// 单元名会拼进远程命令，只允许systemd单元名中合法的字符
+fn valid_unit_name(unit: &str) -> bool {
+    !unit.is_empty()
+        && !unit.starts_with('-')
+        && unit
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || ":-_.@\\".contains(c))
+}
+
+// 系统总线的连接在检查之间复用，查询失败时丢弃，下次重新连接
+#[cfg(target_os = "linux")]
+static SYSTEM_BUS: std::sync::LazyLock<std::sync::Mutex<Option<zbus::Connection>>> =
+    std::sync::LazyLock::new(Default::default);
+
+#[cfg(target_os = "linux")]
+async fn system_bus() -> Result<zbus::Connection> {
+    if let Some(connection) = SYSTEM_BUS.lock().unwrap().clone() {
+        return Ok(connection);
+    }
+    let connection = zbus::Connection::system().await?;
+    *SYSTEM_BUS.lock().unwrap() = Some(connection.clone());
+    Ok(connection)
+}
+
+#[cfg(target_os = "linux")]
+async fn query_local(unit: &str) -> Result<UnitState> {
+    let connection = system_bus().await?;
+    let result = query_bus(&connection, unit).await;
+    if result.is_err() {
+        SYSTEM_BUS.lock().unwrap().take();
+    }
+    result
+}
+
+#[cfg(target_os = "linux")]
+async fn query_bus(connection: &zbus::Connection, unit: &str) -> Result<UnitState> {
+    use zbus::zvariant::OwnedObjectPath;
+
+    const DESTINATION: &str = "org.freedesktop.systemd1";
+    let manager = zbus::Proxy::new(
+        connection,
+        DESTINATION,
+        "/org/freedesktop/systemd1",
+        "org.freedesktop.systemd1.Manager",
+    )
+    .await?;
+    // LoadUnit 对没有在运行的单元也能返回路径，GetUnit 则会报错
+    let path: OwnedObjectPath = manager.call("LoadUnit", &(unit,)).await?;
+    let proxy = zbus::Proxy::new(
+        connection,
+        DESTINATION,
+        path.into_inner(),
+        "org.freedesktop.systemd1.Unit",
+    )
+    .await?;
+    Ok(UnitState {
+        load: proxy.get_property("LoadState").await?,
+        active: proxy.get_property("ActiveState").await?,
+        sub: proxy.get_property("SubState").await?,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+async fn query_local(_unit: &str) -> Result<UnitState> {
+    anyhow::bail!(tr!(
+        "本机查询只支持Linux，请改为通过SSH查询",
+        "Local queries are only supported on Linux; query over SSH instead"
+    ))
+}
+
+// 查询失败（没有权限、总线或 systemctl 报错等）为错误
+fn failed(e: impl std::fmt::Display) -> CheckOutcome {
+    CheckOutcome::new(ServerStatus::Error(1), e.to_string())
+}
+
+// 与SSH命令检查一样，连不上主机为离线
+async fn query_remote(
+    target: Target<'_>,
+    check: &SystemdCheck,
+    unit: &str,
+) -> Result<UnitState, CheckOutcome> {
+    let session = ssh::connect(
+        target,
+        check.port,
+        &check.user,
+        &check.key_path,
+        &check.key_passphrase,
+        check.trust_unknown_host,
+        None,
+    )
+    .await
+    .map_err(|e| CheckOutcome::new(ServerStatus::Offline, e.to_string()))?;
+    let command = format!(
+        "systemctl show --property=LoadState,ActiveState,SubState -- '{}'",
+        unit
+    );
+    let result = ssh::exec(&session, &command).await;
+    let _ = session
+        .disconnect(Disconnect::ByApplication, "", "English")
+        .await;
+    let (code, output) = result.map_err(failed)?;
+    let state = UnitState::parse(&output);
+    if code != 0 || state.active.is_empty() {
+        return Err(failed(output.lines().next().unwrap_or("").trim()));
+    }
+    Ok(state)
+}
+
+pub async fn check(target: Target<'_>, check: &SystemdCheck, timeout: Duration) -> CheckOutcome {
+    let unit = check.unit_name();
+    if !valid_unit_name(&unit) {
+        return CheckOutcome::new(
+            ServerStatus::Error(1),
+            tr!("无效的单元名: {}", "Invalid unit name: {}", unit),
+        );
+    }
+    let query = async {
+        if check.remote {
+            query_remote(target, check, &unit).await
+        } else {
+            query_local(&unit).await.map_err(failed)
+        }
+    };
+    match tokio::time::timeout(timeout, query).await {
+        Ok(Ok(state)) => state.outcome(),
+        Ok(Err(outcome)) => outcome,
+        Err(_) => CheckOutcome::timeout(tr!("systemd查询超时", "systemd query timed out")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_unit_states() {
+        let state = UnitState::parse("LoadState=loaded\nActiveState=active\nSubState=running\n");
+        let outcome = state.outcome();
+        assert_eq!(outcome.status, ServerStatus::Online);
+        assert_eq!(outcome.detail, "active (running)");
+
+        for (output, status) in [
+            (
+                "ActiveState=failed\nSubState=failed\nLoadState=loaded",
+                ServerStatus::Offline,
+            ),
+            (
+                "LoadState=loaded\nActiveState=activating\nSubState=start",
+                ServerStatus::Degraded,
+            ),
+            (
+                "LoadState=not-found\nActiveState=inactive\nSubState=dead",
+                ServerStatus::Error(404),
+            ),
+        ] {
+            assert_eq!(
+                UnitState::parse(output).outcome().status,
+                status,
+                "{}",
+                output
+            );
+        }
+    }
+
+    #[test]
+    fn unit_names() {
+        let mut check = SystemdCheck {
+            unit: " nginx ".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(check.unit_name(), "nginx.service");
+        check.unit = "docker.socket".to_string();
+        assert_eq!(check.unit_name(), "docker.socket");
+
+        assert!(valid_unit_name("getty@tty1.service"));
+        assert!(valid_unit_name("dev-disk-by\\x2duuid.swap"));
+        for name in ["", "--help.service", "a'; rm -rf /.service", "a b.service"] {
+            assert!(!valid_unit_name(name), "{}", name);
+        }
+    }
+}
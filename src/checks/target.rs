@@ -0,0 +1,86 @@
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use tokio::net::{TcpStream, ToSocketAddrs, UdpSocket};
+
+use crate::i18n::tr;
+
+// 检查的目标主机。host 是服务器地址，TLS的SNI和证书校验、SSH的 known_hosts 都用它；
+// 按IP版本检查或经SSH隧道时实际连接的是另一个地址
+#[derive(Debug, Clone, Copy)]
+pub struct Target<'a> {
+    pub host: &'a str,
+    // 按IP版本检查时连接这个地址，端口仍用检查设置的
+    pub ip: Option<IpAddr>,
+    // 经SSH隧道时连接本机的转发端口
+    pub forward: Option<SocketAddr>,
+}
+
+impl<'a> Target<'a> {
+    pub fn new(host: &'a str) -> Self {
+        Self {
+            host,
+            ip: None,
+            forward: None,
+        }
+    }
+
+    // 去掉IPv6地址的方括号
+    pub fn name(&self) -> &'a str {
+        self.host.trim_matches(['[', ']'])
+    }
+
+    // 实际连接的地址，为 None 时解析 name
+    pub fn addr(&self, port: u16) -> Option<SocketAddr> {
+        self.forward
+            .or_else(|| self.ip.map(|ip| SocketAddr::new(ip, port)))
+    }
+
+    pub async fn connect(&self, port: u16) -> io::Result<TcpStream> {
+        match self.addr(port) {
+            Some(addr) => TcpStream::connect(addr).await,
+            None => TcpStream::connect((self.name(), port)).await,
+        }
+    }
+
+    // 不校验主机名的检查（UDP、主机指标等）直接使用要连接的IP
+    pub fn address(&self) -> String {
+        match self.ip {
+            Some(ip) => ip.to_string(),
+            None => self.host.to_string(),
+        }
+    }
+
+    // HTTP客户端和请求的URL：URL中保留服务器地址，只把它解析到实际连接的地址
+    pub fn route(
+        &self,
+        builder: reqwest::ClientBuilder,
+        url: &str,
+        port: u16,
+    ) -> (reqwest::ClientBuilder, String) {
+        match (self.addr(port), url::Url::parse(url)) {
+            (Some(addr), Ok(mut parsed)) => {
+                let builder = super::connect_to(builder, &mut parsed, self.name(), addr);
+                (builder, parsed.to_string())
+            }
+            _ => (builder, url.to_string()),
+        }
+    }
+}
+
+// UDP检查共用：先解析地址，再绑定同一协议族的本地端口并连接，之后只收这个地址的回复
+pub async fn udp_connect(addr: impl ToSocketAddrs) -> io::Result<UdpSocket> {
+    let addr = tokio::net::lookup_host(addr).await?.next().ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::NotFound,
+            tr!("没有解析到地址", "No addresses found"),
+        )
+    })?;
+    let local = if addr.is_ipv6() {
+        SocketAddr::new(Ipv6Addr::UNSPECIFIED.into(), 0)
+    } else {
+        SocketAddr::new(Ipv4Addr::UNSPECIFIED.into(), 0)
+    };
+    let socket = UdpSocket::bind(local).await?;
+    socket.connect(addr).await?;
+    Ok(socket)
+}
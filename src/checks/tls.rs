@@ -0,0 +1,543 @@
+use anyhow::{anyhow, Result};
+use base64::Engine;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone, Utc};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio_rustls::rustls::client::danger::{
+    HandshakeSignatureValid, ServerCertVerified, ServerCertVerifier,
+};
+use tokio_rustls::rustls::crypto::{self, CryptoProvider};
+use tokio_rustls::rustls::pki_types::{CertificateDer, ServerName, UnixTime};
+use tokio_rustls::rustls::{
+    AlertDescription, ClientConfig, DigitallySignedStruct, Error as TlsError, SignatureScheme,
+};
+use tokio_rustls::TlsConnector;
+
+use crate::i18n::tr;
+
+// 只关心协商出的协议和套件，证书是否可信由HTTP检查负责，这里不校验证书
+#[derive(Debug)]
+struct AcceptAnyCert(Arc<CryptoProvider>);
+
+impl ServerCertVerifier for AcceptAnyCert {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &CertificateDer<'_>,
+        _intermediates: &[CertificateDer<'_>],
+        _server_name: &ServerName<'_>,
+        _ocsp_response: &[u8],
+        _now: UnixTime,
+    ) -> Result<ServerCertVerified, tokio_rustls::rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+
+    fn verify_tls12_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        crypto::verify_tls12_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn verify_tls13_signature(
+        &self,
+        message: &[u8],
+        cert: &CertificateDer<'_>,
+        dss: &DigitallySignedStruct,
+    ) -> Result<HandshakeSignatureValid, tokio_rustls::rustls::Error> {
+        crypto::verify_tls13_signature(
+            message,
+            cert,
+            dss,
+            &self.0.signature_verification_algorithms,
+        )
+    }
+
+    fn supported_verify_schemes(&self) -> Vec<SignatureScheme> {
+        self.0.signature_verification_algorithms.supported_schemes()
+    }
+}
+
+// TLS握手结果
+#[derive(Debug, Clone)]
+pub struct TlsInfo {
+    // 例如 TLS 1.3
+    pub version: String,
+    // 例如 TLS13_AES_256_GCM_SHA384
+    pub cipher: String,
+    // 服务器证书的到期时间
+    pub cert_expires: Option<DateTime<Local>>,
+    // 服务器发来的证书链，第一张为服务器证书
+    pub chain: Vec<CertInfo>,
+}
+
+// 证书链中一张证书的主要字段
+#[derive(Debug, Clone, PartialEq)]
+pub struct CertInfo {
+    pub subject: String,
+    pub issuer: String,
+    pub not_before: Option<DateTime<Local>>,
+    pub not_after: Option<DateTime<Local>>,
+    // 证书中的备用名称：域名、IP、邮箱等
+    pub sans: Vec<String>,
+    // 例如 RSA 2048、ECDSA P-256
+    pub key_type: String,
+    pub der: Vec<u8>,
+}
+
+impl CertInfo {
+    pub fn pem(&self) -> String {
+        let encoded = base64::engine::general_purpose::STANDARD.encode(&self.der);
+        let mut pem = String::from("-----BEGIN CERTIFICATE-----\n");
+        for line in encoded.as_bytes().chunks(64) {
+            pem.push_str(std::str::from_utf8(line).unwrap_or_default());
+            pem.push('\n');
+        }
+        pem.push_str("-----END CERTIFICATE-----\n");
+        pem
+    }
+}
+
+// 整条证书链的PEM，可直接交给 openssl 等工具
+pub fn chain_pem(chain: &[CertInfo]) -> String {
+    chain.iter().map(CertInfo::pem).collect()
+}
+
+// 服务器只支持 TLS 1.2 以前的版本时握手失败，与连不上、超时等区分开
+pub fn is_version_mismatch(error: &anyhow::Error) -> bool {
+    let tls = error
+        .downcast_ref::<std::io::Error>()
+        .and_then(|e| e.get_ref())
+        .and_then(|e| e.downcast_ref::<TlsError>());
+    matches!(
+        tls,
+        Some(TlsError::AlertReceived(AlertDescription::ProtocolVersion))
+            | Some(TlsError::PeerIncompatible(_))
+    )
+}
+
+// 单独握手一次，读取协商出的TLS版本和加密套件
+pub async fn probe(host: &str, port: u16, timeout: Duration) -> Result<TlsInfo> {
+    let provider = Arc::new(crypto::ring::default_provider());
+    let config = ClientConfig::builder_with_provider(Arc::clone(&provider))
+        .with_safe_default_protocol_versions()?
+        .dangerous()
+        .with_custom_certificate_verifier(Arc::new(AcceptAnyCert(provider)))
+        .with_no_client_auth();
+    let server_name = ServerName::try_from(host.to_string())?;
+
+    let handshake = async {
+        let stream = TcpStream::connect((host, port)).await?;
+        TlsConnector::from(Arc::new(config))
+            .connect(server_name, stream)
+            .await
+    };
+    let stream = tokio::time::timeout(timeout, handshake)
+        .await
+        .map_err(|_| anyhow!(tr!("TLS握手超时", "TLS handshake timed out")))??;
+
+    let (_, connection) = stream.get_ref();
+    let version = connection
+        .protocol_version()
+        .and_then(|v| v.as_str())
+        .unwrap_or_default()
+        .replace("TLSv1_", "TLS 1.");
+    let cipher = connection
+        .negotiated_cipher_suite()
+        .and_then(|s| s.suite().as_str())
+        .unwrap_or_default()
+        .to_string();
+    let certs = connection.peer_certificates().unwrap_or_default();
+    let cert_expires = certs.first().and_then(|cert| cert_not_after(cert));
+    let chain = certs.iter().filter_map(|cert| parse_cert(cert)).collect();
+    Ok(TlsInfo {
+        version,
+        cipher,
+        cert_expires,
+        chain,
+    })
+}
+
+// 读取一个DER编码的TLV，返回标签、内容和剩余部分
+pub fn read_tlv(input: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = input.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (len, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return None;
+        }
+        let len = rest[..count]
+            .iter()
+            .fold(0usize, |acc, &b| (acc << 8) | b as usize);
+        (len, &rest[count..])
+    };
+    if rest.len() < len {
+        return None;
+    }
+    Some((tag, &rest[..len], &rest[len..]))
+}
+
+// 从DER编码的X.509证书中读取有效期的结束时间（tbsCertificate.validity.notAfter）
+pub fn cert_not_after(der: &[u8]) -> Option<DateTime<Local>> {
+    let (_, cert, _) = read_tlv(der)?;
+    let (_, tbs, _) = read_tlv(cert)?;
+    // 开头是可选的版本号 [0] 和序列号
+    let (tag, _, rest) = read_tlv(tbs)?;
+    let rest = if tag == 0xa0 { read_tlv(rest)?.2 } else { rest };
+    // 跳过签名算法和颁发者
+    let (_, _, rest) = read_tlv(rest)?;
+    let (_, _, rest) = read_tlv(rest)?;
+    let (_, validity, _) = read_tlv(rest)?;
+    let (_, _, validity) = read_tlv(validity)?;
+    let (tag, not_after, _) = read_tlv(validity)?;
+    parse_time(tag, not_after)
+}
+
+fn parse_time(tag: u8, value: &[u8]) -> Option<DateTime<Local>> {
+    let text = std::str::from_utf8(value).ok()?;
+    let text = match tag {
+        // UTCTime 的两位年份：50以下为20xx
+        0x17 if text.len() >= 2 => {
+            let century = if text[..2].parse::<u8>().ok()? < 50 {
+                "20"
+            } else {
+                "19"
+            };
+            format!("{}{}", century, text)
+        }
+        0x18 => text.to_string(),
+        _ => return None,
+    };
+    let time = NaiveDateTime::parse_from_str(&text, "%Y%m%d%H%M%SZ").ok()?;
+    Some(Utc.from_utc_datetime(&time).with_timezone(&Local))
+}
+
+// 常用的对象标识符（DER编码后的内容）
+const OID_RSA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x01];
+const OID_EC: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x02, 0x01];
+const OID_P256: &[u8] = &[0x2a, 0x86, 0x48, 0xce, 0x3d, 0x03, 0x01, 0x07];
+const OID_P384: &[u8] = &[0x2b, 0x81, 0x04, 0x00, 0x22];
+const OID_P521: &[u8] = &[0x2b, 0x81, 0x04, 0x00, 0x23];
+const OID_ED25519: &[u8] = &[0x2b, 0x65, 0x70];
+const OID_ED448: &[u8] = &[0x2b, 0x65, 0x71];
+const OID_SAN: &[u8] = &[0x55, 0x1d, 0x11];
+
+// 点分形式，例如 1.2.840.113549.1.1.1
+pub fn oid_string(oid: &[u8]) -> String {
+    let Some((&first, rest)) = oid.split_first() else {
+        return String::new();
+    };
+    let top = (first / 40).min(2);
+    let mut parts = vec![top as u64, (first - top * 40) as u64];
+    let mut value = 0u64;
+    for &b in rest {
+        value = (value << 7) | (b & 0x7f) as u64;
+        if b & 0x80 == 0 {
+            parts.push(value);
+            value = 0;
+        }
+    }
+    parts
+        .iter()
+        .map(|p| p.to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+fn der_string(tag: u8, value: &[u8]) -> String {
+    match tag {
+        // BMPString 为 UTF-16BE
+        0x1e => {
+            let units: Vec<u16> = value
+                .chunks(2)
+                .map(|c| u16::from_be_bytes([c[0], *c.get(1).unwrap_or(&0)]))
+                .collect();
+            String::from_utf16_lossy(&units)
+        }
+        _ => String::from_utf8_lossy(value).into_owned(),
+    }
+}
+
+// 按证书中的顺序显示，例如 C=CN, O=Example, CN=example.com
+fn name_string(name: &[u8]) -> String {
+    let mut parts = Vec::new();
+    let mut sets = name;
+    while let Some((_, set, rest)) = read_tlv(sets) {
+        sets = rest;
+        let mut attrs = set;
+        while let Some((_, attr, rest)) = read_tlv(attrs) {
+            attrs = rest;
+            let Some((_, oid, value)) = read_tlv(attr) else {
+                continue;
+            };
+            let Some((tag, value, _)) = read_tlv(value) else {
+                continue;
+            };
+            let key = match oid {
+                [0x55, 0x04, 0x03] => "CN".to_string(),
+                [0x55, 0x04, 0x06] => "C".to_string(),
+                [0x55, 0x04, 0x07] => "L".to_string(),
+                [0x55, 0x04, 0x08] => "ST".to_string(),
+                [0x55, 0x04, 0x0a] => "O".to_string(),
+                [0x55, 0x04, 0x0b] => "OU".to_string(),
+                _ => oid_string(oid),
+            };
+            parts.push(format!("{}={}", key, der_string(tag, value)));
+        }
+    }
+    parts.join(", ")
+}
+
+fn key_type(spki: &[u8]) -> String {
+    let Some((_, algorithm, rest)) = read_tlv(spki) else {
+        return String::new();
+    };
+    let Some((_, oid, params)) = read_tlv(algorithm) else {
+        return String::new();
+    };
+    match oid {
+        OID_RSA => {
+            // BIT STRING 的第一个字节是未使用的位数，之后是 SEQUENCE { modulus, exponent }
+            let modulus = read_tlv(rest)
+                .and_then(|(_, bits, _)| read_tlv(bits.get(1..)?))
+                .and_then(|(_, key, _)| read_tlv(key))
+                .map(|(_, modulus, _)| modulus);
+            match modulus.map(|m| {
+                let m = match m.iter().position(|b| *b != 0) {
+                    Some(start) => &m[start..],
+                    None => &[],
+                };
+                m.len() * 8 - m.first().map_or(0, |b| b.leading_zeros() as usize)
+            }) {
+                Some(bits) if bits > 0 => format!("RSA {}", bits),
+                _ => "RSA".to_string(),
+            }
+        }
+        OID_EC => {
+            let curve = match read_tlv(params).map(|(_, curve, _)| curve) {
+                Some(OID_P256) => "P-256".to_string(),
+                Some(OID_P384) => "P-384".to_string(),
+                Some(OID_P521) => "P-521".to_string(),
+                Some(curve) => oid_string(curve),
+                None => String::new(),
+            };
+            format!("ECDSA {}", curve).trim().to_string()
+        }
+        OID_ED25519 => "Ed25519".to_string(),
+        OID_ED448 => "Ed448".to_string(),
+        oid => oid_string(oid),
+    }
+}
+
+// subjectAltName 扩展中的名称
+fn alt_names(value: &[u8]) -> Vec<String> {
+    let mut names = Vec::new();
+    let Some((_, mut entries, _)) = read_tlv(value) else {
+        return names;
+    };
+    while let Some((tag, name, rest)) = read_tlv(entries) {
+        entries = rest;
+        match tag {
+            // rfc822Name、dNSName、URI
+            0x81 | 0x82 | 0x86 => names.push(String::from_utf8_lossy(name).into_owned()),
+            0x87 => {
+                let ip = match name.len() {
+                    4 => <[u8; 4]>::try_from(name).map(std::net::IpAddr::from).ok(),
+                    16 => <[u8; 16]>::try_from(name).map(std::net::IpAddr::from).ok(),
+                    _ => None,
+                };
+                if let Some(ip) = ip {
+                    names.push(ip.to_string());
+                }
+            }
+            _ => {}
+        }
+    }
+    names
+}
+
+// 解析一张DER编码的X.509证书
+pub fn parse_cert(der: &[u8]) -> Option<CertInfo> {
+    let (_, cert, _) = read_tlv(der)?;
+    let (_, tbs, _) = read_tlv(cert)?;
+    let (tag, _, rest) = read_tlv(tbs)?;
+    let rest = if tag == 0xa0 { read_tlv(rest)?.2 } else { rest };
+    let (_, _, rest) = read_tlv(rest)?;
+    let (_, issuer, rest) = read_tlv(rest)?;
+    let (_, validity, rest) = read_tlv(rest)?;
+    let (_, subject, rest) = read_tlv(rest)?;
+    let (_, spki, mut rest) = read_tlv(rest)?;
+
+    let (before_tag, not_before, validity) = read_tlv(validity)?;
+    let (after_tag, not_after, _) = read_tlv(validity)?;
+
+    let mut sans = Vec::new();
+    // 之后是可选的 [1]、[2] 唯一标识和 [3] 扩展
+    while let Some((tag, value, next)) = read_tlv(rest) {
+        rest = next;
+        if tag != 0xa3 {
+            continue;
+        }
+        let Some((_, mut extensions, _)) = read_tlv(value) else {
+            break;
+        };
+        while let Some((_, extension, next)) = read_tlv(extensions) {
+            extensions = next;
+            let Some((_, oid, fields)) = read_tlv(extension) else {
+                continue;
+            };
+            if oid != OID_SAN {
+                continue;
+            }
+            // 跳过可选的 critical 标记
+            let mut fields = fields;
+            while let Some((tag, value, next)) = read_tlv(fields) {
+                fields = next;
+                if tag == 0x04 {
+                    sans = alt_names(value);
+                }
+            }
+        }
+    }
+
+    Some(CertInfo {
+        subject: name_string(subject),
+        issuer: name_string(issuer),
+        not_before: parse_time(before_tag, not_before),
+        not_after: parse_time(after_tag, not_after),
+        sans,
+        key_type: key_type(spki),
+        der: der.to_vec(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncWriteExt;
+
+    fn tlv(tag: u8, content: &[u8]) -> Vec<u8> {
+        let mut out = vec![tag];
+        if content.len() < 0x80 {
+            out.push(content.len() as u8);
+        } else {
+            out.extend([0x82, (content.len() >> 8) as u8, content.len() as u8]);
+        }
+        out.extend_from_slice(content);
+        out
+    }
+
+    // 只包含解析用到的字段的证书结构
+    fn cert_with_validity(not_after: Vec<u8>, with_version: bool) -> Vec<u8> {
+        let mut tbs = Vec::new();
+        if with_version {
+            tbs.extend(tlv(0xa0, &tlv(0x02, &[2])));
+        }
+        tbs.extend(tlv(0x02, &[0x01, 0x23]));
+        tbs.extend(tlv(0x30, &tlv(0x06, &[0x2a, 0x86, 0x48])));
+        // 颁发者足够长，长度需要用多字节表示
+        tbs.extend(tlv(0x30, &[0u8; 200]));
+        let mut validity = tlv(0x17, b"240101000000Z");
+        validity.extend(not_after);
+        tbs.extend(tlv(0x30, &validity));
+        tlv(0x30, &tlv(0x30, &tbs))
+    }
+
+    #[test]
+    fn reads_not_after() {
+        let expected = Utc.with_ymd_and_hms(2030, 6, 1, 12, 0, 0).unwrap();
+        let utc_time = cert_with_validity(tlv(0x17, b"300601120000Z"), true);
+        assert_eq!(cert_not_after(&utc_time).unwrap(), expected);
+        let generalized = cert_with_validity(tlv(0x18, b"20300601120000Z"), false);
+        assert_eq!(cert_not_after(&generalized).unwrap(), expected);
+    }
+
+    // openssl req -x509 -newkey ec -pkeyopt ec_paramgen_curve:P-256 ... 生成的自签名证书
+    const TEST_PEM: &str = "\
+-----BEGIN CERTIFICATE-----\n\
+MIICBjCCAa2gAwIBAgIUPwq4Kjh14nVyzdryP8ceG3/3FKMwCgYIKoZIzj0EAwIw\n\
+PzELMAkGA1UEBhMCQ04xFTATBgNVBAoMDFNlcnZlciBDaGVjazEZMBcGA1UEAwwQ\n\
+dGVzdC5leGFtcGxlLmNvbTAeFw0yNjEwMTQxMDQwMDZaFw0zNjEwMTExMDQwMDZa\n\
+MD8xCzAJBgNVBAYTAkNOMRUwEwYDVQQKDAxTZXJ2ZXIgQ2hlY2sxGTAXBgNVBAMM\n\
+EHRlc3QuZXhhbXBsZS5jb20wWTATBgcqhkjOPQIBBggqhkjOPQMBBwNCAAQY+4S3\n\
+ZWcozteHWEuhEdm+4+fASxKBTOETew/iIuILxe8jgRbNWhAUxO9GxyC4muqVnoNE\n\
+2HsicjVQi3HweMHCo4GGMIGDMB0GA1UdDgQWBBS0yDx0WSMKJIwQjhSq4A70pJZC\n\
+9jAfBgNVHSMEGDAWgBS0yDx0WSMKJIwQjhSq4A70pJZC9jAPBgNVHRMBAf8EBTAD\n\
+AQH/MDAGA1UdEQQpMCeCEHRlc3QuZXhhbXBsZS5jb22CDSouZXhhbXBsZS5jb22H\n\
+BAoAAAEwCgYIKoZIzj0EAwIDRwAwRAIgTIP3rbBAXXVCht07TucDib0wxJ4gqzh1\n\
+DXbZhh1mC7kCIGYKKgiDh5k/PMZic7dwmG3l1gNUU7/pqzRbXzrCEaK3\n\
+-----END CERTIFICATE-----\n\
+";
+
+    #[test]
+    fn parses_certificate_fields() {
+        let body: String = TEST_PEM
+            .lines()
+            .filter(|l| !l.starts_with("-----"))
+            .collect();
+        let der = base64::engine::general_purpose::STANDARD
+            .decode(body)
+            .unwrap();
+        let cert = parse_cert(&der).unwrap();
+        assert_eq!(cert.subject, "C=CN, O=Server Check, CN=test.example.com");
+        assert_eq!(cert.issuer, cert.subject);
+        assert_eq!(
+            cert.sans,
+            vec!["test.example.com", "*.example.com", "10.0.0.1"]
+        );
+        assert_eq!(cert.key_type, "ECDSA P-256");
+        assert_eq!(
+            cert.not_before.unwrap(),
+            Utc.with_ymd_and_hms(2026, 10, 14, 10, 40, 6).unwrap()
+        );
+        assert_eq!(cert.not_after, cert_not_after(&der));
+        assert_eq!(cert.pem(), TEST_PEM);
+        assert_eq!(oid_string(OID_RSA), "1.2.840.113549.1.1.1");
+    }
+
+    #[test]
+    fn rejects_truncated_certificate() {
+        let cert = cert_with_validity(tlv(0x17, b"300601120000Z"), true);
+        assert_eq!(cert_not_after(&cert[..cert.len() - 5]), None);
+        assert_eq!(cert_not_after(&[]), None);
+    }
+
+    #[tokio::test]
+    async fn probe_fails_against_plain_tcp() {
+        // 对方不说TLS：回一段明文后关闭连接
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            if let Ok((mut socket, _)) = listener.accept().await {
+                let _ = socket.write_all(b"HTTP/1.1 400 Bad Request\r\n\r\n").await;
+            }
+        });
+
+        let result = probe("localhost", port, Duration::from_secs(5)).await;
+        assert!(result.is_err());
+    }
+
+    #[tokio::test]
+    async fn probe_times_out() {
+        // 接受连接但不回应握手
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let _held = listener.accept().await;
+            tokio::time::sleep(Duration::from_secs(5)).await;
+        });
+
+        let result = probe("localhost", port, Duration::from_millis(200)).await;
+        assert!(result.is_err());
+    }
+}
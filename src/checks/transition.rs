@@ -0,0 +1,309 @@
+use std::time::Duration;
+
+use super::CheckOutcome;
+use crate::baseline::Baselines;
+use crate::clock::Clock;
+use crate::digest::StatusChange;
+use crate::i18n::tr;
+use crate::{Server, ServerStatus};
+
+// 恢复时自动打开浏览器的最短间隔，避免服务反复掉线时不停弹出页面
+pub const AUTO_OPEN_INTERVAL: Duration = Duration::from_secs(300);
+
+// 合并一次检查结果后需要界面处理的事
+#[derive(Debug, Default, PartialEq)]
+pub struct Effects {
+    // 恢复在线后需要在浏览器中打开的URL
+    pub open_url: Option<String>,
+    // 需要提示的消息
+    pub alerts: Vec<String>,
+    // 新记录了安全基线，需要保存
+    pub baseline_changed: bool,
+    // 状态发生了变化
+    pub change: Option<StatusChange>,
+}
+
+// 把检查结果 result 合并到 server：记录状态变化时间，判断是否从故障中恢复，与安全基线比较
+pub fn apply(
+    server: &mut Server,
+    result: CheckOutcome,
+    clock: &dyn Clock,
+    baselines: &mut Baselines,
+) -> Effects {
+    let mut effects = Effects::default();
+    let now = clock.now();
+    // 离线和错误之间切换仍是同一次故障，从第一次失败开始计算持续时间
+    let down_for = if is_down(&result.status) {
+        server.state.down_since.get_or_insert(now);
+        None
+    } else {
+        server.state.down_since.take().map(|since| now - since)
+    };
+    if server.status != result.status {
+        server.state.last_change = Some(now);
+        effects.change = Some(StatusChange {
+            at: now,
+            server: server.name.clone(),
+            from: server.status.clone(),
+            to: result.status.clone(),
+            down_for,
+        });
+    }
+    let recovered = is_down(&server.status) && result.status == ServerStatus::Online;
+    let rate_ok = server
+        .state
+        .last_auto_open
+        .is_none_or(|t| clock.instant().saturating_duration_since(t) >= AUTO_OPEN_INTERVAL);
+    if recovered && server.open_on_recovery && rate_ok {
+        server.state.last_auto_open = Some(clock.instant());
+        effects.open_url = Some(server.url.clone());
+        effects.alerts.push(tr!(
+            "✅ {} 已恢复，已在浏览器中打开",
+            "✅ {} recovered, opened in browser",
+            server.name
+        ));
+    }
+    server.state.last_checked = Some(now);
+    server.state.stale = false;
+    server.status = result.status;
+    server.state.detail = result.detail;
+    server.state.latency_ms = result.latency.map(|d| d.as_millis() as u64);
+    server.state.metrics = result.metrics;
+    server.state.fields = result.fields;
+    server.state.timed_out = result.timed_out;
+    server.state.rule_maintenance = result.maintenance;
+    server.state.challenge = result.challenge;
+    server.state.content_error = result.content_error;
+    server.state.recent.push(
+        matches!(server.status, ServerStatus::Online | ServerStatus::Degraded),
+        server.state.latency_ms,
+    );
+    // 请求失败时保留上次读到的证书
+    if result.cert_expires.is_some() {
+        server.state.cert_expires = result.cert_expires;
+    }
+
+    // 第一次取到安全配置时作为基线，之后与基线比较；请求失败时保留上次的结果
+    if let Some(profile) = result.security {
+        let key = server.history_key();
+        let changes = match baselines.get(&key) {
+            Some(base) => profile.changes_from(base),
+            None => {
+                baselines.set(&key, profile.clone());
+                effects.baseline_changed = true;
+                Vec::new()
+            }
+        };
+        if !changes.is_empty() && changes != server.state.security_changes {
+            effects.alerts.push(tr!(
+                "🔒 {} 的安全配置发生变化: {}",
+                "🔒 Security settings changed on {}: {}",
+                server.name,
+                changes.join("; ")
+            ));
+        }
+        server.state.security = Some(profile);
+        server.state.security_changes = changes;
+    }
+    effects
+}
+
+pub fn is_down(status: &ServerStatus) -> bool {
+    matches!(status, ServerStatus::Offline | ServerStatus::Error(_))
+}
+
+// 故障持续时间的显示：40秒、25分钟、2小时13分、3天4小时
+pub fn format_downtime(duration: chrono::Duration) -> String {
+    let secs = duration.num_seconds().max(0);
+    let (days, hours, minutes) = (secs / 86400, secs % 86400 / 3600, secs % 3600 / 60);
+    match secs {
+        0..60 => tr!("{}秒", "{}s", secs),
+        60..3600 => tr!("{}分钟", "{}min", minutes),
+        3600..86400 => tr!("{}小时{}分", "{}h {}min", hours, minutes),
+        _ => tr!("{}天{}小时", "{}d {}h", days, hours),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::baseline::SecurityProfile;
+    use crate::clock::ManualClock;
+    use crate::testing;
+
+    fn result_with(status: ServerStatus) -> CheckOutcome {
+        CheckOutcome::new(status, "")
+    }
+
+    fn apply_status(
+        server: &mut Server,
+        status: ServerStatus,
+        clock: &ManualClock,
+        baselines: &mut Baselines,
+    ) -> Effects {
+        let result = result_with(status);
+        apply(server, result, clock, baselines)
+    }
+
+    fn clock() -> ManualClock {
+        ManualClock::at(chrono::Local::now())
+    }
+
+    #[test]
+    fn last_change_only_moves_when_status_changes() {
+        let mut server = testing::http_server("http://127.0.0.1:8080");
+        let mut baselines = Baselines::default();
+        let clock = clock();
+        let first = clock.now();
+        apply_status(&mut server, ServerStatus::Online, &clock, &mut baselines);
+        assert_eq!(server.state.last_change, Some(first));
+
+        clock.advance(Duration::from_secs(30));
+        apply_status(&mut server, ServerStatus::Online, &clock, &mut baselines);
+        assert_eq!(server.state.last_change, Some(first));
+        assert_eq!(server.state.last_checked, Some(clock.now()));
+    }
+
+    #[test]
+    fn recovery_opens_browser_once_per_interval() {
+        let mut server = testing::http_server("http://127.0.0.1:8080/app");
+        server.open_on_recovery = true;
+        server.status = ServerStatus::Offline;
+        let mut baselines = Baselines::default();
+        let clock = clock();
+
+        let effects = apply_status(&mut server, ServerStatus::Online, &clock, &mut baselines);
+        assert_eq!(
+            effects.open_url.as_deref(),
+            Some("http://127.0.0.1:8080/app")
+        );
+        assert_eq!(effects.alerts.len(), 1);
+
+        // 间隔内再次恢复时不重复打开
+        clock.advance(AUTO_OPEN_INTERVAL - Duration::from_secs(1));
+        server.status = ServerStatus::Error(502);
+        let effects = apply_status(&mut server, ServerStatus::Online, &clock, &mut baselines);
+        assert_eq!(effects.open_url, None);
+        assert!(effects.alerts.is_empty());
+
+        // 超过间隔后再次恢复时重新打开
+        clock.advance(Duration::from_secs(1));
+        server.status = ServerStatus::Offline;
+        let effects = apply_status(&mut server, ServerStatus::Online, &clock, &mut baselines);
+        assert!(effects.open_url.is_some());
+    }
+
+    #[test]
+    fn first_check_is_not_a_recovery() {
+        let mut server = testing::http_server("http://127.0.0.1:8080");
+        server.open_on_recovery = true;
+        let effects = apply_status(
+            &mut server,
+            ServerStatus::Online,
+            &clock(),
+            &mut Baselines::default(),
+        );
+        assert_eq!(effects.open_url, None);
+    }
+
+    #[test]
+    fn recovery_without_opt_in_does_nothing() {
+        let mut server = testing::http_server("http://127.0.0.1:8080");
+        server.status = ServerStatus::Offline;
+        let effects = apply_status(
+            &mut server,
+            ServerStatus::Online,
+            &clock(),
+            &mut Baselines::default(),
+        );
+        assert_eq!(effects.open_url, None);
+        assert!(effects.alerts.is_empty());
+        assert_eq!(effects.change.unwrap().from, ServerStatus::Offline);
+        assert_eq!(server.status, ServerStatus::Online);
+    }
+
+    #[test]
+    fn security_baseline_is_recorded_then_compared() {
+        let mut server = testing::http_server("https://example.com");
+        let mut baselines = Baselines::default();
+        let clock = clock();
+        let profile = SecurityProfile {
+            tls_version: Some("TLS 1.3".into()),
+            hsts: Some("max-age=31536000".into()),
+            ..Default::default()
+        };
+
+        let mut result = result_with(ServerStatus::Online);
+        result.security = Some(profile.clone());
+        let effects = apply(&mut server, result, &clock, &mut baselines);
+        assert!(effects.baseline_changed);
+        assert!(effects.alerts.is_empty());
+        assert_eq!(baselines.get(&server.history_key()), Some(&profile));
+
+        // HSTS被去掉：提示一次，之后同样的变化不再重复提示
+        let downgraded = SecurityProfile {
+            hsts: None,
+            ..profile.clone()
+        };
+        for expected_alerts in [1, 0] {
+            let mut result = result_with(ServerStatus::Online);
+            result.security = Some(downgraded.clone());
+            let effects = apply(&mut server, result, &clock, &mut baselines);
+            assert!(!effects.baseline_changed);
+            assert_eq!(effects.alerts.len(), expected_alerts);
+            assert_eq!(server.state.security_changes.len(), 1);
+        }
+        // 基线保持不变，直到用户接受
+        assert_eq!(baselines.get(&server.history_key()), Some(&profile));
+    }
+
+    #[test]
+    fn failed_request_keeps_previous_security_state() {
+        let mut server = testing::http_server("https://example.com");
+        server.state.security_changes = vec!["HSTS: a → none".into()];
+        let effects = apply_status(
+            &mut server,
+            ServerStatus::Offline,
+            &clock(),
+            &mut Baselines::default(),
+        );
+        assert!(!effects.baseline_changed);
+        assert_eq!(server.state.security_changes.len(), 1);
+    }
+
+    #[test]
+    fn downtime_spans_offline_and_error() {
+        let mut server = testing::http_server("http://127.0.0.1:8080");
+        let mut baselines = Baselines::default();
+        let clock = clock();
+        apply_status(&mut server, ServerStatus::Online, &clock, &mut baselines);
+        assert_eq!(server.state.down_since, None);
+
+        clock.advance(Duration::from_secs(60));
+        let started = clock.now();
+        apply_status(&mut server, ServerStatus::Offline, &clock, &mut baselines);
+        clock.advance(Duration::from_secs(3600));
+        apply_status(
+            &mut server,
+            ServerStatus::Error(502),
+            &clock,
+            &mut baselines,
+        );
+        assert_eq!(server.state.down_since, Some(started));
+        assert_eq!(
+            server.status_label(clock.now()),
+            "⚠ 错误 (502) · 已持续 1小时0分"
+        );
+
+        clock.advance(Duration::from_secs(13 * 60));
+        let effects = apply_status(&mut server, ServerStatus::Online, &clock, &mut baselines);
+        let down_for = effects.change.unwrap().down_for.unwrap();
+        assert_eq!(down_for.num_minutes(), 73);
+        assert_eq!(format_downtime(down_for), "1小时13分");
+        assert_eq!(server.state.down_since, None);
+
+        assert_eq!(format_downtime(chrono::Duration::seconds(40)), "40秒");
+        assert_eq!(format_downtime(chrono::Duration::minutes(25)), "25分钟");
+        assert_eq!(format_downtime(chrono::Duration::hours(76)), "3天4小时");
+    }
+}
@@ -0,0 +1,98 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::task::{JoinHandle, JoinSet};
+
+use super::ssh;
+use super::target::Target;
+
+// 经过SSH跳板机检查内网中的服务器：每次检查前连接跳板机，在本机随机端口上做端口转发，
+// 检查结束后关闭
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SshTunnel {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub user: String,
+    pub key_path: String,
+    pub key_passphrase: String,
+    // 跳板机的主机密钥不在 known_hosts 中时信任并记录下来，否则拒绝连接
+    pub trust_unknown_host: bool,
+    // 使用指定的 known_hosts 文件，不写入配置
+    #[serde(skip)]
+    pub known_hosts: Option<PathBuf>,
+}
+
+impl Default for SshTunnel {
+    fn default() -> Self {
+        let ssh = ssh::SshCheck::default();
+        Self {
+            enabled: false,
+            host: String::new(),
+            port: 22,
+            user: ssh.user,
+            key_path: ssh.key_path,
+            key_passphrase: String::new(),
+            trust_unknown_host: false,
+            known_hosts: None,
+        }
+    }
+}
+
+// 本机监听的转发端口，释放时断开与跳板机的连接
+pub struct Tunnel {
+    pub local: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl Drop for Tunnel {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+// 连接跳板机并开始转发到 target_host:target_port（由跳板机连接）
+pub async fn open(config: &SshTunnel, target_host: &str, target_port: u16) -> Result<Tunnel> {
+    let session = ssh::connect(
+        Target::new(config.host.trim()),
+        config.port,
+        &config.user,
+        &config.key_path,
+        &config.key_passphrase,
+        config.trust_unknown_host,
+        config.known_hosts.as_deref(),
+    )
+    .await?;
+    let session = Arc::new(session);
+    let listener = TcpListener::bind("127.0.0.1:0").await?;
+    let local = listener.local_addr()?;
+    let target_host = target_host.to_string();
+    let task = tokio::spawn(async move {
+        // 随监听任务一起结束
+        let mut connections = JoinSet::new();
+        while let Ok((mut stream, peer)) = listener.accept().await {
+            let session = Arc::clone(&session);
+            let target_host = target_host.clone();
+            connections.spawn(async move {
+                let channel = session
+                    .channel_open_direct_tcpip(
+                        target_host,
+                        target_port as u32,
+                        peer.ip().to_string(),
+                        peer.port() as u32,
+                    )
+                    .await;
+                // 跳板机无法连接目标时直接关闭，检查方看到的是连接被断开
+                if let Ok(channel) = channel {
+                    let mut remote = channel.into_stream();
+                    let _ = tokio::io::copy_bidirectional(&mut stream, &mut remote).await;
+                }
+            });
+        }
+    });
+    Ok(Tunnel { local, task })
+}
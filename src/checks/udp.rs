@@ -0,0 +1,218 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::target::udp_connect;
+use super::CheckOutcome;
+use crate::address;
+use crate::i18n::tr;
+use crate::ServerStatus;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PayloadFormat {
+    // 文本，支持 \r \n \t \0 \\ 和 \xNN 转义
+    #[default]
+    Text,
+    // 十六进制，忽略空格，例如 "ff ff ff ff 54"
+    Hex,
+}
+
+impl PayloadFormat {
+    pub const ALL: [PayloadFormat; 2] = [PayloadFormat::Text, PayloadFormat::Hex];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PayloadFormat::Text => tr!("文本", "Text"),
+            PayloadFormat::Hex => tr!("十六进制", "Hex"),
+        }
+    }
+
+    pub fn decode(&self, text: &str) -> Result<Vec<u8>> {
+        match self {
+            PayloadFormat::Text => unescape(text),
+            PayloadFormat::Hex => {
+                let digits: String = text.chars().filter(|c| !c.is_whitespace()).collect();
+                let digits = digits.strip_prefix("0x").unwrap_or(&digits);
+                Ok(hex::decode(digits)?)
+            }
+        }
+    }
+}
+
+// 发送一个数据包，在超时前收到任何回复即为在线；填写了期望回复时还要求回复以它开头
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UdpCheck {
+    pub port: u16,
+    pub format: PayloadFormat,
+    pub payload: String,
+    // 与发送内容使用相同格式，为空时不检查
+    pub expect: String,
+}
+
+impl Default for UdpCheck {
+    fn default() -> Self {
+        Self {
+            port: 53,
+            format: PayloadFormat::Text,
+            payload: String::new(),
+            expect: String::new(),
+        }
+    }
+}
+
+impl UdpCheck {
+    pub fn endpoint(&self, host: &str) -> String {
+        address::build_url("udp", host, self.port)
+    }
+}
+
+fn unescape(text: &str) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            let mut buf = [0u8; 4];
+            out.extend_from_slice(c.encode_utf8(&mut buf).as_bytes());
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push(b'\n'),
+            Some('r') => out.push(b'\r'),
+            Some('t') => out.push(b'\t'),
+            Some('0') => out.push(0),
+            Some('\\') => out.push(b'\\'),
+            Some('x') => {
+                let digits: String = chars.by_ref().take(2).collect();
+                match u8::from_str_radix(&digits, 16) {
+                    Ok(b) if digits.len() == 2 => out.push(b),
+                    _ => bail!(tr!("无效的转义 \\x{}", "Invalid escape \\x{}", digits)),
+                }
+            }
+            Some(other) => bail!(tr!("无效的转义 \\{}", "Invalid escape \\{}", other)),
+            None => bail!(tr!("末尾多余的 \\", "Trailing \\")),
+        }
+    }
+    Ok(out)
+}
+
+// 回复的开头，不可打印的字节显示为 \xNN
+fn preview(data: &[u8]) -> String {
+    let mut text = String::new();
+    for &b in data.iter().take(32) {
+        match b {
+            b' '..=b'~' => text.push(b as char),
+            _ => text.push_str(&format!("\\x{:02x}", b)),
+        }
+    }
+    if data.len() > 32 {
+        text.push('…');
+    }
+    text
+}
+
+async fn exchange(host: &str, port: u16, payload: &[u8]) -> std::io::Result<Vec<u8>> {
+    let socket = udp_connect((host, port)).await?;
+    socket.send(payload).await?;
+    let mut reply = vec![0u8; 65536];
+    // 端口关闭时会收到 ICMP 端口不可达，recv 返回连接被拒绝
+    let n = socket.recv(&mut reply).await?;
+    reply.truncate(n);
+    Ok(reply)
+}
+
+// 没有回复为离线（超时），回复与期望不符为错误
+pub async fn check(host: &str, check: &UdpCheck, timeout: Duration) -> CheckOutcome {
+    let host = host.trim_matches(['[', ']']);
+    let (payload, expect) = match (
+        check.format.decode(&check.payload),
+        check.format.decode(&check.expect),
+    ) {
+        (Ok(payload), Ok(expect)) => (payload, expect),
+        (Err(e), _) | (_, Err(e)) => {
+            return CheckOutcome::new(
+                ServerStatus::Error(1),
+                tr!("发送或期望内容无效: {}", "Invalid payload: {}", e),
+            )
+        }
+    };
+    let reply = match tokio::time::timeout(timeout, exchange(host, check.port, &payload)).await {
+        Ok(Ok(reply)) => reply,
+        Ok(Err(e)) => return CheckOutcome::new(ServerStatus::Offline, e.to_string()),
+        Err(_) => return CheckOutcome::timeout(tr!("UDP 无响应", "No UDP response")),
+    };
+    if !reply.starts_with(&expect) {
+        return CheckOutcome::new(
+            ServerStatus::Error(1),
+            tr!(
+                "回复与期望不符: {}",
+                "Unexpected reply: {}",
+                preview(&reply)
+            ),
+        );
+    }
+    CheckOutcome::new(
+        ServerStatus::Online,
+        tr!(
+            "收到 {} 字节: {}",
+            "Received {} bytes: {}",
+            reply.len(),
+            preview(&reply)
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::net::UdpSocket;
+
+    #[test]
+    fn decodes_payload_formats() {
+        assert_eq!(
+            PayloadFormat::Text.decode("ping\\r\\n\\x00\\\\").unwrap(),
+            b"ping\r\n\0\\"
+        );
+        assert!(PayloadFormat::Text.decode("\\xg1").is_err());
+        assert_eq!(
+            PayloadFormat::Hex.decode("0xFF ff 54").unwrap(),
+            [0xff, 0xff, 0x54]
+        );
+        assert!(PayloadFormat::Hex.decode("abc").is_err());
+        assert_eq!(preview(b"OK\x01"), "OK\\x01");
+    }
+
+    #[tokio::test]
+    async fn matches_reply_prefix() {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let port = socket.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 512];
+            loop {
+                let (n, peer) = socket.recv_from(&mut buf).await.unwrap();
+                // 回显，忽略以 quiet 开头的请求
+                if !buf[..n].starts_with(b"quiet") {
+                    socket.send_to(&buf[..n], peer).await.unwrap();
+                }
+            }
+        });
+        let timeout = Duration::from_secs(5);
+        let mut udp = UdpCheck {
+            port,
+            payload: "PONG\\n".into(),
+            expect: "PONG".into(),
+            ..Default::default()
+        };
+        let outcome = check("127.0.0.1", &udp, timeout).await;
+        assert_eq!(outcome.status, ServerStatus::Online);
+        assert!(outcome.detail.contains("PONG\\x0a"));
+
+        udp.expect = "OK".into();
+        let outcome = check("127.0.0.1", &udp, timeout).await;
+        assert_eq!(outcome.status, ServerStatus::Error(1));
+
+        udp.payload = "quiet".into();
+        let outcome = check("127.0.0.1", &udp, Duration::from_millis(200)).await;
+        assert!(outcome.timed_out);
+    }
+}
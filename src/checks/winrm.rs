@@ -0,0 +1,246 @@
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use super::target::Target;
+use super::CheckOutcome;
+use crate::address;
+use crate::i18n::tr;
+use crate::ServerStatus;
+
+// 通过WinRM(WS-Management)查询WMI，检查Windows服务状态、开机时长和磁盘剩余空间。
+// 使用Basic认证，目标机器需要开启：
+//   winrm set winrm/config/service/auth @{Basic="true"}
+// 走HTTP(5985)时还需要 AllowUnencrypted，建议使用HTTPS(5986)。
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WinRmCheck {
+    pub https: bool,
+    pub port: u16,
+    pub user: String,
+    pub password: String,
+    // 需要处于Running状态的服务名，留空不检查
+    #[serde(default)]
+    pub service: String,
+    // 检查剩余空间的磁盘，例如 C:，留空不检查
+    #[serde(default)]
+    pub disk: String,
+    // 剩余空间低于此百分比时显示为降级
+    pub min_free_percent: f32,
+    // 自签名证书
+    #[serde(default)]
+    pub accept_invalid_certs: bool,
+}
+
+impl Default for WinRmCheck {
+    fn default() -> Self {
+        Self {
+            https: false,
+            port: 5985,
+            user: "Administrator".to_string(),
+            password: String::new(),
+            service: String::new(),
+            disk: "C:".to_string(),
+            min_free_percent: 10.0,
+            accept_invalid_certs: false,
+        }
+    }
+}
+
+impl WinRmCheck {
+    pub fn endpoint(&self, host: &str) -> String {
+        let scheme = if self.https { "https" } else { "http" };
+        format!("{}/wsman", address::build_url(scheme, host, self.port))
+    }
+}
+
+const WMI_CIMV2: &str = "http://schemas.microsoft.com/wbem/wsman/1/wmi/root/cimv2/";
+
+// 构造WS-Transfer Get请求
+fn get_envelope(endpoint: &str, class: &str, selector: Option<(&str, &str)>) -> String {
+    let selector = selector
+        .map(|(name, value)| {
+            format!(
+                "<w:SelectorSet><w:Selector Name=\"{}\">{}</w:Selector></w:SelectorSet>",
+                name,
+                xml_escape(value)
+            )
+        })
+        .unwrap_or_default();
+
+    format!(
+        r#"<s:Envelope xmlns:s="http://www.w3.org/2003/05/soap-envelope" xmlns:a="http://schemas.xmlsoap.org/ws/2004/08/addressing" xmlns:w="http://schemas.dmtf.org/wbem/wsman/1/wsman.xsd">
+<s:Header>
+<a:To>{endpoint}</a:To>
+<w:ResourceURI s:mustUnderstand="true">{WMI_CIMV2}{class}</w:ResourceURI>
+<a:ReplyTo><a:Address s:mustUnderstand="true">http://schemas.xmlsoap.org/ws/2004/08/addressing/role/anonymous</a:Address></a:ReplyTo>
+<a:Action s:mustUnderstand="true">http://schemas.xmlsoap.org/ws/2004/09/transfer/Get</a:Action>
+<w:MaxEnvelopeSize s:mustUnderstand="true">153600</w:MaxEnvelopeSize>
+<a:MessageID>uuid:{id}</a:MessageID>
+<w:OperationTimeout>PT20S</w:OperationTimeout>
+{selector}
+</s:Header>
+<s:Body/>
+</s:Envelope>"#,
+        id = uuid::Uuid::new_v4()
+    )
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// 取出第一个 <前缀:name>...</前缀:name> 元素的文本，忽略命名空间前缀和内部标签
+fn xml_value(body: &str, name: &str) -> Option<String> {
+    let mut offset = 0;
+    while let Some(pos) = body[offset..].find('<') {
+        let start = offset + pos + 1;
+        let tag_end = start + body[start..].find('>')?;
+        let tag = &body[start..tag_end];
+        offset = tag_end;
+
+        if tag.starts_with('/') || tag.ends_with('/') {
+            continue;
+        }
+        let full_name = tag.split_whitespace().next().unwrap_or("");
+        let local = full_name.rsplit(':').next().unwrap_or("");
+        if local != name {
+            continue;
+        }
+
+        let close = format!("</{}>", full_name);
+        let inner_end = tag_end + 1 + body[tag_end + 1..].find(&close)?;
+        let inner = &body[tag_end + 1..inner_end];
+
+        // 去掉内部标签，例如 <cim:Datetime>...</cim:Datetime>
+        let mut text = String::new();
+        let mut in_tag = false;
+        for c in inner.chars() {
+            match c {
+                '<' => in_tag = true,
+                '>' => in_tag = false,
+                _ if !in_tag => text.push(c),
+                _ => {}
+            }
+        }
+        return Some(text.trim().to_string());
+    }
+    None
+}
+
+async fn wmi_get(
+    client: &reqwest::Client,
+    endpoint: &str,
+    check: &WinRmCheck,
+    class: &str,
+    selector: Option<(&str, &str)>,
+) -> Result<String> {
+    let resp = client
+        .post(endpoint)
+        .basic_auth(&check.user, Some(&check.password))
+        .header("Content-Type", "application/soap+xml;charset=UTF-8")
+        .body(get_envelope(endpoint, class, selector))
+        .send()
+        .await?;
+
+    let status = resp.status();
+    let body = resp.text().await?;
+    if status.as_u16() == 401 {
+        bail!(tr!(
+            "WinRM认证失败，请确认已启用Basic认证",
+            "WinRM authentication failed, make sure Basic auth is enabled"
+        ));
+    }
+    if !status.is_success() {
+        let reason = xml_value(&body, "Text").unwrap_or_else(|| status.to_string());
+        bail!("{}: {}", class, reason);
+    }
+    Ok(body)
+}
+
+fn format_uptime(seconds: i64) -> String {
+    let days = seconds / 86400;
+    let hours = seconds % 86400 / 3600;
+    if days > 0 {
+        tr!("{}天{}小时", "{}d {}h", days, hours)
+    } else {
+        tr!("{}小时{}分", "{}h {}m", hours, seconds % 3600 / 60)
+    }
+}
+
+async fn run(target: Target<'_>, check: &WinRmCheck, timeout: Duration) -> Result<CheckOutcome> {
+    let builder = reqwest::Client::builder()
+        .timeout(timeout)
+        .proxy(crate::proxy::global())
+        .danger_accept_invalid_certs(check.accept_invalid_certs);
+    let (builder, endpoint) = target.route(builder, &check.endpoint(target.host), check.port);
+    let client = builder.build()?;
+    let mut parts = Vec::new();
+    let mut status = ServerStatus::Online;
+
+    // 开机时长，同时验证WinRM可用
+    let os = wmi_get(&client, &endpoint, check, "Win32_OperatingSystem", None).await?;
+    if let Some(boot) =
+        xml_value(&os, "LastBootUpTime").and_then(|v| chrono::DateTime::parse_from_rfc3339(&v).ok())
+    {
+        let seconds = (chrono::Utc::now() - boot.with_timezone(&chrono::Utc)).num_seconds();
+        parts.push(tr!("已运行 {}", "up {}", format_uptime(seconds.max(0))));
+    }
+
+    if !check.service.is_empty() {
+        let svc = wmi_get(
+            &client,
+            &endpoint,
+            check,
+            "Win32_Service",
+            Some(("Name", &check.service)),
+        )
+        .await?;
+        let state = xml_value(&svc, "State")
+            .ok_or_else(|| anyhow!(tr!("无法读取服务状态", "Cannot read service state")))?;
+        if state != "Running" {
+            status = ServerStatus::Offline;
+        }
+        parts.insert(
+            0,
+            tr!("服务 {}: {}", "service {}: {}", check.service, state),
+        );
+    }
+
+    if !check.disk.is_empty() {
+        let disk = wmi_get(
+            &client,
+            &endpoint,
+            check,
+            "Win32_LogicalDisk",
+            Some(("DeviceID", &check.disk)),
+        )
+        .await?;
+        let number = |name| xml_value(&disk, name).and_then(|v| v.parse::<f64>().ok());
+        if let (Some(free), Some(size)) = (number("FreeSpace"), number("Size")) {
+            if size > 0.0 {
+                let percent = (free / size * 100.0) as f32;
+                parts.push(tr!("{} 剩余 {:.0}%", "{} {:.0}% free", check.disk, percent));
+                if percent < check.min_free_percent && status == ServerStatus::Online {
+                    status = ServerStatus::Degraded;
+                }
+            }
+        }
+    }
+
+    Ok(CheckOutcome::new(status, parts.join(" · ")))
+}
+
+// 最多查询三次WMI，timeout 是整个检查的时间
+pub async fn check(target: Target<'_>, check: &WinRmCheck, timeout: Duration) -> CheckOutcome {
+    let Ok(result) = tokio::time::timeout(timeout, run(target, check, timeout)).await else {
+        return CheckOutcome::timeout(tr!("WinRM 检查超时", "WinRM check timed out"));
+    };
+    match result {
+        Ok(outcome) => outcome,
+        Err(e) => CheckOutcome::new(ServerStatus::Offline, e.to_string()),
+    }
+}
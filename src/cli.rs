@@ -0,0 +1,400 @@
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+
+use crate::config::{is_valid_profile_name, ConfigLocation, Settings};
+use crate::i18n::{self, tr};
+use crate::{address, inventory, ipc, storage, tags, Server};
+
+// 命令行子命令，供部署脚本注册或移除服务器，不启动界面：
+//   server_check add --name web-01 --url https://10.0.0.5/health [--group 生产] [--tags a,b] [--profile 方案]
+//   server_check remove web-01 [--profile 方案]
+//   server_check import [清单文件] [--profile 方案]
+//   server_check serve [--listen 地址:端口] [--profile 方案]
+// 修改保存到当前使用的存储后，通知正在运行的界面重新加载
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AddArgs {
+    // 不填时按 主机-端口 生成
+    pub name: Option<String>,
+    pub url: String,
+    // 不填时保留已有条目的分组和标签
+    pub group: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CliCommand {
+    Add(AddArgs),
+    Remove(String),
+    // 从Ansible清单或Terraform输出同步，不填文件时使用设置中的
+    Import(Option<String>),
+    // 不启动界面，在后台检查并提供网页看板，参数为监听地址
+    Serve(Option<String>),
+    Help,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CliArgs {
+    pub command: CliCommand,
+    // 不填时使用界面上次使用的方案
+    pub profile: Option<String>,
+}
+
+pub fn usage() -> &'static str {
+    tr!(
+        "用法:\n  server_check add --name <名称> --url <地址> [--group <分组>] [--tags <标签,...>] [--profile <方案>]\n  server_check remove <名称> [--profile <方案>]\n  server_check import [<清单文件>] [--profile <方案>]\n  server_check serve [--listen <地址:端口>] [--profile <方案>]\n同名服务器已存在时 add 更新它的地址",
+        "Usage:\n  server_check add --name <name> --url <address> [--group <group>] [--tags <tag,...>] [--profile <profile>]\n  server_check remove <name> [--profile <profile>]\n  server_check import [<inventory file>] [--profile <profile>]\n  server_check serve [--listen <address:port>] [--profile <profile>]\nadd updates the address of an existing server with the same name"
+    )
+}
+
+// 解析命令行参数（不含程序名）。没有参数或第一个参数不是子命令时返回 None，照常启动界面，
+// 系统或快捷方式传入的其他参数不影响启动
+pub fn parse(args: &[String]) -> Result<Option<CliArgs>> {
+    let Some((subcommand, rest)) = args.split_first() else {
+        return Ok(None);
+    };
+    if !matches!(
+        subcommand.as_str(),
+        "add" | "remove" | "import" | "serve" | "help" | "-h" | "--help"
+    ) {
+        return Ok(None);
+    }
+    let mut options = Vec::new();
+    let mut positional = Vec::new();
+    let mut rest = rest.iter();
+    while let Some(arg) = rest.next() {
+        let Some(key) = arg.strip_prefix("--") else {
+            positional.push(arg.clone());
+            continue;
+        };
+        // 同时支持 --key value 和 --key=value
+        let (key, value) = match key.split_once('=') {
+            Some((key, value)) => (key.to_string(), value.to_string()),
+            None => {
+                let value = rest
+                    .next()
+                    .ok_or_else(|| anyhow!(tr!("--{} 缺少值", "--{} needs a value", key)))?;
+                (key.to_string(), value.clone())
+            }
+        };
+        options.push((key, value));
+    }
+    let mut take = |key: &str| {
+        options
+            .iter()
+            .position(|(k, _)| k == key)
+            .map(|i| options.remove(i).1)
+    };
+    let profile = take("profile");
+
+    let command = match subcommand.as_str() {
+        "add" => {
+            let url =
+                take("url").ok_or_else(|| anyhow!(tr!("add 需要 --url", "add requires --url")))?;
+            CliCommand::Add(AddArgs {
+                name: take("name").filter(|n| !n.trim().is_empty()),
+                url,
+                group: take("group"),
+                tags: take("tags").map(|t| tags::parse_tags(&t)),
+            })
+        }
+        "remove" => match positional.as_slice() {
+            [name] => CliCommand::Remove(name.clone()),
+            _ => match take("name") {
+                Some(name) if positional.is_empty() => CliCommand::Remove(name),
+                _ => bail!(tr!(
+                    "remove 需要一个服务器名称",
+                    "remove requires one server name"
+                )),
+            },
+        },
+        "import" => match positional.as_slice() {
+            [] => CliCommand::Import(None),
+            [path] => CliCommand::Import(Some(path.clone())),
+            [_, extra, ..] => bail!(tr!("多余的参数: {}", "Unexpected argument: {}", extra)),
+        },
+        "serve" => CliCommand::Serve(take("listen")),
+        _ => CliCommand::Help,
+    };
+    if command != CliCommand::Help {
+        if let Some((key, _)) = options.first() {
+            bail!(tr!("未知的选项: --{}", "Unknown option: --{}", key));
+        }
+        if let (CliCommand::Add(_) | CliCommand::Serve(_), Some(arg)) =
+            (&command, positional.first())
+        {
+            bail!(tr!("多余的参数: {}", "Unexpected argument: {}", arg));
+        }
+    }
+    Ok(Some(CliArgs { command, profile }))
+}
+
+// add 的结果
+#[derive(Debug, PartialEq)]
+pub enum AddOutcome {
+    Added,
+    // 同名服务器已存在，更新了地址
+    Updated,
+}
+
+// 添加服务器；同名的已存在时只更新地址以及指定了的分组和标签，保留其他配置，
+// 部署脚本重复执行也不会添加出重复的条目。返回结果和条目在列表中的位置
+pub fn add(servers: &mut Vec<Server>, args: &AddArgs) -> Result<(AddOutcome, usize)> {
+    let parsed = address::parse_address(&args.url)
+        .ok_or_else(|| anyhow!(tr!("无法识别的地址: {}", "Invalid address: {}", args.url)))?;
+    let name = args
+        .name
+        .as_deref()
+        .map(|n| n.trim().to_string())
+        .unwrap_or_else(|| address::fallback_name(&parsed.host, parsed.port));
+
+    let (index, outcome) = match servers.iter().position(|s| s.name == name) {
+        Some(i) => (i, AddOutcome::Updated),
+        None => {
+            servers.push(Server::new(name, parsed.host.clone(), parsed.port));
+            (servers.len() - 1, AddOutcome::Added)
+        }
+    };
+    let server = &mut servers[index];
+    server.ip = parsed.host.clone();
+    server.port = parsed.port;
+    server.url = parsed.full_url();
+    if let Some(group) = &args.group {
+        server.group = group.trim().to_string();
+    }
+    if let Some(tags) = &args.tags {
+        server.tags = tags.clone();
+    }
+    Ok((outcome, index))
+}
+
+// 按名称移除服务器，返回移除的台数
+pub fn remove(servers: &mut Vec<Server>, name: &str) -> Result<usize> {
+    let before = servers.len();
+    servers.retain(|s| s.name != name);
+    match before - servers.len() {
+        0 => bail!(tr!("找不到服务器: {}", "Server not found: {}", name)),
+        removed => Ok(removed),
+    }
+}
+
+// Windows下程序是窗口程序，没有自己的控制台；从命令行运行子命令时接到父进程的控制台上，
+// 否则输出看不到
+#[cfg(windows)]
+pub fn attach_console() {
+    const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn AttachConsole(process_id: u32) -> i32;
+    }
+    // 没有父控制台（如从资源管理器启动）时失败，忽略
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(windows))]
+pub fn attach_console() {}
+
+// 锁定后整段写入，输出被管道关闭时不会 panic
+pub fn print(text: &str) {
+    let _ = writeln!(std::io::stdout().lock(), "{}", text);
+}
+
+pub fn print_error(text: &str) {
+    let _ = writeln!(std::io::stderr().lock(), "{}", text);
+}
+
+// 执行子命令，返回进程退出码
+pub fn run(args: CliArgs) -> i32 {
+    let location = ConfigLocation::resolve();
+    let settings = Settings::load(&location.settings_path());
+    i18n::set_lang(settings.language);
+
+    if args.command == CliCommand::Help {
+        print(usage());
+        return 0;
+    }
+    match apply(&args, &location, &settings) {
+        Ok(message) => {
+            print(&message);
+            if !ipc::notify_reload(&location.dir) {
+                print(tr!(
+                    "界面没有在运行，下次启动时生效",
+                    "The GUI is not running, changes apply on next start"
+                ));
+            }
+            0
+        }
+        Err(e) => {
+            print_error(&e.to_string());
+            1
+        }
+    }
+}
+
+// 修改存储中的服务器列表，不通知界面
+pub fn apply(args: &CliArgs, location: &ConfigLocation, settings: &Settings) -> Result<String> {
+    let profile = args.profile.as_deref().unwrap_or(&settings.profile);
+    if !is_valid_profile_name(profile) {
+        bail!(tr!(
+            "无效的方案名称: {}",
+            "Invalid profile name: {}",
+            profile
+        ));
+    }
+    let storage = storage::open(&settings.storage, location)?;
+    let doc = storage::servers_doc(profile);
+    // 方案还不存在时从空列表开始
+    let mut servers: Vec<Server> = match storage.load(&doc)? {
+        Some(content) => serde_json::from_str(&content)?,
+        None => Vec::new(),
+    };
+
+    let message = match &args.command {
+        CliCommand::Add(add_args) => {
+            let (outcome, index) = add(&mut servers, add_args)?;
+            let server = &servers[index];
+            match outcome {
+                AddOutcome::Added => {
+                    tr!("已添加 {} ({})", "Added {} ({})", server.name, server.url)
+                }
+                AddOutcome::Updated => {
+                    tr!("已更新 {} ({})", "Updated {} ({})", server.name, server.url)
+                }
+            }
+        }
+        CliCommand::Remove(name) => {
+            let removed = remove(&mut servers, name)?;
+            tr!("已移除 {} 台: {}", "Removed {}: {}", removed, name)
+        }
+        CliCommand::Import(path) => {
+            let path = path.as_deref().unwrap_or(settings.inventory.path.trim());
+            if path.is_empty() {
+                bail!(tr!(
+                    "请指定清单文件，或在设置中填写",
+                    "Specify an inventory file or set one in the settings"
+                ));
+            }
+            let hosts = inventory::load(Path::new(path))?;
+            inventory::sync(&mut servers, &hosts, &settings.inventory).message()
+        }
+        // 由 main 和 run 处理，不修改服务器列表
+        CliCommand::Serve(_) | CliCommand::Help => bail!(usage()),
+    };
+    storage.save(&doc, &serde_json::to_string_pretty(&servers)?)?;
+    storage.flush()?;
+    Ok(message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(list: &[&str]) -> Vec<String> {
+        list.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn add_args(name: Option<&str>, url: &str) -> AddArgs {
+        AddArgs {
+            name: name.map(str::to_string),
+            url: url.to_string(),
+            group: None,
+            tags: None,
+        }
+    }
+
+    #[test]
+    fn parses_subcommands_and_options() {
+        assert_eq!(parse(&[]).unwrap(), None);
+        let parsed = parse(&args(&[
+            "add",
+            "--name",
+            "web-01",
+            "--url=https://10.0.0.5/health",
+            "--tags",
+            "prod, gpu",
+            "--profile",
+            "公司",
+        ]))
+        .unwrap()
+        .unwrap();
+        assert_eq!(parsed.profile.as_deref(), Some("公司"));
+        assert_eq!(
+            parsed.command,
+            CliCommand::Add(AddArgs {
+                name: Some("web-01".to_string()),
+                url: "https://10.0.0.5/health".to_string(),
+                group: None,
+                tags: Some(vec!["prod".to_string(), "gpu".to_string()]),
+            })
+        );
+
+        let parsed = parse(&args(&["remove", "web-01"])).unwrap().unwrap();
+        assert_eq!(parsed.command, CliCommand::Remove("web-01".to_string()));
+        let parsed = parse(&args(&["import", "hosts.ini"])).unwrap().unwrap();
+        assert_eq!(
+            parsed.command,
+            CliCommand::Import(Some("hosts.ini".to_string()))
+        );
+
+        let parsed = parse(&args(&["serve", "--listen", "0.0.0.0:8080"]))
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            parsed.command,
+            CliCommand::Serve(Some("0.0.0.0:8080".to_string()))
+        );
+        assert!(parse(&args(&["serve", "extra"])).is_err());
+
+        assert!(parse(&args(&["add", "--name", "x"])).is_err());
+        assert!(parse(&args(&["add", "--url"])).is_err());
+        assert!(parse(&args(&["add", "--url", "x:80", "--port", "1"])).is_err());
+        assert!(parse(&args(&["remove"])).is_err());
+        // 不是子命令的参数照常启动界面
+        assert_eq!(parse(&args(&["rm", "x"])).unwrap(), None);
+        assert_eq!(parse(&args(&["-psn_0_1234"])).unwrap(), None);
+        assert_eq!(
+            parse(&args(&["--help"])).unwrap().unwrap().command,
+            CliCommand::Help
+        );
+    }
+
+    #[test]
+    fn add_updates_server_with_same_name() {
+        let mut servers = Vec::new();
+        let outcome = add(&mut servers, &add_args(Some("web"), "10.0.0.5:8080")).unwrap();
+        assert_eq!(outcome, (AddOutcome::Added, 0));
+        servers[0].group = "生产".to_string();
+        let id = servers[0].id;
+
+        let outcome = add(
+            &mut servers,
+            &add_args(Some("web"), "https://10.0.0.6/health"),
+        )
+        .unwrap();
+        assert_eq!(outcome, (AddOutcome::Updated, 0));
+        assert_eq!(servers.len(), 1);
+        assert_eq!(servers[0].id, id);
+        assert_eq!(servers[0].url, "https://10.0.0.6/health");
+        assert_eq!((servers[0].ip.as_str(), servers[0].port), ("10.0.0.6", 443));
+        assert_eq!(servers[0].group, "生产");
+
+        // 没有名称时按地址生成
+        add(&mut servers, &add_args(None, "10.0.0.7:9000")).unwrap();
+        assert_eq!(servers[1].name, "10.0.0.7-9000");
+        assert!(add(&mut servers, &add_args(None, "ftp://x")).is_err());
+    }
+
+    #[test]
+    fn remove_by_name() {
+        let mut servers = Vec::new();
+        add(&mut servers, &add_args(Some("a"), "10.0.0.1:80")).unwrap();
+        add(&mut servers, &add_args(Some("b"), "10.0.0.2:80")).unwrap();
+        assert_eq!(remove(&mut servers, "a").unwrap(), 1);
+        assert_eq!(servers.len(), 1);
+        assert!(remove(&mut servers, "a").is_err());
+    }
+}
@@ -0,0 +1,59 @@
+use chrono::{DateTime, Local};
+use std::time::Instant;
+
+// 当前时间的来源。自动检查间隔、定时动作等调度逻辑都通过它取时间，
+// 测试时换成手动推进的虚拟时钟，不需要真的等待
+pub trait Clock: Send + Sync {
+    // 单调时间，用于计算间隔
+    fn instant(&self) -> Instant;
+    // 本地时间，用于定时动作和历史记录
+    fn now(&self) -> DateTime<Local>;
+}
+
+#[derive(Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn instant(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn now(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+// 虚拟时钟：只有调用 advance 时才前进
+#[cfg(test)]
+pub struct ManualClock {
+    start_instant: Instant,
+    start_time: DateTime<Local>,
+    offset: std::sync::Mutex<std::time::Duration>,
+}
+
+#[cfg(test)]
+impl ManualClock {
+    pub fn at(start_time: DateTime<Local>) -> Self {
+        Self {
+            start_instant: Instant::now(),
+            start_time,
+            offset: Default::default(),
+        }
+    }
+
+    pub fn advance(&self, duration: std::time::Duration) {
+        *self.offset.lock().unwrap() += duration;
+    }
+}
+
+#[cfg(test)]
+impl Clock for ManualClock {
+    fn instant(&self) -> Instant {
+        self.start_instant + *self.offset.lock().unwrap()
+    }
+
+    fn now(&self) -> DateTime<Local> {
+        let offset = *self.offset.lock().unwrap();
+        self.start_time + chrono::Duration::from_std(offset).unwrap()
+    }
+}
@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::i18n::tr;
+use crate::Server;
+
+// 自定义列的数据来源
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum ColumnSource {
+    // 响应头，例如 Server、X-App-Version
+    #[default]
+    Header,
+    // JSON响应中的字段，用点分隔路径，数组用下标，例如 build.version、players.0.name
+    JsonField,
+}
+
+impl ColumnSource {
+    pub const ALL: [ColumnSource; 2] = [ColumnSource::Header, ColumnSource::JsonField];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ColumnSource::Header => tr!("响应头", "Header"),
+            ColumnSource::JsonField => tr!("JSON字段", "JSON field"),
+        }
+    }
+}
+
+// 表格视图中的自定义列，值从HTTP检查的响应中提取
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ColumnDef {
+    pub title: String,
+    pub source: ColumnSource,
+    // 响应头名称或JSON路径
+    pub key: String,
+    // 版本列：同一分组内各服务器的值不一致时提示
+    #[serde(default)]
+    pub version: bool,
+}
+
+pub fn needs_body(columns: &[ColumnDef]) -> bool {
+    columns.iter().any(|c| c.source == ColumnSource::JsonField)
+}
+
+pub fn json_value<'a>(value: &'a serde_json::Value, path: &str) -> Option<&'a serde_json::Value> {
+    let mut current = value;
+    for part in path.split('.').filter(|p| !p.is_empty()) {
+        current = match current {
+            serde_json::Value::Array(items) => items.get(part.parse::<usize>().ok()?)?,
+            serde_json::Value::Object(map) => map.get(part)?,
+            _ => return None,
+        };
+    }
+    Some(current)
+}
+
+// 按点分隔的路径取JSON中的值，字符串去掉引号，其他类型按JSON文本显示
+pub fn json_field(value: &serde_json::Value, path: &str) -> Option<String> {
+    match json_value(value, path)? {
+        serde_json::Value::String(s) => Some(s.clone()),
+        serde_json::Value::Null => None,
+        other => Some(other.to_string()),
+    }
+}
+
+// 提取各列的值，以列标题为键；提取不到的列不出现在结果中
+pub fn extract(
+    columns: &[ColumnDef],
+    headers: &reqwest::header::HeaderMap,
+    body: Option<&str>,
+) -> HashMap<String, String> {
+    let json = body.and_then(|b| serde_json::from_str::<serde_json::Value>(b).ok());
+    columns
+        .iter()
+        .filter_map(|column| {
+            let value = match column.source {
+                ColumnSource::Header => headers
+                    .get(column.key.trim())
+                    .and_then(|v| v.to_str().ok())
+                    .map(str::to_string),
+                ColumnSource::JsonField => json
+                    .as_ref()
+                    .and_then(|json| json_field(json, column.key.trim())),
+            }?;
+            Some((column.title.clone(), value))
+        })
+        .collect()
+}
+
+// 同一分组内版本列的值不一致，例如灰度机器没有回滚或部署失败
+#[derive(Debug, Clone, PartialEq)]
+pub struct VersionDrift {
+    pub group: String,
+    pub column: String,
+    // 各版本及服务器数量，数量多的在前
+    pub versions: Vec<(String, usize)>,
+}
+
+impl VersionDrift {
+    // 多数服务器使用的版本
+    pub fn majority(&self) -> &str {
+        &self.versions[0].0
+    }
+
+    pub fn key(&self) -> (String, String) {
+        (self.group.clone(), self.column.clone())
+    }
+
+    pub fn summary(&self) -> String {
+        self.versions
+            .iter()
+            .map(|(version, count)| format!("{} ×{}", version, count))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+// 找出版本列的值不一致的分组，没有取到值的服务器不参与比较
+pub fn version_drift(servers: &[Server], columns: &[ColumnDef]) -> Vec<VersionDrift> {
+    let mut drift = Vec::new();
+    for column in columns.iter().filter(|c| c.version) {
+        let mut groups: BTreeMap<&str, BTreeMap<&str, usize>> = BTreeMap::new();
+        // 已暂停的服务器的值可能已经过时
+        for server in servers.iter().filter(|s| !s.paused) {
+            if let Some(value) = server.state.fields.get(&column.title) {
+                *groups
+                    .entry(server.group.as_str())
+                    .or_default()
+                    .entry(value.as_str())
+                    .or_default() += 1;
+            }
+        }
+        for (group, versions) in groups {
+            if versions.len() < 2 {
+                continue;
+            }
+            let mut versions: Vec<(String, usize)> = versions
+                .into_iter()
+                .map(|(version, count)| (version.to_string(), count))
+                .collect();
+            versions.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+            drift.push(VersionDrift {
+                group: group.to_string(),
+                column: column.title.clone(),
+                versions,
+            });
+        }
+    }
+    drift
+}
+
+// 服务器的版本与分组内多数服务器不同
+pub fn is_drifted(drift: &[VersionDrift], server: &Server, column: &str) -> bool {
+    drift.iter().any(|d| {
+        d.group == server.group
+            && d.column == column
+            && server
+                .state
+                .fields
+                .get(column)
+                .is_some_and(|value| value != d.majority())
+    })
+}
@@ -0,0 +1,210 @@
+use eframe::egui;
+
+use crate::filter::StatusFilter;
+use crate::fuzzy;
+use crate::i18n::tr;
+use crate::Server;
+
+// 最多显示的候选命令
+const MAX_RESULTS: usize = 12;
+
+// 命令面板中可以执行的操作
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    // 打开服务器详情
+    OpenServer(usize),
+    CheckServer(usize),
+    CheckAll,
+    ToggleStatus(StatusFilter),
+    ClearFilters,
+    // 一小时内不发送状态变化通知
+    MuteAlerts,
+    UnmuteAlerts,
+    AddServer,
+    OpenSettings,
+    OpenUptime,
+    OpenChannels,
+    OpenDiscovery,
+    ExportStatusPage,
+    ToggleDashboard,
+    FocusSearch,
+    DeleteSelected,
+    ToggleAlwaysOnTop,
+}
+
+// Ctrl+K 打开的命令面板，输入文字模糊匹配命令和服务器
+#[derive(Default)]
+pub struct CommandPalette {
+    pub open: bool,
+    query: String,
+    selected: usize,
+}
+
+impl CommandPalette {
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    // 返回用户选中的命令
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        servers: &[Server],
+        muted: bool,
+        selected: usize,
+    ) -> Option<Command> {
+        let commands = commands(servers, muted, selected);
+        let mut matches: Vec<(u32, &(String, Command))> = commands
+            .iter()
+            .filter_map(|c| fuzzy::score(&self.query, &c.0).map(|s| (s, c)))
+            .collect();
+        // 分数相同时保持原来的顺序
+        matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        matches.truncate(MAX_RESULTS);
+
+        let (up, down, enter, escape) = ctx.input(|i| {
+            (
+                i.key_pressed(egui::Key::ArrowUp),
+                i.key_pressed(egui::Key::ArrowDown),
+                i.key_pressed(egui::Key::Enter),
+                i.key_pressed(egui::Key::Escape),
+            )
+        });
+        if down {
+            self.selected += 1;
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+        self.selected = self.selected.min(matches.len().saturating_sub(1));
+
+        let mut chosen = None;
+        egui::Window::new(tr!("命令面板", "Command palette"))
+            .title_bar(false)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_TOP, egui::vec2(0.0, 60.0))
+            .fixed_size(egui::vec2(420.0, 0.0))
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut self.query)
+                        .hint_text(tr!(
+                            "输入命令或服务器名称…",
+                            "Type a command or server name…"
+                        ))
+                        .desired_width(f32::INFINITY),
+                );
+                response.request_focus();
+                if response.changed() {
+                    self.selected = 0;
+                }
+                ui.separator();
+                if matches.is_empty() {
+                    ui.weak(tr!("没有匹配的命令", "No matching commands"));
+                }
+                for (i, (_, (label, command))) in matches.iter().enumerate() {
+                    if ui.selectable_label(i == self.selected, label).clicked() {
+                        chosen = Some(command.clone());
+                    }
+                }
+            });
+
+        if enter {
+            chosen = chosen.or_else(|| matches.get(self.selected).map(|(_, c)| c.1.clone()));
+        }
+        if chosen.is_some() || escape {
+            self.open = false;
+        }
+        chosen
+    }
+}
+
+// 所有可用的命令及显示文字，有快捷键的写在后面的括号中；服务器相关的命令排在后面
+fn commands(servers: &[Server], muted: bool, selected: usize) -> Vec<(String, Command)> {
+    let mut commands = vec![
+        (
+            tr!("🔄 立即检查全部 (F5)", "🔄 Check all now (F5)").to_string(),
+            Command::CheckAll,
+        ),
+        (
+            tr!("➕ 添加服务器 (Ctrl+N)", "➕ Add server (Ctrl+N)").to_string(),
+            Command::AddServer,
+        ),
+        (
+            tr!("🔍 搜索 (Ctrl+F)", "🔍 Search (Ctrl+F)").to_string(),
+            Command::FocusSearch,
+        ),
+        (
+            tr!("⚙ 打开设置", "⚙ Open settings").to_string(),
+            Command::OpenSettings,
+        ),
+        (
+            tr!("📊 可用率", "📊 Uptime").to_string(),
+            Command::OpenUptime,
+        ),
+        (
+            tr!("🔔 通知渠道", "🔔 Notification channels").to_string(),
+            Command::OpenChannels,
+        ),
+        (
+            tr!("☁ 云资源发现", "☁ Cloud discovery").to_string(),
+            Command::OpenDiscovery,
+        ),
+        (
+            tr!("📄 导出状态页", "📄 Export status page").to_string(),
+            Command::ExportStatusPage,
+        ),
+        (
+            tr!("📺 投屏看板", "📺 Dashboard").to_string(),
+            Command::ToggleDashboard,
+        ),
+        (
+            tr!("✖ 清除筛选", "✖ Clear filters").to_string(),
+            Command::ClearFilters,
+        ),
+        (
+            tr!("📌 切换窗口置顶", "📌 Toggle always on top").to_string(),
+            Command::ToggleAlwaysOnTop,
+        ),
+    ];
+    commands.push(if muted {
+        (
+            tr!("🔔 取消通知静音", "🔔 Unmute alerts").to_string(),
+            Command::UnmuteAlerts,
+        )
+    } else {
+        (
+            tr!("🔕 静音通知1小时", "🔕 Mute alerts for 1 hour").to_string(),
+            Command::MuteAlerts,
+        )
+    });
+    if selected > 0 {
+        commands.push((
+            tr!(
+                "🗑 删除选中的 {} 台服务器 (Del)",
+                "🗑 Delete {} selected servers (Del)",
+                selected
+            ),
+            Command::DeleteSelected,
+        ));
+    }
+    for status in StatusFilter::ALL {
+        commands.push((
+            tr!("筛选: {}", "Filter: {}", status.label()),
+            Command::ToggleStatus(status),
+        ));
+    }
+    for (i, server) in servers.iter().enumerate() {
+        commands.push((
+            tr!("🖥 {} ({})", "🖥 {} ({})", server.name, server.ip),
+            Command::OpenServer(i),
+        ));
+        commands.push((
+            tr!("🔄 检查 {}", "🔄 Check {}", server.name),
+            Command::CheckServer(i),
+        ));
+    }
+    commands
+}
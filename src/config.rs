@@ -0,0 +1,497 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::backup::BackupSettings;
+use crate::checks::docker::DockerImportSettings;
+use crate::columns::ColumnDef;
+use crate::discovery::DiscoverySettings;
+use crate::exporter::ExportSettings;
+use crate::filter::SortKey;
+use crate::flap::FlapSettings;
+use crate::heartbeat::HeartbeatSettings;
+use crate::history::{Exclusion, GroupSla};
+use crate::i18n::{tr, Lang};
+use crate::inventory::InventorySettings;
+use crate::mqtt_publish::MqttPublishSettings;
+use crate::notify::Channel;
+use crate::proxy::ProxySettings;
+use crate::report::ReportSettings;
+use crate::storage::StorageSettings;
+use crate::worker::ConcurrencySettings;
+
+// 配置文件名
+pub const SERVERS_FILE: &str = "servers.json";
+pub const SETTINGS_FILE: &str = "settings.json";
+pub const HISTORY_FILE: &str = "history.json";
+pub const BASELINE_FILE: &str = "baseline.json";
+pub const SESSION_FILE: &str = "session.json";
+pub const THEME_FILE: &str = "theme.json";
+// 可执行文件旁存在此文件时使用便携模式，配置保存在可执行文件目录
+const PORTABLE_MARKER: &str = "portable";
+// 系统配置目录下的子目录名
+const APP_DIR_NAME: &str = "serverCheck";
+// 额外的配置方案保存在此子目录，每个方案一个文件
+const PROFILES_DIR: &str = "profiles";
+// 默认配置方案对应 servers.json
+pub const DEFAULT_PROFILE: &str = "默认";
+
+// 需要随配置目录一起迁移的文件
+const CONFIG_FILES: &[&str] = &[
+    SERVERS_FILE,
+    SETTINGS_FILE,
+    HISTORY_FILE,
+    BASELINE_FILE,
+    SESSION_FILE,
+    THEME_FILE,
+    crate::storage::sqlite::DEFAULT_DB_FILE,
+];
+
+// 获取可执行文件所在目录
+pub fn exe_dir() -> PathBuf {
+    if let Ok(exe_path) = std::env::current_exe() {
+        if let Some(parent) = exe_path.parent() {
+            return parent.to_path_buf();
+        }
+    }
+    // 如果获取失败，使用当前工作目录
+    std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
+}
+
+fn system_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_DIR_NAME))
+}
+
+// 配置文件所在位置
+#[derive(Debug, Clone)]
+pub struct ConfigLocation {
+    pub portable: bool,
+    pub dir: PathBuf,
+}
+
+impl ConfigLocation {
+    // 确定配置目录；非便携模式下首次运行时把可执行文件目录中的旧配置迁移过去
+    pub fn resolve() -> Self {
+        let exe_dir = exe_dir();
+        let portable = exe_dir.join(PORTABLE_MARKER).exists();
+
+        let system_dir = match system_dir() {
+            Some(dir) if !portable => dir,
+            // 便携模式，或系统没有配置目录
+            _ => {
+                return Self {
+                    portable: true,
+                    dir: exe_dir,
+                }
+            }
+        };
+
+        if let Err(e) = std::fs::create_dir_all(&system_dir) {
+            eprintln!(
+                "无法创建配置目录 {:?}: {}，改用可执行文件目录",
+                system_dir, e
+            );
+            return Self {
+                portable: true,
+                dir: exe_dir,
+            };
+        }
+
+        if !system_dir.join(SERVERS_FILE).exists() && exe_dir.join(SERVERS_FILE).exists() {
+            migrate(&exe_dir, &system_dir);
+        }
+
+        Self {
+            portable: false,
+            dir: system_dir,
+        }
+    }
+
+    pub fn servers_path(&self) -> PathBuf {
+        self.dir.join(SERVERS_FILE)
+    }
+
+    pub fn settings_path(&self) -> PathBuf {
+        self.dir.join(SETTINGS_FILE)
+    }
+
+    pub fn history_path(&self) -> PathBuf {
+        self.dir.join(HISTORY_FILE)
+    }
+
+    pub fn baseline_path(&self) -> PathBuf {
+        self.dir.join(BASELINE_FILE)
+    }
+
+    pub fn session_path(&self) -> PathBuf {
+        self.dir.join(SESSION_FILE)
+    }
+
+    pub fn theme_path(&self) -> PathBuf {
+        self.dir.join(THEME_FILE)
+    }
+
+    // 配置方案对应的服务器列表文件
+    pub fn profile_path(&self, profile: &str) -> PathBuf {
+        if profile == DEFAULT_PROFILE {
+            self.servers_path()
+        } else {
+            self.dir
+                .join(PROFILES_DIR)
+                .join(format!("{}.json", profile))
+        }
+    }
+
+    pub fn profiles_dir(&self) -> PathBuf {
+        self.dir.join(PROFILES_DIR)
+    }
+
+    // 切换便携模式，把当前配置复制到新位置
+    pub fn set_portable(&mut self, portable: bool) -> io::Result<()> {
+        if portable == self.portable {
+            return Ok(());
+        }
+
+        let exe_dir = exe_dir();
+        let marker = exe_dir.join(PORTABLE_MARKER);
+        let target = if portable {
+            exe_dir
+        } else {
+            system_dir().ok_or_else(|| {
+                io::Error::other(tr!(
+                    "系统没有可用的配置目录",
+                    "No system config directory is available"
+                ))
+            })?
+        };
+
+        std::fs::create_dir_all(&target)?;
+        copy_config_files(&self.dir, &target)?;
+        if portable {
+            std::fs::write(&marker, "")?;
+        } else if marker.exists() {
+            std::fs::remove_file(&marker)?;
+        }
+
+        println!("配置目录已切换到 {:?}", target);
+        self.portable = portable;
+        self.dir = target;
+        Ok(())
+    }
+}
+
+// 方案名称会作为文件名使用
+pub fn is_valid_profile_name(name: &str) -> bool {
+    let name = name.trim();
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\', ':', '*', '?', '"', '<', '>', '|'])
+}
+
+fn copy_config_files(from: &Path, to: &Path) -> io::Result<()> {
+    for name in CONFIG_FILES {
+        let src = from.join(name);
+        if src.exists() {
+            std::fs::copy(&src, to.join(name))?;
+        }
+    }
+
+    let profiles = from.join(PROFILES_DIR);
+    if profiles.is_dir() {
+        std::fs::create_dir_all(to.join(PROFILES_DIR))?;
+        for entry in std::fs::read_dir(&profiles)?.flatten() {
+            if entry.path().is_file() {
+                std::fs::copy(entry.path(), to.join(PROFILES_DIR).join(entry.file_name()))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+// 迁移旧配置：复制到新目录后把原文件改名，避免之后误改旧文件
+fn migrate(from: &Path, to: &Path) {
+    if let Err(e) = copy_config_files(from, to) {
+        eprintln!("迁移配置到 {:?} 失败: {}", to, e);
+        return;
+    }
+    for name in CONFIG_FILES {
+        let src = from.join(name);
+        if src.exists() {
+            // 安装在只读目录时改名会失败，旧文件保留即可
+            let _ = std::fs::rename(&src, from.join(format!("{}.migrated", name)));
+        }
+    }
+    println!("已将配置从 {:?} 迁移到 {:?}", from, to);
+}
+
+// 服务器列表的显示方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum ViewMode {
+    #[default]
+    Cards,
+    // 紧凑表格，适合服务器较多时
+    Table,
+    // 每台服务器一个大磁贴，只显示名称和延迟，适合在值班室远处查看
+    Wall,
+}
+
+// 界面主题
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum ThemeMode {
+    // 跟随系统
+    #[default]
+    System,
+    Dark,
+    Light,
+}
+
+// 各状态的显示颜色 (RGB)
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StatusColors {
+    pub online: [u8; 3],
+    pub offline: [u8; 3],
+    pub error: [u8; 3],
+    pub degraded: [u8; 3],
+    pub unchecked: [u8; 3],
+}
+
+impl Default for StatusColors {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+impl StatusColors {
+    pub const DEFAULT: Self = Self {
+        online: [0, 150, 0],
+        offline: [200, 0, 0],
+        error: [255, 165, 0],
+        degraded: [200, 170, 0],
+        unchecked: [160, 160, 160],
+    };
+
+    // 红绿色弱友好的配色：蓝/橙/紫
+    pub fn color_blind() -> Self {
+        Self {
+            online: [0, 114, 178],
+            offline: [213, 94, 0],
+            error: [204, 121, 167],
+            degraded: [230, 159, 0],
+            unchecked: [160, 160, 160],
+        }
+    }
+}
+
+// 投屏看板的显示器与是否全屏
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DashboardSettings {
+    // 投到第几台显示器，0为主显示器。按与主显示器同样大小、从左到右排列估算位置
+    pub display: u32,
+    // 全屏时不显示标题栏和边框
+    pub fullscreen: bool,
+    // 磁贴和文字的放大倍数，远处的大屏可以调大
+    pub scale: f32,
+    // 一屏放不下时每页停留的秒数，0表示不翻页，改为滚动
+    pub page_secs: u64,
+    pub always_on_top: bool,
+}
+
+impl Default for DashboardSettings {
+    fn default() -> Self {
+        Self {
+            display: 1,
+            fullscreen: true,
+            scale: 1.0,
+            page_secs: 15,
+            always_on_top: false,
+        }
+    }
+}
+
+// 主窗口的大小和位置，退出时记录，下次启动时恢复
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowGeometry {
+    // 逻辑像素；最大化时保留最大化之前的大小
+    pub size: Option<[f32; 2]>,
+    // 窗口外框左上角
+    pub position: Option<[f32; 2]>,
+    pub maximized: bool,
+}
+
+const DEFAULT_WINDOW_SIZE: [f32; 2] = [490.0, 650.0];
+// 防止记录到最小化过程中的异常大小后窗口小到无法操作
+const MIN_WINDOW_SIZE: [f32; 2] = [320.0, 240.0];
+
+impl WindowGeometry {
+    pub fn size(&self) -> [f32; 2] {
+        match self.size {
+            Some([w, h]) => [w.max(MIN_WINDOW_SIZE[0]), h.max(MIN_WINDOW_SIZE[1])],
+            None => DEFAULT_WINDOW_SIZE,
+        }
+    }
+}
+
+// 自动检查间隔的范围：5秒到1小时
+pub const MIN_CHECK_INTERVAL_SECS: u64 = 5;
+pub const MAX_CHECK_INTERVAL_SECS: u64 = 3600;
+// 启动后首次检查的延迟与通知宽限期的上限
+pub const MAX_STARTUP_SECS: u64 = 3600;
+
+// 程序设置，保存在 settings.json
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub auto_check_enabled: bool,
+    // 自动检查的间隔（秒）
+    pub check_interval_secs: u64,
+    // 启动后多久做第一次自动检查（秒）
+    pub startup_delay_secs: u64,
+    // 启动后的这段时间内状态变化不发送通知（秒），0表示不启用
+    pub startup_grace_secs: u64,
+    // 同时进行的检查数，合计和每台主机
+    pub concurrency: ConcurrencySettings,
+    // 上次使用的配置方案
+    pub profile: String,
+    // 列表排序
+    pub sort_key: SortKey,
+    pub sort_descending: bool,
+    pub view_mode: ViewMode,
+    pub theme: ThemeMode,
+    pub status_colors: StatusColors,
+    pub language: Lang,
+    // 各分组不计入可用率的时段
+    pub group_sla: Vec<GroupSla>,
+    // 表格视图中的自定义列
+    pub columns: Vec<ColumnDef>,
+    // 分组内出现版本不一致时弹出提示
+    pub notify_version_drift: bool,
+    // 记录HTTPS服务器的TLS版本、加密套件和安全响应头，变化时提示
+    pub security_baseline: bool,
+    // 用户指定的中文字体文件，未设置时自动查找
+    pub font_path: Option<PathBuf>,
+    // 用户指定的窗口图标（PNG），未设置时使用内置图标
+    pub icon_path: Option<PathBuf>,
+    // 导出状态页的文件，未设置时导出到配置目录
+    pub status_page_path: Option<PathBuf>,
+    // 服务器列表、历史记录和安全基线的存储方式
+    pub storage: StorageSettings,
+    // 窗口重新获得焦点时显示离开期间的状态变化
+    pub away_digest: bool,
+    // 状态变化时发送通知的渠道
+    pub channels: Vec<Channel>,
+    // 自定义的标签颜色，未设置的按名称自动取色
+    pub tag_colors: BTreeMap<String, [u8; 3]>,
+    // 向外部监控定时发送心跳
+    pub heartbeat: HeartbeatSettings,
+    // 定时把配置上传到远程备份
+    pub backup: BackupSettings,
+    // 每轮检查后把延迟和在线状态推送到 InfluxDB 或 Prometheus
+    pub export: ExportSettings,
+    // 状态变化时发布到 MQTT 代理
+    pub mqtt: MqttPublishSettings,
+    // 投屏看板的显示器
+    pub dashboard: DashboardSettings,
+    // 反复上线、掉线时标记为抖动
+    pub flap: FlapSettings,
+    // 从Ansible清单或Terraform输出同步服务器
+    pub inventory: InventorySettings,
+    // 从Docker守护进程导入容器
+    pub docker: DockerImportSettings,
+    // HTTP请求使用的代理，服务器可以单独设置
+    pub proxy: ProxySettings,
+    // 云账号资源发现
+    pub discovery: DiscoverySettings,
+    // 定期生成SLA报告
+    pub report: ReportSettings,
+    pub window: WindowGeometry,
+    // 主窗口总在最前面
+    pub always_on_top: bool,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            auto_check_enabled: true,
+            check_interval_secs: 30,
+            startup_delay_secs: 30,
+            startup_grace_secs: 0,
+            concurrency: ConcurrencySettings::default(),
+            profile: DEFAULT_PROFILE.to_string(),
+            sort_key: SortKey::Manual,
+            sort_descending: false,
+            view_mode: ViewMode::Cards,
+            theme: ThemeMode::System,
+            status_colors: StatusColors::default(),
+            language: Lang::Zh,
+            group_sla: Vec::new(),
+            columns: Vec::new(),
+            notify_version_drift: true,
+            security_baseline: true,
+            font_path: None,
+            icon_path: None,
+            status_page_path: None,
+            storage: StorageSettings::default(),
+            away_digest: true,
+            channels: Vec::new(),
+            tag_colors: BTreeMap::new(),
+            heartbeat: HeartbeatSettings::default(),
+            backup: BackupSettings::default(),
+            export: ExportSettings::default(),
+            mqtt: MqttPublishSettings::default(),
+            dashboard: DashboardSettings::default(),
+            flap: FlapSettings::default(),
+            inventory: InventorySettings::default(),
+            docker: DockerImportSettings::default(),
+            proxy: ProxySettings::default(),
+            discovery: DiscoverySettings::default(),
+            report: ReportSettings::default(),
+            window: WindowGeometry::default(),
+            always_on_top: false,
+        }
+    }
+}
+
+impl Settings {
+    // 读取设置，文件不存在或格式错误时使用默认值
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    // 手动修改设置文件时可能超出范围，过短会不停地检查
+    pub fn check_interval(&self) -> Duration {
+        Duration::from_secs(
+            self.check_interval_secs
+                .clamp(MIN_CHECK_INTERVAL_SECS, MAX_CHECK_INTERVAL_SECS),
+        )
+    }
+
+    pub fn startup_delay(&self) -> Duration {
+        Duration::from_secs(self.startup_delay_secs.min(MAX_STARTUP_SECS))
+    }
+
+    pub fn startup_grace(&self) -> Duration {
+        Duration::from_secs(self.startup_grace_secs.min(MAX_STARTUP_SECS))
+    }
+
+    pub fn exclusions_for(&self, group: &str) -> &[Exclusion] {
+        self.group_sla
+            .iter()
+            .find(|g| g.group == group)
+            .map(|g| g.exclusions.as_slice())
+            .unwrap_or(&[])
+    }
+}
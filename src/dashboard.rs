@@ -0,0 +1,237 @@
+use eframe::egui;
+
+use crate::config::DashboardSettings;
+use crate::filter::{self, StatusFilter};
+use crate::i18n::tr;
+use crate::{theme, Server};
+
+// 投屏看板：在另一台显示器上全屏显示服务器状态磁贴，管理界面照常留在主显示器上
+
+const TILE_WIDTH: f32 = 220.0;
+const TILE_SPACING: f32 = 12.0;
+
+#[derive(Default)]
+pub struct Dashboard {
+    pub open: bool,
+}
+
+impl Dashboard {
+    pub fn show(&mut self, ctx: &egui::Context, servers: &[Server], settings: &DashboardSettings) {
+        // 主窗口所在显示器的大小，取不到时按1920x1080
+        let monitor = ctx
+            .input(|i| i.viewport().monitor_size)
+            .unwrap_or(egui::vec2(1920.0, 1080.0));
+        let level = if settings.always_on_top {
+            egui::WindowLevel::AlwaysOnTop
+        } else {
+            egui::WindowLevel::Normal
+        };
+        let builder = egui::ViewportBuilder::default()
+            .with_title(tr!("服务器状态看板", "Server status dashboard"))
+            .with_position(egui::pos2(monitor.x * settings.display as f32, 0.0))
+            .with_inner_size(monitor)
+            .with_fullscreen(settings.fullscreen)
+            .with_decorations(!settings.fullscreen)
+            .with_window_level(level);
+
+        ctx.show_viewport_immediate(
+            egui::ViewportId::from_hash_of("dashboard"),
+            builder,
+            |ctx, _class| {
+                egui::CentralPanel::default().show(ctx, |ui| show_tiles(ui, servers, settings));
+                if ctx.input(|i| i.viewport().close_requested() || i.key_pressed(egui::Key::Escape))
+                {
+                    self.open = false;
+                }
+            },
+        );
+    }
+}
+
+// 磁贴的高度：名称和状态各占一行（过长时截断），加上边距
+fn tile_height(ui: &egui::Ui, scale: f32) -> f32 {
+    let rows = ui.fonts(|f| {
+        f.row_height(&egui::FontId::proportional(22.0 * scale))
+            + f.row_height(&egui::FontId::proportional(16.0 * scale))
+    });
+    rows + ui.spacing().item_spacing.y + 24.0 * scale
+}
+
+// 一页能放下的磁贴数
+fn per_page(available: egui::Vec2, scale: f32, tile_height: f32) -> usize {
+    let columns = (available.x + TILE_SPACING) / ((TILE_WIDTH + 24.0) * scale + TILE_SPACING);
+    let rows = (available.y + TILE_SPACING) / (tile_height + TILE_SPACING);
+    (columns.floor().max(1.0) * rows.floor().max(1.0)) as usize
+}
+
+fn show_tiles(ui: &mut egui::Ui, servers: &[Server], settings: &DashboardSettings) {
+    let scale = settings.scale.clamp(0.5, 4.0);
+    // 标题行：一行 24 号字加分隔线
+    let header = ui.fonts(|f| f.row_height(&egui::FontId::proportional(24.0 * scale)))
+        + ui.spacing().item_spacing.y * 2.0
+        + 6.0;
+    let per_page = per_page(
+        ui.available_size() - egui::vec2(0.0, header),
+        scale,
+        tile_height(ui, scale),
+    );
+    let pages = servers.len().div_ceil(per_page).max(1);
+    // 按程序运行的时间轮换，不需要记录翻页状态
+    let page = match settings.page_secs {
+        0 => 0,
+        secs => (ui.input(|i| i.time) as u64 / secs) as usize % pages,
+    };
+    ui.horizontal_wrapped(|ui| {
+        ui.label(
+            egui::RichText::new(tr!("🖥 服务器状态", "🖥 Server status"))
+                .size(24.0 * scale)
+                .strong(),
+        );
+        for (status, count) in filter::status_counts(servers.iter()) {
+            ui.separator();
+            ui.label(
+                egui::RichText::new(format!("{}: {}", status.label(), count))
+                    .size(18.0 * scale)
+                    .color(theme::filter_color(status)),
+            );
+        }
+        if settings.page_secs > 0 && pages > 1 {
+            ui.separator();
+            ui.label(
+                egui::RichText::new(tr!("第 {}/{} 页", "Page {}/{}", page + 1, pages))
+                    .size(18.0 * scale),
+            );
+        }
+    });
+    ui.separator();
+    let tiles = |ui: &mut egui::Ui, servers: &[Server]| {
+        ui.horizontal_wrapped(|ui| {
+            ui.spacing_mut().item_spacing = egui::vec2(TILE_SPACING, TILE_SPACING);
+            for server in servers {
+                show_tile(ui, server, scale);
+            }
+        });
+    };
+    // 标题行换行等情况下一页仍可能放不下，翻页时也可以滚动
+    let shown = if settings.page_secs == 0 {
+        servers
+    } else {
+        let start = page * per_page;
+        &servers[start..(start + per_page).min(servers.len())]
+    };
+    egui::ScrollArea::vertical().show(ui, |ui| tiles(ui, shown));
+}
+
+fn show_tile(ui: &mut egui::Ui, server: &Server, scale: f32) {
+    let status = StatusFilter::of(server);
+    let text = egui::Color32::WHITE;
+    egui::Frame::none()
+        .fill(theme::filter_color(status))
+        .rounding(8.0 * scale)
+        .inner_margin(12.0 * scale)
+        .show(ui, |ui| {
+            ui.set_width(TILE_WIDTH * scale);
+            ui.add(
+                egui::Label::new(
+                    egui::RichText::new(&server.name)
+                        .size(22.0 * scale)
+                        .strong()
+                        .color(text),
+                )
+                .truncate(),
+            );
+            let detail = match server.state.latency_ms {
+                Some(ms) if status == StatusFilter::Online => {
+                    format!("{} · {} ms", status.label(), ms)
+                }
+                _ => status.label().to_string(),
+            };
+            ui.add(
+                egui::Label::new(egui::RichText::new(detail).size(16.0 * scale).color(text))
+                    .truncate(),
+            );
+        });
+}
+
+// 状态墙视图中磁贴的最小宽度和高度
+const WALL_MIN_WIDTH: f32 = 200.0;
+const WALL_MIN_HEIGHT: f32 = 90.0;
+const WALL_SPACING: f32 = 8.0;
+
+// 状态墙：主窗口中每台服务器一个大磁贴，只显示名称和延迟，磁贴随窗口大小伸缩铺满，
+// 服务器较多时滚动。返回点击的服务器下标
+pub fn show_wall(ui: &mut egui::Ui, servers: &[Server], visible: &[usize]) -> Option<usize> {
+    if visible.is_empty() {
+        return None;
+    }
+    let width = ui.available_width();
+    // 滚动区域中的可用高度不受限制，按可见部分计算
+    let height = ui.clip_rect().bottom() - ui.cursor().top();
+    let columns = ((width + WALL_SPACING) / (WALL_MIN_WIDTH + WALL_SPACING))
+        .floor()
+        .max(1.0) as usize;
+    let columns = columns.min(visible.len());
+    let rows = visible.len().div_ceil(columns);
+    let tile_width = (width - WALL_SPACING * (columns - 1) as f32) / columns as f32;
+    let tile_height =
+        ((height - WALL_SPACING * (rows - 1) as f32) / rows as f32).max(WALL_MIN_HEIGHT);
+    // 文字大小跟随磁贴大小
+    let name_size = (tile_height * 0.25)
+        .min(tile_width * 0.12)
+        .clamp(16.0, 64.0);
+
+    let mut clicked = None;
+    ui.spacing_mut().item_spacing = egui::vec2(WALL_SPACING, WALL_SPACING);
+    for row in visible.chunks(columns) {
+        ui.horizontal(|ui| {
+            for &i in row {
+                let server = &servers[i];
+                let (rect, response) = ui
+                    .allocate_exact_size(egui::vec2(tile_width, tile_height), egui::Sense::click());
+                let status = StatusFilter::of(server);
+                let painter = ui.painter();
+                painter.rect_filled(rect, 10.0, theme::filter_color(status));
+                // 名称只占一行，过长时截断，悬停可以看到完整名称
+                let mut job = egui::text::LayoutJob::single_section(
+                    server.name.clone(),
+                    egui::TextFormat::simple(
+                        egui::FontId::proportional(name_size),
+                        egui::Color32::WHITE,
+                    ),
+                );
+                job.wrap = egui::text::TextWrapping::truncate_at_width(rect.width() - 16.0);
+                let name = painter.layout_job(job);
+                let detail = match server.state.latency_ms {
+                    Some(ms) if status == StatusFilter::Online => format!("{} ms", ms),
+                    _ => status.label().to_string(),
+                };
+                let detail = painter.layout_no_wrap(
+                    detail,
+                    egui::FontId::proportional(name_size * 0.6),
+                    egui::Color32::WHITE,
+                );
+                let total = name.size().y + 4.0 + detail.size().y;
+                let top = rect.center().y - total / 2.0;
+                let name_pos = egui::pos2(rect.center().x - name.size().x / 2.0, top);
+                let detail_pos = egui::pos2(
+                    rect.center().x - detail.size().x / 2.0,
+                    top + name.size().y + 4.0,
+                );
+                painter.galley(name_pos, name, egui::Color32::WHITE);
+                painter.galley(detail_pos, detail, egui::Color32::WHITE);
+                if response
+                    .on_hover_text(format!(
+                        "{}\n{}\n{}",
+                        server.name,
+                        server.target_label(),
+                        server.state.detail
+                    ))
+                    .clicked()
+                {
+                    clicked = Some(i);
+                }
+            }
+        });
+    }
+    clicked
+}
@@ -0,0 +1,498 @@
+use chrono::{Datelike, NaiveDate};
+use eframe::egui;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::checks::inspect::{self, HttpDetails};
+use crate::checks::tls::{self, CertInfo};
+use crate::checks::{transition, CheckKind};
+use crate::history::{History, Incident};
+use crate::i18n::tr;
+use crate::{template, theme, Server, ServerStatus};
+
+// 热力图显示的天数和格子大小
+const HEATMAP_DAYS: u32 = 90;
+const HEATMAP_CELL: f32 = 12.0;
+const HEATMAP_GAP: f32 = 3.0;
+
+type DailyUptime = Vec<(NaiveDate, Option<f64>)>;
+
+// 后台请求的结果，附带请求的URL，目标切换后丢弃旧结果
+type InspectSlot = Arc<Mutex<Option<(String, HttpDetails)>>>;
+
+// 单台服务器的详情窗口：检查结果，以及单独请求一次得到的原始响应信息
+#[derive(Default)]
+pub struct DetailWindow {
+    // 以历史记录的键确定服务器，列表顺序变化后仍指向同一台
+    target: Option<String>,
+    // 正在请求的URL
+    pending: Option<String>,
+    details: Option<HttpDetails>,
+    result: InspectSlot,
+    // 正在填写故障原因，输入框失去焦点时才保存历史记录
+    cause_edited: bool,
+    // 故障原因有修改，需要保存历史记录
+    pub history_changed: bool,
+    // 今天以前的每日可用率不会再变，按服务器和日期缓存，不用每帧重新统计
+    heatmap: Option<(String, NaiveDate, DailyUptime)>,
+}
+
+fn format_duration(value: Option<Duration>) -> String {
+    value
+        .map(|d| format!("{:.1}ms", d.as_secs_f64() * 1000.0))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+impl DetailWindow {
+    pub fn is_open(&self) -> bool {
+        self.target.is_some()
+    }
+
+    pub fn open(&mut self, server: &Server, client: &reqwest::Client) {
+        self.target = Some(server.history_key());
+        self.details = None;
+        self.refresh(server, client);
+    }
+
+    fn refresh(&mut self, server: &Server, client: &reqwest::Client) {
+        if server.check != CheckKind::Http {
+            self.pending = None;
+            return;
+        }
+        let url = template::expand(&server.url).into_owned();
+        self.pending = Some(url.clone());
+        let slot = Arc::clone(&self.result);
+        let client = client.clone();
+        tokio::spawn(async move {
+            let details = inspect::inspect(&client, &url).await;
+            *slot.lock().unwrap() = Some((url, details));
+        });
+    }
+
+    fn apply_result(&mut self) {
+        let Some((url, details)) = self.result.lock().unwrap().take() else {
+            return;
+        };
+        if self.pending.as_ref() == Some(&url) {
+            self.pending = None;
+            self.details = Some(details);
+        }
+    }
+
+    // 缓存的以前各天加上每帧重新计算的今天
+    fn daily_uptime(&mut self, history: &History, key: &str) -> DailyUptime {
+        let today = chrono::Local::now().date_naive();
+        let cached = matches!(&self.heatmap, Some((k, day, _)) if k == key && *day == today);
+        if !cached {
+            let past = today
+                .pred_opt()
+                .map(|yesterday| history.daily_uptime(key, yesterday, HEATMAP_DAYS - 1))
+                .unwrap_or_default();
+            self.heatmap = Some((key.to_string(), today, past));
+        }
+        let mut days = self
+            .heatmap
+            .as_ref()
+            .map(|(_, _, days)| days.clone())
+            .unwrap_or_default();
+        days.extend(history.daily_uptime(key, today, 1));
+        days
+    }
+
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        servers: &[Server],
+        history: &mut History,
+        client: &reqwest::Client,
+    ) {
+        self.apply_result();
+        let Some(key) = self.target.clone() else {
+            return;
+        };
+        // 服务器被删除后关闭窗口
+        let Some(server) = servers.iter().find(|s| s.history_key() == key) else {
+            self.target = None;
+            return;
+        };
+
+        let days = self.daily_uptime(history, &key);
+        let mut open = true;
+        let mut refresh = false;
+        egui::Window::new(tr!("🔍 {} 详情", "🔍 {} details", server.name))
+            .id(egui::Id::new("server_detail"))
+            .open(&mut open)
+            .resizable(true)
+            .default_width(520.0)
+            .show(ctx, |ui| {
+                egui::Grid::new("detail_summary")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label(tr!("目标:", "Target:"));
+                        ui.label(server.target_label());
+                        ui.end_row();
+                        ui.label(tr!("状态:", "Status:"));
+                        ui.colored_label(server.status.color(), server.status.to_string());
+                        ui.end_row();
+                        if !server.state.detail.is_empty() {
+                            ui.label(tr!("说明:", "Detail:"));
+                            ui.label(&server.state.detail);
+                            ui.end_row();
+                        }
+                        ui.label(tr!("延迟:", "Latency:"));
+                        ui.label(
+                            server
+                                .state
+                                .latency_ms
+                                .map(|ms| format!("{}ms", ms))
+                                .unwrap_or_else(|| "-".to_string()),
+                        );
+                        ui.end_row();
+                        // 检查得到的附加字段，例如自定义列和 Deployment 的副本数
+                        let mut fields: Vec<_> = server.state.fields.iter().collect();
+                        fields.sort();
+                        for (key, value) in fields {
+                            ui.label(format!("{}:", key));
+                            ui.label(value);
+                            ui.end_row();
+                        }
+                        if let Some(time) = server.state.last_checked {
+                            ui.label(tr!("上次检查:", "Last check:"));
+                            ui.label(time.format("%Y-%m-%d %H:%M:%S").to_string());
+                            ui.end_row();
+                        }
+                    });
+                if !server.notes.is_empty() {
+                    ui.separator();
+                    ui.strong(tr!("备注", "Notes"));
+                    ui.label(&server.notes);
+                }
+
+                ui.separator();
+                egui::CollapsingHeader::new(tr!(
+                    "每日可用率（{}天）",
+                    "Daily uptime ({} days)",
+                    HEATMAP_DAYS
+                ))
+                .id_source("detail_heatmap")
+                .default_open(true)
+                .show(ui, |ui| show_heatmap(ui, &days));
+
+                ui.separator();
+                let incidents = history.incidents_mut(&key);
+                egui::CollapsingHeader::new(tr!(
+                    "故障记录 ({})",
+                    "Incidents ({})",
+                    incidents.len()
+                ))
+                .id_source("detail_incidents")
+                .show(ui, |ui| {
+                    if show_incidents(ui, incidents, &mut self.cause_edited) {
+                        self.history_changed = true;
+                    }
+                });
+
+                ui.separator();
+                if server.check != CheckKind::Http {
+                    ui.small(tr!(
+                        "响应详情仅适用于HTTP检查",
+                        "Response details are only available for HTTP checks"
+                    ));
+                    return;
+                }
+                ui.horizontal(|ui| {
+                    ui.strong(tr!("响应详情", "Response details"));
+                    if self.pending.is_some() {
+                        ui.spinner();
+                    } else if ui.small_button(tr!("🔄 刷新", "🔄 Refresh")).clicked() {
+                        refresh = true;
+                    }
+                });
+                if let Some(details) = &self.details {
+                    show_details(ui, details);
+                }
+            });
+
+        if refresh {
+            self.refresh(server, client);
+        }
+        if !open {
+            self.target = None;
+            self.pending = None;
+            self.details = None;
+        }
+    }
+}
+
+// 与状态的颜色一致：全天可用为在线色，有少量故障时颜色变浅，故障较多时为降级和离线色
+fn heat_color(ui: &egui::Ui, uptime: Option<f64>) -> egui::Color32 {
+    match uptime {
+        None => ui.visuals().widgets.inactive.bg_fill,
+        Some(u) if u >= 99.99 => theme::status_color(&ServerStatus::Online),
+        Some(u) if u >= 99.0 => theme::status_color(&ServerStatus::Online).gamma_multiply(0.55),
+        Some(u) if u >= 95.0 => theme::status_color(&ServerStatus::Degraded),
+        Some(_) => theme::status_color(&ServerStatus::Offline),
+    }
+}
+
+// 类似 GitHub 贡献图：每列一周，周一在最上面，每格一天，悬停显示当天的可用率
+fn show_heatmap(ui: &mut egui::Ui, days: &[(NaiveDate, Option<f64>)]) {
+    let Some((first, _)) = days.first() else {
+        return;
+    };
+    let offset = first.weekday().num_days_from_monday() as usize;
+    let weeks = (offset + days.len()).div_ceil(7);
+    let step = HEATMAP_CELL + HEATMAP_GAP;
+    let (rect, response) = ui.allocate_exact_size(
+        egui::vec2(weeks as f32 * step, 7.0 * step),
+        egui::Sense::hover(),
+    );
+    let cell_rect = |index: usize| {
+        let slot = offset + index;
+        let min = rect.min + egui::vec2((slot / 7) as f32 * step, (slot % 7) as f32 * step);
+        egui::Rect::from_min_size(min, egui::vec2(HEATMAP_CELL, HEATMAP_CELL))
+    };
+    let painter = ui.painter();
+    for (i, (_, uptime)) in days.iter().enumerate() {
+        painter.rect_filled(cell_rect(i), 2.0, heat_color(ui, *uptime));
+    }
+    let hovered = response
+        .hover_pos()
+        .and_then(|pos| (0..days.len()).find(|&i| cell_rect(i).expand(1.0).contains(pos)));
+    if let Some(i) = hovered {
+        let (day, uptime) = days[i];
+        painter.rect_stroke(cell_rect(i), 2.0, ui.visuals().widgets.hovered.fg_stroke);
+        response.on_hover_ui_at_pointer(|ui| {
+            ui.label(day.format("%Y-%m-%d").to_string());
+            ui.label(match uptime {
+                Some(u) => tr!("可用率 {:.2}%", "Uptime {:.2}%", u),
+                None => tr!("没有检查记录", "No checks").to_string(),
+            });
+        });
+    }
+    ui.horizontal(|ui| {
+        for (label, uptime) in [
+            ("100%", Some(100.0)),
+            ("≥99%", Some(99.0)),
+            ("≥95%", Some(95.0)),
+            ("<95%", Some(0.0)),
+            (tr!("无数据", "No data"), None),
+        ] {
+            ui.colored_label(heat_color(ui, uptime), "■");
+            ui.small(label);
+        }
+    });
+}
+
+// 最近的故障在前，返回是否填写完了一条原因
+fn show_incidents(ui: &mut egui::Ui, incidents: &mut [Incident], edited: &mut bool) -> bool {
+    if incidents.is_empty() {
+        ui.small(tr!("还没有故障记录", "No incidents yet"));
+        return false;
+    }
+    let now = chrono::Local::now();
+    let mut finished = false;
+    egui::ScrollArea::vertical()
+        .id_source("incident_list")
+        .max_height(240.0)
+        .show(ui, |ui| {
+            egui::Grid::new("incident_grid")
+                .num_columns(4)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.strong(tr!("开始", "Start"));
+                    ui.strong(tr!("结束", "End"));
+                    ui.strong(tr!("持续", "Duration"));
+                    ui.strong(tr!("原因", "Cause"));
+                    ui.end_row();
+                    for (i, incident) in incidents.iter_mut().enumerate().rev() {
+                        ui.label(incident.start.format("%m-%d %H:%M").to_string());
+                        match incident.end {
+                            Some(end) => ui.label(end.format("%m-%d %H:%M").to_string()),
+                            None => ui.colored_label(
+                                ServerStatus::Offline.color(),
+                                tr!("进行中", "Ongoing"),
+                            ),
+                        };
+                        ui.label(transition::format_downtime(incident.duration(now)));
+                        let response = ui.add(
+                            egui::TextEdit::singleline(&mut incident.cause)
+                                .id(egui::Id::new(("incident_cause", i)))
+                                .hint_text(tr!("填写原因", "Add a cause"))
+                                .desired_width(200.0),
+                        );
+                        if response.changed() {
+                            *edited = true;
+                        }
+                        if response.lost_focus() && std::mem::take(edited) {
+                            finished = true;
+                        }
+                        ui.end_row();
+                    }
+                });
+        });
+    finished
+}
+
+fn format_time(time: Option<chrono::DateTime<chrono::Local>>) -> String {
+    time.map(|t| t.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+// 第一张为服务器证书，之后是中间证书
+fn show_cert(ui: &mut egui::Ui, index: usize, cert: &CertInfo) {
+    let role = if index == 0 {
+        tr!("服务器证书", "Leaf")
+    } else {
+        tr!("中间证书", "Intermediate")
+    };
+    egui::CollapsingHeader::new(format!("{}. {} · {}", index + 1, role, cert.subject))
+        .id_source(("detail_cert", index))
+        .default_open(index == 0)
+        .show(ui, |ui| {
+            egui::Grid::new(("detail_cert_fields", index))
+                .num_columns(2)
+                .show(ui, |ui| {
+                    ui.label(tr!("主题:", "Subject:"));
+                    ui.label(&cert.subject);
+                    ui.end_row();
+                    ui.label(tr!("颁发者:", "Issuer:"));
+                    ui.label(&cert.issuer);
+                    ui.end_row();
+                    ui.label(tr!("有效期:", "Valid:"));
+                    let expired = cert.not_after.is_some_and(|t| t < chrono::Local::now());
+                    let validity = format!(
+                        "{} ~ {}",
+                        format_time(cert.not_before),
+                        format_time(cert.not_after)
+                    );
+                    if expired {
+                        ui.colored_label(ServerStatus::Offline.color(), validity);
+                    } else {
+                        ui.label(validity);
+                    }
+                    ui.end_row();
+                    if !cert.sans.is_empty() {
+                        ui.label(tr!("备用名称:", "SANs:"));
+                        ui.label(cert.sans.join(", "));
+                        ui.end_row();
+                    }
+                    ui.label(tr!("密钥:", "Key:"));
+                    ui.label(&cert.key_type);
+                    ui.end_row();
+                });
+            if ui.small_button(tr!("📋 复制PEM", "📋 Copy PEM")).clicked() {
+                ui.ctx().copy_text(cert.pem());
+            }
+        });
+}
+
+fn show_details(ui: &mut egui::Ui, details: &HttpDetails) {
+    if let Some(error) = &details.error {
+        ui.colored_label(ServerStatus::Offline.color(), error);
+    }
+    egui::Grid::new("detail_response")
+        .num_columns(2)
+        .show(ui, |ui| {
+            ui.label(tr!("请求URL:", "URL:"));
+            ui.label(&details.url);
+            ui.end_row();
+            ui.label(tr!("解析地址:", "Resolved:"));
+            let resolved: Vec<String> = details.resolved.iter().map(|ip| ip.to_string()).collect();
+            ui.label(if resolved.is_empty() {
+                "-".to_string()
+            } else {
+                resolved.join(", ")
+            });
+            ui.end_row();
+            if let Some(addr) = details.remote_addr {
+                ui.label(tr!("连接地址:", "Connected to:"));
+                ui.label(addr.to_string());
+                ui.end_row();
+            }
+            if let Some(status) = details.status {
+                ui.label(tr!("HTTP状态:", "HTTP status:"));
+                ui.label(format!("{} ({})", status, details.version));
+                ui.end_row();
+            }
+            if let Some(tls) = &details.tls {
+                ui.label("TLS:");
+                ui.label(format!("{} / {}", tls.version, tls.cipher));
+                ui.end_row();
+                if let Some(expires) = tls.cert_expires {
+                    ui.label(tr!("证书到期:", "Certificate expires:"));
+                    ui.label(expires.format("%Y-%m-%d %H:%M").to_string());
+                    ui.end_row();
+                }
+            }
+            ui.label(tr!("耗时:", "Timing:"));
+            ui.label(tr!(
+                "DNS {} · 连接 {} · 首字节 {} · 总计 {}",
+                "DNS {} · connect {} · TTFB {} · total {}",
+                format_duration(details.dns),
+                format_duration(details.connect),
+                format_duration(details.ttfb),
+                format_duration(details.total)
+            ));
+            ui.end_row();
+        });
+
+    if let Some(tls) = details.tls.as_ref().filter(|tls| !tls.chain.is_empty()) {
+        egui::CollapsingHeader::new(tr!(
+            "证书链 ({})",
+            "Certificate chain ({})",
+            tls.chain.len()
+        ))
+        .id_source("detail_chain")
+        .show(ui, |ui| {
+            if ui
+                .small_button(tr!("📋 复制整条证书链PEM", "📋 Copy chain as PEM"))
+                .clicked()
+            {
+                ui.ctx().copy_text(tls::chain_pem(&tls.chain));
+            }
+            for (i, cert) in tls.chain.iter().enumerate() {
+                show_cert(ui, i, cert);
+            }
+        });
+    }
+    if !details.headers.is_empty() {
+        egui::CollapsingHeader::new(tr!(
+            "响应头 ({})",
+            "Response headers ({})",
+            details.headers.len()
+        ))
+        .default_open(true)
+        .show(ui, |ui| {
+            egui::Grid::new("detail_headers")
+                .striped(true)
+                .num_columns(2)
+                .show(ui, |ui| {
+                    for (name, value) in &details.headers {
+                        ui.monospace(name);
+                        ui.monospace(value);
+                        ui.end_row();
+                    }
+                });
+        });
+    }
+    if details.status.is_some() {
+        let title = if details.body_truncated {
+            tr!("响应内容（仅显示开头部分）", "Body (truncated)")
+        } else {
+            tr!("响应内容", "Body")
+        };
+        egui::CollapsingHeader::new(title).show(ui, |ui| {
+            egui::ScrollArea::vertical()
+                .max_height(240.0)
+                .show(ui, |ui| {
+                    let mut body = details.body_preview.as_str();
+                    ui.add(
+                        egui::TextEdit::multiline(&mut body)
+                            .code_editor()
+                            .desired_width(f32::INFINITY),
+                    );
+                });
+        });
+    }
+}
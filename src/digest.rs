@@ -0,0 +1,154 @@
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+
+use crate::ServerStatus;
+
+// 一次状态变化
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusChange {
+    pub at: DateTime<Local>,
+    pub server: String,
+    pub from: ServerStatus,
+    pub to: ServerStatus,
+    // 从故障中恢复时，这次故障持续的时间
+    pub down_for: Option<chrono::Duration>,
+}
+
+// 最近的状态变化，只保存在内存中，用于生成离开期间的摘要
+#[derive(Debug, Default)]
+pub struct ChangeLog {
+    entries: VecDeque<StatusChange>,
+}
+
+impl ChangeLog {
+    const MAX_ENTRIES: usize = 2000;
+
+    // 第一次检查（从未检查变为其他状态）不算变化
+    pub fn record(&mut self, change: StatusChange) {
+        if change.from == ServerStatus::Unchecked || change.from == change.to {
+            return;
+        }
+        self.entries.push_back(change);
+        if self.entries.len() > Self::MAX_ENTRIES {
+            self.entries.pop_front();
+        }
+    }
+
+    pub fn since(&self, at: DateTime<Local>) -> Vec<StatusChange> {
+        self.entries.iter().filter(|c| c.at > at).cloned().collect()
+    }
+}
+
+// 一台服务器在离开期间的变化
+#[derive(Debug, Clone, PartialEq)]
+pub struct DigestEntry {
+    pub server: String,
+    // 依次经过的状态，第一个为离开时的状态
+    pub path: Vec<ServerStatus>,
+    pub last_at: DateTime<Local>,
+}
+
+impl DigestEntry {
+    pub fn changes(&self) -> usize {
+        self.path.len() - 1
+    }
+
+    // 离开时和现在的状态相同，说明中间出过问题又恢复了
+    pub fn recovered(&self) -> bool {
+        self.path.first() == self.path.last()
+    }
+}
+
+// 按服务器归并变化，最近有变化的排在前面
+pub fn summarize(changes: &[StatusChange]) -> Vec<DigestEntry> {
+    let mut entries: Vec<DigestEntry> = Vec::new();
+    for change in changes {
+        match entries.iter_mut().find(|e| e.server == change.server) {
+            Some(entry) => {
+                entry.path.push(change.to.clone());
+                entry.last_at = change.at;
+            }
+            None => entries.push(DigestEntry {
+                server: change.server.clone(),
+                path: vec![change.from.clone(), change.to.clone()],
+                last_at: change.at,
+            }),
+        }
+    }
+    entries.sort_by_key(|e| std::cmp::Reverse(e.last_at));
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn change(
+        at: DateTime<Local>,
+        server: &str,
+        from: ServerStatus,
+        to: ServerStatus,
+    ) -> StatusChange {
+        StatusChange {
+            at,
+            server: server.to_string(),
+            from,
+            to,
+            down_for: None,
+        }
+    }
+
+    #[test]
+    fn first_check_is_not_recorded() {
+        let mut log = ChangeLog::default();
+        let now = Local::now();
+        log.record(change(
+            now,
+            "a",
+            ServerStatus::Unchecked,
+            ServerStatus::Online,
+        ));
+        log.record(change(now, "a", ServerStatus::Online, ServerStatus::Online));
+        assert!(log.since(now - Duration::hours(1)).is_empty());
+    }
+
+    #[test]
+    fn overnight_flap_is_summarized() {
+        let mut log = ChangeLog::default();
+        let left = Local::now() - Duration::hours(8);
+        let at = |h| left + Duration::hours(h);
+        log.record(change(
+            left - Duration::hours(1),
+            "old",
+            ServerStatus::Online,
+            ServerStatus::Offline,
+        ));
+        log.record(change(
+            at(1),
+            "web",
+            ServerStatus::Online,
+            ServerStatus::Offline,
+        ));
+        log.record(change(
+            at(2),
+            "db",
+            ServerStatus::Online,
+            ServerStatus::Error(500),
+        ));
+        log.record(change(
+            at(3),
+            "web",
+            ServerStatus::Offline,
+            ServerStatus::Online,
+        ));
+
+        let digest = summarize(&log.since(left));
+        assert_eq!(digest.len(), 2);
+        assert_eq!(digest[0].server, "web");
+        assert_eq!(digest[0].changes(), 2);
+        assert!(digest[0].recovered());
+        assert_eq!(digest[1].server, "db");
+        assert!(!digest[1].recovered());
+    }
+}
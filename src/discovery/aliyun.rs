@@ -0,0 +1,266 @@
+use anyhow::{bail, Result};
+use base64::Engine;
+use ring::hmac;
+use serde_json::Value;
+
+use super::{canonical_query, pick_host, tag, uri_encode, CloudAccount, Target, TargetKind};
+
+// 阿里云：ECS DescribeInstances 和 SLB DescribeLoadBalancers，RPC风格接口，
+// 请求参数使用 HMAC-SHA1 签名，返回JSON
+
+const ECS_VERSION: &str = "2014-05-26";
+const SLB_VERSION: &str = "2014-05-15";
+const PAGE_SIZE: usize = 100;
+
+// 按RPC签名规则计算 Signature 参数；params 不含 Signature 本身
+pub fn signature(secret: &str, method: &str, params: &[(&str, String)]) -> String {
+    let string_to_sign = format!(
+        "{}&{}&{}",
+        method,
+        uri_encode("/"),
+        uri_encode(&canonical_query(params))
+    );
+    let key = hmac::Key::new(
+        hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+        format!("{}&", secret).as_bytes(),
+    );
+    let tag = hmac::sign(&key, string_to_sign.as_bytes());
+    base64::engine::general_purpose::STANDARD.encode(tag.as_ref())
+}
+
+async fn call(
+    client: &reqwest::Client,
+    account: &CloudAccount,
+    host: &str,
+    version: &str,
+    action: &str,
+    extra: &[(&str, String)],
+) -> Result<Value> {
+    let mut params = vec![
+        ("Action", action.to_string()),
+        ("Version", version.to_string()),
+        ("Format", "JSON".to_string()),
+        ("AccessKeyId", account.access_key_id.trim().to_string()),
+        ("SignatureMethod", "HMAC-SHA1".to_string()),
+        ("SignatureVersion", "1.0".to_string()),
+        ("SignatureNonce", uuid::Uuid::new_v4().to_string()),
+        (
+            "Timestamp",
+            chrono::Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string(),
+        ),
+        ("RegionId", account.region.trim().to_string()),
+    ];
+    params.extend_from_slice(extra);
+    let signature = signature(account.secret_key.trim(), "GET", &params);
+    params.push(("Signature", signature));
+
+    let resp = client
+        .get(format!("https://{}/?{}", host, canonical_query(&params)))
+        .send()
+        .await?;
+    let status = resp.status();
+    let json: Value = resp.json().await?;
+    if !status.is_success() {
+        let message = json["Message"].as_str().unwrap_or(status.as_str());
+        bail!("{}", message);
+    }
+    Ok(json)
+}
+
+fn strings(value: &Value) -> impl Iterator<Item = &str> {
+    value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+}
+
+fn tags_of(value: &Value) -> Vec<String> {
+    value["Tags"]["Tag"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|t| {
+            tag(
+                t["TagKey"].as_str().unwrap_or_default(),
+                t["TagValue"].as_str().unwrap_or_default(),
+            )
+        })
+        .collect()
+}
+
+// 解析 DescribeInstances 的一页，返回实例和总数
+pub fn parse_instances(json: &Value) -> (Vec<Target>, usize) {
+    let targets = json["Instances"]["Instance"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|instance| {
+            let eip = instance["EipAddress"]["IpAddress"].as_str();
+            let candidates = strings(&instance["PublicIpAddress"]["IpAddress"])
+                .chain(eip)
+                .chain(strings(
+                    &instance["VpcAttributes"]["PrivateIpAddress"]["IpAddress"],
+                ))
+                .chain(strings(&instance["InnerIpAddress"]["IpAddress"]));
+            Target {
+                account: String::new(),
+                kind: TargetKind::Instance,
+                id: instance["InstanceId"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                name: instance["InstanceName"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                host: pick_host(candidates).unwrap_or_default(),
+                tags: tags_of(instance),
+            }
+        })
+        .collect();
+    (targets, json["TotalCount"].as_u64().unwrap_or(0) as usize)
+}
+
+// 解析 DescribeLoadBalancers 的一页，返回负载均衡和总数
+pub fn parse_load_balancers(json: &Value) -> (Vec<Target>, usize) {
+    let targets = json["LoadBalancers"]["LoadBalancer"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|lb| Target {
+            account: String::new(),
+            kind: TargetKind::LoadBalancer,
+            id: lb["LoadBalancerId"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            name: lb["LoadBalancerName"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            host: lb["Address"].as_str().unwrap_or_default().to_string(),
+            tags: tags_of(lb),
+        })
+        .collect();
+    (targets, json["TotalCount"].as_u64().unwrap_or(0) as usize)
+}
+
+// 按页取完一种资源
+async fn list_all(
+    client: &reqwest::Client,
+    account: &CloudAccount,
+    host: &str,
+    version: &str,
+    action: &str,
+    extra: &[(&str, String)],
+    parse: fn(&Value) -> (Vec<Target>, usize),
+) -> Result<Vec<Target>> {
+    let mut targets = Vec::new();
+    for page in 1.. {
+        let mut params = vec![
+            ("PageSize", PAGE_SIZE.to_string()),
+            ("PageNumber", page.to_string()),
+        ];
+        params.extend_from_slice(extra);
+        let json = call(client, account, host, version, action, &params).await?;
+        let (items, total) = parse(&json);
+        let done = items.len() < PAGE_SIZE || targets.len() + items.len() >= total;
+        targets.extend(items);
+        if done {
+            break;
+        }
+    }
+    Ok(targets)
+}
+
+// 列出运行中的ECS实例和SLB负载均衡
+pub async fn discover(client: &reqwest::Client, account: &CloudAccount) -> Result<Vec<Target>> {
+    let region = account.region.trim();
+    let mut targets = list_all(
+        client,
+        account,
+        &format!("ecs.{}.aliyuncs.com", region),
+        ECS_VERSION,
+        "DescribeInstances",
+        &[("Status", "Running".to_string())],
+        parse_instances,
+    )
+    .await?;
+    targets.extend(
+        list_all(
+            client,
+            account,
+            &format!("slb.{}.aliyuncs.com", region),
+            SLB_VERSION,
+            "DescribeLoadBalancers",
+            &[],
+            parse_load_balancers,
+        )
+        .await?,
+    );
+    Ok(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 阿里云文档中的签名示例
+    #[test]
+    fn signs_documented_example() {
+        let params = [
+            ("Timestamp", "2016-02-23T12:46:24Z".to_string()),
+            ("Format", "XML".to_string()),
+            ("AccessKeyId", "testid".to_string()),
+            ("Action", "DescribeRegions".to_string()),
+            ("SignatureMethod", "HMAC-SHA1".to_string()),
+            (
+                "SignatureNonce",
+                "3ee8c1b8-83d3-44af-a94f-4e0ad82fd6cf".to_string(),
+            ),
+            ("Version", "2014-05-26".to_string()),
+            ("SignatureVersion", "1.0".to_string()),
+        ];
+        assert_eq!(
+            signature("testsecret", "GET", &params),
+            "OLeaidS1JvxuMvnyHOwuJ+uX5qY="
+        );
+    }
+
+    #[test]
+    fn parses_instances_preferring_public_address() {
+        let json: Value = serde_json::from_str(
+            r#"{"TotalCount": 2, "Instances": {"Instance": [
+                {"InstanceId": "i-bp1", "InstanceName": "web-1",
+                 "PublicIpAddress": {"IpAddress": []},
+                 "EipAddress": {"IpAddress": "47.1.2.3"},
+                 "VpcAttributes": {"PrivateIpAddress": {"IpAddress": ["172.16.0.1"]}},
+                 "Tags": {"Tag": [{"TagKey": "env", "TagValue": "prod"}]}},
+                {"InstanceId": "i-bp2", "InstanceName": "db-1",
+                 "EipAddress": {"IpAddress": ""},
+                 "VpcAttributes": {"PrivateIpAddress": {"IpAddress": ["172.16.0.2"]}}}
+            ]}}"#,
+        )
+        .unwrap();
+        let (targets, total) = parse_instances(&json);
+        assert_eq!(total, 2);
+        assert_eq!(targets[0].host, "47.1.2.3");
+        assert_eq!(targets[0].tags, vec!["env:prod".to_string()]);
+        assert_eq!(targets[1].host, "172.16.0.2");
+        assert!(targets[1].tags.is_empty());
+    }
+
+    #[test]
+    fn parses_load_balancers() {
+        let json: Value = serde_json::from_str(
+            r#"{"TotalCount": 1, "LoadBalancers": {"LoadBalancer": [
+                {"LoadBalancerId": "lb-bp1", "LoadBalancerName": "api", "Address": "39.1.2.3"}
+            ]}}"#,
+        )
+        .unwrap();
+        let (targets, _) = parse_load_balancers(&json);
+        assert_eq!(targets[0].kind, TargetKind::LoadBalancer);
+        assert_eq!(targets[0].host, "39.1.2.3");
+    }
+}
@@ -0,0 +1,416 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Utc};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+
+use super::{
+    canonical_query, hmac_sha256, pick_host, sha256_hex, tag, CloudAccount, Target, TargetKind,
+};
+use crate::i18n::tr;
+
+// AWS：EC2 DescribeInstances 和 ELBv2 DescribeLoadBalancers，查询接口返回XML，
+// 请求使用 Signature Version 4 签名
+
+const EC2_VERSION: &str = "2016-11-15";
+const ELB_VERSION: &str = "2015-12-01";
+
+// 中国区的域名不同
+fn endpoint(service: &str, region: &str) -> String {
+    let domain = if region.starts_with("cn-") {
+        "amazonaws.com.cn"
+    } else {
+        "amazonaws.com"
+    };
+    format!("{}.{}.{}", service, region, domain)
+}
+
+pub struct SigningRequest<'a> {
+    pub method: &'a str,
+    pub host: &'a str,
+    pub path: &'a str,
+    // 已排序编码的查询参数
+    pub query: &'a str,
+    // 除 host 和 x-amz-date 外需要签名的请求头，名称小写
+    pub headers: &'a [(&'a str, &'a str)],
+    pub payload: &'a [u8],
+}
+
+// 生成 Authorization 请求头
+pub fn authorization(
+    account: &CloudAccount,
+    service: &str,
+    at: DateTime<Utc>,
+    request: &SigningRequest,
+) -> String {
+    let amz_date = at.format("%Y%m%dT%H%M%SZ").to_string();
+    let date = at.format("%Y%m%d").to_string();
+    let region = account.region.trim();
+
+    let mut headers: Vec<(String, String)> = request
+        .headers
+        .iter()
+        .map(|(k, v)| (k.to_string(), v.trim().to_string()))
+        .collect();
+    headers.push(("host".to_string(), request.host.to_string()));
+    headers.push(("x-amz-date".to_string(), amz_date.clone()));
+    headers.sort();
+    let canonical_headers: String = headers
+        .iter()
+        .map(|(k, v)| format!("{}:{}\n", k, v))
+        .collect();
+    let signed_headers = headers
+        .iter()
+        .map(|(k, _)| k.as_str())
+        .collect::<Vec<_>>()
+        .join(";");
+
+    let canonical_request = format!(
+        "{}\n{}\n{}\n{}\n{}\n{}",
+        request.method,
+        request.path,
+        request.query,
+        canonical_headers,
+        signed_headers,
+        sha256_hex(request.payload)
+    );
+    let scope = format!("{}/{}/{}/aws4_request", date, region, service);
+    let string_to_sign = format!(
+        "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+        amz_date,
+        scope,
+        sha256_hex(canonical_request.as_bytes())
+    );
+
+    let secret = format!("AWS4{}", account.secret_key.trim());
+    let key = hmac_sha256(secret.as_bytes(), &date);
+    let key = hmac_sha256(&key, region);
+    let key = hmac_sha256(&key, service);
+    let key = hmac_sha256(&key, "aws4_request");
+    let signature = hex::encode(hmac_sha256(&key, &string_to_sign));
+    format!(
+        "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+        account.access_key_id.trim(),
+        scope,
+        signed_headers,
+        signature
+    )
+}
+
+// 发送一次签名的GET请求，返回XML
+async fn query(
+    client: &reqwest::Client,
+    account: &CloudAccount,
+    service: &str,
+    params: &[(&str, String)],
+) -> Result<String> {
+    let host = endpoint(service, account.region.trim());
+    let query = canonical_query(params);
+    let now = Utc::now();
+    let request = SigningRequest {
+        method: "GET",
+        host: &host,
+        path: "/",
+        query: &query,
+        headers: &[],
+        payload: b"",
+    };
+    let auth = authorization(account, service, now, &request);
+    let resp = client
+        .get(format!("https://{}/?{}", host, query))
+        .header("x-amz-date", now.format("%Y%m%dT%H%M%SZ").to_string())
+        .header("authorization", auth)
+        .send()
+        .await?;
+    let status = resp.status();
+    let body = resp.text().await?;
+    if !status.is_success() {
+        let node = parse_xml(&body).ok();
+        let message = node
+            .as_ref()
+            .and_then(|n| {
+                n.find(&["Errors", "Error", "Message"])
+                    .or(n.find(&["Error", "Message"]))
+            })
+            .map(|n| n.text.clone())
+            .unwrap_or_else(|| status.to_string());
+        bail!(message);
+    }
+    Ok(body)
+}
+
+// 简单的XML元素树，只保留元素名（去掉命名空间前缀）和文本
+#[derive(Debug, Default)]
+pub struct XmlNode {
+    pub name: String,
+    pub text: String,
+    pub children: Vec<XmlNode>,
+}
+
+impl XmlNode {
+    fn child(&self, name: &str) -> Option<&XmlNode> {
+        self.children.iter().find(|c| c.name == name)
+    }
+
+    fn children<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a XmlNode> {
+        self.children.iter().filter(move |c| c.name == name)
+    }
+
+    // 按路径逐级查找第一个匹配的子元素
+    fn find(&self, path: &[&str]) -> Option<&XmlNode> {
+        path.iter().try_fold(self, |node, name| node.child(name))
+    }
+
+    fn text_of(&self, name: &str) -> &str {
+        self.child(name).map_or("", |c| c.text.as_str())
+    }
+}
+
+// 返回文档的根元素
+pub fn parse_xml(text: &str) -> Result<XmlNode> {
+    let mut reader = Reader::from_str(text);
+    reader.config_mut().trim_text(true);
+    let mut stack = vec![XmlNode::default()];
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => stack.push(XmlNode {
+                name: String::from_utf8_lossy(e.local_name().as_ref()).into_owned(),
+                ..Default::default()
+            }),
+            Event::Empty(e) => {
+                let node = XmlNode {
+                    name: String::from_utf8_lossy(e.local_name().as_ref()).into_owned(),
+                    ..Default::default()
+                };
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(node);
+                }
+            }
+            Event::Text(t) => {
+                if let Some(node) = stack.last_mut() {
+                    node.text.push_str(&t.unescape()?);
+                }
+            }
+            // 栈底是文档本身，结束标签不能把它弹出
+            Event::End(_) => match (stack.pop(), stack.last_mut()) {
+                (Some(node), Some(parent)) => parent.children.push(node),
+                _ => bail!(tr!(
+                    "XML结束标签多于开始标签",
+                    "XML has more end tags than start tags"
+                )),
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    stack
+        .pop()
+        .and_then(|root| root.children.into_iter().next())
+        .ok_or_else(|| anyhow!(tr!("响应不是XML", "Response is not XML")))
+}
+
+fn tags_of(node: &XmlNode) -> (Option<String>, Vec<String>) {
+    let mut name = None;
+    let mut tags = Vec::new();
+    for item in node
+        .find(&["tagSet"])
+        .into_iter()
+        .flat_map(|s| s.children("item"))
+    {
+        let (key, value) = (item.text_of("key"), item.text_of("value"));
+        if key == "Name" {
+            name = Some(value.to_string());
+        } else if !key.starts_with("aws:") {
+            tags.push(tag(key, value));
+        }
+    }
+    (name, tags)
+}
+
+// 解析 DescribeInstances 的响应，返回实例和下一页的 nextToken
+pub fn parse_instances(xml: &str) -> Result<(Vec<Target>, Option<String>)> {
+    let root = parse_xml(xml)?;
+    let mut targets = Vec::new();
+    let reservations = root.find(&["reservationSet"]).into_iter();
+    for reservation in reservations.flat_map(|r| r.children("item")) {
+        let instances = reservation.find(&["instancesSet"]).into_iter();
+        for instance in instances.flat_map(|i| i.children("item")) {
+            let (name, tags) = tags_of(instance);
+            targets.push(Target {
+                account: String::new(),
+                kind: TargetKind::Instance,
+                id: instance.text_of("instanceId").to_string(),
+                name: name.unwrap_or_default(),
+                host: pick_host([
+                    instance.text_of("ipAddress"),
+                    instance.text_of("privateIpAddress"),
+                ])
+                .unwrap_or_default(),
+                tags,
+            });
+        }
+    }
+    let next = Some(root.text_of("nextToken").to_string()).filter(|t| !t.is_empty());
+    Ok((targets, next))
+}
+
+// 解析 DescribeLoadBalancers 的响应，返回负载均衡和下一页的 NextMarker
+pub fn parse_load_balancers(xml: &str) -> Result<(Vec<Target>, Option<String>)> {
+    let root = parse_xml(xml)?;
+    let Some(result) = root.child("DescribeLoadBalancersResult") else {
+        return Ok((Vec::new(), None));
+    };
+    let targets = result
+        .find(&["LoadBalancers"])
+        .into_iter()
+        .flat_map(|l| l.children("member"))
+        .map(|lb| Target {
+            account: String::new(),
+            kind: TargetKind::LoadBalancer,
+            id: lb.text_of("LoadBalancerArn").to_string(),
+            name: lb.text_of("LoadBalancerName").to_string(),
+            host: lb.text_of("DNSName").to_string(),
+            // 负载均衡的标签需要另外调用 DescribeTags，这里不取
+            tags: vec![format!("elb:{}", lb.text_of("Type"))],
+        })
+        .collect();
+    let next = Some(result.text_of("NextMarker").to_string()).filter(|t| !t.is_empty());
+    Ok((targets, next))
+}
+
+// 列出运行中的实例和所有负载均衡，自动翻页
+pub async fn discover(client: &reqwest::Client, account: &CloudAccount) -> Result<Vec<Target>> {
+    let mut targets = Vec::new();
+    let mut token = None;
+    loop {
+        let mut params = vec![
+            ("Action", "DescribeInstances".to_string()),
+            ("Version", EC2_VERSION.to_string()),
+            ("Filter.1.Name", "instance-state-name".to_string()),
+            ("Filter.1.Value.1", "running".to_string()),
+            ("MaxResults", "1000".to_string()),
+        ];
+        params.extend(token.take().map(|t| ("NextToken", t)));
+        let (page, next) = parse_instances(&query(client, account, "ec2", &params).await?)?;
+        targets.extend(page);
+        match next {
+            Some(next) => token = Some(next),
+            None => break,
+        }
+    }
+
+    let mut marker = None;
+    loop {
+        let mut params = vec![
+            ("Action", "DescribeLoadBalancers".to_string()),
+            ("Version", ELB_VERSION.to_string()),
+        ];
+        params.extend(marker.take().map(|m| ("Marker", m)));
+        let xml = query(client, account, "elasticloadbalancing", &params).await?;
+        let (page, next) = parse_load_balancers(&xml)?;
+        targets.extend(page);
+        match next {
+            Some(next) => marker = Some(next),
+            None => break,
+        }
+    }
+    Ok(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    // AWS文档中的签名示例
+    #[test]
+    fn signs_documented_example() {
+        let account = CloudAccount {
+            region: "us-east-1".to_string(),
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_key: "wJalrXUtnFEMI/K7MDENG+bPxRfiCYEXAMPLEKEY".to_string(),
+            ..Default::default()
+        };
+        let request = SigningRequest {
+            method: "GET",
+            host: "iam.amazonaws.com",
+            path: "/",
+            query: "Action=ListUsers&Version=2010-05-08",
+            headers: &[(
+                "content-type",
+                "application/x-www-form-urlencoded; charset=utf-8",
+            )],
+            payload: b"",
+        };
+        let at = Utc.with_ymd_and_hms(2015, 8, 30, 12, 36, 0).unwrap();
+        assert_eq!(
+            authorization(&account, "iam", at, &request),
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20150830/us-east-1/iam/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-date, \
+             Signature=5d672d79c15b13162d9279b0855cfba6789a8edb4c82c400e06b5924a6f2b5d7"
+        );
+    }
+
+    #[test]
+    fn parses_instances_with_tags_and_paging() {
+        let xml = r#"<?xml version="1.0" encoding="UTF-8"?>
+<DescribeInstancesResponse xmlns="http://ec2.amazonaws.com/doc/2016-11-15/">
+  <reservationSet>
+    <item>
+      <instancesSet>
+        <item>
+          <instanceId>i-0abc</instanceId>
+          <privateIpAddress>10.0.0.5</privateIpAddress>
+          <ipAddress>54.1.2.3</ipAddress>
+          <networkInterfaceSet><item><privateIpAddress>10.0.0.99</privateIpAddress></item></networkInterfaceSet>
+          <tagSet>
+            <item><key>Name</key><value>web-1</value></item>
+            <item><key>env</key><value>prod</value></item>
+            <item><key>aws:autoscaling:groupName</key><value>asg</value></item>
+          </tagSet>
+        </item>
+        <item>
+          <instanceId>i-0def</instanceId>
+          <privateIpAddress>10.0.0.6</privateIpAddress>
+        </item>
+      </instancesSet>
+    </item>
+  </reservationSet>
+  <nextToken>page-2</nextToken>
+</DescribeInstancesResponse>"#;
+        let (targets, next) = parse_instances(xml).unwrap();
+        assert_eq!(next.as_deref(), Some("page-2"));
+        assert_eq!(targets.len(), 2);
+        assert_eq!(targets[0].name, "web-1");
+        assert_eq!(targets[0].host, "54.1.2.3");
+        assert_eq!(targets[0].tags, vec!["env:prod".to_string()]);
+        // 没有公网地址时用内网地址
+        assert_eq!(targets[1].host, "10.0.0.6");
+    }
+
+    #[test]
+    fn parses_load_balancers() {
+        let xml = r#"<DescribeLoadBalancersResponse xmlns="http://elasticloadbalancing.amazonaws.com/doc/2015-12-01/">
+  <DescribeLoadBalancersResult>
+    <LoadBalancers>
+      <member>
+        <LoadBalancerArn>arn:aws:elasticloadbalancing:us-east-1:123:loadbalancer/app/my-lb/50dc</LoadBalancerArn>
+        <LoadBalancerName>my-lb</LoadBalancerName>
+        <DNSName>my-lb-123.us-east-1.elb.amazonaws.com</DNSName>
+        <Type>application</Type>
+      </member>
+    </LoadBalancers>
+  </DescribeLoadBalancersResult>
+</DescribeLoadBalancersResponse>"#;
+        let (targets, next) = parse_load_balancers(xml).unwrap();
+        assert_eq!(next, None);
+        // 格式错误的响应返回错误
+        assert!(parse_xml("<a><b></a>").is_err());
+        assert!(parse_xml("</a>").is_err());
+        assert_eq!(targets[0].kind, TargetKind::LoadBalancer);
+        assert_eq!(targets[0].host, "my-lb-123.us-east-1.elb.amazonaws.com");
+        assert_eq!(
+            endpoint("ec2", "cn-north-1"),
+            "ec2.cn-north-1.amazonaws.com.cn"
+        );
+    }
+}
@@ -0,0 +1,314 @@
+pub mod aliyun;
+pub mod aws;
+pub mod tencent;
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use ring::{digest, hmac};
+use serde::{Deserialize, Serialize};
+
+use crate::address;
+use crate::i18n::tr;
+use crate::{secret, Server};
+
+// 云厂商资源发现：用只读凭据定期列出账号中运行中的实例和负载均衡，
+// 作为建议的监控目标，由用户选择后添加；厂商的标签带到服务器的标签上
+
+pub const MIN_DISCOVERY_MINS: u64 = 5;
+pub const MAX_DISCOVERY_MINS: u64 = 24 * 60;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum CloudProvider {
+    #[default]
+    Aws,
+    Aliyun,
+    Tencent,
+}
+
+impl CloudProvider {
+    pub const ALL: [CloudProvider; 3] = [
+        CloudProvider::Aws,
+        CloudProvider::Aliyun,
+        CloudProvider::Tencent,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            CloudProvider::Aws => "AWS",
+            CloudProvider::Aliyun => tr!("阿里云", "Alibaba Cloud"),
+            CloudProvider::Tencent => tr!("腾讯云", "Tencent Cloud"),
+        }
+    }
+
+    // 区域的填写示例
+    pub fn region_hint(&self) -> &'static str {
+        match self {
+            CloudProvider::Aws => "us-east-1",
+            CloudProvider::Aliyun => "cn-hangzhou",
+            CloudProvider::Tencent => "ap-guangzhou",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CloudAccount {
+    pub enabled: bool,
+    // 显示用的名称，同时作为添加的服务器的分组
+    pub name: String,
+    pub provider: CloudProvider,
+    pub region: String,
+    pub access_key_id: String,
+    pub secret_key: String,
+}
+
+impl CloudAccount {
+    pub fn ready(&self) -> bool {
+        self.enabled
+            && !self.region.trim().is_empty()
+            && !self.access_key_id.trim().is_empty()
+            && !self.secret_key.trim().is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DiscoverySettings {
+    pub accounts: Vec<CloudAccount>,
+    pub interval_mins: u64,
+    // 添加的服务器的检查地址按这里的协议和端口生成
+    pub https: bool,
+    pub port: u16,
+    // 用户选择忽略的资源ID，不再建议
+    pub ignored: Vec<String>,
+}
+
+impl Default for DiscoverySettings {
+    fn default() -> Self {
+        Self {
+            accounts: Vec::new(),
+            interval_mins: 60,
+            https: false,
+            port: 80,
+            ignored: Vec::new(),
+        }
+    }
+}
+
+impl DiscoverySettings {
+    pub fn active(&self) -> bool {
+        self.accounts.iter().any(CloudAccount::ready)
+    }
+
+    pub fn interval(&self) -> Duration {
+        let mins = self
+            .interval_mins
+            .clamp(MIN_DISCOVERY_MINS, MAX_DISCOVERY_MINS);
+        Duration::from_secs(mins * 60)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TargetKind {
+    Instance,
+    LoadBalancer,
+}
+
+impl TargetKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TargetKind::Instance => tr!("实例", "Instance"),
+            TargetKind::LoadBalancer => tr!("负载均衡", "Load balancer"),
+        }
+    }
+}
+
+// 发现的一个资源
+#[derive(Debug, Clone, PartialEq)]
+pub struct Target {
+    // 所属账号的名称
+    pub account: String,
+    pub kind: TargetKind,
+    pub id: String,
+    pub name: String,
+    pub host: String,
+    pub tags: Vec<String>,
+}
+
+impl Target {
+    // 已经有服务器使用这个地址
+    pub fn is_monitored(&self, servers: &[Server]) -> bool {
+        servers.iter().any(|s| s.ip == self.host)
+    }
+
+    pub fn to_server(&self, settings: &DiscoverySettings) -> Server {
+        let scheme = if settings.https { "https" } else { "http" };
+        let name = if self.name.is_empty() {
+            &self.id
+        } else {
+            &self.name
+        };
+        let mut server = Server::new(name.clone(), self.host.clone(), settings.port);
+        server.url = address::build_url(scheme, &self.host, settings.port);
+        server.group = self.account.clone();
+        server.tags = self.tags.clone();
+        server
+    }
+}
+
+// 厂商标签转成服务器标签；标签中不能有空格和逗号
+fn tag(key: &str, value: &str) -> String {
+    let text = if value.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}:{}", key, value)
+    };
+    text.replace([' ', ',', '，'], "_")
+}
+
+// 公网地址优先，没有时用内网地址
+fn pick_host<'a>(candidates: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    candidates
+        .into_iter()
+        .find(|h| !h.is_empty())
+        .map(str::to_string)
+}
+
+// 签名用到的URI编码：只保留 A-Z a-z 0-9 - _ . ~
+pub fn uri_encode(text: &str) -> String {
+    let mut out = String::new();
+    for b in text.bytes() {
+        if b.is_ascii_alphanumeric() || b"-_.~".contains(&b) {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+// 按名称排序后拼接查询参数
+fn canonical_query(params: &[(&str, String)]) -> String {
+    let mut pairs: Vec<(String, String)> = params
+        .iter()
+        .map(|(k, v)| (uri_encode(k), uri_encode(v)))
+        .collect();
+    pairs.sort();
+    pairs
+        .iter()
+        .map(|(k, v)| format!("{}={}", k, v))
+        .collect::<Vec<_>>()
+        .join("&")
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let key = hmac::Key::new(hmac::HMAC_SHA256, key);
+    hmac::sign(&key, data.as_bytes()).as_ref().to_vec()
+}
+
+pub fn sha256_hex(data: &[u8]) -> String {
+    hex::encode(digest::digest(&digest::SHA256, data))
+}
+
+// 列出一个账号中的资源
+pub async fn discover(client: &reqwest::Client, account: &CloudAccount) -> Result<Vec<Target>> {
+    // 密钥可以是钥匙串或环境变量的引用，签名前取出实际的值
+    let mut account = account.clone();
+    account.secret_key = secret::resolve_async(&account.secret_key).await?;
+    let account = &account;
+    let mut targets = match account.provider {
+        CloudProvider::Aws => aws::discover(client, account).await?,
+        CloudProvider::Aliyun => aliyun::discover(client, account).await?,
+        CloudProvider::Tencent => tencent::discover(client, account).await?,
+    };
+    targets.retain(|t| !t.host.is_empty());
+    for target in &mut targets {
+        target.account = account.name.clone();
+    }
+    Ok(targets)
+}
+
+// 一次刷新的结果
+#[derive(Debug, Clone)]
+pub struct DiscoveryRun {
+    pub at: DateTime<Local>,
+    pub targets: Vec<Target>,
+    // 失败的账号及原因
+    pub errors: Vec<String>,
+}
+
+pub type DiscoveryResults = Arc<Mutex<Option<DiscoveryRun>>>;
+
+// 在后台刷新所有启用的账号，完成后写入 results
+pub fn spawn_refresh(
+    client: &reqwest::Client,
+    accounts: &[CloudAccount],
+    results: &DiscoveryResults,
+) {
+    let client = client.clone();
+    let accounts: Vec<CloudAccount> = accounts.iter().filter(|a| a.ready()).cloned().collect();
+    let results = Arc::clone(results);
+    tokio::spawn(async move {
+        let runs = futures::future::join_all(accounts.iter().map(|a| discover(&client, a))).await;
+        let mut run = DiscoveryRun {
+            at: Local::now(),
+            targets: Vec::new(),
+            errors: Vec::new(),
+        };
+        for (account, result) in accounts.iter().zip(runs) {
+            match result {
+                Ok(targets) => run.targets.extend(targets),
+                Err(e) => run.errors.push(format!("{}: {}", account.name, e)),
+            }
+        }
+        *results.lock().unwrap() = Some(run);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn encodes_and_sorts_query() {
+        assert_eq!(uri_encode("a b*~/"), "a%20b%2A~%2F");
+        let query = canonical_query(&[
+            ("Version", "2016-11-15".to_string()),
+            ("Action", "DescribeInstances".to_string()),
+        ]);
+        assert_eq!(query, "Action=DescribeInstances&Version=2016-11-15");
+        assert_eq!(tag("team", "ops, infra"), "team:ops__infra");
+        assert_eq!(tag("prod", ""), "prod");
+    }
+
+    #[test]
+    fn target_becomes_server_once() {
+        let target = Target {
+            account: "aws-prod".to_string(),
+            kind: TargetKind::Instance,
+            id: "i-0abc".to_string(),
+            name: String::new(),
+            host: "54.1.2.3".to_string(),
+            tags: vec!["env:prod".to_string()],
+        };
+        let settings = DiscoverySettings {
+            https: true,
+            port: 443,
+            ..Default::default()
+        };
+        let server = target.to_server(&settings);
+        assert_eq!(server.name, "i-0abc");
+        assert_eq!(server.url, "https://54.1.2.3:443");
+        assert_eq!(server.group, "aws-prod");
+        assert_eq!(server.tags, target.tags);
+
+        let mut existing = testing::http_server("http://10.0.0.1");
+        assert!(!target.is_monitored(std::slice::from_ref(&existing)));
+        existing.ip = "54.1.2.3".to_string();
+        assert!(target.is_monitored(&[existing]));
+    }
+}
@@ -0,0 +1,261 @@
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use serde_json::{json, Value};
+
+use super::{hmac_sha256, pick_host, sha256_hex, tag, CloudAccount, Target, TargetKind};
+
+// 腾讯云：CVM DescribeInstances 和 CLB DescribeLoadBalancers，
+// POST JSON，请求使用 TC3-HMAC-SHA256 签名
+
+const CVM_VERSION: &str = "2017-03-12";
+const CLB_VERSION: &str = "2018-03-17";
+const CONTENT_TYPE: &str = "application/json; charset=utf-8";
+const PAGE_SIZE: usize = 100;
+
+// 生成 Authorization 请求头
+pub fn authorization(
+    account: &CloudAccount,
+    service: &str,
+    at: DateTime<Utc>,
+    payload: &str,
+) -> String {
+    let host = format!("{}.tencentcloudapi.com", service);
+    let canonical_request = format!(
+        "POST\n/\n\ncontent-type:{}\nhost:{}\n\ncontent-type;host\n{}",
+        CONTENT_TYPE,
+        host,
+        sha256_hex(payload.as_bytes())
+    );
+    let date = at.format("%Y-%m-%d").to_string();
+    let scope = format!("{}/{}/tc3_request", date, service);
+    let string_to_sign = format!(
+        "TC3-HMAC-SHA256\n{}\n{}\n{}",
+        at.timestamp(),
+        scope,
+        sha256_hex(canonical_request.as_bytes())
+    );
+    let secret = format!("TC3{}", account.secret_key.trim());
+    let key = hmac_sha256(secret.as_bytes(), &date);
+    let key = hmac_sha256(&key, service);
+    let key = hmac_sha256(&key, "tc3_request");
+    let signature = hex::encode(hmac_sha256(&key, &string_to_sign));
+    format!(
+        "TC3-HMAC-SHA256 Credential={}/{}, SignedHeaders=content-type;host, Signature={}",
+        account.access_key_id.trim(),
+        scope,
+        signature
+    )
+}
+
+async fn call(
+    client: &reqwest::Client,
+    account: &CloudAccount,
+    service: &str,
+    version: &str,
+    action: &str,
+    body: &Value,
+) -> Result<Value> {
+    let payload = body.to_string();
+    let now = Utc::now();
+    let resp = client
+        .post(format!("https://{}.tencentcloudapi.com/", service))
+        .header("content-type", CONTENT_TYPE)
+        .header(
+            "authorization",
+            authorization(account, service, now, &payload),
+        )
+        .header("x-tc-action", action)
+        .header("x-tc-version", version)
+        .header("x-tc-region", account.region.trim())
+        .header("x-tc-timestamp", now.timestamp().to_string())
+        .body(payload)
+        .send()
+        .await?;
+    let json: Value = resp.json().await?;
+    // 出错时HTTP状态码仍是200，错误在 Response.Error 中
+    if let Some(message) = json["Response"]["Error"]["Message"].as_str() {
+        bail!("{}", message);
+    }
+    Ok(json)
+}
+
+fn strings(value: &Value) -> impl Iterator<Item = &str> {
+    value
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(Value::as_str)
+}
+
+// CVM的标签是 Key/Value，CLB的是 TagKey/TagValue
+fn tags_of(value: &Value) -> Vec<String> {
+    value["Tags"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|t| {
+            let key = t["Key"].as_str().or(t["TagKey"].as_str());
+            let value = t["Value"].as_str().or(t["TagValue"].as_str());
+            tag(key.unwrap_or_default(), value.unwrap_or_default())
+        })
+        .collect()
+}
+
+fn text(value: &Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+// 解析 DescribeInstances 的一页，返回实例和总数
+pub fn parse_instances(json: &Value) -> (Vec<Target>, usize) {
+    let response = &json["Response"];
+    let targets = response["InstanceSet"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|instance| Target {
+            account: String::new(),
+            kind: TargetKind::Instance,
+            id: text(&instance["InstanceId"]),
+            name: text(&instance["InstanceName"]),
+            host: pick_host(
+                strings(&instance["PublicIpAddresses"])
+                    .chain(strings(&instance["PrivateIpAddresses"])),
+            )
+            .unwrap_or_default(),
+            tags: tags_of(instance),
+        })
+        .collect();
+    (
+        targets,
+        response["TotalCount"].as_u64().unwrap_or(0) as usize,
+    )
+}
+
+// 解析 DescribeLoadBalancers 的一页，域名型负载均衡没有VIP时用域名
+pub fn parse_load_balancers(json: &Value) -> (Vec<Target>, usize) {
+    let response = &json["Response"];
+    let targets = response["LoadBalancerSet"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .map(|lb| Target {
+            account: String::new(),
+            kind: TargetKind::LoadBalancer,
+            id: text(&lb["LoadBalancerId"]),
+            name: text(&lb["LoadBalancerName"]),
+            host: pick_host(strings(&lb["LoadBalancerVips"]).chain(lb["Domain"].as_str()))
+                .unwrap_or_default(),
+            tags: tags_of(lb),
+        })
+        .collect();
+    (
+        targets,
+        response["TotalCount"].as_u64().unwrap_or(0) as usize,
+    )
+}
+
+// 按页取完一种资源，filters 为额外的请求参数
+async fn list_all(
+    client: &reqwest::Client,
+    account: &CloudAccount,
+    service: &str,
+    version: &str,
+    action: &str,
+    filters: Value,
+    parse: fn(&Value) -> (Vec<Target>, usize),
+) -> Result<Vec<Target>> {
+    let mut targets = Vec::new();
+    loop {
+        let mut body = json!({"Limit": PAGE_SIZE, "Offset": targets.len()});
+        if let (Some(body), Some(filters)) = (body.as_object_mut(), filters.as_object()) {
+            body.extend(filters.clone());
+        }
+        let json = call(client, account, service, version, action, &body).await?;
+        let (items, total) = parse(&json);
+        let done = items.len() < PAGE_SIZE || targets.len() + items.len() >= total;
+        targets.extend(items);
+        if done {
+            break;
+        }
+    }
+    Ok(targets)
+}
+
+// 列出运行中的CVM实例和CLB负载均衡
+pub async fn discover(client: &reqwest::Client, account: &CloudAccount) -> Result<Vec<Target>> {
+    let running = json!({"Filters": [{"Name": "instance-state", "Values": ["RUNNING"]}]});
+    let mut targets = list_all(
+        client,
+        account,
+        "cvm",
+        CVM_VERSION,
+        "DescribeInstances",
+        running,
+        parse_instances,
+    )
+    .await?;
+    targets.extend(
+        list_all(
+            client,
+            account,
+            "clb",
+            CLB_VERSION,
+            "DescribeLoadBalancers",
+            json!({}),
+            parse_load_balancers,
+        )
+        .await?,
+    );
+    Ok(targets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_instances_and_load_balancers() {
+        let json: Value = serde_json::from_str(
+            r#"{"Response": {"TotalCount": 1, "RequestId": "r", "InstanceSet": [
+                {"InstanceId": "ins-1", "InstanceName": "web-1",
+                 "PublicIpAddresses": null, "PrivateIpAddresses": ["10.0.0.5"],
+                 "Tags": [{"Key": "env", "Value": "prod"}]}
+            ]}}"#,
+        )
+        .unwrap();
+        let (targets, total) = parse_instances(&json);
+        assert_eq!(total, 1);
+        assert_eq!(targets[0].host, "10.0.0.5");
+        assert_eq!(targets[0].tags, vec!["env:prod".to_string()]);
+
+        let json: Value = serde_json::from_str(
+            r#"{"Response": {"TotalCount": 2, "LoadBalancerSet": [
+                {"LoadBalancerId": "lb-1", "LoadBalancerName": "api",
+                 "LoadBalancerVips": ["129.1.2.3"], "Tags": [{"TagKey": "team", "TagValue": "ops"}]},
+                {"LoadBalancerId": "lb-2", "LoadBalancerName": "cdn",
+                 "LoadBalancerVips": [], "Domain": "lb-2.clb.tencentclb.com"}
+            ]}}"#,
+        )
+        .unwrap();
+        let (targets, _) = parse_load_balancers(&json);
+        assert_eq!(targets[0].host, "129.1.2.3");
+        assert_eq!(targets[0].tags, vec!["team:ops".to_string()]);
+        assert_eq!(targets[1].host, "lb-2.clb.tencentclb.com");
+    }
+
+    #[test]
+    fn signature_scope_uses_utc_date() {
+        let account = CloudAccount {
+            access_key_id: "AKIDEXAMPLE".to_string(),
+            secret_key: "secret".to_string(),
+            ..Default::default()
+        };
+        let at = DateTime::from_timestamp(1551113065, 0).unwrap();
+        let auth = authorization(&account, "cvm", at, "{}");
+        assert!(auth.starts_with(
+            "TC3-HMAC-SHA256 Credential=AKIDEXAMPLE/2019-02-25/cvm/tc3_request, SignedHeaders=content-type;host, Signature="
+        ));
+        // 签名随请求内容变化
+        assert_ne!(auth, authorization(&account, "cvm", at, r#"{"Limit":1}"#));
+    }
+}
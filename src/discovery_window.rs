@@ -0,0 +1,241 @@
+use std::collections::BTreeSet;
+
+use eframe::egui;
+
+use crate::check_editor::secret_input;
+use crate::discovery::{
+    CloudAccount, CloudProvider, DiscoveryRun, DiscoverySettings, Target, MAX_DISCOVERY_MINS,
+    MIN_DISCOVERY_MINS,
+};
+use crate::i18n::tr;
+use crate::{Server, ServerStatus};
+
+// 云资源发现窗口：编辑云账号，列出发现的、还没有监控的资源，勾选后添加
+#[derive(Default)]
+pub struct DiscoveryWindow {
+    pub open: bool,
+    // 勾选的资源ID
+    selected: BTreeSet<String>,
+}
+
+// 窗口中的操作，由调用方执行
+#[derive(Default)]
+pub struct DiscoveryAction {
+    pub settings_changed: bool,
+    pub refresh: bool,
+    pub add: Vec<Server>,
+}
+
+// 还没有监控、也没有被忽略的资源
+pub fn proposals<'a>(
+    run: Option<&'a DiscoveryRun>,
+    settings: &'a DiscoverySettings,
+    servers: &'a [Server],
+) -> impl Iterator<Item = &'a Target> {
+    run.into_iter()
+        .flat_map(|r| r.targets.iter())
+        .filter(|t| !settings.ignored.contains(&t.id) && !t.is_monitored(servers))
+}
+
+fn show_account(ui: &mut egui::Ui, account: &mut CloudAccount) -> bool {
+    let mut changed = ui.checkbox(&mut account.enabled, "").changed();
+    changed |= ui
+        .add(
+            egui::TextEdit::singleline(&mut account.name)
+                .hint_text(tr!("名称", "Name"))
+                .desired_width(90.0),
+        )
+        .changed();
+    egui::ComboBox::from_id_source("cloud_provider")
+        .selected_text(account.provider.label())
+        .show_ui(ui, |ui| {
+            for provider in CloudProvider::ALL {
+                changed |= ui
+                    .selectable_value(&mut account.provider, provider, provider.label())
+                    .changed();
+            }
+        });
+    changed |= ui
+        .add(
+            egui::TextEdit::singleline(&mut account.region)
+                .hint_text(account.provider.region_hint())
+                .desired_width(90.0),
+        )
+        .changed();
+    changed |= ui
+        .add(
+            egui::TextEdit::singleline(&mut account.access_key_id)
+                .hint_text("AccessKey ID")
+                .desired_width(140.0),
+        )
+        .changed();
+    let key = format!("cloud-{}", account.access_key_id.trim());
+    changed |= secret_input(ui, &mut account.secret_key, &key);
+    changed
+}
+
+impl DiscoveryWindow {
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        settings: &mut DiscoverySettings,
+        run: Option<&DiscoveryRun>,
+        refreshing: bool,
+        servers: &[Server],
+    ) -> DiscoveryAction {
+        let mut open = self.open;
+        let mut action = DiscoveryAction::default();
+
+        egui::Window::new(tr!("☁ 云资源发现", "☁ Cloud discovery"))
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.small(tr!(
+                    "使用只读凭据定期列出运行中的实例和负载均衡，勾选后添加为监控目标",
+                    "Lists running instances and load balancers with read-only credentials; tick them to start monitoring"
+                ));
+                let mut remove = None;
+                for (i, account) in settings.accounts.iter_mut().enumerate() {
+                    ui.push_id(i, |ui| {
+                        ui.horizontal(|ui| {
+                            action.settings_changed |= show_account(ui, account);
+                            if ui.small_button("🗑").clicked() {
+                                remove = Some(i);
+                            }
+                        });
+                    });
+                }
+                if let Some(i) = remove {
+                    settings.accounts.remove(i);
+                    action.settings_changed = true;
+                }
+                if ui.button(tr!("➕ 添加账号", "➕ Add account")).clicked() {
+                    settings.accounts.push(CloudAccount {
+                        enabled: true,
+                        ..Default::default()
+                    });
+                    action.settings_changed = true;
+                }
+
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label(tr!("刷新间隔:", "Refresh every:"));
+                    let interval = ui.add(
+                        egui::DragValue::new(&mut settings.interval_mins)
+                            .range(MIN_DISCOVERY_MINS..=MAX_DISCOVERY_MINS)
+                            .suffix(tr!(" 分钟", " min")),
+                    );
+                    action.settings_changed |=
+                        interval.drag_stopped() || (interval.changed() && !interval.dragged());
+                    ui.label(tr!("添加为:", "Add as:"));
+                    action.settings_changed |=
+                        ui.checkbox(&mut settings.https, "HTTPS").changed();
+                    ui.label(tr!("端口:", "Port:"));
+                    let port = ui.add(egui::DragValue::new(&mut settings.port).range(1..=65535));
+                    action.settings_changed |=
+                        port.drag_stopped() || (port.changed() && !port.dragged());
+                });
+                ui.horizontal(|ui| {
+                    let refresh = egui::Button::new(tr!("🔄 立即刷新", "🔄 Refresh now"));
+                    if ui
+                        .add_enabled(settings.active() && !refreshing, refresh)
+                        .clicked()
+                    {
+                        action.refresh = true;
+                    }
+                    if refreshing {
+                        ui.spinner();
+                    } else if let Some(run) = run {
+                        ui.small(tr!(
+                            "上次刷新: {}，共 {} 个资源",
+                            "Last refresh: {}, {} resources",
+                            run.at.format("%H:%M"),
+                            run.targets.len()
+                        ));
+                    }
+                });
+                for error in run.iter().flat_map(|r| r.errors.iter()) {
+                    ui.colored_label(ServerStatus::Offline.color(), format!("❌ {}", error));
+                }
+
+                ui.separator();
+                let found: Vec<Target> = proposals(run, settings, servers).cloned().collect();
+                if found.is_empty() {
+                    ui.weak(tr!(
+                        "没有未监控的资源",
+                        "No unmonitored resources"
+                    ));
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("discovered")
+                            .num_columns(5)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for target in &found {
+                                    let mut checked = self.selected.contains(&target.id);
+                                    if ui.checkbox(&mut checked, "").changed() {
+                                        if checked {
+                                            self.selected.insert(target.id.clone());
+                                        } else {
+                                            self.selected.remove(&target.id);
+                                        }
+                                    }
+                                    let name = if target.name.is_empty() {
+                                        &target.id
+                                    } else {
+                                        &target.name
+                                    };
+                                    ui.label(name).on_hover_text(&target.id);
+                                    ui.small(format!("{} · {}", target.account, target.kind.label()));
+                                    ui.small(&target.host);
+                                    ui.small(target.tags.join(", "));
+                                    ui.end_row();
+                                }
+                            });
+                    });
+
+                self.selected.retain(|id| found.iter().any(|t| t.id == *id));
+                ui.horizontal(|ui| {
+                    let count = self.selected.len();
+                    let any = count > 0;
+                    let add = egui::Button::new(tr!("添加选中 ({})", "Add selected ({})", count));
+                    if ui.add_enabled(any, add).clicked() {
+                        action.add = found
+                            .iter()
+                            .filter(|t| self.selected.contains(&t.id))
+                            .map(|t| t.to_server(settings))
+                            .collect();
+                        self.selected.clear();
+                    }
+                    if ui
+                        .add_enabled(any, egui::Button::new(tr!("忽略选中", "Ignore selected")))
+                        .on_hover_text(tr!(
+                            "不再建议这些资源",
+                            "Stop suggesting these resources"
+                        ))
+                        .clicked()
+                    {
+                        settings.ignored.extend(std::mem::take(&mut self.selected));
+                        action.settings_changed = true;
+                    }
+                    if !settings.ignored.is_empty()
+                        && ui
+                            .small_button(tr!(
+                                "恢复 {} 个已忽略",
+                                "Restore {} ignored",
+                                settings.ignored.len()
+                            ))
+                            .clicked()
+                    {
+                        settings.ignored.clear();
+                        action.settings_changed = true;
+                    }
+                });
+            });
+
+        self.open = open;
+        action
+    }
+}
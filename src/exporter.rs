@@ -0,0 +1,363 @@
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+use crate::checks::http;
+use crate::i18n::tr;
+use crate::schedule::ActionLog;
+use crate::{Server, ServerStatus};
+
+// 每轮检查结束后把每台服务器的在线状态和延迟推送到 InfluxDB 或 Prometheus remote-write，
+// 接入已有的 Grafana 看板。指标名为 server_check_up（0/1）和 server_check_latency_ms，
+// 标签为 server 和 group
+
+const MEASUREMENT: &str = "server_check";
+// 推送失败时最多读取这么多错误说明
+const ERROR_BODY_LIMIT: usize = 1024;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ExportTarget {
+    // 行协议，InfluxDB 1.x 和 2.x 都支持
+    #[default]
+    Influx,
+    RemoteWrite,
+}
+
+impl ExportTarget {
+    pub const ALL: [ExportTarget; 2] = [ExportTarget::Influx, ExportTarget::RemoteWrite];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportTarget::Influx => "InfluxDB",
+            ExportTarget::RemoteWrite => "Prometheus remote-write",
+        }
+    }
+
+    pub fn url_hint(&self) -> &'static str {
+        match self {
+            ExportTarget::Influx => "http://influx:8086/api/v2/write?org=ops&bucket=servercheck",
+            ExportTarget::RemoteWrite => "http://prometheus:9090/api/v1/write",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportSettings {
+    pub enabled: bool,
+    pub target: ExportTarget,
+    // InfluxDB 1.x 为 .../write?db=servercheck，2.x 为 .../api/v2/write?org=..&bucket=..
+    pub url: String,
+    // InfluxDB 2.x 的 API Token，remote-write 中作为 Bearer Token；为空时不发送
+    pub token: String,
+    // 基本认证，用于 InfluxDB 1.x 和需要认证的 remote-write 网关
+    pub username: String,
+    pub password: String,
+}
+
+impl ExportSettings {
+    pub fn active(&self) -> bool {
+        self.enabled && !self.url.trim().is_empty()
+    }
+
+    // Token 和基本认证都写在 Authorization 头中，只能用一种
+    pub fn validate(&self) -> Result<()> {
+        if !self.token.trim().is_empty() && !self.username.is_empty() {
+            return Err(anyhow!(tr!(
+                "Token 和用户名只能填写一个",
+                "Set either a token or a user name, not both"
+            )));
+        }
+        Ok(())
+    }
+}
+
+// 最近一次推送的时间和推送的服务器数，失败时带上原因
+#[derive(Debug, Clone)]
+pub struct ExportResult {
+    pub at: DateTime<Local>,
+    pub count: usize,
+    pub error: Option<String>,
+}
+
+pub type LastExport = Arc<Mutex<Option<ExportResult>>>;
+
+// 一台服务器这一轮的结果；未检查和已暂停的服务器不推送。
+// 离线和出错时的延迟是等到失败的时间，不作为延迟推送
+#[derive(Debug, Clone, PartialEq)]
+struct Sample {
+    server: String,
+    group: String,
+    status: &'static str,
+    up: bool,
+    latency_ms: Option<u64>,
+}
+
+fn samples(servers: &[Server]) -> Vec<Sample> {
+    servers
+        .iter()
+        .filter(|s| !s.paused && s.status != ServerStatus::Unchecked)
+        .map(|s| {
+            let up = matches!(s.status, ServerStatus::Online | ServerStatus::Degraded);
+            Sample {
+                server: s.name.clone(),
+                group: s.group.clone(),
+                status: s.status.id(),
+                up,
+                latency_ms: s.state.latency_ms.filter(|_| up),
+            }
+        })
+        .collect()
+}
+
+// 标签值中的逗号、等号和空格需要转义
+fn escape_tag(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace(',', "\\,")
+        .replace('=', "\\=")
+        .replace(' ', "\\ ")
+}
+
+fn line_protocol(samples: &[Sample], at: DateTime<Local>) -> String {
+    let nanos = at.timestamp_nanos_opt().unwrap_or_default();
+    samples
+        .iter()
+        .map(|s| {
+            let mut line = format!("{},server={}", MEASUREMENT, escape_tag(&s.server));
+            if !s.group.is_empty() {
+                line += &format!(",group={}", escape_tag(&s.group));
+            }
+            line += &format!(" up={}i,status=\"{}\"", s.up as u8, s.status);
+            if let Some(ms) = s.latency_ms {
+                line += &format!(",latency_ms={}i", ms);
+            }
+            format!("{} {}\n", line, nanos)
+        })
+        .collect()
+}
+
+// remote-write 使用 protobuf，这里只需要写入 varint、double 和字符串几种字段
+fn put_varint(buf: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buf.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    buf.push(value as u8);
+}
+
+fn put_bytes(buf: &mut Vec<u8>, field: u64, bytes: &[u8]) {
+    put_varint(buf, field << 3 | 2);
+    put_varint(buf, bytes.len() as u64);
+    buf.extend_from_slice(bytes);
+}
+
+// 一条时间序列；标签按名称排序，__name__ 排在最前
+fn time_series(name: &str, sample: &Sample, value: f64, millis: i64) -> Vec<u8> {
+    let mut labels = vec![("__name__", name), ("server", sample.server.as_str())];
+    if !sample.group.is_empty() {
+        labels.insert(1, ("group", sample.group.as_str()));
+    }
+    let mut series = Vec::new();
+    for (name, value) in labels {
+        let mut label = Vec::new();
+        put_bytes(&mut label, 1, name.as_bytes());
+        put_bytes(&mut label, 2, value.as_bytes());
+        put_bytes(&mut series, 1, &label);
+    }
+    let mut point = Vec::new();
+    put_varint(&mut point, 1 << 3 | 1);
+    point.extend_from_slice(&value.to_le_bytes());
+    put_varint(&mut point, 2 << 3);
+    put_varint(&mut point, millis as u64);
+    put_bytes(&mut series, 2, &point);
+    series
+}
+
+fn write_request(samples: &[Sample], at: DateTime<Local>) -> Vec<u8> {
+    let millis = at.timestamp_millis();
+    let mut request = Vec::new();
+    for sample in samples {
+        let up = if sample.up { 1.0 } else { 0.0 };
+        let series = time_series("server_check_up", sample, up, millis);
+        put_bytes(&mut request, 1, &series);
+        if let Some(ms) = sample.latency_ms {
+            let series = time_series("server_check_latency_ms", sample, ms as f64, millis);
+            put_bytes(&mut request, 1, &series);
+        }
+    }
+    request
+}
+
+// remote-write 要求 snappy 块格式；数据量很小，只写不压缩的字面量块，仍是合法的 snappy 数据
+fn snappy_literal(data: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(data.len() + 16);
+    put_varint(&mut out, data.len() as u64);
+    for chunk in data.chunks(65536) {
+        // 标签 61 表示长度减一用接下来的两个字节表示
+        out.push(61 << 2);
+        out.extend_from_slice(&((chunk.len() - 1) as u16).to_le_bytes());
+        out.extend_from_slice(chunk);
+    }
+    out
+}
+
+pub async fn push(
+    client: &reqwest::Client,
+    settings: &ExportSettings,
+    servers: &[Server],
+    at: DateTime<Local>,
+) -> Result<usize> {
+    settings.validate()?;
+    let samples = samples(servers);
+    if samples.is_empty() {
+        return Ok(0);
+    }
+    let mut request = client.post(settings.url.trim());
+    request = match settings.target {
+        ExportTarget::Influx => request
+            .header("Content-Type", "text/plain; charset=utf-8")
+            .body(line_protocol(&samples, at)),
+        ExportTarget::RemoteWrite => request
+            .header("Content-Type", "application/x-protobuf")
+            .header("Content-Encoding", "snappy")
+            .header("X-Prometheus-Remote-Write-Version", "0.1.0")
+            .body(snappy_literal(&write_request(&samples, at))),
+    };
+    let token = settings.token.trim();
+    if !token.is_empty() {
+        request = match settings.target {
+            ExportTarget::Influx => request.header("Authorization", format!("Token {}", token)),
+            ExportTarget::RemoteWrite => request.bearer_auth(token),
+        };
+    }
+    if !settings.username.is_empty() {
+        request = request.basic_auth(&settings.username, Some(&settings.password));
+    }
+    let resp = request.send().await?;
+    if !resp.status().is_success() {
+        let code = resp.status().as_u16();
+        let body = http::read_text(resp, ERROR_BODY_LIMIT)
+            .await
+            .unwrap_or_default();
+        return Err(anyhow!(tr!(
+            "返回错误状态 {}: {}",
+            "returned error status {}: {}",
+            code,
+            body.trim()
+        )));
+    }
+    Ok(samples.len())
+}
+
+// 在后台推送这一轮的结果，与心跳一样只在开始失败时写日志
+pub fn spawn_push(
+    client: &reqwest::Client,
+    settings: &ExportSettings,
+    servers: &[Server],
+    last: &LastExport,
+    log: &ActionLog,
+) {
+    let client = client.clone();
+    let settings = settings.clone();
+    let servers = servers.to_vec();
+    let last = Arc::clone(last);
+    let log = log.clone();
+    tokio::spawn(async move {
+        let at = Local::now();
+        let result = push(&client, &settings, &servers, at).await;
+        let mut last = last.lock().unwrap();
+        let was_failing = last.as_ref().is_some_and(|r| r.error.is_some());
+        let (count, error) = match result {
+            Ok(count) => (count, None),
+            Err(e) => (0, Some(e.to_string())),
+        };
+        if let (Some(e), false) = (&error, was_failing) {
+            log.push(tr!("推送指标失败: {}", "Metrics export failed: {}", e));
+        }
+        *last = Some(ExportResult { at, count, error });
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use std::time::Duration;
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn servers() -> Vec<Server> {
+        let mut web = testing::http_server("http://10.0.0.1/");
+        web.name = "web 1".to_string();
+        web.group = "prod,eu".to_string();
+        web.status = ServerStatus::Online;
+        web.state.latency_ms = Some(42);
+        let mut db = testing::http_server("http://10.0.0.2/");
+        db.name = "db".to_string();
+        db.status = ServerStatus::Offline;
+        // 离线时的延迟是超时时间，不推送
+        db.state.latency_ms = Some(10_000);
+        let unchecked = testing::http_server("http://10.0.0.3/");
+        vec![web, db, unchecked]
+    }
+
+    #[test]
+    fn formats_line_protocol() {
+        let at = DateTime::from_timestamp(1_700_000_000, 0)
+            .unwrap()
+            .with_timezone(&Local);
+        assert_eq!(
+            line_protocol(&samples(&servers()), at),
+            "server_check,server=web\\ 1,group=prod\\,eu up=1i,status=\"online\",latency_ms=42i 1700000000000000000\n\
+             server_check,server=db up=0i,status=\"offline\" 1700000000000000000\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn pushes_remote_write() {
+        let mock = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/api/v1/write"))
+            .and(header("content-encoding", "snappy"))
+            .and(header("authorization", "Bearer secret"))
+            .respond_with(ResponseTemplate::new(204))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        let settings = ExportSettings {
+            enabled: true,
+            target: ExportTarget::RemoteWrite,
+            url: format!("{}/api/v1/write", mock.uri()),
+            token: "secret".to_string(),
+            ..Default::default()
+        };
+        let client = testing::client(Duration::from_secs(5));
+        let count = push(&client, &settings, &servers(), Local::now())
+            .await
+            .unwrap();
+        assert_eq!(count, 2);
+
+        let body = &mock.received_requests().await.unwrap()[0].body;
+        // 开头是解压后的长度，之后是一个字面量块
+        let payload = write_request(&samples(&servers()), Local::now());
+        assert_eq!(
+            body[0] as usize | (body[1] as usize & 0x7f) << 7,
+            payload.len()
+        );
+        assert_eq!(body.len(), payload.len() + 5);
+        let text = String::from_utf8_lossy(body);
+        assert!(text.contains("server_check_latency_ms"));
+        assert!(text.contains("prod,eu"));
+
+        let both = ExportSettings {
+            username: "user".to_string(),
+            ..settings
+        };
+        assert!(push(&client, &both, &servers(), Local::now())
+            .await
+            .is_err());
+    }
+}
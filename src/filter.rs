@@ -0,0 +1,442 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::net::IpAddr;
+
+use crate::i18n::tr;
+use crate::{Server, ServerStatus};
+
+// 状态快速筛选，同时用作状态栏的分类统计；每台服务器只属于一类
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StatusFilter {
+    Online,
+    Degraded,
+    Offline,
+    Error,
+    Unchecked,
+    // 离线且原因是请求超时
+    Timeout,
+    // 所在分组正处于维护时段（可用率统计的排除时段）
+    Maintenance,
+    Paused,
+    // 短时间内反复上线、掉线
+    Flapping,
+}
+
+impl StatusFilter {
+    // 状态栏中的显示顺序
+    pub const ALL: [StatusFilter; 9] = [
+        StatusFilter::Online,
+        StatusFilter::Degraded,
+        StatusFilter::Flapping,
+        StatusFilter::Error,
+        StatusFilter::Timeout,
+        StatusFilter::Offline,
+        StatusFilter::Maintenance,
+        StatusFilter::Paused,
+        StatusFilter::Unchecked,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatusFilter::Online => tr!("在线", "Online"),
+            StatusFilter::Degraded => tr!("降级", "Degraded"),
+            StatusFilter::Offline => tr!("离线", "Offline"),
+            StatusFilter::Error => tr!("错误", "Error"),
+            StatusFilter::Unchecked => tr!("未检查", "Unchecked"),
+            StatusFilter::Timeout => tr!("超时", "Timeout"),
+            StatusFilter::Maintenance => tr!("维护中", "Maintenance"),
+            StatusFilter::Paused => tr!("已暂停", "Paused"),
+            StatusFilter::Flapping => tr!("抖动", "Flapping"),
+        }
+    }
+
+    // 给程序读取的固定标识，不随界面语言变化
+    pub fn id(&self) -> &'static str {
+        match self {
+            StatusFilter::Online => "online",
+            StatusFilter::Degraded => "degraded",
+            StatusFilter::Offline => "offline",
+            StatusFilter::Error => "error",
+            StatusFilter::Unchecked => "unchecked",
+            StatusFilter::Timeout => "timeout",
+            StatusFilter::Maintenance => "maintenance",
+            StatusFilter::Paused => "paused",
+            StatusFilter::Flapping => "flapping",
+        }
+    }
+
+    // 服务器所属的分类：暂停、维护和抖动优先于检查结果
+    pub fn of(server: &Server) -> StatusFilter {
+        if server.paused {
+            return StatusFilter::Paused;
+        }
+        if server.state.maintenance {
+            return StatusFilter::Maintenance;
+        }
+        if server.state.flap.is_flapping() {
+            return StatusFilter::Flapping;
+        }
+        match server.status {
+            ServerStatus::Online => StatusFilter::Online,
+            ServerStatus::Degraded => StatusFilter::Degraded,
+            ServerStatus::Offline if server.state.timed_out => StatusFilter::Timeout,
+            ServerStatus::Offline => StatusFilter::Offline,
+            ServerStatus::Error(_) => StatusFilter::Error,
+            ServerStatus::Unchecked => StatusFilter::Unchecked,
+        }
+    }
+
+    pub fn matches(&self, server: &Server) -> bool {
+        Self::of(server) == *self
+    }
+}
+
+// 各分类的服务器数量，按 StatusFilter::ALL 的顺序，数量为0的不返回
+pub fn status_counts<'a>(
+    servers: impl IntoIterator<Item = &'a Server>,
+) -> Vec<(StatusFilter, usize)> {
+    let mut counts = [0usize; StatusFilter::ALL.len()];
+    for server in servers {
+        let category = StatusFilter::of(server);
+        if let Some(i) = StatusFilter::ALL.iter().position(|c| *c == category) {
+            counts[i] += 1;
+        }
+    }
+    StatusFilter::ALL
+        .into_iter()
+        .zip(counts)
+        .filter(|(_, count)| *count > 0)
+        .collect()
+}
+
+// 分组统计：(分组名, 服务器数, 有问题的数量)，分组按第一次出现的顺序
+pub fn group_counts(servers: &[Server]) -> Vec<(String, usize, usize)> {
+    let mut groups: Vec<(String, usize, usize)> = Vec::new();
+    for server in servers {
+        let problem = matches!(
+            StatusFilter::of(server),
+            StatusFilter::Offline
+                | StatusFilter::Error
+                | StatusFilter::Timeout
+                | StatusFilter::Flapping
+        );
+        match groups.iter_mut().find(|(g, _, _)| *g == server.group) {
+            Some((_, total, problems)) => {
+                *total += 1;
+                *problems += problem as usize;
+            }
+            None => groups.push((server.group.clone(), 1, problem as usize)),
+        }
+    }
+    groups
+}
+
+// 服务器列表的搜索与筛选条件，只影响显示，不修改列表本身
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ServerFilter {
+    pub text: String,
+    // 选中的状态，为空表示不按状态筛选
+    pub statuses: Vec<StatusFilter>,
+    // 只显示某个分组，空字符串表示未分组
+    pub group: Option<String>,
+    // 只显示同时带有这些标签的服务器
+    pub tags: Vec<String>,
+}
+
+impl ServerFilter {
+    pub fn is_active(&self) -> bool {
+        !self.text.trim().is_empty()
+            || !self.statuses.is_empty()
+            || self.group.is_some()
+            || !self.tags.is_empty()
+    }
+
+    pub fn toggle_status(&mut self, status: StatusFilter) {
+        if let Some(pos) = self.statuses.iter().position(|s| *s == status) {
+            self.statuses.remove(pos);
+        } else {
+            self.statuses.push(status);
+        }
+    }
+
+    pub fn toggle_group(&mut self, group: &str) {
+        if self.group.as_deref() == Some(group) {
+            self.group = None;
+        } else {
+            self.group = Some(group.to_string());
+        }
+    }
+
+    pub fn toggle_tag(&mut self, tag: &str) {
+        if let Some(pos) = self.tags.iter().position(|t| t == tag) {
+            self.tags.remove(pos);
+        } else {
+            self.tags.push(tag.to_string());
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.statuses.clear();
+        self.group = None;
+        self.tags.clear();
+    }
+
+    // 名称、IP、URL、分组、备注、标签 任一包含搜索文本（不区分大小写）且状态、分组、标签符合
+    pub fn matches(&self, server: &Server) -> bool {
+        let text = self.text.trim().to_lowercase();
+        let text_match = text.is_empty()
+            || [
+                &server.name,
+                &server.ip,
+                &server.url,
+                &server.group,
+                &server.notes,
+            ]
+            .into_iter()
+            .chain(&server.tags)
+            .any(|field| field.to_lowercase().contains(&text));
+        let status_match =
+            self.statuses.is_empty() || self.statuses.iter().any(|s| s.matches(server));
+        let group_match = self.group.as_ref().is_none_or(|g| *g == server.group);
+        let tags_match = self.tags.iter().all(|t| server.tags.contains(t));
+        text_match && status_match && group_match && tags_match
+    }
+}
+
+// 列表排序方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum SortKey {
+    // 配置文件中的顺序
+    #[default]
+    Manual,
+    Name,
+    Address,
+    Status,
+    Latency,
+    Health,
+    LastChange,
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 7] = [
+        SortKey::Manual,
+        SortKey::Name,
+        SortKey::Address,
+        SortKey::Status,
+        SortKey::Latency,
+        SortKey::Health,
+        SortKey::LastChange,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortKey::Manual => tr!("默认顺序", "Default order"),
+            SortKey::Name => tr!("名称", "Name"),
+            SortKey::Address => tr!("IP:端口", "IP:port"),
+            SortKey::Status => tr!("状态", "Status"),
+            SortKey::Latency => tr!("延迟", "Latency"),
+            SortKey::Health => tr!("健康分", "Health score"),
+            SortKey::LastChange => tr!("最近变化", "Last change"),
+        }
+    }
+}
+
+// 排序用的状态权重，问题越严重越靠前
+fn status_rank(status: &ServerStatus) -> u8 {
+    match status {
+        ServerStatus::Offline => 0,
+        ServerStatus::Error(_) => 1,
+        ServerStatus::Degraded => 2,
+        ServerStatus::Online => 3,
+        ServerStatus::Unchecked => 4,
+    }
+}
+
+// IP按数值比较，主机名排在IP之后按字母比较
+fn compare_address(a: &Server, b: &Server) -> Ordering {
+    match (a.ip.parse::<IpAddr>(), b.ip.parse::<IpAddr>()) {
+        (Ok(x), Ok(y)) => x.cmp(&y),
+        (Ok(_), Err(_)) => Ordering::Less,
+        (Err(_), Ok(_)) => Ordering::Greater,
+        _ => a.ip.to_lowercase().cmp(&b.ip.to_lowercase()),
+    }
+    .then(a.port.cmp(&b.port))
+}
+
+// 返回排序后的下标，不改变列表本身，删除/编辑仍使用原始下标
+pub fn sorted_indices(servers: &[Server], key: SortKey, descending: bool) -> Vec<usize> {
+    let mut indices: Vec<usize> = (0..servers.len()).collect();
+    if key == SortKey::Manual {
+        if descending {
+            indices.reverse();
+        }
+        return indices;
+    }
+
+    indices.sort_by(|&i, &j| {
+        let (a, b) = (&servers[i], &servers[j]);
+        let ordering = match key {
+            SortKey::Manual => Ordering::Equal,
+            SortKey::Name => a.name.to_lowercase().cmp(&b.name.to_lowercase()),
+            SortKey::Address => compare_address(a, b),
+            SortKey::Status => status_rank(&a.status).cmp(&status_rank(&b.status)),
+            // 没有延迟数据的排在最后
+            SortKey::Latency => match (a.state.latency_ms, b.state.latency_ms) {
+                (Some(x), Some(y)) => x.cmp(&y),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+            // 分数低的排在前面，没有分数的排在最后
+            SortKey::Health => match (&a.state.health, &b.state.health) {
+                (Some(x), Some(y)) => x.score.cmp(&y.score),
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (None, None) => Ordering::Equal,
+            },
+            // 最近变化的排在前面
+            SortKey::LastChange => b.state.last_change.cmp(&a.state.last_change),
+        };
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+    indices
+}
+
+// 保持组内顺序，把同一分组的服务器排在一起，分组按第一次出现的顺序
+pub fn group_indices(servers: &[Server], indices: Vec<usize>) -> Vec<usize> {
+    let mut groups: Vec<&str> = Vec::new();
+    for &i in &indices {
+        if !groups.contains(&servers[i].group.as_str()) {
+            groups.push(&servers[i].group);
+        }
+    }
+    let mut grouped = indices;
+    grouped.sort_by_key(|&i| groups.iter().position(|g| *g == servers[i].group));
+    grouped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn server(group: &str, status: ServerStatus) -> Server {
+        let mut server = testing::http_server("http://127.0.0.1:8080");
+        server.group = group.to_string();
+        server.status = status;
+        server
+    }
+
+    #[test]
+    fn each_server_counts_in_one_category() {
+        let mut servers = vec![
+            server("web", ServerStatus::Online),
+            server("web", ServerStatus::Online),
+            server("web", ServerStatus::Offline),
+            server("db", ServerStatus::Offline),
+            server("db", ServerStatus::Error(500)),
+            server("db", ServerStatus::Unchecked),
+            server("", ServerStatus::Offline),
+            server("", ServerStatus::Online),
+        ];
+        servers[3].state.timed_out = true;
+        servers[6].paused = true;
+        servers[7].state.maintenance = true;
+
+        assert_eq!(
+            status_counts(&servers),
+            vec![
+                (StatusFilter::Online, 2),
+                (StatusFilter::Error, 1),
+                (StatusFilter::Timeout, 1),
+                (StatusFilter::Offline, 1),
+                (StatusFilter::Maintenance, 1),
+                (StatusFilter::Paused, 1),
+                (StatusFilter::Unchecked, 1),
+            ]
+        );
+        assert_eq!(
+            group_counts(&servers),
+            vec![
+                ("web".to_string(), 3, 1),
+                ("db".to_string(), 3, 2),
+                (String::new(), 2, 0),
+            ]
+        );
+    }
+
+    #[test]
+    fn filter_combines_status_and_group() {
+        let servers = [
+            server("web", ServerStatus::Offline),
+            server("db", ServerStatus::Offline),
+            server("db", ServerStatus::Online),
+        ];
+        let mut filter = ServerFilter::default();
+        filter.toggle_status(StatusFilter::Offline);
+        filter.toggle_group("db");
+        let matched: Vec<bool> = servers.iter().map(|s| filter.matches(s)).collect();
+        assert_eq!(matched, vec![false, true, false]);
+
+        filter.toggle_group("db");
+        assert_eq!(filter.group, None);
+    }
+
+    #[test]
+    fn tag_filter_requires_all_selected_tags() {
+        let mut prod_gpu = server("", ServerStatus::Online);
+        prod_gpu.tags = vec!["prod".to_string(), "gpu".to_string()];
+        let mut prod = server("", ServerStatus::Online);
+        prod.tags = vec!["prod".to_string()];
+
+        let mut filter = ServerFilter::default();
+        filter.toggle_tag("prod");
+        assert!(filter.matches(&prod_gpu) && filter.matches(&prod));
+        filter.toggle_tag("gpu");
+        assert!(filter.matches(&prod_gpu) && !filter.matches(&prod));
+        assert!(filter.is_active());
+
+        // 搜索文本也匹配标签
+        let filter = ServerFilter {
+            text: "GPU".to_string(),
+            ..Default::default()
+        };
+        assert!(filter.matches(&prod_gpu) && !filter.matches(&prod));
+    }
+
+    #[test]
+    fn text_search_includes_notes() {
+        let mut server = server("", ServerStatus::Online);
+        server.notes = "负责人: 张三\nsystemctl restart nginx".to_string();
+        let filter = ServerFilter {
+            text: "NGINX".to_string(),
+            ..Default::default()
+        };
+        assert!(filter.matches(&server));
+        server.notes.clear();
+        assert!(!filter.matches(&server));
+    }
+
+    #[test]
+    fn health_sort_puts_worst_first_and_unscored_last() {
+        let scored = |score| {
+            let mut server = server("", ServerStatus::Online);
+            server.state.health = Some(crate::health::HealthScore {
+                score,
+                factors: Vec::new(),
+            });
+            server
+        };
+        let servers = [server("", ServerStatus::Unchecked), scored(90), scored(40)];
+        assert_eq!(
+            sorted_indices(&servers, SortKey::Health, false),
+            vec![2, 1, 0]
+        );
+    }
+}
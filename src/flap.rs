@@ -0,0 +1,152 @@
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+
+// 抖动检测：服务器在一段时间内反复上线、掉线时标记为抖动，期间不再逐次通知状态变化，
+// 只在开始抖动和恢复稳定时各通知一次
+
+pub const MIN_FLAP_CHANGES: u32 = 2;
+pub const MAX_FLAP_CHANGES: u32 = 50;
+pub const MAX_FLAP_WINDOW_MINS: u64 = 24 * 60;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FlapSettings {
+    pub enabled: bool,
+    // 窗口内状态变化超过这个次数时视为抖动
+    pub max_changes: u32,
+    pub window_mins: u64,
+}
+
+impl Default for FlapSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_changes: 4,
+            window_mins: 10,
+        }
+    }
+}
+
+impl FlapSettings {
+    pub fn max_changes(&self) -> usize {
+        self.max_changes.clamp(MIN_FLAP_CHANGES, MAX_FLAP_CHANGES) as usize
+    }
+
+    pub fn window(&self) -> chrono::Duration {
+        chrono::Duration::minutes(self.window_mins.clamp(1, MAX_FLAP_WINDOW_MINS) as i64)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FlapEvent {
+    Started,
+    // 窗口内最多只剩一次变化，恢复稳定
+    Stopped,
+}
+
+// 一台服务器最近的状态变化时间
+#[derive(Debug, Clone, Default)]
+pub struct FlapState {
+    changes: VecDeque<DateTime<Local>>,
+    flapping: bool,
+}
+
+impl FlapState {
+    // 每次检查后调用，changed 表示这次状态是否变化
+    pub fn record(
+        &mut self,
+        now: DateTime<Local>,
+        changed: bool,
+        settings: &FlapSettings,
+    ) -> Option<FlapEvent> {
+        if changed {
+            self.changes.push_back(now);
+        }
+        let since = now - settings.window();
+        while self.changes.front().is_some_and(|t| *t < since) {
+            self.changes.pop_front();
+        }
+
+        if !settings.enabled {
+            // 关闭检测时直接回到正常状态，不再通知
+            self.flapping = false;
+            return None;
+        }
+        if !self.flapping && self.changes.len() > settings.max_changes() {
+            self.flapping = true;
+            return Some(FlapEvent::Started);
+        }
+        if self.flapping && self.changes.len() <= 1 {
+            self.flapping = false;
+            return Some(FlapEvent::Stopped);
+        }
+        None
+    }
+
+    pub fn is_flapping(&self) -> bool {
+        self.flapping
+    }
+
+    // 窗口内的变化次数
+    pub fn change_count(&self) -> usize {
+        self.changes.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn starts_after_too_many_changes_and_stops_when_stable() {
+        let settings = FlapSettings::default();
+        let mut state = FlapState::default();
+        let start = Local::now();
+        let minute = chrono::Duration::minutes(1);
+
+        for i in 0..4 {
+            assert_eq!(state.record(start + minute * i, true, &settings), None);
+        }
+        assert_eq!(
+            state.record(start + minute * 4, true, &settings),
+            Some(FlapEvent::Started)
+        );
+        assert!(state.is_flapping());
+        // 继续抖动时不再重复通知
+        assert_eq!(state.record(start + minute * 5, true, &settings), None);
+        assert_eq!(state.change_count(), 6);
+
+        // 之后不再变化，旧的变化移出窗口后恢复
+        assert_eq!(state.record(start + minute * 12, false, &settings), None);
+        assert_eq!(
+            state.record(start + minute * 15, false, &settings),
+            Some(FlapEvent::Stopped)
+        );
+        assert!(!state.is_flapping());
+    }
+
+    #[test]
+    fn slow_changes_and_disabled_detection_never_flap() {
+        let settings = FlapSettings::default();
+        let mut state = FlapState::default();
+        let start = Local::now();
+        // 每5分钟变化一次，窗口内最多两次
+        for i in 0..20 {
+            let now = start + chrono::Duration::minutes(5 * i);
+            assert_eq!(state.record(now, true, &settings), None);
+        }
+
+        let disabled = FlapSettings {
+            enabled: false,
+            ..Default::default()
+        };
+        let mut state = FlapState::default();
+        for i in 0..10 {
+            let now = start + chrono::Duration::seconds(10 * i);
+            assert_eq!(state.record(now, true, &disabled), None);
+        }
+        assert!(!state.is_flapping());
+    }
+}
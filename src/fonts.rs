@@ -0,0 +1,144 @@
+use eframe::egui;
+use std::path::{Path, PathBuf};
+
+// 常见的系统中文字体
+fn system_font_paths() -> Vec<PathBuf> {
+    let paths: &[&str] = if cfg!(target_os = "windows") {
+        &[
+            "C:\\Windows\\Fonts\\msyh.ttc",   // 微软雅黑
+            "C:\\Windows\\Fonts\\simhei.ttf", // 黑体
+            "C:\\Windows\\Fonts\\simsun.ttc", // 宋体
+        ]
+    } else if cfg!(target_os = "macos") {
+        &[
+            "/System/Library/Fonts/PingFang.ttc",         // 苹方
+            "/System/Library/Fonts/Hiragino Sans GB.ttc", // 冬青黑体
+            "/System/Library/Fonts/STHeiti Light.ttc",    // 华文黑体
+        ]
+    } else {
+        // Linux
+        &[
+            "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
+            "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
+            "/usr/share/fonts/truetype/wqy/wqy-zenhei.ttc",
+            "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
+        ]
+    };
+    paths.iter().map(PathBuf::from).collect()
+}
+
+// 字体文件名中包含这些关键字时认为是中文字体
+const CJK_NAME_HINTS: &[&str] = &[
+    "cjk",
+    "wqy",
+    "droidsansfallback",
+    "sourcehansans",
+    "notosanssc",
+    "notosanscjk",
+    "msyh",
+    "simhei",
+    "pingfang",
+];
+
+// 已知路径都不存在时，在字体目录中按文件名查找，例如各发行版安装位置不同的 Noto CJK
+fn scan_font_dirs() -> Option<PathBuf> {
+    let mut dirs = vec![
+        PathBuf::from("/usr/share/fonts"),
+        PathBuf::from("/usr/local/share/fonts"),
+    ];
+    if let Some(home) = dirs::home_dir() {
+        dirs.push(home.join(".local/share/fonts"));
+        dirs.push(home.join(".fonts"));
+    }
+    if let Some(dir) = dirs::font_dir() {
+        dirs.push(dir);
+    }
+
+    let mut stack = dirs;
+    while let Some(dir) = stack.pop() {
+        for entry in std::fs::read_dir(&dir).into_iter().flatten().flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                stack.push(path);
+                continue;
+            }
+            let name = path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or("")
+                .to_lowercase()
+                .replace(['-', '_', ' '], "");
+            let is_font =
+                name.ends_with(".ttf") || name.ends_with(".ttc") || name.ends_with(".otf");
+            if is_font && CJK_NAME_HINTS.iter().any(|hint| name.contains(hint)) {
+                return Some(path);
+            }
+        }
+    }
+    None
+}
+
+// 编译时设置了 SERVER_CHECK_EMBED_FONT 时，build.rs 把该字体复制到输出目录并打包进程序
+#[cfg(embedded_font)]
+fn embedded_font() -> Option<Vec<u8>> {
+    Some(include_bytes!(concat!(env!("OUT_DIR"), "/embedded_font")).to_vec())
+}
+
+#[cfg(not(embedded_font))]
+fn embedded_font() -> Option<Vec<u8>> {
+    None
+}
+
+fn load_font_data(custom: Option<&Path>) -> Option<(Vec<u8>, String)> {
+    // 设置中指定的字体优先
+    if let Some(path) = custom {
+        match std::fs::read(path) {
+            Ok(data) => return Some((data, path.display().to_string())),
+            Err(e) => eprintln!("无法读取指定的字体 {:?}: {}", path, e),
+        }
+    }
+
+    let system = system_font_paths()
+        .into_iter()
+        .find(|path| path.exists())
+        .or_else(scan_font_dirs);
+    if let Some(path) = system {
+        if let Ok(data) = std::fs::read(&path) {
+            return Some((data, path.display().to_string()));
+        }
+    }
+
+    embedded_font().map(|data| (data, "内置字体".to_string()))
+}
+
+// 初始化中文字体支持，custom 为设置中指定的字体文件
+pub fn init(ctx: &egui::Context, custom: Option<&Path>) {
+    let mut fonts = egui::FontDefinitions::default();
+
+    match load_font_data(custom) {
+        Some((font_data, source)) => {
+            fonts.font_data.insert(
+                "chinese_font".to_owned(),
+                egui::FontData::from_owned(font_data),
+            );
+
+            // 将中文字体添加到字体族中
+            fonts
+                .families
+                .entry(egui::FontFamily::Proportional)
+                .or_default()
+                .insert(0, "chinese_font".to_owned());
+
+            fonts
+                .families
+                .entry(egui::FontFamily::Monospace)
+                .or_default()
+                .push("chinese_font".to_owned());
+
+            println!("找到中文字体: {}", source);
+        }
+        None => println!("警告: 未找到中文字体，中文可能显示为方块"),
+    }
+
+    ctx.set_fonts(fonts);
+}
@@ -0,0 +1,66 @@
+// 命令面板使用的模糊匹配：查询中的字符按顺序出现在候选文字中即算匹配，
+// 连续匹配、匹配在单词开头或更靠前的得分更高。不区分大小写
+
+pub fn score(query: &str, candidate: &str) -> Option<u32> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    if query.is_empty() {
+        return Some(0);
+    }
+    let chars: Vec<char> = candidate.chars().flat_map(char::to_lowercase).collect();
+
+    // 第一个字符可能出现多次，从每一处开始各匹配一次，取最好的
+    (0..chars.len())
+        .filter(|&start| chars[start] == query[0])
+        .filter_map(|start| {
+            let score = score_from(&query, &chars, start)?;
+            // 同分时越早出现越好
+            Some(score * 100 + 99 - start.min(99) as u32)
+        })
+        .max()
+}
+
+fn score_from(query: &[char], chars: &[char], start: usize) -> Option<u32> {
+    let mut score = 0;
+    let mut previous: Option<usize> = None;
+    let mut next = start;
+    for &q in query {
+        let pos = (next..chars.len()).find(|&i| chars[i] == q)?;
+        score += 1;
+        if previous.is_some_and(|p| p + 1 == pos) {
+            score += 5;
+        }
+        if pos == 0 || !chars[pos - 1].is_alphanumeric() {
+            score += 3;
+        }
+        previous = Some(pos);
+        next = pos + 1;
+    }
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn matches_subsequence_case_insensitively() {
+        assert!(score("web", "Web Server 01").is_some());
+        assert!(score("ws1", "Web Server 01").is_some());
+        assert!(score("wx", "Web Server 01").is_none());
+        assert_eq!(score("", "anything"), Some(0));
+        assert!(score("设置", "打开设置").is_some());
+    }
+
+    #[test]
+    fn prefers_consecutive_and_word_start_matches() {
+        assert!(score("che", "check").unwrap() > score("che", "cache").unwrap());
+
+        let word_start = score("db", "prod db-01").unwrap();
+        let inside = score("db", "sandbox").unwrap();
+        assert!(word_start > inside);
+    }
+}
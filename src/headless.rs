@@ -0,0 +1,196 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::clock::{Clock, SystemClock};
+use crate::config::{is_valid_profile_name, ConfigLocation, Settings};
+use crate::i18n::{self, tr};
+use crate::ipc::IpcListener;
+use crate::monitor::Monitor;
+use crate::proxy;
+use crate::report;
+use crate::storage::{self, StorageKind};
+use crate::watcher::ConfigWatcher;
+use crate::web::{self, SharedSnapshot, Snapshot};
+use crate::{ensure_unique_ids, merge_servers, open_storage, Server};
+
+// 无界面模式：不创建窗口、不加载字体，定时检查服务器，发送通知和心跳，
+// 通过网页看板和JSON接口查看状态，适合部署在服务器上的容器中。
+// 配置目录和文件格式与桌面版相同，可以直接复用桌面版的配置
+
+// 默认只在本机监听，容器中需要指定 --listen 0.0.0.0:8080
+pub const DEFAULT_LISTEN: &str = "127.0.0.1:8080";
+
+struct Headless {
+    monitor: Monitor,
+    doc: String,
+    // 最近一次读取的配置内容，内容没变时不重新加载
+    last_content: String,
+    config_watcher: Option<ConfigWatcher>,
+    ipc: Option<IpcListener>,
+    snapshot: SharedSnapshot,
+}
+
+impl Headless {
+    fn new(profile: Option<String>) -> Result<Self, String> {
+        let location = ConfigLocation::resolve();
+        let settings = Settings::load(&location.settings_path());
+        i18n::set_lang(settings.language);
+        let profile = profile.unwrap_or_else(|| settings.profile.clone());
+        if !is_valid_profile_name(&profile) {
+            return Err(tr!(
+                "无效的方案名称: {}",
+                "Invalid profile name: {}",
+                profile
+            ));
+        }
+        let (storage, error) = open_storage(&settings.storage, &location);
+        if let Some(error) = error {
+            eprintln!("{}", error);
+        }
+        let doc = storage::servers_doc(&profile);
+        let clock: Arc<dyn Clock> = Arc::new(SystemClock);
+        proxy::set_global(&settings.proxy);
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(5))
+            .tls_info(true)
+            .proxy(proxy::global())
+            .build()
+            .map_err(|e| e.to_string())?;
+
+        let config_watcher = if settings.storage.kind == StorageKind::JsonFile {
+            ConfigWatcher::new(&location.profile_path(&profile))
+                .map_err(|e| eprintln!("无法监视配置文件: {}", e))
+                .ok()
+        } else {
+            None
+        };
+        let ipc = IpcListener::start(&location.dir)
+            .map_err(|e| eprintln!("无法接收命令行的通知: {}", e))
+            .ok();
+
+        let reports_dir = report::reports_dir(&location);
+        let mut headless = Self {
+            monitor: Monitor::new(settings, storage, clock, client, reports_dir),
+            doc,
+            last_content: String::new(),
+            config_watcher,
+            ipc,
+            snapshot: SharedSnapshot::default(),
+        };
+        headless.reload().map_err(|e| {
+            tr!(
+                "读取服务器列表 {} 失败: {}",
+                "Failed to read server list {}: {}",
+                headless.doc,
+                e
+            )
+        })?;
+        println!(
+            "已加载 {}，共 {} 台服务器",
+            headless.doc,
+            headless.monitor.servers.len()
+        );
+        Ok(headless)
+    }
+
+    // 读取服务器列表，保留仍然存在的服务器的当前状态；方案还不存在时为空列表
+    fn reload(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        let content = self.monitor.storage.load(&self.doc)?.unwrap_or_default();
+        if content == self.last_content {
+            return Ok(false);
+        }
+        let loaded: Vec<Server> = if content.trim().is_empty() {
+            Vec::new()
+        } else {
+            serde_json::from_str(&content)?
+        };
+        let servers = &mut self.monitor.servers;
+        *servers = merge_servers(servers, loaded);
+        ensure_unique_ids(servers);
+        self.last_content = content;
+        if self.monitor.migrate_history_keys() {
+            let json = serde_json::to_string_pretty(&self.monitor.servers)?;
+            self.monitor.storage.save(&self.doc, &json)?;
+            self.last_content = json;
+        }
+        Ok(true)
+    }
+
+    // 每秒执行一次
+    fn tick(&mut self) {
+        let changed = self.config_watcher.as_mut().is_some_and(|w| w.poll());
+        let notified = self.ipc.as_ref().is_some_and(|ipc| ipc.poll());
+        if changed || notified {
+            match self.reload() {
+                Ok(true) => println!("配置已变化，重新加载 {}", self.doc),
+                Ok(false) => {}
+                Err(e) => eprintln!("重新加载配置失败: {}", e),
+            }
+        }
+
+        let updates = self.monitor.tick();
+        for alert in updates.alerts {
+            println!("{}", alert);
+        }
+        for change in updates.changes {
+            println!("{}: {} -> {}", change.server, change.from, change.to);
+        }
+        for reminder in updates.reminders {
+            println!("{}: {}", reminder.server_name, reminder.action.message);
+        }
+        let monitor = &self.monitor;
+        *self.snapshot.lock().unwrap() =
+            Snapshot::new(&monitor.servers, monitor.settings.status_colors);
+    }
+}
+
+// 等待 Ctrl+C，或容器停止时发送的 SIGTERM
+async fn shutdown() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            return;
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+// 运行到收到退出信号，返回进程退出码
+pub async fn run(listen: Option<String>, profile: Option<String>) -> i32 {
+    let mut headless = match Headless::new(profile) {
+        Ok(headless) => headless,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+    let listen = listen.unwrap_or_else(|| DEFAULT_LISTEN.to_string());
+    match web::start(&listen, Arc::clone(&headless.snapshot)) {
+        Ok(addr) => println!("网页看板: http://{}/", addr),
+        Err(e) => {
+            eprintln!(
+                "{}",
+                tr!("无法监听 {}: {}", "Cannot listen on {}: {}", listen, e)
+            );
+            return 1;
+        }
+    }
+
+    let stop = shutdown();
+    tokio::pin!(stop);
+    let mut tick = tokio::time::interval(Duration::from_secs(1));
+    loop {
+        tokio::select! {
+            _ = tick.tick() => headless.tick(),
+            _ = &mut stop => break,
+        }
+    }
+    headless.monitor.save_history();
+    println!("已退出");
+    0
+}
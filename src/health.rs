@@ -0,0 +1,234 @@
+use chrono::{DateTime, Local};
+use std::collections::VecDeque;
+
+use crate::i18n::tr;
+
+// 计算错误率和延迟趋势时参考的最近检查次数
+const RECENT_CHECKS: usize = 20;
+// 延迟趋势至少需要的样本数
+const MIN_LATENCY_SAMPLES: usize = 4;
+// 各项满分，合计100；缺少数据的项不计入，按其余项折算
+const AVAILABILITY_WEIGHT: f64 = 40.0;
+const ERROR_RATE_WEIGHT: f64 = 30.0;
+const LATENCY_WEIGHT: f64 = 15.0;
+const CERT_WEIGHT: f64 = 15.0;
+// 24小时可用率低于该值时可用率一项为0分
+const AVAILABILITY_FLOOR: f64 = 90.0;
+// 近期平均延迟是之前的多少倍时开始扣分、扣完
+const LATENCY_RATIO_OK: f64 = 1.2;
+const LATENCY_RATIO_BAD: f64 = 3.0;
+// 证书剩余天数少于该值时开始扣分
+const CERT_DAYS_OK: f64 = 30.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sample {
+    ok: bool,
+    latency_ms: Option<u64>,
+}
+
+// 本次运行中最近几次检查的结果，不写入配置
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RecentChecks {
+    samples: VecDeque<Sample>,
+}
+
+impl RecentChecks {
+    pub fn push(&mut self, ok: bool, latency_ms: Option<u64>) {
+        self.samples.push_back(Sample { ok, latency_ms });
+        if self.samples.len() > RECENT_CHECKS {
+            self.samples.pop_front();
+        }
+    }
+
+    fn error_rate(&self) -> Option<f64> {
+        if self.samples.is_empty() {
+            return None;
+        }
+        let errors = self.samples.iter().filter(|s| !s.ok).count();
+        Some(errors as f64 / self.samples.len() as f64)
+    }
+
+    // 后一半成功检查的平均延迟是前一半的多少倍
+    fn latency_ratio(&self) -> Option<f64> {
+        let latencies: Vec<f64> = self
+            .samples
+            .iter()
+            .filter(|s| s.ok)
+            .filter_map(|s| s.latency_ms)
+            .map(|ms| ms as f64)
+            .collect();
+        if latencies.len() < MIN_LATENCY_SAMPLES {
+            return None;
+        }
+        let (older, newer) = latencies.split_at(latencies.len() / 2);
+        let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+        // 延迟都在1ms以内时视为没有变化
+        Some(mean(newer).max(1.0) / mean(older).max(1.0))
+    }
+}
+
+// 健康分中的一项
+#[derive(Debug, Clone, PartialEq)]
+pub struct Factor {
+    pub label: String,
+    pub points: f64,
+    pub max: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HealthScore {
+    // 0到100
+    pub score: u8,
+    pub factors: Vec<Factor>,
+}
+
+impl HealthScore {
+    // 悬停提示中显示的各项得分
+    pub fn breakdown(&self) -> String {
+        self.factors
+            .iter()
+            .map(|f| format!("{}: {:.0}/{:.0}", f.label, f.points, f.max))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+// 把 value 从 [bad, good] 线性映射到 [0, 1]
+fn scale(value: f64, bad: f64, good: f64) -> f64 {
+    ((value - bad) / (good - bad)).clamp(0.0, 1.0)
+}
+
+// 根据24小时可用率（百分比）、最近检查的错误率和延迟趋势、证书到期时间计算健康分；
+// 一项数据都没有时返回None
+pub fn score(
+    uptime: Option<f64>,
+    recent: &RecentChecks,
+    cert_expires: Option<DateTime<Local>>,
+    now: DateTime<Local>,
+) -> Option<HealthScore> {
+    let mut factors = Vec::new();
+    if let Some(uptime) = uptime {
+        factors.push(Factor {
+            label: tr!("可用率 {:.2}%", "Availability {:.2}%", uptime),
+            points: AVAILABILITY_WEIGHT * scale(uptime, AVAILABILITY_FLOOR, 100.0),
+            max: AVAILABILITY_WEIGHT,
+        });
+    }
+    if let Some(rate) = recent.error_rate() {
+        factors.push(Factor {
+            label: tr!(
+                "最近错误率 {:.0}%",
+                "Recent error rate {:.0}%",
+                rate * 100.0
+            ),
+            points: ERROR_RATE_WEIGHT * (1.0 - rate),
+            max: ERROR_RATE_WEIGHT,
+        });
+    }
+    if let Some(ratio) = recent.latency_ratio() {
+        factors.push(Factor {
+            label: tr!("延迟趋势 ×{:.1}", "Latency trend ×{:.1}", ratio),
+            points: LATENCY_WEIGHT * scale(ratio, LATENCY_RATIO_BAD, LATENCY_RATIO_OK),
+            max: LATENCY_WEIGHT,
+        });
+    }
+    if let Some(expires) = cert_expires {
+        let days = (expires - now).num_minutes() as f64 / 1440.0;
+        factors.push(Factor {
+            label: tr!(
+                "证书剩余 {:.0} 天",
+                "Certificate expires in {:.0} days",
+                days
+            ),
+            points: CERT_WEIGHT * scale(days, 0.0, CERT_DAYS_OK),
+            max: CERT_WEIGHT,
+        });
+    }
+    if factors.is_empty() {
+        return None;
+    }
+    let points: f64 = factors.iter().map(|f| f.points).sum();
+    let max: f64 = factors.iter().map(|f| f.max).sum();
+    Some(HealthScore {
+        score: (points / max * 100.0).round() as u8,
+        factors,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    fn recent(samples: &[(bool, u64)]) -> RecentChecks {
+        let mut recent = RecentChecks::default();
+        for &(ok, ms) in samples {
+            recent.push(ok, Some(ms));
+        }
+        recent
+    }
+
+    #[test]
+    fn no_data_has_no_score() {
+        assert_eq!(
+            score(None, &RecentChecks::default(), None, Local::now()),
+            None
+        );
+    }
+
+    #[test]
+    fn healthy_server_scores_full() {
+        let now = Local::now();
+        let health = score(
+            Some(100.0),
+            &recent(&[(true, 50); 10]),
+            Some(now + Duration::days(90)),
+            now,
+        )
+        .unwrap();
+        assert_eq!(health.score, 100);
+        assert_eq!(health.factors.len(), 4);
+    }
+
+    #[test]
+    fn each_signal_lowers_the_score() {
+        let now = Local::now();
+        let base = recent(&[(true, 50); 10]);
+        let full = score(Some(100.0), &base, None, now).unwrap().score;
+
+        let lower_uptime = score(Some(95.0), &base, None, now).unwrap().score;
+        assert!(lower_uptime < full);
+
+        let errors = recent(&[(true, 50), (false, 50), (true, 50), (false, 50)]);
+        assert!(score(Some(100.0), &errors, None, now).unwrap().score < full);
+
+        let slower = recent(&[(true, 50), (true, 50), (true, 200), (true, 200)]);
+        assert!(score(Some(100.0), &slower, None, now).unwrap().score < full);
+
+        let expiring = score(Some(100.0), &base, Some(now + Duration::days(3)), now).unwrap();
+        assert!(expiring.score < full);
+    }
+
+    #[test]
+    fn missing_signals_are_reweighted() {
+        // 只有错误率一项时，按该项折算为百分制
+        let mut checks = RecentChecks::default();
+        checks.push(true, None);
+        checks.push(false, None);
+        let health = score(None, &checks, None, Local::now()).unwrap();
+        assert_eq!(health.score, 50);
+        assert_eq!(health.factors.len(), 1);
+    }
+
+    #[test]
+    fn keeps_only_recent_checks() {
+        let mut checks = RecentChecks::default();
+        for _ in 0..RECENT_CHECKS {
+            checks.push(false, None);
+        }
+        for _ in 0..RECENT_CHECKS {
+            checks.push(true, None);
+        }
+        assert_eq!(checks.error_rate(), Some(0.0));
+    }
+}
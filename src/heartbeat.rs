@@ -0,0 +1,190 @@
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+
+use crate::clock::Clock;
+use crate::i18n::tr;
+use crate::schedule::ActionLog;
+
+// 定时访问外部监控（healthchecks.io 之类）的地址。本程序崩溃、卡死或电脑关机后
+// 对方收不到心跳，就会按它自己的规则报警
+
+pub const MIN_HEARTBEAT_SECS: u64 = 10;
+pub const MAX_HEARTBEAT_SECS: u64 = 3600;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HeartbeatSettings {
+    pub enabled: bool,
+    pub url: String,
+    pub interval_secs: u64,
+}
+
+impl Default for HeartbeatSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: String::new(),
+            interval_secs: 60,
+        }
+    }
+}
+
+impl HeartbeatSettings {
+    pub fn active(&self) -> bool {
+        self.enabled && !self.url.trim().is_empty()
+    }
+
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(
+            self.interval_secs
+                .clamp(MIN_HEARTBEAT_SECS, MAX_HEARTBEAT_SECS),
+        )
+    }
+}
+
+// 最近一次心跳的时间，失败时带上原因
+#[derive(Debug, Clone)]
+pub struct PingResult {
+    pub at: DateTime<Local>,
+    pub error: Option<String>,
+}
+
+pub type LastPing = Arc<Mutex<Option<PingResult>>>;
+
+pub async fn ping(client: &reqwest::Client, url: &str) -> Result<()> {
+    let resp = client.get(url.trim()).send().await?;
+    if !resp.status().is_success() {
+        return Err(anyhow!(tr!(
+            "返回错误状态 {}",
+            "returned error status {}",
+            resp.status().as_u16()
+        )));
+    }
+    Ok(())
+}
+
+// 常驻的心跳任务，按间隔发送，不依赖界面刷新；设置变化后重新计时
+pub struct Heartbeat {
+    settings: watch::Sender<HeartbeatSettings>,
+    pub last: LastPing,
+}
+
+impl Heartbeat {
+    pub fn spawn(
+        settings: &HeartbeatSettings,
+        client: &reqwest::Client,
+        clock: Arc<dyn Clock>,
+        log: &ActionLog,
+    ) -> Self {
+        let (sender, receiver) = watch::channel(settings.clone());
+        let last = LastPing::default();
+        tokio::spawn(run(
+            receiver,
+            client.clone(),
+            Arc::clone(&last),
+            clock,
+            log.clone(),
+        ));
+        Self {
+            settings: sender,
+            last,
+        }
+    }
+
+    pub fn update(&self, settings: &HeartbeatSettings) {
+        self.settings.send_if_modified(|current| {
+            let changed = current != settings;
+            if changed {
+                *current = settings.clone();
+            }
+            changed
+        });
+    }
+}
+
+// 结果写入 last；只在开始失败时写日志，避免每次都刷屏
+async fn run(
+    mut settings: watch::Receiver<HeartbeatSettings>,
+    client: reqwest::Client,
+    last: LastPing,
+    clock: Arc<dyn Clock>,
+    log: ActionLog,
+) {
+    loop {
+        let current = settings.borrow_and_update().clone();
+        tokio::select! {
+            changed = settings.changed() => match changed {
+                Ok(()) => continue,
+                // 程序退出
+                Err(_) => break,
+            },
+            _ = tokio::time::sleep(current.interval()) => {}
+        }
+        if !current.active() {
+            continue;
+        }
+        let error = ping(&client, &current.url)
+            .await
+            .err()
+            .map(|e| e.to_string());
+        let mut last = last.lock().unwrap();
+        let was_failing = last.as_ref().is_some_and(|p| p.error.is_some());
+        if let (Some(e), false) = (&error, was_failing) {
+            log.push(tr!("心跳发送失败: {}", "Heartbeat failed: {}", e));
+        }
+        *last = Some(PingResult {
+            at: clock.now(),
+            error,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn inactive_without_url_and_interval_is_clamped() {
+        let mut settings = HeartbeatSettings {
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(!settings.active());
+        settings.url = "https://hc-ping.com/abc".to_string();
+        assert!(settings.active());
+
+        settings.interval_secs = 1;
+        assert_eq!(settings.interval(), Duration::from_secs(MIN_HEARTBEAT_SECS));
+        settings.interval_secs = 86400;
+        assert_eq!(settings.interval(), Duration::from_secs(MAX_HEARTBEAT_SECS));
+    }
+
+    #[tokio::test]
+    async fn ping_reports_error_status() {
+        let mock = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/ok"))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock)
+            .await;
+        Mock::given(path("/gone"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&mock)
+            .await;
+        let client = testing::client(Duration::from_secs(5));
+
+        ping(&client, &format!("{}/ok", mock.uri())).await.unwrap();
+        assert!(ping(&client, &format!("{}/gone", mock.uri()))
+            .await
+            .is_err());
+    }
+}
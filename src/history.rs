@@ -0,0 +1,591 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveTime, Timelike, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::i18n::tr;
+use crate::storage::{Storage, HISTORY_DOC};
+use crate::ServerStatus;
+
+// 历史记录保留天数，覆盖详情窗口中90天的可用率热力图
+const RETENTION_DAYS: i64 = 92;
+// 两次检查间隔超过此时长（例如程序未运行）时，中间的时间不计入统计
+const MAX_GAP_MINUTES: i64 = 10;
+const DAY_SECONDS: i64 = 86400;
+// 每台服务器保留的故障记录条数，比状态历史保留得久，便于事后复盘
+const MAX_INCIDENTS: usize = 200;
+// 延迟按分钟段合并记录，只保留最近几天，供延迟图表使用
+pub const LATENCY_BUCKET_MINUTES: i64 = 5;
+const LATENCY_RETENTION_DAYS: i64 = 7;
+
+// 一段状态相同的连续时间
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Span {
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    // 在线或降级视为可用
+    pub up: bool,
+}
+
+// 一次故障：从第一次检查失败到恢复在线，离线和错误之间切换不算新的故障
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Incident {
+    pub start: DateTime<Local>,
+    // 仍在故障中时为None
+    pub end: Option<DateTime<Local>>,
+    // 事后在详情窗口中填写的原因
+    #[serde(default)]
+    pub cause: String,
+}
+
+// 一个时间段内成功检查的平均和最大延迟
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct LatencySample {
+    // 时间段的开始
+    pub at: DateTime<Local>,
+    pub avg_ms: f64,
+    pub max_ms: u64,
+    pub count: u32,
+}
+
+impl Incident {
+    // 进行中的故障算到 now
+    pub fn duration(&self, now: DateTime<Local>) -> Duration {
+        self.end.unwrap_or(now) - self.start
+    }
+}
+
+// 每台服务器的状态历史，只在状态变化时新增一段，默认保存在配置目录的 history.json
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct History {
+    series: HashMap<String, Vec<Span>>,
+    // 故障记录，旧的历史文件中没有
+    #[serde(default)]
+    incidents: HashMap<String, Vec<Incident>>,
+    #[serde(default)]
+    latency: HashMap<String, Vec<LatencySample>>,
+}
+
+impl History {
+    pub fn load(storage: &dyn Storage) -> Self {
+        match storage.load(HISTORY_DOC) {
+            Ok(content) => content
+                .and_then(|content| serde_json::from_str(&content).ok())
+                .unwrap_or_default(),
+            Err(e) => {
+                eprintln!("读取历史记录失败: {}", e);
+                Self::default()
+            }
+        }
+    }
+
+    pub fn save(&self, storage: &dyn Storage) -> Result<(), Box<dyn std::error::Error>> {
+        storage.save(HISTORY_DOC, &serde_json::to_string(self)?)?;
+        Ok(())
+    }
+
+    // 记录一次检查结果，未检查的状态不记录
+    pub fn record(&mut self, key: &str, at: DateTime<Local>, status: &ServerStatus) {
+        if *status == ServerStatus::Unchecked {
+            return;
+        }
+        let up = matches!(status, ServerStatus::Online | ServerStatus::Degraded);
+        self.track_incident(key, at, up);
+        let spans = self.series.entry(key.to_string()).or_default();
+        match spans.last_mut() {
+            Some(last) if at - last.end <= Duration::minutes(MAX_GAP_MINUTES) => {
+                // 上次检查到这次之间按上次的状态计算
+                last.end = at;
+                if last.up != up {
+                    spans.push(Span {
+                        start: at,
+                        end: at,
+                        up,
+                    });
+                }
+            }
+            _ => spans.push(Span {
+                start: at,
+                end: at,
+                up,
+            }),
+        }
+
+        let cutoff = at - Duration::days(RETENTION_DAYS);
+        let expired = spans.iter().take_while(|s| s.end < cutoff).count();
+        spans.drain(..expired);
+    }
+
+    // 同一时间段内的多次检查合并为一个点
+    pub fn record_latency(&mut self, key: &str, at: DateTime<Local>, ms: u64) {
+        let bucket = LATENCY_BUCKET_MINUTES * 60;
+        let start = at.timestamp() - at.timestamp().rem_euclid(bucket);
+        let start = DateTime::from_timestamp(start, 0)
+            .map(|t| t.with_timezone(&Local))
+            .unwrap_or(at);
+        let samples = self.latency.entry(key.to_string()).or_default();
+        match samples.last_mut() {
+            Some(last) if last.at == start => {
+                last.avg_ms =
+                    (last.avg_ms * last.count as f64 + ms as f64) / (last.count + 1) as f64;
+                last.max_ms = last.max_ms.max(ms);
+                last.count += 1;
+            }
+            _ => samples.push(LatencySample {
+                at: start,
+                avg_ms: ms as f64,
+                max_ms: ms,
+                count: 1,
+            }),
+        }
+        let cutoff = at - Duration::days(LATENCY_RETENTION_DAYS);
+        let expired = samples.iter().take_while(|s| s.at < cutoff).count();
+        samples.drain(..expired);
+    }
+
+    pub fn latency(&self, key: &str, since: DateTime<Local>) -> &[LatencySample] {
+        let samples = self.latency.get(key).map(Vec::as_slice).unwrap_or(&[]);
+        let skip = samples.partition_point(|s| s.at < since);
+        &samples[skip..]
+    }
+
+    // 可用时结束进行中的故障，不可用且没有进行中的故障时新开一条
+    fn track_incident(&mut self, key: &str, at: DateTime<Local>, up: bool) {
+        let last_seen = self.spans(key).last().map(|s| s.end);
+        if up {
+            let Some(incident) = self
+                .incidents
+                .get_mut(key)
+                .and_then(|list| list.last_mut())
+                .filter(|i| i.end.is_none())
+            else {
+                return;
+            };
+            // 程序没有运行期间恢复的，按最后一次检查的时间结束
+            let end = match last_seen {
+                Some(seen) if at - seen > Duration::minutes(MAX_GAP_MINUTES) => seen,
+                _ => at,
+            };
+            incident.end = Some(end.max(incident.start));
+            return;
+        }
+        let list = self.incidents.entry(key.to_string()).or_default();
+        if list.last().is_some_and(|i| i.end.is_none()) {
+            return;
+        }
+        list.push(Incident {
+            start: at,
+            end: None,
+            cause: String::new(),
+        });
+        let excess = list.len().saturating_sub(MAX_INCIDENTS);
+        list.drain(..excess);
+    }
+
+    // 删除服务器时清除它的历史
+    pub fn remove(&mut self, key: &str) {
+        self.series.remove(key);
+        self.incidents.remove(key);
+        self.latency.remove(key);
+    }
+
+    // 把 from 的记录移到 to 下，to 已有记录时不动；有移动时返回 true
+    pub fn rename(&mut self, from: &str, to: &str) -> bool {
+        fn take<T>(map: &mut HashMap<String, T>, from: &str, to: &str) -> bool {
+            if map.contains_key(to) {
+                return false;
+            }
+            let Some(value) = map.remove(from) else {
+                return false;
+            };
+            map.insert(to.to_string(), value);
+            true
+        }
+        let series = take(&mut self.series, from, to);
+        let incidents = take(&mut self.incidents, from, to);
+        take(&mut self.latency, from, to) || series || incidents
+    }
+
+    // 按时间先后排列，可以直接填写故障原因
+    pub fn incidents_mut(&mut self, key: &str) -> &mut [Incident] {
+        self.incidents
+            .get_mut(key)
+            .map(Vec::as_mut_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn spans(&self, key: &str) -> &[Span] {
+        self.series.get(key).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    // 统计 since 之后按时长计算的可用率，排除时段不计入；没有数据时返回None
+    pub fn uptime(
+        &self,
+        key: &str,
+        since: DateTime<Local>,
+        exclusions: &[Exclusion],
+    ) -> Option<f64> {
+        self.uptime_between(key, since, None, exclusions)
+    }
+
+    // 同 uptime，until 为None时统计到最后一次检查
+    pub fn uptime_between(
+        &self,
+        key: &str,
+        since: DateTime<Local>,
+        until: Option<DateTime<Local>>,
+        exclusions: &[Exclusion],
+    ) -> Option<f64> {
+        let (mut up, mut total) = (0i64, 0i64);
+        for span in self.spans(key) {
+            let start = span.start.max(since);
+            let end = until.map_or(span.end, |until| span.end.min(until));
+            if end <= start {
+                continue;
+            }
+            let seconds = (end - start).num_seconds() - excluded_seconds(start, end, exclusions);
+            total += seconds;
+            if span.up {
+                up += seconds;
+            }
+        }
+        (total > 0).then(|| up as f64 / total as f64 * 100.0)
+    }
+
+    // 截至 today 最近 days 天每天的可用率，最早的一天在前；当天没有检查记录时为None
+    pub fn daily_uptime(
+        &self,
+        key: &str,
+        today: NaiveDate,
+        days: u32,
+    ) -> Vec<(NaiveDate, Option<f64>)> {
+        let midnight = |day: NaiveDate| {
+            day.and_time(NaiveTime::MIN)
+                .and_local_timezone(Local)
+                .earliest()
+        };
+        (0..days as u64)
+            .rev()
+            .filter_map(|back| today.checked_sub_days(chrono::Days::new(back)))
+            .map(|day| {
+                let uptime = match (midnight(day), day.succ_opt().and_then(midnight)) {
+                    (Some(start), Some(end)) => self.uptime_between(key, start, Some(end), &[]),
+                    _ => None,
+                };
+                (day, uptime)
+            })
+            .collect()
+    }
+
+    // [since, until) 内每次故障的时长，中间没有恢复过的相邻故障段算作一次；排除时段也计入
+    pub fn outages(
+        &self,
+        key: &str,
+        since: DateTime<Local>,
+        until: DateTime<Local>,
+    ) -> Vec<Duration> {
+        let mut outages = Vec::new();
+        let mut current: Option<Duration> = None;
+        for span in self.spans(key) {
+            // 在窗口开始时刚好结束的故障不算
+            if span.end <= since || span.start >= until {
+                continue;
+            }
+            if span.up {
+                outages.extend(current.take());
+            } else {
+                let length = span.end.min(until) - span.start.max(since);
+                current = Some(current.unwrap_or_else(Duration::zero) + length);
+            }
+        }
+        outages.extend(current);
+        outages
+    }
+}
+
+// [start, end) 中落在排除时段内的秒数，按天拆分计算
+fn excluded_seconds(start: DateTime<Local>, end: DateTime<Local>, exclusions: &[Exclusion]) -> i64 {
+    if exclusions.is_empty() {
+        return 0;
+    }
+    let mut excluded = 0;
+    let mut day = start.date_naive();
+    while day <= end.date_naive() {
+        // 当天与 [start, end) 重叠的部分，以当天的秒数表示
+        let from = if day == start.date_naive() {
+            start.time().num_seconds_from_midnight() as i64
+        } else {
+            0
+        };
+        let to = if day == end.date_naive() {
+            end.time().num_seconds_from_midnight() as i64
+        } else {
+            DAY_SECONDS
+        };
+        if from < to {
+            let mut windows: Vec<(i64, i64)> = exclusions
+                .iter()
+                .flat_map(|e| e.windows_on(day))
+                .map(|(a, b)| (a.max(from), b.min(to)))
+                .filter(|(a, b)| a < b)
+                .collect();
+            // 合并重叠的时段
+            windows.sort();
+            let mut covered_until = from;
+            for (a, b) in windows {
+                let a = a.max(covered_until);
+                if b > a {
+                    excluded += b - a;
+                    covered_until = b;
+                }
+            }
+        }
+        match day.succ_opt() {
+            Some(next) => day = next,
+            None => break,
+        }
+    }
+    excluded
+}
+
+// 不计入可用率的时段，例如周末、节假日、每晚的维护窗口
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Exclusion {
+    Weekends,
+    // 某一天全天
+    Holiday(NaiveDate),
+    // 每天的固定时段，结束时间早于开始时间时表示跨过午夜
+    Daily { start: NaiveTime, end: NaiveTime },
+}
+
+impl Exclusion {
+    // 当天被排除的时段，以当天的秒数表示
+    fn windows_on(&self, day: NaiveDate) -> Vec<(i64, i64)> {
+        match self {
+            Exclusion::Weekends if matches!(day.weekday(), Weekday::Sat | Weekday::Sun) => {
+                vec![(0, DAY_SECONDS)]
+            }
+            Exclusion::Holiday(date) if *date == day => vec![(0, DAY_SECONDS)],
+            Exclusion::Daily { start, end } => {
+                let start = start.num_seconds_from_midnight() as i64;
+                let end = end.num_seconds_from_midnight() as i64;
+                if start <= end {
+                    vec![(start, end)]
+                } else {
+                    // 跨过午夜
+                    vec![(0, end), (start, DAY_SECONDS)]
+                }
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    // at 是否落在排除时段内
+    pub fn contains(&self, at: DateTime<Local>) -> bool {
+        let second = at.time().num_seconds_from_midnight() as i64;
+        self.windows_on(at.date_naive())
+            .iter()
+            .any(|(start, end)| (*start..*end).contains(&second))
+    }
+
+    pub fn label(&self) -> String {
+        match self {
+            Exclusion::Weekends => tr!("周末", "Weekends").to_string(),
+            Exclusion::Holiday(date) => tr!("节假日 {}", "Holiday {}", date),
+            Exclusion::Daily { start, end } => tr!(
+                "每天 {}-{}",
+                "Daily {}-{}",
+                start.format("%H:%M"),
+                end.format("%H:%M")
+            ),
+        }
+    }
+}
+
+// 分组的可用率设置，保存在 settings.json
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GroupSla {
+    // 分组名，空字符串表示未分组的服务器
+    pub group: String,
+    pub exclusions: Vec<Exclusion>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spans_split_only_on_status_change() {
+        let mut history = History::default();
+        let start = Local::now() - Duration::hours(1);
+        let minute = |m| start + Duration::minutes(m);
+        history.record("a", minute(0), &ServerStatus::Online);
+        history.record("a", minute(1), &ServerStatus::Degraded);
+        history.record("a", minute(2), &ServerStatus::Offline);
+        history.record("a", minute(3), &ServerStatus::Error(500));
+        history.record("a", minute(4), &ServerStatus::Unchecked);
+
+        let spans = history.spans("a");
+        assert_eq!(spans.len(), 2);
+        assert!(spans[0].up);
+        assert_eq!(spans[0].end, minute(2));
+        assert!(!spans[1].up);
+        assert_eq!(spans[1].end, minute(3));
+    }
+
+    #[test]
+    fn latency_is_bucketed_and_pruned() {
+        let mut history = History::default();
+        let start = DateTime::from_timestamp(1_700_000_000 - 1_700_000_000 % 300, 0)
+            .unwrap()
+            .with_timezone(&Local);
+        let minute = |m| start + Duration::minutes(m);
+        history.record_latency("a", minute(0), 10);
+        history.record_latency("a", minute(2), 30);
+        history.record_latency("a", minute(6), 50);
+
+        let samples = history.latency("a", minute(-1));
+        assert_eq!(samples.len(), 2);
+        assert_eq!(samples[0].avg_ms, 20.0);
+        assert_eq!(samples[0].max_ms, 30);
+        assert_eq!(samples[1].at, minute(5));
+        assert_eq!(history.latency("a", minute(3)).len(), 1);
+
+        history.record_latency("a", minute(60 * 24 * 8), 5);
+        assert_eq!(history.latency("a", minute(0)).len(), 1);
+    }
+
+    #[test]
+    fn uptime_per_day() {
+        let mut history = History::default();
+        let today = NaiveDate::from_ymd_opt(2024, 3, 10).unwrap();
+        let at = |d, h, m| {
+            NaiveDate::from_ymd_opt(2024, 3, d)
+                .unwrap()
+                .and_hms_opt(h, m, 0)
+                .unwrap()
+                .and_local_timezone(Local)
+                .unwrap()
+        };
+        // 3月8日 10:00-10:08 可用，10:08-10:10 故障
+        for m in (0..=6).step_by(2) {
+            history.record("a", at(8, 10, m), &ServerStatus::Online);
+        }
+        history.record("a", at(8, 10, 8), &ServerStatus::Offline);
+        history.record("a", at(8, 10, 10), &ServerStatus::Offline);
+        history.record("a", at(10, 9, 0), &ServerStatus::Online);
+        history.record("a", at(10, 9, 5), &ServerStatus::Online);
+
+        let days = history.daily_uptime("a", today, 90);
+        assert_eq!(days.len(), 90);
+        assert_eq!(days[89], (today, Some(100.0)));
+        assert_eq!(days[88].1, None);
+        assert_eq!(days[87].0, NaiveDate::from_ymd_opt(2024, 3, 8).unwrap());
+        assert_eq!(days[87].1, Some(80.0));
+    }
+
+    #[test]
+    fn daily_exclusion_across_midnight() {
+        let window = Exclusion::Daily {
+            start: NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(1, 0, 0).unwrap(),
+        };
+        let at = |h, m| {
+            NaiveDate::from_ymd_opt(2024, 3, 1)
+                .unwrap()
+                .and_hms_opt(h, m, 0)
+                .unwrap()
+                .and_local_timezone(Local)
+                .unwrap()
+        };
+        assert!(window.contains(at(23, 30)));
+        assert!(window.contains(at(0, 30)));
+        assert!(!window.contains(at(1, 0)));
+        assert!(!window.contains(at(12, 0)));
+    }
+
+    #[test]
+    fn long_gap_is_not_counted() {
+        let mut history = History::default();
+        let start = Local::now() - Duration::hours(3);
+        history.record("a", start, &ServerStatus::Offline);
+        history.record("a", start + Duration::minutes(5), &ServerStatus::Offline);
+        // 程序停了两个小时，恢复后在线
+        let resumed = start + Duration::hours(2);
+        for m in [0, 5, 10, 15] {
+            history.record("a", resumed + Duration::minutes(m), &ServerStatus::Online);
+        }
+
+        let uptime = history.uptime("a", start, &[]).unwrap();
+        assert!((uptime - 75.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn incidents_open_and_close_with_status() {
+        let mut history = History::default();
+        let start = Local::now() - Duration::hours(5);
+        let minute = |m| start + Duration::minutes(m);
+        for (m, status) in [
+            (0, ServerStatus::Online),
+            (5, ServerStatus::Offline),
+            (6, ServerStatus::Error(500)),
+            (8, ServerStatus::Online),
+            (9, ServerStatus::Degraded),
+            (10, ServerStatus::Offline),
+        ] {
+            history.record("a", minute(m), &status);
+        }
+        let incidents = history.incidents_mut("a");
+        assert_eq!(incidents.len(), 2);
+        assert_eq!(incidents[0].start, minute(5));
+        assert_eq!(incidents[0].end, Some(minute(8)));
+        assert_eq!(incidents[1].end, None);
+        assert_eq!(incidents[1].duration(minute(14)), Duration::minutes(4));
+
+        // 程序停了之后才恢复的，按最后一次检查结束
+        history.record("a", minute(12), &ServerStatus::Offline);
+        history.record("a", minute(120), &ServerStatus::Online);
+        assert_eq!(history.incidents_mut("a")[1].end, Some(minute(12)));
+
+        history.incidents_mut("a")[0].cause = "磁盘写满".to_string();
+        let mut saved: History =
+            serde_json::from_str(&serde_json::to_string(&history).unwrap()).unwrap();
+        assert_eq!(saved.incidents_mut("a"), history.incidents_mut("a"));
+        assert!(history.incidents_mut("b").is_empty());
+
+        // 旧的历史文件没有故障记录
+        let mut old: History = serde_json::from_str(r#"{"series": {}}"#).unwrap();
+        assert!(old.incidents_mut("a").is_empty());
+    }
+
+    #[test]
+    fn outages_within_window() {
+        let mut history = History::default();
+        let start = Local::now() - Duration::hours(1);
+        let minute = |m| start + Duration::minutes(m);
+        for (m, status) in [
+            (0, ServerStatus::Online),
+            (5, ServerStatus::Offline),
+            (8, ServerStatus::Error(502)),
+            (9, ServerStatus::Online),
+            (18, ServerStatus::Online),
+            (25, ServerStatus::Offline),
+            (27, ServerStatus::Offline),
+        ] {
+            history.record("a", minute(m), &status);
+        }
+
+        // 离线和错误之间切换算同一次故障
+        let outages = history.outages("a", minute(0), minute(30));
+        assert_eq!(outages, vec![Duration::minutes(4), Duration::minutes(2)]);
+        // 窗口截断故障
+        assert_eq!(
+            history.outages("a", minute(7), minute(26)),
+            vec![Duration::minutes(2), Duration::minutes(1)]
+        );
+        assert!(history.outages("a", minute(9), minute(20)).is_empty());
+        let uptime = history
+            .uptime_between("a", minute(0), Some(minute(10)), &[])
+            .unwrap();
+        assert!((uptime - 60.0).abs() < 0.01);
+    }
+}
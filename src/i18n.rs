@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU8, Ordering};
+
+// 界面语言
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Lang {
+    #[default]
+    Zh,
+    En,
+}
+
+impl Lang {
+    pub const ALL: [Lang; 2] = [Lang::Zh, Lang::En];
+
+    // 语言名称始终用该语言本身显示
+    pub fn label(&self) -> &'static str {
+        match self {
+            Lang::Zh => "中文",
+            Lang::En => "English",
+        }
+    }
+}
+
+static LANG: AtomicU8 = AtomicU8::new(0);
+
+pub fn lang() -> Lang {
+    match LANG.load(Ordering::Relaxed) {
+        1 => Lang::En,
+        _ => Lang::Zh,
+    }
+}
+
+pub fn set_lang(lang: Lang) {
+    LANG.store(lang as u8, Ordering::Relaxed);
+}
+
+// 按当前语言选择文本，中文在前、英文在后：
+//   tr!("保存", "Save")                      -> &'static str
+//   tr!("在线: {} 台", "Online: {}", count)  -> String
+// 带参数时两种语言都要用到全部参数
+macro_rules! tr {
+    ($zh:literal, $en:literal) => {
+        match $crate::i18n::lang() {
+            $crate::i18n::Lang::Zh => $zh,
+            $crate::i18n::Lang::En => $en,
+        }
+    };
+    ($zh:literal, $en:literal, $($arg:tt)*) => {
+        match $crate::i18n::lang() {
+            $crate::i18n::Lang::Zh => format!($zh, $($arg)*),
+            $crate::i18n::Lang::En => format!($en, $($arg)*),
+        }
+    };
+}
+pub(crate) use tr;
@@ -0,0 +1,51 @@
+use eframe::egui;
+use std::path::Path;
+
+use crate::config;
+
+// 窗口图标。打包时可以不带 Icon.png：不启用 embedded-icon 特性编译，
+// 运行时按 设置中指定的文件 → 内置图标 → 可执行文件旁的 Icon.png 的顺序查找，都没有时使用默认图标
+
+const ICON_FILE: &str = "Icon.png";
+
+#[cfg(feature = "embedded-icon")]
+fn embedded_icon() -> Option<&'static [u8]> {
+    Some(include_bytes!("../Icon.png"))
+}
+
+#[cfg(not(feature = "embedded-icon"))]
+fn embedded_icon() -> Option<&'static [u8]> {
+    None
+}
+
+fn decode(data: &[u8], source: &str) -> Option<egui::IconData> {
+    match eframe::icon_data::from_png_bytes(data) {
+        Ok(icon) => Some(icon),
+        Err(e) => {
+            eprintln!("加载图标 {} 失败: {}", source, e);
+            None
+        }
+    }
+}
+
+fn read(path: &Path) -> Option<egui::IconData> {
+    match std::fs::read(path) {
+        Ok(data) => decode(&data, &path.display().to_string()),
+        Err(e) => {
+            eprintln!("无法读取图标 {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+// custom 为设置中指定的PNG文件
+pub fn load(custom: Option<&Path>) -> egui::IconData {
+    custom
+        .and_then(read)
+        .or_else(|| embedded_icon().and_then(|data| decode(data, "内置图标")))
+        .or_else(|| {
+            let path = config::exe_dir().join(ICON_FILE);
+            path.is_file().then(|| read(&path)).flatten()
+        })
+        .unwrap_or_default()
+}
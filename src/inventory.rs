@@ -0,0 +1,539 @@
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+
+use crate::address;
+use crate::i18n::tr;
+use crate::{yaml, Server};
+
+// 从 Ansible 清单（INI/YAML）或 Terraform 输出导入服务器，清单中的分组对应服务器的分组。
+// 同名的服务器更新地址和分组，其他配置保留；清单中已删除的主机不会从列表中移除
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InventorySettings {
+    // 清单文件，例如 inventory.ini、hosts.yml、terraform.tfstate
+    pub path: String,
+    // 清单中只有主机，检查地址按这里的协议和端口生成
+    pub https: bool,
+    pub port: u16,
+}
+
+impl Default for InventorySettings {
+    fn default() -> Self {
+        Self {
+            path: String::new(),
+            https: false,
+            port: 80,
+        }
+    }
+}
+
+impl InventorySettings {
+    pub fn scheme(&self) -> &'static str {
+        if self.https {
+            "https"
+        } else {
+            "http"
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum InventoryFormat {
+    AnsibleIni,
+    AnsibleYaml,
+    // `ansible-inventory --list` 或动态清单脚本输出的JSON
+    AnsibleJson,
+    // terraform.tfstate 或 `terraform output -json` 的输出
+    Terraform,
+}
+
+// 清单中的一台主机
+#[derive(Debug, Clone, PartialEq)]
+pub struct InventoryHost {
+    pub name: String,
+    pub host: String,
+    pub group: String,
+}
+
+// 状态文件带有 terraform_version；terraform output -json 的每个输出都是带 value 的对象
+fn is_terraform(content: &str) -> bool {
+    if content.contains("\"terraform_version\"") {
+        return true;
+    }
+    match serde_json::from_str::<serde_json::Value>(content) {
+        Ok(serde_json::Value::Object(map)) => {
+            !map.is_empty() && map.values().all(|v| v.get("value").is_some())
+        }
+        _ => false,
+    }
+}
+
+// 按扩展名判断格式；JSON 和没有扩展名的文件看内容
+pub fn detect(path: &Path, content: &str) -> InventoryFormat {
+    let ext = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    match ext.as_str() {
+        "tfstate" => InventoryFormat::Terraform,
+        "yml" | "yaml" => InventoryFormat::AnsibleYaml,
+        "ini" | "cfg" => InventoryFormat::AnsibleIni,
+        _ if ext == "json" || content.trim_start().starts_with('{') => {
+            if is_terraform(content) {
+                InventoryFormat::Terraform
+            } else {
+                InventoryFormat::AnsibleJson
+            }
+        }
+        _ if content.lines().any(|l| l.trim_start().starts_with('[')) => {
+            InventoryFormat::AnsibleIni
+        }
+        _ => InventoryFormat::AnsibleYaml,
+    }
+}
+
+pub fn parse(content: &str, format: InventoryFormat) -> Result<Vec<InventoryHost>> {
+    let hosts = match format {
+        InventoryFormat::AnsibleIni => parse_ansible_ini(content),
+        InventoryFormat::AnsibleYaml => parse_ansible_yaml(content)?,
+        InventoryFormat::AnsibleJson => parse_ansible_json(content)?,
+        InventoryFormat::Terraform => parse_terraform(content)?,
+    };
+    if hosts.is_empty() {
+        bail!(tr!("清单中没有主机", "No hosts in the inventory"));
+    }
+    Ok(hosts)
+}
+
+pub fn load(path: &Path) -> Result<Vec<InventoryHost>> {
+    let content = std::fs::read_to_string(path).map_err(|e| {
+        anyhow!(tr!(
+            "无法读取 {}: {}",
+            "Cannot read {}: {}",
+            path.display(),
+            e
+        ))
+    })?;
+    parse(&content, detect(path, &content))
+}
+
+// all 和 ungrouped 是 Ansible 的内置分组，对应未分组
+fn group_name(name: &str) -> String {
+    match name {
+        "all" | "ungrouped" => String::new(),
+        name => name.to_string(),
+    }
+}
+
+// 同一台主机出现在多个分组中时只保留第一次出现的
+fn push_host(hosts: &mut Vec<InventoryHost>, host: InventoryHost) {
+    if !hosts.iter().any(|h| h.name == host.name) {
+        hosts.push(host);
+    }
+}
+
+// 展开 web[01:03].example.com 这样的数字范围，保留前导零
+fn expand_range(pattern: &str) -> Vec<String> {
+    let range = pattern.find('[').and_then(|start| {
+        let end = start + pattern[start..].find(']')?;
+        let (from, to) = pattern[start + 1..end].split_once(':')?;
+        let width = from.len();
+        let (from, to): (u32, u32) = (from.parse().ok()?, to.parse().ok()?);
+        Some((start, end, from, to, width))
+    });
+    match range {
+        Some((start, end, from, to, width)) if from <= to => (from..=to)
+            .map(|n| {
+                format!(
+                    "{}{:0width$}{}",
+                    &pattern[..start],
+                    n,
+                    &pattern[end + 1..],
+                    width = width
+                )
+            })
+            .collect(),
+        _ => vec![pattern.to_string()],
+    }
+}
+
+// INI格式：[分组] 下每行一台主机，可带 ansible_host=地址；[x:vars] 和 [x:children] 忽略
+pub fn parse_ansible_ini(text: &str) -> Vec<InventoryHost> {
+    let mut hosts = Vec::new();
+    // 第一个分组之前的主机属于未分组；None 表示当前段不是主机列表
+    let mut group = Some(String::new());
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+            continue;
+        }
+        if let Some(section) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            group = (!section.contains(':')).then(|| group_name(section.trim()));
+            continue;
+        }
+        let Some(group) = &group else {
+            continue;
+        };
+        let mut fields = line.split_whitespace();
+        let Some(pattern) = fields.next() else {
+            continue;
+        };
+        let ansible_host = fields
+            .filter_map(|f| f.split_once('='))
+            .find(|(k, _)| *k == "ansible_host")
+            .map(|(_, v)| v.trim_matches(['"', '\'']).to_string());
+        for name in expand_range(pattern) {
+            push_host(
+                &mut hosts,
+                InventoryHost {
+                    host: ansible_host.clone().unwrap_or_else(|| name.clone()),
+                    name,
+                    group: group.clone(),
+                },
+            );
+        }
+    }
+    hosts
+}
+
+// 清单中的分组和主机都是映射
+fn reject_list(node: &yaml::Node) -> Result<()> {
+    match node.items.first() {
+        Some(item) => bail!(tr!(
+            "第{}行: 不支持YAML列表",
+            "Line {}: YAML lists are not supported",
+            item.line
+        )),
+        None => Ok(()),
+    }
+}
+
+fn walk_yaml_group(hosts: &mut Vec<InventoryHost>, group: &str, node: &yaml::Node) -> Result<()> {
+    reject_list(node)?;
+    if let Some(list) = node.get("hosts") {
+        reject_list(list)?;
+        for (name, vars) in &list.children {
+            for name in expand_range(name) {
+                let host = vars
+                    .get("ansible_host")
+                    .and_then(|h| h.value.clone())
+                    .unwrap_or_else(|| name.clone());
+                push_host(
+                    hosts,
+                    InventoryHost {
+                        name,
+                        host,
+                        group: group_name(group),
+                    },
+                );
+            }
+        }
+    }
+    if let Some(children) = node.get("children") {
+        reject_list(children)?;
+        for (name, child) in &children.children {
+            walk_yaml_group(hosts, name, child)?;
+        }
+    }
+    Ok(())
+}
+
+// YAML格式：顶层为分组（通常是 all），分组下有 hosts 和 children
+pub fn parse_ansible_yaml(text: &str) -> Result<Vec<InventoryHost>> {
+    let root = yaml::parse(text)?;
+    let mut hosts = Vec::new();
+    for (name, node) in &root.children {
+        walk_yaml_group(&mut hosts, name, node)?;
+    }
+    Ok(hosts)
+}
+
+// JSON格式：顶层为分组，hosts 是主机名列表，地址在 _meta.hostvars 的 ansible_host 中；
+// children 引用的分组也列在顶层，不需要展开
+pub fn parse_ansible_json(text: &str) -> Result<Vec<InventoryHost>> {
+    let json: serde_json::Value = serde_json::from_str(text)?;
+    let groups = json.as_object().ok_or_else(|| {
+        anyhow!(tr!(
+            "不是Ansible的JSON清单",
+            "Not an Ansible JSON inventory"
+        ))
+    })?;
+    let hostvars = json.pointer("/_meta/hostvars");
+    let mut hosts = Vec::new();
+    for (group, node) in groups.iter().filter(|(name, _)| *name != "_meta") {
+        let names = node.get("hosts").and_then(|h| h.as_array());
+        for name in names.into_iter().flatten().filter_map(|n| n.as_str()) {
+            let host = hostvars
+                .and_then(|vars| vars.get(name))
+                .and_then(|vars| vars.get("ansible_host"))
+                .and_then(|h| h.as_str())
+                .unwrap_or(name);
+            push_host(
+                &mut hosts,
+                InventoryHost {
+                    name: name.to_string(),
+                    host: host.to_string(),
+                    group: group_name(group),
+                },
+            );
+        }
+    }
+    Ok(hosts)
+}
+
+// Terraform输出：每个输出是一个分组，值可以是地址、地址列表或 名称→地址 的映射
+pub fn parse_terraform(text: &str) -> Result<Vec<InventoryHost>> {
+    let json: serde_json::Value = serde_json::from_str(text)?;
+    // terraform.tfstate 中输出在 outputs 下，terraform output -json 直接是输出
+    let outputs = json
+        .get("outputs")
+        .unwrap_or(&json)
+        .as_object()
+        .ok_or_else(|| anyhow!(tr!("没有找到Terraform输出", "No Terraform outputs found")))?;
+    let mut hosts = Vec::new();
+    for (output, entry) in outputs {
+        let value = entry.get("value").unwrap_or(entry);
+        let mut add = |name: String, host: &serde_json::Value| {
+            if let Some(host) = host.as_str() {
+                push_host(
+                    &mut hosts,
+                    InventoryHost {
+                        name,
+                        host: host.to_string(),
+                        group: output.clone(),
+                    },
+                );
+            }
+        };
+        match value {
+            serde_json::Value::Array(list) => {
+                for (i, host) in list.iter().enumerate() {
+                    add(format!("{}-{}", output, i + 1), host);
+                }
+            }
+            serde_json::Value::Object(map) => {
+                for (name, host) in map {
+                    add(name.clone(), host);
+                }
+            }
+            host => add(output.clone(), host),
+        }
+    }
+    Ok(hosts)
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct SyncSummary {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+}
+
+impl SyncSummary {
+    pub fn changed(&self) -> bool {
+        self.added + self.updated > 0
+    }
+
+    pub fn message(&self) -> String {
+        tr!(
+            "从清单同步: 新增 {} 台，更新 {} 台，{} 台无变化",
+            "Inventory sync: {} added, {} updated, {} unchanged",
+            self.added,
+            self.updated,
+            self.unchanged
+        )
+    }
+}
+
+// 把清单合并到服务器列表：按名称匹配，已有的更新主机和分组（保留端口和路径），没有的新建
+pub fn sync(
+    servers: &mut Vec<Server>,
+    hosts: &[InventoryHost],
+    settings: &InventorySettings,
+) -> SyncSummary {
+    let mut summary = SyncSummary::default();
+    for entry in hosts {
+        match servers.iter_mut().find(|s| s.name == entry.name) {
+            Some(server) => {
+                if server.ip == entry.host && server.group == entry.group {
+                    summary.unchanged += 1;
+                    continue;
+                }
+                server.url = address::replace_host_port(&server.url, &entry.host, server.port);
+                server.ip = entry.host.clone();
+                server.group = entry.group.clone();
+                summary.updated += 1;
+            }
+            None => {
+                let mut server = Server::new(entry.name.clone(), entry.host.clone(), settings.port);
+                server.url = address::build_url(settings.scheme(), &entry.host, settings.port);
+                server.group = entry.group.clone();
+                servers.push(server);
+                summary.added += 1;
+            }
+        }
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn host(name: &str, host: &str, group: &str) -> InventoryHost {
+        InventoryHost {
+            name: name.to_string(),
+            host: host.to_string(),
+            group: group.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_ini_groups_ranges_and_ansible_host() {
+        let text = "\
+bastion.example.com
+
+[web]
+web[01:02].example.com
+api ansible_host=10.0.0.9 ansible_port=2222
+
+[web:vars]
+http_port=8080
+
+[db]
+db1 ansible_host='10.0.1.1'
+web01.example.com
+
+[prod:children]
+web
+";
+        assert_eq!(
+            parse_ansible_ini(text),
+            vec![
+                host("bastion.example.com", "bastion.example.com", ""),
+                host("web01.example.com", "web01.example.com", "web"),
+                host("web02.example.com", "web02.example.com", "web"),
+                host("api", "10.0.0.9", "web"),
+                host("db1", "10.0.1.1", "db"),
+            ]
+        );
+    }
+
+    #[test]
+    fn parses_yaml_hosts_and_children() {
+        let text = "\
+---
+all:
+  hosts:
+    mail.example.com:
+  children:
+    webservers:
+      hosts:
+        web1:
+          ansible_host: 10.0.0.1 # 主节点
+          http_port: 80
+        web2: {}
+    dbservers:
+      hosts:
+        \"db1\":
+          ansible_host: \"10.0.1.1\"
+";
+        assert_eq!(
+            parse_ansible_yaml(text).unwrap(),
+            vec![
+                host("mail.example.com", "mail.example.com", ""),
+                host("web1", "10.0.0.1", "webservers"),
+                host("web2", "web2", "webservers"),
+                host("db1", "10.0.1.1", "dbservers"),
+            ]
+        );
+        assert!(parse_ansible_yaml("all:\n  hosts:\n    - web1\n").is_err());
+    }
+
+    #[test]
+    fn parses_terraform_outputs() {
+        let state = r#"{"version": 4, "outputs": {
+            "bastion": {"value": "203.0.113.5", "type": "string"},
+            "web": {"value": ["10.0.0.1", "10.0.0.2"]},
+            "db": {"value": {"db-primary": "10.0.1.1", "count": 2}}
+        }}"#;
+        let hosts = parse_terraform(state).unwrap();
+        assert_eq!(
+            hosts,
+            vec![
+                host("bastion", "203.0.113.5", "bastion"),
+                host("db-primary", "10.0.1.1", "db"),
+                host("web-1", "10.0.0.1", "web"),
+                host("web-2", "10.0.0.2", "web"),
+            ]
+        );
+        // terraform output -json 的格式
+        let output = r#"{"web": {"sensitive": false, "value": ["10.0.0.1"]}}"#;
+        assert_eq!(parse_terraform(output).unwrap().len(), 1);
+        assert_eq!(
+            detect(Path::new("terraform.tfstate"), state),
+            InventoryFormat::Terraform
+        );
+        assert_eq!(
+            detect(Path::new("outputs.json"), output),
+            InventoryFormat::Terraform
+        );
+        // 其他JSON按 ansible-inventory --list 的格式读取
+        let ansible = r#"{"_meta": {"hostvars": {"web1": {"ansible_host": "10.0.0.1"}}},
+            "all": {"children": ["web", "ungrouped"]},
+            "web": {"hosts": ["web1", "web2"]}}"#;
+        assert_eq!(
+            detect(Path::new("inventory.json"), ansible),
+            InventoryFormat::AnsibleJson
+        );
+        assert_eq!(
+            parse(ansible, InventoryFormat::AnsibleJson).unwrap(),
+            vec![host("web1", "10.0.0.1", "web"), host("web2", "web2", "web")]
+        );
+        assert_eq!(
+            detect(Path::new("hosts"), "[web]\nweb1\n"),
+            InventoryFormat::AnsibleIni
+        );
+    }
+
+    #[test]
+    fn sync_updates_by_name_and_keeps_port() {
+        let settings = InventorySettings::default();
+        let mut servers = Vec::new();
+        let first = sync(
+            &mut servers,
+            &[
+                host("web1", "10.0.0.1", "web"),
+                host("db1", "10.0.1.1", "db"),
+            ],
+            &settings,
+        );
+        assert_eq!(first.added, 2);
+        assert_eq!(servers[0].url, "http://10.0.0.1:80");
+
+        servers[0].port = 8080;
+        servers[0].url = "http://10.0.0.1:8080/health".to_string();
+        let second = sync(
+            &mut servers,
+            &[
+                host("web1", "10.0.0.7", "web"),
+                host("db1", "10.0.1.1", "db"),
+            ],
+            &settings,
+        );
+        assert_eq!(
+            second,
+            SyncSummary {
+                added: 0,
+                updated: 1,
+                unchanged: 1
+            }
+        );
+        assert_eq!(servers[0].ip, "10.0.0.7");
+        assert_eq!(servers[0].url, "http://10.0.0.7:8080/health");
+    }
+}
@@ -0,0 +1,105 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::Duration;
+
+// 命令行修改服务器列表后通知正在运行的界面重新加载。界面在本机回环地址上监听
+// 一个随机端口，端口号写在配置目录的 ipc.port 中。JSON文件存储时文件监视也会
+// 触发重新加载，SQLite和远程存储只能靠这个通知
+
+const PORT_FILE: &str = "ipc.port";
+const RELOAD: &str = "reload";
+const TIMEOUT: Duration = Duration::from_secs(2);
+
+pub struct IpcListener {
+    rx: Receiver<()>,
+    port_file: PathBuf,
+    port: u16,
+}
+
+impl IpcListener {
+    pub fn start(dir: &Path) -> std::io::Result<Self> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?;
+        let port = listener.local_addr()?.port();
+        let port_file = dir.join(PORT_FILE);
+        std::fs::write(&port_file, port.to_string())?;
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                let Ok(stream) = stream else {
+                    continue;
+                };
+                let _ = stream.set_read_timeout(Some(TIMEOUT));
+                let mut line = String::new();
+                if BufReader::new(stream).read_line(&mut line).is_ok()
+                    && line.trim() == RELOAD
+                    && tx.send(()).is_err()
+                {
+                    // 界面已关闭
+                    return;
+                }
+            }
+        });
+        Ok(Self {
+            rx,
+            port_file,
+            port,
+        })
+    }
+
+    // 每帧调用，收到过重新加载的通知时返回true
+    pub fn poll(&self) -> bool {
+        self.rx.try_iter().count() > 0
+    }
+}
+
+impl Drop for IpcListener {
+    // 同时运行了另一个界面时端口文件已被它覆盖，不能删掉
+    fn drop(&mut self) {
+        if read_port(&self.port_file) == Some(self.port) {
+            let _ = std::fs::remove_file(&self.port_file);
+        }
+    }
+}
+
+fn read_port(path: &Path) -> Option<u16> {
+    std::fs::read_to_string(path).ok()?.trim().parse().ok()
+}
+
+// 通知正在运行的界面重新加载，没有界面在运行时返回false
+pub fn notify_reload(dir: &Path) -> bool {
+    let Some(port) = read_port(&dir.join(PORT_FILE)) else {
+        return false;
+    };
+    let addr = SocketAddr::from((Ipv4Addr::LOCALHOST, port));
+    TcpStream::connect_timeout(&addr, TIMEOUT)
+        .and_then(|mut stream| writeln!(stream, "{}", RELOAD))
+        .is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    #[test]
+    fn reload_notification_reaches_listener() {
+        let dir = TempDir::new("ipc");
+        assert!(!notify_reload(&dir));
+
+        let listener = IpcListener::start(&dir).unwrap();
+        assert!(!listener.poll());
+        assert!(notify_reload(&dir));
+        let received = (0..50).any(|_| {
+            std::thread::sleep(Duration::from_millis(20));
+            listener.poll()
+        });
+        assert!(received);
+
+        // 退出后删除端口文件
+        drop(listener);
+        assert!(!dir.join(PORT_FILE).exists());
+    }
+}
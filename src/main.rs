@@ -1,20 +1,347 @@
 // Windows下隐藏控制台窗口
-#![cfg_attr(target_os = "windows", windows_subsystem = "windows")]
+#![cfg_attr(
+    all(target_os = "windows", feature = "gui"),
+    windows_subsystem = "windows"
+)]
+// 不带界面构建时，只有界面使用的代码和导入会被报告为未使用
+#![cfg_attr(not(feature = "gui"), allow(dead_code, unused_imports))]
 
+#[cfg(feature = "gui")]
+mod add_dialog;
+mod address;
+mod backup;
+mod baseline;
+#[cfg(feature = "gui")]
+mod channels_window;
+#[cfg(feature = "gui")]
+mod chart_window;
+#[cfg(feature = "gui")]
+mod check_editor;
+mod checks;
+mod cli;
+mod clock;
+mod columns;
+#[cfg(feature = "gui")]
+mod command_palette;
+mod config;
+#[cfg(feature = "gui")]
+mod dashboard;
+#[cfg(feature = "gui")]
+mod detail_window;
+mod digest;
+mod discovery;
+#[cfg(feature = "gui")]
+mod discovery_window;
+mod exporter;
+mod filter;
+mod flap;
+#[cfg(feature = "gui")]
+mod fonts;
+mod fuzzy;
+mod headless;
+mod health;
+mod heartbeat;
+mod history;
+mod i18n;
+#[cfg(feature = "gui")]
+mod icon;
+mod inventory;
+mod ipc;
+mod monitor;
+mod mqtt_publish;
+mod netstate;
+mod notify;
+mod proxy;
+mod report;
+mod schedule;
+mod secret;
+mod session;
+mod status_page;
+mod storage;
+mod tags;
+mod template;
+#[cfg(test)]
+mod testing;
+#[cfg(feature = "gui")]
+mod theme;
+mod theme_file;
+#[cfg(feature = "gui")]
+mod toast;
+#[cfg(feature = "gui")]
+mod uptime_window;
+mod watcher;
+mod web;
+mod worker;
+mod yaml;
+
+#[cfg(feature = "gui")]
+use add_dialog::AddDialog;
+use backup::{BackupTarget, MAX_BACKUP_HOURS, MIN_BACKUP_HOURS};
+use baseline::{Baselines, SecurityProfile};
+#[cfg(feature = "gui")]
+use channels_window::ChannelsWindow;
+#[cfg(feature = "gui")]
+use chart_window::ChartWindow;
+use checks::docker::ContainerListing;
+use checks::dual_stack::IpFamily;
+use checks::http::{Challenge, HttpOptions};
+use checks::mapping::StatusRule;
+use checks::metrics::{HostMetrics, MetricsCheck};
+use checks::tunnel::SshTunnel;
+use checks::{transition, CheckKind};
+use clock::{Clock, SystemClock};
+use columns::{ColumnDef, ColumnSource, VersionDrift};
+#[cfg(feature = "gui")]
+use command_palette::{Command, CommandPalette};
+use config::{
+    ConfigLocation, Settings, ViewMode, DEFAULT_PROFILE, MAX_CHECK_INTERVAL_SECS, MAX_STARTUP_SECS,
+    MIN_CHECK_INTERVAL_SECS,
+};
+#[cfg(feature = "gui")]
+use dashboard::Dashboard;
+#[cfg(feature = "gui")]
+use detail_window::DetailWindow;
+use digest::{ChangeLog, DigestEntry};
+use discovery::{DiscoveryResults, DiscoveryRun};
+#[cfg(feature = "gui")]
+use discovery_window::DiscoveryWindow;
+#[cfg(feature = "gui")]
 use eframe::egui;
+use exporter::ExportTarget;
+use filter::{ServerFilter, SortKey};
+use flap::{FlapState, MAX_FLAP_CHANGES, MAX_FLAP_WINDOW_MINS, MIN_FLAP_CHANGES};
+use health::{HealthScore, RecentChecks};
+use heartbeat::{MAX_HEARTBEAT_SECS, MIN_HEARTBEAT_SECS};
+use history::History;
+use i18n::{tr, Lang};
+use ipc::IpcListener;
+use monitor::Monitor;
+use schedule::{ActionKind, DueAction, IntervalTimer, ScheduledAction};
 use serde::{Deserialize, Serialize};
+use session::Session;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
 use std::path::{Path, PathBuf};
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
+use storage::{Storage, StorageKind, StorageSettings};
+use theme_file::ThemeFile;
+#[cfg(feature = "gui")]
+use toast::Toasts;
+#[cfg(feature = "gui")]
+use uptime_window::UptimeWindow;
+use uuid::Uuid;
+use watcher::ConfigWatcher;
 
 // 服务器信息结构体
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct Server {
+    // 稳定的标识，检查结果按它合并；旧配置没有时自动生成
+    #[serde(default = "Uuid::new_v4")]
+    id: Uuid,
     name: String,
     ip: String,
     port: u16,
     status: ServerStatus,
     url: String,
+    // 定时动作（提醒/Webhook）
+    #[serde(default)]
+    actions: Vec<ScheduledAction>,
+    // 检查方式，旧配置默认为HTTP
+    #[serde(default)]
+    check: CheckKind,
+    // 分组，用于可用率统计
+    #[serde(default)]
+    group: String,
+    // 暂停检查，不计入统计
+    #[serde(default)]
+    paused: bool,
+    // 从离线或错误恢复为在线时自动在浏览器中打开
+    #[serde(default)]
+    open_on_recovery: bool,
+    // 备注：负责人、运行的服务、重启方法等
+    #[serde(default)]
+    notes: String,
+    // 标签，可以有多个，例如 prod、gpu
+    #[serde(default)]
+    tags: Vec<String>,
+    // 状态映射规则，把原始检查结果改写为最终状态
+    #[serde(default)]
+    status_rules: Vec<StatusRule>,
+    // 单独设置的请求超时（秒），None 时使用全局的5秒
+    #[serde(default)]
+    timeout_secs: Option<u64>,
+    // HTTP检查的 User-Agent 和认证
+    #[serde(default)]
+    http: HttpOptions,
+    // 经过SSH跳板机检查
+    #[serde(default)]
+    tunnel: SshTunnel,
+    // 主机名有IPv4和IPv6地址时检查哪一个
+    #[serde(default)]
+    ip_family: IpFamily,
+    // 检查得到的运行时状态，不写入配置；编辑或重新加载配置时整体保留
+    #[serde(skip)]
+    state: RuntimeState,
+}
+
+// 服务器的运行时状态
+#[derive(Debug, Clone, Default)]
+struct RuntimeState {
+    // 最近一次检查的附加说明与耗时
+    detail: String,
+    latency_ms: Option<u64>,
+    metrics: Option<HostMetrics>,
+    // 自定义列的值
+    fields: HashMap<String, String>,
+    // 状态最近一次变化的时间
+    last_change: Option<chrono::DateTime<chrono::Local>>,
+    // 最近一次检查完成的时间
+    last_checked: Option<chrono::DateTime<chrono::Local>>,
+    // 本机断网期间状态被冻结，显示的是断网前的结果
+    stale: bool,
+    // 最近一次检查得到的安全配置，以及与基线相比的变化
+    security: Option<SecurityProfile>,
+    security_changes: Vec<String>,
+    // 上次恢复时自动打开浏览器的时间
+    last_auto_open: Option<Instant>,
+    // 最近一次检查因超时失败
+    timed_out: bool,
+    // 所在分组正处于维护时段，每帧根据设置更新
+    maintenance: bool,
+    // 最近一次检查命中了"视为维护中"的状态映射规则
+    rule_maintenance: bool,
+    // 最近一次HTTP错误是因为需要认证或被WAF拦截
+    challenge: Option<Challenge>,
+    // 最近一次HTTP检查的JSON响应不符合断言
+    content_error: bool,
+    // HTTPS证书的到期时间
+    cert_expires: Option<chrono::DateTime<chrono::Local>>,
+    // 最近几次检查的结果，以及据此算出的健康分
+    recent: RecentChecks,
+    health: Option<HealthScore>,
+    // 最近的状态变化，用于抖动检测
+    flap: FlapState,
+    // 当前这次故障中第一次检查失败的时间，恢复后清除
+    down_since: Option<chrono::DateTime<chrono::Local>>,
+    // 在进行中的检查批次里还没有检查完，每帧根据检查任务更新
+    checking: bool,
+}
+
+impl Server {
+    fn new(name: String, ip: String, port: u16) -> Self {
+        let url = address::build_url("http", &ip, port);
+        Self {
+            id: Uuid::new_v4(),
+            name,
+            ip,
+            port,
+            status: ServerStatus::Unchecked,
+            url,
+            actions: Vec::new(),
+            check: CheckKind::Http,
+            group: String::new(),
+            paused: false,
+            open_on_recovery: false,
+            notes: String::new(),
+            tags: Vec::new(),
+            status_rules: Vec::new(),
+            timeout_secs: None,
+            http: HttpOptions::default(),
+            tunnel: SshTunnel::default(),
+            ip_family: IpFamily::Auto,
+            state: RuntimeState::default(),
+        }
+    }
+
+    fn timeout(&self) -> Option<Duration> {
+        self.timeout_secs
+            .map(|secs| Duration::from_secs(secs.clamp(1, MAX_TIMEOUT_SECS)))
+    }
+
+    // 历史记录和安全基线的键，改名或修改URL后仍然是同一台服务器
+    fn history_key(&self) -> String {
+        self.id.to_string()
+    }
+
+    // 旧版本使用的键
+    fn legacy_history_key(&self) -> String {
+        format!("{}|{}", self.name, self.url)
+    }
+
+    // 状态文字，故障中时带上已持续的时间：❌ 离线 · 已持续 2小时13分
+    fn status_label(&self, now: chrono::DateTime<chrono::Local>) -> String {
+        // 需要认证或被WAF拦截时不显示笼统的"错误"
+        let status = match (&self.status, &self.state.challenge) {
+            (ServerStatus::Error(code), Some(challenge)) => {
+                format!("{} ({})", challenge.label(), code)
+            }
+            (ServerStatus::Error(_), None) if self.state.content_error => {
+                tr!("⚠ 内容异常", "⚠ Unexpected content").to_string()
+            }
+            _ => self.status.to_string(),
+        };
+        match self.state.down_since {
+            Some(since) if transition::is_down(&self.status) => tr!(
+                "{} · 已持续 {}",
+                "{} · for {}",
+                status,
+                transition::format_downtime(now - since)
+            ),
+            _ => status,
+        }
+    }
+
+    // 列表中显示的检查目标
+    fn target_label(&self) -> String {
+        let target = match &self.check {
+            CheckKind::Http => self.url.clone(),
+            CheckKind::Ssh(ssh) => format!(
+                "ssh {}@{} $ {}",
+                ssh.user,
+                address::host_port(&self.ip, ssh.port),
+                ssh.command
+            ),
+            CheckKind::Metrics(metrics) => {
+                format!("{} {}", metrics.source.label(), metrics.endpoint(&self.ip))
+            }
+            CheckKind::WinRm(winrm) => format!("WinRM {}", winrm.endpoint(&self.ip)),
+            CheckKind::Systemd(systemd) if systemd.remote => {
+                format!(
+                    "systemd {}@{}: {}",
+                    systemd.user,
+                    self.ip,
+                    systemd.unit_name()
+                )
+            }
+            CheckKind::Systemd(systemd) => {
+                tr!("systemd 本机: {}", "systemd local: {}", systemd.unit_name())
+            }
+            CheckKind::SshBanner(banner) => address::build_url("ssh", &self.ip, banner.port),
+            CheckKind::Database(database) => database.endpoint(&self.ip),
+            CheckKind::Mqtt(mqtt) => mqtt.endpoint(&self.ip),
+            CheckKind::Mail(mail) => mail.endpoint(&self.ip),
+            CheckKind::Elasticsearch(es) => format!("{}/_cluster/health", es.endpoint(&self.ip)),
+            CheckKind::Docker(docker) => format!("{} {}", docker.endpoint(), docker.container),
+            CheckKind::Kubernetes(k8s) => k8s.target(),
+            CheckKind::Snmp(snmp) => snmp.endpoint(&self.ip),
+            CheckKind::Ntp(ntp) => ntp.endpoint(&self.ip),
+            CheckKind::Game(game) => game.endpoint(&self.ip),
+            CheckKind::Command(command) => format!("$ {}", command.command_line(&self.ip)),
+            CheckKind::Udp(udp) => udp.endpoint(&self.ip),
+            CheckKind::HttpSteps(steps) => tr!(
+                "{} · {} 个步骤",
+                "{} · {} steps",
+                self.url,
+                steps.steps.len()
+            ),
+        };
+        if self.tunnel.enabled {
+            tr!("{} (经 {})", "{} (via {})", target, self.tunnel.host.trim())
+        } else {
+            target
+        }
+    }
 }
 
 // 服务器状态枚举
@@ -23,458 +350,4102 @@ enum ServerStatus {
     Unchecked,
     Online,
     Offline,
-    Error(u16), // HTTP状态码
+    Error(u16), // HTTP状态码或命令退出码
+    Degraded,   // 可访问，但主机指标超过阈值
 }
 
-impl ServerStatus {
-    fn to_string(&self) -> String {
+impl fmt::Display for ServerStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
-            ServerStatus::Unchecked => "未检查".to_string(),
-            ServerStatus::Online => "✅ 在线".to_string(),
-            ServerStatus::Offline => "❌ 离线".to_string(),
-            ServerStatus::Error(code) => format!("⚠ 错误 ({})", code),
+            ServerStatus::Unchecked => f.write_str(tr!("未检查", "Unchecked")),
+            ServerStatus::Online => f.write_str(tr!("✅ 在线", "✅ Online")),
+            ServerStatus::Offline => f.write_str(tr!("❌ 离线", "❌ Offline")),
+            ServerStatus::Error(code) => f.write_str(&tr!("⚠ 错误 ({})", "⚠ Error ({})", code)),
+            ServerStatus::Degraded => f.write_str(tr!("🟡 降级", "🟡 Degraded")),
         }
     }
+}
 
+impl ServerStatus {
+    // 颜色可在设置中自定义
+    #[cfg(feature = "gui")]
     fn color(&self) -> egui::Color32 {
+        theme::status_color(self)
+    }
+
+    // 给程序读取的固定标识，不随界面语言变化
+    fn id(&self) -> &'static str {
         match self {
-            ServerStatus::Online => egui::Color32::from_rgb(0, 150, 0),
-            ServerStatus::Offline => egui::Color32::from_rgb(200, 0, 0),
-            ServerStatus::Error(_) => egui::Color32::from_rgb(255, 165, 0),
-            ServerStatus::Unchecked => egui::Color32::GRAY,
+            ServerStatus::Unchecked => "unchecked",
+            ServerStatus::Online => "online",
+            ServerStatus::Offline => "offline",
+            ServerStatus::Error(_) => "error",
+            ServerStatus::Degraded => "degraded",
         }
     }
+
+    fn code(&self) -> Option<u16> {
+        match self {
+            ServerStatus::Error(code) => Some(*code),
+            _ => None,
+        }
+    }
+}
+
+// 单台服务器可以设置的最长请求超时（秒）
+const MAX_TIMEOUT_SECS: u64 = 120;
+
+// 历史记录写入磁盘的间隔
+const HISTORY_SAVE_INTERVAL: Duration = Duration::from_secs(300);
+
+// 编辑对话框正在编辑的条目
+#[cfg(feature = "gui")]
+#[derive(Debug, Clone)]
+enum EditTarget {
+    Existing(usize),
+    // 复制出的新条目，保存时插入到 after 之后；base_url 为打开对话框时的URL
+    Copy { after: usize, base_url: String },
+}
+
+// 离开超过这个时长才在回来时显示变化摘要
+#[cfg(feature = "gui")]
+const AWAY_DIGEST_MIN: Duration = Duration::from_secs(60);
+
+// 删除后可以撤销的时长
+#[cfg(feature = "gui")]
+const UNDO_DELETE_WINDOW: Duration = Duration::from_secs(10);
+
+// 已删除但还可以撤销的服务器，超时后才清除它们的历史记录和安全基线
+#[cfg(feature = "gui")]
+struct DeletedServers {
+    // (原下标, 服务器)，按下标升序
+    servers: Vec<(usize, Server)>,
+    deleted_at: Instant,
+    // "撤销删除"提示的编号
+    toast_id: u64,
 }
 
 // 应用程序状态
+#[cfg(feature = "gui")]
 struct ServerMonitorApp {
-    servers: Arc<Mutex<Vec<Server>>>,
-    last_check: Instant,
-    auto_check_enabled: bool,
-    check_interval: Duration,
+    // 检查、历史记录、通知等与界面无关的部分，无界面模式共用
+    monitor: Monitor,
+    // 列表搜索与筛选
+    filter: ServerFilter,
+    // 配置目录，以及设置窗口中编辑的存储设置
+    location: ConfigLocation,
+    storage_input: StorageSettings,
+    show_settings: bool,
+    settings_error: Option<String>,
+    // 设置窗口中编辑的字体路径
+    font_path_input: String,
+    icon_path_input: String,
+    // 配置方案列表，远程存储时避免每帧都请求
+    profiles: Vec<String>,
+    // 新建配置方案对话框
+    new_profile_name: Option<String>,
+    profile_error: Option<String>,
     // 添加服务器对话框状态
-    show_add_dialog: bool,
-    new_server_name: String,
-    new_server_ip: String,
-    new_server_port: String,
-    // 删除服务器状态
-    selected_server_index: Option<usize>,
-    // HTTP客户端
-    client: reqwest::Client,
+    add_dialog: AddDialog,
+    // 勾选的服务器下标，用于批量操作；列表被整体替换时清空
+    selected: BTreeSet<usize>,
+    // 等待确认删除的服务器下标
+    delete_request: Vec<usize>,
+    // 已删除、还可以撤销的服务器
+    deleted: Option<DeletedServers>,
+    // 批量移动到的分组名
+    bulk_group: String,
+    // 编辑服务器对话框状态: (索引, 编辑中的副本)
+    editing_server: Option<(EditTarget, Server)>,
+    edit_server_port: String,
+    // 编辑对话框中逗号分隔的标签
+    edit_server_tags: String,
+    // 设置中正在重命名的标签：(原名称, 新名称)
+    tag_rename: Option<(String, String)>,
+    // 配置文件热加载
+    config_watcher: Option<ConfigWatcher>,
+    // 配置目录中的主题文件，修改后自动重新加载
+    theme_file: Option<ThemeFile>,
+    theme_error: Option<String>,
+    theme_watcher: Option<ConfigWatcher>,
+    // 接收命令行修改服务器列表后的通知
+    ipc: Option<IpcListener>,
+    // 最近一次读取或写入的配置内容，用于忽略自身保存触发的变化
+    last_config_content: String,
+    toasts: Toasts,
+    // 到期还没有确认的提醒
+    pending_reminders: Vec<DueAction>,
+    // 界面状态和历史记录一起定期保存
+    last_session_save: Instant,
+    uptime_window: UptimeWindow,
+    detail_window: DetailWindow,
+    chart_window: ChartWindow,
+    dashboard: Dashboard,
+    palette: CommandPalette,
+    // 下一帧让搜索框获得焦点（Ctrl+F）
+    focus_search: bool,
+    // 已经应用到主窗口的置顶状态
+    window_on_top: bool,
+    channels_window: ChannelsWindow,
+    // 云资源发现：刷新计时、后台结果、最近一次结果及已经提示过的资源
+    discovery_window: DiscoveryWindow,
+    discovery_timer: IntervalTimer,
+    discovery: DiscoveryResults,
+    discovery_refreshing: bool,
+    discovery_run: Option<DiscoveryRun>,
+    // 从Docker导入时后台列出的容器
+    docker_listing: ContainerListing,
+    docker_importing: bool,
+    discovery_known: BTreeSet<String>,
+    // 卡片视图中折叠的分组、定时任务日志是否展开
+    collapsed_groups: BTreeSet<String>,
+    log_open: bool,
+    // 服务器列表的滚动位置；启动后第一帧恢复上次的位置
+    scroll_offset: f32,
+    restore_scroll: Option<f32>,
+    // 已经提示过版本不一致的 (分组, 列)
+    drift_notified: BTreeSet<(String, String)>,
+    // 最近的状态变化；窗口失去焦点的时间，以及回来后显示的离开期间摘要
+    changes: ChangeLog,
+    unfocused_at: Option<chrono::DateTime<chrono::Local>>,
+    away_digest: Option<(chrono::DateTime<chrono::Local>, Vec<DigestEntry>)>,
 }
 
+#[cfg(feature = "gui")]
 impl Default for ServerMonitorApp {
     fn default() -> Self {
+        let mut app = Self::open(ConfigLocation::resolve(), Arc::new(SystemClock));
+        app.watch_config();
+        app.watch_theme();
+        match IpcListener::start(&app.location.dir) {
+            Ok(listener) => app.ipc = Some(listener),
+            Err(e) => eprintln!("无法接收命令行的通知: {}", e),
+        }
+        app.restore_session();
+        if app.monitor.settings.discovery.active() {
+            app.refresh_discovery();
+        }
+
+        app
+    }
+}
+
+#[cfg(feature = "gui")]
+impl ServerMonitorApp {
+    // 从配置目录加载设置和服务器列表，不监视文件也不接收命令行通知
+    fn open(location: ConfigLocation, clock: Arc<dyn Clock>) -> Self {
+        let settings = Settings::load(&location.settings_path());
+        let (storage, storage_error) = open_storage(&settings.storage, &location);
+        let storage_input = settings.storage.clone();
+        proxy::set_global(&settings.proxy);
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(5))
+            .tls_info(true)
+            .proxy(proxy::global())
+            .build()
+            .unwrap();
+        let font_path_input = settings
+            .font_path
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+        let icon_path_input = settings
+            .icon_path
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+
+        let discovery_timer = IntervalTimer::new(settings.discovery.interval(), clock.instant());
+        let last_session_save = clock.instant();
+        let reports_dir = report::reports_dir(&location);
         let mut app = Self {
-            servers: Arc::new(Mutex::new(Vec::new())),
-            last_check: Instant::now(),
-            auto_check_enabled: true,
-            check_interval: Duration::from_secs(30),
-            show_add_dialog: false,
-            new_server_name: String::new(),
-            new_server_ip: String::new(),
-            new_server_port: String::new(),
-            selected_server_index: None,
-            client: reqwest::Client::builder()
-                .timeout(Duration::from_secs(5))
-                .build()
-                .unwrap(),
+            monitor: Monitor::new(settings, storage, clock, client, reports_dir),
+            location,
+            filter: ServerFilter::default(),
+            discovery_timer,
+            show_settings: false,
+            settings_error: storage_error,
+            font_path_input,
+            icon_path_input,
+            storage_input,
+            profiles: Vec::new(),
+            new_profile_name: None,
+            profile_error: None,
+            add_dialog: AddDialog::default(),
+            selected: BTreeSet::new(),
+            delete_request: Vec::new(),
+            deleted: None,
+            bulk_group: String::new(),
+            editing_server: None,
+            edit_server_port: String::new(),
+            edit_server_tags: String::new(),
+            tag_rename: None,
+            config_watcher: None,
+            theme_file: None,
+            theme_error: None,
+            theme_watcher: None,
+            ipc: None,
+            last_config_content: String::new(),
+            toasts: Toasts::default(),
+            pending_reminders: Vec::new(),
+            last_session_save,
+            uptime_window: UptimeWindow::default(),
+            detail_window: DetailWindow::default(),
+            chart_window: ChartWindow::default(),
+            dashboard: Dashboard::default(),
+            palette: CommandPalette::default(),
+            focus_search: false,
+            window_on_top: false,
+            channels_window: ChannelsWindow::default(),
+            discovery_window: DiscoveryWindow::default(),
+            discovery: DiscoveryResults::default(),
+            discovery_refreshing: false,
+            discovery_run: None,
+            docker_listing: ContainerListing::default(),
+            docker_importing: false,
+            discovery_known: BTreeSet::new(),
+            collapsed_groups: BTreeSet::new(),
+            log_open: false,
+            scroll_offset: 0.0,
+            restore_scroll: None,
+            drift_notified: BTreeSet::new(),
+            changes: ChangeLog::default(),
+            unfocused_at: None,
+            away_digest: None,
         };
 
+        i18n::set_lang(app.monitor.settings.language);
+
+        // 上次使用的方案不存在时回到默认方案
+        app.refresh_profiles();
+        if !app.profiles.contains(&app.monitor.settings.profile) {
+            app.monitor.settings.profile = DEFAULT_PROFILE.to_string();
+        }
+
         // 尝试加载配置文件，如果失败则使用默认配置
-        if let Err(_) = app.load_servers() {
+        if app.load_servers().is_err() {
             app.load_default_servers();
         }
-
         app
     }
-}
 
-impl ServerMonitorApp {
-    // 获取可执行文件所在目录
-    fn get_exe_dir() -> PathBuf {
-        if let Ok(exe_path) = std::env::current_exe() {
-            if let Some(parent) = exe_path.parent() {
-                return parent.to_path_buf();
+    // 当前配置方案的服务器列表文件，用于JSON文件存储时监视外部修改
+    fn config_path(&self) -> std::path::PathBuf {
+        self.location.profile_path(&self.monitor.settings.profile)
+    }
+
+    fn servers_doc(&self) -> String {
+        storage::servers_doc(&self.monitor.settings.profile)
+    }
+
+    fn refresh_profiles(&mut self) {
+        self.profiles = storage::list_profiles(&*self.monitor.storage);
+    }
+
+    // 切换配置方案：中止进行中的检查，保存当前方案后加载新方案
+    fn switch_profile(&mut self, profile: String) {
+        if profile == self.monitor.settings.profile {
+            return;
+        }
+        self.monitor.worker.cancel();
+        if let Err(e) = self.save_servers() {
+            eprintln!("保存配置失败: {}", e);
+        }
+
+        self.monitor.settings.profile = profile;
+        if let Err(e) = self.load_servers() {
+            eprintln!("加载配置方案失败: {}", e);
+            self.monitor.servers.clear();
+            self.last_config_content.clear();
+        }
+        self.save_settings();
+        self.watch_config();
+        self.monitor.check_timer.reset(self.monitor.clock.instant());
+        self.check_all_servers();
+        self.toasts.push(tr!(
+            "已切换到配置方案: {}",
+            "Switched to profile: {}",
+            self.monitor.settings.profile
+        ));
+    }
+
+    // 新建空方案并切换过去
+    fn create_profile(&mut self, name: &str) -> bool {
+        let name = name.trim();
+        match storage::create_profile(&*self.monitor.storage, name) {
+            Ok(_) => {
+                self.profile_error = None;
+                self.refresh_profiles();
+                self.switch_profile(name.to_string());
+                true
+            }
+            Err(e) => {
+                self.profile_error = Some(e.to_string());
+                false
+            }
+        }
+    }
+
+    // 监视当前配置目录中的服务器配置，只有JSON文件存储需要
+    fn watch_config(&mut self) {
+        if self.monitor.settings.storage.kind != StorageKind::JsonFile {
+            self.config_watcher = None;
+            return;
+        }
+        match ConfigWatcher::new(&self.config_path()) {
+            Ok(watcher) => self.config_watcher = Some(watcher),
+            Err(e) => {
+                self.config_watcher = None;
+                eprintln!("无法监视配置文件: {}", e);
+            }
+        }
+    }
+
+    // 读取主题文件，格式有误时保留之前的主题
+    fn load_theme_file(&mut self) {
+        match ThemeFile::load(&self.location.theme_path()) {
+            Ok(theme) => {
+                self.theme_file = theme;
+                self.theme_error = None;
+            }
+            Err(e) => {
+                eprintln!("读取主题文件失败: {}", e);
+                self.theme_error = Some(e);
             }
         }
-        // 如果获取失败，使用当前工作目录
-        std::env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
     }
 
-    // 获取配置文件路径
-    fn get_config_path() -> PathBuf {
-        Self::get_exe_dir().join("servers.json")
+    // 监视配置目录中的主题文件，文件还不存在时创建后也会加载
+    fn watch_theme(&mut self) {
+        self.load_theme_file();
+        self.theme_watcher = ConfigWatcher::new(&self.location.theme_path())
+            .map_err(|e| eprintln!("无法监视主题文件: {}", e))
+            .ok();
+    }
+
+    fn poll_theme_file(&mut self, ctx: &egui::Context) {
+        if !self.theme_watcher.as_mut().is_some_and(|w| w.poll()) {
+            return;
+        }
+        self.load_theme_file();
+        theme::apply(ctx, &self.monitor.settings, self.theme_file.as_ref());
+        match &self.theme_error {
+            None => self
+                .toasts
+                .push(tr!("主题文件已重新加载", "Theme file reloaded")),
+            Some(e) => self
+                .toasts
+                .push(tr!("主题文件有误: {}", "Invalid theme file: {}", e)),
+        }
+    }
+
+    // 恢复上次退出时的界面状态
+    fn restore_session(&mut self) {
+        let session = Session::load(&self.location.session_path());
+        self.filter = session.filter;
+        self.collapsed_groups = session.collapsed_groups;
+        self.log_open = session.log_open;
+        self.uptime_window.open = session.uptime_open;
+        self.restore_scroll = Some(session.scroll_offset);
+        let servers = &self.monitor.servers;
+        self.selected = servers
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| session.selected.contains(&s.history_key()))
+            .map(|(i, _)| i)
+            .collect();
+    }
+
+    fn save_session(&self) {
+        let servers = &self.monitor.servers;
+        let session = Session {
+            filter: self.filter.clone(),
+            collapsed_groups: self.collapsed_groups.clone(),
+            selected: self
+                .selected
+                .iter()
+                .filter_map(|&i| servers.get(i).map(Server::history_key))
+                .collect(),
+            scroll_offset: self.scroll_offset,
+            log_open: self.log_open,
+            uptime_open: self.uptime_window.open,
+        };
+        if let Err(e) = session.save(&self.location.session_path()) {
+            eprintln!("保存界面状态失败: {}", e);
+        }
+    }
+
+    // 保存程序设置
+    fn save_settings(&mut self) {
+        if let Err(e) = self.monitor.settings.save(&self.location.settings_path()) {
+            eprintln!("保存设置失败: {}", e);
+        }
+    }
+
+    // 切换便携模式，成功后监视新位置的配置文件
+    fn set_portable(&mut self, portable: bool) {
+        // 先把当前列表写入旧位置，保证复制过去的是最新内容
+        if let Err(e) = self.save_servers() {
+            eprintln!("保存配置失败: {}", e);
+        }
+        self.save_settings();
+        self.monitor.save_history();
+        self.save_session();
+
+        match self.location.set_portable(portable) {
+            Ok(()) => {
+                // 存储位置随配置目录变化
+                let (storage, error) = open_storage(&self.monitor.settings.storage, &self.location);
+                self.monitor.storage = storage;
+                self.monitor.reports_dir = report::reports_dir(&self.location);
+                self.settings_error = error;
+                self.watch_config();
+                self.toasts.push(tr!(
+                    "配置目录: {}",
+                    "Config directory: {}",
+                    self.location.dir.display()
+                ));
+            }
+            Err(e) => {
+                self.settings_error = Some(tr!(
+                    "切换配置目录失败: {}",
+                    "Failed to switch config directory: {}",
+                    e
+                ))
+            }
+        }
     }
 
     // 加载默认服务器配置
     fn load_default_servers(&mut self) {
-        let mut servers = self.servers.lock().unwrap();
+        let servers = &mut self.monitor.servers;
         servers.clear();
 
         // 添加一个默认的测试服务器
-        servers.push(Server {
-            name: "测试服务".to_string(),
-            ip: "127.0.0.1".to_string(),
-            port: 8080,
-            status: ServerStatus::Unchecked,
-            url: "http://127.0.0.1:8080".to_string(),
-        });
+        servers.push(Server::new(
+            "测试服务".to_string(),
+            "127.0.0.1".to_string(),
+            8080,
+        ));
 
         println!("使用默认服务器配置");
     }
 
-    // 保存服务器配置到文件
-    fn save_servers(&self) -> Result<(), Box<dyn std::error::Error>> {
-        let config_path = Self::get_config_path();
-        let servers = self.servers.lock().unwrap();
-        let json = serde_json::to_string_pretty(&*servers)?;
-        std::fs::write(&config_path, json)?;
-        println!("配置已保存到 {:?}", config_path);
+    // 保存服务器配置
+    fn save_servers(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        let doc = self.servers_doc();
+        let json = serde_json::to_string_pretty(&self.monitor.servers)?;
+        self.monitor.storage.save(&doc, &json)?;
+        self.last_config_content = json;
+        println!("配置已保存到 {}", doc);
         Ok(())
     }
 
-    // 从文件加载服务器配置
+    // 加载服务器配置
     fn load_servers(&mut self) -> Result<(), Box<dyn std::error::Error>> {
-        let config_path = Self::get_config_path();
-        let content = std::fs::read_to_string(&config_path)?;
+        let doc = self.servers_doc();
+        let content = self
+            .monitor
+            .storage
+            .load(&doc)?
+            .ok_or_else(|| tr!("配置方案不存在: {}", "Profile not found: {}", doc))?;
         let loaded_servers: Vec<Server> = serde_json::from_str(&content)?;
 
-        let mut servers = self.servers.lock().unwrap();
-        *servers = loaded_servers;
+        // 列表被整体替换后无法撤销之前的删除
+        self.finalize_deleted();
+        self.monitor.servers = loaded_servers;
+        ensure_unique_ids(&mut self.monitor.servers);
+        self.last_config_content = content;
+        self.selected.clear();
+        if self.monitor.migrate_history_keys() {
+            self.save_servers()?;
+        }
 
-        println!("成功加载配置文件 {:?}", config_path);
+        println!("成功加载配置 {}", doc);
         Ok(())
     }
 
-    // 检查单个服务器状态
-    async fn check_server_status(&self, server: &mut Server) {
-        let response = self.client.get(&server.url).send().await;
-
-        server.status = match response {
-            Ok(resp) => {
-                if resp.status().is_success() {
-                    ServerStatus::Online
-                } else {
-                    ServerStatus::Error(resp.status().as_u16())
-                }
-            }
-            Err(_) => ServerStatus::Offline,
+    // 配置文件在外部被修改后重新加载，保留仍然存在的服务器的当前状态
+    fn reload_servers_from_disk(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
+        let doc = self.servers_doc();
+        let Some(content) = self.monitor.storage.load(&doc)? else {
+            return Ok(false);
         };
-    }
+        if content == self.last_config_content {
+            return Ok(false);
+        }
+        let loaded_servers: Vec<Server> = serde_json::from_str(&content)?;
 
-    // 检查所有服务器状态
-    fn check_all_servers(&self) {
-        let servers = Arc::clone(&self.servers);
-        let client = self.client.clone();
-
-        tokio::spawn(async move {
-            let servers_to_check: Vec<Server> = {
-                let servers_guard = servers.lock().unwrap();
-                servers_guard.clone()
-            };
+        let servers = &mut self.monitor.servers;
+        *servers = merge_servers(servers, loaded_servers);
+        ensure_unique_ids(servers);
+        self.last_config_content = content;
+        self.selected.clear();
+        // 文件中可能又加回了删除的服务器，不再允许撤销
+        self.finalize_deleted();
 
-            let mut futures = Vec::new();
+        println!("配置已变化，重新加载 {}", doc);
+        Ok(true)
+    }
 
-            for mut server in servers_to_check {
-                let client_clone = client.clone();
+    // 处理配置文件变化，以及命令行添加或移除服务器后的通知
+    fn poll_config_changes(&mut self) {
+        let changed = self
+            .config_watcher
+            .as_mut()
+            .map(|w| w.poll())
+            .unwrap_or(false);
+        let notified = self.ipc.as_ref().is_some_and(|ipc| ipc.poll());
+        let changed = changed || notified;
+        if !changed {
+            return;
+        }
 
-                let future = async move {
-                    let response = client_clone.get(&server.url).send().await;
-                    server.status = match response {
-                        Ok(resp) => {
-                            if resp.status().is_success() {
-                                ServerStatus::Online
-                            } else {
-                                ServerStatus::Error(resp.status().as_u16())
-                            }
-                        }
-                        Err(_) => ServerStatus::Offline,
-                    };
-                    server
-                };
+        match self.reload_servers_from_disk() {
+            Ok(true) => self
+                .toasts
+                .push(tr!("配置已重新加载", "Configuration reloaded")),
+            Ok(false) => {}
+            // 文件可能正在写入中，等待下一次变化事件
+            Err(e) => eprintln!("重新加载配置失败: {}", e),
+        }
+    }
 
-                futures.push(future);
-            }
+    // 检查所有服务器状态
+    fn check_all_servers(&mut self) {
+        self.monitor.check_servers(None);
+    }
+
+    // 立即重新生成上一周期的SLA报告
+    fn generate_report(&mut self) {
+        match self.monitor.generate_report(true) {
+            Ok(Some(path)) => self.toasts.push(tr!(
+                "📑 SLA报告已保存到 {}",
+                "📑 SLA report saved to {}",
+                path.display()
+            )),
+            Ok(None) => {}
+            Err(e) => self.toasts.push(tr!(
+                "生成SLA报告失败: {}",
+                "Failed to generate SLA report: {}",
+                e
+            )),
+        }
+    }
 
-            // 并发执行所有检查
-            let results = futures::future::join_all(futures).await;
+    // 导出静态状态页到设置中的路径
+    fn export_status_page(&mut self) {
+        let path = self
+            .monitor
+            .settings
+            .status_page_path
+            .clone()
+            .unwrap_or_else(|| status_page::default_path(&self.location));
+        let html = status_page::render(
+            &self.monitor.servers,
+            &self.monitor.history,
+            &self.monitor.settings,
+            self.monitor.clock.now(),
+        );
+        match status_page::export(&path, &html) {
+            Ok(()) => self.toasts.push(tr!(
+                "📄 状态页已导出到 {}",
+                "📄 Status page exported to {}",
+                path.display()
+            )),
+            Err(e) => self.toasts.push(tr!(
+                "导出状态页失败: {}",
+                "Failed to export status page: {}",
+                e
+            )),
+        }
+    }
 
-            // 更新结果
-            let mut servers_guard = servers.lock().unwrap();
-            for (i, result) in results.into_iter().enumerate() {
-                if i < servers_guard.len() {
-                    servers_guard[i].status = result.status;
+    // 执行命令面板中选中的命令
+    fn run_command(&mut self, command: Command) {
+        match command {
+            Command::OpenServer(index) => {
+                if let Some(server) = self.monitor.servers.get(index) {
+                    self.detail_window.open(server, &self.monitor.client);
                 }
             }
-        });
+            Command::CheckServer(index) => {
+                self.monitor.check_servers(Some(&BTreeSet::from([index])))
+            }
+            Command::CheckAll => {
+                self.check_all_servers();
+                self.monitor.check_timer.reset(self.monitor.clock.instant());
+            }
+            Command::ToggleStatus(status) => self.filter.toggle_status(status),
+            Command::ClearFilters => self.filter.clear(),
+            Command::MuteAlerts => {
+                self.monitor.muted_until =
+                    Some(self.monitor.clock.now() + chrono::Duration::hours(1));
+            }
+            Command::UnmuteAlerts => self.monitor.muted_until = None,
+            Command::AddServer => self.add_dialog.open(),
+            Command::OpenSettings => self.show_settings = true,
+            Command::OpenUptime => self.uptime_window.open = true,
+            Command::OpenChannels => self.channels_window.open = true,
+            Command::OpenDiscovery => self.discovery_window.open = true,
+            Command::ExportStatusPage => self.export_status_page(),
+            Command::ToggleDashboard => self.dashboard.open = !self.dashboard.open,
+            Command::FocusSearch => self.focus_search = true,
+            Command::ToggleAlwaysOnTop => {
+                self.monitor.settings.always_on_top = !self.monitor.settings.always_on_top;
+                self.save_settings();
+            }
+            Command::DeleteSelected => {
+                let selected: Vec<usize> = self.selected.iter().copied().collect();
+                self.request_delete(selected)
+            }
+        }
     }
 
-    // 添加新服务器
-    fn add_server(&mut self) {
-        if !self.new_server_name.is_empty() && !self.new_server_ip.is_empty() {
-            if let Ok(port) = self.new_server_port.parse::<u16>() {
-                let server = Server {
-                    name: self.new_server_name.clone(),
-                    ip: self.new_server_ip.clone(),
-                    port,
-                    status: ServerStatus::Unchecked,
-                    url: format!("http://{}:{}", self.new_server_ip, port),
-                };
-
-                self.servers.lock().unwrap().push(server);
+    // 记录主窗口的大小和位置，退出时随设置一起保存；最小化或最大化时保留之前的值。
+    // 同时应用置顶设置
+    fn track_window(&mut self, ctx: &egui::Context) {
+        let (inner, outer, maximized, minimized) = ctx.input(|i| {
+            let viewport = i.viewport();
+            (
+                viewport.inner_rect,
+                viewport.outer_rect,
+                viewport.maximized.unwrap_or(false),
+                viewport.minimized.unwrap_or(false),
+            )
+        });
+        // 置顶设置变化后（包括启动时）通知窗口
+        if self.window_on_top != self.monitor.settings.always_on_top {
+            self.window_on_top = self.monitor.settings.always_on_top;
+            let level = if self.window_on_top {
+                egui::WindowLevel::AlwaysOnTop
+            } else {
+                egui::WindowLevel::Normal
+            };
+            ctx.send_viewport_cmd(egui::ViewportCommand::WindowLevel(level));
+        }
+        let window = &mut self.monitor.settings.window;
+        if minimized {
+            return;
+        }
+        window.maximized = maximized;
+        if maximized {
+            return;
+        }
+        if let Some(rect) = inner {
+            window.size = Some([rect.width(), rect.height()]);
+        }
+        if let Some(rect) = outer {
+            window.position = Some([rect.min.x, rect.min.y]);
+        }
+    }
 
-                // 清空输入框
-                self.new_server_name.clear();
-                self.new_server_ip.clear();
-                self.new_server_port.clear();
-                self.show_add_dialog = false;
+    // 全局快捷键；Del 只在没有输入框获得焦点时生效，避免删除文字时删掉服务器
+    fn handle_shortcuts(&mut self, ctx: &egui::Context) {
+        use egui::{Key, KeyboardShortcut, Modifiers};
+        let shortcuts = [
+            (KeyboardShortcut::new(Modifiers::COMMAND, Key::K), None),
+            (
+                KeyboardShortcut::new(Modifiers::NONE, Key::F5),
+                Some(Command::CheckAll),
+            ),
+            (
+                KeyboardShortcut::new(Modifiers::COMMAND, Key::N),
+                Some(Command::AddServer),
+            ),
+            (
+                KeyboardShortcut::new(Modifiers::COMMAND, Key::F),
+                Some(Command::FocusSearch),
+            ),
+        ];
+        for (shortcut, command) in shortcuts {
+            if !ctx.input_mut(|i| i.consume_shortcut(&shortcut)) {
+                continue;
+            }
+            match command {
+                Some(command) => {
+                    self.palette.open = false;
+                    self.run_command(command);
+                }
+                None => self.palette.toggle(),
             }
         }
+        let delete = KeyboardShortcut::new(Modifiers::NONE, Key::Delete);
+        if !ctx.wants_keyboard_input()
+            && !self.selected.is_empty()
+            && ctx.input_mut(|i| i.consume_shortcut(&delete))
+        {
+            self.run_command(Command::DeleteSelected);
+        }
     }
 
-    // 删除服务器
-    fn remove_server(&mut self, index: usize) {
-        let mut servers = self.servers.lock().unwrap();
-        if index < servers.len() {
-            servers.remove(index);
+    // 打开编辑对话框
+    fn start_edit_server(&mut self, index: usize) {
+        let servers = &self.monitor.servers;
+        if let Some(server) = servers.get(index) {
+            self.edit_server_port = server.port.to_string();
+            self.edit_server_tags = server.tags.join(", ");
+            self.editing_server = Some((EditTarget::Existing(index), server.clone()));
         }
     }
 
-    // 获取统计信息
-    fn get_stats(&self) -> (usize, usize, usize) {
-        let servers = self.servers.lock().unwrap();
-        let total = servers.len();
-        let online = servers
+    // 以已有条目为模板新建：同一IP的下一个未使用的端口，并打开编辑对话框
+    fn start_duplicate_server(&mut self, index: usize) {
+        let servers = &self.monitor.servers;
+        let Some(original) = servers.get(index) else {
+            return;
+        };
+        let mut port = original.port;
+        while servers
             .iter()
-            .filter(|s| s.status == ServerStatus::Online)
-            .count();
-        let offline = total - online;
-        (total, online, offline)
-    }
-}
-
-impl eframe::App for ServerMonitorApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        // 自动检查逻辑
-        if self.auto_check_enabled && self.last_check.elapsed() >= self.check_interval {
-            self.check_all_servers();
-            self.last_check = Instant::now();
+            .any(|s| s.ip == original.ip && s.port == port)
+        {
+            port = port.wrapping_add(1);
+            if port == original.port {
+                break;
+            }
         }
+        // 沿用所有设置，只换掉ID、名称、端口和检查状态
+        let mut copy = original.clone();
+        copy.id = Uuid::new_v4();
+        copy.name = tr!("{} (副本)", "{} (copy)", original.name);
+        copy.port = port;
+        copy.url = address::replace_host_port(&original.url, &original.ip, port);
+        copy.status = ServerStatus::Unchecked;
+        copy.state = RuntimeState::default();
+        self.edit_server_tags = original.tags.join(", ");
+        self.edit_server_port = port.to_string();
+        self.editing_server = Some((
+            EditTarget::Copy {
+                after: index,
+                base_url: copy.url.clone(),
+            },
+            copy,
+        ));
+    }
 
-        // 主窗口
-        egui::CentralPanel::default().show(ctx, |ui| {
-            ui.heading("🖥 服务器状态监控");
-            ui.separator();
-
-            // 统计信息
-            let (total, online, offline) = self.get_stats();
-            ui.horizontal(|ui| {
-                ui.label(format!("总计: {} 台服务器", total));
-                ui.separator();
-                ui.colored_label(
-                    egui::Color32::from_rgb(0, 150, 0),
-                    format!("在线: {} 台", online),
-                );
-                ui.separator();
-                ui.colored_label(
-                    if offline == 0 {
-                        egui::Color32::from_rgb(100, 100, 100) // 黑灰色
-                    } else {
-                        egui::Color32::from_rgb(200, 0, 0) // 红色
-                    },
-                    format!("离线: {} 台", offline),
-                );
-            });
-
-            ui.separator();
-
-            // 控制按钮
-            ui.horizontal(|ui| {
-                if ui.button("🔄 立即检查").clicked() {
-                    self.check_all_servers();
-                    self.last_check = Instant::now();
+    // 保存编辑结果，端口无效时保持对话框打开
+    fn apply_edit_server(&mut self) {
+        let Ok(port) = self.edit_server_port.parse::<u16>() else {
+            return;
+        };
+        let Some((target, mut edited)) = self.editing_server.take() else {
+            return;
+        };
+        edited.tags = tags::parse_tags(&self.edit_server_tags);
+        // IPv6地址可能带着方括号粘贴进来
+        edited.ip = edited.ip.trim().trim_matches(['[', ']']).to_string();
+        let index = match target {
+            EditTarget::Existing(index) => index,
+            EditTarget::Copy { after, base_url } => {
+                // 复制的条目没有检查状态，只需要处理URL
+                match address::parse_address(&edited.url) {
+                    Some(parsed) if edited.url != base_url => {
+                        edited.url = with_scheme(&edited.url, &parsed);
+                        edited.ip = parsed.host;
+                        edited.port = parsed.port;
+                    }
+                    _ => {
+                        edited.port = port;
+                        edited.url = address::replace_host_port(&base_url, &edited.ip, port);
+                    }
                 }
-
-                if ui.button("➕ 添加服务器").clicked() {
-                    self.show_add_dialog = true;
+                let servers = &mut self.monitor.servers;
+                let at = (after + 1).min(servers.len());
+                servers.insert(at, edited);
+                // 下标已变化
+                self.selected.clear();
+                if let Err(e) = self.save_servers() {
+                    eprintln!("保存配置失败: {}", e);
                 }
-
-                if ui.button("💾 保存配置").clicked() {
-                    if let Err(e) = self.save_servers() {
-                        eprintln!("保存配置失败: {}", e);
+                return;
+            }
+        };
+        {
+            let servers = &mut self.monitor.servers;
+            if let Some(server) = servers.get_mut(index) {
+                // 直接修改了URL时以URL为准，否则把新的IP和端口替换进原URL，保留路径和查询参数
+                match address::parse_address(&edited.url) {
+                    Some(parsed) if edited.url != server.url => {
+                        edited.url = with_scheme(&edited.url, &parsed);
+                        edited.ip = parsed.host;
+                        edited.port = parsed.port;
+                    }
+                    _ => {
+                        edited.port = port;
+                        edited.url = address::replace_host_port(&server.url, &edited.ip, port);
                     }
                 }
+                // 保留最新的检查状态
+                edited.status = server.status.clone();
+                edited.state = std::mem::take(&mut server.state);
+                *server = edited;
+            }
+        }
+        if let Err(e) = self.save_servers() {
+            eprintln!("保存配置失败: {}", e);
+        }
+    }
 
-                if ui.button("📁 加载配置").clicked() {
-                    if let Err(e) = self.load_servers() {
-                        eprintln!("加载配置失败: {}", e);
-                    }
+    // 拖动排序：把 from 处的服务器移动到 to 的位置并保存
+    fn move_server(&mut self, from: usize, to: usize) {
+        {
+            let servers = &mut self.monitor.servers;
+            if from >= servers.len() || to >= servers.len() || from == to {
+                return;
+            }
+            let server = servers.remove(from);
+            servers.insert(to, server);
+        }
+        // 下标已变化
+        self.selected.clear();
+        if let Err(e) = self.save_servers() {
+            eprintln!("保存配置失败: {}", e);
+        }
+    }
+
+    // 删除服务器，从下标大的开始删除以免影响其他下标；删除的服务器先放入撤销缓冲区
+    fn remove_servers(&mut self, mut indices: Vec<usize>) {
+        // 同一时间只保留最近一次删除
+        self.finalize_deleted();
+        indices.sort_unstable();
+        indices.dedup();
+        let mut removed = Vec::new();
+        {
+            let servers = &mut self.monitor.servers;
+            for index in indices.into_iter().rev() {
+                if index < servers.len() {
+                    removed.push((index, servers.remove(index)));
                 }
+            }
+        }
+        self.selected.clear();
+        if removed.is_empty() {
+            return;
+        }
+        removed.reverse();
+        // 立即保存，否则重新加载配置时删除的服务器会回来；撤销时再写回
+        if let Err(e) = self.save_servers() {
+            eprintln!("保存配置失败: {}", e);
+        }
 
-                ui.checkbox(&mut self.auto_check_enabled, "自动检查 (30秒)");
-            });
+        let text = match removed.as_slice() {
+            [(_, server)] => tr!("已删除 {}", "Deleted {}", server.name),
+            _ => tr!("已删除 {} 台服务器", "Deleted {} servers", removed.len()),
+        };
+        let toast_id =
+            self.toasts
+                .push_with_action(text, tr!("撤销删除", "Undo"), UNDO_DELETE_WINDOW);
+        self.deleted = Some(DeletedServers {
+            servers: removed,
+            deleted_at: self.monitor.clock.instant(),
+            toast_id,
+        });
+    }
 
-            ui.separator();
+    // 撤销最近一次删除，按原来的位置放回
+    fn undo_delete(&mut self) {
+        let Some(deleted) = self.deleted.take() else {
+            return;
+        };
+        self.toasts.dismiss(deleted.toast_id);
+        let servers = &mut self.monitor.servers;
+        for (index, server) in deleted.servers {
+            // 已经通过其他方式加回来的不再重复插入
+            if servers.iter().any(|s| s.id == server.id) {
+                continue;
+            }
+            let index = index.min(servers.len());
+            servers.insert(index, server);
+        }
+        ensure_unique_ids(servers);
+        self.selected.clear();
+        if let Err(e) = self.save_servers() {
+            eprintln!("保存配置失败: {}", e);
+        }
+    }
 
-            // 服务器列表
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                let servers = self.servers.lock().unwrap();
+    // 撤销期限已过，清除已删除服务器的历史记录和安全基线
+    fn finalize_deleted(&mut self) {
+        let Some(deleted) = self.deleted.take() else {
+            return;
+        };
+        self.toasts.dismiss(deleted.toast_id);
+        let servers = &self.monitor.servers;
+        let history = &mut self.monitor.history;
+        let baselines = &mut self.monitor.baselines;
+        for (_, server) in deleted.servers {
+            let key = server.history_key();
+            // 列表中还有同一ID的服务器时保留
+            if servers.iter().any(|s| s.history_key() == key) {
+                continue;
+            }
+            history.remove(&key);
+            baselines.remove(&key);
+        }
+        if let Err(e) = baselines.save(&*self.monitor.storage) {
+            eprintln!("保存安全基线失败: {}", e);
+        }
+    }
 
-                for (i, server) in servers.iter().enumerate() {
-                    ui.group(|ui| {
-                        ui.horizontal(|ui| {
-                            ui.vertical(|ui| {
-                                ui.strong(&server.name);
-                                ui.label(&server.url);
-                                ui.colored_label(server.status.color(), server.status.to_string());
-                            });
+    // 记录窗口失去焦点的时间，回来时汇总这段时间内的状态变化
+    fn track_focus(&mut self, ctx: &egui::Context) {
+        let focused = ctx.input(|i| i.viewport().focused).unwrap_or(true);
+        let now = self.monitor.clock.now();
+        match (focused, self.unfocused_at) {
+            (false, None) => self.unfocused_at = Some(now),
+            (true, Some(since)) => {
+                self.unfocused_at = None;
+                let away = (now - since).to_std().unwrap_or_default();
+                if !self.monitor.settings.away_digest || away < AWAY_DIGEST_MIN {
+                    return;
+                }
+                let entries = digest::summarize(&self.changes.since(since));
+                if !entries.is_empty() {
+                    self.away_digest = Some((since, entries));
+                }
+            }
+            _ => {}
+        }
+    }
 
-                            ui.with_layout(
-                                egui::Layout::right_to_left(egui::Align::Center),
-                                |ui| {
-                                    if ui.button("🗑 删除").clicked() {
-                                        self.selected_server_index = Some(i);
-                                    }
-                                    // 淡蓝色主题的打开按钮
-                                    let open_button = egui::Button::new("🌐 打开")
-                                        .fill(egui::Color32::from_rgb(173, 216, 230)); // 淡蓝色背景
-                                    if ui.add(open_button).clicked() {
-                                        if let Err(e) = webbrowser::open(&server.url) {
-                                            eprintln!("无法打开浏览器: {}", e);
+    fn show_away_digest(&mut self, ctx: &egui::Context) {
+        let Some((since, entries)) = &self.away_digest else {
+            return;
+        };
+        let mut open = true;
+        let mut dismissed = false;
+        egui::Window::new(tr!("🕘 离开期间的变化", "🕘 While you were away"))
+            .open(&mut open)
+            .collapsible(false)
+            .default_width(420.0)
+            .show(ctx, |ui| {
+                ui.label(tr!(
+                    "自 {} 以来 {} 台服务器的状态发生过变化:",
+                    "{1} server(s) changed status since {0}:",
+                    since.format("%m-%d %H:%M"),
+                    entries.len()
+                ));
+                egui::ScrollArea::vertical()
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        egui::Grid::new("away_digest")
+                            .num_columns(3)
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for entry in entries {
+                                    ui.strong(&entry.server);
+                                    ui.horizontal_wrapped(|ui| {
+                                        for (i, status) in entry.path.iter().enumerate() {
+                                            if i > 0 {
+                                                ui.label("→");
+                                            }
+                                            ui.colored_label(status.color(), status.to_string());
                                         }
+                                    });
+                                    let mut note = tr!(
+                                        "{} 次变化，最近 {}",
+                                        "{} change(s), last at {}",
+                                        entry.changes(),
+                                        entry.last_at.format("%H:%M")
+                                    );
+                                    if entry.recovered() {
+                                        note.push_str(tr!("（已恢复）", " (recovered)"));
                                     }
-                                },
-                            );
-                        });
+                                    ui.small(note);
+                                    ui.end_row();
+                                }
+                            });
                     });
-                    ui.add_space(5.0);
-                }
+                dismissed = ui.button(tr!("知道了", "Got it")).clicked();
             });
-        });
+        if !open || dismissed {
+            self.away_digest = None;
+        }
+    }
 
-        // 添加服务器对话框
-        if self.show_add_dialog {
-            egui::Window::new("添加服务器")
-                .collapsible(false)
-                .resizable(false)
-                .show(ctx, |ui| {
-                    ui.label("服务器名称:");
-                    ui.text_edit_singleline(&mut self.new_server_name);
+    // 确认对话框打开时再次请求删除不追加，避免同一台服务器列出两次
+    fn request_delete(&mut self, indices: impl IntoIterator<Item = usize>) {
+        if self.delete_request.is_empty() {
+            self.delete_request.extend(indices);
+        }
+    }
 
-                    ui.label("IP地址:");
-                    ui.text_edit_singleline(&mut self.new_server_ip);
+    // 删除确认对话框
+    fn show_delete_confirm(&mut self, ctx: &egui::Context) {
+        if self.delete_request.is_empty() {
+            return;
+        }
+        let names: Vec<String> = {
+            let servers = &self.monitor.servers;
+            self.delete_request
+                .iter()
+                .filter_map(|&i| servers.get(i).map(|s| s.name.clone()))
+                .collect()
+        };
+        let mut confirmed = None;
+        egui::Window::new(tr!("🗑 删除服务器", "🗑 Delete servers"))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.label(tr!(
+                    "确定删除以下 {} 台服务器?",
+                    "Delete these {} servers?",
+                    names.len()
+                ));
+                // 数量多时只列出前几台
+                for name in names.iter().take(5) {
+                    ui.strong(name);
+                }
+                if names.len() > 5 {
+                    ui.label(tr!("等 {} 台", "and {} more", names.len() - 5));
+                }
+                ui.horizontal(|ui| {
+                    let delete = egui::Button::new(
+                        egui::RichText::new(tr!("删除", "Delete")).color(egui::Color32::WHITE),
+                    )
+                    .fill(ServerStatus::Offline.color());
+                    if ui.add(delete).clicked() {
+                        confirmed = Some(true);
+                    }
+                    if ui.button(tr!("取消", "Cancel")).clicked() {
+                        confirmed = Some(false);
+                    }
+                });
+            });
+        match confirmed {
+            Some(true) => {
+                let indices = std::mem::take(&mut self.delete_request);
+                self.remove_servers(indices);
+            }
+            Some(false) => self.delete_request.clear(),
+            None => {}
+        }
+    }
+
+    // 批量修改勾选的服务器并保存
+    fn update_selected(&mut self, update: impl Fn(&mut Server)) {
+        {
+            let servers = &mut self.monitor.servers;
+            for &i in &self.selected {
+                if let Some(server) = servers.get_mut(i) {
+                    update(server);
+                }
+            }
+        }
+        if let Err(e) = self.save_servers() {
+            eprintln!("保存配置失败: {}", e);
+        }
+    }
+
+    // 勾选服务器后显示的批量操作栏
+    fn show_bulk_bar(&mut self, ui: &mut egui::Ui, visible: &[usize]) {
+        if self.selected.is_empty() {
+            return;
+        }
+        ui.horizontal_wrapped(|ui| {
+            ui.strong(tr!("已选 {} 台", "{} selected", self.selected.len()));
+            if ui.small_button(tr!("全选", "Select all")).clicked() {
+                self.selected.extend(visible.iter().copied());
+            }
+            if ui.small_button(tr!("取消选择", "Clear")).clicked() {
+                self.selected.clear();
+            }
+            ui.separator();
+            if ui.button(tr!("🔄 检查", "🔄 Check")).clicked() {
+                let selected = self.selected.clone();
+                self.monitor.check_servers(Some(&selected));
+            }
+            if ui.button(tr!("⏸ 暂停", "⏸ Pause")).clicked() {
+                self.update_selected(|s| s.paused = true);
+            }
+            if ui.button(tr!("▶ 恢复", "▶ Resume")).clicked() {
+                self.update_selected(|s| s.paused = false);
+                let selected = self.selected.clone();
+                self.monitor.check_servers(Some(&selected));
+            }
+            ui.add(
+                egui::TextEdit::singleline(&mut self.bulk_group)
+                    .hint_text(tr!("分组", "Group"))
+                    .desired_width(80.0),
+            );
+            if ui.button(tr!("移动到分组", "Move to group")).clicked() {
+                let group = self.bulk_group.trim().to_string();
+                self.update_selected(|s| s.group = group.clone());
+            }
+            if ui.button(tr!("🗑 删除", "🗑 Delete")).clicked() {
+                let selected: Vec<usize> = self.selected.iter().copied().collect();
+                self.request_delete(selected);
+            }
+        });
+    }
+
+    // 按状态和分组统计，点击可筛选列表
+    fn show_stats_bar(&mut self, ui: &mut egui::Ui) {
+        let (total, counts, groups) = {
+            let servers = &self.monitor.servers;
+            (
+                servers.len(),
+                filter::status_counts(servers.iter()),
+                filter::group_counts(servers),
+            )
+        };
+        ui.horizontal_wrapped(|ui| {
+            ui.label(tr!("总计: {} 台服务器", "Total: {} servers", total));
+            for (status, count) in counts {
+                ui.separator();
+                let selected = self.filter.statuses.contains(&status);
+                let text = egui::RichText::new(format!("{}: {}", status.label(), count))
+                    .color(theme::filter_color(status));
+                if ui
+                    .selectable_label(selected, text)
+                    .on_hover_text(tr!("点击筛选", "Click to filter"))
+                    .clicked()
+                {
+                    self.filter.toggle_status(status);
+                }
+            }
+        });
+        // 只有一个分组时不需要分组统计
+        if groups.len() < 2 {
+            return;
+        }
+        ui.horizontal_wrapped(|ui| {
+            ui.small(tr!("分组:", "Groups:"));
+            for (group, count, problems) in groups {
+                let selected = self.filter.group.as_ref() == Some(&group);
+                let mut text = format!("{} {}", group_label(&group), count);
+                if problems > 0 {
+                    text.push_str(&format!(" ⚠{}", problems));
+                }
+                let mut text = egui::RichText::new(text).small();
+                if problems > 0 {
+                    text = text.color(ServerStatus::Offline.color());
+                }
+                if ui.selectable_label(selected, text).clicked() {
+                    self.filter.toggle_group(&group);
+                }
+            }
+        });
+    }
+
+    // 暂停或恢复检查，恢复后立即检查这一台
+    fn toggle_paused(&mut self, index: usize) {
+        let resumed = {
+            let servers = &mut self.monitor.servers;
+            let Some(server) = servers.get_mut(index) else {
+                return;
+            };
+            server.paused = !server.paused;
+            !server.paused
+        };
+        if let Err(e) = self.save_servers() {
+            eprintln!("保存配置失败: {}", e);
+        }
+        if resumed {
+            self.monitor.check_servers(Some(&BTreeSet::from([index])));
+        }
+    }
+}
+
+// 合并重新加载的服务器列表：按ID找到原来的条目，手工添加、没有ID的条目按名称和URL匹配；
+// URL未变的沿用当前状态，避免刚检查过的结果被文件中的旧状态覆盖
+fn merge_servers(current: &[Server], loaded: Vec<Server>) -> Vec<Server> {
+    loaded
+        .into_iter()
+        .map(|mut server| {
+            let existing = current.iter().find(|s| s.id == server.id).or_else(|| {
+                current
+                    .iter()
+                    .find(|s| s.name == server.name && s.url == server.url)
+            });
+            let Some(existing) = existing else {
+                return server;
+            };
+            server.id = existing.id;
+            if existing.url == server.url {
+                server.status = existing.status.clone();
+                server.state = existing.state.clone();
+            }
+            server
+        })
+        .collect()
+}
+
+// 手工编辑配置时复制的条目会带着相同的ID，重新生成重复的
+fn ensure_unique_ids(servers: &mut [Server]) {
+    let mut seen = BTreeSet::new();
+    for server in servers.iter_mut() {
+        if !seen.insert(server.id) {
+            server.id = Uuid::new_v4();
+            seen.insert(server.id);
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+impl eframe::App for ServerMonitorApp {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        self.finalize_deleted();
+        self.monitor.save_history();
+        self.save_session();
+        // 保存窗口大小和位置
+        self.save_settings();
+    }
+
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.track_window(ctx);
+        // 检查、通知、心跳、备份、报告和定时任务
+        let updates = self.monitor.tick();
+        for alert in updates.alerts {
+            self.toasts.push(alert);
+        }
+        for change in updates.changes {
+            self.changes.record(change);
+        }
+        self.pending_reminders.extend(updates.reminders);
+        for url in updates.open_urls {
+            if let Err(e) = webbrowser::open(&template::expand(&url)) {
+                eprintln!("无法打开浏览器: {}", e);
+            }
+        }
+        for server in self.monitor.servers.iter_mut() {
+            server.state.checking = self.monitor.worker.is_pending(&server.id);
+        }
+
+        // 云资源发现
+        let now = self.monitor.clock.instant();
+        if self.monitor.settings.discovery.active()
+            && self.discovery_timer.poll(now)
+            && !self.discovery_refreshing
+        {
+            self.refresh_discovery();
+        }
+        self.poll_discovery();
+        self.poll_docker_import();
+
+        // 配置文件和主题文件热加载
+        self.poll_config_changes();
+        self.poll_theme_file(ctx);
+
+        // 定期保存界面状态
+        if now.saturating_duration_since(self.last_session_save) >= HISTORY_SAVE_INTERVAL {
+            self.save_session();
+            self.last_session_save = now;
+        }
+
+        // 版本不一致检测
+        let drift = self.update_version_drift();
+
+        // 主窗口
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading(tr!("🖥 服务器状态监控", "🖥 Server Monitor"));
+            if self.monitor.network_down {
+                egui::Frame::none()
+                    .fill(ServerStatus::Offline.color())
+                    .inner_margin(6.0)
+                    .rounding(4.0)
+                    .show(ui, |ui| {
+                        ui.colored_label(
+                            egui::Color32::WHITE,
+                            tr!(
+                                "⚠ 本机网络断开，状态已冻结，显示的是断网前的检查结果",
+                                "⚠ Local network is down, statuses are frozen at the last results"
+                            ),
+                        );
+                    });
+            }
+            for d in &drift {
+                ui.colored_label(
+                    ServerStatus::Degraded.color(),
+                    tr!(
+                        "⚠ 分组 [{}] 的{}不一致: {}",
+                        "⚠ {1} differs in group [{0}]: {2}",
+                        group_label(&d.group),
+                        d.column,
+                        d.summary()
+                    ),
+                );
+            }
+            ui.separator();
+
+            // 统计信息
+            self.show_stats_bar(ui);
+
+            ui.separator();
+
+            // 控制按钮
+            ui.horizontal(|ui| {
+                // 配置方案
+                let mut selected_profile = None;
+                egui::ComboBox::from_id_source("profile")
+                    .selected_text(format!("📋 {}", profile_label(&self.monitor.settings.profile)))
+                    .show_ui(ui, |ui| {
+                        for profile in self.profiles.clone() {
+                            let current = profile == self.monitor.settings.profile;
+                            if ui
+                                .selectable_label(current, profile_label(&profile))
+                                .clicked()
+                            {
+                                selected_profile = Some(profile);
+                            }
+                        }
+                        ui.separator();
+                        if ui
+                            .selectable_label(false, tr!("➕ 新建方案...", "➕ New profile..."))
+                            .clicked()
+                        {
+                            self.new_profile_name = Some(String::new());
+                        }
+                    });
+                if let Some(profile) = selected_profile {
+                    self.switch_profile(profile);
+                }
+
+                if ui.button(tr!("🔄 立即检查", "🔄 Check now")).clicked() {
+                    self.check_all_servers();
+                    self.monitor.check_timer.reset(self.monitor.clock.instant());
+                }
+                if let Some((done, total)) = self.monitor.worker.progress() {
+                    ui.add(
+                        egui::ProgressBar::new(done as f32 / total.max(1) as f32)
+                            .desired_width(90.0)
+                            .text(format!("{}/{}", done, total)),
+                    )
+                    .on_hover_text(tr!("正在检查", "Checking"));
+                }
+
+                if ui.button(tr!("➕ 添加服务器", "➕ Add server")).clicked() {
+                    self.add_dialog.open();
+                }
+
+                if ui.button(tr!("💾 保存配置", "💾 Save")).clicked() {
+                    if let Err(e) = self.save_servers() {
+                        eprintln!("保存配置失败: {}", e);
+                    }
+                }
+
+                if ui.button(tr!("📁 加载配置", "📁 Load")).clicked() {
+                    if let Err(e) = self.load_servers() {
+                        eprintln!("加载配置失败: {}", e);
+                    }
+                }
+
+                if ui.button(tr!("📊 可用率", "📊 Uptime")).clicked() {
+                    self.uptime_window.open = true;
+                }
+
+                let label = if self.dashboard.open {
+                    tr!("📺 关闭看板", "📺 Close dashboard")
+                } else {
+                    tr!("📺 投屏看板", "📺 Dashboard")
+                };
+                if ui
+                    .button(label)
+                    .on_hover_text(tr!(
+                        "在设置中选择的显示器上全屏显示状态磁贴，按 Esc 关闭",
+                        "Show status tiles full screen on the display chosen in settings; Esc closes it"
+                    ))
+                    .clicked()
+                {
+                    self.dashboard.open = !self.dashboard.open;
+                }
+
+                if ui.button(tr!("🔔 通知", "🔔 Notify")).clicked() {
+                    self.channels_window.open = true;
+                }
+
+                if ui.button(tr!("☁ 云发现", "☁ Cloud")).clicked() {
+                    self.discovery_window.open = true;
+                }
+
+                if ui.button(tr!("⚙ 设置", "⚙ Settings")).clicked() {
+                    self.show_settings = true;
+                }
+
+                if let Some(until) = self.monitor.muted_until.filter(|_| self.monitor.alerts_muted()) {
+                    let label = tr!(
+                        "🔕 静音至 {}",
+                        "🔕 Muted until {}",
+                        until.format("%H:%M")
+                    );
+                    if ui
+                        .button(label)
+                        .on_hover_text(tr!("点击取消静音", "Click to unmute"))
+                        .clicked()
+                    {
+                        self.monitor.muted_until = None;
+                    }
+                }
+
+                let mut changed = ui
+                    .checkbox(
+                        &mut self.monitor.settings.auto_check_enabled,
+                        tr!("自动检查，间隔", "Auto check every"),
+                    )
+                    .changed();
+                let interval = ui
+                    .add_enabled(
+                        self.monitor.settings.auto_check_enabled,
+                        egui::DragValue::new(&mut self.monitor.settings.check_interval_secs)
+                            .range(MIN_CHECK_INTERVAL_SECS..=MAX_CHECK_INTERVAL_SECS)
+                            .custom_formatter(|secs, _| format_interval(secs as u64))
+                            // 输入时按秒数解析
+                            .custom_parser(|text| {
+                                text.trim()
+                                    .trim_end_matches(['秒', 's'])
+                                    .trim()
+                                    .parse()
+                                    .ok()
+                            })
+                            .speed(1.0),
+                    )
+                    .on_hover_text(tr!(
+                        "拖动或双击输入秒数（5秒到1小时）",
+                        "Drag or double-click to enter seconds (5s to 1h)"
+                    ));
+                if interval.changed() {
+                    // 立即生效，从现在重新计时
+                    self.monitor.check_timer.interval = self.monitor.settings.check_interval();
+                    self.monitor.check_timer.reset(self.monitor.clock.instant());
+                }
+                // 拖动过程中不反复写文件
+                changed |= interval.drag_stopped() || (interval.changed() && !interval.dragged());
+                if changed {
+                    self.save_settings();
+                }
+            });
+
+            ui.separator();
+
+            // 定时任务日志
+            let log = egui::CollapsingHeader::new(tr!("📜 定时任务日志", "📜 Scheduled task log"))
+                .default_open(self.log_open)
+                .show(ui, |ui| {
+                    let entries = self.monitor.action_log.entries();
+                    if entries.is_empty() {
+                        ui.label(tr!("暂无记录", "No entries yet"));
+                    }
+                    egui::ScrollArea::vertical()
+                        .id_source("action_log")
+                        .max_height(120.0)
+                        .stick_to_bottom(true)
+                        .show(ui, |ui| {
+                            for entry in entries {
+                                ui.small(format!(
+                                    "{} {}",
+                                    entry.time.format("%m-%d %H:%M:%S"),
+                                    entry.message
+                                ));
+                            }
+                        });
+                });
+            self.log_open = log.openness > 0.5;
+
+            ui.separator();
+
+            // 搜索与筛选
+            ui.horizontal(|ui| {
+                let search = ui.add(
+                    egui::TextEdit::singleline(&mut self.filter.text)
+                        .hint_text(tr!(
+                            "🔍 搜索名称/IP/URL/分组/备注/标签",
+                            "🔍 Search name/IP/URL/group/notes/tags"
+                        ))
+                        .desired_width(160.0),
+                );
+                if std::mem::take(&mut self.focus_search) {
+                    search.request_focus();
+                }
+                // 状态和分组筛选在上方的统计栏中点击
+                for status in self.filter.statuses.clone() {
+                    if ui.small_button(format!("{} ✖", status.label())).clicked() {
+                        self.filter.toggle_status(status);
+                    }
+                }
+                if let Some(group) = self.filter.group.clone() {
+                    if ui
+                        .small_button(format!("{} ✖", group_label(&group)))
+                        .clicked()
+                    {
+                        self.filter.group = None;
+                    }
+                }
+                for tag in self.filter.tags.clone() {
+                    if ui.small_button(format!("#{} ✖", tag)).clicked() {
+                        self.filter.toggle_tag(&tag);
+                    }
+                }
+                if self.filter.is_active() && ui.small_button(tr!("✖ 清除", "✖ Clear")).clicked()
+                {
+                    self.filter.clear();
+                }
+            });
+
+            // 排序
+            ui.horizontal(|ui| {
+                ui.label(tr!("排序:", "Sort:"));
+                let mut sort_changed = false;
+                egui::ComboBox::from_id_source("sort_key")
+                    .selected_text(self.monitor.settings.sort_key.label())
+                    .show_ui(ui, |ui| {
+                        for key in SortKey::ALL {
+                            sort_changed |= ui
+                                .selectable_value(&mut self.monitor.settings.sort_key, key, key.label())
+                                .changed();
+                        }
+                    });
+                let direction = if self.monitor.settings.sort_descending {
+                    tr!("⬇ 降序", "⬇ Descending")
+                } else {
+                    tr!("⬆ 升序", "⬆ Ascending")
+                };
+                if ui.button(direction).clicked() {
+                    self.monitor.settings.sort_descending = !self.monitor.settings.sort_descending;
+                    sort_changed = true;
+                }
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    let view = &mut self.monitor.settings.view_mode;
+                    sort_changed |= ui
+                        .selectable_value(view, ViewMode::Table, tr!("☷ 表格", "☷ Table"))
+                        .changed();
+                    sort_changed |= ui
+                        .selectable_value(view, ViewMode::Wall, tr!("▦ 状态墙", "▦ Wall"))
+                        .changed();
+                    sort_changed |= ui
+                        .selectable_value(view, ViewMode::Cards, tr!("▤ 卡片", "▤ Cards"))
+                        .changed();
+                });
+                if sort_changed {
+                    self.save_settings();
+                }
+            });
+
+            // 服务器列表
+            let mut edit_index = None;
+            let mut detail_index = None;
+            let mut chart_index = None;
+            let mut duplicate_index = None;
+            let mut delete_index = None;
+            let mut moved = None;
+            let mut accept_baseline = None;
+            let mut toggle_pause = None;
+            let mut toggle_select = None;
+            let mut toggle_tag = None;
+            let mut toggle_group = None;
+            let table_view = self.monitor.settings.view_mode == ViewMode::Table;
+            let card_view = self.monitor.settings.view_mode == ViewMode::Cards;
+            // 只有卡片视图按默认顺序显示时才能拖动排序；编辑中禁止拖动，避免编辑对话框的索引失效
+            let can_reorder = card_view
+                && self.monitor.settings.sort_key == SortKey::Manual
+                && self.editing_server.is_none();
+            // 排序和筛选只影响显示顺序，删除/编辑仍使用原始下标
+            let (visible, grouped) = {
+                let servers = &self.monitor.servers;
+                let visible: Vec<usize> = filter::sorted_indices(
+                    servers,
+                    self.monitor.settings.sort_key,
+                    self.monitor.settings.sort_descending,
+                )
+                .into_iter()
+                .filter(|&i| self.filter.matches(&servers[i]))
+                .collect();
+                // 有分组时卡片按分组集中显示，分组可以折叠
+                let grouped = card_view && servers.iter().any(|s| !s.group.is_empty());
+                if grouped {
+                    (filter::group_indices(servers, visible), true)
+                } else {
+                    (visible, false)
+                }
+            };
+            self.show_bulk_bar(ui, &visible);
+            let mut scroll = egui::ScrollArea::vertical();
+            if let Some(offset) = self.restore_scroll.take() {
+                scroll = scroll.vertical_scroll_offset(offset);
+            }
+            let scroll_output = scroll.show(ui, |ui| {
+                let servers = &self.monitor.servers;
+
+                if self.filter.is_active() {
+                    ui.small(tr!(
+                        "显示 {} / {} 台",
+                        "Showing {} / {}",
+                        visible.len(),
+                        servers.len()
+                    ));
+                }
+                if !can_reorder && card_view && servers.len() > 1 {
+                    ui.small(tr!(
+                        "切换到默认顺序后可拖动 ☰ 调整顺序",
+                        "Switch to default order to drag ☰ and reorder"
+                    ));
+                }
+
+                if table_view {
+                    let action = show_server_table(
+                        ui,
+                        servers,
+                        &visible,
+                        &self.selected,
+                        &self.monitor.settings,
+                        &self.filter.tags,
+                        &drift,
+                    );
+                    match action {
+                        Some((i, RowAction::ToggleSelect)) => toggle_select = Some(i),
+                        Some((i, RowAction::Details)) => detail_index = Some(i),
+                        Some((i, RowAction::Chart)) => chart_index = Some(i),
+                        Some((_, RowAction::FilterTag(tag))) => toggle_tag = Some(tag),
+                        Some((i, RowAction::Edit)) => edit_index = Some(i),
+                        Some((i, RowAction::Duplicate)) => duplicate_index = Some(i),
+                        Some((i, RowAction::TogglePause)) => toggle_pause = Some(i),
+                        Some((i, RowAction::Delete)) => delete_index = Some(i),
+                        None => {}
+                    }
+                    return;
+                }
+                if self.monitor.settings.view_mode == ViewMode::Wall {
+                    detail_index = dashboard::show_wall(ui, servers, &visible);
+                    return;
+                }
+
+                let mut current_group = None;
+                for &i in &visible {
+                    let server = &servers[i];
+                    if grouped && current_group != Some(&server.group) {
+                        current_group = Some(&server.group);
+                        let collapsed = self.collapsed_groups.contains(&server.group);
+                        let count = visible
+                            .iter()
+                            .filter(|&&j| servers[j].group == server.group)
+                            .count();
+                        let header = format!(
+                            "{} {} ({})",
+                            if collapsed { "▶" } else { "▼" },
+                            group_label(&server.group),
+                            count
+                        );
+                        if ui
+                            .selectable_label(false, egui::RichText::new(header).strong())
+                            .clicked()
+                        {
+                            toggle_group = Some(server.group.clone());
+                        }
+                    }
+                    if grouped && self.collapsed_groups.contains(&server.group) {
+                        continue;
+                    }
+                    let card = ui.group(|ui| {
+                        // 已暂停的服务器淡化显示
+                        if server.paused {
+                            ui.multiply_opacity(0.5);
+                        }
+                        ui.horizontal(|ui| {
+                            let mut checked = self.selected.contains(&i);
+                            if ui.checkbox(&mut checked, "").changed() {
+                                toggle_select = Some(i);
+                            }
+                            if can_reorder {
+                                ui.dnd_drag_source(egui::Id::new(("server_drag", i)), i, |ui| {
+                                    ui.label("☰");
+                                })
+                                .response
+                                .on_hover_text(tr!("拖动调整顺序", "Drag to reorder"));
+                            }
+                            ui.vertical(|ui| {
+                                ui.horizontal(|ui| {
+                                    if copy_menu_label(
+                                        ui,
+                                        egui::RichText::new(&server.name).strong(),
+                                        server,
+                                    )
+                                    .clicked()
+                                    {
+                                        detail_index = Some(i);
+                                    }
+                                    if !server.group.is_empty() {
+                                        ui.small(format!("[{}]", server.group));
+                                    }
+                                    for tag in &server.tags {
+                                        let selected = self.filter.tags.contains(tag);
+                                        if tag_chip(ui, tag, &self.monitor.settings.tag_colors, selected)
+                                            .clicked()
+                                        {
+                                            toggle_tag = Some(tag.clone());
+                                        }
+                                    }
+                                    if !server.notes.is_empty() {
+                                        ui.small("📝").on_hover_text(&server.notes);
+                                    }
+                                    if server.paused {
+                                        ui.small(tr!("⏸ 已暂停", "⏸ Paused"));
+                                    } else if server.state.maintenance {
+                                        ui.small(tr!("🔧 维护中", "🔧 Maintenance"));
+                                    } else if server.state.flap.is_flapping() {
+                                        ui.small(tr!("🔁 抖动", "🔁 Flapping"));
+                                    }
+                                });
+                                if copy_menu_label(ui, server.target_label(), server).clicked() {
+                                    detail_index = Some(i);
+                                }
+                                ui.horizontal(|ui| {
+                                    ui.colored_label(
+                                        server.status.color(),
+                                        server.status_label(self.monitor.clock.now()),
+                                    );
+                                    if server.state.stale {
+                                        ui.small(tr!("(已过期)", "(stale)"));
+                                    }
+                                    if server.state.checking {
+                                        ui.spinner();
+                                        ui.small(tr!("检查中…", "Checking…"));
+                                    }
+                                    if let Some(ms) = server.state.latency_ms {
+                                        ui.small(format!("{}ms", ms));
+                                    }
+                                    if let Some(health) = &server.state.health {
+                                        show_health_badge(ui, health);
+                                    }
+                                    if let Some(changed) = server.state.last_change {
+                                        ui.small(tr!(
+                                            "变化于 {}",
+                                            "changed at {}",
+                                            changed.format("%H:%M:%S")
+                                        ));
+                                    }
+                                });
+                                if let (Some(metrics), CheckKind::Metrics(check)) =
+                                    (&server.state.metrics, &server.check)
+                                {
+                                    show_metric_gauges(ui, metrics, check);
+                                } else if !server.state.detail.is_empty() {
+                                    ui.small(&server.state.detail);
+                                }
+                                let fields: Vec<String> = self
+                                    .monitor
+                                    .settings
+                                    .columns
+                                    .iter()
+                                    .filter_map(|c| {
+                                        let value = server.state.fields.get(&c.title)?;
+                                        let mark = if columns::is_drifted(&drift, server, &c.title)
+                                        {
+                                            " ⚠"
+                                        } else {
+                                            ""
+                                        };
+                                        Some(format!("{}: {}{}", c.title, value, mark))
+                                    })
+                                    .collect();
+                                if !fields.is_empty() {
+                                    ui.small(fields.join(" · "));
+                                }
+                                if !server.state.security_changes.is_empty() {
+                                    ui.horizontal_wrapped(|ui| {
+                                        ui.colored_label(
+                                            ServerStatus::Degraded.color(),
+                                            tr!(
+                                                "🔒 安全配置变化: {}",
+                                                "🔒 Security changed: {}",
+                                                server.state.security_changes.join("; ")
+                                            ),
+                                        );
+                                        if ui
+                                            .small_button(tr!("接受", "Accept"))
+                                            .on_hover_text(tr!(
+                                                "把当前配置作为新的基线",
+                                                "Use the current settings as the new baseline"
+                                            ))
+                                            .clicked()
+                                        {
+                                            accept_baseline = Some(i);
+                                        }
+                                    });
+                                }
+                                let scheduled: Vec<String> = server
+                                    .actions
+                                    .iter()
+                                    .filter(|a| a.enabled)
+                                    .map(|a| a.time_label())
+                                    .collect();
+                                if !scheduled.is_empty() {
+                                    ui.small(format!("⏰ {}", scheduled.join(", ")));
+                                }
+                            });
+
+                            ui.with_layout(
+                                egui::Layout::right_to_left(egui::Align::Center),
+                                |ui| {
+                                    if ui.button(tr!("🗑 删除", "🗑 Delete")).clicked() {
+                                        delete_index = Some(i);
+                                    }
+                                    if ui.button(tr!("✏ 编辑", "✏ Edit")).clicked() {
+                                        edit_index = Some(i);
+                                    }
+                                    if ui.button(tr!("📋 复制", "📋 Duplicate")).clicked() {
+                                        duplicate_index = Some(i);
+                                    }
+                                    if ui.button(tr!("📈 图表", "📈 Chart")).clicked() {
+                                        chart_index = Some(i);
+                                    }
+                                    let pause_label = if server.paused {
+                                        tr!("▶ 恢复", "▶ Resume")
+                                    } else {
+                                        tr!("⏸ 暂停", "⏸ Pause")
+                                    };
+                                    if ui.button(pause_label).clicked() {
+                                        toggle_pause = Some(i);
+                                    }
+                                    // 淡蓝色主题的打开按钮
+                                    let open_button = egui::Button::new(tr!("🌐 打开", "🌐 Open"))
+                                        .fill(egui::Color32::from_rgb(173, 216, 230)); // 淡蓝色背景
+                                    if ui.add(open_button).clicked() {
+                                        if let Err(e) =
+                                            webbrowser::open(&template::expand(&server.url))
+                                        {
+                                            eprintln!("无法打开浏览器: {}", e);
+                                        }
+                                    }
+                                },
+                            );
+                        });
+                    });
+                    if can_reorder {
+                        let response = &card.response;
+                        if response.dnd_hover_payload::<usize>().is_some() {
+                            ui.painter().rect_stroke(
+                                response.rect,
+                                4.0,
+                                ui.visuals().selection.stroke,
+                            );
+                        }
+                        if let Some(from) = response.dnd_release_payload::<usize>() {
+                            moved = Some((*from, i));
+                        }
+                    }
+                    ui.add_space(5.0);
+                }
+            });
+
+            if let Some(index) = edit_index {
+                self.start_edit_server(index);
+            }
+            if let Some(index) = detail_index {
+                let servers = &self.monitor.servers;
+                if let Some(server) = servers.get(index) {
+                    self.detail_window.open(server, &self.monitor.client);
+                }
+            }
+            if let Some(server) = chart_index.and_then(|i| self.monitor.servers.get(i)) {
+                self.chart_window.open(server);
+            }
+            if let Some(index) = duplicate_index {
+                self.start_duplicate_server(index);
+            }
+            if let Some(index) = delete_index {
+                self.request_delete([index]);
+            }
+            if let Some(index) = accept_baseline {
+                self.accept_security_baseline(index);
+            }
+            if let Some(index) = toggle_pause {
+                self.toggle_paused(index);
+            }
+            self.scroll_offset = scroll_output.state.offset.y;
+            if let Some(group) = toggle_group {
+                if !self.collapsed_groups.remove(&group) {
+                    self.collapsed_groups.insert(group);
+                }
+            }
+            if let Some(tag) = toggle_tag {
+                self.filter.toggle_tag(&tag);
+            }
+            if let Some(index) = toggle_select {
+                if !self.selected.remove(&index) {
+                    self.selected.insert(index);
+                }
+            }
+            if let Some((from, to)) = moved {
+                self.move_server(from, to);
+            }
+        });
+
+        // 编辑服务器对话框
+        self.show_edit_dialog(ctx);
+
+        // 可用率统计
+        if self.uptime_window.open {
+            let changed = self.uptime_window.show(
+                ctx,
+                &self.monitor.servers,
+                &self.monitor.history,
+                &mut self.monitor.settings,
+                &status_page::default_path(&self.location),
+                &self.monitor.reports_dir,
+            );
+            if changed {
+                self.save_settings();
+            }
+            if std::mem::take(&mut self.uptime_window.export_requested) {
+                self.export_status_page();
+            }
+            if std::mem::take(&mut self.uptime_window.report_requested) {
+                self.generate_report();
+            }
+        }
+
+        // 通知渠道
+        if self.channels_window.open {
+            let changed = self.channels_window.show(
+                ctx,
+                &mut self.monitor.settings.channels,
+                &self.monitor.client,
+                &self.monitor.action_log,
+            );
+            if changed {
+                self.save_settings();
+            }
+        }
+
+        // 云资源发现
+        if self.discovery_window.open {
+            let action = self.discovery_window.show(
+                ctx,
+                &mut self.monitor.settings.discovery,
+                self.discovery_run.as_ref(),
+                self.discovery_refreshing,
+                &self.monitor.servers,
+            );
+            if action.settings_changed {
+                self.discovery_timer.interval = self.monitor.settings.discovery.interval();
+                self.save_settings();
+            }
+            if action.refresh {
+                self.discovery_timer.reset(self.monitor.clock.instant());
+                self.refresh_discovery();
+            }
+            if !action.add.is_empty() {
+                let count = action.add.len();
+                self.monitor.servers.extend(action.add);
+                if let Err(e) = self.save_servers() {
+                    eprintln!("保存配置失败: {}", e);
+                }
+                self.toasts
+                    .push(tr!("已添加 {} 台服务器", "Added {} servers", count));
+                self.check_all_servers();
+            }
+        }
+
+        // 快捷键与命令面板
+        self.handle_shortcuts(ctx);
+        if self.palette.open {
+            let muted = self.monitor.alerts_muted();
+            let selected = self.selected.len();
+            if let Some(command) = self
+                .palette
+                .show(ctx, &self.monitor.servers, muted, selected)
+            {
+                self.run_command(command);
+            }
+        }
+
+        // 投屏看板
+        if self.dashboard.open {
+            self.dashboard
+                .show(ctx, &self.monitor.servers, &self.monitor.settings.dashboard);
+        }
+
+        // 服务器详情
+        if self.detail_window.is_open() {
+            let servers = &self.monitor.servers;
+            self.detail_window.show(
+                ctx,
+                servers,
+                &mut self.monitor.history,
+                &self.monitor.client,
+            );
+            if std::mem::take(&mut self.detail_window.history_changed) {
+                self.monitor.save_history();
+            }
+        }
+        if self.chart_window.is_open() {
+            self.chart_window.show(
+                ctx,
+                &self.monitor.servers,
+                &self.monitor.history,
+                self.monitor.clock.now(),
+            );
+        }
+
+        // 设置窗口
+        self.show_settings_window(ctx);
+
+        // 新建配置方案对话框
+        self.show_new_profile_dialog(ctx);
+
+        // 定时提醒弹窗
+        if let Some(reminder) = self.pending_reminders.first() {
+            let mut dismissed = false;
+            egui::Window::new(tr!("⏰ 定时提醒", "⏰ Reminder"))
+                .collapsible(false)
+                .resizable(false)
+                .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+                .show(ctx, |ui| {
+                    ui.strong(&reminder.server_name);
+                    ui.label(&reminder.server_url);
+                    ui.label(tr!(
+                        "{} 提醒: {}",
+                        "{} reminder: {}",
+                        reminder.action.time_label(),
+                        reminder.action.message
+                    ));
+                    if ui.button(tr!("知道了", "Got it")).clicked() {
+                        dismissed = true;
+                    }
+                });
+            if dismissed {
+                self.pending_reminders.remove(0);
+            }
+        }
+
+        // 添加服务器对话框
+        if self.add_dialog.open {
+            let existing: Vec<String> =
+                self.monitor.servers.iter().map(|s| s.url.clone()).collect();
+            let added = self.add_dialog.show(ctx, &self.monitor.client, &existing);
+            if added.len() > 1 {
+                self.toasts
+                    .push(tr!("已添加 {} 台服务器", "Added {} servers", added.len()));
+            }
+            if !added.is_empty() {
+                self.monitor.servers.extend(added);
+                if let Err(e) = self.save_servers() {
+                    eprintln!("保存配置失败: {}", e);
+                }
+            }
+        }
+
+        // 删除确认与撤销
+        self.show_delete_confirm(ctx);
+        self.track_focus(ctx);
+        self.show_away_digest(ctx);
+        if self.deleted.as_ref().is_some_and(|d| {
+            self.monitor
+                .clock
+                .instant()
+                .saturating_duration_since(d.deleted_at)
+                >= UNDO_DELETE_WINDOW
+        }) {
+            self.finalize_deleted();
+            if let Err(e) = self.save_servers() {
+                eprintln!("保存配置失败: {}", e);
+            }
+        }
+
+        let clicked = self.toasts.show(ctx);
+        if clicked.is_some() && clicked == self.deleted.as_ref().map(|d| d.toast_id) {
+            self.undo_delete();
+        }
+
+        // 请求重绘以保持UI响应
+        ctx.request_repaint_after(Duration::from_millis(100));
+    }
+}
+
+#[cfg(feature = "gui")]
+impl ServerMonitorApp {
+    fn show_new_profile_dialog(&mut self, ctx: &egui::Context) {
+        let Some(mut name) = self.new_profile_name.take() else {
+            return;
+        };
+        let mut keep_open = true;
+        let mut create = false;
+
+        egui::Window::new(tr!("新建配置方案", "New profile"))
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(tr!("方案名称:", "Profile name:"));
+                let response = ui.text_edit_singleline(&mut name);
+                if response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)) {
+                    create = true;
+                }
+                if let Some(error) = &self.profile_error {
+                    ui.colored_label(ServerStatus::Offline.color(), error);
+                }
+                ui.horizontal(|ui| {
+                    if ui.button(tr!("创建", "Create")).clicked() {
+                        create = true;
+                    }
+                    if ui.button(tr!("取消", "Cancel")).clicked() {
+                        keep_open = false;
+                    }
+                });
+            });
+
+        if create && self.create_profile(&name) {
+            keep_open = false;
+        }
+        if keep_open {
+            self.new_profile_name = Some(name);
+        } else {
+            self.profile_error = None;
+        }
+    }
+
+    // 把服务器当前的安全配置接受为新的基线
+    fn accept_security_baseline(&mut self, index: usize) {
+        let servers = &mut self.monitor.servers;
+        let Some(server) = servers.get_mut(index) else {
+            return;
+        };
+        if let Some(profile) = &server.state.security {
+            let baselines = &mut self.monitor.baselines;
+            baselines.set(&server.history_key(), profile.clone());
+            if let Err(e) = baselines.save(&*self.monitor.storage) {
+                eprintln!("保存安全基线失败: {}", e);
+            }
+            server.state.security_changes.clear();
+        }
+    }
+
+    // 计算版本不一致的分组，新出现时按设置弹出提示
+    fn update_version_drift(&mut self) -> Vec<VersionDrift> {
+        let drift = columns::version_drift(&self.monitor.servers, &self.monitor.settings.columns);
+        for d in &drift {
+            if self.drift_notified.insert(d.key()) && self.monitor.settings.notify_version_drift {
+                self.toasts.push(tr!(
+                    "⚠ 分组 [{}] 的{}不一致: {}",
+                    "⚠ {1} differs in group [{0}]: {2}",
+                    group_label(&d.group),
+                    d.column,
+                    d.summary()
+                ));
+            }
+        }
+        // 恢复一致后再次出现时重新提示
+        self.drift_notified
+            .retain(|key| drift.iter().any(|d| &d.key() == key));
+        drift
+    }
+
+    // 切换到设置窗口中填写的存储；新存储中还没有当前方案时，把当前数据复制过去
+    fn apply_storage(&mut self) {
+        let new = match storage::open(&self.storage_input, &self.location) {
+            Ok(storage) => storage,
+            Err(e) => {
+                self.settings_error =
+                    Some(tr!("无法打开存储: {}", "Failed to open storage: {}", e));
+                return;
+            }
+        };
+        let copied = match new.load(&self.servers_doc()) {
+            Ok(Some(_)) => Ok(false),
+            Ok(None) => self.copy_data_to(&*new).map(|_| true),
+            Err(e) => Err(e.into()),
+        };
+        let copied = match copied {
+            Ok(copied) => copied,
+            Err(e) => {
+                self.settings_error =
+                    Some(tr!("无法访问存储: {}", "Failed to access storage: {}", e));
+                return;
+            }
+        };
+
+        self.monitor.worker.cancel();
+        self.monitor.storage = new;
+        self.monitor.settings.storage = self.storage_input.clone();
+        self.settings_error = None;
+        self.save_settings();
+        self.monitor.history = History::load(&*self.monitor.storage);
+        self.monitor.baselines = Baselines::load(&*self.monitor.storage);
+        self.refresh_profiles();
+        if let Err(e) = self.load_servers() {
+            eprintln!("加载配置失败: {}", e);
+        }
+        self.watch_config();
+        self.check_all_servers();
+        self.toasts.push(if copied {
+            tr!(
+                "已切换存储，并复制了当前数据",
+                "Switched storage and copied the current data"
+            )
+        } else {
+            tr!("已切换存储", "Switched storage")
+        });
+    }
+
+    // 把所有配置方案、历史记录和安全基线写入另一个存储
+    fn copy_data_to(&mut self, target: &dyn Storage) -> Result<(), Box<dyn std::error::Error>> {
+        // 当前方案以内存中的列表为准
+        self.save_servers()?;
+        for profile in &self.profiles {
+            let doc = storage::servers_doc(profile);
+            if let Some(content) = self.monitor.storage.load(&doc)? {
+                target.save(&doc, &content)?;
+            }
+        }
+        self.monitor.history.save(target)?;
+        self.monitor.baselines.save(target)?;
+        Ok(())
+    }
+
+    // 按设置中的清单文件新增或更新服务器
+    fn refresh_discovery(&mut self) {
+        self.discovery_refreshing = true;
+        discovery::spawn_refresh(
+            &self.monitor.client,
+            &self.monitor.settings.discovery.accounts,
+            &self.discovery,
+        );
+    }
+
+    // 取回后台刷新的结果，有新的未监控资源时提示
+    fn poll_discovery(&mut self) {
+        let Some(run) = self.discovery.lock().unwrap().take() else {
+            return;
+        };
+        self.discovery_refreshing = false;
+        for error in &run.errors {
+            eprintln!("云资源发现失败: {}", error);
+        }
+        let fresh: Vec<String> = discovery_window::proposals(
+            Some(&run),
+            &self.monitor.settings.discovery,
+            &self.monitor.servers,
+        )
+        .map(|t| t.id.clone())
+        .filter(|id| !self.discovery_known.contains(id))
+        .collect();
+        if !fresh.is_empty() {
+            self.toasts.push(tr!(
+                "☁ 发现 {} 个新的监控目标",
+                "☁ Found {} new monitoring targets",
+                fresh.len()
+            ));
+            self.discovery_known.extend(fresh);
+        }
+        self.discovery_run = Some(run);
+    }
+
+    fn sync_inventory(&mut self) {
+        let path = self.monitor.settings.inventory.path.trim();
+        let hosts = match inventory::load(Path::new(path)) {
+            Ok(hosts) => hosts,
+            Err(e) => {
+                self.toasts
+                    .push(tr!("从清单同步失败: {}", "Inventory sync failed: {}", e));
+                return;
+            }
+        };
+        let summary = inventory::sync(
+            &mut self.monitor.servers,
+            &hosts,
+            &self.monitor.settings.inventory,
+        );
+        self.toasts.push(summary.message());
+        if summary.changed() {
+            if let Err(e) = self.save_servers() {
+                eprintln!("保存配置失败: {}", e);
+            }
+            self.check_all_servers();
+        }
+    }
+
+    fn poll_docker_import(&mut self) {
+        let Some(result) = self.docker_listing.lock().unwrap().take() else {
+            return;
+        };
+        self.docker_importing = false;
+        let containers = match result {
+            Ok(containers) => containers,
+            Err(e) => {
+                self.toasts
+                    .push(tr!("从Docker导入失败: {}", "Docker import failed: {}", e));
+                return;
+            }
+        };
+        let added = checks::docker::import(
+            &mut self.monitor.servers,
+            &containers,
+            &self.monitor.settings.docker,
+        );
+        self.toasts.push(tr!(
+            "从Docker导入: 新增 {} 台，{} 台已存在",
+            "Docker import: {} added, {} already present",
+            added,
+            containers.len() - added
+        ));
+        if added > 0 {
+            if let Err(e) = self.save_servers() {
+                eprintln!("保存配置失败: {}", e);
+            }
+            self.check_all_servers();
+        }
+    }
+
+    // 守护进程地址和导入的分组，返回设置是否被修改
+    fn show_docker_settings(&mut self, ui: &mut egui::Ui) -> bool {
+        let docker = &mut self.monitor.settings.docker;
+        let mut changed = false;
+        egui::Grid::new("docker_import")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label(tr!("守护进程:", "Daemon:"));
+                changed |= ui
+                    .add(
+                        egui::TextEdit::singleline(&mut docker.endpoint)
+                            .hint_text(checks::docker::DEFAULT_SOCKET)
+                            .desired_width(260.0),
+                    )
+                    .lost_focus();
+                ui.end_row();
+                ui.label(tr!("分组:", "Group:"));
+                changed |= ui.text_edit_singleline(&mut docker.group).lost_focus();
+                ui.end_row();
+            });
+        ui.small(tr!(
+            "导入运行中且有发布端口的容器，检查方式为Docker；已有同名服务器的跳过",
+            "Imports running containers with published ports using the Docker check; names already in the list are skipped"
+        ));
+        let button = if self.docker_importing {
+            tr!("⏳ 正在导入…", "⏳ Importing…")
+        } else {
+            tr!("🐳 从Docker导入", "🐳 Import from Docker")
+        };
+        if ui
+            .add_enabled(!self.docker_importing, egui::Button::new(button))
+            .clicked()
+        {
+            self.docker_importing = true;
+            checks::docker::spawn_list(
+                &self.monitor.settings.docker.endpoint,
+                &self.docker_listing,
+            );
+        }
+        changed
+    }
+
+    // 清单文件和生成检查地址用的协议、端口，返回设置是否被修改
+    fn show_inventory_settings(&mut self, ui: &mut egui::Ui) -> bool {
+        let inventory = &mut self.monitor.settings.inventory;
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label(tr!("文件:", "File:"));
+            changed |= ui
+                .add(
+                    egui::TextEdit::singleline(&mut inventory.path)
+                        .hint_text("inventory.ini / hosts.yml / terraform.tfstate")
+                        .desired_width(260.0),
+                )
+                .lost_focus();
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr!("新增的服务器:", "New servers:"));
+            changed |= ui.checkbox(&mut inventory.https, "HTTPS").changed();
+            ui.label(tr!("端口:", "Port:"));
+            let port = ui.add(egui::DragValue::new(&mut inventory.port).range(1..=65535));
+            changed |= port.drag_stopped() || (port.changed() && !port.dragged());
+        });
+        ui.small(tr!(
+            "按名称匹配，已有的服务器只更新主机和分组；清单中删除的主机不会被移除",
+            "Matched by name; existing servers only get their host and group updated. Hosts removed from the inventory are kept"
+        ));
+        let can_sync = !inventory.path.trim().is_empty();
+        if ui
+            .add_enabled(
+                can_sync,
+                egui::Button::new(tr!("🔄 立即同步", "🔄 Sync now")),
+            )
+            .clicked()
+        {
+            self.sync_inventory();
+        }
+        changed
+    }
+
+    // 启动后首次检查的延迟和通知宽限期，返回设置是否被修改
+    fn show_startup_settings(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        egui::Grid::new("startup_settings")
+            .num_columns(2)
+            .show(ui, |ui| {
+                let rows = [
+                    (
+                        tr!("首次检查延迟:", "First check after:"),
+                        &mut self.monitor.settings.startup_delay_secs,
+                    ),
+                    (
+                        tr!("通知宽限期:", "Notification grace period:"),
+                        &mut self.monitor.settings.startup_grace_secs,
+                    ),
+                ];
+                for (label, secs) in rows {
+                    ui.label(label);
+                    let value = ui.add(
+                        egui::DragValue::new(secs)
+                            .range(0..=MAX_STARTUP_SECS)
+                            .custom_formatter(|secs, _| format_interval(secs as u64))
+                            .speed(1.0),
+                    );
+                    changed |= value.drag_stopped() || (value.changed() && !value.dragged());
+                    ui.end_row();
+                }
+            });
+        ui.small(tr!(
+            "第一轮检查完成后的宽限期内状态变化不发送通知，结束时对仍然异常的服务器补发一次，适合监控电脑和服务器一起重启的情况；0表示不启用",
+            "Status changes during the grace period after the first check round are not notified; servers still down when it ends are notified once. Useful when this PC reboots together with the servers; 0 disables it"
+        ));
+        changed
+    }
+
+    // 同时进行的检查数，返回设置是否被修改
+    fn show_concurrency_settings(&mut self, ui: &mut egui::Ui) -> bool {
+        let concurrency = &mut self.monitor.settings.concurrency;
+        let mut changed = false;
+        egui::Grid::new("concurrency_settings")
+            .num_columns(2)
+            .show(ui, |ui| {
+                let rows = [
+                    (
+                        tr!("同时检查:", "Concurrent checks:"),
+                        &mut concurrency.max_total,
+                        worker::MAX_TOTAL_CHECKS,
+                    ),
+                    (
+                        tr!("每台主机:", "Per host:"),
+                        &mut concurrency.max_per_host,
+                        worker::MAX_PER_HOST_CHECKS,
+                    ),
+                ];
+                for (label, value, max) in rows {
+                    ui.label(label);
+                    let response = ui.add(egui::DragValue::new(value).range(1..=max));
+                    changed |=
+                        response.drag_stopped() || (response.changed() && !response.dragged());
+                    ui.end_row();
+                }
+            });
+        ui.small(tr!(
+            "同一主机（URL中的主机名或IP）上的多个服务同时被探测时可能触发对方的限流而误报错误，超出的检查排队等待",
+            "Probing many services on the same host (URL host name or IP) at once can trip its rate limiter and cause false errors; extra checks wait in line"
+        ));
+        changed
+    }
+
+    // 心跳地址和间隔，返回设置是否被修改
+    fn show_heartbeat_settings(&mut self, ui: &mut egui::Ui) -> bool {
+        let heartbeat = &mut self.monitor.settings.heartbeat;
+        let mut changed = ui
+            .checkbox(
+                &mut heartbeat.enabled,
+                tr!(
+                    "定时访问外部监控地址，本程序停止运行后由对方报警",
+                    "Ping an external monitor so it alerts when this program stops running"
+                ),
+            )
+            .changed();
+        ui.horizontal(|ui| {
+            ui.label("URL:");
+            // 失去焦点时才保存
+            changed |= ui
+                .add(
+                    egui::TextEdit::singleline(&mut heartbeat.url)
+                        .hint_text("https://hc-ping.com/<uuid>")
+                        .desired_width(260.0),
+                )
+                .lost_focus();
+        });
+        ui.horizontal(|ui| {
+            ui.label(tr!("间隔:", "Interval:"));
+            let interval = ui.add(
+                egui::DragValue::new(&mut heartbeat.interval_secs)
+                    .range(MIN_HEARTBEAT_SECS..=MAX_HEARTBEAT_SECS)
+                    .custom_formatter(|secs, _| format_interval(secs as u64))
+                    .speed(1.0),
+            );
+            changed |= interval.drag_stopped() || (interval.changed() && !interval.dragged());
+        });
+        match &*self.monitor.heartbeat.last.lock().unwrap() {
+            Some(last) => match &last.error {
+                None => ui.small(tr!(
+                    "上次心跳: {} 成功",
+                    "Last ping: {} OK",
+                    last.at.format("%H:%M:%S")
+                )),
+                Some(e) => ui.colored_label(
+                    ServerStatus::Offline.color(),
+                    tr!(
+                        "上次心跳: {} 失败: {}",
+                        "Last ping: {} failed: {}",
+                        last.at.format("%H:%M:%S"),
+                        e
+                    ),
+                ),
+            },
+            None => ui.small(tr!("尚未发送心跳", "No ping sent yet")),
+        };
+        changed
+    }
+
+    // 备份目标和间隔，返回设置是否被修改
+    fn show_backup_settings(&mut self, ui: &mut egui::Ui) -> bool {
+        let backup = &mut self.monitor.settings.backup;
+        let mut changed = ui
+            .checkbox(
+                &mut backup.enabled,
+                tr!(
+                    "定时把设置和服务器列表上传到远程，本机损坏后可以恢复",
+                    "Periodically upload settings and server lists so they survive losing this machine"
+                ),
+            )
+            .changed();
+        ui.horizontal(|ui| {
+            ui.label(tr!("目标:", "Target:"));
+            for target in BackupTarget::ALL {
+                changed |= ui
+                    .radio_value(&mut backup.target, target, target.label())
+                    .changed();
+            }
+        });
+        egui::Grid::new("backup_settings")
+            .num_columns(2)
+            .show(ui, |ui| {
+                // 输入框在失去焦点时才保存，不在每次按键时写入设置
+                let field = |ui: &mut egui::Ui, label: &str, value: &mut String, hint: &str| {
+                    ui.label(label);
+                    let changed = ui
+                        .add(
+                            egui::TextEdit::singleline(value)
+                                .hint_text(hint)
+                                .desired_width(260.0),
+                        )
+                        .lost_focus();
+                    ui.end_row();
+                    changed
+                };
+                match backup.target {
+                    BackupTarget::S3 => {
+                        changed |= field(
+                            ui,
+                            tr!("地址:", "Endpoint:"),
+                            &mut backup.s3_endpoint,
+                            "https://s3.us-east-1.amazonaws.com",
+                        );
+                        changed |= field(
+                            ui,
+                            tr!("区域:", "Region:"),
+                            &mut backup.s3_region,
+                            "us-east-1",
+                        );
+                        changed |= field(ui, tr!("存储桶:", "Bucket:"), &mut backup.s3_bucket, "");
+                        changed |= field(ui, "Access Key:", &mut backup.s3_access_key, "");
+                        ui.label("Secret Key:");
+                        changed |= ui
+                            .add(
+                                egui::TextEdit::singleline(&mut backup.s3_secret_key)
+                                    .password(true),
+                            )
+                            .lost_focus();
+                        ui.end_row();
+                    }
+                    BackupTarget::WebDav => {
+                        changed |= field(
+                            ui,
+                            "URL:",
+                            &mut backup.webdav_url,
+                            "https://dav.example.com/remote.php/dav/files/me",
+                        );
+                        changed |= field(
+                            ui,
+                            tr!("用户名:", "User:"),
+                            &mut backup.webdav_user,
+                            tr!("可留空", "Optional"),
+                        );
+                        ui.label(tr!("密码:", "Password:"));
+                        changed |= ui
+                            .add(
+                                egui::TextEdit::singleline(&mut backup.webdav_password)
+                                    .password(true),
+                            )
+                            .lost_focus();
+                        ui.end_row();
+                    }
+                    BackupTarget::Sftp => {
+                        changed |= field(
+                            ui,
+                            tr!("主机:", "Host:"),
+                            &mut backup.sftp_host,
+                            "backup.example.com",
+                        );
+                        ui.label(tr!("端口:", "Port:"));
+                        let port = ui.add(egui::DragValue::new(&mut backup.sftp_port));
+                        changed |= port.drag_stopped() || (port.changed() && !port.dragged());
+                        ui.end_row();
+                        changed |= field(ui, tr!("用户名:", "User:"), &mut backup.sftp_user, "");
+                        changed |= field(
+                            ui,
+                            tr!("私钥路径:", "Private key:"),
+                            &mut backup.sftp_key_path,
+                            "~/.ssh/id_ed25519",
+                        );
+                        ui.label(tr!("私钥密码:", "Key passphrase:"));
+                        changed |= ui
+                            .add(
+                                egui::TextEdit::singleline(&mut backup.sftp_key_passphrase)
+                                    .password(true),
+                            )
+                            .lost_focus();
+                        ui.end_row();
+                        ui.label("");
+                        changed |= check_editor::trust_unknown_host_checkbox(
+                            ui,
+                            &mut backup.sftp_trust_unknown_host,
+                        );
+                        ui.end_row();
+                    }
+                }
+                changed |= field(
+                    ui,
+                    tr!("远程目录:", "Remote folder:"),
+                    &mut backup.path,
+                    tr!("留空为根目录", "Empty for the root"),
+                );
+            });
+        changed |= ui
+            .checkbox(
+                &mut backup.include_history,
+                tr!("同时备份状态历史", "Include status history"),
+            )
+            .changed();
+        let mut backup_now = false;
+        ui.horizontal(|ui| {
+            ui.label(tr!("间隔:", "Interval:"));
+            let interval = ui.add(
+                egui::DragValue::new(&mut backup.interval_hours)
+                    .range(MIN_BACKUP_HOURS..=MAX_BACKUP_HOURS)
+                    .suffix(tr!(" 小时", " h")),
+            );
+            if interval.changed() {
+                self.monitor.backup_timer.interval = backup.interval();
+            }
+            changed |= interval.drag_stopped() || (interval.changed() && !interval.dragged());
+            backup_now = ui
+                .add_enabled(
+                    backup.ready(),
+                    egui::Button::new(tr!("立即备份", "Back up now")),
+                )
+                .clicked();
+        });
+        if backup_now {
+            self.monitor
+                .backup_timer
+                .reset(self.monitor.clock.instant());
+            self.monitor.run_backup();
+        }
+        match &*self.monitor.last_backup.lock().unwrap() {
+            Some(last) => match &last.error {
+                None => ui.small(tr!(
+                    "上次备份: {} 成功，{} 个文件",
+                    "Last backup: {} OK, {} files",
+                    last.at.format("%m-%d %H:%M"),
+                    last.files
+                )),
+                Some(e) => ui.colored_label(
+                    ServerStatus::Offline.color(),
+                    tr!(
+                        "上次备份: {} 失败: {}",
+                        "Last backup: {} failed: {}",
+                        last.at.format("%m-%d %H:%M"),
+                        e
+                    ),
+                ),
+            },
+            None => ui.small(tr!("本次运行尚未备份", "No backup yet in this session")),
+        };
+        ui.small(tr!(
+            "上传 settings.json、所有配置方案的服务器列表和安全基线，每次覆盖上一次的文件；其中包含检查和通知用的密码等，请使用可信的存储",
+            "Uploads settings.json, every profile's server list and the security baseline, overwriting the previous copy. They include check and notification passwords, so use trusted storage"
+        ));
+        changed
+    }
+
+    // 指标推送的目标和认证，返回设置是否被修改
+    fn show_export_settings(&mut self, ui: &mut egui::Ui) -> bool {
+        let export = &mut self.monitor.settings.export;
+        let mut changed = ui
+            .checkbox(
+                &mut export.enabled,
+                tr!(
+                    "每轮检查后推送延迟和在线状态，接入 Grafana 看板",
+                    "Push latency and up/down after every check cycle, for Grafana dashboards"
+                ),
+            )
+            .changed();
+        ui.horizontal(|ui| {
+            ui.label(tr!("目标:", "Target:"));
+            for target in ExportTarget::ALL {
+                changed |= ui
+                    .radio_value(&mut export.target, target, target.label())
+                    .changed();
+            }
+        });
+        egui::Grid::new("export_settings")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label("URL:");
+                changed |= ui
+                    .add(
+                        egui::TextEdit::singleline(&mut export.url)
+                            .hint_text(export.target.url_hint())
+                            .desired_width(260.0),
+                    )
+                    .changed();
+                ui.end_row();
+                ui.label("Token:");
+                changed |= ui
+                    .add(
+                        egui::TextEdit::singleline(&mut export.token)
+                            .hint_text(tr!("可留空", "Optional"))
+                            .password(true),
+                    )
+                    .changed();
+                ui.end_row();
+                ui.label(tr!("用户名:", "User:"));
+                changed |= ui
+                    .add(
+                        egui::TextEdit::singleline(&mut export.username)
+                            .hint_text(tr!("可留空", "Optional")),
+                    )
+                    .changed();
+                ui.end_row();
+                ui.label(tr!("密码:", "Password:"));
+                changed |= ui
+                    .add(egui::TextEdit::singleline(&mut export.password).password(true))
+                    .changed();
+                ui.end_row();
+            });
+        if let Err(e) = export.validate() {
+            ui.colored_label(ServerStatus::Offline.color(), e.to_string());
+        }
+        match &*self.monitor.last_export.lock().unwrap() {
+            Some(last) => match &last.error {
+                None => ui.small(tr!(
+                    "上次推送: {} 成功，{} 台服务器",
+                    "Last push: {} OK, {} servers",
+                    last.at.format("%H:%M:%S"),
+                    last.count
+                )),
+                Some(e) => ui.colored_label(
+                    ServerStatus::Offline.color(),
+                    tr!(
+                        "上次推送: {} 失败: {}",
+                        "Last push: {} failed: {}",
+                        last.at.format("%H:%M:%S"),
+                        e
+                    ),
+                ),
+            },
+            None => ui.small(tr!("尚未推送", "Nothing pushed yet")),
+        };
+        ui.small(tr!(
+            "指标为 server_check_up（1 在线，0 故障）和 server_check_latency_ms，标签为 server 和 group；InfluxDB 中写入 server_check 测量",
+            "Metrics are server_check_up (1 up, 0 down) and server_check_latency_ms labelled by server and group; InfluxDB gets a server_check measurement"
+        ));
+        changed
+    }
+
+    // MQTT 代理和主题前缀，返回设置是否被修改
+    fn show_mqtt_settings(&mut self, ui: &mut egui::Ui) -> bool {
+        let mqtt = &mut self.monitor.settings.mqtt;
+        let mut changed = ui
+            .checkbox(
+                &mut mqtt.enabled,
+                tr!(
+                    "状态变化时发布到 MQTT，供 Home Assistant 等自动化使用",
+                    "Publish status changes to MQTT for Home Assistant and other automations"
+                ),
+            )
+            .changed();
+        egui::Grid::new("mqtt_publish_settings")
+            .num_columns(2)
+            .show(ui, |ui| {
+                ui.label(tr!("代理:", "Broker:"));
+                ui.horizontal(|ui| {
+                    changed |= ui
+                        .add(
+                            egui::TextEdit::singleline(&mut mqtt.host)
+                                .hint_text("mqtt.lan")
+                                .desired_width(180.0),
+                        )
+                        .changed();
+                    ui.label(tr!("端口:", "Port:"));
+                    changed |= ui.add(egui::DragValue::new(&mut mqtt.port)).changed();
+                    changed |= ui.checkbox(&mut mqtt.tls, "TLS").changed();
+                });
+                ui.end_row();
+                if mqtt.tls {
+                    ui.label("");
+                    changed |= ui
+                        .checkbox(
+                            &mut mqtt.accept_invalid_certs,
+                            tr!("接受无效证书", "Accept invalid certificates"),
+                        )
+                        .changed();
+                    ui.end_row();
+                }
+                ui.label(tr!("用户名:", "User:"));
+                changed |= ui
+                    .add(
+                        egui::TextEdit::singleline(&mut mqtt.user)
+                            .hint_text(tr!("可留空", "Optional")),
+                    )
+                    .changed();
+                ui.end_row();
+                ui.label(tr!("密码:", "Password:"));
+                changed |= ui
+                    .add(egui::TextEdit::singleline(&mut mqtt.password).password(true))
+                    .changed();
+                ui.end_row();
+                ui.label(tr!("主题前缀:", "Topic prefix:"));
+                changed |= ui
+                    .add(egui::TextEdit::singleline(&mut mqtt.prefix).hint_text("servercheck"))
+                    .changed();
+                ui.end_row();
+            });
+        ui.horizontal(|ui| {
+            changed |= ui
+                .checkbox(
+                    &mut mqtt.ha_discovery,
+                    tr!("Home Assistant 自动发现", "Home Assistant discovery"),
+                )
+                .on_hover_text(tr!(
+                    "每台服务器自动作为 binary_sensor 实体出现，延迟等作为实体属性",
+                    "Each server shows up as a binary_sensor entity with latency and other attributes"
+                ))
+                .changed();
+            if mqtt.ha_discovery {
+                ui.label(tr!("发现前缀:", "Discovery prefix:"));
+                changed |= ui
+                    .add(
+                        egui::TextEdit::singleline(&mut mqtt.ha_prefix)
+                            .hint_text("homeassistant")
+                            .desired_width(120.0),
+                    )
+                    .changed();
+            }
+        });
+        changed |= ui
+            .checkbox(
+                &mut mqtt.retain,
+                tr!(
+                    "保留消息（重新订阅时立即收到当前状态）",
+                    "Retain messages (subscribers get the current status right away)"
+                ),
+            )
+            .changed();
+        match &*self.monitor.mqtt.last.lock().unwrap() {
+            Some(last) => match &last.error {
+                None => ui.small(tr!(
+                    "上次发布: {} 成功，{} 条消息",
+                    "Last publish: {} OK, {} messages",
+                    last.at.format("%H:%M:%S"),
+                    last.count
+                )),
+                Some(e) => ui.colored_label(
+                    ServerStatus::Offline.color(),
+                    tr!(
+                        "上次发布: {} 失败: {}",
+                        "Last publish: {} failed: {}",
+                        last.at.format("%H:%M:%S"),
+                        e
+                    ),
+                ),
+            },
+            None => ui.small(tr!("尚未发布", "Nothing published yet")),
+        };
+        ui.small(tr!(
+            "主题为 <前缀>/<名称>/status，内容为 online、offline、error 或 degraded；名称中的 / + # 换成 _。<前缀>/availability 表示程序是否在线",
+            "Topic is <prefix>/<name>/status with online, offline, error or degraded; / + # in names become _. <prefix>/availability tells whether server_check is running"
+        ));
+        changed
+    }
+
+    // 抖动检测的窗口和次数，返回设置是否被修改
+    fn show_flap_settings(&mut self, ui: &mut egui::Ui) -> bool {
+        let flap = &mut self.monitor.settings.flap;
+        let mut changed = ui
+            .checkbox(
+                &mut flap.enabled,
+                tr!(
+                    "反复上线、掉线时标记为抖动，只通知一次",
+                    "Mark servers that keep going up and down as flapping and alert once"
+                ),
+            )
+            .changed();
+        ui.add_enabled_ui(flap.enabled, |ui| {
+            ui.horizontal(|ui| {
+                let window = ui.add(
+                    egui::DragValue::new(&mut flap.window_mins)
+                        .range(1..=MAX_FLAP_WINDOW_MINS)
+                        .suffix(tr!(" 分钟内", " min")),
+                );
+                ui.label(tr!("变化超过", "window, more than"));
+                let count = ui.add(
+                    egui::DragValue::new(&mut flap.max_changes)
+                        .range(MIN_FLAP_CHANGES..=MAX_FLAP_CHANGES)
+                        .suffix(tr!(" 次", " changes")),
+                );
+                for response in [window, count] {
+                    changed |=
+                        response.drag_stopped() || (response.changed() && !response.dragged());
+                }
+            });
+        });
+        changed
+    }
+
+    // 标签管理：颜色、重命名、删除，返回标签颜色是否被修改
+    fn show_tag_settings(&mut self, ui: &mut egui::Ui) -> bool {
+        let counts = tags::tag_counts(&self.monitor.servers);
+        if counts.is_empty() {
+            ui.small(tr!(
+                "还没有标签，可在编辑服务器时添加",
+                "No tags yet; add them when editing a server"
+            ));
+            return false;
+        }
+        let mut colors_changed = false;
+        let mut rename = None;
+        let mut remove = None;
+        egui::Grid::new("tag_settings")
+            .num_columns(3)
+            .show(ui, |ui| {
+                for (tag, count) in &counts {
+                    let mut color = tags::color(tag, &self.monitor.settings.tag_colors);
+                    if ui.color_edit_button_srgb(&mut color).changed() {
+                        self.monitor.settings.tag_colors.insert(tag.clone(), color);
+                        colors_changed = true;
+                    }
+                    match &mut self.tag_rename {
+                        Some((from, to)) if from == tag => {
+                            ui.horizontal(|ui| {
+                                ui.add(egui::TextEdit::singleline(to).desired_width(100.0));
+                                if ui.small_button("✔").clicked() {
+                                    rename = Some((from.clone(), to.trim().to_string()));
+                                }
+                                if ui.small_button("✖").clicked() {
+                                    rename = Some((from.clone(), from.clone()));
+                                }
+                            });
+                        }
+                        _ => {
+                            ui.label(tr!("{} ({} 台)", "{} ({} servers)", tag, count));
+                        }
+                    }
+                    ui.horizontal(|ui| {
+                        if ui
+                            .small_button("✏")
+                            .on_hover_text(tr!("重命名", "Rename"))
+                            .clicked()
+                        {
+                            self.tag_rename = Some((tag.clone(), tag.clone()));
+                        }
+                        if self.monitor.settings.tag_colors.contains_key(tag)
+                            && ui
+                                .small_button("↺")
+                                .on_hover_text(tr!("恢复默认颜色", "Reset color"))
+                                .clicked()
+                        {
+                            self.monitor.settings.tag_colors.remove(tag);
+                            colors_changed = true;
+                        }
+                        if ui
+                            .small_button("🗑")
+                            .on_hover_text(tr!("从所有服务器上移除", "Remove from all servers"))
+                            .clicked()
+                        {
+                            remove = Some(tag.clone());
+                        }
+                    });
+                    ui.end_row();
+                }
+            });
+
+        let mut servers_changed = false;
+        if let Some((from, to)) = rename {
+            self.tag_rename = None;
+            let to = tags::parse_tags(&to);
+            if !to.is_empty() && to != [from.clone()] {
+                tags::rename(&mut self.monitor.servers, &from, &to);
+                if let Some(color) = self.monitor.settings.tag_colors.remove(&from) {
+                    for tag in &to {
+                        self.monitor
+                            .settings
+                            .tag_colors
+                            .entry(tag.clone())
+                            .or_insert(color);
+                    }
+                    colors_changed = true;
+                }
+                if let Some(t) = self.filter.tags.iter_mut().find(|t| **t == from) {
+                    *t = to[0].clone();
+                }
+                servers_changed = true;
+            }
+        }
+        if let Some(tag) = remove {
+            tags::remove(&mut self.monitor.servers, &tag);
+            colors_changed |= self.monitor.settings.tag_colors.remove(&tag).is_some();
+            self.filter.tags.retain(|t| *t != tag);
+            servers_changed = true;
+        }
+        if servers_changed {
+            if let Err(e) = self.save_servers() {
+                eprintln!("保存配置失败: {}", e);
+            }
+        }
+        colors_changed
+    }
+
+    // 存储设置，返回是否点击了应用
+    fn show_storage_settings(&mut self, ui: &mut egui::Ui) -> bool {
+        let input = &mut self.storage_input;
+        egui::ComboBox::from_label(tr!("存储方式", "Storage"))
+            .selected_text(input.kind.label())
+            .show_ui(ui, |ui| {
+                for kind in StorageKind::ALL {
+                    ui.selectable_value(&mut input.kind, kind, kind.label());
+                }
+            });
+        match input.kind {
+            StorageKind::JsonFile => {
+                ui.small(tr!(
+                    "保存在上面的配置目录中",
+                    "Stored in the config directory above"
+                ));
+            }
+            StorageKind::Sqlite => {
+                ui.horizontal(|ui| {
+                    ui.label(tr!("数据库文件:", "Database file:"));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut input.sqlite_path)
+                            .hint_text(tr!(
+                                "留空使用配置目录中的 servercheck.db",
+                                "Empty for servercheck.db in the config directory"
+                            ))
+                            .desired_width(220.0),
+                    );
+                });
+            }
+            StorageKind::Remote => {
+                egui::Grid::new("remote_storage")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label(tr!("地址:", "URL:"));
+                        ui.add(
+                            egui::TextEdit::singleline(&mut input.remote_url)
+                                .hint_text("https://config.example.com/servercheck")
+                                .desired_width(220.0),
+                        );
+                        ui.end_row();
+                        ui.label(tr!("令牌（可选）:", "Token (optional):"));
+                        ui.horizontal(|ui| {
+                            check_editor::secret_input(
+                                ui,
+                                &mut input.remote_token,
+                                "remote-storage-token",
+                            );
+                        });
+                        ui.end_row();
+                    });
+                ui.small(tr!(
+                    "服务端需支持 GET/PUT/DELETE {地址}/{文档名}，以及 GET {地址}?prefix= 返回文档名列表",
+                    "The server must support GET/PUT/DELETE {url}/{document} and GET {url}?prefix= returning document names"
+                ));
+            }
+        }
+        let changed = *input != self.monitor.settings.storage;
+        ui.add_enabled(changed, egui::Button::new(tr!("应用", "Apply")))
+            .clicked()
+    }
+
+    // 字体文件设置，返回是否需要重新加载字体
+    fn show_font_settings(&mut self, ui: &mut egui::Ui) -> bool {
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label(tr!("中文字体:", "CJK font:"));
+            ui.add(
+                egui::TextEdit::singleline(&mut self.font_path_input)
+                    .hint_text(tr!(
+                        "字体文件路径，留空自动查找",
+                        "Font file path, empty to auto-detect"
+                    ))
+                    .desired_width(220.0),
+            );
+            if ui.button(tr!("应用", "Apply")).clicked() {
+                let path = self.font_path_input.trim();
+                if !path.is_empty() && !Path::new(path).is_file() {
+                    self.settings_error =
+                        Some(tr!("字体文件不存在: {}", "Font file not found: {}", path));
+                } else {
+                    self.monitor.settings.font_path =
+                        (!path.is_empty()).then(|| PathBuf::from(path));
+                    self.settings_error = None;
+                    changed = true;
+                }
+            }
+        });
+        changed
+    }
+
+    // 主题文件的位置和加载状态
+    fn show_theme_file_status(&self, ui: &mut egui::Ui) {
+        let path = self.location.theme_path();
+        let state = match (&self.theme_error, &self.theme_file) {
+            (Some(e), _) => tr!("格式有误: {}", "invalid: {}", e),
+            (None, Some(_)) => tr!(
+                "已加载，其中的颜色优先",
+                "loaded, its colors take precedence"
+            )
+            .to_string(),
+            (None, None) => tr!("未创建", "not present").to_string(),
+        };
+        ui.small(tr!("主题文件 {}: {}", "Theme file {}: {}", path.display(), state))
+            .on_hover_text(tr!(
+                "在此文件中设置配色、间距、圆角和状态颜色，保存后自动生效，格式见使用说明",
+                "Set colors, spacing, corner radius and status colors in this file; changes apply on save, see the readme for the format"
+            ));
+    }
+
+    // 窗口图标设置，修改后立即更换图标
+    fn show_icon_settings(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        ui.horizontal(|ui| {
+            ui.label(tr!("窗口图标:", "Window icon:"));
+            ui.add(
+                egui::TextEdit::singleline(&mut self.icon_path_input)
+                    .hint_text(tr!(
+                        "PNG文件路径，留空使用内置图标",
+                        "PNG file path, empty for the built-in icon"
+                    ))
+                    .desired_width(220.0),
+            );
+            if ui.button(tr!("应用", "Apply")).clicked() {
+                let path = self.icon_path_input.trim();
+                if !path.is_empty() && !Path::new(path).is_file() {
+                    self.settings_error =
+                        Some(tr!("图标文件不存在: {}", "Icon file not found: {}", path));
+                } else {
+                    self.monitor.settings.icon_path =
+                        (!path.is_empty()).then(|| PathBuf::from(path));
+                    self.settings_error = None;
+                    let icon = icon::load(self.monitor.settings.icon_path.as_deref());
+                    ctx.send_viewport_cmd(egui::ViewportCommand::Icon(Some(Arc::new(icon))));
+                    self.save_settings();
+                }
+            }
+        });
+    }
+
+    fn show_settings_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_settings;
+        let mut portable = self.location.portable;
+        let mut delete_profile = None;
+        let mut theme_changed = false;
+        let mut language_changed = false;
+        let mut columns_changed = false;
+        let mut font_changed = false;
+        let mut storage_apply = false;
+
+        egui::Window::new(tr!("⚙ 设置", "⚙ Settings"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.strong(tr!("配置文件", "Configuration"));
+                ui.checkbox(
+                    &mut portable,
+                    tr!(
+                        "便携模式（配置保存在程序所在目录）",
+                        "Portable mode (store config next to the executable)"
+                    ),
+                );
+                ui.horizontal(|ui| {
+                    ui.label(tr!("配置目录:", "Config directory:"));
+                    ui.monospace(self.location.dir.display().to_string());
+                });
+                if let Some(error) = &self.settings_error {
+                    ui.colored_label(ServerStatus::Offline.color(), error);
+                }
 
-                    ui.label("端口号:");
-                    ui.text_edit_singleline(&mut self.new_server_port);
+                ui.separator();
+                ui.strong(tr!("数据存储", "Data storage"));
+                storage_apply = self.show_storage_settings(ui);
 
+                ui.separator();
+                ui.strong(tr!("配置方案", "Profiles"));
+                for profile in self.profiles.clone() {
                     ui.horizontal(|ui| {
-                        if ui.button("添加").clicked() {
-                            self.add_server();
+                        ui.label(profile_label(&profile));
+                        let removable =
+                            profile != DEFAULT_PROFILE && profile != self.monitor.settings.profile;
+                        if removable && ui.small_button("🗑").clicked() {
+                            delete_profile = Some(profile.clone());
                         }
+                    });
+                }
 
-                        if ui.button("取消").clicked() {
-                            self.show_add_dialog = false;
-                            self.new_server_name.clear();
-                            self.new_server_ip.clear();
-                            self.new_server_port.clear();
+                ui.separator();
+                ui.strong(tr!("外观", "Appearance"));
+                egui::ComboBox::from_label(tr!("语言", "Language"))
+                    .selected_text(self.monitor.settings.language.label())
+                    .show_ui(ui, |ui| {
+                        for lang in Lang::ALL {
+                            language_changed |= ui
+                                .selectable_value(&mut self.monitor.settings.language, lang, lang.label())
+                                .changed();
                         }
                     });
+                theme_changed = theme::show_settings(ui, &mut self.monitor.settings);
+                self.show_theme_file_status(ui);
+                font_changed = self.show_font_settings(ui);
+                self.show_icon_settings(ui, ctx);
+
+                ui.separator();
+                ui.strong(tr!("自定义列", "Custom columns"));
+                columns_changed = show_column_settings(ui, &mut self.monitor.settings.columns);
+                columns_changed |= ui
+                    .checkbox(
+                        &mut self.monitor.settings.notify_version_drift,
+                        tr!(
+                            "分组内版本不一致时弹出提示",
+                            "Notify when versions differ within a group"
+                        ),
+                    )
+                    .changed();
+                ui.separator();
+                ui.strong(tr!("标签", "Tags"));
+                columns_changed |= self.show_tag_settings(ui);
+                ui.separator();
+                ui.strong(tr!("离开提醒", "Away digest"));
+                columns_changed |= ui
+                    .checkbox(
+                        &mut self.monitor.settings.away_digest,
+                        tr!(
+                            "窗口重新获得焦点时，列出离开期间状态发生过变化的服务器",
+                            "When the window regains focus, list servers whose status changed while away"
+                        ),
+                    )
+                    .changed();
+                ui.separator();
+                ui.strong(tr!("投屏看板", "Dashboard"));
+                let dashboard = &mut self.monitor.settings.dashboard;
+                ui.horizontal(|ui| {
+                    ui.label(tr!("显示器:", "Display:"));
+                    let display = ui.add(
+                        egui::DragValue::new(&mut dashboard.display)
+                            .range(0..=8)
+                            .custom_formatter(|n, _| match n as u32 {
+                                0 => tr!("主显示器", "Primary").to_string(),
+                                n => tr!("第 {} 台", "#{}", n + 1),
+                            }),
+                    );
+                    columns_changed |=
+                        display.drag_stopped() || (display.changed() && !display.dragged());
+                });
+                ui.horizontal(|ui| {
+                    columns_changed |= ui
+                        .checkbox(&mut dashboard.fullscreen, tr!("无边框全屏", "Borderless full screen"))
+                        .changed();
+                    columns_changed |= ui
+                        .checkbox(&mut dashboard.always_on_top, tr!("置顶", "Always on top"))
+                        .changed();
+                });
+                ui.horizontal(|ui| {
+                    ui.label(tr!("放大:", "Scale:"));
+                    let scale = ui.add(
+                        egui::DragValue::new(&mut dashboard.scale)
+                            .range(0.5..=4.0)
+                            .speed(0.05)
+                            .fixed_decimals(2)
+                            .suffix("×"),
+                    );
+                    columns_changed |= scale.drag_stopped() || (scale.changed() && !scale.dragged());
+                    ui.label(tr!("每页停留:", "Page every:"));
+                    let page = ui.add(
+                        egui::DragValue::new(&mut dashboard.page_secs)
+                            .range(0..=600)
+                            .custom_formatter(|n, _| match n as u64 {
+                                0 => tr!("不翻页", "never").to_string(),
+                                n => tr!("{} 秒", "{} s", n),
+                            }),
+                    );
+                    columns_changed |= page.drag_stopped() || (page.changed() && !page.dragged());
                 });
+                ui.small(tr!(
+                    "按显示器与主显示器同样大小、从左到右排列计算位置；一屏放不下时按设置的间隔轮流显示各页，设为不翻页时改为滚动",
+                    "The position assumes displays of the same size as the primary, arranged left to right. When the tiles don't fit, pages rotate at the set interval; with paging off the dashboard scrolls instead"
+                ));
+                ui.separator();
+                ui.strong(tr!("窗口", "Window"));
+                columns_changed |= ui
+                    .checkbox(
+                        &mut self.monitor.settings.always_on_top,
+                        tr!("主窗口总在最前面", "Keep the main window on top"),
+                    )
+                    .changed();
+                ui.separator();
+                ui.strong(tr!("启动", "Startup"));
+                columns_changed |= self.show_startup_settings(ui);
+                ui.separator();
+                ui.strong(tr!("并发检查", "Concurrency"));
+                columns_changed |= self.show_concurrency_settings(ui);
+                ui.separator();
+                ui.strong(tr!("心跳", "Heartbeat"));
+                columns_changed |= self.show_heartbeat_settings(ui);
+                ui.separator();
+                ui.strong(tr!("远程备份", "Remote backup"));
+                columns_changed |= self.show_backup_settings(ui);
+                ui.separator();
+                ui.strong(tr!("指标推送", "Metrics export"));
+                columns_changed |= self.show_export_settings(ui);
+                ui.separator();
+                ui.strong(tr!("MQTT发布", "MQTT publishing"));
+                columns_changed |= self.show_mqtt_settings(ui);
+                ui.separator();
+                ui.strong(tr!("抖动检测", "Flap detection"));
+                columns_changed |= self.show_flap_settings(ui);
+                ui.separator();
+                ui.strong(tr!("从清单导入", "Inventory import"));
+                columns_changed |= self.show_inventory_settings(ui);
+                ui.separator();
+                ui.strong(tr!("从Docker导入", "Docker import"));
+                columns_changed |= self.show_docker_settings(ui);
+                ui.separator();
+                ui.strong(tr!("代理", "Proxy"));
+                if check_editor::show_proxy(ui, "global_proxy", &mut self.monitor.settings.proxy) {
+                    proxy::set_global(&self.monitor.settings.proxy);
+                    columns_changed = true;
+                }
+                ui.small(tr!(
+                    "HTTP检查、Elasticsearch、Kubernetes 等通过代理请求；留空时使用 HTTP_PROXY/HTTPS_PROXY/NO_PROXY 环境变量，服务器可以在请求设置中单独设置",
+                    "HTTP, Elasticsearch, Kubernetes and other requests go through this proxy. When empty the HTTP_PROXY/HTTPS_PROXY/NO_PROXY environment variables are used; servers can override it in their request options"
+                ));
+                ui.separator();
+                ui.strong(tr!("安全基线", "Security baseline"));
+                columns_changed |= ui
+                    .checkbox(
+                        &mut self.monitor.settings.security_baseline,
+                        tr!(
+                            "记录HTTPS服务器的TLS版本、加密套件和 HSTS/Server 响应头，变化时提示",
+                            "Record TLS version, cipher and HSTS/Server headers of HTTPS servers and warn on changes"
+                        ),
+                    )
+                    .changed();
+            });
+
+        if columns_changed {
+            self.save_settings();
         }
 
-        // 处理删除服务器
-        if let Some(index) = self.selected_server_index.take() {
-            self.remove_server(index);
+        if storage_apply {
+            self.apply_storage();
         }
 
-        // 请求重绘以保持UI响应
-        ctx.request_repaint_after(Duration::from_millis(100));
+        if font_changed {
+            fonts::init(ctx, self.monitor.settings.font_path.as_deref());
+            self.save_settings();
+        }
+
+        if language_changed {
+            i18n::set_lang(self.monitor.settings.language);
+            ctx.send_viewport_cmd(egui::ViewportCommand::Title(window_title().to_string()));
+            self.save_settings();
+        }
+
+        if theme_changed {
+            theme::apply(ctx, &self.monitor.settings, self.theme_file.as_ref());
+            self.save_settings();
+        }
+
+        self.show_settings = open;
+        if let Some(profile) = delete_profile {
+            if let Err(e) = storage::delete_profile(&*self.monitor.storage, &profile) {
+                self.settings_error =
+                    Some(tr!("删除方案失败: {}", "Failed to delete profile: {}", e));
+            }
+            self.refresh_profiles();
+        }
+        if portable != self.location.portable {
+            self.set_portable(portable);
+            // 新配置目录中的主题文件
+            self.watch_theme();
+            theme::apply(ctx, &self.monitor.settings, self.theme_file.as_ref());
+        }
+    }
+
+    // 编辑服务器对话框，包括定时动作设置
+    fn show_edit_dialog(&mut self, ctx: &egui::Context) {
+        let mut save = false;
+        let mut cancel = false;
+
+        if let Some((target, server)) = self.editing_server.as_mut() {
+            let title = match target {
+                EditTarget::Existing(_) => tr!("编辑服务器", "Edit server"),
+                EditTarget::Copy { .. } => tr!("复制服务器", "Duplicate server"),
+            };
+            egui::Window::new(title)
+                .collapsible(false)
+                .resizable(false)
+                .show(ctx, |ui| {
+                    ui.label(tr!("服务器名称:", "Server name:"));
+                    ui.text_edit_singleline(&mut server.name);
+
+                    ui.label(tr!("主机:", "Host:"));
+                    ui.text_edit_singleline(&mut server.ip);
+
+                    ui.label(tr!("端口号:", "Port:"));
+                    ui.text_edit_singleline(&mut self.edit_server_port);
+
+                    ui.label(tr!("分组:", "Group:"));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut server.group)
+                            .hint_text(tr!("可选，例如 生产环境", "Optional, e.g. production")),
+                    );
+                    ui.label(tr!("标签:", "Tags:"));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.edit_server_tags).hint_text(tr!(
+                            "可选，逗号分隔，例如 prod, gpu",
+                            "Optional, comma separated, e.g. prod, gpu"
+                        )),
+                    );
+                    ui.label(tr!("备注:", "Notes:"));
+                    ui.add(
+                        egui::TextEdit::multiline(&mut server.notes)
+                            .hint_text(tr!(
+                                "可选，例如负责人、运行的服务、重启方法",
+                                "Optional, e.g. owner, what runs there, how to restart it"
+                            ))
+                            .desired_rows(3),
+                    );
+                    ui.checkbox(
+                        &mut server.paused,
+                        tr!(
+                            "暂停检查（不计入统计）",
+                            "Pause checks (excluded from stats)"
+                        ),
+                    );
+                    ui.checkbox(
+                        &mut server.open_on_recovery,
+                        tr!(
+                            "恢复在线时自动在浏览器中打开（5分钟内最多一次）",
+                            "Open in browser when it recovers (at most once per 5 min)"
+                        ),
+                    );
+
+                    if matches!(server.check, CheckKind::Http | CheckKind::HttpSteps(_)) {
+                        ui.label("URL:");
+                        ui.add(egui::TextEdit::singleline(&mut server.url).desired_width(300.0));
+                        ui.small(tr!(
+                            "修改URL后IP和端口会随之更新",
+                            "Changing the URL also updates IP and port"
+                        ));
+                        ui.small(tr!(
+                            "可使用占位符: {}",
+                            "Placeholders: {}",
+                            template::PLACEHOLDERS.join(" ")
+                        ));
+                    }
+
+                    ui.separator();
+                    check_editor::show(ui, &mut server.check, &server.ip);
+                    if matches!(server.check, CheckKind::Http | CheckKind::HttpSteps(_)) {
+                        check_editor::show_http(ui, &mut server.http);
+                    }
+                    check_editor::show_timeout(ui, &mut server.timeout_secs);
+                    if server.check.uses_ip() {
+                        check_editor::show_ip_family(ui, &mut server.ip_family);
+                    }
+                    check_editor::show_tunnel(ui, &mut server.tunnel);
+
+                    ui.separator();
+                    check_editor::show_status_rules(ui, &mut server.status_rules);
+
+                    ui.separator();
+                    ui.strong(tr!("定时任务", "Scheduled tasks"));
+
+                    let mut remove_action = None;
+                    for (i, action) in server.actions.iter_mut().enumerate() {
+                        ui.group(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.checkbox(&mut action.enabled, tr!("启用", "Enabled"));
+                                ui.label(tr!("每天", "Daily at"));
+                                ui.add(egui::DragValue::new(&mut action.hour).range(0..=23));
+                                ui.label(":");
+                                ui.add(egui::DragValue::new(&mut action.minute).range(0..=59));
+                                egui::ComboBox::from_id_source(("action_kind", i))
+                                    .selected_text(action.kind.label())
+                                    .show_ui(ui, |ui| {
+                                        for kind in [ActionKind::Remind, ActionKind::Webhook] {
+                                            ui.selectable_value(
+                                                &mut action.kind,
+                                                kind,
+                                                kind.label(),
+                                            );
+                                        }
+                                    });
+                                if ui.small_button("🗑").clicked() {
+                                    remove_action = Some(i);
+                                }
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label(tr!("内容:", "Message:"));
+                                ui.text_edit_singleline(&mut action.message);
+                            });
+                            if action.kind == ActionKind::Webhook {
+                                ui.horizontal(|ui| {
+                                    ui.label("URL:");
+                                    ui.text_edit_singleline(&mut action.webhook_url);
+                                });
+                            }
+                            if let Some(date) = action.last_run {
+                                ui.small(tr!("上次执行: {}", "Last run: {}", date));
+                            }
+                        });
+                    }
+                    if let Some(i) = remove_action {
+                        server.actions.remove(i);
+                    }
+
+                    if ui
+                        .button(tr!("➕ 添加定时任务", "➕ Add scheduled task"))
+                        .clicked()
+                    {
+                        server.actions.push(ScheduledAction::default());
+                    }
+
+                    ui.separator();
+                    ui.horizontal(|ui| {
+                        if ui.button(tr!("保存", "Save")).clicked() {
+                            save = true;
+                        }
+                        if ui.button(tr!("取消", "Cancel")).clicked() {
+                            cancel = true;
+                        }
+                    });
+                });
+        }
+
+        if save {
+            self.apply_edit_server();
+        } else if cancel {
+            self.editing_server = None;
+        }
     }
 }
 
-// 初始化中文字体支持
-fn init_chinese_font(ctx: &egui::Context) {
-    let mut fonts = egui::FontDefinitions::default();
-
-    // 定义不同操作系统的中文字体路径
-    let font_paths = if cfg!(target_os = "windows") {
-        vec![
-            "C:\\Windows\\Fonts\\msyh.ttc",   // 微软雅黑
-            "C:\\Windows\\Fonts\\simhei.ttf", // 黑体
-            "C:\\Windows\\Fonts\\simsun.ttc", // 宋体
-        ]
-    } else if cfg!(target_os = "macos") {
-        vec![
-            "/System/Library/Fonts/PingFang.ttc",         // 苹方
-            "/System/Library/Fonts/Hiragino Sans GB.ttc", // 冬青黑体
-            "/System/Library/Fonts/STHeiti Light.ttc",    // 华文黑体
-        ]
-    } else {
-        // Linux
-        vec![
-            "/usr/share/fonts/truetype/droid/DroidSansFallbackFull.ttf",
-            "/usr/share/fonts/truetype/wqy/wqy-microhei.ttc",
-            "/usr/share/fonts/truetype/wqy/wqy-zenhei.ttc",
-            "/usr/share/fonts/opentype/noto/NotoSansCJK-Regular.ttc",
-        ]
-    };
+// 主机指标的使用率进度条，超过阈值的项显示为降级颜色
+#[cfg(feature = "gui")]
+fn show_metric_gauges(ui: &mut egui::Ui, metrics: &HostMetrics, check: &MetricsCheck) {
+    let items = [
+        ("CPU", metrics.cpu, check.cpu_threshold),
+        (tr!("内存", "Mem"), metrics.mem, check.mem_threshold),
+        (tr!("磁盘", "Disk"), metrics.disk, check.disk_threshold),
+    ];
+    for (label, value, threshold) in items {
+        ui.horizontal(|ui| {
+            ui.add_sized(
+                [32.0, 14.0],
+                egui::Label::new(egui::RichText::new(label).small()),
+            );
+            match value {
+                Some(value) => {
+                    let mut bar = egui::ProgressBar::new(value / 100.0)
+                        .desired_width(140.0)
+                        .text(format!("{:.0}%", value));
+                    if value >= threshold {
+                        bar = bar.fill(ServerStatus::Degraded.color());
+                    }
+                    ui.add(bar);
+                }
+                None => {
+                    ui.small("-");
+                }
+            }
+        });
+    }
+}
 
-    // 尝试加载中文字体
-    let mut font_loaded = false;
-    for font_path in font_paths {
-        if Path::new(font_path).exists() {
-            if let Ok(font_data) = std::fs::read(font_path) {
-                fonts.font_data.insert(
-                    "chinese_font".to_owned(),
-                    egui::FontData::from_owned(font_data),
-                );
+// 自定义列设置，返回是否有修改
+#[cfg(feature = "gui")]
+fn show_column_settings(ui: &mut egui::Ui, columns: &mut Vec<ColumnDef>) -> bool {
+    let mut changed = false;
+    ui.small(tr!(
+        "从HTTP检查的响应中提取数据，显示在列表中，例如响应头 Server 或JSON字段 build.version",
+        "Values extracted from HTTP check responses, e.g. the Server header or the JSON field build.version"
+    ));
+    let mut remove = None;
+    egui::Grid::new("custom_columns")
+        .num_columns(5)
+        .show(ui, |ui| {
+            for (i, column) in columns.iter_mut().enumerate() {
+                changed |= ui
+                    .add(
+                        egui::TextEdit::singleline(&mut column.title)
+                            .hint_text(tr!("列标题", "Title"))
+                            .desired_width(80.0),
+                    )
+                    .changed();
+                egui::ComboBox::from_id_source(("column_source", i))
+                    .selected_text(column.source.label())
+                    .show_ui(ui, |ui| {
+                        for source in ColumnSource::ALL {
+                            changed |= ui
+                                .selectable_value(&mut column.source, source, source.label())
+                                .changed();
+                        }
+                    });
+                let hint = match column.source {
+                    ColumnSource::Header => "X-App-Version",
+                    ColumnSource::JsonField => "build.version",
+                };
+                changed |= ui
+                    .add(
+                        egui::TextEdit::singleline(&mut column.key)
+                            .hint_text(hint)
+                            .desired_width(120.0),
+                    )
+                    .changed();
+                changed |= ui
+                    .checkbox(&mut column.version, tr!("版本", "Version"))
+                    .on_hover_text(tr!(
+                        "同一分组内的值不一致时提示",
+                        "Warn when servers in a group report different values"
+                    ))
+                    .changed();
+                if ui.small_button("🗑").clicked() {
+                    remove = Some(i);
+                }
+                ui.end_row();
+            }
+        });
+    if let Some(i) = remove {
+        columns.remove(i);
+        changed = true;
+    }
+    if ui.button(tr!("➕ 添加列", "➕ Add column")).clicked() {
+        columns.push(ColumnDef::default());
+        changed = true;
+    }
+    changed
+}
 
-                // 将中文字体添加到字体族中
-                fonts
-                    .families
-                    .entry(egui::FontFamily::Proportional)
-                    .or_default()
-                    .insert(0, "chinese_font".to_owned());
-
-                fonts
-                    .families
-                    .entry(egui::FontFamily::Monospace)
-                    .or_default()
-                    .push("chinese_font".to_owned());
-
-                font_loaded = true;
-                println!("找到中文字体: {}", font_path);
-                break;
+// 表格中按钮触发的操作
+#[cfg(feature = "gui")]
+enum RowAction {
+    ToggleSelect,
+    Details,
+    Chart,
+    FilterTag(String),
+    Edit,
+    Duplicate,
+    TogglePause,
+    Delete,
+}
+
+// 紧凑表格视图：每台服务器一行，返回被点击的行（服务器下标）和操作
+#[cfg(feature = "gui")]
+fn show_server_table(
+    ui: &mut egui::Ui,
+    servers: &[Server],
+    visible: &[usize],
+    selected: &BTreeSet<usize>,
+    settings: &Settings,
+    active_tags: &[String],
+    drift: &[VersionDrift],
+) -> Option<(usize, RowAction)> {
+    let columns = &settings.columns;
+    let mut action = None;
+    egui::Grid::new("server_table")
+        .striped(true)
+        .num_columns(8 + columns.len())
+        .spacing([12.0, 4.0])
+        .show(ui, |ui| {
+            let headers = match i18n::lang() {
+                Lang::Zh => ["名称", "地址", "状态", "延迟", "健康", "上次检查", "操作"],
+                Lang::En => [
+                    "Name",
+                    "Address",
+                    "Status",
+                    "Latency",
+                    "Health",
+                    "Last check",
+                    "Actions",
+                ],
+            };
+            // 自定义列放在操作列之前
+            let (fixed, actions) = headers.split_at(headers.len() - 1);
+            // 勾选列
+            ui.label("");
+            for header in fixed {
+                ui.strong(*header);
+            }
+            for column in columns {
+                ui.strong(&column.title);
+            }
+            ui.strong(actions[0]);
+            ui.end_row();
+
+            for &i in visible {
+                let server = &servers[i];
+                let mut checked = selected.contains(&i);
+                if ui.checkbox(&mut checked, "").changed() {
+                    action = Some((i, RowAction::ToggleSelect));
+                }
+                let name = if server.paused {
+                    tr!("{} ⏸", "{} ⏸", server.name)
+                } else if server.state.maintenance {
+                    format!("{} 🔧", server.name)
+                } else if server.state.flap.is_flapping() {
+                    format!("{} 🔁", server.name)
+                } else {
+                    server.name.clone()
+                };
+                ui.horizontal(|ui| {
+                    if copy_menu_label(ui, name, server).clicked() {
+                        action = Some((i, RowAction::Details));
+                    }
+                    for tag in &server.tags {
+                        let selected = active_tags.contains(tag);
+                        if tag_chip(ui, tag, &settings.tag_colors, selected).clicked() {
+                            action = Some((i, RowAction::FilterTag(tag.clone())));
+                        }
+                    }
+                });
+                // 地址可能很长，截断显示，悬停查看完整内容
+                let target = server.target_label();
+                ui.scope(|ui| {
+                    ui.set_max_width(200.0);
+                    ui.add(
+                        egui::Label::new(&target)
+                            .truncate()
+                            .sense(egui::Sense::click()),
+                    )
+                    .on_hover_text(&target)
+                    .context_menu(|ui| show_copy_menu(ui, server));
+                });
+                let mut status_text = server.status_label(chrono::Local::now());
+                if server.state.stale {
+                    status_text.push_str(tr!(" (已过期)", " (stale)"));
+                }
+                if !server.state.security_changes.is_empty() {
+                    status_text.push_str(" 🔒");
+                }
+                let status = ui
+                    .horizontal(|ui| {
+                        let status = ui.colored_label(server.status.color(), status_text);
+                        if server.state.checking {
+                            ui.spinner().on_hover_text(tr!("检查中…", "Checking…"));
+                        }
+                        status
+                    })
+                    .inner;
+                let mut hover: Vec<&str> = Vec::new();
+                if !server.state.detail.is_empty() {
+                    hover.push(&server.state.detail);
+                }
+                hover.extend(server.state.security_changes.iter().map(String::as_str));
+                if !hover.is_empty() {
+                    status.on_hover_text(hover.join("\n"));
+                }
+                match server.state.latency_ms {
+                    Some(ms) => ui.label(format!("{}ms", ms)),
+                    None => ui.label("-"),
+                };
+                match &server.state.health {
+                    Some(health) => show_health_badge(ui, health),
+                    None => {
+                        ui.label("-");
+                    }
+                }
+                match server.state.last_checked {
+                    Some(time) => ui.label(time.format("%H:%M:%S").to_string()),
+                    None => ui.label("-"),
+                };
+                for column in columns {
+                    match server.state.fields.get(&column.title) {
+                        Some(value) if columns::is_drifted(drift, server, &column.title) => ui
+                            .colored_label(ServerStatus::Degraded.color(), format!("{} ⚠", value))
+                            .on_hover_text(tr!(
+                                "与分组内多数服务器的版本不同",
+                                "Differs from most servers in the group"
+                            )),
+                        Some(value) => ui.label(value),
+                        None => ui.label("-"),
+                    };
+                }
+                ui.horizontal(|ui| {
+                    if ui
+                        .small_button("🌐")
+                        .on_hover_text(tr!("打开", "Open"))
+                        .clicked()
+                    {
+                        if let Err(e) = webbrowser::open(&template::expand(&server.url)) {
+                            eprintln!("无法打开浏览器: {}", e);
+                        }
+                    }
+                    if ui
+                        .small_button("✏")
+                        .on_hover_text(tr!("编辑", "Edit"))
+                        .clicked()
+                    {
+                        action = Some((i, RowAction::Edit));
+                    }
+                    if ui
+                        .small_button("📈")
+                        .on_hover_text(tr!("延迟图表", "Latency chart"))
+                        .clicked()
+                    {
+                        action = Some((i, RowAction::Chart));
+                    }
+                    if ui
+                        .small_button("📋")
+                        .on_hover_text(tr!("复制", "Duplicate"))
+                        .clicked()
+                    {
+                        action = Some((i, RowAction::Duplicate));
+                    }
+                    let (icon, hint) = if server.paused {
+                        ("▶", tr!("恢复", "Resume"))
+                    } else {
+                        ("⏸", tr!("暂停", "Pause"))
+                    };
+                    if ui.small_button(icon).on_hover_text(hint).clicked() {
+                        action = Some((i, RowAction::TogglePause));
+                    }
+                    if ui
+                        .small_button("🗑")
+                        .on_hover_text(tr!("删除", "Delete"))
+                        .clicked()
+                    {
+                        action = Some((i, RowAction::Delete));
+                    }
+                });
+                ui.end_row();
             }
+        });
+    action
+}
+
+// 标签色块，点击按标签筛选
+#[cfg(feature = "gui")]
+fn tag_chip(
+    ui: &mut egui::Ui,
+    tag: &str,
+    colors: &BTreeMap<String, [u8; 3]>,
+    selected: bool,
+) -> egui::Response {
+    let [r, g, b] = tags::color(tag, colors);
+    let mut button =
+        egui::Button::new(egui::RichText::new(tag).small().color(egui::Color32::WHITE))
+            .fill(egui::Color32::from_rgb(r, g, b))
+            .rounding(8.0)
+            .small();
+    if selected {
+        button = button.stroke(ui.visuals().selection.stroke);
+    }
+    ui.add(button)
+        .on_hover_text(tr!("点击按标签筛选", "Click to filter by tag"))
+}
+
+// 健康分徽章，悬停显示各项得分
+#[cfg(feature = "gui")]
+fn show_health_badge(ui: &mut egui::Ui, health: &HealthScore) {
+    ui.colored_label(
+        theme::health_color(health.score),
+        format!("♥ {}", health.score),
+    )
+    .on_hover_text(health.breakdown());
+}
+
+// 点击打开详情、右键可以复制地址的服务器名称
+#[cfg(feature = "gui")]
+fn copy_menu_label(
+    ui: &mut egui::Ui,
+    text: impl Into<egui::WidgetText>,
+    server: &Server,
+) -> egui::Response {
+    let mut hint = tr!(
+        "点击查看详情，右键复制地址",
+        "Click for details, right-click to copy the address"
+    )
+    .to_string();
+    if server.paused {
+        hint = format!("{}\n{}", tr!("已暂停", "Paused"), hint);
+    } else if server.state.maintenance {
+        hint = format!("{}\n{}", tr!("维护中", "Maintenance"), hint);
+    } else if server.state.flap.is_flapping() {
+        hint = format!(
+            "{}\n{}",
+            tr!(
+                "抖动：{} 次状态变化",
+                "Flapping: {} status changes",
+                server.state.flap.change_count()
+            ),
+            hint
+        );
+    }
+    let response = ui
+        .add(egui::Label::new(text).sense(egui::Sense::click()))
+        .on_hover_text(hint);
+    response.context_menu(|ui| show_copy_menu(ui, server));
+    response
+}
+
+// 复制URL、主机:端口或curl命令，方便粘贴到终端或聊天中
+#[cfg(feature = "gui")]
+fn show_copy_menu(ui: &mut egui::Ui, server: &Server) {
+    let url = template::expand(&server.url).into_owned();
+    let mut copied = None;
+    if ui.button(tr!("复制URL", "Copy URL")).clicked() {
+        copied = Some(url.clone());
+    }
+    if ui.button(tr!("复制IP:端口", "Copy IP:port")).clicked() {
+        copied = Some(address::host_port(&server.ip, server.port));
+    }
+    if server.check == CheckKind::Http
+        && ui
+            .button(tr!("复制为curl命令", "Copy as curl command"))
+            .clicked()
+    {
+        copied = Some(address::curl_command(&url, &server.http));
+    }
+    if let Some(text) = copied {
+        ui.ctx().copy_text(text);
+        ui.close_menu();
+    }
+}
+
+// 检查间隔的显示：90 -> 1分30秒
+fn format_interval(secs: u64) -> String {
+    let (minutes, seconds) = (secs / 60, secs % 60);
+    match (minutes, seconds) {
+        (0, s) => tr!("{}秒", "{}s", s),
+        (m, 0) if m % 60 == 0 => tr!("{}小时", "{}h", m / 60),
+        (m, 0) => tr!("{}分钟", "{}min", m),
+        (m, s) => tr!("{}分{}秒", "{}min {}s", m, s),
+    }
+}
+
+fn window_title() -> &'static str {
+    tr!("服务器状态监控 - Rust版", "Server Monitor - Rust")
+}
+
+// 编辑时填写的URL没有协议（如 api.example.com/health）时补全，否则原样保留
+fn with_scheme(url: &str, parsed: &address::ParsedAddress) -> String {
+    if url.contains("://") {
+        url.to_string()
+    } else {
+        parsed.full_url()
+    }
+}
+
+// 打开设置中的存储，失败时退回到JSON文件并返回错误信息
+fn open_storage(
+    settings: &StorageSettings,
+    location: &ConfigLocation,
+) -> (Arc<dyn Storage>, Option<String>) {
+    match storage::open(settings, location) {
+        Ok(storage) => (storage, None),
+        Err(e) => {
+            eprintln!("无法打开存储: {}，改用JSON文件", e);
+            let fallback = StorageSettings::default();
+            let storage = storage::open(&fallback, location).expect("JSON文件存储不会打开失败");
+            (
+                storage,
+                Some(tr!(
+                    "无法打开存储，已改用JSON文件: {}",
+                    "Failed to open storage, using JSON files: {}",
+                    e
+                )),
+            )
         }
     }
+}
 
-    if !font_loaded {
-        println!("警告: 未找到中文字体，中文可能显示为方块");
+fn group_label(group: &str) -> &str {
+    if group.is_empty() {
+        tr!("未分组", "Ungrouped")
+    } else {
+        group
     }
+}
 
-    ctx.set_fonts(fonts);
+// 默认方案的名称是文件标识，显示时按界面语言翻译
+fn profile_label(profile: &str) -> &str {
+    if profile == DEFAULT_PROFILE {
+        tr!("默认", "Default")
+    } else {
+        profile
+    }
 }
 
 #[tokio::main]
-async fn main() -> Result<(), eframe::Error> {
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // 设置日志
     env_logger::init();
 
-    // 加载图标
-    let icon_data = include_bytes!("../Icon.png");
-    let icon = eframe::icon_data::from_png_bytes(icon_data).unwrap_or_else(|err| {
-        eprintln!("加载图标失败: {}", err);
-        egui::IconData::default()
-    });
+    // 命令行子命令执行完直接退出，不启动界面
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let parsed = cli::parse(&args);
+    if !matches!(parsed, Ok(None)) {
+        cli::attach_console();
+    }
+    match parsed {
+        Ok(Some(cli::CliArgs {
+            command: cli::CliCommand::Serve(listen),
+            profile,
+        })) => std::process::exit(headless::run(listen, profile).await),
+        Ok(Some(args)) => std::process::exit(cli::run(args)),
+        Ok(None) => {}
+        Err(e) => {
+            cli::print_error(&format!("{}\n\n{}", e, cli::usage()));
+            std::process::exit(2);
+        }
+    }
+
+    // 不带界面构建时直接以无界面模式运行
+    #[cfg(not(feature = "gui"))]
+    std::process::exit(headless::run(None, None).await);
+    #[cfg(feature = "gui")]
+    run_gui()?;
+    #[cfg(feature = "gui")]
+    Ok(())
+}
+
+#[cfg(feature = "gui")]
+fn run_gui() -> Result<(), eframe::Error> {
+    // 加载图标，窗口创建前需要先读取设置中指定的图标文件
+    let settings = Settings::load(&ConfigLocation::resolve().settings_path());
+    let icon = icon::load(settings.icon_path.as_deref());
 
+    // 恢复上次退出时的窗口大小和位置
+    let mut viewport = egui::ViewportBuilder::default()
+        .with_inner_size(settings.window.size())
+        .with_title(window_title())
+        .with_resizable(true)
+        .with_maximized(settings.window.maximized)
+        .with_icon(icon);
+    if let Some(position) = settings.window.position {
+        viewport = viewport.with_position(position);
+    }
     let options = eframe::NativeOptions {
-        viewport: egui::ViewportBuilder::default()
-            .with_inner_size([490.0, 650.0])
-            .with_title("服务器状态监控 - Rust版")
-            .with_resizable(true)
-            .with_icon(icon),
+        viewport,
+        // 主题由设置控制
+        follow_system_theme: false,
         ..Default::default()
     };
 
@@ -482,9 +4453,192 @@ async fn main() -> Result<(), eframe::Error> {
         "服务器状态监控",
         options,
         Box::new(|cc| {
-            // 初始化中文字体
-            init_chinese_font(&cc.egui_ctx);
-            Ok(Box::new(ServerMonitorApp::default()))
+            theme::init(cc);
+            let app = ServerMonitorApp::default();
+            // 初始化中文字体，设置中可以指定字体文件
+            fonts::init(&cc.egui_ctx, app.monitor.settings.font_path.as_deref());
+            theme::apply(&cc.egui_ctx, &app.monitor.settings, app.theme_file.as_ref());
+            // 窗口创建时还没有读取设置，这里按设置的语言更新标题
+            cc.egui_ctx
+                .send_viewport_cmd(egui::ViewportCommand::Title(window_title().to_string()));
+            Ok(Box::new(app))
         }),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_reloaded_servers_by_id() {
+        let mut web = Server::new("web".to_string(), "10.0.0.1".to_string(), 80);
+        web.status = ServerStatus::Online;
+        let mut manual = Server::new("db".to_string(), "10.0.0.2".to_string(), 5432);
+        manual.status = ServerStatus::Offline;
+        let mut api = Server::new("api".to_string(), "10.0.0.4".to_string(), 80);
+        api.status = ServerStatus::Online;
+        let current = vec![web.clone(), manual.clone(), api.clone()];
+
+        // 文件中改了名字，ID不变时沿用状态；没有ID的条目按名称和URL找到原来的
+        let mut renamed = web.clone();
+        renamed.name = "web-1".to_string();
+        renamed.status = ServerStatus::Unchecked;
+        let mut reloaded_manual = manual.clone();
+        reloaded_manual.id = Uuid::new_v4();
+        reloaded_manual.status = ServerStatus::Unchecked;
+        // URL改了的保留ID，不沿用状态
+        let mut moved = api.clone();
+        moved.url = "http://10.0.0.9/".to_string();
+        moved.status = ServerStatus::Unchecked;
+        let added = Server::new("new".to_string(), "10.0.0.3".to_string(), 80);
+
+        let merged = merge_servers(
+            &current,
+            vec![renamed, reloaded_manual, moved, added.clone()],
+        );
+        assert_eq!(merged[0].id, web.id);
+        assert_eq!(merged[0].name, "web-1");
+        assert_eq!(merged[0].status, ServerStatus::Online);
+        assert_eq!(merged[1].id, manual.id);
+        assert_eq!(merged[1].status, ServerStatus::Offline);
+        assert_eq!(merged[2].id, api.id);
+        assert_eq!(merged[2].status, ServerStatus::Unchecked);
+        assert_eq!(merged[3].id, added.id);
+    }
+
+    #[test]
+    fn replaces_duplicate_ids() {
+        let first = Server::new("a".to_string(), "10.0.0.1".to_string(), 80);
+        let mut copy = Server::new("b".to_string(), "10.0.0.2".to_string(), 80);
+        copy.id = first.id;
+        let mut servers = vec![first.clone(), copy, first.clone()];
+        ensure_unique_ids(&mut servers);
+        // 第一个保留原来的ID，之后重复的都换成新的
+        assert_eq!(servers[0].id, first.id);
+        let ids: BTreeSet<_> = servers.iter().map(|s| s.id).collect();
+        assert_eq!(ids.len(), 3);
+    }
+
+    #[cfg(feature = "gui")]
+    fn open_app(dir: &testing::TempDir) -> ServerMonitorApp {
+        let location = ConfigLocation {
+            portable: false,
+            dir: dir.to_path_buf(),
+        };
+        ServerMonitorApp::open(location, Arc::new(SystemClock))
+    }
+
+    // 模拟外部程序修改配置文件
+    #[cfg(feature = "gui")]
+    fn edit_servers_file(app: &ServerMonitorApp, update: impl FnOnce(&mut Vec<Server>)) {
+        let doc = app.servers_doc();
+        let content = app.monitor.storage.load(&doc).unwrap().unwrap();
+        let mut servers: Vec<Server> = serde_json::from_str(&content).unwrap();
+        update(&mut servers);
+        let json = serde_json::to_string_pretty(&servers).unwrap();
+        app.monitor.storage.save(&doc, &json).unwrap();
+    }
+
+    #[cfg(feature = "gui")]
+    #[tokio::test]
+    async fn keeps_gui_edits_when_the_file_changes() {
+        let dir = testing::TempDir::new("reload-edits");
+        let mut app = open_app(&dir);
+        app.start_edit_server(0);
+        app.editing_server.as_mut().unwrap().1.name = "edited".to_string();
+        app.apply_edit_server();
+
+        edit_servers_file(&app, |servers| {
+            servers.push(Server::new("new".to_string(), "10.0.0.3".to_string(), 80));
+        });
+        assert!(app.reload_servers_from_disk().unwrap());
+        let names: Vec<_> = app
+            .monitor
+            .servers
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(names, ["edited", "new"]);
+    }
+
+    #[cfg(feature = "gui")]
+    #[tokio::test]
+    async fn cli_add_keeps_gui_edits() {
+        let dir = testing::TempDir::new("reload-cli");
+        let mut app = open_app(&dir);
+        app.start_edit_server(0);
+        app.editing_server.as_mut().unwrap().1.group = "edited".to_string();
+        app.apply_edit_server();
+
+        let args = cli::CliArgs {
+            command: cli::CliCommand::Add(cli::AddArgs {
+                name: Some("web-01".to_string()),
+                url: "https://10.0.0.5/health".to_string(),
+                group: None,
+                tags: None,
+            }),
+            profile: None,
+        };
+        cli::apply(&args, &app.location, &app.monitor.settings).unwrap();
+        // 命令行修改后通知界面重新加载
+        assert!(app.reload_servers_from_disk().unwrap());
+        let servers = &app.monitor.servers;
+        assert_eq!(servers.len(), 2);
+        assert_eq!(servers[0].group, "edited");
+        assert_eq!(servers[1].name, "web-01");
+    }
+
+    #[cfg(feature = "gui")]
+    #[tokio::test]
+    async fn duplicate_keeps_every_setting() {
+        let dir = testing::TempDir::new("duplicate");
+        let mut app = open_app(&dir);
+        let original = &mut app.monitor.servers[0];
+        original.group = "prod".to_string();
+        original.paused = true;
+        original.notes = "note".to_string();
+        original.tags = vec!["gpu".to_string()];
+        original.timeout_secs = Some(20);
+        original.http.user_agent = "probe".to_string();
+        original.ip_family = IpFamily::Both;
+        original.status = ServerStatus::Online;
+        original.state.detail = "ok".to_string();
+        let original = original.clone();
+
+        app.start_duplicate_server(0);
+        let (_, copy) = app.editing_server.as_ref().unwrap();
+        assert_ne!(copy.id, original.id);
+        assert_eq!(copy.port, original.port + 1);
+        assert_eq!(copy.status, ServerStatus::Unchecked);
+        assert!(copy.state.detail.is_empty());
+        // 除了换掉的几项，其余序列化后完全相同
+        let settings = |server: &Server| {
+            let mut value = serde_json::to_value(server).unwrap();
+            let map = value.as_object_mut().unwrap();
+            for key in ["id", "name", "port", "url", "status"] {
+                map.remove(key);
+            }
+            value
+        };
+        assert_eq!(settings(copy), settings(&original));
+    }
+
+    #[cfg(feature = "gui")]
+    #[tokio::test]
+    async fn undo_after_reload_does_not_duplicate() {
+        let dir = testing::TempDir::new("reload-undo");
+        let mut app = open_app(&dir);
+        let server = app.monitor.servers[0].clone();
+        app.remove_servers(vec![0]);
+        assert!(app.monitor.servers.is_empty());
+
+        // 撤销之前外部又把它加了回来
+        edit_servers_file(&app, |servers| servers.push(server.clone()));
+        assert!(app.reload_servers_from_disk().unwrap());
+        assert!(app.deleted.is_none());
+        app.undo_delete();
+        let ids: Vec<_> = app.monitor.servers.iter().map(|s| s.id).collect();
+        assert_eq!(ids, [server.id]);
+    }
+}
@@ -0,0 +1,535 @@
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Instant;
+
+use chrono::{DateTime, Local};
+
+use crate::backup::{self, LastBackup};
+use crate::baseline::Baselines;
+use crate::checks::transition;
+use crate::clock::Clock;
+use crate::config::Settings;
+use crate::digest::StatusChange;
+use crate::exporter::{self, LastExport};
+use crate::heartbeat::Heartbeat;
+use crate::history::History;
+use crate::i18n::tr;
+use crate::mqtt_publish::{HaDiscovery, Publisher};
+use crate::notify::{self, StatusEvent};
+use crate::report;
+use crate::schedule::{
+    self, ActionKind, ActionLog, DueAction, IntervalTimer, Scheduler, StartupTimer,
+};
+use crate::storage::Storage;
+use crate::worker::{CheckWorker, WorkerEvent};
+use crate::{health, Server, ServerStatus, HISTORY_SAVE_INTERVAL};
+
+// 界面和无界面模式共用的监控核心：按计划提交检查，合并结果并记录历史、发送通知，
+// 以及心跳、远程备份、指标推送、MQTT 发布、SLA报告和定时动作。界面只负责显示和编辑
+pub struct Monitor {
+    pub servers: Vec<Server>,
+    pub settings: Settings,
+    // 服务器列表、历史记录和安全基线的存储
+    pub storage: Arc<dyn Storage>,
+    // 状态历史与可用率统计
+    pub history: History,
+    // 安全配置基线
+    pub baselines: Baselines,
+    // 时间来源，测试时可替换为虚拟时钟
+    pub clock: Arc<dyn Clock>,
+    pub client: reqwest::Client,
+    // 后台检查任务，切换配置方案时丢弃进行中的结果
+    pub worker: CheckWorker,
+    pub check_timer: IntervalTimer,
+    // 启动后的首次检查延迟与通知宽限期
+    pub startup: StartupTimer,
+    // 向外部监控发送心跳的后台任务，以及最近一次的结果
+    pub heartbeat: Heartbeat,
+    // 远程备份的计时，以及最近一次的结果
+    pub backup_timer: IntervalTimer,
+    pub last_backup: LastBackup,
+    // 启动后第一次更新时检查备份是否已经过期
+    backup_startup_pending: bool,
+    pub last_export: LastExport,
+    pub mqtt: Publisher,
+    ha_discovery: HaDiscovery,
+    // 定期检查是否需要生成SLA报告，报告保存在 reports_dir 中
+    report_timer: IntervalTimer,
+    pub reports_dir: PathBuf,
+    scheduler: Scheduler,
+    pub action_log: ActionLog,
+    // 最近一次检查判断本机网络已断开
+    pub network_down: bool,
+    // 在此之前不发送状态变化通知
+    pub muted_until: Option<DateTime<Local>>,
+    last_history_save: Instant,
+}
+
+// 一次更新中需要界面显示或写到终端的内容
+#[derive(Default)]
+pub struct Updates {
+    // 安全基线变化、开始或停止抖动、生成了报告等提示
+    pub alerts: Vec<String>,
+    pub changes: Vec<StatusChange>,
+    // 到期的提醒
+    pub reminders: Vec<DueAction>,
+    // 从故障恢复后要在浏览器中打开的地址
+    pub open_urls: Vec<String>,
+}
+
+impl Monitor {
+    pub fn new(
+        settings: Settings,
+        storage: Arc<dyn Storage>,
+        clock: Arc<dyn Clock>,
+        client: reqwest::Client,
+        reports_dir: PathBuf,
+    ) -> Self {
+        let now = clock.instant();
+        let action_log = ActionLog::default();
+        Self {
+            servers: Vec::new(),
+            history: History::load(&*storage),
+            baselines: Baselines::load(&*storage),
+            worker: CheckWorker::spawn(client.clone(), Arc::clone(&clock)),
+            check_timer: IntervalTimer::new(settings.check_interval(), now),
+            startup: StartupTimer::new(now),
+            heartbeat: Heartbeat::spawn(
+                &settings.heartbeat,
+                &client,
+                Arc::clone(&clock),
+                &action_log,
+            ),
+            backup_timer: IntervalTimer::new(settings.backup.interval(), now),
+            last_backup: backup::load_last(&*storage),
+            backup_startup_pending: true,
+            last_export: LastExport::default(),
+            mqtt: Publisher::spawn(&action_log),
+            ha_discovery: HaDiscovery::default(),
+            report_timer: IntervalTimer::new(report::REPORT_CHECK_INTERVAL, now),
+            reports_dir,
+            scheduler: Scheduler::new(clock.now().naive_local()),
+            network_down: false,
+            muted_until: None,
+            last_history_save: now,
+            action_log,
+            settings,
+            storage,
+            clock,
+            client,
+        }
+    }
+
+    // 每次界面刷新或无界面模式每秒调用一次
+    pub fn tick(&mut self) -> Updates {
+        let mut updates = self.apply_check_results();
+
+        // 启动后先等待设置的延迟，之后按间隔检查，上一轮还没结束时跳过，避免检查排队
+        let now = self.clock.instant();
+        if self.settings.auto_check_enabled {
+            if self.startup.first_check_pending() {
+                if self
+                    .startup
+                    .first_check_due(now, self.settings.startup_delay())
+                {
+                    self.check_timer.reset(now);
+                    self.check_servers(None);
+                }
+            } else if self.check_timer.poll(now) && !self.worker.is_checking() {
+                self.check_servers(None);
+            }
+        }
+        if self.startup.grace_ended(now, self.settings.startup_grace()) {
+            self.notify_still_down();
+        }
+
+        self.heartbeat.update(&self.settings.heartbeat);
+        let startup_backup = std::mem::take(&mut self.backup_startup_pending)
+            && backup::overdue(&self.last_backup, &self.settings.backup, self.clock.now());
+        if self.settings.backup.active() && (startup_backup || self.backup_timer.poll(now)) {
+            self.backup_timer.reset(now);
+            self.run_backup();
+        }
+        if self.settings.report.enabled && self.report_timer.poll(now) {
+            match self.generate_report(false) {
+                Ok(Some(path)) => updates.alerts.push(tr!(
+                    "📑 SLA报告已保存到 {}",
+                    "📑 SLA report saved to {}",
+                    path.display()
+                )),
+                Ok(None) => {}
+                Err(e) => eprintln!("生成SLA报告失败: {}", e),
+            }
+        }
+
+        updates.reminders = self.run_scheduled_actions();
+        self.update_maintenance();
+
+        if now.saturating_duration_since(self.last_history_save) >= HISTORY_SAVE_INTERVAL {
+            self.save_history();
+        }
+        updates
+    }
+
+    // 检查服务器状态，only 为要检查的下标，None 表示全部；跳过已暂停的服务器
+    pub fn check_servers(&mut self, only: Option<&BTreeSet<usize>>) {
+        self.startup.checked();
+        let servers: Vec<Server> = self
+            .servers
+            .iter()
+            .enumerate()
+            .filter(|(i, s)| !s.paused && only.is_none_or(|o| o.contains(i)))
+            .map(|(_, s)| s.clone())
+            .collect();
+        self.worker.submit(
+            servers,
+            Arc::new(self.settings.columns.clone()),
+            self.settings.security_baseline,
+            self.settings.concurrency,
+        );
+    }
+
+    pub fn alerts_muted(&self) -> bool {
+        self.muted_until.is_some_and(|t| self.clock.now() < t)
+    }
+
+    // 启动宽限期结束时，对仍然离线或出错的服务器补发一次通知
+    fn notify_still_down(&mut self) {
+        let now = self.clock.now();
+        let down: Vec<&Server> = self
+            .servers
+            .iter()
+            .filter(|s| !s.paused && !s.state.maintenance)
+            .filter(|s| matches!(s.status, ServerStatus::Offline | ServerStatus::Error(_)))
+            .collect();
+        if down.is_empty() || self.alerts_muted() {
+            return;
+        }
+        self.action_log.push(tr!(
+            "启动宽限期结束，{} 台服务器仍然异常",
+            "Startup grace period ended, {} servers are still down",
+            down.len()
+        ));
+        for server in down {
+            let change = StatusChange {
+                at: now,
+                server: server.name.clone(),
+                from: ServerStatus::Unchecked,
+                to: server.status.clone(),
+                down_for: None,
+            };
+            let event = StatusEvent::new(&change, &server.url);
+            notify::notify_all(
+                &self.client,
+                &self.settings.channels,
+                event,
+                &self.action_log,
+            );
+        }
+    }
+
+    // 合并后台检查任务送回的结果；检查期间配置可能被重新加载，按ID匹配而不是按位置。
+    // 第一次检查、启动宽限期内、静音、维护和抖动期间不逐次通知
+    fn apply_check_results(&mut self) -> Updates {
+        let mut updates = Updates::default();
+        let mut baselines_changed = false;
+        let now = self.clock.now();
+        let quiet = self.alerts_muted()
+            || self
+                .startup
+                .in_grace(self.clock.instant(), self.settings.startup_grace());
+        let mut cycle_done = false;
+        let mut mqtt_messages = Vec::new();
+        for event in self.worker.poll() {
+            let result = match event {
+                WorkerEvent::Result(result) => *result,
+                WorkerEvent::NetworkDown => {
+                    // 本机断网时不更新状态，只标记为过期
+                    if !self.network_down {
+                        eprintln!("本机网络已断开，检查结果被忽略");
+                    }
+                    self.network_down = true;
+                    for server in self.servers.iter_mut() {
+                        server.state.stale = true;
+                    }
+                    continue;
+                }
+                WorkerEvent::Done => {
+                    cycle_done = true;
+                    self.startup.cycle_done(self.clock.instant());
+                    continue;
+                }
+            };
+            self.network_down = false;
+            let Some(server) = self.servers.iter_mut().find(|s| s.id == result.id) else {
+                continue;
+            };
+            self.history
+                .record(&server.history_key(), now, &result.outcome.status);
+            if let (Some(latency), ServerStatus::Online | ServerStatus::Degraded) =
+                (result.outcome.latency, &result.outcome.status)
+            {
+                self.history
+                    .record_latency(&server.history_key(), now, latency.as_millis() as u64);
+            }
+            let effects =
+                transition::apply(server, result.outcome, &*self.clock, &mut self.baselines);
+            let flap = server
+                .state
+                .flap
+                .record(now, effects.change.is_some(), &self.settings.flap);
+            let uptime = self.history.uptime(
+                &server.history_key(),
+                now - chrono::Duration::hours(24),
+                self.settings.exclusions_for(&server.group),
+            );
+            server.state.health =
+                health::score(uptime, &server.state.recent, server.state.cert_expires, now);
+            baselines_changed |= effects.baseline_changed;
+            updates.open_urls.extend(effects.open_url);
+            updates.alerts.extend(effects.alerts);
+            if effects.change.is_some() {
+                mqtt_messages.extend(self.settings.mqtt.messages(server));
+            }
+            if let Some(change) = effects.change {
+                if change.from != ServerStatus::Unchecked
+                    && !quiet
+                    && !server.state.maintenance
+                    && !server.state.flap.is_flapping()
+                {
+                    let event = StatusEvent::new(&change, &server.url);
+                    notify::notify_all(
+                        &self.client,
+                        &self.settings.channels,
+                        event,
+                        &self.action_log,
+                    );
+                }
+                updates.changes.push(change);
+            }
+            // 开始抖动和恢复稳定时各通知一次
+            if let Some(flap) = flap {
+                let event = StatusEvent::flapping(server, flap, now, &self.settings.flap);
+                updates.alerts.push(event.text.clone());
+                self.action_log.push(event.text.clone());
+                if !quiet {
+                    notify::notify_all(
+                        &self.client,
+                        &self.settings.channels,
+                        event,
+                        &self.action_log,
+                    );
+                }
+            }
+        }
+        if baselines_changed {
+            if let Err(e) = self.baselines.save(&*self.storage) {
+                eprintln!("保存安全基线失败: {}", e);
+            }
+        }
+        if cycle_done && self.settings.export.active() {
+            exporter::spawn_push(
+                &self.client,
+                &self.settings.export,
+                &self.servers,
+                &self.last_export,
+                &self.action_log,
+            );
+        }
+        if self.settings.mqtt.active() {
+            // 上次发布失败或代理设置变化后，下一批改为全部服务器的状态和发现配置
+            let full = (cycle_done || !mqtt_messages.is_empty())
+                && self.mqtt.needs_full(&self.settings.mqtt);
+            if full {
+                self.ha_discovery.reset();
+                mqtt_messages = self
+                    .servers
+                    .iter()
+                    .flat_map(|s| self.settings.mqtt.messages(s))
+                    .collect();
+            }
+            if cycle_done || full {
+                let mut discovery = self
+                    .ha_discovery
+                    .messages(&self.settings.mqtt, &self.servers);
+                discovery.append(&mut mqtt_messages);
+                mqtt_messages = discovery;
+            }
+            self.mqtt.publish(&self.settings.mqtt, mqtt_messages);
+        }
+        updates
+    }
+
+    // 执行到期的定时动作，返回到期的提醒
+    fn run_scheduled_actions(&mut self) -> Vec<DueAction> {
+        let now = self.clock.now().naive_local();
+        let mut reminders = Vec::new();
+        for item in self.scheduler.tick(now, &mut self.servers) {
+            match item.action.kind {
+                ActionKind::Remind => {
+                    self.action_log.push(tr!(
+                        "{}: 提醒 \"{}\" ({})",
+                        "{}: reminder \"{}\" ({})",
+                        item.server_name,
+                        item.action.message,
+                        item.action.time_label()
+                    ));
+                    reminders.push(item);
+                }
+                ActionKind::Webhook => {
+                    self.action_log.push(tr!(
+                        "{}: 调用Webhook {}",
+                        "{}: calling webhook {}",
+                        item.server_name,
+                        item.action.webhook_url
+                    ));
+                    schedule::fire_webhook(self.client.clone(), item, self.action_log.clone());
+                }
+            }
+        }
+        reminders
+    }
+
+    // 根据分组的排除时段标记正在维护的服务器
+    fn update_maintenance(&mut self) {
+        let now = self.clock.now();
+        for server in self.servers.iter_mut() {
+            server.state.maintenance = server.state.rule_maintenance
+                || self
+                    .settings
+                    .exclusions_for(&server.group)
+                    .iter()
+                    .any(|e| e.contains(now));
+        }
+    }
+
+    pub fn run_backup(&mut self) {
+        match serde_json::to_string_pretty(&self.settings) {
+            Ok(settings_json) => backup::spawn_backup(
+                &self.client,
+                &self.settings.backup,
+                &self.storage,
+                settings_json,
+                &self.last_backup,
+                &self.action_log,
+            ),
+            Err(e) => eprintln!("序列化设置失败: {}", e),
+        }
+    }
+
+    // 生成上一周期的SLA报告，force 为true时即使已经生成过也重新生成；设置了时把摘要发到通知渠道
+    pub fn generate_report(&mut self, force: bool) -> std::io::Result<Option<PathBuf>> {
+        let now = self.clock.now();
+        let dir = &self.reports_dir;
+        let generated = if force {
+            report::generate(&self.servers, &self.history, &self.settings, now, dir).map(Some)?
+        } else {
+            report::generate_due(&self.servers, &self.history, &self.settings, now, dir)?
+        };
+        let Some((path, report)) = generated else {
+            return Ok(None);
+        };
+        if self.settings.report.notify {
+            let event = StatusEvent::report(report::summary(&report));
+            notify::notify_all(
+                &self.client,
+                &self.settings.channels,
+                event,
+                &self.action_log,
+            );
+        }
+        Ok(Some(path))
+    }
+
+    // 旧版本的历史记录和安全基线以 名称|URL 为键，加载服务器后改用ID。有改动时返回 true，
+    // 调用方要保存服务器列表，配置文件中没有ID的服务器才会一直使用这次生成的ID
+    pub fn migrate_history_keys(&mut self) -> bool {
+        let mut changed = false;
+        for server in &self.servers {
+            let (legacy, key) = (server.legacy_history_key(), server.history_key());
+            changed |= self.history.rename(&legacy, &key);
+            changed |= self.baselines.rename(&legacy, &key);
+        }
+        if changed {
+            self.save_history();
+            if let Err(e) = self.baselines.save(&*self.storage) {
+                eprintln!("保存安全基线失败: {}", e);
+            }
+        }
+        changed
+    }
+
+    pub fn save_history(&mut self) {
+        if let Err(e) = self.history.save(&*self.storage) {
+            eprintln!("保存历史记录失败: {}", e);
+        }
+        self.last_history_save = self.clock.instant();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::storage::sqlite::SqliteStorage;
+    use crate::testing;
+    use std::path::Path;
+    use std::time::Duration;
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[tokio::test]
+    async fn applies_results_and_records_history() {
+        let mock = MockServer::start().await;
+        Mock::given(path("/ok"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock)
+            .await;
+        let settings = Settings {
+            auto_check_enabled: false,
+            ..Default::default()
+        };
+        let storage = Arc::new(SqliteStorage::open(Path::new(":memory:")).unwrap());
+        let mut monitor = Monitor::new(
+            settings,
+            storage,
+            Arc::new(ManualClock::at(Local::now())),
+            testing::client(Duration::from_secs(5)),
+            PathBuf::new(),
+        );
+        let server = testing::http_server(&format!("{}/ok", mock.uri()));
+        let key = server.history_key();
+        monitor.servers.push(server);
+
+        monitor.check_servers(None);
+        let mut changes = Vec::new();
+        for _ in 0..100 {
+            changes.extend(monitor.tick().changes);
+            if !monitor.worker.is_checking() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].to, ServerStatus::Online);
+        assert_eq!(monitor.servers[0].status, ServerStatus::Online);
+        assert_eq!(monitor.history.spans(&key).len(), 1);
+
+        // 旧版本以 名称|URL 为键的记录改到ID下
+        let legacy = testing::http_server("http://10.0.0.7/");
+        monitor.history.record(
+            &legacy.legacy_history_key(),
+            Local::now(),
+            &ServerStatus::Offline,
+        );
+        monitor.servers.push(legacy.clone());
+        assert!(monitor.migrate_history_keys());
+        assert!(!monitor.migrate_history_keys());
+        assert_eq!(monitor.history.spans(&legacy.history_key()).len(), 1);
+        assert!(monitor
+            .history
+            .spans(&legacy.legacy_history_key())
+            .is_empty());
+    }
+}
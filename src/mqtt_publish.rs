@@ -0,0 +1,515 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use uuid::Uuid;
+
+use crate::checks::mqtt::{self, MqttCheck};
+use crate::i18n::tr;
+use crate::schedule::ActionLog;
+use crate::{Server, ServerStatus};
+
+// 状态变化时发布到 MQTT 代理的 <前缀>/<名称>/status 主题，内容为 online、offline、error、
+// degraded，默认作为保留消息，Home Assistant 等自动化重新连接后也能拿到当前状态。
+// 与通知不同，第一次检查、启动宽限期、静音和抖动期间同样发布，主题上总是最新的状态。
+// 开启 Home Assistant 自动发现后，每台服务器作为一个 connectivity 类型的 binary_sensor 出现，
+// 状态另外发布到 <前缀>/<ID>/status，延迟等写在 <前缀>/<ID>/attributes 主题中作为实体属性，每轮检查后更新。
+// 程序与代理保持连接，<前缀>/availability 为 online，程序退出或断开后代理按遗嘱改为 offline
+
+const PUBLISH_TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct MqttPublishSettings {
+    pub enabled: bool,
+    pub host: String,
+    pub port: u16,
+    pub tls: bool,
+    pub accept_invalid_certs: bool,
+    // 留空时匿名连接
+    pub user: String,
+    pub password: String,
+    pub prefix: String,
+    pub retain: bool,
+    pub ha_discovery: bool,
+    // Home Assistant 的发现前缀，默认 homeassistant
+    pub ha_prefix: String,
+}
+
+impl Default for MqttPublishSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            host: String::new(),
+            port: 1883,
+            tls: false,
+            accept_invalid_certs: false,
+            user: String::new(),
+            password: String::new(),
+            prefix: "servercheck".to_string(),
+            retain: true,
+            ha_discovery: false,
+            ha_prefix: "homeassistant".to_string(),
+        }
+    }
+}
+
+impl MqttPublishSettings {
+    pub fn active(&self) -> bool {
+        self.enabled && !self.host.trim().is_empty()
+    }
+
+    fn broker(&self) -> MqttCheck {
+        MqttCheck {
+            tls: self.tls,
+            port: self.port,
+            user: self.user.clone(),
+            password: self.password.clone(),
+            topic: String::new(),
+            accept_invalid_certs: self.accept_invalid_certs,
+            tls_name: String::new(),
+        }
+    }
+
+    // 名称中的 / 会变成多级主题，+ 和 # 是通配符，都换成 _
+    pub fn topic(&self, server: &Server) -> String {
+        let name: String = server
+            .name
+            .trim()
+            .chars()
+            .map(|c| if matches!(c, '/' | '+' | '#') { '_' } else { c })
+            .collect();
+        self.prefixed(&format!("{}/status", name))
+    }
+
+    fn prefixed(&self, topic: &str) -> String {
+        match self.prefix.trim().trim_end_matches('/') {
+            "" => topic.to_string(),
+            prefix => format!("{}/{}", prefix, topic),
+        }
+    }
+
+    // 程序在线时为 online；连接断开或程序退出后代理按遗嘱发布 offline
+    pub fn availability_topic(&self) -> String {
+        self.prefixed("availability")
+    }
+
+    // Home Assistant 实体的状态和属性按服务器ID发布，同名的服务器互不影响，改名后实体不变
+    fn entity_topic(&self, id: Uuid, leaf: &str) -> String {
+        self.prefixed(&format!("{}/{}", id.simple(), leaf))
+    }
+
+    fn discovery_topic(&self, id: Uuid) -> String {
+        let prefix = match self.ha_prefix.trim().trim_end_matches('/') {
+            "" => "homeassistant",
+            prefix => prefix,
+        };
+        format!(
+            "{}/binary_sensor/servercheck_{}/config",
+            prefix,
+            id.simple()
+        )
+    }
+
+    // 发现配置：在线和降级为 ON，离线和错误为 OFF
+    fn discovery_config(&self, server: &Server) -> String {
+        let unique_id = format!("servercheck_{}", server.id.simple());
+        serde_json::json!({
+            "name": server.name,
+            "unique_id": unique_id,
+            "object_id": unique_id,
+            "device_class": "connectivity",
+            "state_topic": self.entity_topic(server.id, "status"),
+            "value_template": "{{ 'ON' if value in ['online', 'degraded'] else 'OFF' }}",
+            "json_attributes_topic": self.entity_topic(server.id, "attributes"),
+            "availability_topic": self.availability_topic(),
+            "device": {
+                "identifiers": [unique_id],
+                "name": server.name,
+                "model": server.target_label(),
+                "manufacturer": "server_check",
+            },
+        })
+        .to_string()
+    }
+
+    // 实体属性，每轮检查后发布
+    fn attributes(&self, server: &Server) -> Option<(String, String)> {
+        if server.status == ServerStatus::Unchecked {
+            return None;
+        }
+        let attributes = serde_json::json!({
+            "status": server.status.id(),
+            "latency_ms": server.state.latency_ms,
+            "detail": server.state.detail,
+            "target": server.target_label(),
+            "group": server.group,
+            "last_checked": server.state.last_checked.map(|t| t.to_rfc3339()),
+        });
+        Some((
+            self.entity_topic(server.id, "attributes"),
+            attributes.to_string(),
+        ))
+    }
+
+    // 这台服务器当前状态的消息，开启自动发现时同时发布到实体的状态主题；未检查时不发布
+    pub fn messages(&self, server: &Server) -> Vec<(String, String)> {
+        if server.status == ServerStatus::Unchecked {
+            return Vec::new();
+        }
+        let status = server.status.id().to_string();
+        let mut messages = vec![(self.topic(server), status.clone())];
+        if self.ha_discovery {
+            messages.push((self.entity_topic(server.id, "status"), status));
+        }
+        messages
+    }
+}
+
+// 已发布的发现配置，只在服务器增删、改名或设置变化时重新发布；删除的服务器发布空的保留消息，
+// Home Assistant 随之删除实体，状态和属性主题也一并清空
+#[derive(Debug, Default)]
+pub struct HaDiscovery {
+    announced: HashMap<Uuid, String>,
+}
+
+impl HaDiscovery {
+    // 需要重新发布全部状态时，发现配置也全部重新发布
+    pub fn reset(&mut self) {
+        self.announced.clear();
+    }
+
+    // 一轮检查后要发布的消息：有变化的发现配置和所有服务器的属性
+    pub fn messages(
+        &mut self,
+        settings: &MqttPublishSettings,
+        servers: &[Server],
+    ) -> Vec<(String, String)> {
+        if !settings.ha_discovery {
+            return Vec::new();
+        }
+        let current: HashMap<Uuid, String> = servers
+            .iter()
+            .map(|s| (s.id, settings.discovery_config(s)))
+            .collect();
+        let mut messages: Vec<(String, String)> = self
+            .announced
+            .keys()
+            .filter(|id| !current.contains_key(*id))
+            .flat_map(|id| {
+                [
+                    settings.discovery_topic(*id),
+                    settings.entity_topic(*id, "status"),
+                    settings.entity_topic(*id, "attributes"),
+                ]
+            })
+            .map(|topic| (topic, String::new()))
+            .collect();
+        messages.extend(
+            current
+                .iter()
+                .filter(|(id, config)| self.announced.get(*id) != Some(config))
+                .map(|(id, config)| (settings.discovery_topic(*id), config.clone())),
+        );
+        messages.sort();
+        self.announced = current;
+        messages.extend(servers.iter().filter_map(|s| settings.attributes(s)));
+        messages
+    }
+}
+
+// 最近一次发布的时间和消息数，失败时带上原因
+#[derive(Debug, Clone)]
+pub struct PublishResult {
+    pub at: DateTime<Local>,
+    pub count: usize,
+    pub error: Option<String>,
+}
+
+pub type LastPublish = Arc<Mutex<Option<PublishResult>>>;
+
+// 一批消息和提交时的设置
+struct Batch {
+    settings: MqttPublishSettings,
+    messages: Vec<(String, String)>,
+}
+
+// 常驻的发布任务：每批消息按提交的顺序发布，前一批没发完时后一批排队，旧状态不会在新状态之后送达。
+// 发布失败或设置变化后，下一次需要重新发布全部服务器的状态
+pub struct Publisher {
+    sender: mpsc::UnboundedSender<Batch>,
+    pub last: LastPublish,
+    failed: Arc<AtomicBool>,
+    // 上次提交时的设置
+    settings: Option<MqttPublishSettings>,
+}
+
+impl Publisher {
+    pub fn spawn(log: &ActionLog) -> Self {
+        let (sender, batches) = mpsc::unbounded_channel();
+        let last = LastPublish::default();
+        let failed = Arc::new(AtomicBool::new(false));
+        tokio::spawn(run(
+            batches,
+            Arc::clone(&last),
+            Arc::clone(&failed),
+            log.clone(),
+        ));
+        Self {
+            sender,
+            last,
+            failed,
+            settings: None,
+        }
+    }
+
+    // 上次发布失败、还没有发布过或代理设置变化时为 true
+    pub fn needs_full(&self, settings: &MqttPublishSettings) -> bool {
+        self.failed.load(Ordering::SeqCst) || self.settings.as_ref() != Some(settings)
+    }
+
+    pub fn publish(&mut self, settings: &MqttPublishSettings, messages: Vec<(String, String)>) {
+        if messages.is_empty() {
+            return;
+        }
+        self.failed.store(false, Ordering::SeqCst);
+        self.settings = Some(settings.clone());
+        let _ = self.sender.send(Batch {
+            settings: settings.clone(),
+            messages,
+        });
+    }
+}
+
+// 保持的连接和连接时的设置
+type Open = Option<(MqttPublishSettings, mqtt::Connection)>;
+
+// 需要时（重新）连接代理，发布 online 后发布这一批；返回是否重新连接过已断开的连接，
+// 断开期间的消息可能丢失，下一次要重新发布全部状态
+async fn send(open: &mut Open, batch: &Batch) -> anyhow::Result<bool> {
+    let mut reconnected = false;
+    if let Some((settings, connection)) = open {
+        if *settings != batch.settings {
+            let (settings, connection) = open.take().unwrap();
+            close(&settings, connection).await;
+        } else if connection.closed() {
+            *open = None;
+            reconnected = true;
+        }
+    }
+    let availability = batch.settings.availability_topic();
+    let connection = match open {
+        Some((_, connection)) => connection,
+        None => {
+            let will = mqtt::Will {
+                topic: &availability,
+                payload: "offline",
+            };
+            let mut connection = mqtt::Connection::open(
+                batch.settings.host.trim(),
+                &batch.settings.broker(),
+                Some(&will),
+            )
+            .await?;
+            connection
+                .publish(&[(availability.clone(), "online".to_string())], true)
+                .await?;
+            &mut open.insert((batch.settings.clone(), connection)).1
+        }
+    };
+    // Home Assistant 重启后要靠保留的发现配置和状态恢复实体，开启自动发现时总是保留
+    let retain = batch.settings.retain || batch.settings.ha_discovery;
+    connection.publish(&batch.messages, retain).await?;
+    Ok(reconnected)
+}
+
+// 与心跳一样只在开始失败时写日志。连接一直保持并定时发送心跳，
+// 程序退出时发布 offline，异常退出或断网时由代理按遗嘱发布
+async fn run(
+    mut batches: mpsc::UnboundedReceiver<Batch>,
+    last: LastPublish,
+    failed: Arc<AtomicBool>,
+    log: ActionLog,
+) {
+    let mut open: Open = None;
+    let mut ping = tokio::time::interval(mqtt::PING_INTERVAL);
+    loop {
+        let batch = tokio::select! {
+            batch = batches.recv() => match batch {
+                Some(batch) => batch,
+                None => break,
+            },
+            _ = ping.tick() => {
+                if let Some((_, connection)) = &mut open {
+                    let ping = tokio::time::timeout(PUBLISH_TIMEOUT, connection.ping()).await;
+                    if connection.closed() || !matches!(ping, Ok(Ok(()))) {
+                        open = None;
+                        failed.store(true, Ordering::SeqCst);
+                    }
+                }
+                continue;
+            }
+        };
+        let error = match tokio::time::timeout(PUBLISH_TIMEOUT, send(&mut open, &batch)).await {
+            Ok(Ok(reconnected)) => {
+                if reconnected {
+                    failed.store(true, Ordering::SeqCst);
+                }
+                None
+            }
+            Ok(Err(e)) => Some(format!("{:#}", e)),
+            Err(_) => Some(tr!("连接代理超时", "Timed out connecting to the broker").to_string()),
+        };
+        if error.is_some() {
+            open = None;
+            failed.store(true, Ordering::SeqCst);
+        }
+        let mut last = last.lock().unwrap();
+        let was_failing = last.as_ref().is_some_and(|r| r.error.is_some());
+        if let (Some(e), false) = (&error, was_failing) {
+            log.push(tr!("MQTT发布失败: {}", "MQTT publish failed: {}", e));
+        }
+        *last = Some(PublishResult {
+            at: Local::now(),
+            count: batch.messages.len(),
+            error,
+        });
+    }
+    if let Some((settings, connection)) = open {
+        close(&settings, connection).await;
+    }
+}
+
+// 正常断开时代理不发布遗嘱，自己先发布 offline
+async fn close(settings: &MqttPublishSettings, mut connection: mqtt::Connection) {
+    let offline = [(settings.availability_topic(), "offline".to_string())];
+    let _ = connection.publish(&offline, true).await;
+    let _ = connection.disconnect().await;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    #[test]
+    fn builds_topics_and_payloads() {
+        let mut settings = MqttPublishSettings::default();
+        let mut server = testing::http_server("http://10.0.0.1/");
+        server.name = "web/eu #1".to_string();
+        assert!(settings.messages(&server).is_empty());
+
+        server.status = ServerStatus::Error(502);
+        assert_eq!(
+            settings.messages(&server),
+            [(
+                "servercheck/web_eu _1/status".to_string(),
+                "error".to_string()
+            )]
+        );
+        settings.prefix = "home/lab/".to_string();
+        assert_eq!(settings.topic(&server), "home/lab/web_eu _1/status");
+        assert_eq!(settings.availability_topic(), "home/lab/availability");
+
+        // 自动发现的实体另外按ID发布
+        settings.ha_discovery = true;
+        let messages = settings.messages(&server);
+        assert_eq!(messages.len(), 2);
+        assert_eq!(
+            messages[1].0,
+            format!("home/lab/{}/status", server.id.simple())
+        );
+    }
+
+    #[test]
+    fn announces_only_changed_entities() {
+        let settings = MqttPublishSettings {
+            ha_discovery: true,
+            ..Default::default()
+        };
+        let mut web = testing::http_server("http://10.0.0.1/");
+        web.name = "web".to_string();
+        web.status = ServerStatus::Online;
+        web.state.latency_ms = Some(12);
+        // 同名的服务器是不同的实体
+        let mut db = testing::http_server("http://10.0.0.2/");
+        db.name = "web".to_string();
+        let mut discovery = HaDiscovery::default();
+
+        let messages = discovery.messages(&settings, &[web.clone(), db.clone()]);
+        // 两条发现配置，未检查的 db 没有属性
+        assert_eq!(messages.len(), 3);
+        let config_topic = format!(
+            "homeassistant/binary_sensor/servercheck_{}/config",
+            web.id.simple()
+        );
+        let config = &messages.iter().find(|(t, _)| *t == config_topic).unwrap().1;
+        let config: serde_json::Value = serde_json::from_str(config).unwrap();
+        assert_eq!(
+            config["state_topic"],
+            format!("servercheck/{}/status", web.id.simple())
+        );
+        assert_eq!(config["availability_topic"], "servercheck/availability");
+        assert_eq!(config["device_class"], "connectivity");
+        let attributes: serde_json::Value = serde_json::from_str(&messages[2].1).unwrap();
+        assert_eq!(
+            messages[2].0,
+            format!("servercheck/{}/attributes", web.id.simple())
+        );
+        assert_eq!(attributes["latency_ms"], 12);
+
+        // 没有变化时只更新属性；删除的服务器清空配置、状态和属性
+        assert_eq!(
+            discovery
+                .messages(&settings, &[web.clone(), db.clone()])
+                .len(),
+            1
+        );
+        let messages = discovery.messages(&settings, &[web]);
+        assert_eq!(messages.len(), 4);
+        assert!(messages[..3]
+            .iter()
+            .all(|(t, p)| t.contains(&db.id.simple().to_string()) && p.is_empty()));
+    }
+
+    #[tokio::test]
+    async fn resends_everything_after_a_failure() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut settings = MqttPublishSettings {
+            enabled: true,
+            host: "127.0.0.1".to_string(),
+            port: listener.local_addr().unwrap().port(),
+            ..Default::default()
+        };
+        drop(listener);
+        let mut publisher = Publisher::spawn(&ActionLog::default());
+        assert!(publisher.needs_full(&settings));
+
+        publisher.publish(&settings, vec![("a/status".into(), "offline".into())]);
+        assert!(!publisher.needs_full(&settings));
+        for _ in 0..100 {
+            if publisher.needs_full(&settings) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert!(publisher.needs_full(&settings));
+        assert!(publisher
+            .last
+            .lock()
+            .unwrap()
+            .as_ref()
+            .unwrap()
+            .error
+            .is_some());
+
+        // 换了代理同样需要全部重新发布
+        publisher.publish(&settings, vec![("a/status".into(), "online".into())]);
+        settings.port += 1;
+        assert!(publisher.needs_full(&settings));
+    }
+}
@@ -0,0 +1,76 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr, UdpSocket};
+use std::time::Duration;
+
+// 检测本机网络是否可用。断网时所有服务器都会变成离线，
+// 这时应冻结现有状态，而不是把每台服务器都标记为离线。
+
+// 有没有默认路由：UDP connect 只查路由表，不会真正发包
+fn has_default_route() -> bool {
+    let targets: [SocketAddr; 2] = [
+        "1.1.1.1:53".parse().unwrap(),
+        "[2606:4700:4700::1111]:53".parse().unwrap(),
+    ];
+    targets.iter().any(|target| {
+        let bind: SocketAddr = if target.is_ipv4() {
+            "0.0.0.0:0".parse().unwrap()
+        } else {
+            "[::]:0".parse().unwrap()
+        };
+        UdpSocket::bind(bind)
+            .and_then(|socket| {
+                socket.connect(target)?;
+                socket.local_addr()
+            })
+            .is_ok_and(|addr| !addr.ip().is_unspecified() && !addr.ip().is_loopback())
+    })
+}
+
+// Linux下从 /proc/net/route 读取默认网关
+#[cfg(target_os = "linux")]
+fn default_gateway() -> Option<IpAddr> {
+    let table = std::fs::read_to_string("/proc/net/route").ok()?;
+    table.lines().skip(1).find_map(|line| {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        // Iface Destination Gateway ...，目标为0的是默认路由
+        if fields.len() < 3 || fields[1] != "00000000" {
+            return None;
+        }
+        let gateway = u32::from_str_radix(fields[2], 16).ok()?;
+        // 以小端序存储
+        (gateway != 0).then(|| IpAddr::V4(Ipv4Addr::from(gateway.swap_bytes())))
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn default_gateway() -> Option<IpAddr> {
+    None
+}
+
+// 网关是否可达：很多网关直接丢弃发往80端口的包，超时不能说明断网，
+// 只有系统报告主机或网络不可达（例如ARP没有应答）时才算不可达
+async fn gateway_reachable(gateway: IpAddr) -> bool {
+    let connect = tokio::net::TcpStream::connect(SocketAddr::new(gateway, 80));
+    match tokio::time::timeout(Duration::from_secs(2), connect).await {
+        Ok(Err(e)) => !is_unreachable(&e),
+        Ok(Ok(_)) | Err(_) => true,
+    }
+}
+
+fn is_unreachable(error: &std::io::Error) -> bool {
+    use std::io::ErrorKind;
+    matches!(
+        error.kind(),
+        ErrorKind::HostUnreachable | ErrorKind::NetworkUnreachable | ErrorKind::AddrNotAvailable
+    )
+}
+
+// 只在所有检查都失败时才用此结果判断为断网
+pub async fn local_network_up() -> bool {
+    if !has_default_route() {
+        return false;
+    }
+    match default_gateway() {
+        Some(gateway) => gateway_reachable(gateway).await,
+        None => true,
+    }
+}
@@ -0,0 +1,745 @@
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use std::process::Stdio;
+use std::time::Duration;
+
+use crate::checks::transition;
+use crate::digest::StatusChange;
+use crate::flap::{FlapEvent, FlapSettings};
+use crate::i18n::tr;
+use crate::schedule::{self, ActionLog};
+use crate::{secret, Server};
+
+const FLAPPING: &str = "flapping";
+const REPORT: &str = "report";
+// 运行命令渠道的超时，超时后结束进程
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+// 运行命令时传入的环境变量及说明，渠道窗口中也显示这份列表
+pub fn command_env_docs() -> Vec<(&'static str, &'static str)> {
+    vec![
+        (
+            "SERVERCHECK_EVENT",
+            tr!(
+                "事件: down（离线或出错）/ up（恢复在线）/ degraded / flapping（开始抖动）/ stable（停止抖动）/ change / report（定期报告）/ test",
+                "Event: down (offline or error) / up (back online) / degraded / flapping / stable (stopped flapping) / change / report (scheduled report) / test"
+            ),
+        ),
+        ("SERVERCHECK_SERVER", tr!("服务器名称", "Server name")),
+        ("SERVERCHECK_URL", tr!("服务器地址", "Server address")),
+        (
+            "SERVERCHECK_FROM",
+            tr!(
+                "之前的状态: unchecked/online/offline/error/degraded/flapping",
+                "Previous status: unchecked/online/offline/error/degraded/flapping"
+            ),
+        ),
+        ("SERVERCHECK_TO", tr!("新的状态，取值同上", "New status, same values")),
+        (
+            "SERVERCHECK_STATUS_CODE",
+            tr!(
+                "状态为error时的HTTP状态码或退出码，否则为空",
+                "HTTP status or exit code when the status is error, otherwise empty"
+            ),
+        ),
+        (
+            "SERVERCHECK_DOWNTIME_SECS",
+            tr!(
+                "恢复时这次故障持续的秒数，否则为空",
+                "Seconds the outage lasted when recovering, otherwise empty"
+            ),
+        ),
+        ("SERVERCHECK_AT", tr!("发生时间，RFC 3339格式", "Time of the event, RFC 3339")),
+        ("SERVERCHECK_MESSAGE", tr!("与机器人消息相同的说明", "Same text as bot messages")),
+        (
+            "SERVERCHECK_SECRET_<NAME>",
+            tr!(
+                "渠道中标记的钥匙串密钥，名称转为大写，字母和数字以外的字符换成 _",
+                "Keychain secrets marked for the channel; the name is upper-cased with other characters replaced by _"
+            ),
+        ),
+    ]
+}
+
+// 发给渠道的一次状态变化。状态使用固定的英文标识，接收方不需要解析界面上的文字
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatusEvent {
+    pub server: String,
+    pub url: String,
+    // unchecked / online / offline / error / degraded，开始和停止抖动时其中一个为 flapping
+    pub from: &'static str,
+    pub to: &'static str,
+    // 状态为error时的HTTP状态码或命令退出码
+    pub status_code: Option<u16>,
+    // 从故障中恢复时，这次故障持续的秒数
+    pub downtime_secs: Option<i64>,
+    pub at: String,
+    // 按界面语言生成的说明，机器人消息只发送这一段
+    #[serde(skip)]
+    pub text: String,
+}
+
+impl StatusEvent {
+    pub fn new(change: &StatusChange, url: &str) -> Self {
+        let mut text = tr!(
+            "{}: {} → {}",
+            "{}: {} → {}",
+            change.server,
+            change.from,
+            change.to
+        );
+        if let Some(down_for) = change.down_for {
+            text.push_str(&tr!(
+                "（故障持续 {}）",
+                " (down for {})",
+                transition::format_downtime(down_for)
+            ));
+        }
+        Self {
+            server: change.server.clone(),
+            url: url.to_string(),
+            from: change.from.id(),
+            to: change.to.id(),
+            status_code: change.to.code(),
+            downtime_secs: change.down_for.map(|d| d.num_seconds()),
+            at: change.at.to_rfc3339(),
+            text,
+        }
+    }
+
+    // 开始抖动或恢复稳定，server 为当前状态
+    pub fn flapping(
+        server: &Server,
+        flap: FlapEvent,
+        at: chrono::DateTime<chrono::Local>,
+        settings: &FlapSettings,
+    ) -> Self {
+        let (from, to, text) = match flap {
+            FlapEvent::Started => (
+                server.status.id(),
+                FLAPPING,
+                tr!(
+                    "🔁 {}: 状态抖动，{} 分钟内变化 {} 次，暂停逐次通知",
+                    "🔁 {}: flapping, {} min window saw {} changes, individual alerts paused",
+                    server.name,
+                    settings.window().num_minutes(),
+                    server.state.flap.change_count()
+                ),
+            ),
+            FlapEvent::Stopped => (
+                FLAPPING,
+                server.status.id(),
+                tr!(
+                    "{}: 已停止抖动，当前 {}",
+                    "{}: stopped flapping, now {}",
+                    server.name,
+                    server.status
+                ),
+            ),
+        };
+        Self {
+            server: server.name.clone(),
+            url: server.url.clone(),
+            from,
+            to,
+            status_code: server.status.code(),
+            downtime_secs: None,
+            at: at.to_rfc3339(),
+            text,
+        }
+    }
+
+    // 事件类型，供脚本判断是故障还是恢复
+    pub fn kind(&self) -> &'static str {
+        match (self.from, self.to) {
+            ("", "") => "test",
+            (_, REPORT) => REPORT,
+            (_, FLAPPING) => "flapping",
+            (FLAPPING, _) => "stable",
+            (_, "offline" | "error") => "down",
+            (_, "online") => "up",
+            (_, "degraded") => "degraded",
+            _ => "change",
+        }
+    }
+
+    // 生成定期报告后发送的摘要，to 为 report
+    pub fn report(text: String) -> Self {
+        Self {
+            server: String::new(),
+            url: String::new(),
+            from: "",
+            to: REPORT,
+            status_code: None,
+            downtime_secs: None,
+            at: chrono::Local::now().to_rfc3339(),
+            text,
+        }
+    }
+
+    // 运行命令时传入的环境变量，不含密钥
+    pub fn env(&self) -> Vec<(&'static str, String)> {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        vec![
+            ("SERVERCHECK_EVENT", self.kind().to_string()),
+            ("SERVERCHECK_SERVER", self.server.clone()),
+            ("SERVERCHECK_URL", self.url.clone()),
+            ("SERVERCHECK_FROM", self.from.to_string()),
+            ("SERVERCHECK_TO", self.to.to_string()),
+            (
+                "SERVERCHECK_STATUS_CODE",
+                optional(self.status_code.map(|c| c.to_string())),
+            ),
+            (
+                "SERVERCHECK_DOWNTIME_SECS",
+                optional(self.downtime_secs.map(|s| s.to_string())),
+            ),
+            ("SERVERCHECK_AT", self.at.clone()),
+            ("SERVERCHECK_MESSAGE", self.text.clone()),
+        ]
+    }
+
+    // 测试消息不对应任何服务器
+    pub fn test() -> Self {
+        Self {
+            server: String::new(),
+            url: String::new(),
+            from: "",
+            to: "",
+            status_code: None,
+            downtime_secs: None,
+            at: chrono::Local::now().to_rfc3339(),
+            text: tr!(
+                "来自服务器状态监控的测试消息",
+                "Test message from Server Monitor"
+            )
+            .to_string(),
+        }
+    }
+}
+
+// 通知渠道的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ChannelKind {
+    // POST JSON到任意地址
+    #[default]
+    Webhook,
+    DingTalk,
+    WeCom,
+    Feishu,
+    Telegram,
+    // 在本机运行命令，状态通过环境变量传入
+    Command,
+}
+
+impl ChannelKind {
+    pub const ALL: [ChannelKind; 6] = [
+        ChannelKind::Webhook,
+        ChannelKind::DingTalk,
+        ChannelKind::WeCom,
+        ChannelKind::Feishu,
+        ChannelKind::Telegram,
+        ChannelKind::Command,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ChannelKind::Webhook => "Webhook",
+            ChannelKind::DingTalk => tr!("钉钉机器人", "DingTalk bot"),
+            ChannelKind::WeCom => tr!("企业微信机器人", "WeCom bot"),
+            ChannelKind::Feishu => tr!("飞书机器人", "Feishu bot"),
+            ChannelKind::Telegram => "Telegram",
+            ChannelKind::Command => tr!("运行命令", "Run command"),
+        }
+    }
+
+    // 密钥一栏的含义
+    pub fn token_label(&self) -> &'static str {
+        match self {
+            ChannelKind::Webhook => tr!("Bearer令牌（可选）", "Bearer token (optional)"),
+            ChannelKind::DingTalk => "access_token",
+            ChannelKind::WeCom => "key",
+            ChannelKind::Feishu => tr!("Webhook地址中的令牌", "Token from the webhook URL"),
+            ChannelKind::Telegram => "Bot token",
+            ChannelKind::Command => tr!("钥匙串密钥", "Keychain secrets"),
+        }
+    }
+
+    // 除通用Webhook和运行命令外，没有密钥无法发送
+    pub fn requires_token(&self) -> bool {
+        !matches!(self, ChannelKind::Webhook | ChannelKind::Command)
+    }
+}
+
+// 状态变化时发送通知的渠道。密钥单独存放在 token 中，可以填 keyring:名称，导出模板时清空
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Channel {
+    pub name: String,
+    pub kind: ChannelKind,
+    pub enabled: bool,
+    // 通用Webhook的地址
+    pub url: String,
+    // Telegram的会话ID
+    pub chat_id: String,
+    // 运行命令渠道的命令行，Windows下由 cmd /C 执行，其他系统由 sh -c 执行
+    pub command: String,
+    // 运行命令时传入的钥匙串条目名称，逗号分隔
+    pub secrets: String,
+    pub token: String,
+}
+
+impl Default for Channel {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            kind: ChannelKind::Webhook,
+            enabled: true,
+            url: String::new(),
+            chat_id: String::new(),
+            command: String::new(),
+            secrets: String::new(),
+            token: String::new(),
+        }
+    }
+}
+
+impl Channel {
+    // 缺少发送所需的密钥
+    pub fn missing_token(&self) -> bool {
+        self.kind.requires_token() && self.token.trim().is_empty()
+    }
+
+    fn endpoint(&self, token: &str) -> String {
+        match self.kind {
+            ChannelKind::Webhook => self.url.trim().to_string(),
+            ChannelKind::DingTalk => format!(
+                "https://oapi.dingtalk.com/robot/send?access_token={}",
+                token
+            ),
+            ChannelKind::WeCom => format!(
+                "https://qyapi.weixin.qq.com/cgi-bin/webhook/send?key={}",
+                token
+            ),
+            ChannelKind::Feishu => {
+                format!("https://open.feishu.cn/open-apis/bot/v2/hook/{}", token)
+            }
+            ChannelKind::Telegram => format!("https://api.telegram.org/bot{}/sendMessage", token),
+            // 不经过HTTP发送
+            ChannelKind::Command => String::new(),
+        }
+    }
+
+    fn body(&self, event: &StatusEvent) -> serde_json::Value {
+        let message = event.text.as_str();
+        match self.kind {
+            // 通用Webhook同时带上结构化的字段
+            ChannelKind::Webhook => {
+                let mut body = serde_json::to_value(event).unwrap_or_default();
+                body["message"] = message.into();
+                body
+            }
+            ChannelKind::DingTalk | ChannelKind::WeCom => serde_json::json!({
+                "msgtype": "text",
+                "text": { "content": message },
+            }),
+            ChannelKind::Feishu => serde_json::json!({
+                "msg_type": "text",
+                "content": { "text": message },
+            }),
+            ChannelKind::Telegram => serde_json::json!({
+                "chat_id": self.chat_id.trim(),
+                "text": message,
+            }),
+            ChannelKind::Command => serde_json::Value::Null,
+        }
+    }
+}
+
+#[cfg(windows)]
+pub fn shell(command: &str) -> tokio::process::Command {
+    // 不弹出控制台窗口
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+    let mut cmd = tokio::process::Command::new("cmd");
+    // arg 会按C运行库的规则给整段命令加引号和转义，cmd 不认这种转义，原样传入
+    cmd.arg("/C")
+        .raw_arg(command)
+        .creation_flags(CREATE_NO_WINDOW);
+    cmd
+}
+
+#[cfg(not(windows))]
+pub fn shell(command: &str) -> tokio::process::Command {
+    let mut cmd = tokio::process::Command::new("sh");
+    // 单独的进程组，超时后可以连同命令启动的子进程一起结束
+    cmd.arg("-c").arg(command).process_group(0);
+    cmd
+}
+
+// 结束 shell 启动的命令及其所有子进程
+#[cfg(windows)]
+pub fn kill_tree(pid: u32) {
+    let _ = std::process::Command::new("taskkill")
+        .args(["/F", "/T", "/PID", &pid.to_string()])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+}
+
+#[cfg(not(windows))]
+pub fn kill_tree(pid: u32) {
+    // shell 是进程组的组长，组号与它的PID相同
+    unsafe {
+        libc::killpg(pid as libc::pid_t, libc::SIGKILL);
+    }
+}
+
+// 运行命令渠道：以环境变量传入状态和标记的密钥，退出码非0时把标准错误的最后一行作为错误说明
+async fn run_command(channel: &Channel, event: &StatusEvent) -> Result<()> {
+    let command = channel.command.trim();
+    if command.is_empty() {
+        return Err(anyhow!(tr!("未填写命令", "Command is not set")));
+    }
+    let secrets = secret::hook_env(&channel.secrets).await?;
+    let mut cmd = shell(command);
+    cmd.envs(event.env())
+        .envs(secrets)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+    let output = tokio::time::timeout(COMMAND_TIMEOUT, cmd.output())
+        .await
+        .map_err(|_| anyhow!(tr!("命令执行超时", "Command timed out")))??;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let last = stderr.lines().rev().find(|l| !l.trim().is_empty());
+        return Err(anyhow!(tr!(
+            "命令退出码 {}: {}",
+            "Command exited with {}: {}",
+            output
+                .status
+                .code()
+                .map(|c| c.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            last.unwrap_or_default().trim()
+        )));
+    }
+    Ok(())
+}
+
+// 向一个渠道发送消息
+pub async fn send(client: &reqwest::Client, channel: &Channel, event: &StatusEvent) -> Result<()> {
+    if channel.missing_token() {
+        return Err(anyhow!(tr!("未填写密钥", "Token is not set")));
+    }
+    if channel.kind == ChannelKind::Command {
+        return run_command(channel, event).await;
+    }
+    let token = secret::resolve_async(channel.token.trim()).await?;
+    let token = token.trim();
+    // 只有通用Webhook用Bearer令牌，其他渠道的令牌在地址中
+    let bearer = if channel.kind == ChannelKind::Webhook {
+        token
+    } else {
+        ""
+    };
+    let status = schedule::post_json(
+        client,
+        &channel.endpoint(token),
+        &channel.body(event),
+        bearer,
+    )
+    .await?;
+    if !status.is_success() {
+        return Err(anyhow!(tr!(
+            "返回错误状态 {}",
+            "returned error status {}",
+            status.as_u16()
+        )));
+    }
+    Ok(())
+}
+
+// 向所有启用的渠道发送，失败写入日志
+pub fn notify_all(
+    client: &reqwest::Client,
+    channels: &[Channel],
+    event: StatusEvent,
+    log: &ActionLog,
+) {
+    for channel in channels.iter().filter(|c| c.enabled) {
+        let client = client.clone();
+        let channel = channel.clone();
+        let event = event.clone();
+        let log = log.clone();
+        tokio::spawn(async move {
+            if let Err(e) = send(&client, &channel, &event).await {
+                log.push(tr!(
+                    "通知渠道 {} 发送失败: {}",
+                    "Notification channel {} failed: {}",
+                    channel.name,
+                    e
+                ));
+            }
+        });
+    }
+}
+
+// 导出的模板文件格式
+#[derive(Debug, Serialize, Deserialize)]
+struct ChannelTemplate {
+    channels: Vec<Channel>,
+}
+
+// Webhook地址的路径和查询参数中常带有密钥（Slack、Discord、?key=），导出时只保留协议和主机
+fn redact_url(url: &str) -> String {
+    match url::Url::parse(url.trim()) {
+        Ok(parsed) => parsed.origin().ascii_serialization(),
+        Err(_) => String::new(),
+    }
+}
+
+// 导出为可分享的模板，不包含密钥
+pub fn export_template(channels: &[Channel]) -> String {
+    let template = ChannelTemplate {
+        channels: channels
+            .iter()
+            .map(|c| Channel {
+                token: String::new(),
+                url: redact_url(&c.url),
+                ..c.clone()
+            })
+            .collect(),
+    };
+    serde_json::to_string_pretty(&template).unwrap_or_default()
+}
+
+pub fn import_template(text: &str) -> Result<Vec<Channel>> {
+    let template: ChannelTemplate = serde_json::from_str(text.trim())
+        .map_err(|e| anyhow!(tr!("模板格式错误: {}", "Invalid template: {}", e)))?;
+    if template.channels.is_empty() {
+        return Err(anyhow!(tr!(
+            "模板中没有渠道",
+            "The template has no channels"
+        )));
+    }
+    // 导入的命令要先确认再启用
+    let channels = template
+        .channels
+        .into_iter()
+        .map(|c| Channel {
+            enabled: c.enabled && c.kind != ChannelKind::Command,
+            ..c
+        })
+        .collect();
+    Ok(channels)
+}
+
+// 导入的渠道与已有渠道同名时替换，保留已有的密钥和被隐去的完整地址；其余追加到末尾
+pub fn merge_imported(channels: &mut Vec<Channel>, imported: Vec<Channel>) {
+    for mut channel in imported {
+        match channels.iter_mut().find(|c| c.name == channel.name) {
+            Some(existing) => {
+                if channel.token.trim().is_empty() && existing.kind == channel.kind {
+                    channel.token = existing.token.clone();
+                }
+                if !existing.url.is_empty() && channel.url == redact_url(&existing.url) {
+                    channel.url = existing.url.clone();
+                }
+                *existing = channel;
+            }
+            None => channels.push(channel),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use crate::ServerStatus;
+    use wiremock::matchers::{body_partial_json, header, method};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn channel(name: &str, kind: ChannelKind, token: &str) -> Channel {
+        Channel {
+            name: name.to_string(),
+            kind,
+            token: token.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn export_strips_tokens() {
+        let mut webhook = channel("ops", ChannelKind::Webhook, "secret-1");
+        webhook.url = "https://hooks.example.com/services/T0/B0/xyz?key=k1".to_string();
+        let mut telegram = channel("tg", ChannelKind::Telegram, "123:abc");
+        telegram.chat_id = "-1001".to_string();
+
+        let text = export_template(&[webhook.clone(), telegram]);
+        assert!(!text.contains("secret-1") && !text.contains("123:abc"));
+        assert!(!text.contains("xyz") && !text.contains("k1"));
+
+        let imported = import_template(&text).unwrap();
+        assert_eq!(imported.len(), 2);
+        assert_eq!(imported[0].url, "https://hooks.example.com");
+        assert_eq!(imported[1].chat_id, "-1001");
+        assert!(imported[1].missing_token());
+        assert!(!imported[0].missing_token());
+
+        // 导入回原配置时保留完整的地址
+        let mut channels = vec![webhook.clone()];
+        merge_imported(&mut channels, imported);
+        assert_eq!(channels[0].url, webhook.url);
+    }
+
+    #[test]
+    fn import_rejects_invalid_templates() {
+        assert!(import_template("not json").is_err());
+        assert!(import_template(r#"{"channels": []}"#).is_err());
+    }
+
+    #[test]
+    fn merge_replaces_same_name_and_keeps_token() {
+        let mut channels = vec![channel("ding", ChannelKind::DingTalk, "t1")];
+        let mut updated = channel("ding", ChannelKind::DingTalk, "");
+        updated.enabled = false;
+        merge_imported(
+            &mut channels,
+            vec![updated, channel("wecom", ChannelKind::WeCom, "k")],
+        );
+        assert_eq!(channels.len(), 2);
+        assert_eq!(channels[0].token, "t1");
+        assert!(!channels[0].enabled);
+        assert_eq!(channels[1].name, "wecom");
+    }
+
+    #[test]
+    fn flapping_event_uses_flapping_id() {
+        let settings = FlapSettings::default();
+        let mut server = testing::http_server("http://127.0.0.1:8080");
+        server.status = ServerStatus::Offline;
+        let now = chrono::Local::now();
+
+        let started = StatusEvent::flapping(&server, FlapEvent::Started, now, &settings);
+        assert_eq!((started.from, started.to), ("offline", "flapping"));
+        server.status = ServerStatus::Error(502);
+        let stopped = StatusEvent::flapping(&server, FlapEvent::Stopped, now, &settings);
+        assert_eq!((stopped.from, stopped.to), ("flapping", "error"));
+        assert_eq!(stopped.status_code, Some(502));
+    }
+
+    #[tokio::test]
+    async fn webhook_sends_stable_status_ids_with_bearer_token() {
+        let mock = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(header("authorization", "Bearer s3cret"))
+            .and(body_partial_json(serde_json::json!({
+                "server": "web",
+                "url": "http://10.0.0.1",
+                "from": "online",
+                "to": "error",
+                "status_code": 503,
+            })))
+            .respond_with(ResponseTemplate::new(200))
+            .expect(1)
+            .mount(&mock)
+            .await;
+
+        let mut webhook = channel("ops", ChannelKind::Webhook, "s3cret");
+        webhook.url = mock.uri();
+        let client = testing::client(Duration::from_secs(5));
+        let change = StatusChange {
+            at: chrono::Local::now(),
+            server: "web".to_string(),
+            from: ServerStatus::Online,
+            to: ServerStatus::Error(503),
+            down_for: None,
+        };
+        let event = StatusEvent::new(&change, "http://10.0.0.1");
+        send(&client, &webhook, &event).await.unwrap();
+    }
+
+    #[test]
+    fn event_kind_and_env() {
+        let change = StatusChange {
+            at: chrono::Local::now(),
+            server: "web".to_string(),
+            from: ServerStatus::Offline,
+            to: ServerStatus::Online,
+            down_for: Some(chrono::Duration::seconds(95)),
+        };
+        let event = StatusEvent::new(&change, "http://10.0.0.1");
+        assert_eq!(event.kind(), "up");
+        let env = event.env();
+        let get = |name: &str| env.iter().find(|(k, _)| *k == name).unwrap().1.clone();
+        assert_eq!(get("SERVERCHECK_DOWNTIME_SECS"), "95");
+        assert_eq!(get("SERVERCHECK_STATUS_CODE"), "");
+        assert_eq!(StatusEvent::test().kind(), "test");
+        // 文档中列出了所有传入的变量
+        let docs = command_env_docs();
+        assert!(env.iter().all(|(k, _)| docs.iter().any(|(d, _)| d == k)));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn command_channel_passes_env() {
+        let mut hook = channel("hook", ChannelKind::Command, "");
+        hook.command =
+            r#"test "$SERVERCHECK_EVENT" = down && test "$SERVERCHECK_STATUS_CODE" = 503"#
+                .to_string();
+        let change = StatusChange {
+            at: chrono::Local::now(),
+            server: "web".to_string(),
+            from: ServerStatus::Online,
+            to: ServerStatus::Error(503),
+            down_for: None,
+        };
+        let event = StatusEvent::new(&change, "http://10.0.0.1");
+        send(&testing::client(Duration::from_secs(5)), &hook, &event)
+            .await
+            .unwrap();
+
+        hook.command = "echo boom >&2; exit 3".to_string();
+        let err = send(&testing::client(Duration::from_secs(5)), &hook, &event)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().contains("3") && err.to_string().contains("boom"));
+
+        // 标记的密钥读不到时不运行命令
+        hook.command = "exit 0".to_string();
+        hook.secrets = "server-check-test-missing".to_string();
+        assert!(
+            send(&testing::client(Duration::from_secs(5)), &hook, &event)
+                .await
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn imported_commands_start_disabled() {
+        let mut hook = channel("hook", ChannelKind::Command, "");
+        hook.command = "notify.sh".to_string();
+        let imported = import_template(&export_template(&[hook])).unwrap();
+        assert!(!imported[0].enabled);
+        assert_eq!(imported[0].command, "notify.sh");
+    }
+
+    #[tokio::test]
+    async fn send_reports_error_status_and_missing_token() {
+        let mock = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(500))
+            .mount(&mock)
+            .await;
+        let client = testing::client(Duration::from_secs(5));
+
+        let mut webhook = channel("ops", ChannelKind::Webhook, "");
+        webhook.url = mock.uri();
+        assert!(send(&client, &webhook, &StatusEvent::test()).await.is_err());
+        let feishu = channel("fs", ChannelKind::Feishu, "");
+        assert!(send(&client, &feishu, &StatusEvent::test()).await.is_err());
+    }
+}
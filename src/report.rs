@@ -0,0 +1,464 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::checks::transition::format_downtime;
+use crate::config::{ConfigLocation, Settings};
+use crate::history::History;
+use crate::i18n::tr;
+use crate::web::escape;
+use crate::{group_label, Server};
+
+// 定期生成的SLA报告：每个分组的可用率、故障次数、平均恢复时间(MTTR)和最长故障，
+// 保存在配置目录的 reports 子目录，文件名包含周期，已经生成过的周期不会重复生成
+
+const REPORTS_DIR: &str = "reports";
+// 多久检查一次是否需要生成报告
+pub const REPORT_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ReportPeriod {
+    // 每周一生成上一周（周一到周日）的报告
+    #[default]
+    Weekly,
+    // 每月1日生成上个月的报告
+    Monthly,
+}
+
+impl ReportPeriod {
+    pub const ALL: [ReportPeriod; 2] = [ReportPeriod::Weekly, ReportPeriod::Monthly];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReportPeriod::Weekly => tr!("每周", "Weekly"),
+            ReportPeriod::Monthly => tr!("每月", "Monthly"),
+        }
+    }
+
+    // now 之前最近一个完整的周期 [start, end)，以及文件名中的周期标识
+    pub fn last_complete(
+        &self,
+        now: DateTime<Local>,
+    ) -> (DateTime<Local>, DateTime<Local>, String) {
+        let today = now.date_naive();
+        let (start, end) = match self {
+            ReportPeriod::Weekly => {
+                let monday = today - Duration::days(today.weekday().num_days_from_monday() as i64);
+                (monday - Duration::days(7), monday)
+            }
+            ReportPeriod::Monthly => {
+                let first = today.with_day(1).unwrap_or(today);
+                let previous = (first - Duration::days(1)).with_day(1).unwrap_or(first);
+                (previous, first)
+            }
+        };
+        let label = match self {
+            ReportPeriod::Weekly => {
+                let week = start.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            ReportPeriod::Monthly => start.format("%Y-%m").to_string(),
+        };
+        (midnight(start, now), midnight(end, now), label)
+    }
+}
+
+fn midnight(date: NaiveDate, fallback: DateTime<Local>) -> DateTime<Local> {
+    date.and_hms_opt(0, 0, 0)
+        .and_then(|t| t.and_local_timezone(Local).earliest())
+        .unwrap_or(fallback)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ReportFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    pub const ALL: [ReportFormat; 2] = [ReportFormat::Markdown, ReportFormat::Html];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "Markdown",
+            ReportFormat::Html => "HTML",
+        }
+    }
+
+    fn extension(&self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "md",
+            ReportFormat::Html => "html",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ReportSettings {
+    pub enabled: bool,
+    pub period: ReportPeriod,
+    pub format: ReportFormat,
+    // 生成后把摘要发送到启用的通知渠道
+    pub notify: bool,
+}
+
+// 一台服务器在报告周期内的统计
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerSla {
+    pub name: String,
+    pub uptime: Option<f64>,
+    pub outages: Vec<Duration>,
+}
+
+impl ServerSla {
+    pub fn mttr(&self) -> Option<Duration> {
+        mean(&self.outages)
+    }
+
+    pub fn longest(&self) -> Option<Duration> {
+        self.outages.iter().max().copied()
+    }
+}
+
+fn mean(outages: &[Duration]) -> Option<Duration> {
+    (!outages.is_empty())
+        .then(|| outages.iter().fold(Duration::zero(), |sum, d| sum + *d) / outages.len() as i32)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct GroupReport {
+    pub group: String,
+    pub servers: Vec<ServerSla>,
+}
+
+impl GroupReport {
+    // 分组的可用率为有数据的服务器的平均值
+    pub fn uptime(&self) -> Option<f64> {
+        let values: Vec<f64> = self.servers.iter().filter_map(|s| s.uptime).collect();
+        (!values.is_empty()).then(|| values.iter().sum::<f64>() / values.len() as f64)
+    }
+
+    fn outages(&self) -> Vec<Duration> {
+        self.servers
+            .iter()
+            .flat_map(|s| s.outages.iter().copied())
+            .collect()
+    }
+
+    pub fn incidents(&self) -> usize {
+        self.servers.iter().map(|s| s.outages.len()).sum()
+    }
+
+    pub fn mttr(&self) -> Option<Duration> {
+        mean(&self.outages())
+    }
+
+    pub fn longest(&self) -> Option<Duration> {
+        self.outages().into_iter().max()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Report {
+    pub label: String,
+    pub start: DateTime<Local>,
+    pub end: DateTime<Local>,
+    pub groups: Vec<GroupReport>,
+}
+
+// 统计 [start, end) 内每个分组的数据，分组按第一次出现的顺序；可用率不计入分组的排除时段
+pub fn build(
+    servers: &[Server],
+    history: &History,
+    settings: &Settings,
+    start: DateTime<Local>,
+    end: DateTime<Local>,
+    label: String,
+) -> Report {
+    let mut groups: Vec<GroupReport> = Vec::new();
+    for server in servers {
+        let key = server.history_key();
+        let sla = ServerSla {
+            name: server.name.clone(),
+            uptime: history.uptime_between(
+                &key,
+                start,
+                Some(end),
+                settings.exclusions_for(&server.group),
+            ),
+            outages: history.outages(&key, start, end),
+        };
+        match groups.iter_mut().find(|g| g.group == server.group) {
+            Some(group) => group.servers.push(sla),
+            None => groups.push(GroupReport {
+                group: server.group.clone(),
+                servers: vec![sla],
+            }),
+        }
+    }
+    Report {
+        label,
+        start,
+        end,
+        groups,
+    }
+}
+
+fn uptime_text(uptime: Option<f64>) -> String {
+    uptime
+        .map(|v| format!("{:.2}%", v))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn duration_text(duration: Option<Duration>) -> String {
+    duration
+        .map(format_downtime)
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn title(report: &Report) -> String {
+    tr!(
+        "SLA报告 {}（{} 至 {}）",
+        "SLA report {} ({} to {})",
+        report.label,
+        report.start.format("%Y-%m-%d"),
+        (report.end - Duration::days(1)).format("%Y-%m-%d")
+    )
+}
+
+fn headers() -> [&'static str; 5] {
+    [
+        tr!("服务器", "Server"),
+        tr!("可用率", "Uptime"),
+        tr!("故障次数", "Incidents"),
+        "MTTR",
+        tr!("最长故障", "Longest outage"),
+    ]
+}
+
+// 每行为 名称、可用率、故障次数、MTTR、最长故障；第一行为分组汇总
+fn rows(group: &GroupReport) -> Vec<[String; 5]> {
+    let mut rows = vec![[
+        tr!("全部", "All").to_string(),
+        uptime_text(group.uptime()),
+        group.incidents().to_string(),
+        duration_text(group.mttr()),
+        duration_text(group.longest()),
+    ]];
+    for server in &group.servers {
+        rows.push([
+            server.name.clone(),
+            uptime_text(server.uptime),
+            server.outages.len().to_string(),
+            duration_text(server.mttr()),
+            duration_text(server.longest()),
+        ]);
+    }
+    rows
+}
+
+pub fn render(report: &Report, format: ReportFormat) -> String {
+    match format {
+        ReportFormat::Markdown => render_markdown(report),
+        ReportFormat::Html => render_html(report),
+    }
+}
+
+fn render_markdown(report: &Report) -> String {
+    // 表格中的 | 需要转义
+    let cell = |text: &str| text.replace('|', "\\|");
+    let mut out = format!("# {}\n", title(report));
+    for group in &report.groups {
+        out.push_str(&format!("\n## {}\n\n", cell(group_label(&group.group))));
+        out.push_str(&format!("| {} |\n", headers().join(" | ")));
+        out.push_str("| --- | ---: | ---: | ---: | ---: |\n");
+        for (i, row) in rows(group).iter().enumerate() {
+            let cells: Vec<String> = row.iter().map(|c| cell(c)).collect();
+            let line = cells.join(" | ");
+            if i == 0 {
+                // 汇总行加粗
+                out.push_str(&format!("| **{}** |\n", cells.join("** | **")));
+            } else {
+                out.push_str(&format!("| {} |\n", line));
+            }
+        }
+    }
+    out
+}
+
+fn render_html(report: &Report) -> String {
+    let title = escape(&title(report));
+    let mut out = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{}</title><style>\
+         body{{font-family:sans-serif;margin:2em auto;max-width:900px;padding:0 1em}}\
+         table{{border-collapse:collapse;width:100%;margin-bottom:1.5em}}\
+         th,td{{padding:.4em .6em;border-bottom:1px solid #ddd;text-align:right;font-size:14px}}\
+         th:first-child,td:first-child{{text-align:left}}tr.total td{{font-weight:bold;background:#f5f5f5}}\
+         </style></head><body><h1>{}</h1>",
+        title, title
+    );
+    for group in &report.groups {
+        out.push_str(&format!(
+            "<h2>{}</h2><table><tr>",
+            escape(group_label(&group.group))
+        ));
+        for header in headers() {
+            out.push_str(&format!("<th>{}</th>", header));
+        }
+        out.push_str("</tr>");
+        for (i, row) in rows(group).iter().enumerate() {
+            out.push_str(if i == 0 {
+                "<tr class=\"total\">"
+            } else {
+                "<tr>"
+            });
+            for cell in row {
+                out.push_str(&format!("<td>{}</td>", escape(cell)));
+            }
+            out.push_str("</tr>");
+        }
+        out.push_str("</table>");
+    }
+    out.push_str("</body></html>");
+    out
+}
+
+// 发送到通知渠道的摘要，每个分组一行
+pub fn summary(report: &Report) -> String {
+    let mut text = title(report);
+    for group in &report.groups {
+        text.push_str(&tr!(
+            "\n{}: 可用率 {}，故障 {} 次，MTTR {}，最长 {}",
+            "\n{}: uptime {}, {} incidents, MTTR {}, longest {}",
+            group_label(&group.group),
+            uptime_text(group.uptime()),
+            group.incidents(),
+            duration_text(group.mttr()),
+            duration_text(group.longest())
+        ));
+    }
+    text
+}
+
+pub fn reports_dir(location: &ConfigLocation) -> PathBuf {
+    location.dir.join(REPORTS_DIR)
+}
+
+fn report_path(dir: &Path, settings: &ReportSettings, label: &str) -> PathBuf {
+    dir.join(format!("sla-{}.{}", label, settings.format.extension()))
+}
+
+// 生成上一个完整周期的报告并保存，返回文件路径
+pub fn generate(
+    servers: &[Server],
+    history: &History,
+    settings: &Settings,
+    now: DateTime<Local>,
+    dir: &Path,
+) -> std::io::Result<(PathBuf, Report)> {
+    let (start, end, label) = settings.report.period.last_complete(now);
+    let path = report_path(dir, &settings.report, &label);
+    let report = build(servers, history, settings, start, end, label);
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(&path, render(&report, settings.report.format))?;
+    Ok((path, report))
+}
+
+// 启用且上一个周期的报告还没有生成时生成
+pub fn generate_due(
+    servers: &[Server],
+    history: &History,
+    settings: &Settings,
+    now: DateTime<Local>,
+    dir: &Path,
+) -> std::io::Result<Option<(PathBuf, Report)>> {
+    if !settings.report.enabled {
+        return Ok(None);
+    }
+    let (_, _, label) = settings.report.period.last_complete(now);
+    if report_path(dir, &settings.report, &label).exists() {
+        return Ok(None);
+    }
+    generate(servers, history, settings, now, dir).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use crate::ServerStatus;
+
+    fn at(y: i32, m: u32, d: u32, h: u32) -> DateTime<Local> {
+        NaiveDate::from_ymd_opt(y, m, d)
+            .unwrap()
+            .and_hms_opt(h, 0, 0)
+            .unwrap()
+            .and_local_timezone(Local)
+            .unwrap()
+    }
+
+    #[test]
+    fn last_complete_period() {
+        // 2024-05-08 是周三
+        let now = at(2024, 5, 8, 10);
+        let (start, end, label) = ReportPeriod::Weekly.last_complete(now);
+        assert_eq!((start, end), (at(2024, 4, 29, 0), at(2024, 5, 6, 0)));
+        assert_eq!(label, "2024-W18");
+
+        let (start, end, label) = ReportPeriod::Monthly.last_complete(at(2024, 3, 1, 0));
+        assert_eq!((start, end), (at(2024, 2, 1, 0), at(2024, 3, 1, 0)));
+        assert_eq!(label, "2024-02");
+        let (start, _, label) = ReportPeriod::Monthly.last_complete(at(2024, 1, 15, 0));
+        assert_eq!(start, at(2023, 12, 1, 0));
+        assert_eq!(label, "2023-12");
+    }
+
+    #[test]
+    fn builds_group_stats_and_saves_once() {
+        let mut web = testing::http_server("http://10.0.0.1");
+        web.name = "web".to_string();
+        web.group = "prod".to_string();
+        let mut db = testing::http_server("http://10.0.0.2");
+        db.name = "db".to_string();
+        db.group = "prod".to_string();
+
+        // 上周一 09:00 起每5分钟检查一次
+        let now = at(2024, 5, 8, 10);
+        let start = at(2024, 4, 29, 9);
+        let minute = |m| start + Duration::minutes(m);
+        let mut history = History::default();
+        for m in (0..=60).step_by(5) {
+            let web_status = if (10..20).contains(&m) || m == 40 {
+                ServerStatus::Offline
+            } else {
+                ServerStatus::Online
+            };
+            history.record(&web.history_key(), minute(m), &web_status);
+            history.record(&db.history_key(), minute(m), &ServerStatus::Online);
+        }
+
+        let mut settings = Settings::default();
+        settings.report.enabled = true;
+        let dir = testing::TempDir::new("report");
+        let (path, report) = generate_due(&[web, db], &history, &settings, now, &dir)
+            .unwrap()
+            .unwrap();
+        assert!(path.ends_with("sla-2024-W18.md"));
+
+        let group = &report.groups[0];
+        assert_eq!(group.incidents(), 2);
+        assert_eq!(group.servers[0].longest(), Some(Duration::minutes(10)));
+        assert_eq!(group.mttr(), Some(Duration::minutes(15) / 2));
+        assert_eq!(group.servers[1].uptime, Some(100.0));
+        let markdown = std::fs::read_to_string(&path).unwrap();
+        assert!(markdown.contains("## prod"));
+        assert!(markdown.contains("| web | 75.00% | 2 |"));
+
+        // 这一周期已经生成过
+        assert!(generate_due(&[], &history, &settings, now, &dir)
+            .unwrap()
+            .is_none());
+    }
+}
@@ -0,0 +1,421 @@
+use chrono::{Local, NaiveDate, NaiveDateTime, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::i18n::tr;
+use crate::{template, Server};
+
+// 定时动作类型
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ActionKind {
+    Remind,  // 弹出提醒
+    Webhook, // 调用Webhook
+}
+
+impl ActionKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            ActionKind::Remind => tr!("⏰ 提醒", "⏰ Reminder"),
+            ActionKind::Webhook => "🔗 Webhook",
+        }
+    }
+}
+
+// 每台服务器上挂载的定时动作，例如每天03:00提醒重启
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduledAction {
+    pub enabled: bool,
+    pub hour: u32,
+    pub minute: u32,
+    pub kind: ActionKind,
+    // 提醒内容，同时作为Webhook请求中的message字段
+    pub message: String,
+    #[serde(default)]
+    pub webhook_url: String,
+    // 上次执行日期，仅用于界面展示
+    #[serde(default)]
+    pub last_run: Option<NaiveDate>,
+}
+
+impl Default for ScheduledAction {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            hour: 3,
+            minute: 0,
+            kind: ActionKind::Remind,
+            message: "重启服务".to_string(),
+            webhook_url: String::new(),
+            last_run: None,
+        }
+    }
+}
+
+impl ScheduledAction {
+    fn time(&self) -> NaiveTime {
+        NaiveTime::from_hms_opt(self.hour.min(23), self.minute.min(59), 0).unwrap_or_default()
+    }
+
+    pub fn time_label(&self) -> String {
+        format!("{:02}:{:02}", self.hour.min(23), self.minute.min(59))
+    }
+}
+
+// 到期需要执行的动作
+#[derive(Debug, Clone)]
+pub struct DueAction {
+    pub server_name: String,
+    pub server_url: String,
+    pub action: ScheduledAction,
+}
+
+// 定时调度器：记录上一次检查的时间点，执行所有在(上次, 本次]区间内到期的动作。
+// 程序启动前已经过去的时间点不会补执行。
+pub struct Scheduler {
+    last_tick: NaiveDateTime,
+}
+
+impl Scheduler {
+    pub fn new(now: NaiveDateTime) -> Self {
+        Self { last_tick: now }
+    }
+
+    pub fn tick(&mut self, now: NaiveDateTime, servers: &mut [Server]) -> Vec<DueAction> {
+        let mut due = Vec::new();
+        if now <= self.last_tick {
+            return due;
+        }
+
+        for server in servers.iter_mut() {
+            for action in server.actions.iter_mut().filter(|a| a.enabled) {
+                let target = now.date().and_time(action.time());
+                if self.last_tick < target && target <= now {
+                    action.last_run = Some(now.date());
+                    due.push(DueAction {
+                        server_name: server.name.clone(),
+                        server_url: server.url.clone(),
+                        action: action.clone(),
+                    });
+                }
+            }
+        }
+
+        self.last_tick = now;
+        due
+    }
+}
+
+// 固定间隔的自动检查计时
+pub struct IntervalTimer {
+    pub interval: Duration,
+    last: Instant,
+}
+
+impl IntervalTimer {
+    pub fn new(interval: Duration, now: Instant) -> Self {
+        Self {
+            interval,
+            last: now,
+        }
+    }
+
+    // 到期时返回true，并从 now 重新计时
+    pub fn poll(&mut self, now: Instant) -> bool {
+        if now.saturating_duration_since(self.last) < self.interval {
+            return false;
+        }
+        self.last = now;
+        true
+    }
+
+    // 手动检查后重新计时
+    pub fn reset(&mut self, now: Instant) {
+        self.last = now;
+    }
+}
+
+// 程序启动后的首次检查延迟与通知宽限期。监控电脑和服务器一起重启时，
+// 刚启动的一段时间内网络和服务都可能还没就绪，这期间的状态变化不发送通知。
+// 宽限期从第一轮检查完成时开始计算，否则宽限期不长于首次检查延迟时，结果到达前就已结束
+pub struct StartupTimer {
+    started: Instant,
+    first_check_pending: bool,
+    first_cycle: Option<Instant>,
+    grace_pending: bool,
+}
+
+impl StartupTimer {
+    pub fn new(now: Instant) -> Self {
+        Self {
+            started: now,
+            first_check_pending: true,
+            first_cycle: None,
+            grace_pending: true,
+        }
+    }
+
+    // 到了首次自动检查的时间时返回true，只返回一次
+    pub fn first_check_due(&mut self, now: Instant, delay: Duration) -> bool {
+        if !self.first_check_pending || now.saturating_duration_since(self.started) < delay {
+            return false;
+        }
+        self.first_check_pending = false;
+        true
+    }
+
+    // 在延迟结束前手动检查过，就不再单独做首次检查
+    pub fn checked(&mut self) {
+        self.first_check_pending = false;
+    }
+
+    pub fn first_check_pending(&self) -> bool {
+        self.first_check_pending
+    }
+
+    // 一轮检查完成，第一次调用时开始宽限期
+    pub fn cycle_done(&mut self, now: Instant) {
+        self.first_cycle.get_or_insert(now);
+    }
+
+    pub fn in_grace(&self, now: Instant, grace: Duration) -> bool {
+        !grace.is_zero()
+            && self
+                .first_cycle
+                .is_none_or(|t| now.saturating_duration_since(t) < grace)
+    }
+
+    // 宽限期刚结束时返回true，只返回一次；没有设置宽限期时不返回
+    pub fn grace_ended(&mut self, now: Instant, grace: Duration) -> bool {
+        if !self.grace_pending || self.in_grace(now, grace) {
+            return false;
+        }
+        self.grace_pending = false;
+        !grace.is_zero()
+    }
+}
+
+// 日志条目
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub time: NaiveDateTime,
+    pub message: String,
+}
+
+// 定时任务执行日志，后台任务也会写入
+#[derive(Clone, Default)]
+pub struct ActionLog {
+    entries: Arc<Mutex<Vec<LogEntry>>>,
+}
+
+impl ActionLog {
+    const MAX_ENTRIES: usize = 200;
+
+    pub fn push(&self, message: String) {
+        println!("[定时任务] {}", message);
+        let mut entries = self.entries.lock().unwrap();
+        entries.push(LogEntry {
+            time: Local::now().naive_local(),
+            message,
+        });
+        let len = entries.len();
+        if len > Self::MAX_ENTRIES {
+            entries.drain(..len - Self::MAX_ENTRIES);
+        }
+    }
+
+    pub fn entries(&self) -> Vec<LogEntry> {
+        self.entries.lock().unwrap().clone()
+    }
+}
+
+// POST JSON到Webhook，返回状态码；通知渠道也通过它发送
+pub async fn post_json(
+    client: &reqwest::Client,
+    url: &str,
+    body: &serde_json::Value,
+    bearer: &str,
+) -> reqwest::Result<reqwest::StatusCode> {
+    let mut request = client.post(url).json(body);
+    if !bearer.is_empty() {
+        request = request.bearer_auth(bearer);
+    }
+    Ok(request.send().await?.status())
+}
+
+// 调用Webhook，结果写入日志
+pub fn fire_webhook(client: reqwest::Client, due: DueAction, log: ActionLog) {
+    tokio::spawn(async move {
+        let body = serde_json::json!({
+            "server": due.server_name,
+            "url": due.server_url,
+            "message": template::expand(&due.action.message),
+            "scheduled_at": due.action.time_label(),
+            "fired_at": Local::now().to_rfc3339(),
+        });
+
+        let url = template::expand(&due.action.webhook_url);
+        match post_json(&client, &url, &body, "").await {
+            Ok(status) if status.is_success() => log.push(tr!(
+                "{}: Webhook调用成功 ({})",
+                "{}: webhook succeeded ({})",
+                due.server_name,
+                status.as_u16()
+            )),
+            Ok(status) => log.push(tr!(
+                "{}: Webhook返回错误状态 ({})",
+                "{}: webhook returned error status ({})",
+                due.server_name,
+                status.as_u16()
+            )),
+            Err(e) => log.push(tr!(
+                "{}: Webhook调用失败: {}",
+                "{}: webhook failed: {}",
+                due.server_name,
+                e
+            )),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+    use crate::testing;
+    use chrono::TimeZone;
+
+    fn clock_at(hour: u32, minute: u32) -> ManualClock {
+        ManualClock::at(Local.with_ymd_and_hms(2024, 3, 1, hour, minute, 0).unwrap())
+    }
+
+    fn server_with_action(hour: u32, minute: u32) -> Server {
+        let mut server = testing::http_server("http://127.0.0.1:8080");
+        server.actions.push(ScheduledAction {
+            hour,
+            minute,
+            ..Default::default()
+        });
+        server
+    }
+
+    #[test]
+    fn interval_timer_fires_once_per_interval() {
+        let clock = clock_at(12, 0);
+        let mut timer = IntervalTimer::new(Duration::from_secs(30), clock.instant());
+        assert!(!timer.poll(clock.instant()));
+
+        clock.advance(Duration::from_secs(29));
+        assert!(!timer.poll(clock.instant()));
+        clock.advance(Duration::from_secs(1));
+        assert!(timer.poll(clock.instant()));
+        assert!(!timer.poll(clock.instant()));
+
+        // 手动检查后从头计时
+        clock.advance(Duration::from_secs(20));
+        timer.reset(clock.instant());
+        clock.advance(Duration::from_secs(20));
+        assert!(!timer.poll(clock.instant()));
+        clock.advance(Duration::from_secs(10));
+        assert!(timer.poll(clock.instant()));
+    }
+
+    #[test]
+    fn startup_timer_delays_first_check_and_ends_grace_once() {
+        let clock = clock_at(12, 0);
+        let mut startup = StartupTimer::new(clock.instant());
+        let delay = Duration::from_secs(10);
+        let grace = Duration::from_secs(60);
+
+        assert!(!startup.first_check_due(clock.instant(), delay));
+        assert!(startup.in_grace(clock.instant(), grace));
+        clock.advance(delay);
+        assert!(startup.first_check_due(clock.instant(), delay));
+        assert!(!startup.first_check_due(clock.instant(), delay));
+
+        // 第一轮检查完成前宽限期不会结束
+        clock.advance(grace);
+        assert!(startup.in_grace(clock.instant(), grace));
+        assert!(!startup.grace_ended(clock.instant(), grace));
+        startup.cycle_done(clock.instant());
+        clock.advance(grace / 2);
+        startup.cycle_done(clock.instant());
+        assert!(!startup.grace_ended(clock.instant(), grace));
+        clock.advance(grace / 2);
+        assert!(!startup.in_grace(clock.instant(), grace));
+        assert!(startup.grace_ended(clock.instant(), grace));
+        assert!(!startup.grace_ended(clock.instant(), grace));
+    }
+
+    #[test]
+    fn manual_check_cancels_first_check_and_zero_grace_is_silent() {
+        let clock = clock_at(12, 0);
+        let mut startup = StartupTimer::new(clock.instant());
+        startup.checked();
+        clock.advance(Duration::from_secs(60));
+        assert!(!startup.first_check_due(clock.instant(), Duration::ZERO));
+        assert!(!startup.in_grace(clock.instant(), Duration::ZERO));
+        assert!(!startup.grace_ended(clock.instant(), Duration::ZERO));
+    }
+
+    #[test]
+    fn action_fires_once_a_day() {
+        let clock = clock_at(2, 0);
+        let mut scheduler = Scheduler::new(clock.now().naive_local());
+        let mut servers = vec![server_with_action(3, 0)];
+
+        // 每分钟检查一次，跨过两个03:00
+        let mut fired = Vec::new();
+        for _ in 0..(2 * 24 * 60) {
+            clock.advance(Duration::from_secs(60));
+            for due in scheduler.tick(clock.now().naive_local(), &mut servers) {
+                fired.push((clock.now().naive_local(), due.server_name));
+            }
+        }
+        assert_eq!(fired.len(), 2);
+        assert_eq!(fired[0].0.time(), NaiveTime::from_hms_opt(3, 0, 0).unwrap());
+        assert_eq!(
+            servers[0].actions[0].last_run,
+            Some(NaiveDate::from_ymd_opt(2024, 3, 2).unwrap())
+        );
+    }
+
+    #[test]
+    fn missed_time_before_start_is_not_run() {
+        let clock = clock_at(3, 30);
+        let mut scheduler = Scheduler::new(clock.now().naive_local());
+        let mut servers = vec![server_with_action(3, 0)];
+        clock.advance(Duration::from_secs(60));
+        assert!(scheduler
+            .tick(clock.now().naive_local(), &mut servers)
+            .is_empty());
+    }
+
+    #[test]
+    fn long_pause_between_ticks_still_fires() {
+        // 界面长时间没有刷新，下次刷新时补上区间内到期的动作
+        let clock = clock_at(2, 0);
+        let mut scheduler = Scheduler::new(clock.now().naive_local());
+        let mut servers = vec![server_with_action(3, 0), server_with_action(4, 0)];
+        servers[1].actions[0].enabled = false;
+        clock.advance(Duration::from_secs(90 * 60));
+        let due = scheduler.tick(clock.now().naive_local(), &mut servers);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].action.time_label(), "03:00");
+    }
+
+    #[test]
+    fn time_going_backwards_is_ignored() {
+        let clock = clock_at(3, 0);
+        let mut scheduler = Scheduler::new(clock.now().naive_local());
+        let mut servers = vec![server_with_action(2, 59)];
+        let earlier = clock.now().naive_local() - chrono::Duration::minutes(5);
+        assert!(scheduler.tick(earlier, &mut servers).is_empty());
+        // 时钟回拨后不从更早的时间重新计算，02:59 的动作不会再执行一次
+        clock.advance(Duration::from_secs(60));
+        assert!(scheduler
+            .tick(clock.now().naive_local(), &mut servers)
+            .is_empty());
+        assert_eq!(servers[0].actions[0].last_run, None);
+    }
+}
@@ -0,0 +1,141 @@
+use anyhow::{anyhow, Result};
+
+use crate::i18n::tr;
+
+// 密码类的字段可以不直接写在配置文件中：填 keyring:名称 时从系统钥匙串读取（Windows 凭据管理器、
+// macOS 钥匙串、Linux Secret Service），填 env:变量名 时从环境变量读取，适合在容器中运行。
+// 其他内容原样作为密钥使用，兼容直接填写的配置
+
+// 钥匙串中条目的服务名
+const SERVICE: &str = "server_check";
+const KEYRING_PREFIX: &str = "keyring:";
+const ENV_PREFIX: &str = "env:";
+// 钩子和命令检查中标记的密钥以这个前缀加名称的环境变量传入
+pub const HOOK_ENV_PREFIX: &str = "SERVERCHECK_SECRET_";
+
+fn entry(name: &str) -> Result<keyring::Entry> {
+    keyring::Entry::new(SERVICE, name.trim()).map_err(|e| {
+        anyhow!(tr!(
+            "无法打开钥匙串条目 {}: {}",
+            "Cannot open keychain entry {}: {}",
+            name,
+            e
+        ))
+    })
+}
+
+// 读取钥匙串中的一个密钥
+pub fn keyring_get(name: &str) -> Result<String> {
+    entry(name)?.get_password().map_err(|e| {
+        anyhow!(tr!(
+            "无法从钥匙串读取 {}: {}",
+            "Cannot read {} from the keychain: {}",
+            name,
+            e
+        ))
+    })
+}
+
+// 把密钥存入钥匙串，返回写在配置中的引用
+pub fn store(name: &str, value: &str) -> Result<String> {
+    entry(name)?.set_password(value).map_err(|e| {
+        anyhow!(tr!(
+            "无法把 {} 存入钥匙串: {}",
+            "Cannot store {} in the keychain: {}",
+            name,
+            e
+        ))
+    })?;
+    Ok(format!("{}{}", KEYRING_PREFIX, name.trim()))
+}
+
+pub fn is_reference(value: &str) -> bool {
+    let value = value.trim();
+    value.starts_with(KEYRING_PREFIX) || value.starts_with(ENV_PREFIX)
+}
+
+// 取得字段实际的密钥；钥匙串会阻塞，异步代码中使用 resolve_async
+pub fn resolve(value: &str) -> Result<String> {
+    let trimmed = value.trim();
+    if let Some(name) = trimmed.strip_prefix(KEYRING_PREFIX) {
+        return keyring_get(name);
+    }
+    if let Some(var) = trimmed.strip_prefix(ENV_PREFIX) {
+        return std::env::var(var.trim()).map_err(|_| {
+            anyhow!(tr!(
+                "环境变量 {} 未设置",
+                "Environment variable {} is not set",
+                var.trim()
+            ))
+        });
+    }
+    Ok(value.to_string())
+}
+
+pub async fn resolve_async(value: &str) -> Result<String> {
+    if !is_reference(value) {
+        return Ok(value.to_string());
+    }
+    let value = value.to_string();
+    tokio::task::spawn_blocking(move || resolve(&value)).await?
+}
+
+// 逗号分隔的钥匙串条目名称
+pub fn names(list: &str) -> impl Iterator<Item = &str> {
+    list.split(',').map(str::trim).filter(|n| !n.is_empty())
+}
+
+// 名称中字母和数字以外的字符换成 _，例如 db-password -> SERVERCHECK_SECRET_DB_PASSWORD
+pub fn hook_env_name(name: &str) -> String {
+    let name: String = name
+        .trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{}{}", HOOK_ENV_PREFIX, name)
+}
+
+// 读取钩子或命令检查标记的密钥，返回要设置的环境变量；读取失败时不运行命令
+pub async fn hook_env(list: &str) -> Result<Vec<(String, String)>> {
+    let names: Vec<String> = names(list).map(str::to_string).collect();
+    if names.is_empty() {
+        return Ok(Vec::new());
+    }
+    tokio::task::spawn_blocking(move || {
+        names
+            .iter()
+            .map(|name| Ok((hook_env_name(name), keyring_get(name)?)))
+            .collect()
+    })
+    .await?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolves_references() {
+        std::env::set_var("SERVERCHECK_TEST_SECRET", "s3cret");
+        assert_eq!(resolve("env:SERVERCHECK_TEST_SECRET").unwrap(), "s3cret");
+        assert!(resolve("env:SERVERCHECK_TEST_MISSING").is_err());
+        // 直接填写的密钥原样使用，包括首尾空格
+        assert_eq!(resolve(" plain ").unwrap(), " plain ");
+        assert!(is_reference(" keyring:db") && !is_reference("hunter2"));
+
+        assert_eq!(
+            names(" db-password, ,api ").collect::<Vec<_>>(),
+            ["db-password", "api"]
+        );
+        assert_eq!(
+            hook_env_name("db-password"),
+            "SERVERCHECK_SECRET_DB_PASSWORD"
+        );
+    }
+}
@@ -0,0 +1,34 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::Path;
+
+use crate::filter::ServerFilter;
+
+// 界面状态，退出时保存到 session.json，下次启动时恢复到离开时的样子
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub filter: ServerFilter,
+    // 卡片视图中折叠的分组
+    pub collapsed_groups: BTreeSet<String>,
+    // 勾选的服务器，以历史记录的键保存，避免列表顺序变化后选错
+    pub selected: Vec<String>,
+    // 服务器列表的滚动位置
+    pub scroll_offset: f32,
+    pub log_open: bool,
+    pub uptime_open: bool,
+}
+
+impl Session {
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Duration, Local};
+
+use crate::config::{ConfigLocation, Settings};
+use crate::filter::{self, StatusFilter};
+use crate::history::History;
+use crate::i18n::tr;
+use crate::web::{category_color, escape};
+use crate::{group_label, Server};
+
+// 导出的静态状态页：当前状态和24小时可用率，不依赖外部资源，
+// 可以直接放到内网共享目录给不使用本程序的人查看
+
+const TEMPLATE: &str = include_str!("../assets/status_page.html");
+const STATUS_PAGE_FILE: &str = "status.html";
+
+// 未在设置中指定时导出到配置目录
+pub fn default_path(location: &ConfigLocation) -> PathBuf {
+    location.dir.join(STATUS_PAGE_FILE)
+}
+
+fn uptime_cell(uptime: Option<f64>, color: &str) -> String {
+    match uptime {
+        Some(value) => format!(
+            "<span class=\"bar\"><span style=\"width:{:.0}%;background:{}\"></span></span>{:.2}%",
+            value, color, value
+        ),
+        None => "<span class=\"muted\">-</span>".to_string(),
+    }
+}
+
+pub fn render(
+    servers: &[Server],
+    history: &History,
+    settings: &Settings,
+    now: DateTime<Local>,
+) -> String {
+    let colors = &settings.status_colors;
+    let mut summary = String::new();
+    for (filter, count) in filter::status_counts(servers) {
+        summary.push_str(&format!(
+            "<span class=\"count\"><span class=\"dot\" style=\"background:{}\"></span>{} {}</span>",
+            category_color(filter, colors),
+            filter.label(),
+            count
+        ));
+    }
+
+    // 分组按第一次出现的顺序
+    let mut groups: Vec<&str> = Vec::new();
+    for server in servers {
+        if !groups.contains(&server.group.as_str()) {
+            groups.push(&server.group);
+        }
+    }
+    let since = now - Duration::hours(24);
+    let header = format!(
+        "<tr><th>{}</th><th>{}</th><th>{}</th><th>{}</th><th>{}</th><th>{}</th></tr>",
+        tr!("名称", "Name"),
+        tr!("地址", "Address"),
+        tr!("状态", "Status"),
+        tr!("延迟", "Latency"),
+        tr!("24小时可用率", "24h uptime"),
+        tr!("上次检查", "Last check"),
+    );
+    let mut tables = String::new();
+    for group in groups {
+        tables.push_str(&format!(
+            "<h2>{}</h2><table>{}",
+            escape(group_label(group)),
+            header
+        ));
+        let exclusions = settings.exclusions_for(group);
+        for server in servers.iter().filter(|s| s.group == group) {
+            let filter = StatusFilter::of(server);
+            let color = category_color(filter, colors);
+            let uptime = history.uptime(&server.history_key(), since, exclusions);
+            tables.push_str(&format!(
+                "<tr><td>{}</td><td class=\"muted\">{}</td>\
+                 <td title=\"{}\"><span class=\"dot\" style=\"background:{}\"></span>{}</td>\
+                 <td>{}</td><td>{}</td><td class=\"muted\">{}</td></tr>",
+                escape(&server.name),
+                escape(&server.target_label()),
+                escape(&server.state.detail),
+                color,
+                escape(&server.status_label(now)),
+                server
+                    .state
+                    .latency_ms
+                    .map(|ms| format!("{} ms", ms))
+                    .unwrap_or_default(),
+                uptime_cell(uptime, &category_color(StatusFilter::Online, colors)),
+                server
+                    .state
+                    .last_checked
+                    .map(|t| t.format("%m-%d %H:%M").to_string())
+                    .unwrap_or_default(),
+            ));
+        }
+        tables.push_str("</table>");
+    }
+
+    let generated = tr!(
+        "生成时间: {}，共 {} 台服务器",
+        "Generated at {}, {} servers",
+        now.format("%Y-%m-%d %H:%M:%S"),
+        servers.len()
+    );
+    TEMPLATE
+        .replace("{{title}}", &escape(tr!("服务器状态", "Server status")))
+        .replace("{{generated}}", &escape(&generated))
+        .replace("{{summary}}", &summary)
+        .replace("{{groups}}", &tables)
+}
+
+// 先写临时文件再改名，共享目录上的读者不会读到写了一半的页面
+pub fn export(path: &Path, html: &str) -> std::io::Result<()> {
+    let tmp = path.with_extension("html.tmp");
+    std::fs::write(&tmp, html)?;
+    std::fs::rename(&tmp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use crate::ServerStatus;
+
+    #[test]
+    fn renders_status_and_uptime() {
+        let now = Local::now();
+        let mut up = testing::http_server("http://10.0.0.1");
+        up.name = "<api>".to_string();
+        up.group = "prod".to_string();
+        up.status = ServerStatus::Online;
+        let mut down = testing::http_server("http://10.0.0.2");
+        down.name = "db".to_string();
+        down.status = ServerStatus::Offline;
+
+        let mut history = History::default();
+        history.record(&up.history_key(), now - Duration::minutes(5), &up.status);
+        history.record(&up.history_key(), now, &up.status);
+
+        let html = render(&[up, down], &history, &Settings::default(), now);
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(!html.contains("{{"));
+        assert!(html.contains("&lt;api&gt;"));
+        assert!(html.contains("<h2>prod</h2>"));
+        assert!(html.contains("100.00%"));
+        // 没有历史记录的服务器不显示可用率
+        assert!(html.contains("<span class=\"muted\">-</span>"));
+
+        let dir = testing::TempDir::new("page");
+        let path = dir.join("status.html");
+        export(&path, &html).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), html);
+    }
+}
@@ -0,0 +1,73 @@
+use anyhow::Result;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use super::{Storage, BASELINE_DOC, HISTORY_DOC, SERVERS_PREFIX};
+use crate::config::{ConfigLocation, SERVERS_FILE};
+
+// 保存在配置目录中，文件布局与之前相同：servers.json、profiles/<方案名>.json、history.json 等
+pub struct JsonFileStorage {
+    location: ConfigLocation,
+}
+
+impl JsonFileStorage {
+    pub fn new(location: ConfigLocation) -> Self {
+        Self { location }
+    }
+
+    fn path(&self, doc: &str) -> PathBuf {
+        match doc {
+            HISTORY_DOC => self.location.history_path(),
+            BASELINE_DOC => self.location.baseline_path(),
+            _ => match doc.strip_prefix(SERVERS_PREFIX) {
+                Some(profile) => self.location.profile_path(profile),
+                None => self.location.dir.join(format!("{}.json", doc)),
+            },
+        }
+    }
+}
+
+impl Storage for JsonFileStorage {
+    fn load(&self, doc: &str) -> Result<Option<String>> {
+        match std::fs::read_to_string(self.path(doc)) {
+            Ok(content) => Ok(Some(content)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn save(&self, doc: &str, content: &str) -> Result<()> {
+        let path = self.path(doc);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, content)?;
+        Ok(())
+    }
+
+    fn delete(&self, doc: &str) -> Result<()> {
+        std::fs::remove_file(self.path(doc))?;
+        Ok(())
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        // 只有服务器列表有多份
+        if prefix != SERVERS_PREFIX {
+            return Ok(Vec::new());
+        }
+        let mut docs = Vec::new();
+        if self.location.dir.join(SERVERS_FILE).exists() {
+            docs.push(super::servers_doc(crate::config::DEFAULT_PROFILE));
+        }
+        docs.extend(
+            std::fs::read_dir(self.location.profiles_dir())
+                .into_iter()
+                .flatten()
+                .flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+                .filter_map(|path| path.file_stem()?.to_str().map(super::servers_doc)),
+        );
+        Ok(docs)
+    }
+}
@@ -0,0 +1,137 @@
+pub mod json_file;
+pub mod remote;
+pub mod sqlite;
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::config::{is_valid_profile_name, ConfigLocation, DEFAULT_PROFILE};
+use crate::i18n::tr;
+
+// 文档名：服务器列表按配置方案各一份，历史记录和安全基线各一份
+pub const HISTORY_DOC: &str = "history";
+pub const BASELINE_DOC: &str = "baseline";
+const SERVERS_PREFIX: &str = "servers/";
+
+pub fn servers_doc(profile: &str) -> String {
+    format!("{}{}", SERVERS_PREFIX, profile)
+}
+
+// 服务器列表、状态历史和安全基线的存储，按文档名整份读写JSON；
+// 程序设置和界面状态始终保存在本地
+pub trait Storage: Send + Sync {
+    // 文档不存在时返回 None
+    fn load(&self, doc: &str) -> Result<Option<String>>;
+    fn save(&self, doc: &str, content: &str) -> Result<()>;
+    fn delete(&self, doc: &str) -> Result<()>;
+    // 以 prefix 开头的文档名
+    fn list(&self, prefix: &str) -> Result<Vec<String>>;
+    // 等待在后台进行的写入完成，返回其中的错误
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+// 存储方式
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum StorageKind {
+    // 配置目录中的JSON文件
+    #[default]
+    JsonFile,
+    Sqlite,
+    // 团队共用的HTTP服务
+    Remote,
+}
+
+impl StorageKind {
+    pub const ALL: [StorageKind; 3] = [
+        StorageKind::JsonFile,
+        StorageKind::Sqlite,
+        StorageKind::Remote,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StorageKind::JsonFile => tr!("JSON文件", "JSON files"),
+            StorageKind::Sqlite => "SQLite",
+            StorageKind::Remote => tr!("远程HTTP", "Remote HTTP"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageSettings {
+    pub kind: StorageKind,
+    // 数据库文件，留空时使用配置目录中的 servercheck.db
+    pub sqlite_path: String,
+    // 例如 https://config.example.com/servercheck
+    pub remote_url: String,
+    // 可选，作为 Bearer 令牌发送
+    pub remote_token: String,
+}
+
+pub fn open(settings: &StorageSettings, location: &ConfigLocation) -> Result<Arc<dyn Storage>> {
+    Ok(match settings.kind {
+        StorageKind::JsonFile => Arc::new(json_file::JsonFileStorage::new(location.clone())),
+        StorageKind::Sqlite => {
+            let path = match settings.sqlite_path.trim() {
+                "" => location.dir.join(sqlite::DEFAULT_DB_FILE),
+                path => path.into(),
+            };
+            Arc::new(sqlite::SqliteStorage::open(&path)?)
+        }
+        StorageKind::Remote => Arc::new(remote::RemoteStorage::new(
+            settings.remote_url.trim(),
+            settings.remote_token.trim(),
+        )?),
+    })
+}
+
+// 所有配置方案，默认方案排在最前
+pub fn list_profiles(storage: &dyn Storage) -> Vec<String> {
+    let mut profiles: Vec<String> = match storage.list(SERVERS_PREFIX) {
+        Ok(docs) => docs
+            .iter()
+            .filter_map(|doc| doc.strip_prefix(SERVERS_PREFIX))
+            .filter(|name| *name != DEFAULT_PROFILE)
+            .map(str::to_string)
+            .collect(),
+        Err(e) => {
+            eprintln!("读取配置方案列表失败: {}", e);
+            Vec::new()
+        }
+    };
+    profiles.sort();
+    profiles.insert(0, DEFAULT_PROFILE.to_string());
+    profiles
+}
+
+// 新建空的配置方案
+pub fn create_profile(storage: &dyn Storage, profile: &str) -> Result<()> {
+    if !is_valid_profile_name(profile) {
+        bail!(tr!(
+            "方案名称不能为空或包含路径字符",
+            "Profile name must not be empty or contain path characters"
+        ));
+    }
+    let doc = servers_doc(profile);
+    if storage.load(&doc)?.is_some() {
+        bail!(tr!(
+            "同名方案已存在",
+            "A profile with this name already exists"
+        ));
+    }
+    storage.save(&doc, "[]")
+}
+
+pub fn delete_profile(storage: &dyn Storage, profile: &str) -> Result<()> {
+    if profile == DEFAULT_PROFILE {
+        bail!(tr!(
+            "默认方案不能删除",
+            "The default profile cannot be deleted"
+        ));
+    }
+    storage.delete(&servers_doc(profile))
+}
@@ -0,0 +1,333 @@
+use anyhow::{anyhow, bail, Result};
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::Duration;
+use url::Url;
+
+use super::Storage;
+use crate::i18n::tr;
+use crate::secret;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+// 远程HTTP存储，服务端需要支持：
+//   GET/PUT/DELETE {地址}/{文档名}   读取、写入、删除一份文档，不存在时返回404
+//   GET {地址}?prefix=servers/       返回文档名的JSON数组
+// 保存和删除交给后台线程按顺序完成，界面和检查任务不必等待网络；还没写完的文档读取时使用本地的内容
+pub struct RemoteStorage {
+    remote: Remote,
+    pending: Pending,
+    next_seq: AtomicU64,
+    jobs: Option<mpsc::Sender<Job>>,
+    writer: Option<std::thread::JoinHandle<()>>,
+}
+
+// 等待写入的文档和写入的序号，内容为 None 表示删除
+type Pending = Arc<Mutex<HashMap<String, (u64, Option<String>)>>>;
+
+enum Job {
+    Write {
+        seq: u64,
+        doc: String,
+        content: Option<String>,
+    },
+    // 回复上次等待之后第一个写入错误
+    Flush(mpsc::Sender<Option<String>>),
+}
+
+struct Remote {
+    base: Url,
+    token: String,
+    client: reqwest::Client,
+}
+
+// 在界面线程和检查任务中都会调用，这里等待请求完成
+fn block_on<T>(future: impl Future<Output = Result<T>>) -> Result<T> {
+    match tokio::runtime::Handle::try_current() {
+        Ok(handle) => tokio::task::block_in_place(|| handle.block_on(future)),
+        Err(_) => tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()?
+            .block_on(future),
+    }
+}
+
+impl Remote {
+    fn new(base: &Url, token: &str) -> Result<Self> {
+        Ok(Self {
+            base: base.clone(),
+            token: token.to_string(),
+            client: reqwest::Client::builder()
+                .timeout(REQUEST_TIMEOUT)
+                .proxy(crate::proxy::global())
+                .build()?,
+        })
+    }
+
+    // 文档名作为一个路径段，其中的 / 会被编码
+    fn doc_url(&self, doc: &str) -> Url {
+        let mut url = self.base.clone();
+        if let Ok(mut segments) = url.path_segments_mut() {
+            segments.pop_if_empty().push(doc);
+        }
+        url
+    }
+
+    fn request(&self, method: reqwest::Method, url: Url) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, url);
+        if self.token.is_empty() {
+            request
+        } else {
+            request.bearer_auth(&self.token)
+        }
+    }
+
+    async fn load(&self, doc: &str) -> Result<Option<String>> {
+        let resp = self
+            .request(reqwest::Method::GET, self.doc_url(doc))
+            .send()
+            .await?;
+        if resp.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+        Ok(Some(resp.error_for_status()?.text().await?))
+    }
+
+    async fn save(&self, doc: &str, content: &str) -> Result<()> {
+        self.request(reqwest::Method::PUT, self.doc_url(doc))
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(content.to_string())
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    async fn delete(&self, doc: &str) -> Result<()> {
+        let resp = self
+            .request(reqwest::Method::DELETE, self.doc_url(doc))
+            .send()
+            .await?;
+        if resp.status() != reqwest::StatusCode::NOT_FOUND {
+            resp.error_for_status()?;
+        }
+        Ok(())
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut url = self.base.clone();
+        url.query_pairs_mut().append_pair("prefix", prefix);
+        self.request(reqwest::Method::GET, url)
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Vec<String>>()
+            .await
+            .map_err(|e| anyhow!(tr!("文档列表格式错误: {}", "Invalid document list: {}", e)))
+    }
+}
+
+// 后台线程有自己的运行时；写入失败时保留本地内容，之后读取仍得到最新的文档
+fn run_writer(remote: Remote, jobs: mpsc::Receiver<Job>, pending: Pending) {
+    let runtime = match tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            eprintln!("无法启动远程存储的写入线程: {}", e);
+            return;
+        }
+    };
+    let mut error = None;
+    while let Ok(job) = jobs.recv() {
+        let (seq, doc, content) = match job {
+            Job::Write { seq, doc, content } => (seq, doc, content),
+            Job::Flush(reply) => {
+                let _ = reply.send(error.take());
+                continue;
+            }
+        };
+        let result = runtime.block_on(async {
+            match &content {
+                Some(content) => remote.save(&doc, content).await,
+                None => remote.delete(&doc).await,
+            }
+        });
+        let mut pending = pending.lock().unwrap();
+        match result {
+            Ok(()) => {
+                if pending.get(&doc).is_some_and(|(s, _)| *s == seq) {
+                    pending.remove(&doc);
+                }
+            }
+            Err(e) => {
+                eprintln!("写入远程存储失败 {}: {:#}", doc, e);
+                error.get_or_insert(format!("{}: {:#}", doc, e));
+            }
+        }
+    }
+}
+
+impl RemoteStorage {
+    pub fn new(base: &str, token: &str) -> Result<Self> {
+        if base.is_empty() {
+            bail!(tr!("请填写远程存储地址", "Enter the remote storage URL"));
+        }
+        let base = Url::parse(base)?;
+        if base.cannot_be_a_base() {
+            bail!(tr!("远程存储地址无效", "Invalid remote storage URL"));
+        }
+        // 令牌可以填 keyring: 或 env: 引用
+        let token = secret::resolve(token)?;
+        let pending = Pending::default();
+        let (jobs, queue) = mpsc::channel();
+        let writer = {
+            let remote = Remote::new(&base, &token)?;
+            let pending = Arc::clone(&pending);
+            std::thread::spawn(move || run_writer(remote, queue, pending))
+        };
+        Ok(Self {
+            remote: Remote::new(&base, &token)?,
+            pending,
+            next_seq: AtomicU64::new(0),
+            jobs: Some(jobs),
+            writer: Some(writer),
+        })
+    }
+
+    fn send(&self, job: Job) -> Result<()> {
+        match &self.jobs {
+            Some(jobs) if jobs.send(job).is_ok() => Ok(()),
+            _ => bail!(tr!(
+                "远程存储的写入线程已停止",
+                "The remote storage writer has stopped"
+            )),
+        }
+    }
+
+    fn queue(&self, doc: &str, content: Option<String>) -> Result<()> {
+        let seq = self.next_seq.fetch_add(1, Ordering::SeqCst);
+        self.pending
+            .lock()
+            .unwrap()
+            .insert(doc.to_string(), (seq, content.clone()));
+        self.send(Job::Write {
+            seq,
+            doc: doc.to_string(),
+            content,
+        })
+    }
+}
+
+// 退出前等待排队的写入完成
+impl Drop for RemoteStorage {
+    fn drop(&mut self) {
+        self.jobs = None;
+        if let Some(writer) = self.writer.take() {
+            let _ = writer.join();
+        }
+    }
+}
+
+impl Storage for RemoteStorage {
+    fn load(&self, doc: &str) -> Result<Option<String>> {
+        if let Some((_, content)) = self.pending.lock().unwrap().get(doc) {
+            return Ok(content.clone());
+        }
+        block_on(self.remote.load(doc))
+    }
+
+    fn save(&self, doc: &str, content: &str) -> Result<()> {
+        self.queue(doc, Some(content.to_string()))
+    }
+
+    fn delete(&self, doc: &str) -> Result<()> {
+        self.queue(doc, None)
+    }
+
+    fn flush(&self) -> Result<()> {
+        let (reply, result) = mpsc::channel();
+        self.send(Job::Flush(reply))?;
+        match result.recv() {
+            Ok(Some(e)) => bail!(tr!(
+                "写入远程存储失败 {}",
+                "Failed to write to remote storage {}",
+                e
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let mut docs = block_on(self.remote.list(prefix))?;
+        for (doc, (_, content)) in self.pending.lock().unwrap().iter() {
+            if !doc.starts_with(prefix) {
+                continue;
+            }
+            match content {
+                Some(_) if !docs.contains(doc) => docs.push(doc.clone()),
+                None => docs.retain(|d| d != doc),
+                _ => {}
+            }
+        }
+        Ok(docs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use wiremock::matchers::{header, method, path, query_param};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    #[test]
+    fn writes_in_the_background() {
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        let mock = runtime.block_on(async {
+            let mock = MockServer::start().await;
+            Mock::given(method("PUT"))
+                .and(path("/store/servers%2Fdefault"))
+                .and(header("authorization", "Bearer t0ken"))
+                .respond_with(ResponseTemplate::new(204).set_delay(Duration::from_millis(200)))
+                .expect(1)
+                .mount(&mock)
+                .await;
+            Mock::given(method("GET"))
+                .and(path("/store"))
+                .and(query_param("prefix", "servers/"))
+                .respond_with(ResponseTemplate::new(200).set_body_json(["servers/lab"]))
+                .mount(&mock)
+                .await;
+            Mock::given(method("DELETE"))
+                .respond_with(ResponseTemplate::new(404))
+                .mount(&mock)
+                .await;
+            mock
+        });
+        std::env::set_var("SERVERCHECK_TEST_REMOTE_TOKEN", "t0ken");
+        let storage = RemoteStorage::new(
+            &format!("{}/store", mock.uri()),
+            "env:SERVERCHECK_TEST_REMOTE_TOKEN",
+        )
+        .unwrap();
+
+        // 保存立即返回，写完之前读取和列表使用本地的内容
+        let started = std::time::Instant::now();
+        storage.save("servers/default", "[]").unwrap();
+        storage.delete("servers/lab").unwrap();
+        assert!(started.elapsed() < Duration::from_millis(200));
+        assert_eq!(
+            storage.load("servers/default").unwrap().as_deref(),
+            Some("[]")
+        );
+        assert_eq!(storage.list("servers/").unwrap(), ["servers/default"]);
+
+        // 删除不存在的文档不算错误；释放时等待写入完成
+        storage.flush().unwrap();
+        drop(storage);
+        runtime.block_on(mock.verify());
+    }
+}
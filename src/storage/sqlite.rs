@@ -0,0 +1,70 @@
+use anyhow::Result;
+use rusqlite::{params, Connection, OptionalExtension};
+use std::path::Path;
+use std::sync::Mutex;
+
+use super::Storage;
+
+pub const DEFAULT_DB_FILE: &str = "servercheck.db";
+
+// 所有文档保存在一张表中，便于用其他工具查询或备份
+pub struct SqliteStorage {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStorage {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS documents (
+                name TEXT PRIMARY KEY,
+                content TEXT NOT NULL,
+                updated_at TEXT NOT NULL
+            )",
+            [],
+        )?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn load(&self, doc: &str) -> Result<Option<String>> {
+        let conn = self.conn.lock().unwrap();
+        Ok(conn
+            .query_row(
+                "SELECT content FROM documents WHERE name = ?1",
+                params![doc],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn save(&self, doc: &str, content: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO documents (name, content, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(name) DO UPDATE SET content = excluded.content, updated_at = excluded.updated_at",
+            params![doc, content, chrono::Local::now().to_rfc3339()],
+        )?;
+        Ok(())
+    }
+
+    fn delete(&self, doc: &str) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("DELETE FROM documents WHERE name = ?1", params![doc])?;
+        Ok(())
+    }
+
+    fn list(&self, prefix: &str) -> Result<Vec<String>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT name FROM documents WHERE substr(name, 1, length(?1)) = ?1 ORDER BY name",
+        )?;
+        let names = stmt
+            .query_map(params![prefix], |row| row.get(0))?
+            .collect::<rusqlite::Result<Vec<String>>>()?;
+        Ok(names)
+    }
+}
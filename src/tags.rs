@@ -0,0 +1,125 @@
+use std::collections::BTreeMap;
+
+use crate::Server;
+
+// 没有自定义颜色的标签按名称从这里取色
+const PALETTE: [[u8; 3]; 8] = [
+    [52, 120, 198],
+    [46, 150, 100],
+    [200, 110, 40],
+    [150, 80, 180],
+    [190, 60, 90],
+    [40, 150, 160],
+    [130, 130, 50],
+    [110, 110, 130],
+];
+
+// 拆分逗号或空格分隔的标签，去掉空白和重复，保持输入顺序
+pub fn parse_tags(text: &str) -> Vec<String> {
+    let mut tags: Vec<String> = Vec::new();
+    for tag in text.split([',', '，', ' ']).map(str::trim) {
+        if !tag.is_empty() && !tags.iter().any(|t| t == tag) {
+            tags.push(tag.to_string());
+        }
+    }
+    tags
+}
+
+// 所有服务器用到的标签及使用次数，按名称排序
+pub fn tag_counts(servers: &[Server]) -> Vec<(String, usize)> {
+    let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+    for tag in servers.iter().flat_map(|s| &s.tags) {
+        *counts.entry(tag).or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .map(|(tag, count)| (tag.to_string(), count))
+        .collect()
+}
+
+// 重命名标签，新名称由 parse_tags 得到，可以拆成多个；服务器已有新名称时合并
+pub fn rename(servers: &mut [Server], from: &str, to: &[String]) {
+    for server in servers.iter_mut() {
+        if let Some(mut pos) = server.tags.iter().position(|t| t == from) {
+            server.tags.remove(pos);
+            for tag in to {
+                if !server.tags.contains(tag) {
+                    server.tags.insert(pos, tag.clone());
+                    pos += 1;
+                }
+            }
+        }
+    }
+}
+
+pub fn remove(servers: &mut [Server], tag: &str) {
+    for server in servers.iter_mut() {
+        server.tags.retain(|t| t != tag);
+    }
+}
+
+// 标签颜色：优先使用设置中指定的，否则按名称固定取一种
+pub fn color(tag: &str, custom: &BTreeMap<String, [u8; 3]>) -> [u8; 3] {
+    if let Some(color) = custom.get(tag) {
+        return *color;
+    }
+    // FNV-1a，保证每次启动同一标签颜色相同
+    let hash = tag.bytes().fold(0x811c9dc5u32, |h, b| {
+        (h ^ b as u32).wrapping_mul(0x01000193)
+    });
+    PALETTE[hash as usize % PALETTE.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+
+    fn tagged(tags: &[&str]) -> Server {
+        let mut server = testing::http_server("http://127.0.0.1:8080");
+        server.tags = tags.iter().map(|t| t.to_string()).collect();
+        server
+    }
+
+    #[test]
+    fn parses_separated_tags() {
+        assert_eq!(
+            parse_tags(" prod, gpu  client-x，prod ,"),
+            vec!["prod", "gpu", "client-x"]
+        );
+        assert!(parse_tags("  ").is_empty());
+    }
+
+    #[test]
+    fn counts_rename_and_remove() {
+        let mut servers = vec![tagged(&["prod", "gpu"]), tagged(&["staging", "gpu"])];
+        assert_eq!(
+            tag_counts(&servers),
+            vec![
+                ("gpu".to_string(), 2),
+                ("prod".to_string(), 1),
+                ("staging".to_string(), 1)
+            ]
+        );
+
+        rename(&mut servers, "staging", &parse_tags(" prod "));
+        assert_eq!(servers[1].tags, vec!["prod", "gpu"]);
+        rename(&mut servers, "gpu", &parse_tags("prod"));
+        assert_eq!(servers[0].tags, vec!["prod"]);
+        // 与输入标签时一样按逗号和空格拆分
+        rename(&mut servers, "prod", &parse_tags("eu, web"));
+        assert_eq!(servers[0].tags, vec!["eu", "web"]);
+
+        remove(&mut servers, "eu");
+        remove(&mut servers, "web");
+        assert!(servers.iter().all(|s| s.tags.is_empty()));
+    }
+
+    #[test]
+    fn color_is_stable_unless_customized() {
+        let mut custom = BTreeMap::new();
+        assert_eq!(color("prod", &custom), color("prod", &custom));
+        custom.insert("prod".to_string(), [1, 2, 3]);
+        assert_eq!(color("prod", &custom), [1, 2, 3]);
+    }
+}
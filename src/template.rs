@@ -0,0 +1,38 @@
+use std::borrow::Cow;
+
+// URL和请求内容中的动态占位符，每次检查时重新生成，用于防缓存和防重放的接口
+//   {timestamp}     Unix时间戳（秒）
+//   {timestamp_ms}  Unix时间戳（毫秒）
+//   {date}          当天日期，例如 2024-05-01
+//   {random}        8位随机十六进制字符串
+//   {uuid}          随机UUID
+pub const PLACEHOLDERS: &[&str] = &[
+    "{timestamp}",
+    "{timestamp_ms}",
+    "{date}",
+    "{random}",
+    "{uuid}",
+];
+
+// 没有占位符时原样返回，避免无谓的分配和随机数
+pub fn expand(input: &str) -> Cow<'_, str> {
+    if !input.contains('{') {
+        return Cow::Borrowed(input);
+    }
+
+    let now = chrono::Local::now();
+    let mut output = input
+        .replace("{timestamp_ms}", &now.timestamp_millis().to_string())
+        .replace("{timestamp}", &now.timestamp().to_string())
+        .replace("{date}", &now.format("%Y-%m-%d").to_string());
+    // 每个占位符单独生成，同一字符串里出现多次时值也不同
+    while let Some(pos) = output.find("{random}") {
+        let value = uuid::Uuid::new_v4().simple().to_string();
+        output.replace_range(pos..pos + "{random}".len(), &value[..8]);
+    }
+    while let Some(pos) = output.find("{uuid}") {
+        let value = uuid::Uuid::new_v4().to_string();
+        output.replace_range(pos..pos + "{uuid}".len(), &value);
+    }
+    Cow::Owned(output)
+}
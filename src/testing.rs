@@ -0,0 +1,51 @@
+// 测试用的辅助函数
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::Server;
+
+// 与程序中一样设置了整体超时的HTTP客户端
+pub fn client(timeout: Duration) -> reqwest::Client {
+    reqwest::Client::builder().timeout(timeout).build().unwrap()
+}
+
+// 检查指定URL的HTTP服务器
+pub fn http_server(url: &str) -> Server {
+    let parsed = crate::address::parse_address(url).expect("测试URL无效");
+    let mut server = Server::new("test".to_string(), parsed.host, parsed.port);
+    server.url = url.to_string();
+    server
+}
+
+// 系统临时目录下单独的目录，名称带随机后缀，测试结束（包括断言失败）时删除
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        let dir =
+            std::env::temp_dir().join(format!("servercheck-{}-{}", name, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
@@ -0,0 +1,170 @@
+use eframe::egui;
+use std::sync::{OnceLock, RwLock};
+
+use crate::config::{Settings, StatusColors, ThemeMode};
+use crate::filter::StatusFilter;
+use crate::i18n::tr;
+use crate::theme_file::{Rgb, ThemeFile};
+use crate::ServerStatus;
+
+// 当前使用的状态颜色，ServerStatus::color() 从这里读取
+static STATUS_COLORS: RwLock<StatusColors> = RwLock::new(StatusColors::DEFAULT);
+// 启动时检测到的系统主题
+static SYSTEM_DARK: OnceLock<bool> = OnceLock::new();
+
+pub fn init(cc: &eframe::CreationContext) {
+    let dark = cc.integration_info.system_theme != Some(eframe::Theme::Light);
+    let _ = SYSTEM_DARK.set(dark);
+}
+
+fn color(rgb: Rgb) -> egui::Color32 {
+    let [r, g, b] = rgb.0;
+    egui::Color32::from_rgb(r, g, b)
+}
+
+// 应用主题和状态颜色，设置或主题文件变化后需要重新调用；主题文件中的值优先
+pub fn apply(ctx: &egui::Context, settings: &Settings, file: Option<&ThemeFile>) {
+    let dark = match settings.theme {
+        ThemeMode::System => *SYSTEM_DARK.get().unwrap_or(&true),
+        ThemeMode::Dark => true,
+        ThemeMode::Light => false,
+    };
+    let file = file.cloned().unwrap_or_default();
+    let mut style = egui::Style {
+        visuals: if file.dark.unwrap_or(dark) {
+            egui::Visuals::dark()
+        } else {
+            egui::Visuals::light()
+        },
+        ..Default::default()
+    };
+    let visuals = &mut style.visuals;
+    if let Some(accent) = file.accent.map(color) {
+        visuals.selection.bg_fill = accent;
+        visuals.hyperlink_color = accent;
+    }
+    if let Some(background) = file.background.map(color) {
+        visuals.panel_fill = background;
+    }
+    if let Some(panel) = file.panel.map(color) {
+        visuals.window_fill = panel;
+        visuals.faint_bg_color = panel;
+    }
+    visuals.override_text_color = file.text.map(color);
+    if let Some(rounding) = file.rounding.map(egui::Rounding::same) {
+        visuals.window_rounding = rounding;
+        visuals.menu_rounding = rounding;
+        for widget in [
+            &mut visuals.widgets.noninteractive,
+            &mut visuals.widgets.inactive,
+            &mut visuals.widgets.hovered,
+            &mut visuals.widgets.active,
+            &mut visuals.widgets.open,
+        ] {
+            widget.rounding = rounding;
+        }
+    }
+    if let Some(spacing) = file.spacing {
+        style.spacing.item_spacing = egui::vec2(spacing, spacing / 2.0);
+        style.spacing.button_padding = egui::vec2(spacing / 2.0, spacing / 4.0);
+        style.spacing.window_margin = egui::Margin::same(spacing);
+    }
+    ctx.set_style(style);
+    *STATUS_COLORS.write().unwrap() = file.status_colors(settings.status_colors);
+}
+
+pub fn status_color(status: &ServerStatus) -> egui::Color32 {
+    let colors = STATUS_COLORS.read().unwrap();
+    let [r, g, b] = match status {
+        ServerStatus::Online => colors.online,
+        ServerStatus::Offline => colors.offline,
+        ServerStatus::Error(_) => colors.error,
+        ServerStatus::Degraded => colors.degraded,
+        ServerStatus::Unchecked => colors.unchecked,
+    };
+    egui::Color32::from_rgb(r, g, b)
+}
+
+// 状态栏分类的颜色
+pub fn filter_color(filter: StatusFilter) -> egui::Color32 {
+    match filter {
+        StatusFilter::Online => status_color(&ServerStatus::Online),
+        StatusFilter::Degraded => status_color(&ServerStatus::Degraded),
+        StatusFilter::Error => status_color(&ServerStatus::Error(0)),
+        StatusFilter::Offline | StatusFilter::Timeout => status_color(&ServerStatus::Offline),
+        StatusFilter::Unchecked => status_color(&ServerStatus::Unchecked),
+        StatusFilter::Maintenance => egui::Color32::from_rgb(90, 140, 200),
+        StatusFilter::Paused => egui::Color32::from_rgb(100, 100, 100),
+        StatusFilter::Flapping => egui::Color32::from_rgb(170, 100, 200),
+    }
+}
+
+// 健康分徽章的颜色
+pub fn health_color(score: u8) -> egui::Color32 {
+    match score {
+        80.. => status_color(&ServerStatus::Online),
+        50..=79 => status_color(&ServerStatus::Degraded),
+        _ => status_color(&ServerStatus::Offline),
+    }
+}
+
+// 设置窗口中的主题与颜色选项，返回是否有修改
+pub fn show_settings(ui: &mut egui::Ui, settings: &mut Settings) -> bool {
+    let mut changed = false;
+    ui.horizontal(|ui| {
+        ui.label(tr!("主题:", "Theme:"));
+        changed |= ui
+            .radio_value(
+                &mut settings.theme,
+                ThemeMode::System,
+                tr!("跟随系统", "System"),
+            )
+            .changed();
+        changed |= ui
+            .radio_value(
+                &mut settings.theme,
+                ThemeMode::Light,
+                tr!("☀ 浅色", "☀ Light"),
+            )
+            .changed();
+        changed |= ui
+            .radio_value(
+                &mut settings.theme,
+                ThemeMode::Dark,
+                tr!("🌙 深色", "🌙 Dark"),
+            )
+            .changed();
+    });
+
+    let colors = &mut settings.status_colors;
+    egui::Grid::new("status_colors")
+        .num_columns(2)
+        .show(ui, |ui| {
+            for (label, color) in [
+                (tr!("在线", "Online"), &mut colors.online),
+                (tr!("离线", "Offline"), &mut colors.offline),
+                (tr!("错误", "Error"), &mut colors.error),
+                (tr!("降级", "Degraded"), &mut colors.degraded),
+                (tr!("未检查", "Unchecked"), &mut colors.unchecked),
+            ] {
+                ui.label(label);
+                changed |= ui.color_edit_button_srgb(color).changed();
+                ui.end_row();
+            }
+        });
+
+    ui.horizontal(|ui| {
+        if ui.button(tr!("恢复默认颜色", "Reset colors")).clicked() {
+            settings.status_colors = StatusColors::default();
+            changed = true;
+        }
+        if ui
+            .button(tr!("色弱友好配色", "Color-blind friendly"))
+            .clicked()
+        {
+            settings.status_colors = StatusColors::color_blind();
+            changed = true;
+        }
+    });
+    changed
+}
@@ -0,0 +1,170 @@
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::config::StatusColors;
+
+// 配置目录中的 theme.json，用于不重新编译就定制界面（例如值班室的投屏看板）。
+// 所有字段都可以省略，省略的沿用设置中的主题；颜色写成 "#RRGGBB" 或 [r, g, b]：
+// {
+//   "dark": true,
+//   "accent": "#1f6feb",
+//   "background": "#0d1117",
+//   "panel": "#161b22",
+//   "text": "#e6edf3",
+//   "spacing": 8,
+//   "rounding": 6,
+//   "status": { "online": "#2da44e", "offline": "#cf222e" }
+// }
+
+// 间距和圆角的范围，超出时取边界值
+pub const MAX_SPACING: f32 = 32.0;
+pub const MAX_ROUNDING: f32 = 24.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "RawColor")]
+pub struct Rgb(pub [u8; 3]);
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawColor {
+    Hex(String),
+    Rgb([u8; 3]),
+}
+
+impl TryFrom<RawColor> for Rgb {
+    type Error = String;
+
+    fn try_from(raw: RawColor) -> Result<Self, Self::Error> {
+        match raw {
+            RawColor::Rgb(rgb) => Ok(Rgb(rgb)),
+            RawColor::Hex(text) => parse_hex(&text)
+                .map(Rgb)
+                .ok_or_else(|| format!("无效的颜色: {}", text)),
+        }
+    }
+}
+
+// "#RRGGBB" 或 "#RGB"，# 可以省略
+fn parse_hex(text: &str) -> Option<[u8; 3]> {
+    let hex = text.trim().trim_start_matches('#');
+    if !hex.is_ascii() {
+        return None;
+    }
+    let channel = |s: &str| u8::from_str_radix(s, 16).ok();
+    match hex.len() {
+        6 => Some([
+            channel(&hex[0..2])?,
+            channel(&hex[2..4])?,
+            channel(&hex[4..6])?,
+        ]),
+        3 => {
+            let short = |i: usize| channel(&hex[i..i + 1]).map(|v| v * 17);
+            Some([short(0)?, short(1)?, short(2)?])
+        }
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatusOverrides {
+    pub online: Option<Rgb>,
+    pub offline: Option<Rgb>,
+    pub error: Option<Rgb>,
+    pub degraded: Option<Rgb>,
+    pub unchecked: Option<Rgb>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeFile {
+    // 深色或浅色底，未设置时按设置中的主题
+    pub dark: Option<bool>,
+    // 选中项和链接的颜色
+    pub accent: Option<Rgb>,
+    // 主界面背景
+    pub background: Option<Rgb>,
+    // 窗口和弹出框背景
+    pub panel: Option<Rgb>,
+    pub text: Option<Rgb>,
+    pub spacing: Option<f32>,
+    pub rounding: Option<f32>,
+    // 覆盖设置中的状态颜色
+    pub status: StatusOverrides,
+}
+
+impl ThemeFile {
+    // 文件不存在时返回None，格式错误时返回错误说明
+    pub fn load(path: &Path) -> Result<Option<Self>, String> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.to_string()),
+        };
+        let mut theme: Self = serde_json::from_str(&content).map_err(|e| e.to_string())?;
+        theme.spacing = theme.spacing.map(|v| v.clamp(0.0, MAX_SPACING));
+        theme.rounding = theme.rounding.map(|v| v.clamp(0.0, MAX_ROUNDING));
+        Ok(Some(theme))
+    }
+
+    // 在设置的状态颜色上应用文件中的颜色
+    pub fn status_colors(&self, base: StatusColors) -> StatusColors {
+        let pick = |value: Option<Rgb>, fallback: [u8; 3]| value.map_or(fallback, |c| c.0);
+        StatusColors {
+            online: pick(self.status.online, base.online),
+            offline: pick(self.status.offline, base.offline),
+            error: pick(self.status.error, base.error),
+            degraded: pick(self.status.degraded, base.degraded),
+            unchecked: pick(self.status.unchecked, base.unchecked),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::TempDir;
+
+    // 目录在返回的 TempDir 释放时删除
+    fn temp_file(content: &str) -> (TempDir, std::path::PathBuf) {
+        let dir = TempDir::new("theme");
+        let path = dir.join("theme.json");
+        std::fs::write(&path, content).unwrap();
+        (dir, path)
+    }
+
+    #[test]
+    fn parses_colors_and_overrides_status() {
+        let (_dir, path) = temp_file(
+            r##"{"dark": false, "accent": "#1F6FEB", "panel": [1, 2, 3], "text": "#fff",
+                "spacing": 100, "status": {"online": "00aa00"}}"##,
+        );
+        let theme = ThemeFile::load(&path).unwrap().unwrap();
+        assert_eq!(theme.dark, Some(false));
+        assert_eq!(theme.accent, Some(Rgb([0x1f, 0x6f, 0xeb])));
+        assert_eq!(theme.panel, Some(Rgb([1, 2, 3])));
+        assert_eq!(theme.text, Some(Rgb([255, 255, 255])));
+        assert_eq!(theme.spacing, Some(MAX_SPACING));
+        assert_eq!(theme.rounding, None);
+
+        let colors = theme.status_colors(StatusColors::DEFAULT);
+        assert_eq!(colors.online, [0, 0xaa, 0]);
+        assert_eq!(colors.offline, StatusColors::DEFAULT.offline);
+    }
+
+    #[test]
+    fn rejects_invalid_files() {
+        assert_eq!(
+            ThemeFile::load(Path::new("/nonexistent/theme.json")),
+            Ok(None)
+        );
+        for content in [
+            r##"{"accent": "#12345"}"##,
+            r##"{"acent": "#123456"}"##,
+            "{",
+        ] {
+            let (_dir, path) = temp_file(content);
+            assert!(ThemeFile::load(&path).is_err(), "{}", content);
+        }
+    }
+}
@@ -0,0 +1,84 @@
+use eframe::egui;
+use std::time::{Duration, Instant};
+
+// 右下角的临时提示
+struct Toast {
+    id: u64,
+    text: String,
+    created: Instant,
+    lifetime: Duration,
+    // 操作按钮，例如"撤销删除"
+    action: Option<String>,
+}
+
+#[derive(Default)]
+pub struct Toasts {
+    items: Vec<Toast>,
+    next_id: u64,
+}
+
+impl Toasts {
+    const LIFETIME: Duration = Duration::from_secs(3);
+
+    pub fn push(&mut self, text: impl Into<String>) {
+        self.add(text.into(), Self::LIFETIME, None);
+    }
+
+    // 带操作按钮的提示，返回提示的编号
+    pub fn push_with_action(
+        &mut self,
+        text: impl Into<String>,
+        action: impl Into<String>,
+        lifetime: Duration,
+    ) -> u64 {
+        self.add(text.into(), lifetime, Some(action.into()))
+    }
+
+    fn add(&mut self, text: String, lifetime: Duration, action: Option<String>) -> u64 {
+        self.next_id += 1;
+        self.items.push(Toast {
+            id: self.next_id,
+            text,
+            created: Instant::now(),
+            lifetime,
+            action,
+        });
+        self.next_id
+    }
+
+    pub fn dismiss(&mut self, id: u64) {
+        self.items.retain(|t| t.id != id);
+    }
+
+    // 返回被点击了操作按钮的提示编号
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<u64> {
+        self.items.retain(|t| t.created.elapsed() < t.lifetime);
+        if self.items.is_empty() {
+            return None;
+        }
+
+        let mut clicked = None;
+        egui::Area::new(egui::Id::new("toasts"))
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                for toast in &self.items {
+                    egui::Frame::popup(ui.style()).show(ui, |ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(&toast.text);
+                            if let Some(action) = &toast.action {
+                                if ui.button(action).clicked() {
+                                    clicked = Some(toast.id);
+                                }
+                            }
+                        });
+                    });
+                    ui.add_space(4.0);
+                }
+            });
+        if let Some(id) = clicked {
+            self.dismiss(id);
+        }
+        clicked
+    }
+}
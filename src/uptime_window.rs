@@ -0,0 +1,317 @@
+use chrono::{Duration, Local, NaiveDate, NaiveTime};
+use eframe::egui;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use crate::config::Settings;
+use crate::history::{Exclusion, GroupSla, History};
+use crate::i18n::tr;
+use crate::report::{ReportFormat, ReportPeriod, ReportSettings};
+use crate::{group_label, Server};
+
+// 可用率统计窗口
+#[derive(Default)]
+pub struct UptimeWindow {
+    pub open: bool,
+    // 点击了导出状态页，由调用方导出
+    pub export_requested: bool,
+    export_path: Option<String>,
+    // 点击了立即生成SLA报告
+    pub report_requested: bool,
+    // 正在编辑排除时段的分组
+    group: String,
+    holiday: String,
+    daily_start: String,
+    daily_end: String,
+    error: Option<String>,
+}
+
+fn format_uptime(value: Option<f64>) -> String {
+    value
+        .map(|v| format!("{:.2}%", v))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+impl UptimeWindow {
+    // 返回分组设置是否被修改
+    pub fn show(
+        &mut self,
+        ctx: &egui::Context,
+        servers: &[Server],
+        history: &History,
+        settings: &mut Settings,
+        default_export: &Path,
+        reports_dir: &Path,
+    ) -> bool {
+        let mut open = self.open;
+        let mut changed = false;
+        let groups: BTreeSet<String> = servers.iter().map(|s| s.group.clone()).collect();
+
+        egui::Window::new(tr!("📊 可用率", "📊 Uptime"))
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                let now = Local::now();
+                let periods = [
+                    ("24h", now - Duration::hours(24)),
+                    ("7d", now - Duration::days(7)),
+                    ("30d", now - Duration::days(30)),
+                ];
+
+                egui::Grid::new("uptime_table")
+                    .striped(true)
+                    .num_columns(4)
+                    .show(ui, |ui| {
+                        ui.strong(tr!("服务器", "Server"));
+                        for (label, _) in &periods {
+                            ui.strong(*label);
+                        }
+                        ui.end_row();
+
+                        for group in &groups {
+                            ui.strong(group_label(group));
+                            ui.end_row();
+                            let exclusions = settings.exclusions_for(group);
+                            for server in servers.iter().filter(|s| &s.group == group) {
+                                ui.label(format!("  {}", server.name));
+                                for (_, since) in &periods {
+                                    let uptime =
+                                        history.uptime(&server.history_key(), *since, exclusions);
+                                    ui.label(format_uptime(uptime));
+                                }
+                                ui.end_row();
+                            }
+                        }
+                    });
+
+                ui.separator();
+                ui.strong(tr!("排除时段", "Excluded windows"));
+                ui.small(tr!(
+                    "这些时段内的检查结果不计入可用率，例如SLA只覆盖工作时间",
+                    "Checks in these windows are left out of uptime, e.g. when the SLA covers business hours only"
+                ));
+                changed |= self.show_exclusions(ui, &groups, settings);
+
+                ui.separator();
+                changed |= self.show_export(ui, settings, default_export);
+
+                ui.separator();
+                changed |= self.show_report(ui, &mut settings.report, reports_dir);
+            });
+
+        self.open = open;
+        changed
+    }
+
+    // 定期SLA报告的设置
+    fn show_report(
+        &mut self,
+        ui: &mut egui::Ui,
+        report: &mut ReportSettings,
+        reports_dir: &Path,
+    ) -> bool {
+        let mut changed = false;
+        ui.strong(tr!("SLA报告", "SLA reports"));
+        ui.small(tr!(
+            "按分组统计上一周期的可用率、故障次数、MTTR和最长故障，保存到 {}",
+            "Per-group uptime, incidents, MTTR and longest outage for the last period, saved to {}",
+            reports_dir.display()
+        ));
+        ui.horizontal(|ui| {
+            changed |= ui
+                .checkbox(&mut report.enabled, tr!("定期生成", "Generate"))
+                .changed();
+            egui::ComboBox::from_id_source("report_period")
+                .selected_text(report.period.label())
+                .show_ui(ui, |ui| {
+                    for period in ReportPeriod::ALL {
+                        changed |= ui
+                            .selectable_value(&mut report.period, period, period.label())
+                            .changed();
+                    }
+                });
+            for format in ReportFormat::ALL {
+                changed |= ui
+                    .radio_value(&mut report.format, format, format.label())
+                    .changed();
+            }
+        });
+        ui.horizontal(|ui| {
+            changed |= ui
+                .checkbox(
+                    &mut report.notify,
+                    tr!(
+                        "生成后发送摘要到通知渠道",
+                        "Send a summary to notification channels"
+                    ),
+                )
+                .changed();
+            if ui
+                .button(tr!("📑 立即生成", "📑 Generate now"))
+                .on_hover_text(tr!(
+                    "生成上一个完整周期的报告，已存在时覆盖",
+                    "Generate the last complete period, replacing an existing file"
+                ))
+                .clicked()
+            {
+                self.report_requested = true;
+            }
+        });
+        changed
+    }
+
+    // 导出静态状态页，路径为空时导出到配置目录
+    fn show_export(
+        &mut self,
+        ui: &mut egui::Ui,
+        settings: &mut Settings,
+        default_export: &Path,
+    ) -> bool {
+        let mut changed = false;
+        let path = self.export_path.get_or_insert_with(|| {
+            settings
+                .status_page_path
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or_default()
+        });
+        ui.strong(tr!("状态页", "Status page"));
+        ui.small(tr!(
+            "导出为单个HTML文件，包含当前状态和24小时可用率，可放到内网共享目录",
+            "Exports current statuses and 24h uptime as one HTML file to publish on a share"
+        ));
+        ui.horizontal(|ui| {
+            let response = ui.add(
+                egui::TextEdit::singleline(path)
+                    .hint_text(default_export.display().to_string())
+                    .desired_width(260.0),
+            );
+            if response.lost_focus() {
+                let trimmed = path.trim();
+                let new = (!trimmed.is_empty()).then(|| PathBuf::from(trimmed));
+                if new != settings.status_page_path {
+                    settings.status_page_path = new;
+                    changed = true;
+                }
+            }
+            if ui
+                .button(tr!("📄 导出状态页", "📄 Export status page"))
+                .clicked()
+            {
+                self.export_requested = true;
+            }
+        });
+        changed
+    }
+
+    fn show_exclusions(
+        &mut self,
+        ui: &mut egui::Ui,
+        groups: &BTreeSet<String>,
+        settings: &mut Settings,
+    ) -> bool {
+        let mut changed = false;
+        if !groups.contains(&self.group) {
+            self.group = groups.iter().next().cloned().unwrap_or_default();
+        }
+        egui::ComboBox::from_label(tr!("分组", "Group"))
+            .selected_text(group_label(&self.group))
+            .show_ui(ui, |ui| {
+                for group in groups {
+                    ui.selectable_value(&mut self.group, group.clone(), group_label(group));
+                }
+            });
+
+        let index = match settings
+            .group_sla
+            .iter()
+            .position(|g| g.group == self.group)
+        {
+            Some(index) => index,
+            None => {
+                settings.group_sla.push(GroupSla {
+                    group: self.group.clone(),
+                    exclusions: Vec::new(),
+                });
+                settings.group_sla.len() - 1
+            }
+        };
+        let exclusions = &mut settings.group_sla[index].exclusions;
+
+        let mut remove = None;
+        for (i, exclusion) in exclusions.iter().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(exclusion.label());
+                if ui.small_button("🗑").clicked() {
+                    remove = Some(i);
+                }
+            });
+        }
+        if let Some(i) = remove {
+            exclusions.remove(i);
+            changed = true;
+        }
+
+        let mut added = None;
+        ui.horizontal(|ui| {
+            if !exclusions.contains(&Exclusion::Weekends)
+                && ui.button(tr!("➕ 周末", "➕ Weekends")).clicked()
+            {
+                added = Some(Exclusion::Weekends);
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.holiday)
+                    .hint_text("2024-10-01")
+                    .desired_width(90.0),
+            );
+            if ui.button(tr!("➕ 节假日", "➕ Holiday")).clicked() {
+                match NaiveDate::parse_from_str(self.holiday.trim(), "%Y-%m-%d") {
+                    Ok(date) => added = Some(Exclusion::Holiday(date)),
+                    Err(_) => {
+                        self.error = Some(tr!("日期格式应为 YYYY-MM-DD", "Use YYYY-MM-DD").into())
+                    }
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut self.daily_start)
+                    .hint_text("02:00")
+                    .desired_width(45.0),
+            );
+            ui.label("-");
+            ui.add(
+                egui::TextEdit::singleline(&mut self.daily_end)
+                    .hint_text("04:00")
+                    .desired_width(45.0),
+            );
+            if ui.button(tr!("➕ 每天", "➕ Daily")).clicked() {
+                let parse = |s: &str| NaiveTime::parse_from_str(s.trim(), "%H:%M");
+                match (parse(&self.daily_start), parse(&self.daily_end)) {
+                    (Ok(start), Ok(end)) if start != end => {
+                        added = Some(Exclusion::Daily { start, end })
+                    }
+                    _ => self.error = Some(tr!("时间格式应为 HH:MM", "Use HH:MM").into()),
+                }
+            }
+        });
+
+        if let Some(exclusion) = added {
+            if !exclusions.contains(&exclusion) {
+                exclusions.push(exclusion);
+                changed = true;
+            }
+            self.holiday.clear();
+            self.error = None;
+        }
+        if let Some(error) = &self.error {
+            ui.small(error);
+        }
+
+        // 没有排除时段的分组不需要保存
+        settings.group_sla.retain(|g| !g.exclusions.is_empty());
+        changed
+    }
+}
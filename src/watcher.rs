@@ -0,0 +1,66 @@
+use notify::{Event, RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+
+// 配置文件监视器。
+// 监视配置文件所在的目录而不是文件本身，因为很多编辑器和同步工具保存时会先写临时文件再重命名。
+pub struct ConfigWatcher {
+    _watcher: RecommendedWatcher,
+    rx: Receiver<notify::Result<Event>>,
+    file_name: PathBuf,
+    // 最近一次收到变化事件的时间，用于防抖
+    pending_since: Option<Instant>,
+}
+
+impl ConfigWatcher {
+    // 变化事件停止多久后才真正重新加载
+    const DEBOUNCE: Duration = Duration::from_millis(300);
+
+    pub fn new(path: &Path) -> notify::Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(move |event| {
+            let _ = tx.send(event);
+        })?;
+
+        let dir = path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."));
+        watcher.watch(&dir, RecursiveMode::NonRecursive)?;
+
+        Ok(Self {
+            _watcher: watcher,
+            rx,
+            file_name: path.file_name().map(PathBuf::from).unwrap_or_default(),
+            pending_since: None,
+        })
+    }
+
+    // 每帧调用，文件变化且已稳定时返回true
+    pub fn poll(&mut self) -> bool {
+        while let Ok(event) = self.rx.try_recv() {
+            let Ok(event) = event else {
+                continue;
+            };
+            if event.kind.is_access() {
+                continue;
+            }
+            let touches_config = event
+                .paths
+                .iter()
+                .any(|p| p.file_name() == Some(self.file_name.as_os_str()));
+            if touches_config {
+                self.pending_since = Some(Instant::now());
+            }
+        }
+
+        match self.pending_since {
+            Some(since) if since.elapsed() >= Self::DEBOUNCE => {
+                self.pending_since = None;
+                true
+            }
+            _ => false,
+        }
+    }
+}
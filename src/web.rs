@@ -0,0 +1,335 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::config::StatusColors;
+use crate::filter::{self, StatusFilter};
+use crate::i18n::tr;
+use crate::{group_label, Server};
+
+// 无界面模式的网页看板和JSON接口，只读：
+//   GET /             状态页面，定时自动刷新
+//   GET /api/servers  所有服务器的当前状态
+//   GET /healthz      程序本身在运行，供容器的健康检查使用
+// 修改服务器列表请编辑配置文件或使用命令行子命令
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+// 请求头的长度上限，请求体不读取
+const MAX_HEAD_BYTES: u64 = 16 * 1024;
+const REFRESH_SECS: u64 = 15;
+
+// 接口返回的一台服务器
+#[derive(Debug, Clone, Serialize)]
+pub struct ServerView {
+    pub id: Uuid,
+    pub name: String,
+    pub group: String,
+    pub tags: Vec<String>,
+    pub target: String,
+    // 检查结果：online/offline/error/degraded/unchecked
+    pub status: &'static str,
+    pub code: Option<u16>,
+    // 与界面状态栏相同的分类，暂停、维护和抖动优先
+    pub category: &'static str,
+    pub detail: String,
+    pub latency_ms: Option<u64>,
+    pub health: Option<u8>,
+    pub last_checked: Option<DateTime<Local>>,
+    pub last_change: Option<DateTime<Local>>,
+    // 故障中时为这次故障第一次检查失败的时间
+    pub down_since: Option<DateTime<Local>>,
+    #[serde(skip)]
+    filter: StatusFilter,
+}
+
+impl ServerView {
+    pub fn new(server: &Server) -> Self {
+        let filter = StatusFilter::of(server);
+        Self {
+            id: server.id,
+            name: server.name.clone(),
+            group: server.group.clone(),
+            tags: server.tags.clone(),
+            target: server.target_label(),
+            status: server.status.id(),
+            code: server.status.code(),
+            category: filter.id(),
+            detail: server.state.detail.clone(),
+            latency_ms: server.state.latency_ms,
+            health: server.state.health.as_ref().map(|h| h.score),
+            last_checked: server.state.last_checked,
+            last_change: server.state.last_change,
+            down_since: server.state.down_since,
+            filter,
+        }
+    }
+}
+
+// 网页和接口读取的状态，由检查循环更新
+#[derive(Debug, Clone, Default)]
+pub struct Snapshot {
+    pub servers: Vec<ServerView>,
+    pub counts: Vec<(StatusFilter, usize)>,
+    pub colors: StatusColors,
+}
+
+impl Snapshot {
+    pub fn new(servers: &[Server], colors: StatusColors) -> Self {
+        Self {
+            servers: servers.iter().map(ServerView::new).collect(),
+            counts: filter::status_counts(servers),
+            colors,
+        }
+    }
+}
+
+pub type SharedSnapshot = Arc<Mutex<Snapshot>>;
+
+pub struct Response {
+    pub status: &'static str,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Response {
+    fn new(status: &'static str, content_type: &'static str, body: String) -> Self {
+        Self {
+            status,
+            content_type,
+            body,
+        }
+    }
+
+    fn write_to(&self, mut stream: &TcpStream) -> io::Result<()> {
+        write!(
+            stream,
+            "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nCache-Control: no-store\r\nConnection: close\r\n\r\n{}",
+            self.status,
+            self.content_type,
+            self.body.len(),
+            self.body
+        )
+    }
+}
+
+// 在 addr 上监听，每个连接一个线程；返回实际监听的地址
+pub fn start(addr: &str, snapshot: SharedSnapshot) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(addr)?;
+    let local = listener.local_addr()?;
+    std::thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let snapshot = Arc::clone(&snapshot);
+            std::thread::spawn(move || {
+                if let Err(e) = handle(&stream, &snapshot) {
+                    eprintln!("处理网页请求失败: {}", e);
+                }
+            });
+        }
+    });
+    Ok(local)
+}
+
+fn handle(stream: &TcpStream, snapshot: &SharedSnapshot) -> io::Result<()> {
+    stream.set_read_timeout(Some(TIMEOUT))?;
+    let mut reader = BufReader::new(stream.take(MAX_HEAD_BYTES));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // 读完请求头再回复，否则有的客户端会报连接被重置
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && !header.trim().is_empty() {
+        header.clear();
+    }
+    let response = respond(&request_line, &snapshot.lock().unwrap());
+    response.write_to(stream)
+}
+
+pub fn respond(request_line: &str, snapshot: &Snapshot) -> Response {
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default();
+    let path = parts
+        .next()
+        .unwrap_or_default()
+        .split('?')
+        .next()
+        .unwrap_or_default();
+    if method != "GET" {
+        return Response::new(
+            "405 Method Not Allowed",
+            "text/plain; charset=utf-8",
+            "method not allowed".to_string(),
+        );
+    }
+    match path {
+        "/" => Response::new("200 OK", "text/html; charset=utf-8", render_html(snapshot)),
+        "/api/servers" => Response::new(
+            "200 OK",
+            "application/json",
+            serde_json::to_string(&snapshot.servers).unwrap_or_default(),
+        ),
+        "/healthz" => Response::new("200 OK", "text/plain; charset=utf-8", "ok".to_string()),
+        _ => Response::new(
+            "404 Not Found",
+            "text/plain; charset=utf-8",
+            "not found".to_string(),
+        ),
+    }
+}
+
+pub fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+// 与界面状态栏相同的配色，检查结果的颜色可在设置中自定义
+pub fn category_color(filter: StatusFilter, colors: &StatusColors) -> String {
+    let [r, g, b] = match filter {
+        StatusFilter::Online => colors.online,
+        StatusFilter::Degraded => colors.degraded,
+        StatusFilter::Error => colors.error,
+        StatusFilter::Offline | StatusFilter::Timeout => colors.offline,
+        StatusFilter::Unchecked => colors.unchecked,
+        StatusFilter::Maintenance => [90, 140, 200],
+        StatusFilter::Paused => [100, 100, 100],
+        StatusFilter::Flapping => [170, 100, 200],
+    };
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+fn render_html(snapshot: &Snapshot) -> String {
+    let title = tr!("服务器状态", "Server status");
+    let mut html = format!(
+        "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><meta http-equiv=\"refresh\" content=\"{}\">\
+         <meta name=\"viewport\" content=\"width=device-width\"><title>{}</title><style>\
+         body{{font-family:sans-serif;margin:1.5em;background:#fafafa}}\
+         table{{border-collapse:collapse;width:100%;background:#fff}}\
+         th,td{{padding:.4em .6em;border-bottom:1px solid #ddd;text-align:left;font-size:14px}}\
+         .dot{{display:inline-block;width:.8em;height:.8em;border-radius:50%;margin-right:.4em}}\
+         .count{{display:inline-block;margin:0 1em .8em 0}}.muted{{color:#888}}\
+         </style></head><body><h2>{}</h2><div>",
+        REFRESH_SECS, title, title
+    );
+    for (filter, count) in snapshot.counts.iter().filter(|(_, count)| *count > 0) {
+        html.push_str(&format!(
+            "<span class=\"count\"><span class=\"dot\" style=\"background:{}\"></span>{} {}</span>",
+            category_color(*filter, &snapshot.colors),
+            filter.label(),
+            count
+        ));
+    }
+    html.push_str(&format!(
+        "</div><table><tr><th>{}</th><th>{}</th><th>{}</th><th>{}</th><th>{}</th><th>{}</th><th>{}</th></tr>",
+        tr!("名称", "Name"),
+        tr!("分组", "Group"),
+        tr!("地址", "Address"),
+        tr!("状态", "Status"),
+        tr!("延迟", "Latency"),
+        tr!("健康分", "Health"),
+        tr!("上次检查", "Last check"),
+    ));
+    for server in &snapshot.servers {
+        let tags = if server.tags.is_empty() {
+            String::new()
+        } else {
+            format!(
+                " <span class=\"muted\">{}</span>",
+                escape(&server.tags.join(", "))
+            )
+        };
+        html.push_str(&format!(
+            "<tr><td>{}{}</td><td>{}</td><td>{}</td>\
+             <td title=\"{}\"><span class=\"dot\" style=\"background:{}\"></span>{}</td>\
+             <td>{}</td><td>{}</td><td class=\"muted\">{}</td></tr>",
+            escape(&server.name),
+            tags,
+            escape(group_label(&server.group)),
+            escape(&server.target),
+            escape(&server.detail),
+            category_color(server.filter, &snapshot.colors),
+            server.filter.label(),
+            server
+                .latency_ms
+                .map(|ms| format!("{} ms", ms))
+                .unwrap_or_default(),
+            server.health.map(|h| h.to_string()).unwrap_or_default(),
+            server
+                .last_checked
+                .map(|t| t.format("%H:%M:%S").to_string())
+                .unwrap_or_default(),
+        ));
+    }
+    html.push_str("</table></body></html>");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing;
+    use crate::ServerStatus;
+
+    fn snapshot() -> Snapshot {
+        let mut server = testing::http_server("http://10.0.0.1");
+        server.name = "<web>".to_string();
+        server.status = ServerStatus::Error(503);
+        server.state.latency_ms = Some(12);
+        Snapshot::new(&[server], StatusColors::default())
+    }
+
+    #[test]
+    fn serves_dashboard_and_api() {
+        let snapshot = snapshot();
+        let page = respond("GET / HTTP/1.1", &snapshot);
+        assert_eq!(page.status, "200 OK");
+        assert!(page.body.contains("&lt;web&gt;"));
+        assert!(!page.body.contains("<web>"));
+
+        let api = respond("GET /api/servers?x=1 HTTP/1.1", &snapshot);
+        let json: serde_json::Value = serde_json::from_str(&api.body).unwrap();
+        assert_eq!(json[0]["status"], "error");
+        assert_eq!(json[0]["code"], 503);
+        assert_eq!(json[0]["category"], "error");
+        assert_eq!(json[0]["latency_ms"], 12);
+
+        assert_eq!(respond("GET /healthz HTTP/1.1", &snapshot).body, "ok");
+        assert_eq!(
+            respond("GET /nope HTTP/1.1", &snapshot).status,
+            "404 Not Found"
+        );
+        assert_eq!(
+            respond("POST /api/servers HTTP/1.1", &snapshot).status,
+            "405 Method Not Allowed"
+        );
+    }
+
+    #[test]
+    fn answers_over_tcp() {
+        let shared = SharedSnapshot::new(Mutex::new(snapshot()));
+        let addr = start("127.0.0.1:0", shared).unwrap();
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream
+            .write_all(b"GET /healthz HTTP/1.1\r\nHost: x\r\n\r\n")
+            .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\nok"));
+    }
+}
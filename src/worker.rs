@@ -0,0 +1,490 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{mpsc, Arc, Mutex};
+
+use tokio::sync::mpsc as job_channel;
+use tokio::sync::Semaphore;
+use uuid::Uuid;
+
+use crate::checks::{self, mapping, CheckKind, CheckOutcome};
+use crate::clock::Clock;
+use crate::columns::ColumnDef;
+use crate::{netstate, template, Server, ServerStatus};
+
+// 后台检查任务。界面把要检查的服务器发过来，检查结果逐条通过通道送回界面，
+// 由界面线程合并到服务器列表，检查期间不需要锁住列表
+
+pub const MAX_TOTAL_CHECKS: usize = 256;
+pub const MAX_PER_HOST_CHECKS: usize = 32;
+
+// 同时进行的检查数，所有批次共用
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ConcurrencySettings {
+    // 全部服务器合计
+    pub max_total: usize,
+    // 同一目标主机，同一台机器上的多个服务同时被探测时可能触发对方的限流
+    pub max_per_host: usize,
+}
+
+impl Default for ConcurrencySettings {
+    fn default() -> Self {
+        Self {
+            max_total: 32,
+            max_per_host: 2,
+        }
+    }
+}
+
+impl ConcurrencySettings {
+    pub fn max_total(&self) -> usize {
+        self.max_total.clamp(1, MAX_TOTAL_CHECKS)
+    }
+
+    pub fn max_per_host(&self) -> usize {
+        self.max_per_host.clamp(1, MAX_PER_HOST_CHECKS)
+    }
+}
+
+// 一台服务器的检查结果，按ID找回列表中的服务器；检查期间列表被修改也不会对错
+#[derive(Debug)]
+pub struct CheckResult {
+    pub id: Uuid,
+    pub outcome: CheckOutcome,
+}
+
+#[derive(Debug)]
+pub enum WorkerEvent {
+    Result(Box<CheckResult>),
+    // 全部离线且本机断网，这一批结果被丢弃
+    NetworkDown,
+    // 这一批的结果已全部送出
+    Done,
+}
+
+// 通道中的消息：每台检查完成时先报告进度，结果在整批完成后送出
+enum Message {
+    Checked(Uuid),
+    Event(WorkerEvent),
+}
+
+// 提交后还没有完成的一批
+struct Batch {
+    total: usize,
+    // 还没有检查完的服务器
+    pending: BTreeSet<Uuid>,
+}
+
+struct CheckJob {
+    id: u64,
+    servers: Vec<Server>,
+    columns: Arc<Vec<ColumnDef>>,
+    security: bool,
+    limits: ConcurrencySettings,
+}
+
+// 固定许可数的信号量，设置修改后换成新的
+struct Gate {
+    permits: usize,
+    semaphore: Arc<Semaphore>,
+}
+
+impl Gate {
+    fn new(permits: usize) -> Self {
+        Self {
+            permits,
+            semaphore: Arc::new(Semaphore::new(permits)),
+        }
+    }
+
+    fn get(&mut self, permits: usize) -> Arc<Semaphore> {
+        if self.permits != permits {
+            *self = Gate::new(permits);
+        }
+        Arc::clone(&self.semaphore)
+    }
+}
+
+// 全局和按主机的并发限制，手动检查和自动检查的批次同时进行时也一起计算
+struct Limiter {
+    total: Mutex<Gate>,
+    hosts: Mutex<HashMap<String, Gate>>,
+}
+
+impl Limiter {
+    fn new() -> Self {
+        Self {
+            total: Mutex::new(Gate::new(ConcurrencySettings::default().max_total())),
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    fn semaphores(
+        &self,
+        host: &str,
+        limits: &ConcurrencySettings,
+    ) -> (Arc<Semaphore>, Arc<Semaphore>) {
+        let per_host = limits.max_per_host();
+        let host = self
+            .hosts
+            .lock()
+            .unwrap()
+            .entry(host.to_string())
+            .or_insert_with(|| Gate::new(per_host))
+            .get(per_host);
+        (host, self.total.lock().unwrap().get(limits.max_total()))
+    }
+}
+
+// 按目标主机限制并发，HTTP检查取URL中的主机名，其他检查方式使用IP
+fn destination(server: &Server) -> String {
+    let host = match server.check {
+        CheckKind::Http => url::Url::parse(&template::expand(&server.url))
+            .ok()
+            .and_then(|url| url.host_str().map(str::to_string)),
+        _ => None,
+    };
+    host.unwrap_or_else(|| server.ip.clone())
+        .to_ascii_lowercase()
+}
+
+pub struct CheckWorker {
+    jobs: job_channel::UnboundedSender<CheckJob>,
+    events: mpsc::Receiver<(u64, Message)>,
+    next_id: u64,
+    // 提交后还没有完成的批次
+    running: BTreeMap<u64, Batch>,
+    // 编号小于它的批次已取消，结果直接丢弃
+    cancelled_before: u64,
+}
+
+impl CheckWorker {
+    // 需要在tokio运行时中调用
+    pub fn spawn(client: reqwest::Client, clock: Arc<dyn Clock>) -> Self {
+        let (jobs, job_rx) = job_channel::unbounded_channel();
+        let (event_tx, events) = mpsc::channel();
+        tokio::spawn(run(client, clock, job_rx, event_tx));
+        Self {
+            jobs,
+            events,
+            next_id: 0,
+            running: BTreeMap::new(),
+            cancelled_before: 0,
+        }
+    }
+
+    // 提交一批检查，已暂停的服务器由调用方过滤
+    pub fn submit(
+        &mut self,
+        servers: Vec<Server>,
+        columns: Arc<Vec<ColumnDef>>,
+        security: bool,
+        limits: ConcurrencySettings,
+    ) {
+        let id = self.next_id;
+        self.next_id += 1;
+        let batch = Batch {
+            total: servers.len(),
+            pending: servers.iter().map(|s| s.id).collect(),
+        };
+        let job = CheckJob {
+            id,
+            servers,
+            columns,
+            security,
+            limits,
+        };
+        if self.jobs.send(job).is_ok() {
+            self.running.insert(id, batch);
+        } else {
+            eprintln!("检查任务已退出");
+        }
+    }
+
+    // 丢弃已提交批次的结果，例如切换配置方案后
+    pub fn cancel(&mut self) {
+        self.cancelled_before = self.next_id;
+        self.running.clear();
+    }
+
+    pub fn is_checking(&self) -> bool {
+        !self.running.is_empty()
+    }
+
+    // 这台服务器在进行中的批次里，还没有检查完
+    pub fn is_pending(&self, id: &Uuid) -> bool {
+        self.running.values().any(|b| b.pending.contains(id))
+    }
+
+    // 进行中的批次合计已完成和总共的台数，没有进行中的检查时为 None
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        if self.running.is_empty() {
+            return None;
+        }
+        let total: usize = self.running.values().map(|b| b.total).sum();
+        let pending: usize = self.running.values().map(|b| b.pending.len()).sum();
+        Some((total - pending, total))
+    }
+
+    // 取出已经送达的结果，不等待
+    pub fn poll(&mut self) -> Vec<WorkerEvent> {
+        let mut events = Vec::new();
+        while let Ok((id, message)) = self.events.try_recv() {
+            if id < self.cancelled_before {
+                continue;
+            }
+            let event = match message {
+                Message::Checked(server) => {
+                    if let Some(batch) = self.running.get_mut(&id) {
+                        batch.pending.remove(&server);
+                    }
+                    continue;
+                }
+                Message::Event(event) => event,
+            };
+            if matches!(event, WorkerEvent::Done) {
+                self.running.remove(&id);
+            }
+            events.push(event);
+        }
+        events
+    }
+}
+
+async fn run(
+    client: reqwest::Client,
+    clock: Arc<dyn Clock>,
+    mut jobs: job_channel::UnboundedReceiver<CheckJob>,
+    events: mpsc::Sender<(u64, Message)>,
+) {
+    let limiter = Arc::new(Limiter::new());
+    while let Some(job) = jobs.recv().await {
+        // 每批单独执行，手动检查几台服务器时不必等待正在进行的全部检查
+        tokio::spawn(run_job(
+            client.clone(),
+            Arc::clone(&clock),
+            Arc::clone(&limiter),
+            job,
+            events.clone(),
+        ));
+    }
+}
+
+async fn run_job(
+    client: reqwest::Client,
+    clock: Arc<dyn Clock>,
+    limiter: Arc<Limiter>,
+    job: CheckJob,
+    events: mpsc::Sender<(u64, Message)>,
+) {
+    let futures = job.servers.into_iter().map(|server| {
+        let client = &client;
+        let clock = &clock;
+        let columns = &job.columns;
+        let events = &events;
+        let (host, total) = limiter.semaphores(&destination(&server), &job.limits);
+        async move {
+            // 先等同一主机的空位，排队时不占用全局的名额；信号量不会关闭
+            let _host = host.acquire_owned().await;
+            let _total = total.acquire_owned().await;
+            let mut outcome = checks::run_check(client, &server, columns, job.security).await;
+            mapping::apply(&server.status_rules, &mut outcome, clock.now());
+            let _ = events.send((job.id, Message::Checked(server.id)));
+            CheckResult {
+                id: server.id,
+                outcome,
+            }
+        }
+    });
+    // 在并发限制内同时执行
+    let results = futures::future::join_all(futures).await;
+
+    // 全部离线且本机没有路由或网关不可达时，判断为本机断网；
+    // 只要有一台可达就说明本机网络正常（例如没有默认网关的内网环境）
+    let all_offline = !results.is_empty()
+        && results
+            .iter()
+            .all(|r| r.outcome.status == ServerStatus::Offline);
+    if all_offline && !netstate::local_network_up().await {
+        let _ = events.send((job.id, Message::Event(WorkerEvent::NetworkDown)));
+    } else {
+        for result in results {
+            let event = WorkerEvent::Result(Box::new(result));
+            // 界面已关闭
+            if events.send((job.id, Message::Event(event))).is_err() {
+                return;
+            }
+        }
+    }
+    let _ = events.send((job.id, Message::Event(WorkerEvent::Done)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::testing;
+    use std::time::Duration;
+    use wiremock::matchers::path;
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    // 等到所有批次完成，返回收到的事件
+    async fn wait_done(worker: &mut CheckWorker) -> Vec<WorkerEvent> {
+        let mut events = Vec::new();
+        for _ in 0..100 {
+            events.extend(worker.poll());
+            if !worker.is_checking() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        events
+    }
+
+    fn worker() -> CheckWorker {
+        CheckWorker::spawn(
+            testing::client(Duration::from_secs(5)),
+            Arc::new(ManualClock::at(chrono::Local::now())),
+        )
+    }
+
+    #[tokio::test]
+    async fn sends_one_result_per_server_then_done() {
+        let mock = MockServer::start().await;
+        Mock::given(path("/ok"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock)
+            .await;
+        Mock::given(path("/fail"))
+            .respond_with(ResponseTemplate::new(503))
+            .mount(&mock)
+            .await;
+
+        let mut worker = worker();
+        let ok = testing::http_server(&format!("{}/ok", mock.uri()));
+        let fail = testing::http_server(&format!("{}/fail", mock.uri()));
+        let expected = vec![
+            (ok.id, ServerStatus::Online),
+            (fail.id, ServerStatus::Error(503)),
+        ];
+        worker.submit(
+            vec![ok, fail],
+            Arc::new(Vec::new()),
+            false,
+            ConcurrencySettings::default(),
+        );
+        assert!(worker.is_checking());
+
+        let events = wait_done(&mut worker).await;
+        assert!(!worker.is_checking());
+        let mut statuses: Vec<_> = events
+            .iter()
+            .filter_map(|e| match e {
+                WorkerEvent::Result(r) => Some((r.id, r.outcome.status.clone())),
+                _ => None,
+            })
+            .collect();
+        // 同名服务器也按ID区分
+        statuses.sort_by_key(|(id, _)| expected.iter().position(|(e, _)| e == id));
+        assert_eq!(statuses, expected);
+        assert!(matches!(events.last(), Some(WorkerEvent::Done)));
+    }
+
+    #[tokio::test]
+    async fn reports_progress_as_servers_finish() {
+        let mock = MockServer::start().await;
+        Mock::given(path("/fast"))
+            .respond_with(ResponseTemplate::new(200))
+            .mount(&mock)
+            .await;
+        Mock::given(path("/slow"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(500)))
+            .mount(&mock)
+            .await;
+
+        let mut worker = worker();
+        let fast = testing::http_server(&format!("{}/fast", mock.uri()));
+        let slow = testing::http_server(&format!("{}/slow", mock.uri()));
+        let (fast_id, slow_id) = (fast.id, slow.id);
+        worker.submit(
+            vec![fast, slow],
+            Arc::new(Vec::new()),
+            false,
+            ConcurrencySettings::default(),
+        );
+        assert_eq!(worker.progress(), Some((0, 2)));
+        assert!(worker.is_pending(&fast_id));
+
+        for _ in 0..40 {
+            // 结果在整批完成后才送出
+            assert!(worker.poll().is_empty());
+            if !worker.is_pending(&fast_id) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(worker.progress(), Some((1, 2)));
+        assert!(worker.is_pending(&slow_id));
+
+        wait_done(&mut worker).await;
+        assert_eq!(worker.progress(), None);
+        assert!(!worker.is_pending(&slow_id));
+    }
+
+    #[tokio::test]
+    async fn limits_concurrent_checks_per_host() {
+        let mock = MockServer::start().await;
+        Mock::given(path("/"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(150)))
+            .mount(&mock)
+            .await;
+        let servers = |url: &str| {
+            (0..3)
+                .map(|_| testing::http_server(url))
+                .collect::<Vec<_>>()
+        };
+        let limits = ConcurrencySettings {
+            max_total: 32,
+            max_per_host: 1,
+        };
+
+        // 同一主机的三台依次检查
+        let mut worker = worker();
+        let started = std::time::Instant::now();
+        worker.submit(servers(&mock.uri()), Arc::new(Vec::new()), false, limits);
+        wait_done(&mut worker).await;
+        assert!(started.elapsed() >= Duration::from_millis(450));
+
+        // 按URL中的主机名区分，localhost 与 127.0.0.1 分开计算
+        let port = mock.address().port();
+        let mut mixed = servers(&mock.uri());
+        mixed[1] = testing::http_server(&format!("http://localhost:{}/", port));
+        assert_ne!(destination(&mixed[0]), destination(&mixed[1]));
+        assert_eq!(destination(&mixed[0]), destination(&mixed[2]));
+        // 占位符展开后再取主机名
+        let templated = testing::http_server("http://{date}.example.com/");
+        assert!(destination(&templated).ends_with(".example.com"));
+    }
+
+    #[tokio::test]
+    async fn cancelled_results_are_dropped() {
+        let mock = MockServer::start().await;
+        Mock::given(path("/"))
+            .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_millis(200)))
+            .mount(&mock)
+            .await;
+
+        let mut worker = worker();
+        worker.submit(
+            vec![testing::http_server(&mock.uri())],
+            Arc::new(Vec::new()),
+            false,
+            ConcurrencySettings::default(),
+        );
+        worker.cancel();
+        assert!(!worker.is_checking());
+
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        assert!(worker.poll().is_empty());
+    }
+}
@@ -0,0 +1,273 @@
+use anyhow::{bail, Result};
+
+use crate::i18n::tr;
+
+// 按缩进解析的YAML子集，Ansible 清单和 kubeconfig 共用：支持嵌套映射、块列表和标量，
+// 不支持多行字符串、锚点和 {a: 1} 这样的行内写法
+
+#[derive(Debug, Default)]
+pub struct Node {
+    // 所在行号，从1开始，用于报错
+    pub line: usize,
+    pub value: Option<String>,
+    pub children: Vec<(String, Node)>,
+    pub items: Vec<Node>,
+}
+
+impl Node {
+    pub fn get(&self, key: &str) -> Option<&Node> {
+        self.children.iter().find(|(k, _)| k == key).map(|(_, n)| n)
+    }
+
+    // 按路径取标量，例如 node.str(&["user", "token"])
+    pub fn str(&self, path: &[&str]) -> Option<&str> {
+        path.iter()
+            .try_fold(self, |node, key| node.get(key))
+            .and_then(|node| node.value.as_deref())
+    }
+}
+
+// 只去掉成对的首尾引号，echo 'x' 这样的值保持原样
+fn unquote(text: &str) -> String {
+    let text = text.trim();
+    for quote in ['"', '\''] {
+        if let Some(inner) = text.strip_prefix(quote).and_then(|t| t.strip_suffix(quote)) {
+            return inner.to_string();
+        }
+    }
+    text.to_string()
+}
+
+// 去掉注释：# 在行首或空白之后、且不在引号中时才是注释
+fn strip_comment(line: &str) -> &str {
+    let mut quote: Option<char> = None;
+    let mut previous = ' ';
+    for (i, c) in line.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' && previous.is_whitespace() => return &line[..i],
+            None => {}
+        }
+        previous = c;
+    }
+    line
+}
+
+// 键值行的冒号后面要有空格或行尾，这样 https://host:6443 这样的标量不会当成键
+fn split_key(text: &str) -> Option<(&str, &str)> {
+    if let Some(key) = text.strip_suffix(':') {
+        return Some((key, ""));
+    }
+    text.split_once(": ")
+}
+
+fn scalar(text: &str) -> Option<String> {
+    let value = unquote(text);
+    match value.as_str() {
+        "" | "~" | "null" | "{}" | "[]" => None,
+        _ => Some(value),
+    }
+}
+
+struct Line<'a> {
+    number: usize,
+    indent: usize,
+    text: &'a str,
+}
+
+struct Parser<'a> {
+    lines: Vec<Line<'a>>,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Line<'a>> {
+        self.lines.get(self.pos)
+    }
+
+    fn is_item(text: &str) -> bool {
+        text == "-" || text.starts_with("- ")
+    }
+
+    // 解析从当前行开始、缩进为 indent 的一个块
+    fn block(&mut self, indent: usize) -> Result<Node> {
+        match self.peek() {
+            Some(line) if Self::is_item(line.text) => self.list(indent),
+            _ => self.map(indent),
+        }
+    }
+
+    // 键后面没有值时，下一行缩进更深的块是它的值；列表也可以和键对齐
+    fn nested(&mut self, indent: usize, line: usize) -> Result<Node> {
+        let node = match self.peek() {
+            Some(next) if next.indent > indent => {
+                let indent = next.indent;
+                self.block(indent)?
+            }
+            Some(next) if next.indent == indent && Self::is_item(next.text) => self.list(indent)?,
+            _ => Node::default(),
+        };
+        Ok(Node { line, ..node })
+    }
+
+    fn map(&mut self, indent: usize) -> Result<Node> {
+        let mut node = Node {
+            line: self.peek().map_or(0, |l| l.number),
+            ..Default::default()
+        };
+        while let Some(&Line {
+            number,
+            indent: line_indent,
+            text,
+        }) = self.peek()
+        {
+            if line_indent < indent || (line_indent == indent && Self::is_item(text)) {
+                break;
+            }
+            if line_indent > indent {
+                bail!(tr!(
+                    "第{}行: 缩进不正确",
+                    "Line {}: bad indentation",
+                    number
+                ));
+            }
+            let Some((key, value)) = split_key(text) else {
+                bail!(tr!(
+                    "第{}行: 应为 键: 值",
+                    "Line {}: expected key: value",
+                    number
+                ));
+            };
+            let (key, value) = (unquote(key), value.trim());
+            self.pos += 1;
+            let child = if value.is_empty() {
+                self.nested(indent, number)?
+            } else {
+                Node {
+                    line: number,
+                    value: scalar(value),
+                    ..Default::default()
+                }
+            };
+            node.children.push((key, child));
+        }
+        Ok(node)
+    }
+
+    fn list(&mut self, indent: usize) -> Result<Node> {
+        let mut node = Node {
+            line: self.peek().map_or(0, |l| l.number),
+            ..Default::default()
+        };
+        while let Some(&Line {
+            number,
+            indent: line_indent,
+            text,
+        }) = self.peek()
+        {
+            if line_indent != indent || !Self::is_item(text) {
+                break;
+            }
+            let rest = text[1..].trim_start();
+            if rest.is_empty() {
+                self.pos += 1;
+                let item = self.nested(indent, number)?;
+                node.items.push(item);
+                continue;
+            }
+            if split_key(rest).is_some() && !rest.starts_with(['"', '\'']) {
+                // "- 键: 值" 是一个映射，把这一行看作缩进到 "- " 之后的第一个键
+                let item_indent = indent + text.len() - rest.len();
+                self.lines[self.pos] = Line {
+                    number,
+                    indent: item_indent,
+                    text: rest,
+                };
+                let item = self.map(item_indent)?;
+                node.items.push(item);
+            } else {
+                self.pos += 1;
+                node.items.push(Node {
+                    line: number,
+                    value: scalar(rest),
+                    ..Default::default()
+                });
+            }
+        }
+        Ok(node)
+    }
+}
+
+pub fn parse(text: &str) -> Result<Node> {
+    let lines = text
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let content = strip_comment(line).trim_end();
+            let trimmed = content.trim_start();
+            (!trimmed.is_empty() && trimmed != "---").then(|| Line {
+                number: i + 1,
+                indent: content.len() - trimmed.len(),
+                text: trimmed,
+            })
+        })
+        .collect();
+    let mut parser = Parser { lines, pos: 0 };
+    let indent = parser.peek().map_or(0, |l| l.indent);
+    let root = parser.block(indent)?;
+    if let Some(line) = parser.peek() {
+        bail!(tr!(
+            "第{}行: 缩进不正确",
+            "Line {}: bad indentation",
+            line.number
+        ));
+    }
+    Ok(root)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_maps_lists_and_comments() {
+        let text = "\
+# 注释
+clusters:
+- cluster:
+    server: https://10.0.0.1:6443 # 地址
+  name: prod
+users:
+  - name: dev
+    user:
+      exec:
+        args:
+        - get-token
+        - \"--region=cn #1\"
+        command: aws
+      password: 'a#b'
+";
+        let root = parse(text).unwrap();
+        let clusters = &root.get("clusters").unwrap().items;
+        assert_eq!(clusters.len(), 1);
+        assert_eq!(
+            clusters[0].str(&["cluster", "server"]),
+            Some("https://10.0.0.1:6443")
+        );
+        assert_eq!(clusters[0].str(&["name"]), Some("prod"));
+
+        let user = &root.get("users").unwrap().items[0];
+        assert_eq!(user.str(&["name"]), Some("dev"));
+        let exec = user.get("user").and_then(|u| u.get("exec")).unwrap();
+        let args: Vec<_> = exec.get("args").unwrap().items.iter().collect();
+        assert_eq!(args[0].value.as_deref(), Some("get-token"));
+        assert_eq!(args[1].value.as_deref(), Some("--region=cn #1"));
+        assert_eq!(exec.str(&["command"]), Some("aws"));
+        assert_eq!(user.str(&["user", "password"]), Some("a#b"));
+
+        assert!(parse("a:\n    b: 1\n  c: 2\n").is_err());
+        assert!(parse("a:\n  just text\n").is_err());
+    }
+}